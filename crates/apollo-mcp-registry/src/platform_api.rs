@@ -21,6 +21,12 @@ pub struct PlatformApiConfig {
 
     /// The URL of the Apollo registry
     pub registry_url: Url,
+
+    /// How many times to retry a request failing with a transient error
+    pub max_retries: u32,
+
+    /// The delay before the first retry, doubled for each retry after it
+    pub retry_backoff: Duration,
 }
 
 impl PlatformApiConfig {
@@ -38,8 +44,18 @@ impl PlatformApiConfig {
             #[allow(clippy::expect_used)]
             registry_url: registry_url
                 .unwrap_or(Url::parse(DEFAULT_PLATFORM_API).expect("default URL should be valid")),
+            max_retries: 0,
+            retry_backoff: Duration::from_secs(1),
         }
     }
+
+    /// Retry requests failing with a transient error up to `max_retries` times, waiting
+    /// `retry_backoff` before the first retry and doubling the wait for each retry after it
+    pub fn with_retries(mut self, max_retries: u32, retry_backoff: Duration) -> Self {
+        self.max_retries = max_retries;
+        self.retry_backoff = retry_backoff;
+        self
+    }
 }
 
 #[cfg(test)]
@@ -60,5 +76,6 @@ mod test {
         assert_eq!(config.poll_interval, Duration::from_secs(10));
         assert_eq!(config.timeout, Duration::from_secs(5));
         assert_eq!(config.registry_url.to_string(), DEFAULT_PLATFORM_API);
+        assert_eq!(config.max_retries, 0);
     }
 }
@@ -160,6 +160,25 @@ pub struct OperationData {
     pub headers: Option<Vec<(String, String)>>,
     pub variables: Option<String>,
 }
+impl OperationData {
+    /// Create the data of an operation in a collection, with the headers and variables saved
+    /// alongside it
+    pub fn new(
+        id: String,
+        last_updated_at: String,
+        source_text: String,
+        headers: Option<Vec<(String, String)>>,
+        variables: Option<String>,
+    ) -> Self {
+        Self {
+            id,
+            last_updated_at,
+            source_text,
+            headers,
+            variables,
+        }
+    }
+}
 impl From<&OperationCollectionEntry> for OperationData {
     fn from(operation: &OperationCollectionEntry) -> Self {
         Self {
@@ -626,10 +645,40 @@ async fn poll_operation_collection_default(
     }
 }
 
+/// Send a request to the platform API, retrying it on transient errors as configured
 async fn graphql_request<Query>(
     request_body: &graphql_client::QueryBody<Query::Variables>,
     platform_api_config: &PlatformApiConfig,
 ) -> Result<Query::ResponseData, CollectionError>
+where
+    Query: graphql_client::GraphQLQuery,
+    <Query as graphql_client::GraphQLQuery>::ResponseData: std::fmt::Debug,
+{
+    let mut backoff = platform_api_config.retry_backoff;
+    let mut retries = 0;
+    loop {
+        match send_graphql_request::<Query>(request_body, platform_api_config).await {
+            Err(err)
+                if retries < platform_api_config.max_retries
+                    && is_collection_error_transient(&err) =>
+            {
+                retries += 1;
+                tracing::warn!(
+                    "Platform API request failed (transient error), retrying in {backoff:?} ({retries}/{}): {err}",
+                    platform_api_config.max_retries
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+            }
+            result => return result,
+        }
+    }
+}
+
+async fn send_graphql_request<Query>(
+    request_body: &graphql_client::QueryBody<Query::Variables>,
+    platform_api_config: &PlatformApiConfig,
+) -> Result<Query::ResponseData, CollectionError>
 where
     Query: graphql_client::GraphQLQuery,
     <Query as graphql_client::GraphQLQuery>::ResponseData: std::fmt::Debug,
@@ -655,6 +704,7 @@ where
         .json(request_body)
         .send()
         .await
+        .and_then(reqwest::Response::error_for_status)
         .map_err(CollectionError::Request)?;
 
     let response_body: graphql_client::Response<Query::ResponseData> =
@@ -663,3 +713,77 @@ where
         .data
         .ok_or(CollectionError::Response("missing data".to_string()))
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use secrecy::SecretString;
+    use serde_json::json;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+
+    async fn default_polling_request(
+        platform_api_config: &PlatformApiConfig,
+    ) -> Result<operation_collection_default_polling_query::ResponseData, CollectionError> {
+        graphql_request::<OperationCollectionDefaultPollingQuery>(
+            &OperationCollectionDefaultPollingQuery::build_query(
+                operation_collection_default_polling_query::Variables {
+                    graph_ref: "graph@current".to_string(),
+                },
+            ),
+            platform_api_config,
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "variant": null }
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = PlatformApiConfig::new(
+            SecretString::from("key"),
+            Duration::from_secs(30),
+            Duration::from_secs(5),
+            Some(server.uri().parse().unwrap()),
+        )
+        .with_retries(1, Duration::from_millis(10));
+        let response = default_polling_request(&config).await.unwrap();
+        assert!(response.variant.is_none());
+    }
+
+    #[tokio::test]
+    async fn transient_failures_are_returned_once_retries_run_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        let config = PlatformApiConfig::new(
+            SecretString::from("key"),
+            Duration::from_secs(30),
+            Duration::from_secs(5),
+            Some(server.uri().parse().unwrap()),
+        )
+        .with_retries(2, Duration::from_millis(10));
+        let error = default_polling_request(&config).await.unwrap_err();
+        assert!(is_collection_error_transient(&error));
+    }
+}
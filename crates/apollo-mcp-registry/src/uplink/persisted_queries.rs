@@ -4,6 +4,7 @@ pub mod event;
 mod manifest;
 mod manifest_poller;
 
+pub use manifest::ExecutionPolicy;
 pub use manifest::FullPersistedQueryOperationId;
 pub use manifest::ManifestOperation;
 pub use manifest::PersistedQueryManifest;
@@ -1,10 +1,12 @@
 use std::fmt::Debug;
 use std::fmt::Formatter;
 
+use super::ManifestOperation;
+
 /// Persisted Query events
 pub enum Event {
     /// The persisted query manifest was updated
-    UpdateManifest(Vec<(String, String)>),
+    UpdateManifest(Vec<ManifestOperation>),
 }
 
 impl Debug for Event {
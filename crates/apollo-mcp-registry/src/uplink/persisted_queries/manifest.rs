@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::time::Duration;
 
 use serde::Deserialize;
 use serde::Serialize;
@@ -26,6 +27,25 @@ pub struct ManifestOperation {
     pub body: String,
     /// The client name associated with the operation. If None, can be any client.
     pub client_name: Option<String>,
+    /// How to execute the operation.
+    #[serde(flatten)]
+    pub policy: ExecutionPolicy,
+}
+
+/// Per-operation execution policy, which travels with the operation in the manifest.
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPolicy {
+    /// Time out the operation after this duration, such as `5s`, instead of the server's timeout.
+    #[serde(
+        default,
+        with = "humantime_serde",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub timeout: Option<Duration>,
+    /// Fail the operation if its response is larger than this many bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_response_bytes: Option<usize>,
 }
 
 /// The format of each persisted query chunk returned from uplink.
@@ -63,20 +83,44 @@ impl SignedUrlChunk {
 #[derive(Debug, Clone, Default)]
 pub struct PersistedQueryManifest {
     inner: HashMap<FullPersistedQueryOperationId, String>,
+    policies: HashMap<FullPersistedQueryOperationId, ExecutionPolicy>,
 }
 
 impl PersistedQueryManifest {
     /// Add a chunk to the manifest.
     pub fn add_chunk(&mut self, chunk: &SignedUrlChunk) {
         for operation in &chunk.operations {
-            self.inner.insert(
-                FullPersistedQueryOperationId {
-                    operation_id: operation.id.clone(),
-                    client_name: operation.client_name.clone(),
-                },
-                operation.body.clone(),
-            );
+            self.add_operation(operation.clone());
+        }
+    }
+
+    /// Add a single operation to the manifest.
+    fn add_operation(&mut self, operation: ManifestOperation) {
+        let id = FullPersistedQueryOperationId {
+            operation_id: operation.id,
+            client_name: operation.client_name,
+        };
+        if operation.policy == ExecutionPolicy::default() {
+            self.policies.remove(&id);
+        } else {
+            self.policies.insert(id.clone(), operation.policy);
         }
+        self.inner.insert(id, operation.body);
+    }
+
+    /// The execution policy of an operation in the manifest.
+    pub fn policy(&self, id: &FullPersistedQueryOperationId) -> ExecutionPolicy {
+        self.policies.get(id).copied().unwrap_or_default()
+    }
+
+    /// The operations in the manifest.
+    pub fn operations(&self) -> impl Iterator<Item = ManifestOperation> + '_ {
+        self.inner.iter().map(|(id, body)| ManifestOperation {
+            id: id.operation_id.clone(),
+            body: body.clone(),
+            client_name: id.client_name.clone(),
+            policy: self.policy(id),
+        })
     }
 }
 
@@ -84,13 +128,7 @@ impl From<Vec<ManifestOperation>> for PersistedQueryManifest {
     fn from(operations: Vec<ManifestOperation>) -> Self {
         let mut manifest = PersistedQueryManifest::default();
         for operation in operations {
-            manifest.insert(
-                FullPersistedQueryOperationId {
-                    operation_id: operation.id,
-                    client_name: operation.client_name,
-                },
-                operation.body,
-            );
+            manifest.add_operation(operation);
         }
         manifest
     }
@@ -35,12 +35,7 @@ impl ManifestSource {
         match create_manifest_stream(self).await {
             Ok(stream) => stream
                 .map(|result| match result {
-                    Ok(manifest) => Event::UpdateManifest(
-                        manifest
-                            .iter()
-                            .map(|(k, v)| (k.operation_id.clone(), v.clone()))
-                            .collect(),
-                    ),
+                    Ok(manifest) => Event::UpdateManifest(manifest.operations().collect()),
                     Err(e) => {
                         tracing::error!("error from manifest stream: {}", e);
                         Event::UpdateManifest(vec![])
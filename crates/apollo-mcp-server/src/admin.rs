@@ -0,0 +1,158 @@
+//! Admin module for Apollo MCP Server
+//!
+//! Allows tools to be disabled and re-enabled at runtime via an HTTP admin endpoint, so that a
+//! misbehaving tool can be taken out of service without a redeploy. Disabled tools are kept in
+//! memory only, so every tool is enabled again after a restart.
+//!
+//! The endpoint requires the MCP transport's auth, and a token granted the admin scope, so that
+//! clients which can only call tools can't disable them for every other client.
+
+use std::{collections::HashSet, sync::Arc};
+
+use axum::{
+    Router,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::auth::ValidToken;
+
+/// Configuration options for the admin endpoint.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct AdminConfig {
+    /// Set to true to expose the admin endpoint (HTTP transports only)
+    pub enabled: bool,
+
+    /// Optionally set a custom admin path
+    /// Defaults to /admin
+    pub path: String,
+
+    /// The OAuth scope a token must be granted to use the admin endpoint
+    /// Defaults to admin
+    pub scope: String,
+}
+
+impl Default for AdminConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "/admin".to_string(),
+            scope: "admin".to_string(),
+        }
+    }
+}
+
+impl AdminConfig {
+    /// Require a token granted the admin scope on every route of the router. The router must be
+    /// served behind the auth middleware, which validates the token.
+    pub(crate) fn protect(&self, router: Router) -> Router {
+        router.layer(axum::middleware::from_fn_with_state(
+            self.scope.clone(),
+            admin_scope_validate,
+        ))
+    }
+}
+
+/// Reject requests without a valid token, or with a token not granted the admin scope
+async fn admin_scope_validate(
+    State(scope): State<String>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    match request.extensions().get::<ValidToken>() {
+        Some(token) if token.scopes().contains(&scope) => Ok(next.run(request).await),
+        Some(_) => Err(StatusCode::FORBIDDEN),
+        None => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// The names of the tools disabled at runtime
+#[derive(Clone, Debug, Default)]
+pub struct DisabledTools(Arc<RwLock<HashSet<String>>>);
+
+impl DisabledTools {
+    /// Disable or re-enable a tool, returning whether its state changed
+    pub async fn set_enabled(&self, name: &str, enabled: bool) -> bool {
+        let mut disabled = self.0.write().await;
+        if enabled {
+            disabled.remove(name)
+        } else {
+            disabled.insert(name.to_string())
+        }
+    }
+
+    /// Check if a tool is disabled
+    pub async fn is_disabled(&self, name: &str) -> bool {
+        self.0.read().await.contains(name)
+    }
+
+    /// The names of all disabled tools, in sorted order
+    pub async fn names(&self) -> Vec<String> {
+        let mut names: Vec<_> = self.0.read().await.iter().cloned().collect();
+        names.sort();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::get;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn disables_and_enables_tools() {
+        let disabled_tools = DisabledTools::default();
+        assert!(disabled_tools.set_enabled("GetAlerts", false).await);
+        assert!(!disabled_tools.set_enabled("GetAlerts", false).await);
+        assert!(disabled_tools.is_disabled("GetAlerts").await);
+        assert_eq!(disabled_tools.names().await, vec!["GetAlerts"]);
+
+        assert!(disabled_tools.set_enabled("GetAlerts", true).await);
+        assert!(!disabled_tools.is_disabled("GetAlerts").await);
+        assert!(disabled_tools.names().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn admin_routes_require_the_admin_scope() {
+        let status = |token: Option<ValidToken>| async move {
+            let router = AdminConfig::default()
+                .protect(Router::new().route("/admin/tools/disabled", get(|| async { "[]" })))
+                .layer(axum::middleware::map_request(
+                    move |mut request: Request| {
+                        let token = token.clone();
+                        async move {
+                            if let Some(token) = token {
+                                request.extensions_mut().insert(token);
+                            }
+                            request
+                        }
+                    },
+                ));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, router).await });
+            reqwest::get(format!("http://{address}/admin/tools/disabled"))
+                .await
+                .unwrap()
+                .status()
+        };
+
+        assert_eq!(
+            status(Some(ValidToken::with_scopes(&["admin"]))).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            status(Some(ValidToken::with_scopes(&["read"]))).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(status(None).await, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -14,7 +14,10 @@ use http::Method;
 use networked_token_validator::NetworkedTokenValidator;
 use schemars::JsonSchema;
 use serde::Deserialize;
+use std::sync::Arc;
+use tokio::sync::RwLock;
 use tower_http::cors::{Any, CorsLayer};
+use tracing::{info, warn};
 use url::Url;
 
 mod networked_token_validator;
@@ -49,11 +52,80 @@ pub struct Config {
 }
 
 impl Config {
+    pub fn enable_middleware(&self, router: Router) -> Router {
+        ReloadableConfig::new(self.clone()).enable_middleware(router)
+    }
+}
+
+/// Reads the latest auth configuration when it is reloaded, or `None` if the configuration no
+/// longer has auth
+pub type ConfigLoader = Arc<dyn Fn() -> anyhow::Result<Option<Config>> + Send + Sync>;
+
+/// Auth configuration which can be swapped while the server is running, such as when the
+/// identity provider changes its issuer or keys
+#[derive(Clone)]
+pub struct ReloadableConfig {
+    current: Arc<RwLock<Config>>,
+}
+
+impl ReloadableConfig {
+    pub fn new(config: Config) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(config)),
+        }
+    }
+
+    /// The configuration in use
+    pub async fn current(&self) -> Config {
+        self.current.read().await.clone()
+    }
+
+    /// Replace the configuration with the one read by the loader. Requests validated after this
+    /// returns use the new configuration.
+    ///
+    /// Auth can't be disabled by a reload, so a configuration without auth is rejected.
+    pub async fn reload(&self, loader: &ConfigLoader) -> anyhow::Result<()> {
+        let config = loader()?.ok_or_else(|| {
+            anyhow::anyhow!("The reloaded configuration has no auth, so auth can't be reloaded")
+        })?;
+        *self.current.write().await = config;
+        Ok(())
+    }
+
+    /// Reload the configuration whenever the process receives `SIGHUP`
+    pub fn reload_on_hangup(&self, loader: ConfigLoader) {
+        #[cfg(unix)]
+        {
+            let config = self.clone();
+            match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                Ok(mut hangup) => {
+                    tokio::spawn(async move {
+                        while hangup.recv().await.is_some() {
+                            match config.reload(&loader).await {
+                                Ok(()) => info!("Reloaded the auth configuration"),
+                                Err(e) => warn!("Failed to reload the auth configuration: {e}"),
+                            }
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to install SIGHUP handler to reload auth: {e}"),
+            }
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = loader;
+            warn!("Reloading the auth configuration on SIGHUP is only supported on Unix");
+        }
+    }
+
     pub fn enable_middleware(&self, router: Router) -> Router {
         /// Simple handler to encode our config into the desired OAuth 2.1 protected
         /// resource format
-        async fn protected_resource(State(auth_config): State<Config>) -> Json<ProtectedResource> {
-            Json(auth_config.into())
+        async fn protected_resource(
+            State(auth_config): State<ReloadableConfig>,
+        ) -> Json<ProtectedResource> {
+            Json(auth_config.current().await.into())
         }
 
         // Set up auth routes. NOTE: CORs needs to allow for get requests to the
@@ -81,11 +153,13 @@ impl Config {
 
 /// Validate that requests made have a corresponding bearer JWT token
 async fn oauth_validate(
-    State(auth_config): State<Config>,
+    State(auth_config): State<ReloadableConfig>,
     token: Option<TypedHeader<Authorization<Bearer>>>,
     mut request: Request,
     next: Next,
 ) -> Result<Response, (StatusCode, TypedHeader<WwwAuthenticate>)> {
+    let auth_config = auth_config.current().await;
+
     // Consolidated unauthorized error for use with any fallible step in this process
     let unauthorized_error = || {
         let mut resource = auth_config.resource.clone();
@@ -114,3 +188,164 @@ async fn oauth_validate(
     let response = next.run(request).await;
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use figment::Figment;
+    use figment::providers::{Format as _, Yaml};
+    use jsonwebtoken::jwk::KeyAlgorithm;
+    use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, encode};
+    use jwks::Jwk;
+    use serde_json::json;
+
+    use super::*;
+
+    /// Validates tokens against the audiences and servers of a config, with a fixed key
+    struct ConfigValidator {
+        config: Config,
+    }
+
+    impl ValidateToken for ConfigValidator {
+        fn get_audiences(&self) -> &Vec<String> {
+            &self.config.audiences
+        }
+
+        fn get_servers(&self) -> &Vec<Url> {
+            &self.config.servers
+        }
+
+        async fn get_key(&self, server: &Url, key_id: &str) -> Option<Jwk> {
+            (server.as_str() == "https://new-issuer.example.com/" && key_id == "key").then(|| Jwk {
+                alg: KeyAlgorithm::HS512,
+                decoding_key: DecodingKey::from_base64_secret("DEADBEEF").unwrap(),
+            })
+        }
+    }
+
+    fn config(server: &str, audience: &str) -> Config {
+        Config {
+            servers: vec![Url::parse(server).unwrap()],
+            audiences: vec![audience.to_string()],
+            resource: Url::parse("https://mcp.example.com").unwrap(),
+            resource_documentation: None,
+            scopes: vec![],
+        }
+    }
+
+    fn token(audience: &str) -> Authorization<Bearer> {
+        let mut header = Header::new(Algorithm::HS512);
+        header.kid = Some("key".to_string());
+        let token = encode(
+            &header,
+            &json!({
+                "aud": audience,
+                "exp": chrono::Utc::now().timestamp() + 1000,
+                "sub": "test user",
+            }),
+            &EncodingKey::from_base64_secret("DEADBEEF").unwrap(),
+        )
+        .unwrap();
+        Authorization::bearer(&token).unwrap()
+    }
+
+    async fn accepts(config: &ReloadableConfig, token: Authorization<Bearer>) -> bool {
+        ConfigValidator {
+            config: config.current().await,
+        }
+        .validate(token)
+        .await
+        .is_some()
+    }
+
+    #[tokio::test]
+    async fn reloaded_config_accepts_tokens_valid_under_it() {
+        let auth = ReloadableConfig::new(config("https://old-issuer.example.com", "old"));
+        assert!(!accepts(&auth, token("new")).await);
+
+        let loader: ConfigLoader =
+            Arc::new(|| Ok(Some(config("https://new-issuer.example.com", "new"))));
+        auth.reload(&loader).await.unwrap();
+
+        assert!(accepts(&auth, token("new")).await);
+        assert!(!accepts(&auth, token("old")).await);
+    }
+
+    #[tokio::test]
+    async fn reload_without_auth_keeps_the_current_config() {
+        let auth = ReloadableConfig::new(config("https://new-issuer.example.com", "new"));
+
+        let loader: ConfigLoader = Arc::new(|| Ok(None));
+        assert!(auth.reload(&loader).await.is_err());
+
+        assert!(accepts(&auth, token("new")).await);
+    }
+
+    #[tokio::test]
+    async fn reloading_auth_config_changes_the_accepted_audiences() {
+        let mut issuer = mockito::Server::new_async().await;
+        issuer
+            .mock("GET", "/.well-known/oauth-authorization-server")
+            .with_body(json!({ "jwks_uri": format!("{}/jwks", issuer.url()) }).to_string())
+            .create_async()
+            .await;
+        issuer
+            .mock("GET", "/jwks")
+            .with_body(
+                json!({
+                    "keys": [{ "kty": "oct", "kid": "key", "alg": "HS512", "k": "DEADBEEF" }]
+                })
+                .to_string(),
+            )
+            .expect_at_least(1)
+            .create_async()
+            .await;
+
+        let path = std::env::temp_dir().join(format!("auth-reload-{}.yaml", std::process::id()));
+        let write_config = |audience: &str| {
+            std::fs::write(
+                &path,
+                format!(
+                    "servers: [{}]\naudiences: [{audience}]\nresource: https://mcp.example.com\nscopes: []\n",
+                    issuer.url()
+                ),
+            )
+            .unwrap()
+        };
+        write_config("old");
+        let loader: ConfigLoader = {
+            let path = path.clone();
+            Arc::new(move || Ok(Some(Figment::from(Yaml::file(&path)).extract()?)))
+        };
+        let auth = ReloadableConfig::new(loader().unwrap().unwrap());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let router = auth.enable_middleware(Router::new().route("/mcp", get(|| async { "ok" })));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let request = |audience: &str| {
+            reqwest::Client::new()
+                .get(&url)
+                .header(
+                    http::header::AUTHORIZATION,
+                    format!("Bearer {}", token(audience).token()),
+                )
+                .send()
+        };
+
+        assert_eq!(request("old").await.unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            request("new").await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        write_config("new");
+        auth.reload(&loader).await.unwrap();
+
+        assert_eq!(request("new").await.unwrap().status(), StatusCode::OK);
+        assert_eq!(
+            request("old").await.unwrap().status(),
+            StatusCode::UNAUTHORIZED
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
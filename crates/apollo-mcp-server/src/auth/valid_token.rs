@@ -4,6 +4,7 @@ use headers::{Authorization, authorization::Bearer};
 use jsonwebtoken::{Algorithm, Validation, decode, decode_header, jwk};
 use jwks::Jwk;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use tracing::{info, warn};
 use url::Url;
 
@@ -12,7 +13,35 @@ use url::Url;
 /// Note: This is used as a marker to ensure that we have validated this
 /// separately from just reading the header itself.
 #[derive(Clone, Debug, PartialEq)]
-pub(crate) struct ValidToken(pub(super) Authorization<Bearer>);
+pub(crate) struct ValidToken(
+    pub(super) Authorization<Bearer>,
+    pub(super) Vec<String>,
+    pub(super) Map<String, Value>,
+);
+
+impl ValidToken {
+    /// The scopes granted to this token
+    pub(crate) fn scopes(&self) -> &[String] {
+        &self.1
+    }
+
+    /// The claims of this token
+    pub(crate) fn claims(&self) -> &Map<String, Value> {
+        &self.2
+    }
+}
+
+#[cfg(test)]
+impl ValidToken {
+    /// A token granted the scopes, for testing handlers behind the auth middleware
+    pub(crate) fn with_scopes(scopes: &[&str]) -> Self {
+        Self(
+            Authorization::bearer("token").unwrap(),
+            scopes.iter().map(ToString::to_string).collect(),
+            Map::new(),
+        )
+    }
+}
 
 impl Deref for ValidToken {
     type Target = Authorization<Bearer>;
@@ -48,6 +77,10 @@ pub(super) trait ValidateToken {
 
             /// The user who owns this token
             pub sub: String,
+
+            /// The space-delimited scopes granted to this token
+            #[serde(default)]
+            pub scope: Option<String>,
         }
 
         fn deserialize_audience<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
@@ -103,9 +136,20 @@ pub(super) trait ValidateToken {
                 val
             };
 
-            match decode::<Claims>(jwt, &jwk.decoding_key, &validation) {
-                Ok(_) => {
-                    return Some(ValidToken(token));
+            // Keep all of the claims, so that they can be mapped to operation variables
+            match decode::<Map<String, Value>>(jwt, &jwk.decoding_key, &validation).and_then(
+                |data| {
+                    Claims::deserialize(Value::Object(data.claims.clone()))
+                        .map(|claims| (claims, data.claims))
+                        .map_err(jsonwebtoken::errors::Error::from)
+                },
+            ) {
+                Ok((claims, all_claims)) => {
+                    let scopes = claims
+                        .scope
+                        .map(|scope| scope.split_whitespace().map(String::from).collect())
+                        .unwrap_or_default();
+                    return Some(ValidToken(token, scopes, all_claims));
                 }
                 Err(e) => warn!("Token failed validation with error: {e}"),
             };
@@ -179,6 +223,7 @@ mod test {
             aud: String,
             exp: i64,
             sub: String,
+            scope: String,
         }
 
         let header = {
@@ -193,6 +238,7 @@ mod test {
                 aud: audience,
                 exp: expires_at,
                 sub: "test user".to_string(),
+                scope: "read:user read:email".to_string(),
             },
             &key,
         )
@@ -224,14 +270,12 @@ mod test {
         };
 
         let token = jwt.token().to_string();
+        let valid_token = test_validator.validate(jwt).await.expect("valid token");
+        assert_eq!(valid_token.0.token(), token);
+        assert_eq!(valid_token.scopes(), ["read:user", "read:email"]);
         assert_eq!(
-            test_validator
-                .validate(jwt)
-                .await
-                .expect("valid token")
-                .0
-                .token(),
-            token
+            valid_token.claims().get("sub"),
+            Some(&serde_json::Value::from("test user"))
         );
     }
 
@@ -0,0 +1,284 @@
+//! Auxiliary endpoints module for Apollo MCP Server
+//!
+//! The health check and metrics endpoints are served alongside the MCP transport by default, and
+//! are not protected by its auth. When they are exposed outside of a trusted network, they can
+//! require a bearer token, and be served on a separate listener with TLS.
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::PathBuf,
+    time::Duration,
+};
+
+use axum::{
+    Router,
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::Response,
+};
+use axum_extra::TypedHeader;
+use headers::{Authorization, authorization::Bearer};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio_native_tls::{TlsAcceptor, TlsStream, native_tls};
+use tracing::{error, info, warn};
+
+use crate::errors::ServerError;
+
+/// How long a client has to complete the TLS handshake
+const TLS_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configuration options for the auxiliary (health check and metrics) endpoints.
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct AuxiliaryConfig {
+    /// Require requests to the auxiliary endpoints to send this token in an
+    /// `Authorization: Bearer <token>` header
+    pub bearer_token: Option<String>,
+
+    /// Serve the auxiliary endpoints on a separate port, instead of alongside the MCP transport
+    pub port: Option<u16>,
+
+    /// The address to bind the separate port to (default: 127.0.0.1)
+    pub address: IpAddr,
+
+    /// Serve the separate port with TLS. Requires `port` to be set
+    pub tls: Option<TlsConfig>,
+}
+
+impl Default for AuxiliaryConfig {
+    fn default() -> Self {
+        Self {
+            bearer_token: None,
+            port: None,
+            address: IpAddr::V4(Ipv4Addr::LOCALHOST),
+            tls: None,
+        }
+    }
+}
+
+// The bearer token is redacted so that it never appears in logs
+impl fmt::Debug for AuxiliaryConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuxiliaryConfig")
+            .field(
+                "bearer_token",
+                &self.bearer_token.as_ref().map(|_| "[REDACTED]"),
+            )
+            .field("port", &self.port)
+            .field("address", &self.address)
+            .field("tls", &self.tls)
+            .finish()
+    }
+}
+
+/// Configuration options for serving with TLS.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+    /// The PEM-encoded certificate chain
+    pub certificate: PathBuf,
+
+    /// The PEM-encoded PKCS#8 private key
+    pub key: PathBuf,
+}
+
+impl AuxiliaryConfig {
+    /// Require the configured bearer token, if any, on every route of the router
+    pub(crate) fn protect(&self, router: Router) -> Router {
+        match &self.bearer_token {
+            Some(token) => router.layer(axum::middleware::from_fn_with_state(
+                token.clone(),
+                bearer_token_validate,
+            )),
+            None => router,
+        }
+    }
+
+    /// Serve the router on the separate port, if one is configured, returning it otherwise so
+    /// that it can be served alongside the MCP transport
+    pub(crate) async fn serve(&self, router: Router) -> Result<Option<Router>, ServerError> {
+        let Some(port) = self.port else {
+            if self.tls.is_some() {
+                warn!("TLS for the auxiliary endpoints is ignored, as they have no separate port");
+            }
+            return Ok(Some(router));
+        };
+        let listen_address = SocketAddr::new(self.address, port);
+        let tcp_listener = TcpListener::bind(listen_address).await?;
+        info!(port = ?port, address = ?self.address, tls = self.tls.is_some(), "Serving auxiliary endpoints");
+        match &self.tls {
+            Some(tls) => {
+                let listener = TlsListener::new(tcp_listener, tls.acceptor()?)?;
+                tokio::spawn(async move {
+                    if let Err(e) = axum::serve(listener, router).await {
+                        error!("Failed to serve auxiliary endpoints: {e:?}");
+                    }
+                });
+            }
+            None => {
+                tokio::spawn(async move {
+                    if let Err(e) = axum::serve(tcp_listener, router).await {
+                        error!("Failed to serve auxiliary endpoints: {e:?}");
+                    }
+                });
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl TlsConfig {
+    fn acceptor(&self) -> Result<TlsAcceptor, ServerError> {
+        let certificate = std::fs::read(&self.certificate)?;
+        let key = std::fs::read(&self.key)?;
+        native_tls::Identity::from_pkcs8(&certificate, &key)
+            .and_then(native_tls::TlsAcceptor::new)
+            .map(TlsAcceptor::from)
+            .map_err(|e| ServerError::Tls(e.to_string()))
+    }
+}
+
+/// Reject requests without the expected bearer token
+async fn bearer_token_validate(
+    State(expected): State<String>,
+    token: Option<TypedHeader<Authorization<Bearer>>>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    match token {
+        Some(TypedHeader(Authorization(bearer)))
+            if constant_time_eq(bearer.token().as_bytes(), expected.as_bytes()) =>
+        {
+            Ok(next.run(request).await)
+        }
+        _ => Err(StatusCode::UNAUTHORIZED),
+    }
+}
+
+/// Compare two byte strings in time independent of where they differ
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// A TCP listener completing a TLS handshake on each connection. Each handshake runs in its own
+/// task, so that a client which never completes its handshake doesn't hold up other connections.
+struct TlsListener {
+    streams: mpsc::UnboundedReceiver<(TlsStream<TcpStream>, SocketAddr)>,
+    local_addr: SocketAddr,
+}
+
+impl TlsListener {
+    fn new(mut tcp_listener: TcpListener, acceptor: TlsAcceptor) -> std::io::Result<Self> {
+        let local_addr = tcp_listener.local_addr()?;
+        let (sender, streams) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let (stream, address) = tokio::select! {
+                    accepted = axum::serve::Listener::accept(&mut tcp_listener) => accepted,
+                    // Stop accepting connections once the listener is dropped
+                    _ = sender.closed() => break,
+                };
+                let acceptor = acceptor.clone();
+                let sender = sender.clone();
+                tokio::spawn(async move {
+                    match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await
+                    {
+                        Ok(Ok(stream)) => {
+                            let _ = sender.send((stream, address));
+                        }
+                        Ok(Err(e)) => warn!(%address, "TLS handshake failed: {e}"),
+                        Err(_) => warn!(%address, "TLS handshake timed out"),
+                    }
+                });
+            }
+        });
+        Ok(Self {
+            streams,
+            local_addr,
+        })
+    }
+}
+
+impl axum::serve::Listener for TlsListener {
+    type Io = TlsStream<TcpStream>;
+    type Addr = SocketAddr;
+
+    async fn accept(&mut self) -> (Self::Io, Self::Addr) {
+        match self.streams.recv().await {
+            Some(accepted) => accepted,
+            // The accept loop only stops once this listener is dropped
+            None => std::future::pending().await,
+        }
+    }
+
+    fn local_addr(&self) -> std::io::Result<Self::Addr> {
+        Ok(self.local_addr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::routing::get;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn bearer_token_protects_endpoints() {
+        let config = AuxiliaryConfig {
+            bearer_token: Some("secret".to_string()),
+            ..Default::default()
+        };
+        let router = config.protect(Router::new().route("/health", get(|| async { "UP" })));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = reqwest::Client::new();
+        let unauthenticated = client.get(&url).send().await.unwrap();
+        assert_eq!(unauthenticated.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let wrong_token = client.get(&url).bearer_auth("guess").send().await.unwrap();
+        assert_eq!(wrong_token.status(), reqwest::StatusCode::UNAUTHORIZED);
+
+        let authenticated = client.get(&url).bearer_auth("secret").send().await.unwrap();
+        assert_eq!(authenticated.status(), reqwest::StatusCode::OK);
+        assert_eq!(authenticated.text().await.unwrap(), "UP");
+    }
+
+    #[tokio::test]
+    async fn stalled_tls_handshakes_do_not_block_other_connections() {
+        let tls = TlsConfig {
+            certificate: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("src/testdata/certificate.pem"),
+            key: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src/testdata/key.pem"),
+        };
+        let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listener = TlsListener::new(tcp_listener, tls.acceptor().unwrap()).unwrap();
+        let address = listener.local_addr;
+        let router = Router::new().route("/health", get(|| async { "UP" }));
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        // A client which connects but never starts its handshake
+        let _stalled = TcpStream::connect(address).await.unwrap();
+
+        let response = tokio::time::timeout(
+            Duration::from_secs(5),
+            reqwest::Client::builder()
+                .danger_accept_invalid_certs(true)
+                .build()
+                .unwrap()
+                .get(format!("https://{address}/health"))
+                .send(),
+        )
+        .await
+        .expect("the handshake shouldn't wait for the stalled client")
+        .unwrap();
+        assert_eq!(response.text().await.unwrap(), "UP");
+    }
+}
@@ -0,0 +1,233 @@
+//! Circuit breaker module for Apollo MCP Server
+//!
+//! Stops calling a tool which keeps failing. After a number of consecutive failures within a
+//! window, the circuit for the tool opens and calls fail fast for a cooldown period. After the
+//! cooldown, the circuit is half-open: a single call is let through to test whether the tool has
+//! recovered, closing the circuit if it succeeds and opening it again if it fails. If the test
+//! call never completes, such as when the client cancels it, another call is let through once
+//! the cooldown has passed again.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+/// Configuration options for the circuit breaker.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// Set to true to fail calls to a tool fast after repeated failures
+    pub enabled: bool,
+
+    /// The number of consecutive failures which open the circuit (default: 5)
+    pub failure_threshold: u32,
+
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    #[schemars(with = "String")]
+    /// The window the consecutive failures must occur within (default: 60s)
+    pub window: Duration,
+
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    #[schemars(with = "String")]
+    /// How long calls fail fast once the circuit opens (default: 30s)
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            failure_threshold: 5,
+            window: Duration::from_secs(60),
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// The state of the circuit for a single tool
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Circuit {
+    /// Calls are let through, counting consecutive failures since the first one
+    Closed { failures: u32, since: Instant },
+
+    /// Calls fail fast until the cooldown ends
+    Open { until: Instant },
+
+    /// A single call is testing whether the tool has recovered, until it completes or the
+    /// cooldown ends
+    HalfOpen { until: Instant },
+}
+
+#[derive(Clone)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    circuits: Arc<Mutex<HashMap<String, Circuit>>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            circuits: Default::default(),
+        }
+    }
+
+    /// Check if a call to a tool can go ahead, returning how long until it can be retried if the
+    /// circuit is open
+    pub async fn check(&self, tool: &str) -> Result<(), Duration> {
+        let mut circuits = self.circuits.lock().await;
+        let Some(circuit) = circuits.get_mut(tool) else {
+            return Ok(());
+        };
+        let now = Instant::now();
+        match *circuit {
+            Circuit::Closed { .. } => Ok(()),
+            Circuit::Open { until } | Circuit::HalfOpen { until } if now >= until => {
+                *circuit = Circuit::HalfOpen {
+                    until: now + self.config.cooldown,
+                };
+                Ok(())
+            }
+            Circuit::Open { until } | Circuit::HalfOpen { until } => Err(until - now),
+        }
+    }
+
+    /// Record the outcome of a call to a tool
+    pub async fn record(&self, tool: &str, failed: bool) {
+        let mut circuits = self.circuits.lock().await;
+        if !failed {
+            circuits.remove(tool);
+            return;
+        }
+
+        let now = Instant::now();
+        let circuit = match circuits.get(tool).copied() {
+            // Calls let through before the circuit opened can still fail after it opens
+            Some(Circuit::Open { .. }) => return,
+            Some(Circuit::HalfOpen { .. }) => Circuit::Open {
+                until: now + self.config.cooldown,
+            },
+            Some(Circuit::Closed { failures, since })
+                if now.duration_since(since) <= self.config.window =>
+            {
+                Circuit::Closed {
+                    failures: failures + 1,
+                    since,
+                }
+            }
+            _ => Circuit::Closed {
+                failures: 1,
+                since: now,
+            },
+        };
+        let circuit = match circuit {
+            Circuit::Closed { failures, .. } if failures >= self.config.failure_threshold => {
+                Circuit::Open {
+                    until: now + self.config.cooldown,
+                }
+            }
+            circuit => circuit,
+        };
+        circuits.insert(tool.to_string(), circuit);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn opens_after_repeated_failures_until_cooldown_elapses() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 3,
+            window: Duration::from_secs(60),
+            cooldown: Duration::from_secs(30),
+        });
+
+        for _ in 0..3 {
+            assert!(breaker.check("GetAlerts").await.is_ok());
+            breaker.record("GetAlerts", true).await;
+        }
+        assert_eq!(
+            breaker.check("GetAlerts").await,
+            Err(Duration::from_secs(30))
+        );
+        assert!(breaker.check("GetForecast").await.is_ok());
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(
+            breaker.check("GetAlerts").await,
+            Err(Duration::from_secs(20))
+        );
+
+        // Half-open after the cooldown, letting a single call through
+        tokio::time::advance(Duration::from_secs(20)).await;
+        assert!(breaker.check("GetAlerts").await.is_ok());
+        assert!(breaker.check("GetAlerts").await.is_err());
+
+        // A failed test call opens the circuit again, while a successful one closes it
+        breaker.record("GetAlerts", true).await;
+        assert!(breaker.check("GetAlerts").await.is_err());
+        tokio::time::advance(Duration::from_secs(30)).await;
+        assert!(breaker.check("GetAlerts").await.is_ok());
+        breaker.record("GetAlerts", false).await;
+        assert!(breaker.check("GetAlerts").await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancelled_test_calls_let_another_call_through_after_the_cooldown() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 1,
+            window: Duration::from_secs(60),
+            cooldown: Duration::from_secs(30),
+        });
+        breaker.record("GetAlerts", true).await;
+        tokio::time::advance(Duration::from_secs(30)).await;
+
+        // The test call is cancelled before its outcome is recorded
+        let test_call = async {
+            breaker.check("GetAlerts").await.unwrap();
+            tokio::time::sleep(Duration::from_secs(3600)).await;
+            breaker.record("GetAlerts", false).await;
+        };
+        assert!(
+            tokio::time::timeout(Duration::from_secs(1), test_call)
+                .await
+                .is_err()
+        );
+        assert_eq!(
+            breaker.check("GetAlerts").await,
+            Err(Duration::from_secs(29))
+        );
+
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert!(breaker.check("GetAlerts").await.is_ok());
+        breaker.record("GetAlerts", false).await;
+        assert!(breaker.check("GetAlerts").await.is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failures_outside_the_window_do_not_open_the_circuit() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            enabled: true,
+            failure_threshold: 2,
+            window: Duration::from_secs(5),
+            cooldown: Duration::from_secs(30),
+        });
+
+        breaker.record("GetAlerts", true).await;
+        tokio::time::advance(Duration::from_secs(10)).await;
+        breaker.record("GetAlerts", true).await;
+        assert!(breaker.check("GetAlerts").await.is_ok());
+
+        breaker.record("GetAlerts", true).await;
+        assert!(breaker.check("GetAlerts").await.is_err());
+    }
+}
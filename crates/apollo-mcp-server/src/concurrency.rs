@@ -0,0 +1,91 @@
+//! Concurrency module for Apollo MCP Server
+//!
+//! Caps the number of calls to a tool which run at the same time, so that expensive operations
+//! can't overload the graph. Calls over the cap wait for an earlier call to finish. Each tool has
+//! its own cap, and tools without one are not limited.
+
+use std::{collections::HashMap, future::Future, sync::Arc};
+
+use tokio::sync::Semaphore;
+use tracing::warn;
+
+#[derive(Clone, Default)]
+pub struct ToolConcurrency {
+    semaphores: Arc<HashMap<String, (usize, Arc<Semaphore>)>>,
+}
+
+impl ToolConcurrency {
+    /// Create the semaphores for the concurrency limits, by tool name
+    pub fn new(limits: &HashMap<String, usize>) -> Self {
+        let semaphores = limits
+            .iter()
+            .filter_map(|(tool, limit)| {
+                if *limit == 0 {
+                    warn!("Ignoring the concurrency limit of 0 for tool {tool}");
+                    return None;
+                }
+                Some((tool.clone(), (*limit, Arc::new(Semaphore::new(*limit)))))
+            })
+            .collect();
+        Self {
+            semaphores: Arc::new(semaphores),
+        }
+    }
+
+    /// Run a call to a tool once the tool is below its concurrency limit
+    pub async fn run<F: Future>(&self, tool: &str, call: F) -> F::Output {
+        let Some((_, semaphore)) = self.semaphores.get(tool) else {
+            return call.await;
+        };
+        // The semaphores are never closed, so acquiring a permit can't fail
+        let _permit = semaphore.acquire().await.ok();
+        call.await
+    }
+
+    /// The concurrency limits in effect, by tool name
+    pub fn limits(&self) -> HashMap<&str, usize> {
+        self.semaphores
+            .iter()
+            .map(|(tool, (limit, _))| (tool.as_str(), *limit))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn a_limit_of_one_serializes_calls_to_the_tool() {
+        let concurrency = ToolConcurrency::new(&HashMap::from([("slow".to_string(), 1)]));
+        let call = |tool: &'static str| {
+            let concurrency = concurrency.clone();
+            async move {
+                concurrency
+                    .run(tool, async {
+                        let started = Instant::now();
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                        started
+                    })
+                    .await
+            }
+        };
+
+        let start = Instant::now();
+        let (first, second, other, another) =
+            tokio::join!(call("slow"), call("slow"), call("fast"), call("fast"));
+
+        // The second call to the limited tool waits for the first to finish
+        let mut slow = [first - start, second - start];
+        slow.sort();
+        assert_eq!(slow, [Duration::ZERO, Duration::from_secs(1)]);
+
+        // Calls to other tools run concurrently
+        assert_eq!(other - start, Duration::ZERO);
+        assert_eq!(another - start, Duration::ZERO);
+    }
+}
@@ -1,9 +1,14 @@
+use crate::graphql::PARTIAL_DATA_WARNING;
 use crate::introspection::tools::search::IndexingError;
 use apollo_compiler::{Schema, ast::Document, validation::WithErrors};
 use apollo_federation::error::FederationError;
 use apollo_mcp_registry::platform_api::operation_collections::error::CollectionError;
 use reqwest::header::{InvalidHeaderName, InvalidHeaderValue};
+use rmcp::model::{CallToolResult, Content, ErrorCode, RawContent};
 use rmcp::serde_json;
+use std::hash::{BuildHasher, Hasher, RandomState};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
 use tokio::task::JoinError;
 use url::ParseError;
 
@@ -25,6 +30,55 @@ pub enum OperationError {
     #[error("{0}No operations defined", .source_path.as_ref().map(|s| format!("{s}: ")).unwrap_or_default())]
     NoOperations { source_path: Option<String> },
 
+    #[error("{0}Operation {1} references unknown header set {2}", .source_path.as_ref().map(|s| format!("{s}: ")).unwrap_or_default(), operation, header_set)]
+    UnknownHeaderSet {
+        source_path: Option<String>,
+        operation: String,
+        header_set: String,
+    },
+
+    #[error("{0}Operation {1} has an invalid comment directive: {2}", .source_path.as_ref().map(|s| format!("{s}: ")).unwrap_or_default(), operation, directive)]
+    InvalidDirective {
+        source_path: Option<String>,
+        operation: String,
+        directive: String,
+    },
+
+    #[error("{0}Operation source refers to the unknown constant {1}", .source_path.as_ref().map(|s| format!("{s}: ")).unwrap_or_default(), constant)]
+    UnknownConstant {
+        source_path: Option<String>,
+        constant: String,
+    },
+
+    #[error("{0}Operation {1} selects no fields in the schema", .source_path.as_ref().map(|s| format!("{s}: ")).unwrap_or_default(), operation)]
+    NoSelectedFields {
+        source_path: Option<String>,
+        operation: String,
+    },
+
+    #[error("{0}Operation {1} selects fields which are not in the schema: {2}", .source_path.as_ref().map(|s| format!("{s}: ")).unwrap_or_default(), operation, fields.join(", "))]
+    MissingFields {
+        source_path: Option<String>,
+        operation: String,
+        fields: Vec<String>,
+    },
+
+    #[error("{0}Operation {1} is {2} levels deep, more than the maximum of {3}", .source_path.as_ref().map(|s| format!("{s}: ")).unwrap_or_default(), operation, depth, max_depth)]
+    TooDeep {
+        source_path: Option<String>,
+        operation: String,
+        depth: usize,
+        max_depth: usize,
+    },
+
+    #[error("{0}Operation {1} has {2} variables, more than the maximum of {3}", .source_path.as_ref().map(|s| format!("{s}: ")).unwrap_or_default(), operation, count, max_variables)]
+    TooManyVariables {
+        source_path: Option<String>,
+        operation: String,
+        count: usize,
+        max_variables: usize,
+    },
+
     #[error("Invalid JSON: {0}")]
     Json(#[from] serde_json::Error),
 
@@ -34,6 +88,15 @@ pub enum OperationError {
         count: usize,
     },
 
+    #[error("Too many operation tools. Expected at most {limit} but got {count}")]
+    TooManyTools { count: usize, limit: usize },
+
+    #[error("Operation {operation} is defined more than once, in {}", sources.join(" and "))]
+    DuplicateOperation {
+        operation: String,
+        sources: Vec<String>,
+    },
+
     #[error(transparent)]
     File(#[from] std::io::Error),
 
@@ -89,6 +152,15 @@ pub enum ServerError {
     #[error("No valid schema was supplied")]
     NoSchema,
 
+    #[error("Offline mode requires a cache directory, set with offline.cache_dir")]
+    OfflineCacheDir,
+
+    #[error(
+        "Offline mode requires a cached schema and operations, but {} does not exist. Run the server online with offline.cache_dir set to populate the cache.",
+        .0.display()
+    )]
+    OfflineCacheMissing(PathBuf),
+
     #[error("Failed to start server")]
     StartupError(#[from] JoinError),
 
@@ -100,7 +172,182 @@ pub enum ServerError {
 
     #[error("Failed to index schema: {0}")]
     Indexing(#[from] IndexingError),
+
+    #[error("Invalid TLS configuration: {0}")]
+    Tls(String),
+
+    #[error(
+        "The admin endpoint requires auth, so that only tokens granted the admin scope can disable tools. Configure transport.auth, or disable the admin endpoint."
+    )]
+    AdminWithoutAuth,
+
+    #[error("Invalid REST tool {name}: {reason}")]
+    RestTool { name: String, reason: String },
 }
 
 /// An MCP tool error
 pub type McpError = rmcp::model::ErrorData;
+
+/// Replace the detail of a failed tool call with a generic message and an error ID, logging the
+/// full detail against the ID so that it can be found from a client's report. The errors warned
+/// about alongside partial data are replaced the same way, keeping the data.
+///
+/// Errors about the call itself, such as invalid arguments, are returned as they are.
+pub(crate) fn sanitize_result(
+    tool: &str,
+    result: Result<CallToolResult, McpError>,
+) -> Result<CallToolResult, McpError> {
+    match result {
+        Err(error) if error.code == ErrorCode::INTERNAL_ERROR || error.data.is_some() => {
+            let id = error_id();
+            tracing::error!(
+                error_id = %id,
+                tool,
+                data = ?error.data,
+                "Tool call failed: {}",
+                error.message
+            );
+            Err(McpError::new(error.code, sanitized_message(&id), None))
+        }
+        Ok(result) if result.is_error == Some(true) => {
+            let id = error_id();
+            let detail = serde_json::to_string(&result.content).unwrap_or_default();
+            tracing::error!(error_id = %id, tool, "Tool call failed: {detail}");
+            Ok(CallToolResult::error(vec![Content::text(
+                sanitized_message(&id),
+            )]))
+        }
+        Ok(mut result) => {
+            for content in &mut result.content {
+                if let RawContent::Text(text) = &mut content.raw
+                    && text.text.starts_with(PARTIAL_DATA_WARNING)
+                {
+                    let id = error_id();
+                    tracing::error!(error_id = %id, tool, "Tool call returned partial data: {}", text.text);
+                    text.text =
+                        format!("{PARTIAL_DATA_WARNING}. Report error ID {id} for details.");
+                }
+            }
+            Ok(result)
+        }
+        result => result,
+    }
+}
+
+fn sanitized_message(id: &str) -> String {
+    format!("The operation failed. Report error ID {id} for details.")
+}
+
+/// A random ID for a sanitized error
+fn error_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    format!("{:016x}", hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    fn text(result: &CallToolResult) -> &str {
+        match result.content.first().map(|content| &content.raw) {
+            Some(RawContent::Text(text)) => &text.text,
+            _ => "",
+        }
+    }
+
+    #[traced_test]
+    #[test]
+    fn upstream_errors_are_sanitized_and_logged() {
+        let result = sanitize_result(
+            "GetUser",
+            Err(McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                "Failed to send GraphQL request: connection refused to 10.0.0.7:4000",
+                None,
+            )),
+        );
+
+        let error = result.err().unwrap();
+        assert_eq!(error.code, ErrorCode::INTERNAL_ERROR);
+        assert!(!error.message.contains("10.0.0.7"));
+        let id = error
+            .message
+            .strip_prefix("The operation failed. Report error ID ")
+            .and_then(|message| message.strip_suffix(" for details."))
+            .unwrap();
+        assert_eq!(id.len(), 16);
+        assert!(logs_contain(&format!("error_id={id}")));
+        assert!(logs_contain("connection refused to 10.0.0.7:4000"));
+    }
+
+    #[traced_test]
+    #[test]
+    fn failed_results_are_sanitized_and_logged() {
+        let result = sanitize_result(
+            "GetUser",
+            Ok(CallToolResult::error(vec![Content::text(
+                r#"{"errors":[{"message":"column users.ssn does not exist"}]}"#,
+            )])),
+        )
+        .unwrap();
+
+        assert_eq!(result.is_error, Some(true));
+        assert!(!text(&result).contains("users.ssn"));
+        let id = text(&result).split(' ').nth(6).unwrap();
+        assert!(logs_contain(&format!("error_id={id}")));
+        assert!(logs_contain("column users.ssn does not exist"));
+    }
+
+    #[traced_test]
+    #[test]
+    fn partial_data_warnings_are_sanitized_and_logged() {
+        let result = sanitize_result(
+            "GetUser",
+            Ok(CallToolResult::success(vec![
+                Content::text(r#"{"data":{"user":{"id":"1","ssn":null}}}"#),
+                Content::text(format!(
+                    r#"{PARTIAL_DATA_WARNING}: [{{"message":"column users.ssn does not exist"}}]"#
+                )),
+            ])),
+        )
+        .unwrap();
+
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(text(&result), r#"{"data":{"user":{"id":"1","ssn":null}}}"#);
+        let RawContent::Text(warning) = &result.content[1].raw else {
+            panic!("expected a text warning");
+        };
+        assert!(!warning.text.contains("users.ssn"));
+        let id = warning
+            .text
+            .strip_prefix(&format!("{PARTIAL_DATA_WARNING}. Report error ID "))
+            .and_then(|message| message.strip_suffix(" for details."))
+            .unwrap();
+        assert!(logs_contain(&format!("error_id={id}")));
+        assert!(logs_contain("column users.ssn does not exist"));
+    }
+
+    #[test]
+    fn invalid_calls_are_not_sanitized() {
+        let result = sanitize_result(
+            "GetUser",
+            Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "Missing argument id",
+                None,
+            )),
+        );
+        assert_eq!(result.err().unwrap().message, "Missing argument id");
+
+        let result = sanitize_result(
+            "GetUser",
+            Ok(CallToolResult::success(vec![Content::text("{}")])),
+        )
+        .unwrap();
+        assert_eq!(text(&result), "{}");
+    }
+}
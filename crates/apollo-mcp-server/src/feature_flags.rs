@@ -0,0 +1,123 @@
+//! Feature flags gating operation tools
+//!
+//! Operations can require a feature flag, so that their tools are only exposed while the flag is
+//! on. Flags are set in the config file, or with `APOLLO_MCP_FEATURE_FLAGS__FLAGS__<KEY>`
+//! environment variables like the rest of the config, and are evaluated again when the config is
+//! reloaded on `SIGHUP`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+/// Configuration options for feature flags.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct FeatureFlagsConfig {
+    /// Whether each feature flag is on, by key. Flags which are not set are off.
+    pub flags: HashMap<String, bool>,
+
+    /// The key of the feature flag each operation requires, by operation name
+    pub operations: HashMap<String, String>,
+}
+
+impl FeatureFlagsConfig {
+    /// Whether an operation is exposed, because it requires no feature flag or its flag is on
+    pub fn is_exposed(&self, operation_name: &str) -> bool {
+        self.operations
+            .get(operation_name)
+            .is_none_or(|flag| self.flags.get(flag).copied().unwrap_or_default())
+    }
+}
+
+/// Reads the latest feature flags when the config is reloaded
+pub type ConfigLoader = Arc<dyn Fn() -> anyhow::Result<FeatureFlagsConfig> + Send + Sync>;
+
+/// Feature flags which can be swapped while the server is running
+#[derive(Clone, Debug, Default)]
+pub struct FeatureFlags {
+    current: Arc<RwLock<FeatureFlagsConfig>>,
+}
+
+impl FeatureFlags {
+    pub fn new(config: FeatureFlagsConfig) -> Self {
+        Self {
+            current: Arc::new(RwLock::new(config)),
+        }
+    }
+
+    /// The flags in use
+    pub async fn current(&self) -> FeatureFlagsConfig {
+        self.current.read().await.clone()
+    }
+
+    /// Replace the flags with those read by the loader
+    pub async fn reload(&self, loader: &ConfigLoader) -> anyhow::Result<()> {
+        let config = loader()?;
+        *self.current.write().await = config;
+        Ok(())
+    }
+
+    /// Reload the flags whenever the process receives `SIGHUP`, calling `on_reload` after each
+    /// successful reload
+    pub fn reload_on_hangup<F, Fut>(&self, loader: ConfigLoader, on_reload: F)
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        #[cfg(unix)]
+        {
+            let flags = self.clone();
+            match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                Ok(mut hangup) => {
+                    tokio::spawn(async move {
+                        while hangup.recv().await.is_some() {
+                            match flags.reload(&loader).await {
+                                Ok(()) => {
+                                    info!("Reloaded the feature flags");
+                                    on_reload().await;
+                                }
+                                Err(e) => warn!("Failed to reload the feature flags: {e}"),
+                            }
+                        }
+                    });
+                }
+                Err(e) => warn!("Failed to install SIGHUP handler to reload feature flags: {e}"),
+            }
+        }
+
+        #[cfg(not(unix))]
+        {
+            let _ = (loader, on_reload);
+            warn!("Reloading the feature flags on SIGHUP is only supported on Unix");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operations_are_exposed_only_when_their_flag_is_on() {
+        let config = FeatureFlagsConfig {
+            flags: HashMap::from([
+                ("new_checkout".to_string(), true),
+                ("beta".to_string(), false),
+            ]),
+            operations: HashMap::from([
+                ("Checkout".to_string(), "new_checkout".to_string()),
+                ("BetaSearch".to_string(), "beta".to_string()),
+                ("Preview".to_string(), "unset".to_string()),
+            ]),
+        };
+        assert!(config.is_exposed("Checkout"));
+        assert!(!config.is_exposed("BetaSearch"));
+        assert!(!config.is_exposed("Preview"));
+        assert!(config.is_exposed("GetUser"));
+    }
+}
@@ -1,15 +1,72 @@
 //! Execute GraphQL operations from an MCP tool
 
 use crate::errors::McpError;
-use reqwest::header::{HeaderMap, HeaderValue};
+use crate::operations::{ErrorCodes, GraphQLErrors, OutputValidation, StripNulls};
+use crate::projection::Projection;
+use crate::signing::SigningConfig;
+use crate::telemetry::GRAPHQL_REQUEST_SPAN_NAME;
+use apollo_mcp_registry::uplink::persisted_queries::ExecutionPolicy;
+use incremental::TimedResponse;
+use reqwest::header::{ACCEPT, HeaderMap, HeaderValue};
 use rmcp::model::{CallToolResult, Content, ErrorCode};
 use serde_json::{Map, Value};
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{Instrument as _, field, warn};
 use url::Url;
+use websocket::WebSocketError;
+
+mod cost;
+mod incremental;
+mod output_schema;
+mod scope_mask;
+mod strip_nulls;
+mod websocket;
+
+pub use cost::{ESTIMATE_ARGUMENT_NAME, estimate_cost};
+pub use output_schema::output_schema;
+pub use scope_mask::ScopeMask;
+pub use websocket::WebSocketConnection;
+
+/// The start of the warning returned alongside partial data, followed by the errors
+pub(crate) const PARTIAL_DATA_WARNING: &str =
+    "Warning: the operation returned partial data with errors";
+
+/// How long to wait for an operation to complete
+#[derive(Clone, Copy, Debug)]
+pub struct OperationTimeout {
+    /// The time after which the operation times out
+    pub duration: Duration,
+
+    /// Return the data delivered so far by an incremental (`@defer`) response on timeout,
+    /// instead of an error
+    pub partial_results: bool,
+}
 
 pub struct Request<'a> {
     pub input: Value,
     pub endpoint: &'a Url,
     pub headers: HeaderMap,
+    /// Masks fields in the response which the caller is not scoped for, if any
+    pub scope_mask: Option<ScopeMask>,
+    /// The claims of the caller's validated JWT, if any
+    pub claims: Option<&'a Map<String, Value>>,
+    /// Times out the operation, if set
+    pub timeout: Option<OperationTimeout>,
+    /// How to return responses containing errors alongside data
+    pub graphql_errors: GraphQLErrors,
+    /// The MCP error codes to fail with for upstream GraphQL error codes
+    pub error_codes: &'a ErrorCodes,
+    /// Signs the request before it is sent, if set
+    pub signing: Option<&'a SigningConfig>,
+    /// Selects part of the response to return, if set
+    pub projection: Option<Projection>,
+    /// Removes null and empty fields from the response, if set
+    pub strip_nulls: Option<StripNulls>,
+    /// Describes the coercions applied to the input in the result
+    pub report_coercions: bool,
+    /// Sends the operation over a persistent websocket connection for its headers, if set
+    pub websocket: Option<&'a WebSocketConnection>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -26,25 +83,79 @@ pub trait Executable {
     /// Get the operation to execute and its name
     fn operation(&self, input: Value) -> Result<OperationDetails, McpError>;
 
-    /// Get the variables to execute the operation with
-    fn variables(&self, input: Value) -> Result<Value, McpError>;
+    /// Get the variables to execute the operation with, given the caller's JWT claims (if any)
+    fn variables(
+        &self,
+        input: Value,
+        claims: Option<&Map<String, Value>>,
+    ) -> Result<Value, McpError>;
+
+    /// Get the variables to execute the operation with, alongside descriptions of the coercions
+    /// applied to the input to produce them
+    fn coerced_variables(
+        &self,
+        input: Value,
+        claims: Option<&Map<String, Value>>,
+    ) -> Result<(Value, Vec<String>), McpError> {
+        self.variables(input, claims)
+            .map(|variables| (variables, Vec::new()))
+    }
 
     /// Get the headers to execute the operation with
     fn headers(&self, default_headers: &HeaderMap<HeaderValue>) -> HeaderMap<HeaderValue>;
 
+    /// Get the policy the operation was defined with, which takes precedence over the request's
+    fn execution_policy(&self) -> ExecutionPolicy {
+        ExecutionPolicy::default()
+    }
+
+    /// The output schema to check the data in the response against, and what to do when it
+    /// doesn't match, if the data is checked
+    fn output_validation(&self) -> Option<(&Value, OutputValidation)> {
+        None
+    }
+
     /// Execute as a GraphQL operation using the endpoint and headers
     async fn execute(&self, request: Request<'_>) -> Result<CallToolResult, McpError> {
+        let policy = self.execution_policy();
+        let timeout = policy
+            .timeout
+            .map(|duration| OperationTimeout {
+                duration,
+                partial_results: request
+                    .timeout
+                    .is_some_and(|timeout| timeout.partial_results),
+            })
+            .or(request.timeout);
+
+        let span = tracing::info_span!(
+            GRAPHQL_REQUEST_SPAN_NAME,
+            "url.full" = %request.endpoint,
+            "graphql.operation.name" = field::Empty,
+            "graphql.persisted_query.id" = field::Empty,
+            "http.response.status_code" = field::Empty
+        );
+
         let client_metadata = serde_json::json!({
             "name": "mcp",
             "version": std::env!("CARGO_PKG_VERSION")
         });
 
-        let mut request_body = Map::from_iter([(
-            String::from("variables"),
-            self.variables(request.input.clone())?,
-        )]);
+        let (variables, coercions) =
+            self.coerced_variables(request.input.clone(), request.claims)?;
+        let coercion_notice = (request.report_coercions && !coercions.is_empty())
+            .then(|| format!("Note: the input was coerced: {}", coercions.join("; ")));
+        let mut request_body = Map::from_iter([(String::from("variables"), variables)]);
+
+        // The operation is needed to find which response fields to mask
+        let masked_operation = request
+            .scope_mask
+            .as_ref()
+            .map(|_| self.operation(request.input.clone()))
+            .transpose()?;
 
         if let Some(id) = self.persisted_query_id() {
+            span.record("graphql.persisted_query.id", id.as_str());
             request_body.insert(
                 String::from("extensions"),
                 serde_json::json!({
@@ -70,53 +181,235 @@ pub trait Executable {
             );
 
             if let Some(op_name) = operation_name {
+                span.record("graphql.operation.name", op_name.as_str());
                 request_body.insert(String::from("operationName"), Value::String(op_name));
             }
         }
 
-        reqwest::Client::new()
-            .post(request.endpoint.as_str())
-            .headers(self.headers(&request.headers))
-            .body(Value::Object(request_body).to_string())
-            .send()
-            .await
+        let mut headers = self.headers(&request.headers);
+        if timeout.is_some_and(|timeout| timeout.partial_results) {
+            headers.insert(
+                ACCEPT,
+                HeaderValue::from_static(incremental::ACCEPT_INCREMENTAL),
+            );
+        }
+
+        let deadline = timeout.map(|timeout| (Instant::now() + timeout.duration, timeout));
+
+        // Operations are sent over the websocket when configured, on a connection initialized with
+        // their headers, and over HTTP when it can't be connected to. Incremental delivery and
+        // signing, which covers each request body, are only supported over HTTP.
+        let websocket = request.websocket.filter(|_| {
+            !timeout.is_some_and(|timeout| timeout.partial_results) && request.signing.is_none()
+        });
+        let websocket_json = match websocket {
+            Some(websocket) => {
+                let execute = websocket
+                    .execute(&headers, Value::Object(request_body.clone()))
+                    .instrument(span.clone());
+                let result = match deadline {
+                    Some((deadline, timeout)) => tokio::time::timeout_at(deadline, execute)
+                        .await
+                        .map_err(|_| timed_out(timeout.duration))?,
+                    None => execute.await,
+                };
+                match result {
+                    Ok(json) => Some(json),
+                    Err(error @ WebSocketError::Connect(_)) => {
+                        warn!("{error}, falling back to HTTP");
+                        None
+                    }
+                    Err(error) => {
+                        return Err(McpError::new(
+                            ErrorCode::INTERNAL_ERROR,
+                            error.to_string(),
+                            None,
+                        ));
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let mut partial_notice = None;
+        let mut json = if let Some(json) = websocket_json {
+            json
+        } else {
+            let body = Value::Object(request_body).to_string();
+            if let Some(signing) = request.signing {
+                signing.sign(&body, &mut headers)?;
+            }
+
+            let send = reqwest::Client::new()
+                .post(request.endpoint.as_str())
+                .headers(headers)
+                .body(body)
+                .send()
+                .instrument(span.clone());
+            let response = match deadline {
+                Some((deadline, timeout)) => tokio::time::timeout_at(deadline, send)
+                    .await
+                    .map_err(|_| timed_out(timeout.duration))?,
+                None => send.await,
+            }
             .map_err(|reqwest_error| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
                     format!("Failed to send GraphQL request: {reqwest_error}"),
                     None,
                 )
-            })?
-            .json::<Value>()
-            .await
-            .map_err(|reqwest_error| {
+            })?;
+            span.record("http.response.status_code", response.status().as_u16());
+
+            let read_error = |reqwest_error: reqwest::Error| {
                 McpError::new(
                     ErrorCode::INTERNAL_ERROR,
                     format!("Failed to read GraphQL response body: {reqwest_error}"),
                     None,
                 )
+            };
+            match deadline {
+                Some((deadline, timeout)) => match incremental::read_response(response, deadline)
+                    .await
+                    .map_err(read_error)?
+                {
+                    TimedResponse::Complete(json) => json,
+                    TimedResponse::Partial(json) if timeout.partial_results => {
+                        partial_notice = Some(format!(
+                            "The operation timed out after {:?}. The response contains only the data delivered before the timeout.",
+                            timeout.duration
+                        ));
+                        json
+                    }
+                    TimedResponse::Partial(_) | TimedResponse::TimedOut => {
+                        return Err(timed_out(timeout.duration));
+                    }
+                },
+                None => response.json::<Value>().await.map_err(read_error)?,
+            }
+        };
+
+        if let Some(max_response_bytes) = policy.max_response_bytes {
+            let response_bytes = json.to_string().len();
+            if response_bytes > max_response_bytes {
+                return Err(McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!(
+                        "GraphQL response of {response_bytes} bytes exceeds the maximum of {max_response_bytes} bytes"
+                    ),
+                    None,
+                ));
+            }
+        }
+
+        // The data is checked before it is masked or stripped of nulls, which both change its shape
+        let mut output_warning = None;
+        if let Some((output_schema, validation)) = self.output_validation()
+            && let Some(data) = json.get("data").filter(|data| !data.is_null())
+        {
+            let mismatches = output_schema::validate(output_schema, data);
+            if !mismatches.is_empty() {
+                let message = format!(
+                    "The response does not match the output schema of the operation: {}",
+                    mismatches.join("; ")
+                );
+                match validation {
+                    OutputValidation::Off => {}
+                    OutputValidation::Warn => {
+                        warn!("{message}");
+                        output_warning = Some(format!("Warning: {message}"));
+                    }
+                    OutputValidation::Fail => {
+                        return Err(McpError::new(ErrorCode::INTERNAL_ERROR, message, None));
+                    }
+                }
+            }
+        }
+
+        if let (Some(scope_mask), Some(operation)) = (&request.scope_mask, masked_operation) {
+            scope_mask
+                .apply(
+                    &operation.query,
+                    operation.operation_name.as_deref(),
+                    &mut json,
+                )
+                .await;
+        }
+
+        if let Some(strip_nulls) = &request.strip_nulls {
+            strip_nulls.apply(&mut json);
+        }
+
+        let has_errors = json.get("errors").is_some_and(|errors| {
+            !errors.is_null() && errors.as_array().is_none_or(|errors| !errors.is_empty())
+        });
+        let has_data = json.get("data").is_some_and(|data| !data.is_null());
+        let failed = has_errors && (!has_data || request.graphql_errors == GraphQLErrors::Fail);
+
+        // Errors with a mapped code fail the call with the matching MCP error instead
+        if failed
+            && let Some(errors) = json.get("errors").and_then(Value::as_array)
+            && let Some((code, message)) = errors.iter().find_map(|error| {
+                let code = request
+                    .error_codes
+                    .get(error.pointer("/extensions/code")?.as_str()?)?;
+                let message = error.get("message").and_then(Value::as_str);
+                Some((code, message.unwrap_or("The operation failed").to_string()))
             })
-            .map(|json| CallToolResult {
-                content: vec![Content::json(&json).unwrap_or(Content::text(json.to_string()))],
-                is_error: Some(
-                    json.get("errors")
-                        .filter(|value| !matches!(value, Value::Null))
-                        .is_some()
-                        && json
-                            .get("data")
-                            .filter(|value| !matches!(value, Value::Null))
-                            .is_none(),
-                ),
-            })
+        {
+            return Err(McpError::new(
+                code,
+                message,
+                Some(serde_json::json!({ "errors": errors })),
+            ));
+        }
+
+        let errors_warning =
+            (has_errors && has_data && request.graphql_errors == GraphQLErrors::Warn)
+                .then(|| json.as_object_mut().and_then(|json| json.remove("errors")))
+                .flatten()
+                .map(|errors| format!("{PARTIAL_DATA_WARNING}: {errors}"));
+
+        let json = match &request.projection {
+            Some(projection) => projection.apply(&json)?,
+            None => json,
+        };
+
+        Ok(CallToolResult {
+            content: std::iter::once(
+                Content::json(&json).unwrap_or(Content::text(json.to_string())),
+            )
+            .chain(coercion_notice.map(Content::text))
+            .chain(errors_warning.map(Content::text))
+            .chain(output_warning.map(Content::text))
+            .chain(partial_notice.map(Content::text))
+            .collect(),
+            is_error: Some(failed),
+        })
     }
 }
 
+/// The error returned when an operation times out
+fn timed_out(duration: Duration) -> McpError {
+    McpError::new(
+        ErrorCode::INTERNAL_ERROR,
+        format!("GraphQL operation timed out after {duration:?}"),
+        None,
+    )
+}
+
 #[cfg(test)]
 mod test {
     use crate::errors::McpError;
-    use crate::graphql::{Executable, OperationDetails, Request};
+    use crate::graphql::{
+        Executable, OperationDetails, OperationTimeout, Request, WebSocketConnection, websocket,
+    };
+    use crate::operations::{ErrorCodes, GraphQLErrors};
+    use crate::signing::{SigningAlgorithm, SigningConfig};
     use http::{HeaderMap, HeaderValue};
+    use rmcp::model::ErrorCode;
     use serde_json::{Map, Value, json};
+    use std::time::Duration;
     use url::Url;
 
     struct TestExecutableWithoutPersistedQueryId;
@@ -133,7 +426,11 @@ mod test {
             })
         }
 
-        fn variables(&self, _input: Value) -> Result<Value, McpError> {
+        fn variables(
+            &self,
+            _input: Value,
+            _claims: Option<&Map<String, Value>>,
+        ) -> Result<Value, McpError> {
             let json = r#"{ "arg1": "foobar" }"#;
             let parsed_json = serde_json::from_str(json).expect("Failed to parse json");
             let json_map: Map<String, Value> = match parsed_json {
@@ -143,8 +440,8 @@ mod test {
             Ok(Value::from(json_map))
         }
 
-        fn headers(&self, _default_headers: &HeaderMap<HeaderValue>) -> HeaderMap<HeaderValue> {
-            HeaderMap::new()
+        fn headers(&self, default_headers: &HeaderMap<HeaderValue>) -> HeaderMap<HeaderValue> {
+            default_headers.clone()
         }
     }
 
@@ -162,7 +459,11 @@ mod test {
             })
         }
 
-        fn variables(&self, _input: Value) -> Result<Value, McpError> {
+        fn variables(
+            &self,
+            _input: Value,
+            _claims: Option<&Map<String, Value>>,
+        ) -> Result<Value, McpError> {
             Ok(Value::String("mock_variables".to_string()))
         }
 
@@ -180,6 +481,16 @@ mod test {
             input: json!({}),
             endpoint: &url,
             headers: HeaderMap::new(),
+            scope_mask: None,
+            claims: None,
+            timeout: None,
+            graphql_errors: GraphQLErrors::default(),
+            error_codes: &ErrorCodes::default(),
+            signing: None,
+            projection: None,
+            strip_nulls: None,
+            report_coercions: false,
+            websocket: None,
         };
         let expected_request_body = json!({
             "variables": { "arg1": "foobar" },
@@ -223,6 +534,16 @@ mod test {
             input: json!({}),
             endpoint: &url,
             headers: HeaderMap::new(),
+            scope_mask: None,
+            claims: None,
+            timeout: None,
+            graphql_errors: GraphQLErrors::default(),
+            error_codes: &ErrorCodes::default(),
+            signing: None,
+            projection: None,
+            strip_nulls: None,
+            report_coercions: false,
+            websocket: None,
         };
         let expected_request_body = json!({
             "variables": "mock_variables",
@@ -267,6 +588,16 @@ mod test {
             input: json!({}),
             endpoint: &url,
             headers: HeaderMap::new(),
+            scope_mask: None,
+            claims: None,
+            timeout: None,
+            graphql_errors: GraphQLErrors::default(),
+            error_codes: &ErrorCodes::default(),
+            signing: None,
+            projection: None,
+            strip_nulls: None,
+            report_coercions: false,
+            websocket: None,
         };
 
         // when
@@ -297,6 +628,16 @@ mod test {
             input: json!({}),
             endpoint: &url,
             headers: HeaderMap::new(),
+            scope_mask: None,
+            claims: None,
+            timeout: None,
+            graphql_errors: GraphQLErrors::default(),
+            error_codes: &ErrorCodes::default(),
+            signing: None,
+            projection: None,
+            strip_nulls: None,
+            report_coercions: false,
+            websocket: None,
         };
 
         server
@@ -336,6 +677,16 @@ mod test {
             input: json!({}),
             endpoint: &url,
             headers: HeaderMap::new(),
+            scope_mask: None,
+            claims: None,
+            timeout: None,
+            graphql_errors: GraphQLErrors::default(),
+            error_codes: &ErrorCodes::default(),
+            signing: None,
+            projection: None,
+            strip_nulls: None,
+            report_coercions: false,
+            websocket: None,
         };
 
         server
@@ -355,4 +706,429 @@ mod test {
         assert!(result.is_error.is_some());
         assert!(result.is_error.unwrap());
     }
+
+    #[tokio::test]
+    async fn gql_response_errors_with_data_are_warnings() {
+        let mut server = mockito::Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+        server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "data": { "mockOp": { "id": "1", "name": null } },
+                    "errors": [{ "message": "name failed", "path": ["mockOp", "name"] }]
+                })
+                .to_string(),
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let error_codes = ErrorCodes::default();
+        let execute = |graphql_errors| {
+            TestExecutableWithoutPersistedQueryId {}.execute(Request {
+                input: json!({}),
+                endpoint: &url,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors,
+                error_codes: &error_codes,
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: None,
+            })
+        };
+
+        let result = execute(GraphQLErrors::Warn).await.unwrap();
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(
+            result
+                .content
+                .iter()
+                .filter_map(|content| content.as_text().map(|text| text.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                r#"{"data":{"mockOp":{"id":"1","name":null}}}"#,
+                r#"Warning: the operation returned partial data with errors: [{"message":"name failed","path":["mockOp","name"]}]"#
+            ]
+        );
+
+        // Sanitizing keeps the partial data, but not the errors
+        let sanitized = crate::errors::sanitize_result("mockOp", Ok(result)).unwrap();
+        let sanitized = serde_json::to_string(&sanitized.content).unwrap();
+        assert!(sanitized.contains(r#"{\"mockOp\":{\"id\":\"1\",\"name\":null}}"#));
+        assert!(!sanitized.contains("name failed"));
+
+        let result = execute(GraphQLErrors::Fail).await.unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert_eq!(result.content.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn gql_error_codes_are_mapped_to_mcp_error_codes() {
+        let mut server = mockito::Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+        server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "data": null,
+                    "errors": [{
+                        "message": "Not logged in",
+                        "extensions": { "code": "UNAUTHENTICATED" }
+                    }]
+                })
+                .to_string(),
+            )
+            .expect(2)
+            .create_async()
+            .await;
+        let execute = |error_codes| {
+            TestExecutableWithoutPersistedQueryId {}.execute(Request {
+                input: json!({}),
+                endpoint: &url,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors: GraphQLErrors::default(),
+                error_codes,
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: None,
+            })
+        };
+
+        let default_codes = ErrorCodes::default();
+        let error = execute(&default_codes).await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_REQUEST);
+        assert_eq!(error.message, "Not logged in");
+
+        let configured_codes: ErrorCodes =
+            serde_json::from_value(json!({ "UNAUTHENTICATED": "resource_not_found" })).unwrap();
+        let error = execute(&configured_codes).await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::RESOURCE_NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn requests_are_signed() {
+        let mut server = mockito::Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+        let mock = server
+            .mock("POST", "/")
+            .match_header(
+                "x-signature",
+                mockito::Matcher::Regex("^[0-9a-f]{64}$".to_string()),
+            )
+            .match_header(
+                "x-signature-timestamp",
+                mockito::Matcher::Regex("^[0-9]+$".to_string()),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "data": {} }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let signing = SigningConfig {
+            secret: "secret".to_string(),
+            algorithm: SigningAlgorithm::HmacSha256,
+            header: "x-signature".to_string(),
+            timestamp_header: "x-signature-timestamp".to_string(),
+        };
+
+        let result = TestExecutableWithoutPersistedQueryId {}
+            .execute(Request {
+                input: json!({}),
+                endpoint: &url,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &ErrorCodes::default(),
+                signing: Some(&signing),
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: None,
+            })
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(false));
+        mock.assert_async().await;
+    }
+
+    /// Respond with an initial deferred payload, then stall before delivering the rest
+    fn deferred_response(server: &mut mockito::Server) -> mockito::Mock {
+        server
+            .mock("POST", "/")
+            .match_header("accept", mockito::Matcher::Regex("multipart/mixed".into()))
+            .with_status(200)
+            .with_header(
+                "content-type",
+                "multipart/mixed;boundary=\"graphql\";deferSpec=20220824",
+            )
+            .with_chunked_body(|w| {
+                w.write_all(b"\r\n--graphql\r\ncontent-type: application/json\r\n\r\n{\"data\":{\"mockOp\":{\"id\":\"1\"}},\"hasNext\":true}\r\n--graphql\r\n")?;
+                w.flush()?;
+                std::thread::sleep(Duration::from_secs(2));
+                w.write_all(b"content-type: application/json\r\n\r\n{\"incremental\":[{\"data\":{\"name\":\"slow\"},\"path\":[\"mockOp\"]}],\"hasNext\":false}\r\n--graphql--\r\n")
+            })
+            .create()
+    }
+
+    #[tokio::test]
+    async fn returns_partial_data_when_deferred_response_times_out() {
+        let mut server = mockito::Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+        let _mock = deferred_response(&mut server);
+
+        let result = TestExecutableWithoutPersistedQueryId {}
+            .execute(Request {
+                input: json!({}),
+                endpoint: &url,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: Some(OperationTimeout {
+                    duration: Duration::from_millis(500),
+                    partial_results: true,
+                }),
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &ErrorCodes::default(),
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: None,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(
+            result
+                .content
+                .iter()
+                .filter_map(|content| content.as_text().map(|text| text.text.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                r#"{"data":{"mockOp":{"id":"1"}}}"#,
+                "The operation timed out after 500ms. The response contains only the data delivered before the timeout."
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn times_out_without_partial_results() {
+        let mut server = mockito::Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+        server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_chunked_body(|w| {
+                std::thread::sleep(Duration::from_secs(2));
+                w.write_all(br#"{"data":{}}"#)
+            })
+            .create_async()
+            .await;
+
+        let result = TestExecutableWithoutPersistedQueryId {}
+            .execute(Request {
+                input: json!({}),
+                endpoint: &url,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: Some(OperationTimeout {
+                    duration: Duration::from_millis(500),
+                    partial_results: true,
+                }),
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &ErrorCodes::default(),
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: None,
+            })
+            .await;
+
+        assert_eq!(
+            result.map_err(|e| e.message.to_string()),
+            Err("GraphQL operation timed out after 500ms".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn executes_over_websocket_when_configured() {
+        // given
+        let url = Url::parse("http://localhost/no-server").unwrap();
+        let server = websocket::test::mock_server(Some(json!({ "mockOp": { "id": "1" } }))).await;
+        let websocket = WebSocketConnection::new(server.url.clone());
+        let mock_request = Request {
+            input: json!({}),
+            endpoint: &url,
+            headers: HeaderMap::new(),
+            scope_mask: None,
+            claims: None,
+            timeout: None,
+            graphql_errors: GraphQLErrors::default(),
+            error_codes: &ErrorCodes::default(),
+            signing: None,
+            projection: None,
+            strip_nulls: None,
+            report_coercions: false,
+            websocket: Some(&websocket),
+        };
+
+        // when
+        let test_executable = TestExecutableWithoutPersistedQueryId {};
+        let result = test_executable.execute(mock_request).await.unwrap();
+
+        // then
+        assert!(!result.is_error.unwrap());
+        assert_eq!(
+            result.content[0].as_text().unwrap().text,
+            json!({ "data": { "mockOp": { "id": "1" } } }).to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_http_when_websocket_cannot_be_reached() {
+        // given
+        let mut server = mockito::Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+        let websocket = WebSocketConnection::new(Url::parse("ws://localhost:1/graphql").unwrap());
+        let mock_request = Request {
+            input: json!({}),
+            endpoint: &url,
+            headers: HeaderMap::new(),
+            scope_mask: None,
+            claims: None,
+            timeout: None,
+            graphql_errors: GraphQLErrors::default(),
+            error_codes: &ErrorCodes::default(),
+            signing: None,
+            projection: None,
+            strip_nulls: None,
+            report_coercions: false,
+            websocket: Some(&websocket),
+        };
+        let mock = server
+            .mock("POST", "/")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "data": {} }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+
+        // when
+        let test_executable = TestExecutableWithoutPersistedQueryId {};
+        let result = test_executable.execute(mock_request).await.unwrap();
+
+        // then
+        mock.assert();
+        assert!(!result.is_error.unwrap());
+    }
+
+    #[tokio::test]
+    async fn forwarded_authorization_reaches_the_websocket() {
+        let url = Url::parse("http://localhost/no-server").unwrap();
+        let server = websocket::test::mock_server(Some(json!({ "mockOp": { "id": "1" } }))).await;
+        let websocket = WebSocketConnection::new(server.url.clone());
+        let error_codes = ErrorCodes::default();
+        let execute = |authorization: &'static str| {
+            TestExecutableWithoutPersistedQueryId {}.execute(Request {
+                input: json!({}),
+                endpoint: &url,
+                headers: HeaderMap::from_iter([(
+                    http::header::AUTHORIZATION,
+                    HeaderValue::from_static(authorization),
+                )]),
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &error_codes,
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: Some(&websocket),
+            })
+        };
+
+        execute("Bearer alice").await.unwrap();
+        execute("Bearer bob").await.unwrap();
+
+        // Each caller's operations are sent over a connection initialized with their token
+        assert_eq!(
+            server.connection_inits(),
+            vec![
+                json!({ "authorization": "Bearer alice" }),
+                json!({ "authorization": "Bearer bob" })
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn signed_requests_are_sent_over_http() {
+        let mut server = mockito::Server::new_async().await;
+        let url = Url::parse(server.url().as_str()).unwrap();
+        let mock = server
+            .mock("POST", "/")
+            .match_header(
+                "x-signature",
+                mockito::Matcher::Regex("^[0-9a-f]{64}$".to_string()),
+            )
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "data": {} }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let websocket_server = websocket::test::mock_server(Some(json!({}))).await;
+        let websocket = WebSocketConnection::new(websocket_server.url.clone());
+        let signing = SigningConfig {
+            secret: "secret".to_string(),
+            algorithm: SigningAlgorithm::HmacSha256,
+            header: "x-signature".to_string(),
+            timestamp_header: "x-signature-timestamp".to_string(),
+        };
+
+        TestExecutableWithoutPersistedQueryId {}
+            .execute(Request {
+                input: json!({}),
+                endpoint: &url,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &ErrorCodes::default(),
+                signing: Some(&signing),
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: Some(&websocket),
+            })
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert!(websocket_server.connection_inits().is_empty());
+    }
 }
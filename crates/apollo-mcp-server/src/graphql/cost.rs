@@ -0,0 +1,206 @@
+//! Estimate the cost of an operation from the `@cost` and `@listSize` annotations in the schema
+//!
+//! The estimate follows the static cost analysis of demand control, without executing the
+//! operation:
+//!
+//! - Each field costs the weight of its `@cost` annotation, or the `@cost` weight of the type it
+//!   returns. Without either, fields returning objects, interfaces, and unions cost 1, and other
+//!   fields cost nothing.
+//! - The cost of a field returning a list, including the cost of its selections, is multiplied by
+//!   the size of the list. The size is the largest value passed to the `slicingArguments` of the
+//!   field's `@listSize` annotation, or its `assumedSize`, or 1 if the field is not annotated.
+
+use apollo_compiler::ast::Value as GraphQLValue;
+use apollo_compiler::executable::{Field, Selection, SelectionSet};
+use apollo_compiler::schema::{Directive, ExtendedType};
+use apollo_compiler::validation::Valid;
+use apollo_compiler::{ExecutableDocument, Schema};
+use rmcp::model::ErrorCode;
+use serde_json::{Map, Value};
+
+use crate::errors::McpError;
+
+/// The name of the operation tool argument asking for the cost estimate instead of the result
+pub const ESTIMATE_ARGUMENT_NAME: &str = "__estimate";
+
+/// The name of the directive setting the cost of a field or type
+const COST_DIRECTIVE_NAME: &str = "cost";
+
+/// The name of the directive setting the size of lists returned by a field
+const LIST_SIZE_DIRECTIVE_NAME: &str = "listSize";
+
+/// Estimate the cost of an operation with the given variables
+pub fn estimate_cost(
+    schema: &Valid<Schema>,
+    query: &str,
+    operation_name: Option<&str>,
+    variables: &Value,
+) -> Result<u64, McpError> {
+    let invalid = |message: String| McpError::new(ErrorCode::INVALID_PARAMS, message, None);
+    let document = ExecutableDocument::parse_and_validate(schema, query, "operation.graphql")
+        .map_err(|e| {
+            invalid(format!(
+                "Failed to estimate the cost of an invalid operation: {}",
+                e.errors
+            ))
+        })?;
+    let operation = document
+        .operations
+        .get(operation_name)
+        .map_err(|_| invalid(format!("Unknown operation: {operation_name:?}")))?;
+
+    let empty = Map::new();
+    let estimator = CostEstimator {
+        schema,
+        document: &document,
+        variables: variables.as_object().unwrap_or(&empty),
+    };
+    Ok(estimator.selection_set_cost(&operation.selection_set))
+}
+
+struct CostEstimator<'a> {
+    schema: &'a Schema,
+    document: &'a ExecutableDocument,
+    variables: &'a Map<String, Value>,
+}
+
+impl CostEstimator<'_> {
+    fn selection_set_cost(&self, selection_set: &SelectionSet) -> u64 {
+        selection_set
+            .selections
+            .iter()
+            .map(|selection| match selection {
+                Selection::Field(field) => self.field_cost(field),
+                Selection::FragmentSpread(spread) => self
+                    .document
+                    .fragments
+                    .get(&spread.fragment_name)
+                    .map(|fragment| self.selection_set_cost(&fragment.selection_set))
+                    .unwrap_or_default(),
+                Selection::InlineFragment(inline) => self.selection_set_cost(&inline.selection_set),
+            })
+            .fold(0, u64::saturating_add)
+    }
+
+    fn field_cost(&self, field: &Field) -> u64 {
+        let return_type = self
+            .schema
+            .types
+            .get(field.definition.ty.inner_named_type());
+        let weight = field
+            .definition
+            .directives
+            .get(COST_DIRECTIVE_NAME)
+            .and_then(|directive| cost_weight(directive))
+            .or_else(|| {
+                return_type
+                    .and_then(|ty| ty.directives().get(COST_DIRECTIVE_NAME))
+                    .and_then(|directive| cost_weight(directive))
+            })
+            .unwrap_or(match return_type {
+                Some(
+                    ExtendedType::Object(_) | ExtendedType::Interface(_) | ExtendedType::Union(_),
+                ) => 1,
+                _ => 0,
+            });
+        let multiplier = if field.definition.ty.is_list() {
+            self.list_size(field)
+        } else {
+            1
+        };
+        weight
+            .saturating_add(self.selection_set_cost(&field.selection_set))
+            .saturating_mul(multiplier)
+    }
+
+    /// The size of the list returned by a field, from its `@listSize` annotation
+    fn list_size(&self, field: &Field) -> u64 {
+        let Some(list_size) = field.definition.directives.get(LIST_SIZE_DIRECTIVE_NAME) else {
+            return 1;
+        };
+        let slicing_arguments = list_size
+            .specified_argument_by_name("slicingArguments")
+            .and_then(|arguments| arguments.as_list())
+            .unwrap_or_default();
+        slicing_arguments
+            .iter()
+            .filter_map(|name| {
+                let argument = field.argument_by_name(name.as_str()?).ok()?;
+                match argument.as_ref() {
+                    GraphQLValue::Variable(variable) => {
+                        self.variables.get(variable.as_str())?.as_u64()
+                    }
+                    value => value.to_i32().and_then(|size| u64::try_from(size).ok()),
+                }
+            })
+            .max()
+            .or_else(|| {
+                list_size
+                    .specified_argument_by_name("assumedSize")
+                    .and_then(|size| size.to_i32())
+                    .and_then(|size| u64::try_from(size).ok())
+            })
+            .unwrap_or(1)
+    }
+}
+
+/// The weight of a `@cost` annotation
+fn cost_weight(directive: &Directive) -> Option<u64> {
+    directive
+        .specified_argument_by_name("weight")?
+        .to_i32()
+        .and_then(|weight| u64::try_from(weight).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Valid<Schema> {
+        Schema::parse_and_validate(
+            r#"
+            directive @cost(weight: Int!) on FIELD_DEFINITION | OBJECT | SCALAR
+            directive @listSize(assumedSize: Int, slicingArguments: [String!]) on FIELD_DEFINITION
+            type Query {
+                user(id: ID!): User
+                users(first: Int): [User] @listSize(slicingArguments: ["first"])
+                search: [User] @listSize(assumedSize: 20)
+            }
+            type User @cost(weight: 2) {
+                id: ID
+                posts: [Post] @listSize(assumedSize: 5)
+                score: Int @cost(weight: 3)
+            }
+            type Post { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn estimates_cost_from_annotations() {
+        let schema = schema();
+        let estimate = |query: &str, variables: Value| {
+            estimate_cost(&schema, query, None, &variables).unwrap()
+        };
+
+        // User (2) + score (3)
+        assert_eq!(estimate("{ user(id: 1) { id score } }", json!({})), 5);
+        // 10 users, each 2 + 5 posts of 1
+        assert_eq!(
+            estimate(
+                "query($first: Int) { users(first: $first) { posts { id } } }",
+                json!({ "first": 10 })
+            ),
+            70
+        );
+        // 20 assumed users, selected through a fragment
+        assert_eq!(
+            estimate("{ search { ...F } } fragment F on User { id }", json!({})),
+            40
+        );
+    }
+}
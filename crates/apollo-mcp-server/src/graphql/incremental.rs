@@ -0,0 +1,298 @@
+//! Read incrementally delivered (`@defer`) responses, keeping partial results on timeout
+
+use reqwest::header::CONTENT_TYPE;
+use serde_json::Value;
+use tokio::time::Instant;
+use tracing::debug;
+
+/// The accept header requesting incremental delivery, falling back to a single JSON response
+pub(super) const ACCEPT_INCREMENTAL: &str = "multipart/mixed;deferSpec=20220824, application/json";
+
+/// A response read before the timeout fired
+pub(super) enum TimedResponse {
+    /// The complete response
+    Complete(Value),
+
+    /// The data delivered before the timeout fired
+    Partial(Value),
+
+    /// Nothing usable was delivered before the timeout fired
+    TimedOut,
+}
+
+/// Read a response, waiting no longer than the deadline.
+///
+/// If the response is delivered incrementally and the initial payload has arrived by the
+/// deadline, the data delivered so far is returned.
+pub(super) async fn read_response(
+    mut response: reqwest::Response,
+    deadline: Instant,
+) -> Result<TimedResponse, reqwest::Error> {
+    let Some(boundary) = multipart_boundary(&response) else {
+        return match tokio::time::timeout_at(deadline, response.json::<Value>()).await {
+            Ok(json) => json.map(TimedResponse::Complete),
+            Err(_) => Ok(TimedResponse::TimedOut),
+        };
+    };
+
+    let mut parser = MultipartParser::new(&boundary);
+    let mut merged = IncrementalResponse::default();
+    loop {
+        match tokio::time::timeout_at(deadline, response.chunk()).await {
+            Ok(Ok(Some(chunk))) => {
+                for payload in parser.push(&chunk) {
+                    merged.apply(payload);
+                }
+                if merged.is_complete() {
+                    return Ok(TimedResponse::Complete(merged.into_value()));
+                }
+            }
+            Ok(Ok(None)) => return Ok(TimedResponse::Complete(merged.into_value())),
+            Ok(Err(e)) => return Err(e),
+            Err(_) => {
+                return Ok(if merged.has_initial_payload() {
+                    TimedResponse::Partial(merged.into_value())
+                } else {
+                    TimedResponse::TimedOut
+                });
+            }
+        }
+    }
+}
+
+/// Get the multipart boundary of a response, if it is a multipart response
+fn multipart_boundary(response: &reqwest::Response) -> Option<String> {
+    let content_type = response.headers().get(CONTENT_TYPE)?.to_str().ok()?;
+    if !content_type.starts_with("multipart/mixed") {
+        return None;
+    }
+    Some(
+        content_type
+            .split(';')
+            .filter_map(|param| param.trim().strip_prefix("boundary="))
+            .map(|boundary| boundary.trim_matches('"').to_string())
+            .next()
+            .unwrap_or_else(|| "-".to_string()),
+    )
+}
+
+/// Splits a multipart body into JSON payloads as chunks arrive
+struct MultipartParser {
+    delimiter: Vec<u8>,
+    buffer: Vec<u8>,
+}
+
+impl MultipartParser {
+    fn new(boundary: &str) -> Self {
+        Self {
+            delimiter: format!("--{boundary}").into_bytes(),
+            buffer: Vec::new(),
+        }
+    }
+
+    /// Add a chunk of the body, returning the payloads of any parts it completes
+    fn push(&mut self, chunk: &[u8]) -> Vec<Value> {
+        self.buffer.extend_from_slice(chunk);
+        let mut payloads = Vec::new();
+        // A part is complete once the delimiter following it has arrived
+        while let Some(start) = find(&self.buffer, &self.delimiter) {
+            let body_start = start + self.delimiter.len();
+            let Some(end) = self
+                .buffer
+                .get(body_start..)
+                .and_then(|rest| find(rest, &self.delimiter))
+            else {
+                break;
+            };
+            let part: Vec<u8> = self
+                .buffer
+                .drain(..body_start + end)
+                .skip(body_start)
+                .collect();
+            if let Some(payload) = part_payload(&part) {
+                payloads.push(payload);
+            }
+        }
+        payloads
+    }
+}
+
+/// Find the position of a needle in a haystack
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Parse the JSON body of a part, skipping its headers
+fn part_payload(part: &[u8]) -> Option<Value> {
+    let part = String::from_utf8_lossy(part);
+    let body = part
+        .split_once("\r\n\r\n")
+        .or_else(|| part.split_once("\n\n"))
+        .map_or(part.as_ref(), |(_headers, body)| body)
+        .trim();
+    if body.is_empty() {
+        return None;
+    }
+    serde_json::from_str(body)
+        .inspect_err(|e| debug!("Skipping unparseable multipart payload: {e}"))
+        .ok()
+}
+
+/// A response assembled from an initial payload and subsequent incremental payloads
+#[derive(Default)]
+struct IncrementalResponse {
+    response: Option<Value>,
+    has_next: bool,
+}
+
+impl IncrementalResponse {
+    fn has_initial_payload(&self) -> bool {
+        self.response.is_some()
+    }
+
+    fn is_complete(&self) -> bool {
+        self.has_initial_payload() && !self.has_next
+    }
+
+    fn into_value(self) -> Value {
+        self.response.unwrap_or(Value::Null)
+    }
+
+    /// Merge a payload into the response
+    fn apply(&mut self, mut payload: Value) {
+        let Some(object) = payload.as_object_mut() else {
+            return;
+        };
+        if let Some(has_next) = object.remove("hasNext") {
+            self.has_next = has_next.as_bool().unwrap_or_default();
+        }
+        let incremental = object.remove("incremental");
+
+        let Some(response) = &mut self.response else {
+            self.response = Some(payload);
+            return;
+        };
+
+        for item in incremental
+            .as_ref()
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+        {
+            let path = item
+                .get("path")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            if let Some(target) = response
+                .get_mut("data")
+                .and_then(|data| value_at_path(data, &path))
+            {
+                if let Some(data) = item.get("data") {
+                    merge(target, data);
+                }
+                if let (Some(list), Some(items)) = (
+                    target.as_array_mut(),
+                    item.get("items").and_then(Value::as_array),
+                ) {
+                    list.extend(items.iter().cloned());
+                }
+            }
+            if let Some(errors) = item.get("errors").and_then(Value::as_array)
+                && let Some(response) = response.as_object_mut()
+            {
+                let all_errors = response
+                    .entry("errors")
+                    .or_insert_with(|| Value::Array(Vec::new()));
+                if let Some(all_errors) = all_errors.as_array_mut() {
+                    all_errors.extend(errors.iter().cloned());
+                }
+            }
+        }
+    }
+}
+
+/// Get the value at a response path
+fn value_at_path<'a>(value: &'a mut Value, path: &[Value]) -> Option<&'a mut Value> {
+    path.iter().try_fold(value, |value, segment| match segment {
+        Value::String(key) => value.get_mut(key.as_str()),
+        Value::Number(index) => index
+            .as_u64()
+            .and_then(|index| usize::try_from(index).ok())
+            .and_then(|index| value.get_mut(index)),
+        _ => None,
+    })
+}
+
+/// Deep merge a source object into a target object
+fn merge(target: &mut Value, source: &Value) {
+    match (target, source) {
+        (Value::Object(target), Value::Object(source)) => {
+            for (key, value) in source {
+                match target.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, source) => *target = source.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_parts_split_across_chunks() {
+        let mut parser = MultipartParser::new("graphql");
+        assert!(
+            parser
+                .push(b"\r\n--graphql\r\ncontent-type: application/json\r\n\r\n{\"data\":")
+                .is_empty()
+        );
+        assert_eq!(
+            parser.push(b"{\"a\":1},\"hasNext\":true}\r\n--graphql\r\ncontent-type: application/json\r\n\r\n{\"hasNext\":false}\r\n--graphql--\r\n"),
+            vec![
+                json!({ "data": { "a": 1 }, "hasNext": true }),
+                json!({ "hasNext": false })
+            ]
+        );
+    }
+
+    #[test]
+    fn merges_incremental_payloads() {
+        let mut response = IncrementalResponse::default();
+        response.apply(json!({
+            "data": { "product": { "id": "1" }, "reviews": [{ "id": "r1" }] },
+            "hasNext": true
+        }));
+        assert!(response.has_initial_payload());
+        assert!(!response.is_complete());
+
+        response.apply(json!({
+            "incremental": [
+                { "data": { "name": "Widget" }, "path": ["product"] },
+                { "items": [{ "id": "r2" }], "path": ["reviews"] },
+                { "data": { "rating": 5 }, "path": ["reviews", 0], "errors": [{ "message": "oops" }] }
+            ],
+            "hasNext": false
+        }));
+        assert!(response.is_complete());
+        assert_eq!(
+            response.into_value(),
+            json!({
+                "data": {
+                    "product": { "id": "1", "name": "Widget" },
+                    "reviews": [{ "id": "r1", "rating": 5 }, { "id": "r2" }]
+                },
+                "errors": [{ "message": "oops" }]
+            })
+        );
+    }
+}
@@ -0,0 +1,326 @@
+//! Check the data returned for an operation against the output schema of the operation
+//!
+//! The output schema is a JSON schema of the `data` in the response, built from the fields the
+//! operation selects and their types in the GraphQL schema. Fields are required unless they are
+//! only selected conditionally, with `@skip`/`@include` or in a fragment on another type. Custom
+//! scalars can be any value. A response which doesn't match the output schema usually means the
+//! GraphQL endpoint no longer serves the schema the operation was loaded with.
+
+use std::collections::HashMap;
+
+use apollo_compiler::ast::{Document, FragmentDefinition, OperationDefinition, Selection, Type};
+use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::{Name, Node, Schema};
+use serde_json::{Map, Value, json};
+
+/// Build the output schema of an operation, if its root type is in the schema
+pub fn output_schema(
+    schema: &Schema,
+    document: &Document,
+    operation: &OperationDefinition,
+) -> Option<Value> {
+    let root_type = schema.root_operation(operation.operation_type)?;
+    let builder = OutputSchemaBuilder {
+        schema,
+        fragments: document
+            .definitions
+            .iter()
+            .filter_map(|definition| definition.as_fragment_definition())
+            .map(|fragment| (&fragment.name, fragment))
+            .collect(),
+    };
+    Some(builder.object_schema(root_type, &operation.selection_set))
+}
+
+/// Check data against an output schema, returning a description of each mismatch
+pub fn validate(output_schema: &Value, data: &Value) -> Vec<String> {
+    let mut mismatches = Vec::new();
+    check(output_schema, data, "/data", &mut mismatches);
+    mismatches
+}
+
+struct OutputSchemaBuilder<'a> {
+    schema: &'a Schema,
+    fragments: HashMap<&'a Name, &'a Node<FragmentDefinition>>,
+}
+
+impl OutputSchemaBuilder<'_> {
+    fn object_schema(&self, type_name: &str, selection_set: &[Selection]) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        self.collect_fields(
+            type_name,
+            selection_set,
+            false,
+            &mut Vec::new(),
+            &mut properties,
+            &mut required,
+        );
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    }
+
+    /// Add the fields in a selection set to the properties of an object schema. Fields selected
+    /// conditionally are not required.
+    fn collect_fields<'s>(
+        &'s self,
+        type_name: &str,
+        selection_set: &'s [Selection],
+        conditional: bool,
+        visited_fragments: &mut Vec<&'s Name>,
+        properties: &mut Map<String, Value>,
+        required: &mut Vec<String>,
+    ) {
+        for selection in selection_set {
+            match selection {
+                Selection::Field(field) => {
+                    let key = field.alias.as_ref().unwrap_or(&field.name).to_string();
+                    if !conditional
+                        && !is_conditional(&field.directives)
+                        && !required.contains(&key)
+                    {
+                        required.push(key.clone());
+                    }
+                    if properties.contains_key(&key) {
+                        continue;
+                    }
+                    let field_schema = self
+                        .schema
+                        .type_field(type_name, &field.name)
+                        .map(|definition| self.type_schema(&definition.ty, &field.selection_set))
+                        .unwrap_or_else(|_| json!({}));
+                    properties.insert(key, field_schema);
+                }
+                Selection::FragmentSpread(spread) => {
+                    let Some(fragment) = self.fragments.get(&spread.fragment_name) else {
+                        continue;
+                    };
+                    if visited_fragments.contains(&&fragment.name) {
+                        continue;
+                    }
+                    visited_fragments.push(&fragment.name);
+                    self.collect_fields(
+                        &fragment.type_condition,
+                        &fragment.selection_set,
+                        conditional
+                            || is_conditional(&spread.directives)
+                            || !self.always_applies(type_name, &fragment.type_condition),
+                        visited_fragments,
+                        properties,
+                        required,
+                    );
+                    visited_fragments.pop();
+                }
+                Selection::InlineFragment(inline) => {
+                    let fragment_type = inline
+                        .type_condition
+                        .as_ref()
+                        .map_or(type_name, |type_condition| type_condition.as_str());
+                    self.collect_fields(
+                        fragment_type,
+                        &inline.selection_set,
+                        conditional
+                            || is_conditional(&inline.directives)
+                            || !self.always_applies(type_name, fragment_type),
+                        visited_fragments,
+                        properties,
+                        required,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Whether a fragment on a type always applies to a selection on another type. Fragments on
+    /// other types only apply to some of the objects of an interface or union.
+    fn always_applies(&self, type_name: &str, fragment_type: &str) -> bool {
+        type_name == fragment_type
+            || matches!(
+                self.schema.types.get(type_name),
+                Some(ExtendedType::Object(_))
+            )
+    }
+
+    fn type_schema(&self, ty: &Type, selection_set: &[Selection]) -> Value {
+        match ty {
+            Type::Named(name) => nullable(self.named_type_schema(name, selection_set)),
+            Type::NonNullNamed(name) => self.named_type_schema(name, selection_set),
+            Type::List(item) => nullable(json!({
+                "type": "array",
+                "items": self.type_schema(item, selection_set),
+            })),
+            Type::NonNullList(item) => json!({
+                "type": "array",
+                "items": self.type_schema(item, selection_set),
+            }),
+        }
+    }
+
+    fn named_type_schema(&self, name: &Name, selection_set: &[Selection]) -> Value {
+        match self.schema.types.get(name) {
+            Some(ExtendedType::Scalar(_)) => match name.as_str() {
+                "Int" => json!({ "type": "integer" }),
+                "Float" => json!({ "type": "number" }),
+                "String" | "ID" => json!({ "type": "string" }),
+                "Boolean" => json!({ "type": "boolean" }),
+                // Custom scalars can be serialized as anything
+                _ => json!({}),
+            },
+            Some(ExtendedType::Enum(_)) => json!({ "type": "string" }),
+            Some(ExtendedType::Object(_) | ExtendedType::Interface(_) | ExtendedType::Union(_)) => {
+                self.object_schema(name, selection_set)
+            }
+            Some(ExtendedType::InputObject(_)) | None => json!({}),
+        }
+    }
+}
+
+/// Whether a selection is only made depending on a `@skip` or `@include` directive
+fn is_conditional(directives: &apollo_compiler::ast::DirectiveList) -> bool {
+    directives.get("skip").is_some() || directives.get("include").is_some()
+}
+
+/// Allow null alongside the type of a schema. Schemas allowing any value already allow null.
+fn nullable(mut schema: Value) -> Value {
+    if let Some(ty) = schema.get_mut("type")
+        && let Value::String(name) = ty
+    {
+        *ty = json!([name.clone(), "null"]);
+    }
+    schema
+}
+
+fn check(schema: &Value, value: &Value, path: &str, mismatches: &mut Vec<String>) {
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(ty) => vec![ty.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|ty| has_type(value, ty)) {
+            mismatches.push(format!(
+                "{path}: expected {}, found {}",
+                types.join(" or "),
+                type_of(value)
+            ));
+            return;
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            for name in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !object.contains_key(name) {
+                    mismatches.push(format!("{path}: missing field `{name}`"));
+                }
+            }
+            for (name, property) in schema
+                .get("properties")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flatten()
+            {
+                if let Some(value) = object.get(name) {
+                    check(property, value, &format!("{path}/{name}"), mismatches);
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    check(item_schema, item, &format!("{path}/{index}"), mismatches);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema_of(operation: &str) -> Value {
+        let schema = Schema::parse(
+            "type Query { venue(id: ID!): Venue }
+             type Venue { id: ID! name: String capacity: Int tags: [String!]! }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let document = Document::parse(operation, "operation.graphql").unwrap();
+        let operation = document
+            .definitions
+            .iter()
+            .find_map(|definition| definition.as_operation_definition())
+            .unwrap();
+        output_schema(&schema, &document, operation).unwrap()
+    }
+
+    #[test]
+    fn matching_data_is_valid() {
+        let schema = schema_of("query V { venue(id: \"1\") { id name tags } }");
+        assert!(
+            validate(
+                &schema,
+                &json!({ "venue": { "id": "1", "name": null, "tags": ["outdoor"] } })
+            )
+            .is_empty()
+        );
+        assert!(validate(&schema, &json!({ "venue": null })).is_empty());
+    }
+
+    #[test]
+    fn mismatches_are_described() {
+        let schema = schema_of(
+            "query V { venue(id: \"1\") { id ...Size capacity @include(if: false) } }
+             fragment Size on Venue { seats: capacity tags }",
+        );
+        assert_eq!(
+            validate(
+                &schema,
+                &json!({ "venue": { "id": 1, "seats": 100, "tags": [null] } })
+            ),
+            vec![
+                "/data/venue/id: expected string, found number".to_string(),
+                "/data/venue/tags/0: expected string, found null".to_string(),
+            ]
+        );
+        assert_eq!(
+            validate(&schema, &json!({ "venue": { "id": "1", "tags": [] } })),
+            vec!["/data/venue: missing field `seats`".to_string()]
+        );
+    }
+}
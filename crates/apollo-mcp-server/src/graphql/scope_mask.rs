@@ -0,0 +1,188 @@
+//! Mask response fields the caller is not scoped for
+
+use std::sync::Arc;
+
+use apollo_compiler::executable::{Selection, SelectionSet};
+use apollo_compiler::schema::FieldDefinition;
+use apollo_compiler::validation::Valid;
+use apollo_compiler::{ExecutableDocument, Schema};
+use serde_json::{Map, Value};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// The name of the directive marking fields which require a scope
+const REQUIRES_SCOPE_DIRECTIVE_NAME: &str = "requiresScope";
+
+/// The argument of the directive naming the required scope
+const SCOPE_ARGUMENT_NAME: &str = "scope";
+
+/// Masks response fields marked with `@requiresScope(scope:)` when the caller does not have
+/// the required scope
+pub struct ScopeMask {
+    schema: Arc<Mutex<Valid<Schema>>>,
+    scopes: Vec<String>,
+}
+
+impl ScopeMask {
+    pub fn new(schema: Arc<Mutex<Valid<Schema>>>, scopes: Vec<String>) -> Self {
+        Self { schema, scopes }
+    }
+
+    /// Replace the values of fields the caller is not scoped for with null
+    pub async fn apply(&self, query: &str, operation_name: Option<&str>, response: &mut Value) {
+        let Some(data) = response.get_mut("data") else {
+            return;
+        };
+
+        let schema = self.schema.lock().await;
+        let document =
+            match ExecutableDocument::parse_and_validate(&schema, query, "operation.graphql") {
+                Ok(document) => document,
+                Err(e) => {
+                    // Without a valid document there is no way to know which fields are protected
+                    warn!(
+                        "Masking entire response for invalid operation: {}",
+                        e.errors
+                    );
+                    *data = Value::Null;
+                    return;
+                }
+            };
+
+        match document.operations.get(operation_name) {
+            Ok(operation) => {
+                mask_selection_set(&operation.selection_set, &document, &self.scopes, data)
+            }
+            Err(_) => {
+                warn!("Masking entire response for unknown operation: {operation_name:?}");
+                *data = Value::Null;
+            }
+        }
+    }
+}
+
+fn mask_selection_set(
+    selection_set: &SelectionSet,
+    document: &ExecutableDocument,
+    scopes: &[String],
+    value: &mut Value,
+) {
+    match value {
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| mask_selection_set(selection_set, document, scopes, item)),
+        Value::Object(object) => mask_object(selection_set, document, scopes, object),
+        _ => {}
+    }
+}
+
+fn mask_object(
+    selection_set: &SelectionSet,
+    document: &ExecutableDocument,
+    scopes: &[String],
+    object: &mut Map<String, Value>,
+) {
+    for selection in &selection_set.selections {
+        match selection {
+            Selection::Field(field) => {
+                let Some(field_value) = object.get_mut(field.response_key().as_str()) else {
+                    continue;
+                };
+                if required_scope(&field.definition)
+                    .is_some_and(|scope| !scopes.iter().any(|s| s == scope))
+                {
+                    *field_value = Value::Null;
+                } else {
+                    mask_selection_set(&field.selection_set, document, scopes, field_value);
+                }
+            }
+            Selection::FragmentSpread(spread) => {
+                if let Some(fragment) = document.fragments.get(&spread.fragment_name) {
+                    mask_object(&fragment.selection_set, document, scopes, object);
+                }
+            }
+            Selection::InlineFragment(inline) => {
+                mask_object(&inline.selection_set, document, scopes, object);
+            }
+        }
+    }
+}
+
+/// Get the scope required by a field, if any
+fn required_scope(definition: &FieldDefinition) -> Option<&str> {
+    definition
+        .directives
+        .get(REQUIRES_SCOPE_DIRECTIVE_NAME)?
+        .specified_argument_by_name(SCOPE_ARGUMENT_NAME)?
+        .as_str()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Arc<Mutex<Valid<Schema>>> {
+        Arc::new(Mutex::new(
+            Schema::parse_and_validate(
+                r#"
+                directive @requiresScope(scope: String!) on FIELD_DEFINITION
+                type Query { user: User users: [User] }
+                type User {
+                    id: ID!
+                    name: String
+                    email: String @requiresScope(scope: "read:email")
+                }
+                "#,
+                "schema.graphql",
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn masks_fields_caller_is_not_scoped_for() {
+        let mask = ScopeMask::new(schema(), vec!["read:profile".to_string()]);
+        let mut response = json!({
+            "data": {
+                "user": { "id": "1", "name": "Ada", "contact": "ada@example.com" },
+                "users": [{ "id": "2", "email": "grace@example.com" }]
+            }
+        });
+
+        mask.apply(
+            "query Users { user { id name contact: email } users { ...UserFields } } fragment UserFields on User { id email }",
+            Some("Users"),
+            &mut response,
+        )
+        .await;
+
+        assert_eq!(
+            response,
+            json!({
+                "data": {
+                    "user": { "id": "1", "name": "Ada", "contact": null },
+                    "users": [{ "id": "2", "email": null }]
+                }
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_fields_caller_is_scoped_for() {
+        let mask = ScopeMask::new(schema(), vec!["read:email".to_string()]);
+        let mut response = json!({
+            "data": { "user": { "id": "1", "email": "ada@example.com" } }
+        });
+
+        mask.apply("query { user { id email } }", None, &mut response)
+            .await;
+
+        assert_eq!(
+            response,
+            json!({
+                "data": { "user": { "id": "1", "email": "ada@example.com" } }
+            })
+        );
+    }
+}
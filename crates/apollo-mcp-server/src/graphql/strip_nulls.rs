@@ -0,0 +1,167 @@
+//! Remove null and empty fields from response data
+
+use serde_json::Value;
+
+use crate::operations::StripNulls;
+
+/// A segment of the path to a value in the response data
+enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+impl StripNulls {
+    /// Recursively remove fields whose value is null, an empty list, or an empty object from the
+    /// `data` of a response. Null list items are kept, as removing them would change the
+    /// positions of the other items. In strict mode, fields which are null because of an error
+    /// and empty lists are kept as well.
+    pub(crate) fn apply(&self, response: &mut Value) {
+        let error_paths: Vec<Vec<Value>> = if self.strict {
+            response
+                .get("errors")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|error| error.get("path")?.as_array().cloned())
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if let Some(data) = response.get_mut("data") {
+            self.strip(data, &mut Vec::new(), &error_paths);
+        }
+    }
+
+    fn strip(&self, value: &mut Value, path: &mut Vec<Segment>, error_paths: &[Vec<Value>]) {
+        if path.len() >= self.max_depth {
+            return;
+        }
+        match value {
+            Value::Object(object) => {
+                // Strip the children first, so that objects and lists emptied by stripping are
+                // removed too
+                let mut removed = Vec::new();
+                for (name, child) in object.iter_mut() {
+                    path.push(Segment::Field(name.clone()));
+                    self.strip(child, path, error_paths);
+                    let keep = match child {
+                        Value::Null => self.strict && is_error_path(path, error_paths),
+                        Value::Array(items) if items.is_empty() => self.strict,
+                        Value::Object(fields) => !fields.is_empty(),
+                        _ => true,
+                    };
+                    path.pop();
+                    if !keep {
+                        removed.push(name.clone());
+                    }
+                }
+                for name in removed {
+                    object.remove(&name);
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    path.push(Segment::Index(index));
+                    self.strip(item, path, error_paths);
+                    path.pop();
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether a path in the response data is the path of an error
+fn is_error_path(path: &[Segment], error_paths: &[Vec<Value>]) -> bool {
+    error_paths.iter().any(|error_path| {
+        error_path.len() == path.len()
+            && error_path.iter().zip(path).all(|(error_segment, segment)| {
+                match (error_segment, segment) {
+                    (Value::String(name), Segment::Field(field)) => name == field,
+                    (Value::Number(index), Segment::Index(position)) => {
+                        index.as_u64() == Some(*position as u64)
+                    }
+                    _ => false,
+                }
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn response() -> Value {
+        json!({
+            "data": {
+                "user": {
+                    "name": "Ada",
+                    "email": null,
+                    "address": { "street": null, "city": null },
+                    "tags": [],
+                    "friends": [
+                        { "name": "Grace", "nickname": null },
+                        null
+                    ],
+                    "manager": null
+                }
+            },
+            "errors": [
+                { "message": "Forbidden", "path": ["user", "manager"] }
+            ]
+        })
+    }
+
+    #[test]
+    fn null_fields_are_removed() {
+        let mut response = response();
+        StripNulls {
+            max_depth: 32,
+            strict: false,
+        }
+        .apply(&mut response);
+        assert_eq!(
+            response["data"],
+            json!({
+                "user": {
+                    "name": "Ada",
+                    "friends": [{ "name": "Grace" }, null]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn strict_keeps_error_nulls_and_empty_lists() {
+        let mut response = response();
+        StripNulls {
+            max_depth: 32,
+            strict: true,
+        }
+        .apply(&mut response);
+        assert_eq!(
+            response["data"],
+            json!({
+                "user": {
+                    "name": "Ada",
+                    "tags": [],
+                    "friends": [{ "name": "Grace" }, null],
+                    "manager": null
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn fields_deeper_than_the_maximum_depth_are_kept() {
+        let mut response = response();
+        StripNulls {
+            max_depth: 1,
+            strict: false,
+        }
+        .apply(&mut response);
+        assert_eq!(response, self::response());
+    }
+}
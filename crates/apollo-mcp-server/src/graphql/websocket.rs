@@ -0,0 +1,591 @@
+//! Execute GraphQL operations over persistent websocket connections, using the
+//! `graphql-transport-ws` protocol.
+//!
+//! Websockets can't send headers with each operation, so the headers of an operation, which carry
+//! the identity of its caller, are sent when a connection is initialized. Operations are sent over
+//! a connection initialized with exactly their headers, established on first use, and shared by
+//! all operations with the same headers, each identified by its own ID. The MCP session ID isn't
+//! sent, so that a caller's sessions share a connection. If a connection closes, the operations
+//! waiting on it fail, and the next operation with its headers connects again. Connections with
+//! no operations for a while are closed, so that connections for callers which have gone away,
+//! or whose tokens have expired, don't accumulate.
+
+use futures::stream::{SplitSink, SplitStream};
+use futures::{SinkExt as _, StreamExt as _};
+use reqwest::header::HeaderMap;
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as SyncMutex};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::{Mutex, oneshot};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest as _;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream, connect_async};
+use tracing::{debug, warn};
+use url::Url;
+
+/// The websocket subprotocol spoken with the endpoint
+const PROTOCOL: &str = "graphql-transport-ws";
+
+/// How long a connection is kept open without any operations
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Headers which identify the MCP session rather than the caller, and so are not sent
+const SESSION_HEADERS: [&str; 1] = ["mcp-session-id"];
+
+type Stream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+type Sink = Arc<Mutex<SplitSink<Stream, Message>>>;
+
+/// The operations waiting for their result, by ID
+type Pending = Arc<SyncMutex<HashMap<String, oneshot::Sender<Result<Value, String>>>>>;
+
+/// The headers a connection was initialized with, sorted so that equal headers have equal keys
+type HeadersKey = Vec<(String, Vec<u8>)>;
+
+/// The connection for one set of headers, if it is established
+type Slot = Arc<Mutex<Option<Connected>>>;
+
+/// An error executing an operation over a websocket
+#[derive(Debug, thiserror::Error)]
+pub enum WebSocketError {
+    /// The connection could not be established, so the operation was never sent
+    #[error("Failed to connect to the GraphQL websocket: {0}")]
+    Connect(String),
+
+    /// The operation was sent, but failed or did not complete
+    #[error("GraphQL websocket operation failed: {0}")]
+    Operation(String),
+}
+
+/// An established connection
+struct Connected {
+    sink: Sink,
+    pending: Pending,
+    last_used: Arc<SyncMutex<Instant>>,
+}
+
+/// Persistent websocket connections to a GraphQL endpoint, one for each set of headers that
+/// operations are sent with
+#[derive(Clone)]
+pub struct WebSocketConnection {
+    url: Url,
+    connections: Arc<Mutex<HashMap<HeadersKey, Slot>>>,
+    next_id: Arc<AtomicU64>,
+    idle_timeout: Duration,
+}
+
+/// Stops waiting for the result of an operation when dropped, such as when the operation times
+/// out or its call is cancelled, telling the endpoint to stop executing it if no result arrived
+struct PendingGuard {
+    id: String,
+    pending: Pending,
+    sink: Sink,
+}
+
+impl Drop for PendingGuard {
+    fn drop(&mut self) {
+        let waiting = self
+            .pending
+            .lock()
+            .is_ok_and(|mut pending| pending.remove(&self.id).is_some());
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        if waiting {
+            let complete = json!({ "id": self.id, "type": "complete" }).to_string();
+            let sink = self.sink.clone();
+            runtime.spawn(async move {
+                if let Err(e) = sink.lock().await.send(Message::text(complete)).await {
+                    debug!("Failed to complete a GraphQL websocket operation: {e}");
+                }
+            });
+        }
+    }
+}
+
+impl WebSocketConnection {
+    /// Create connections to the endpoint, each established on first use
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            connections: Arc::default(),
+            next_id: Arc::new(AtomicU64::new(1)),
+            idle_timeout: IDLE_TIMEOUT,
+        }
+    }
+
+    /// Execute an operation, given its headers and the body of the GraphQL request, returning the
+    /// GraphQL response of its first result
+    pub async fn execute(&self, headers: &HeaderMap, body: Value) -> Result<Value, WebSocketError> {
+        let mut headers = headers.clone();
+        for name in SESSION_HEADERS {
+            headers.remove(name);
+        }
+        let headers = &headers;
+        let key = headers_key(headers);
+        let slot = self
+            .connections
+            .lock()
+            .await
+            .entry(key.clone())
+            .or_default()
+            .clone();
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed).to_string();
+        let (sender, receiver) = oneshot::channel();
+        let guard = {
+            let mut connected = slot.lock().await;
+            if connected.is_none() {
+                *connected = Some(self.connect(headers, key, slot.clone()).await?);
+            }
+            let Some(connection) = connected.as_ref() else {
+                return Err(WebSocketError::Connect("no connection".to_string()));
+            };
+            if let Ok(mut pending) = connection.pending.lock() {
+                pending.insert(id.clone(), sender);
+            }
+            if let Ok(mut last_used) = connection.last_used.lock() {
+                *last_used = Instant::now();
+            }
+            PendingGuard {
+                id: id.clone(),
+                pending: connection.pending.clone(),
+                sink: connection.sink.clone(),
+            }
+        };
+
+        let subscribe = json!({ "id": id, "type": "subscribe", "payload": body });
+        guard
+            .sink
+            .lock()
+            .await
+            .send(Message::text(subscribe.to_string()))
+            .await
+            .map_err(|e| WebSocketError::Operation(e.to_string()))?;
+
+        receiver
+            .await
+            .map_err(|_| WebSocketError::Operation("the connection closed".to_string()))?
+            .map_err(WebSocketError::Operation)
+    }
+
+    /// Connect to the endpoint and initialize the connection with the headers, spawning a task to
+    /// dispatch the messages it receives
+    async fn connect(
+        &self,
+        headers: &HeaderMap,
+        key: HeadersKey,
+        slot: Slot,
+    ) -> Result<Connected, WebSocketError> {
+        let connect_error = |e: &dyn std::fmt::Display| WebSocketError::Connect(e.to_string());
+        let mut request = self
+            .url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| connect_error(&e))?;
+        request
+            .headers_mut()
+            .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(PROTOCOL));
+        let (mut stream, _) = connect_async(request)
+            .await
+            .map_err(|e| connect_error(&e))?;
+
+        let headers: Map<String, Value> = headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), Value::from(value.to_str().ok()?))))
+            .collect();
+        let init = json!({ "type": "connection_init", "payload": headers });
+        stream
+            .send(Message::text(init.to_string()))
+            .await
+            .map_err(|e| connect_error(&e))?;
+        loop {
+            match stream.next().await {
+                Some(Ok(Message::Text(text))) => match message_type(&text).as_deref() {
+                    Some("connection_ack") => break,
+                    Some("ping") => {}
+                    _ => {
+                        return Err(WebSocketError::Connect(format!(
+                            "expected connection_ack, got {text}"
+                        )));
+                    }
+                },
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(connect_error(&e)),
+                None => return Err(connect_error(&"the connection closed")),
+            }
+        }
+        debug!("Connected to the GraphQL websocket at {}", self.url);
+
+        let (sink, stream) = stream.split();
+        let connected = Connected {
+            sink: Arc::new(Mutex::new(sink)),
+            pending: Pending::default(),
+            last_used: Arc::new(SyncMutex::new(Instant::now())),
+        };
+        tokio::spawn(dispatch(
+            stream,
+            Connected {
+                sink: connected.sink.clone(),
+                pending: connected.pending.clone(),
+                last_used: connected.last_used.clone(),
+            },
+            self.idle_timeout,
+            self.connections.clone(),
+            key,
+            slot,
+        ));
+        Ok(connected)
+    }
+}
+
+/// The key of the connection for a set of headers
+fn headers_key(headers: &HeaderMap) -> HeadersKey {
+    let mut key: HeadersKey = headers
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.as_bytes().to_vec()))
+        .collect();
+    key.sort();
+    key
+}
+
+/// Dispatch the results received on a connection to the operations waiting for them, until the
+/// connection closes, or is closed after being idle
+async fn dispatch(
+    mut stream: SplitStream<Stream>,
+    connection: Connected,
+    idle_timeout: Duration,
+    connections: Arc<Mutex<HashMap<HeadersKey, Slot>>>,
+    key: HeadersKey,
+    slot: Slot,
+) {
+    let Connected {
+        sink,
+        pending,
+        last_used,
+    } = connection;
+    loop {
+        let idle_until = last_used
+            .lock()
+            .map(|last_used| *last_used + idle_timeout)
+            .unwrap_or_else(|_| Instant::now());
+        let message = tokio::select! {
+            message = stream.next() => message,
+            _ = tokio::time::sleep_until(idle_until) => {
+                // The connection is forgotten while no operation can start using it
+                let mut connections = connections.lock().await;
+                let mut connected = slot.lock().await;
+                let idle = pending.lock().is_ok_and(|pending| pending.is_empty())
+                    && last_used
+                        .lock()
+                        .is_ok_and(|last_used| last_used.elapsed() >= idle_timeout);
+                if !idle {
+                    continue;
+                }
+                forget(&mut connections, &mut connected, &key, &slot, &pending);
+                drop((connected, connections));
+                debug!("Closing an idle GraphQL websocket connection");
+                if let Err(e) = sink.lock().await.close().await {
+                    debug!("Failed to close an idle GraphQL websocket connection: {e}");
+                }
+                return;
+            }
+        };
+        let Some(message) = message else {
+            break;
+        };
+        let text = match message {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Close(_)) => break,
+            Ok(_) => continue,
+            Err(e) => {
+                warn!("The GraphQL websocket failed: {e}");
+                break;
+            }
+        };
+        let Ok(message) = serde_json::from_str::<Value>(&text) else {
+            warn!("Ignoring invalid GraphQL websocket message: {text}");
+            continue;
+        };
+        let id = message
+            .get("id")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let payload = message.get("payload").cloned().unwrap_or_default();
+        let result = match message.get("type").and_then(Value::as_str) {
+            Some("next") => Ok(payload),
+            Some("error") => Err(payload.to_string()),
+            Some("complete") => Err("the operation completed without a result".to_string()),
+            Some("ping") => {
+                let pong = json!({ "type": "pong" }).to_string();
+                if let Err(e) = sink.lock().await.send(Message::text(pong)).await {
+                    warn!("Failed to answer a GraphQL websocket ping: {e}");
+                }
+                continue;
+            }
+            _ => continue,
+        };
+        // Only the first result of an operation is used
+        let sender = pending
+            .lock()
+            .ok()
+            .and_then(|mut pending| pending.remove(id));
+        if let Some(sender) = sender {
+            let _ = sender.send(result);
+        }
+    }
+
+    // Operations waiting on the connection fail when their senders are dropped, and the next
+    // operation with the same headers connects again
+    let mut connections = connections.lock().await;
+    let mut connected = slot.lock().await;
+    forget(&mut connections, &mut connected, &key, &slot, &pending);
+    if let Ok(mut pending) = pending.lock() {
+        pending.clear();
+    }
+}
+
+/// Forget a connection, if it is still the one for its headers, so that the next operation with
+/// the headers connects again
+fn forget(
+    connections: &mut HashMap<HeadersKey, Slot>,
+    connected: &mut Option<Connected>,
+    key: &HeadersKey,
+    slot: &Slot,
+    pending: &Pending,
+) {
+    if connected
+        .as_ref()
+        .is_some_and(|current| Arc::ptr_eq(&current.pending, pending))
+    {
+        *connected = None;
+        if connections
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, slot))
+        {
+            connections.remove(key);
+        }
+    }
+}
+
+/// The `type` of a protocol message
+fn message_type(text: &str) -> Option<String> {
+    serde_json::from_str::<Value>(text)
+        .ok()?
+        .get("type")?
+        .as_str()
+        .map(ToString::to_string)
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use super::*;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_hdr_async;
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+
+    /// Accept a websocket handshake, agreeing to the protocol, which clients require
+    #[allow(clippy::result_large_err)]
+    fn accept_protocol(_: &Request, mut response: Response) -> Result<Response, ErrorResponse> {
+        response
+            .headers_mut()
+            .insert(SEC_WEBSOCKET_PROTOCOL, HeaderValue::from_static(PROTOCOL));
+        Ok(response)
+    }
+
+    /// A websocket server for tests
+    pub(crate) struct MockServer {
+        pub(crate) url: Url,
+
+        /// The messages received on each connection, in the order the connections were made
+        connections: Arc<SyncMutex<Vec<Vec<Value>>>>,
+    }
+
+    impl MockServer {
+        /// The payloads of the `connection_init` messages, one for each connection
+        pub(crate) fn connection_inits(&self) -> Vec<Value> {
+            self.messages_of_type("connection_init")
+                .into_iter()
+                .map(|message| message["payload"].clone())
+                .collect()
+        }
+
+        /// The messages of a type received on any connection
+        fn messages_of_type(&self, message_type: &str) -> Vec<Value> {
+            self.connections
+                .lock()
+                .unwrap()
+                .iter()
+                .flatten()
+                .filter(|message| message["type"] == message_type)
+                .cloned()
+                .collect()
+        }
+    }
+
+    /// Serve websocket connections, answering every operation with the given data, or never
+    /// answering if there is none
+    pub(crate) async fn mock_server(data: Option<Value>) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/graphql", listener.local_addr().unwrap());
+        let connections = Arc::new(SyncMutex::new(Vec::new()));
+        let received = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let data = data.clone();
+                let received = received.clone();
+                tokio::spawn(async move {
+                    let mut socket = accept_hdr_async(stream, accept_protocol).await.unwrap();
+                    let connection = {
+                        let mut received = received.lock().unwrap();
+                        received.push(Vec::new());
+                        received.len() - 1
+                    };
+                    while let Some(Ok(Message::Text(text))) = socket.next().await {
+                        let message: Value = serde_json::from_str(&text).unwrap();
+                        received.lock().unwrap()[connection].push(message.clone());
+                        let replies = match (message["type"].as_str(), &data) {
+                            (Some("connection_init"), _) => {
+                                vec![json!({ "type": "connection_ack" })]
+                            }
+                            (Some("subscribe"), Some(data)) => vec![
+                                json!({ "id": message["id"], "type": "next", "payload": { "data": data } }),
+                                json!({ "id": message["id"], "type": "complete" }),
+                            ],
+                            _ => vec![],
+                        };
+                        for reply in replies {
+                            socket.send(Message::text(reply.to_string())).await.unwrap();
+                        }
+                    }
+                });
+            }
+        });
+        MockServer {
+            url: url.parse().unwrap(),
+            connections,
+        }
+    }
+
+    fn headers(authorization: &str) -> HeaderMap {
+        HeaderMap::from_iter([(
+            reqwest::header::AUTHORIZATION,
+            HeaderValue::from_str(authorization).unwrap(),
+        )])
+    }
+
+    #[tokio::test]
+    async fn sessions_of_the_same_caller_share_one_connection() {
+        let server = mock_server(Some(json!({ "id": "1" }))).await;
+        let connection = WebSocketConnection::new(server.url.clone());
+
+        for session_id in ["1", "2"] {
+            let mut headers = headers("Bearer a");
+            headers.insert("mcp-session-id", HeaderValue::from_static(session_id));
+            connection
+                .execute(&headers, json!({ "query": "query { id }" }))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            server.connection_inits(),
+            vec![json!({ "authorization": "Bearer a" })]
+        );
+    }
+
+    #[tokio::test]
+    async fn idle_connections_are_closed() {
+        let server = mock_server(Some(json!({ "id": "1" }))).await;
+        let mut connection = WebSocketConnection::new(server.url.clone());
+        connection.idle_timeout = Duration::from_millis(100);
+
+        connection
+            .execute(&headers("Bearer a"), json!({ "query": "query { id }" }))
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(connection.connections.lock().await.is_empty());
+
+        // The next operation connects again
+        connection
+            .execute(&headers("Bearer a"), json!({ "query": "query { id }" }))
+            .await
+            .unwrap();
+        assert_eq!(server.connection_inits().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn operations_with_the_same_headers_share_one_connection() {
+        let server = mock_server(Some(json!({ "id": "1" }))).await;
+        let connection = WebSocketConnection::new(server.url.clone());
+
+        for _ in 0..2 {
+            let response = connection
+                .execute(&headers("Bearer a"), json!({ "query": "query { id }" }))
+                .await
+                .unwrap();
+            assert_eq!(response, json!({ "data": { "id": "1" } }));
+        }
+
+        assert_eq!(
+            server.connection_inits(),
+            vec![json!({ "authorization": "Bearer a" })]
+        );
+    }
+
+    #[tokio::test]
+    async fn operations_with_different_headers_use_their_own_connections() {
+        let server = mock_server(Some(json!({ "id": "1" }))).await;
+        let connection = WebSocketConnection::new(server.url.clone());
+
+        for authorization in ["Bearer a", "Bearer b", "Bearer a"] {
+            connection
+                .execute(&headers(authorization), json!({ "query": "query { id }" }))
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(
+            server.connection_inits(),
+            vec![
+                json!({ "authorization": "Bearer a" }),
+                json!({ "authorization": "Bearer b" })
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn cancelled_operations_are_completed_and_forgotten() {
+        let server = mock_server(None).await;
+        let connection = WebSocketConnection::new(server.url.clone());
+
+        let headers = HeaderMap::new();
+        let execute = connection.execute(&headers, json!({ "query": "query { id }" }));
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), execute)
+                .await
+                .is_err()
+        );
+
+        let slot = connection.connections.lock().await.values().next().cloned();
+        let pending = slot.unwrap().lock().await.as_ref().unwrap().pending.clone();
+        assert!(pending.lock().unwrap().is_empty());
+
+        // The complete message is sent in the background
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let subscribe = server.messages_of_type("subscribe");
+        let complete = server.messages_of_type("complete");
+        assert_eq!(
+            complete,
+            vec![json!({ "id": subscribe[0]["id"], "type": "complete" })]
+        );
+    }
+}
@@ -3,6 +3,9 @@
 //! Provides liveness and readiness checks for the MCP server, inspired by Apollo Router's health check implementation.
 //!
 //! The health check is exposed via HTTP endpoints and can be used by load balancers, container orchestrators, and monitoring systems to determine server health.
+//!
+//! When embedding the server, additional named [`ReadinessCheck`]s can be registered, such as of
+//! a cache the server depends on. The server is only ready when all of them pass.
 
 use std::{
     sync::{
@@ -13,13 +16,14 @@ use std::{
 };
 
 use axum::http::StatusCode;
+use futures::future::{BoxFuture, join_all};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use tokio::time::Instant;
 use tracing::debug;
 
 /// Health status enumeration
-#[derive(Debug, Serialize)]
+#[derive(Debug, PartialEq, Serialize)]
 #[serde(rename_all = "UPPERCASE")]
 pub enum HealthStatus {
     Up,
@@ -30,6 +34,59 @@ pub enum HealthStatus {
 #[derive(Debug, Serialize)]
 pub struct Health {
     status: HealthStatus,
+
+    /// The status of each registered readiness check, when readiness is queried
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    checks: Vec<CheckHealth>,
+}
+
+/// The status of a single readiness check
+#[derive(Debug, Serialize)]
+pub struct CheckHealth {
+    name: String,
+    status: HealthStatus,
+
+    /// Why the check failed, if it did
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+}
+
+/// The result of a readiness check, with the reason the server is not ready if it failed
+pub type ReadinessCheckResult = Result<(), String>;
+
+/// A named check of whether something the server depends on is ready
+#[derive(Clone)]
+pub struct ReadinessCheck {
+    name: String,
+    check: Arc<dyn Fn() -> BoxFuture<'static, ReadinessCheckResult> + Send + Sync>,
+}
+
+impl ReadinessCheck {
+    /// Create a readiness check from an async function, run each time readiness is queried
+    pub fn new<F, Fut>(name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ReadinessCheckResult> + Send + 'static,
+    {
+        Self {
+            name: name.into(),
+            check: Arc::new(move || Box::pin(check())),
+        }
+    }
+
+    /// The name the check is reported under
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn run(&self) -> CheckHealth {
+        let result = (self.check)().await;
+        CheckHealth {
+            name: self.name.clone(),
+            status: status(result.is_ok()),
+            reason: result.err(),
+        }
+    }
 }
 
 /// Configuration options for the readiness health interval sub-component.
@@ -114,6 +171,7 @@ pub struct HealthCheck {
     ready: Arc<AtomicBool>,
     rejected: Arc<AtomicUsize>,
     ticker: Arc<tokio::task::JoinHandle<()>>,
+    checks: Arc<[ReadinessCheck]>,
 }
 
 impl HealthCheck {
@@ -158,9 +216,16 @@ impl HealthCheck {
             ready,
             rejected,
             ticker: Arc::new(ticker),
+            checks: Arc::new([]),
         }
     }
 
+    /// Also require the checks to pass for the server to be ready
+    pub fn with_readiness_checks(mut self, checks: Vec<ReadinessCheck>) -> Self {
+        self.checks = checks.into();
+        self
+    }
+
     pub fn record_rejection(&self) {
         self.rejected.fetch_add(1, Ordering::Relaxed);
     }
@@ -169,43 +234,45 @@ impl HealthCheck {
         &self.config
     }
 
-    pub fn get_health_state(&self, query: Option<&str>) -> (Health, StatusCode) {
-        let mut status_code = StatusCode::OK;
-
-        let health = if let Some(query) = query {
-            let query_upper = query.to_ascii_uppercase();
-
-            if query_upper.starts_with("READY") {
-                let status = if self.ready.load(Ordering::SeqCst) {
-                    HealthStatus::Up
-                } else {
-                    status_code = StatusCode::SERVICE_UNAVAILABLE;
-                    HealthStatus::Down
-                };
-                Health { status }
-            } else if query_upper.starts_with("LIVE") {
-                let status = if self.live.load(Ordering::SeqCst) {
-                    HealthStatus::Up
-                } else {
-                    status_code = StatusCode::SERVICE_UNAVAILABLE;
-                    HealthStatus::Down
-                };
-                Health { status }
-            } else {
-                Health {
-                    status: HealthStatus::Up,
-                }
+    pub async fn get_health_state(&self, query: Option<&str>) -> (Health, StatusCode) {
+        let query_upper = query.map(str::to_ascii_uppercase).unwrap_or_default();
+
+        let health = if query_upper.starts_with("READY") {
+            let checks = join_all(self.checks.iter().map(ReadinessCheck::run)).await;
+            let ready = self.ready.load(Ordering::SeqCst)
+                && checks.iter().all(|check| check.status == HealthStatus::Up);
+            Health {
+                status: status(ready),
+                checks,
+            }
+        } else if query_upper.starts_with("LIVE") {
+            Health {
+                status: status(self.live.load(Ordering::SeqCst)),
+                checks: Vec::new(),
             }
         } else {
             Health {
                 status: HealthStatus::Up,
+                checks: Vec::new(),
             }
         };
 
+        let status_code = match health.status {
+            HealthStatus::Up => StatusCode::OK,
+            HealthStatus::Down => StatusCode::SERVICE_UNAVAILABLE,
+        };
         (health, status_code)
     }
 }
 
+fn status(up: bool) -> HealthStatus {
+    if up {
+        HealthStatus::Up
+    } else {
+        HealthStatus::Down
+    }
+}
+
 impl Drop for HealthCheck {
     fn drop(&mut self) {
         self.ticker.abort();
@@ -252,4 +319,31 @@ mod tests {
         assert!(health_check.live.load(Ordering::SeqCst));
         assert!(!health_check.ready.load(Ordering::SeqCst));
     }
+
+    #[tokio::test]
+    async fn failing_readiness_checks_make_the_server_unready() {
+        let health_check =
+            HealthCheck::new(HealthCheckConfig::default()).with_readiness_checks(vec![
+                ReadinessCheck::new("database", || async { Ok(()) }),
+                ReadinessCheck::new("cache", || async { Err("connection refused".to_string()) }),
+            ]);
+
+        let (health, status_code) = health_check.get_health_state(Some("ready")).await;
+        assert_eq!(status_code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            serde_json::to_value(&health).unwrap(),
+            serde_json::json!({
+                "status": "DOWN",
+                "checks": [
+                    { "name": "database", "status": "UP" },
+                    { "name": "cache", "status": "DOWN", "reason": "connection refused" },
+                ],
+            })
+        );
+
+        // Liveness is unaffected
+        let (health, status_code) = health_check.get_health_state(Some("live")).await;
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(health.status, HealthStatus::Up);
+    }
 }
@@ -2,5 +2,6 @@
 
 pub(crate) mod execute;
 pub(crate) mod introspect;
+pub(crate) mod list_operations;
 pub(crate) mod search;
 pub(crate) mod validate;
@@ -1,23 +1,41 @@
 use crate::errors::McpError;
-use crate::operations::{MutationMode, operation_defs, operation_name};
+use crate::operations::{MutationMode, Operation, operation_defs, operation_name};
 use crate::{
     graphql::{self, OperationDetails},
     schema_from_type,
 };
+use apollo_compiler::ast::{
+    Definition, Document, Field, NamedType, OperationDefinition, OperationType, Selection,
+};
+use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::validation::Valid;
+use apollo_compiler::{Name, Node, Schema, name};
 use reqwest::header::{HeaderMap, HeaderValue};
 use rmcp::model::{ErrorCode, Tool};
 use rmcp::schemars::JsonSchema;
-use rmcp::serde_json::Value;
+use rmcp::serde_json::{Map, Value};
 use rmcp::{schemars, serde_json};
 use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
 
 /// The name of the tool to execute an ad hoc GraphQL operation
 pub const EXECUTE_TOOL_NAME: &str = "execute";
 
+/// The introspection fields which cannot be selected unless introspection is enabled
+const INTROSPECTION_FIELDS: [&str; 2] = ["__schema", "__type"];
+
 #[derive(Clone)]
 pub struct Execute {
     pub tool: Tool,
     mutation_mode: MutationMode,
+    allow_introspection: bool,
+    /// Root mutation fields which can be selected regardless of the mutation mode, because an
+    /// operation selecting them has the `all` mutation mode
+    buildable_mutation_fields: Arc<RwLock<HashSet<String>>>,
+    /// The schema to complete the selections of composite fields selected without subfields
+    /// against, if they are completed
+    default_selections: Arc<RwLock<Option<Valid<Schema>>>>,
 }
 
 /// Input for the execute tool.
@@ -32,9 +50,12 @@ pub struct Input {
 }
 
 impl Execute {
-    pub fn new(mutation_mode: MutationMode) -> Self {
+    pub fn new(mutation_mode: MutationMode, allow_introspection: bool) -> Self {
         Self {
             mutation_mode,
+            allow_introspection,
+            buildable_mutation_fields: Arc::default(),
+            default_selections: Arc::default(),
             tool: Tool::new(
                 EXECUTE_TOOL_NAME,
                 "Execute a GraphQL operation. Use the `introspect` tool to get information about the GraphQL schema. Always use the schema to create operations - do not try arbitrary operations. If available, first use the `validate` tool to validate operations. DO NOT try to execute introspection queries.",
@@ -42,6 +63,54 @@ impl Execute {
             ),
         }
     }
+
+    /// Allow building mutations which select only the root fields of operations whose mutation
+    /// mode is `all`
+    pub(crate) fn set_buildable_mutations(&self, operations: &[Operation]) {
+        if let Ok(mut fields) = self.buildable_mutation_fields.write() {
+            *fields = operations
+                .iter()
+                .flat_map(Operation::buildable_mutation_fields)
+                .collect();
+        }
+    }
+
+    /// Complete the selections of object, interface, and union fields selected without subfields
+    /// before executing operations
+    pub(crate) fn enable_default_selections(&self, schema: &Valid<Schema>) {
+        if let Ok(mut default_selections) = self.default_selections.write() {
+            *default_selections = Some(schema.clone());
+        }
+    }
+
+    /// Complete selections against an updated schema, if they are completed
+    pub(crate) fn update_default_selections(&self, schema: &Valid<Schema>) {
+        if let Ok(mut default_selections) = self.default_selections.write()
+            && default_selections.is_some()
+        {
+            *default_selections = Some(schema.clone());
+        }
+    }
+
+    /// Check if the LLM can build an operation, given the mutation mode
+    fn is_buildable(&self, operation: &OperationDefinition) -> bool {
+        if operation.operation_type != OperationType::Mutation
+            || self.mutation_mode == MutationMode::All
+        {
+            return true;
+        }
+        let Ok(fields) = self.buildable_mutation_fields.read() else {
+            return false;
+        };
+        !operation.selection_set.is_empty()
+            && operation
+                .selection_set
+                .iter()
+                .all(|selection| match selection {
+                    Selection::Field(field) => fields.contains(field.name.as_str()),
+                    _ => false,
+                })
+    }
 }
 
 impl graphql::Executable for Execute {
@@ -54,24 +123,47 @@ impl graphql::Executable for Execute {
             McpError::new(ErrorCode::INVALID_PARAMS, "Invalid input".to_string(), None)
         })?;
 
-        let (_, operation_def, source_path) =
-            operation_defs(&input.query, self.mutation_mode == MutationMode::All, None)
-                .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?
-                .ok_or_else(|| {
-                    McpError::new(
-                        ErrorCode::INVALID_PARAMS,
-                        "Invalid operation type".to_string(),
-                        None,
-                    )
-                })?;
+        let invalid_operation_type = || {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "Invalid operation type".to_string(),
+                None,
+            )
+        };
+        let (mut document, operation_def, source_path) = operation_defs(&input.query, true, None)
+            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?
+            .ok_or_else(invalid_operation_type)?;
+        if !self.is_buildable(&operation_def) {
+            return Err(invalid_operation_type());
+        }
+
+        if !self.allow_introspection && selects_introspection(&document) {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "Introspection queries are not allowed".to_string(),
+                None,
+            ));
+        }
+
+        let mut query = input.query;
+        if let Ok(default_selections) = self.default_selections.read()
+            && let Some(schema) = default_selections.as_ref()
+            && complete_selections(schema, &mut document)
+        {
+            query = document.to_string();
+        }
 
         Ok(OperationDetails {
-            query: input.query,
+            query,
             operation_name: operation_name(&operation_def, source_path).ok(),
         })
     }
 
-    fn variables(&self, input: Value) -> Result<Value, McpError> {
+    fn variables(
+        &self,
+        input: Value,
+        _claims: Option<&Map<String, Value>>,
+    ) -> Result<Value, McpError> {
         let input = serde_json::from_value::<Input>(input).map_err(|_| {
             McpError::new(ErrorCode::INVALID_PARAMS, "Invalid input".to_string(), None)
         })?;
@@ -95,18 +187,148 @@ impl graphql::Executable for Execute {
     }
 }
 
+/// Check whether any operation or fragment in the document selects an introspection field
+fn selects_introspection(document: &Document) -> bool {
+    fn selects(selections: &[Selection]) -> bool {
+        selections.iter().any(|selection| match selection {
+            Selection::Field(field) => {
+                INTROSPECTION_FIELDS.contains(&field.name.as_str()) || selects(&field.selection_set)
+            }
+            Selection::InlineFragment(fragment) => selects(&fragment.selection_set),
+            Selection::FragmentSpread(_) => false,
+        })
+    }
+
+    document
+        .definitions
+        .iter()
+        .any(|definition| match definition {
+            Definition::OperationDefinition(operation) => selects(&operation.selection_set),
+            Definition::FragmentDefinition(fragment) => selects(&fragment.selection_set),
+            _ => false,
+        })
+}
+
+/// Complete the selections of object, interface, and union fields selected without subfields,
+/// returning whether any selection was completed
+fn complete_selections(schema: &Schema, document: &mut Document) -> bool {
+    fn complete(schema: &Schema, parent_type: &NamedType, selections: &mut [Selection]) -> bool {
+        let mut completed = false;
+        for selection in selections {
+            match selection {
+                Selection::Field(field) => {
+                    let Ok(definition) = schema.type_field(parent_type, &field.name) else {
+                        continue;
+                    };
+                    let field_type = definition.ty.inner_named_type().clone();
+                    if field.selection_set.is_empty() {
+                        let defaults = default_selection(schema, &field_type);
+                        if !defaults.is_empty() {
+                            field.make_mut().selection_set = defaults;
+                            completed = true;
+                        }
+                    } else {
+                        completed |=
+                            complete(schema, &field_type, &mut field.make_mut().selection_set);
+                    }
+                }
+                Selection::InlineFragment(fragment) => {
+                    let type_condition = fragment
+                        .type_condition
+                        .clone()
+                        .unwrap_or_else(|| parent_type.clone());
+                    completed |= complete(
+                        schema,
+                        &type_condition,
+                        &mut fragment.make_mut().selection_set,
+                    );
+                }
+                Selection::FragmentSpread(_) => {}
+            }
+        }
+        completed
+    }
+
+    let mut completed = false;
+    for definition in &mut document.definitions {
+        match definition {
+            Definition::OperationDefinition(operation) => {
+                let Some(root) = schema.root_operation(operation.operation_type).cloned() else {
+                    continue;
+                };
+                completed |= complete(schema, &root, &mut operation.make_mut().selection_set);
+            }
+            Definition::FragmentDefinition(fragment) => {
+                let type_condition = fragment.type_condition.clone();
+                completed |= complete(
+                    schema,
+                    &type_condition,
+                    &mut fragment.make_mut().selection_set,
+                );
+            }
+            _ => {}
+        }
+    }
+    completed
+}
+
+/// The default selection of a type: its scalar and enum fields without required arguments, or
+/// `__typename` if it has none. Leaf types have no selection.
+fn default_selection(schema: &Schema, type_name: &NamedType) -> Vec<Selection> {
+    let fields = match schema.types.get(type_name) {
+        Some(ExtendedType::Object(object)) => &object.fields,
+        Some(ExtendedType::Interface(interface)) => &interface.fields,
+        Some(ExtendedType::Union(_)) => return vec![field_selection(name!("__typename"))],
+        _ => return Vec::new(),
+    };
+    let leaves: Vec<Selection> = fields
+        .values()
+        .filter(|field| {
+            field
+                .arguments
+                .iter()
+                .all(|argument| !argument.is_required())
+                && schema
+                    .types
+                    .get(field.ty.inner_named_type())
+                    .is_some_and(|ty| ty.is_scalar() || ty.is_enum())
+        })
+        .map(|field| field_selection(field.name.clone()))
+        .collect();
+    if leaves.is_empty() {
+        vec![field_selection(name!("__typename"))]
+    } else {
+        leaves
+    }
+}
+
+/// Select a field without arguments or subfields
+fn field_selection(name: Name) -> Selection {
+    Selection::Field(Node::new(Field {
+        alias: None,
+        name,
+        arguments: Vec::new(),
+        directives: Default::default(),
+        selection_set: Vec::new(),
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::errors::McpError;
-    use crate::graphql::{Executable, OperationDetails};
+    use crate::graphql::{Executable, OperationDetails, Request};
     use crate::introspection::tools::execute::Execute;
-    use crate::operations::MutationMode;
+    use crate::operations::{ErrorCodes, GraphQLErrors, MutationMode};
+    use apollo_compiler::Schema;
+    use mockito::Matcher;
+    use reqwest::header::HeaderMap;
     use rmcp::model::ErrorCode;
     use rmcp::serde_json::{Value, json};
+    use url::Url;
 
     #[test]
     fn execute_query_with_variables_as_string() {
-        let execute = Execute::new(MutationMode::None);
+        let execute = Execute::new(MutationMode::None, false);
 
         let query = "query GetUser($id: ID!) { user(id: $id) { id name } }";
         let variables = json!({ "id": "123" });
@@ -123,12 +345,12 @@ mod tests {
                 operation_name: Some("GetUser".to_string()),
             })
         );
-        assert_eq!(Executable::variables(&execute, input), Ok(variables));
+        assert_eq!(Executable::variables(&execute, input, None), Ok(variables));
     }
 
     #[test]
     fn execute_query_with_variables_as_json() {
-        let execute = Execute::new(MutationMode::None);
+        let execute = Execute::new(MutationMode::None, false);
 
         let query = "query GetUser($id: ID!) { user(id: $id) { id name } }";
         let variables = json!({ "id": "123" });
@@ -145,12 +367,12 @@ mod tests {
                 operation_name: Some("GetUser".to_string()),
             })
         );
-        assert_eq!(Executable::variables(&execute, input), Ok(variables));
+        assert_eq!(Executable::variables(&execute, input, None), Ok(variables));
     }
 
     #[test]
     fn execute_query_without_variables() {
-        let execute = Execute::new(MutationMode::None);
+        let execute = Execute::new(MutationMode::None, false);
 
         let query = "query GetUser($id: ID!) { user(id: $id) { id name } }";
 
@@ -165,12 +387,15 @@ mod tests {
                 operation_name: Some("GetUser".to_string()),
             })
         );
-        assert_eq!(Executable::variables(&execute, input), Ok(Value::Null));
+        assert_eq!(
+            Executable::variables(&execute, input, None),
+            Ok(Value::Null)
+        );
     }
 
     #[test]
     fn execute_query_anonymous_operation() {
-        let execute = Execute::new(MutationMode::None);
+        let execute = Execute::new(MutationMode::None, false);
 
         let query = "{ user(id: \"123\") { id name } }";
         let input = json!({
@@ -188,7 +413,7 @@ mod tests {
 
     #[test]
     fn execute_query_err_with_mutation_when_mutation_mode_is_none() {
-        let execute = Execute::new(MutationMode::None);
+        let execute = Execute::new(MutationMode::None, false);
 
         let query = "mutation MutationName { id }".to_string();
         let input = json!({
@@ -207,7 +432,7 @@ mod tests {
 
     #[test]
     fn execute_query_ok_with_mutation_when_mutation_mode_is_all() {
-        let execute = Execute::new(MutationMode::All);
+        let execute = Execute::new(MutationMode::All, false);
 
         let query = "mutation MutationName { id }".to_string();
         let input = json!({
@@ -230,7 +455,7 @@ mod tests {
             MutationMode::Explicit,
             MutationMode::All,
         ] {
-            let execute = Execute::new(mutation_mode);
+            let execute = Execute::new(mutation_mode, false);
 
             let input = json!({
                 "query": "subscription SubscriptionName { id }",
@@ -249,7 +474,7 @@ mod tests {
 
     #[test]
     fn execute_query_invalid_input() {
-        let execute = Execute::new(MutationMode::None);
+        let execute = Execute::new(MutationMode::None, false);
 
         let input = json!({
             "nonsense": "whatever",
@@ -264,7 +489,7 @@ mod tests {
             ))
         );
         assert_eq!(
-            Executable::variables(&execute, input),
+            Executable::variables(&execute, input, None),
             Err(McpError::new(
                 ErrorCode::INVALID_PARAMS,
                 "Invalid input".to_string(),
@@ -275,7 +500,7 @@ mod tests {
 
     #[test]
     fn execute_query_invalid_variables() {
-        let execute = Execute::new(MutationMode::None);
+        let execute = Execute::new(MutationMode::None, false);
 
         let input = json!({
             "query": "query GetUser($id: ID!) { user(id: $id) { id name } }",
@@ -283,7 +508,7 @@ mod tests {
         });
 
         assert_eq!(
-            Executable::variables(&execute, input),
+            Executable::variables(&execute, input, None),
             Err(McpError::new(
                 ErrorCode::INVALID_PARAMS,
                 "Invalid input".to_string(),
@@ -291,4 +516,119 @@ mod tests {
             ))
         );
     }
+
+    #[test]
+    fn execute_query_err_with_introspection_when_disabled() {
+        let execute = Execute::new(MutationMode::None, false);
+
+        for query in [
+            "query { __schema { types { name } } }",
+            "query { user(id: \"1\") { ...Introspect } } fragment Introspect on User { id __type(name: \"User\") { name } }",
+        ] {
+            assert_eq!(
+                Executable::operation(&execute, json!({ "query": query })),
+                Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    "Introspection queries are not allowed".to_string(),
+                    None
+                ))
+            );
+        }
+    }
+
+    #[test]
+    fn execute_query_ok_with_introspection_when_enabled() {
+        let execute = Execute::new(MutationMode::None, true);
+
+        let query = "query { __schema { types { name } } }";
+
+        assert_eq!(
+            Executable::operation(&execute, json!({ "query": query })),
+            Ok(OperationDetails {
+                query: query.to_string(),
+                operation_name: None,
+            })
+        );
+    }
+
+    #[test]
+    fn execute_query_ok_with_typename_when_introspection_disabled() {
+        let execute = Execute::new(MutationMode::None, false);
+
+        let query = "query { user(id: \"1\") { __typename id } }";
+
+        assert!(Executable::operation(&execute, json!({ "query": query })).is_ok());
+    }
+
+    #[tokio::test]
+    async fn object_fields_without_subfields_are_completed_when_enabled() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { user(id: ID!): User, search: [Result] }
+            type User { id: ID!, name: String, role: Role, friends: [User] }
+            enum Role { ADMIN MEMBER }
+            union Result = User
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let execute = Execute::new(MutationMode::None, false);
+        execute.enable_default_selections(&schema);
+
+        let input = json!({ "query": "query { user(id: \"1\") { name friends } search }" });
+        let query = Executable::operation(&execute, input.clone())
+            .unwrap()
+            .query;
+        assert_eq!(
+            query,
+            "{\n  user(id: \"1\") {\n    name\n    friends {\n      id\n      name\n      role\n    }\n  }\n  search {\n    __typename\n  }\n}\n"
+        );
+
+        let mut server = mockito::Server::new_async().await;
+        let endpoint = Url::parse(server.url().as_str()).unwrap();
+        let mock = server
+            .mock("POST", "/")
+            .match_body(Matcher::PartialJson(json!({ "query": query })))
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(json!({ "data": { "user": null, "search": [] } }).to_string())
+            .expect(1)
+            .create_async()
+            .await;
+        let result = execute
+            .execute(Request {
+                input,
+                endpoint: &endpoint,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &ErrorCodes::default(),
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: None,
+            })
+            .await
+            .unwrap();
+
+        mock.assert();
+        assert!(!result.is_error.unwrap());
+    }
+
+    #[test]
+    fn object_fields_without_subfields_are_kept_when_disabled() {
+        let execute = Execute::new(MutationMode::None, false);
+
+        let query = "query { user(id: \"1\") { friends } }";
+
+        assert_eq!(
+            Executable::operation(&execute, json!({ "query": query }))
+                .unwrap()
+                .query,
+            query
+        );
+    }
 }
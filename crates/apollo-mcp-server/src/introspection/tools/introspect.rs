@@ -1,16 +1,18 @@
 use crate::errors::McpError;
 use crate::introspection::minify::MinifyExt as _;
+use crate::introspection_schema::INTROSPECTION_QUERY;
 use crate::schema_from_type;
 use crate::schema_tree_shake::{DepthLimit, SchemaTreeShaker};
-use apollo_compiler::Schema;
 use apollo_compiler::ast::OperationType;
 use apollo_compiler::schema::ExtendedType;
 use apollo_compiler::validation::Valid;
-use rmcp::model::{CallToolResult, Content, Tool};
+use apollo_compiler::{ExecutableDocument, Schema, introspection};
+use rmcp::model::{CallToolResult, Content, ErrorCode, Tool};
 use rmcp::schemars::JsonSchema;
 use rmcp::serde_json::Value;
 use rmcp::{schemars, serde_json};
 use serde::Deserialize;
+use std::collections::HashSet;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -34,6 +36,20 @@ pub struct Input {
     /// How far to recurse the type hierarchy. Use 0 for no limit. Defaults to 1.
     #[serde(default = "default_depth")]
     depth: usize,
+    /// The format of the type information. Defaults to `sdl`.
+    #[serde(default)]
+    format: Format,
+}
+
+/// The format of the type information returned by the introspect tool
+#[derive(JsonSchema, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Format {
+    /// GraphQL schema definition language, with a result for each type
+    #[default]
+    Sdl,
+    /// The standard JSON introspection result, limited to the types that would be returned as SDL
+    Json,
 }
 
 impl Introspect {
@@ -78,26 +94,37 @@ impl Introspect {
         }
         let shaken = tree_shaker.shaken().unwrap_or_else(|schema| schema.partial);
 
-        Ok(CallToolResult {
-            content: shaken
-                .types
-                .iter()
-                .filter(|(_name, extended_type)| {
-                    !extended_type.is_built_in()
-                        && schema
-                            .root_operation(OperationType::Mutation)
-                            .is_none_or(|root_name| {
-                                extended_type.name() != root_name
-                                    || (type_name == root_name.as_str() && self.allow_mutations)
-                            })
-                        && schema
-                            .root_operation(OperationType::Subscription)
-                            .is_none_or(|root_name| extended_type.name() != root_name)
-                })
-                .map(|(_, extended_type)| extended_type)
+        let types = shaken
+            .types
+            .iter()
+            .filter(|(_name, extended_type)| {
+                !extended_type.is_built_in()
+                    && schema
+                        .root_operation(OperationType::Mutation)
+                        .is_none_or(|root_name| {
+                            extended_type.name() != root_name
+                                || (type_name == root_name.as_str() && self.allow_mutations)
+                        })
+                    && schema
+                        .root_operation(OperationType::Subscription)
+                        .is_none_or(|root_name| extended_type.name() != root_name)
+            })
+            .map(|(_, extended_type)| extended_type);
+
+        let content = match input.format {
+            Format::Sdl => types
                 .map(|extended_type| self.serialize(extended_type))
                 .map(Content::text)
                 .collect(),
+            Format::Json => {
+                let names = types
+                    .map(|extended_type| extended_type.name().as_str())
+                    .collect();
+                vec![Content::json(introspection_result(&schema, &names)?)?]
+            }
+        };
+        Ok(CallToolResult {
+            content,
             is_error: None,
         })
     }
@@ -111,6 +138,58 @@ impl Introspect {
     }
 }
 
+/// Execute the standard introspection query against the schema, keeping only the named types and
+/// the built-in types
+fn introspection_result(schema: &Valid<Schema>, names: &HashSet<&str>) -> Result<Value, McpError> {
+    let internal_error = |message: String| McpError::new(ErrorCode::INTERNAL_ERROR, message, None);
+    let document = ExecutableDocument::parse_and_validate(
+        schema,
+        INTROSPECTION_QUERY,
+        "introspection.graphql",
+    )
+    .map_err(|e| internal_error(format!("Invalid introspection query: {}", e.errors)))?;
+    let operation = document
+        .operations
+        .get(None)
+        .map_err(|_| internal_error("Missing introspection query".to_string()))?;
+    let response = introspection::partial_execute(
+        schema,
+        &schema.implementers_map(),
+        &document,
+        operation,
+        Valid::assume_valid_ref(&Default::default()),
+    )
+    .map_err(|e| internal_error(format!("Failed to introspect the schema: {}", e.message())))?;
+
+    let mut data = serde_json::to_value(response.data)
+        .map_err(|e| internal_error(format!("Failed to serialize introspection: {e}")))?;
+    let included = |name: Option<&str>| {
+        name.is_some_and(|name| {
+            names.contains(name)
+                || schema
+                    .types
+                    .get(name)
+                    .is_some_and(|extended_type| extended_type.is_built_in())
+        })
+    };
+    if let Some(introspected) = data.get_mut("__schema").and_then(Value::as_object_mut) {
+        if let Some(Value::Array(types)) = introspected.get_mut("types") {
+            types.retain(|introspected_type| {
+                included(introspected_type.get("name").and_then(Value::as_str))
+            });
+        }
+        // Hidden root types are left out, as they are from the SDL
+        for root in ["mutationType", "subscriptionType"] {
+            if let Some(root_type) = introspected.get_mut(root)
+                && !included(root_type.get("name").and_then(Value::as_str))
+            {
+                *root_type = Value::Null;
+            }
+        }
+    }
+    Ok(data)
+}
+
 fn tool_description(
     root_query_type: Option<String>,
     root_mutation_type: Option<String>,
@@ -135,3 +214,87 @@ fn tool_description(
 fn default_depth() -> usize {
     1
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::RawContent;
+    use serde_json::json;
+
+    fn introspect(sdl: &str) -> Introspect {
+        let schema = Schema::parse_and_validate(sdl, "schema.graphql").unwrap();
+        Introspect::new(
+            Arc::new(Mutex::new(schema)),
+            Some("Query".to_string()),
+            None,
+            false,
+        )
+    }
+
+    fn text(result: &CallToolResult) -> Vec<&str> {
+        result
+            .content
+            .iter()
+            .filter_map(|content| match &content.raw {
+                RawContent::Text(text) => Some(text.text.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn json_format_returns_an_introspection_result() {
+        let tool = introspect(
+            "type Query { user(id: ID!): User } type User { id: ID! name: String } type Mutation { deleteUser(id: ID!): Boolean }",
+        );
+        let result = tool
+            .execute(
+                serde_json::from_value(
+                    json!({ "type_name": "Query", "depth": 0, "format": "json" }),
+                )
+                .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let texts = text(&result);
+        assert_eq!(texts.len(), 1);
+        let introspection: Value = serde_json::from_str(texts.first().unwrap()).unwrap();
+        let schema = &introspection["__schema"];
+        assert_eq!(schema["queryType"], json!({ "name": "Query" }));
+        // Mutations are hidden when they aren't allowed, as they are in SDL
+        assert_eq!(schema["mutationType"], Value::Null);
+
+        let types: Vec<&str> = schema["types"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|introspected_type| introspected_type["name"].as_str())
+            .collect();
+        assert!(types.contains(&"Query"));
+        assert!(types.contains(&"User"));
+        assert!(types.contains(&"String"));
+        assert!(types.contains(&"__Schema"));
+        assert!(!types.contains(&"Mutation"));
+
+        let query = schema["types"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|introspected_type| introspected_type["name"] == "Query")
+            .unwrap();
+        assert_eq!(query["kind"], "OBJECT");
+        assert_eq!(query["fields"][0]["name"], "user");
+    }
+
+    #[tokio::test]
+    async fn sdl_format_is_the_default() {
+        let tool = introspect("type Query { id: ID }");
+        let result = tool
+            .execute(serde_json::from_value(json!({ "type_name": "Query" })).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(text(&result), vec!["type Query {\n  id: ID\n}\n"]);
+    }
+}
@@ -0,0 +1,99 @@
+use crate::schema_from_type;
+use rmcp::model::{CallToolResult, Content, Tool};
+use rmcp::schemars::JsonSchema;
+use rmcp::serde_json::Value;
+use rmcp::{schemars, serde_json};
+use serde::Deserialize;
+
+/// The name of the tool to list the available operation tools
+pub const LIST_OPERATIONS_TOOL_NAME: &str = "list_operations";
+
+#[derive(Clone)]
+pub struct ListOperations {
+    pub tool: Tool,
+}
+
+/// Input for the list operations tool
+#[derive(JsonSchema, Deserialize)]
+pub struct Input {}
+
+impl ListOperations {
+    pub fn new() -> Self {
+        Self {
+            tool: Tool::new(
+                LIST_OPERATIONS_TOOL_NAME,
+                "Lists the names and one-line descriptions of the tools for the GraphQL operations currently available.",
+                schema_from_type!(Input),
+            ),
+        }
+    }
+
+    /// List the operation tools, one per line, with the first line of their descriptions
+    pub fn execute(&self, operations: &[Tool]) -> CallToolResult {
+        let listing = operations
+            .iter()
+            .map(|tool| {
+                let summary = tool.description.as_deref().and_then(|description| {
+                    description
+                        .lines()
+                        .map(str::trim)
+                        .find(|line| !line.is_empty())
+                });
+                match summary {
+                    Some(summary) => format!("{}: {summary}", tool.name),
+                    None => tool.name.to_string(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        CallToolResult {
+            content: vec![Content::text(if listing.is_empty() {
+                "No operations are available".to_string()
+            } else {
+                listing.join("\n")
+            })],
+            is_error: None,
+        }
+    }
+}
+
+impl Default for ListOperations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn lists_operation_names_and_descriptions() {
+        let tool = |name: &'static str, description: &'static str| {
+            Tool::new(
+                name,
+                description,
+                Arc::new(json!({}).as_object().unwrap().clone()),
+            )
+        };
+        let result = ListOperations::new().execute(&[
+            tool(
+                "GetAlerts",
+                "Get the active alerts\n\nThe returned fields are: ...",
+            ),
+            tool("GetForecast", ""),
+        ]);
+        assert_eq!(
+            result
+                .content
+                .first()
+                .and_then(|c| c.as_text())
+                .map(|t| t.text.as_str()),
+            Some("GetAlerts: Get the active alerts\nGetForecast")
+        );
+    }
+}
@@ -7,12 +7,16 @@ use crate::schema_tree_shake::{DepthLimit, SchemaTreeShaker};
 use apollo_compiler::ast::{Field, OperationType as AstOperationType, Selection};
 use apollo_compiler::validation::Valid;
 use apollo_compiler::{Name, Node, Schema};
-use apollo_schema_index::{OperationType, Options, SchemaIndex};
+use apollo_schema_index::{
+    IndexOptions, LeafSelection, MinShouldMatch, OperationType, Options, PathNode, SchemaIndex,
+    Scored, query_skeleton,
+};
+use enumset::EnumSet;
 use rmcp::model::{CallToolResult, Content, ErrorCode, Tool};
 use rmcp::schemars::JsonSchema;
 use rmcp::serde_json::Value;
 use rmcp::{schemars, serde_json};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -30,16 +34,84 @@ pub struct Search {
     schema: Arc<Mutex<Valid<Schema>>>,
     index: SchemaIndex,
     allow_mutations: bool,
-    leaf_depth: usize,
-    minify: bool,
+    options: SearchOptions,
     pub tool: Tool,
 }
 
+/// How the search tool searches the index and presents its results
+#[derive(Clone, Copy, Debug)]
+pub struct SearchOptions {
+    /// The depth of subtype information to include from matching types
+    pub leaf_depth: usize,
+
+    /// Minify the definitions of the matching types
+    pub minify: bool,
+
+    /// How to summarize the paths to the matching types
+    pub format: SearchFormat,
+
+    /// Collapse paths which are the start of a longer path, or differ only in their
+    /// intermediate types
+    pub collapse_redundant_paths: bool,
+
+    /// Retry searches which find nothing once, matching their words fuzzily
+    pub broaden_empty_results: bool,
+
+    /// The maximum number of partial paths visited while looking for the paths to each
+    /// matching type
+    pub max_visited_paths_per_type: usize,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            leaf_depth: 1,
+            minify: false,
+            format: SearchFormat::default(),
+            collapse_redundant_paths: false,
+            broaden_empty_results: false,
+            max_visited_paths_per_type: Options::default().max_visited_paths_per_type,
+        }
+    }
+}
+
+/// How to summarize the paths to the types matching a search, ahead of their definitions
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, ::schemars::JsonSchema)]
+#[schemars(crate = "::schemars")]
+#[serde(rename_all = "snake_case")]
+pub enum SearchFormat {
+    /// Return only the definitions of the matching types
+    #[default]
+    Plain,
+
+    /// Summarize the matching paths in a markdown table
+    Markdown,
+
+    /// Summarize the matching paths in a JSON array of `{type, path, score, skeleton}` objects,
+    /// where `skeleton` is a query selecting the type along its path
+    Json,
+}
+
+/// A path to a type matching a search
+#[derive(Serialize)]
+struct SearchResult {
+    r#type: String,
+    path: String,
+    score: f32,
+    /// A query selecting the type along the path, if the path follows any fields
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skeleton: Option<String>,
+}
+
 /// Input for the search tool.
 #[derive(JsonSchema, Deserialize)]
 pub struct Input {
-    /// The search terms
+    /// The search terms, which can be joined by the boolean operators `AND`, `OR`, and `NOT`
     terms: Vec<String>,
+
+    /// Set to true to also search custom directive definitions
+    #[serde(default)]
+    include_directives: bool,
 }
 
 /// An error while indexing the GraphQL schema.
@@ -55,28 +127,21 @@ pub enum IndexingError {
 impl Search {
     pub fn new(
         schema: Arc<Mutex<Valid<Schema>>>,
-        allow_mutations: bool,
-        leaf_depth: usize,
-        index_memory_bytes: usize,
-        minify: bool,
+        root_types: EnumSet<OperationType>,
+        index_options: IndexOptions,
+        options: SearchOptions,
     ) -> Result<Self, IndexingError> {
-        let root_types = if allow_mutations {
-            OperationType::Query | OperationType::Mutation
-        } else {
-            OperationType::Query.into()
-        };
         let locked = &schema.try_lock()?;
         Ok(Self {
             schema: schema.clone(),
-            index: SchemaIndex::new(locked, root_types, index_memory_bytes)?,
-            allow_mutations,
-            leaf_depth,
-            minify,
+            index: SchemaIndex::new(locked, root_types, index_options)?,
+            allow_mutations: root_types.contains(OperationType::Mutation),
+            options,
             tool: Tool::new(
                 SEARCH_TOOL_NAME,
                 format!(
                     "Search a GraphQL schema{}",
-                    if minify {
+                    if options.minify {
                         " - T=type,I=input,E=enum,U=union,F=interface;s=String,i=Int,f=Float,b=Boolean,d=ID;!=required,[]=list,<>=implements"
                     } else {
                         ""
@@ -88,16 +153,39 @@ impl Search {
     }
 
     pub async fn execute(&self, input: Input) -> Result<CallToolResult, McpError> {
-        let mut root_paths = self
-            .index
-            .search(input.terms.clone(), Options::default())
-            .map_err(|e| {
-                McpError::new(
-                    ErrorCode::INTERNAL_ERROR,
-                    format!("Failed to search index: {e}"),
-                    None,
+        let search = |options: Options| {
+            self.index
+                .search(
+                    input.terms.clone(),
+                    Options {
+                        collapse_redundant_paths: self.options.collapse_redundant_paths,
+                        max_visited_paths_per_type: self.options.max_visited_paths_per_type,
+                        ..options
+                    },
                 )
+                .map_err(|e| {
+                    McpError::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to search index: {e}"),
+                        None,
+                    )
+                })
+        };
+        let mut root_paths = search(Options::default())?;
+
+        // Retry a search finding nothing once, matching any word fuzzily
+        let broadened = root_paths.is_empty() && self.options.broaden_empty_results;
+        if broadened {
+            debug!(
+                "No types matched {}, broadening the search",
+                input.terms.join(", ")
+            );
+            root_paths = search(Options {
+                fuzzy: true,
+                min_should_match: MinShouldMatch::Count(1),
+                ..Default::default()
             })?;
+        }
 
         root_paths.truncate(MAX_SEARCH_RESULTS);
         debug!(
@@ -110,14 +198,34 @@ impl Search {
                 .join("\n"),
         );
 
+        let directives = if input.include_directives {
+            self.index
+                .search_directives(input.terms.clone(), MAX_SEARCH_RESULTS)
+                .map_err(|e| {
+                    McpError::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to search index: {e}"),
+                        None,
+                    )
+                })?
+        } else {
+            Vec::new()
+        };
+
         let schema = self.schema.lock().await;
+        let summary = self.summary(&schema, &root_paths)?;
+        let broadened_notice = (broadened && !root_paths.is_empty()).then(|| {
+            "Broadened: no types matched the search exactly, so these are the closest matches"
+                .to_string()
+        });
+
         let mut tree_shaker = SchemaTreeShaker::new(&schema);
         for root_path in root_paths {
             let path_len = root_path.inner.len();
             for (i, path_node) in root_path.inner.into_iter().enumerate() {
                 if let Some(extended_type) = schema.types.get(path_node.node_type.as_str()) {
                     let (selection_set, depth) = if i == path_len - 1 {
-                        (None, DepthLimit::Limited(self.leaf_depth))
+                        (None, DepthLimit::Limited(self.options.leaf_depth))
                     } else {
                         (
                             path_node.field_name.as_ref().map(|field_name| {
@@ -146,29 +254,87 @@ impl Search {
         let shaken = tree_shaker.shaken().unwrap_or_else(|schema| schema.partial);
 
         Ok(CallToolResult {
-            content: shaken
-                .types
-                .iter()
-                .filter(|(_name, extended_type)| {
-                    !extended_type.is_built_in()
-                        && schema
-                            .root_operation(AstOperationType::Mutation)
-                            .is_none_or(|root_name| {
-                                extended_type.name() != root_name || self.allow_mutations
-                            })
-                })
-                .map(|(_, extended_type)| {
-                    if self.minify {
-                        extended_type.minify()
-                    } else {
-                        extended_type.serialize().to_string()
-                    }
-                })
+            content: broadened_notice
+                .into_iter()
+                .chain(summary)
+                .chain(
+                    shaken
+                        .types
+                        .iter()
+                        .filter(|(_name, extended_type)| {
+                            !extended_type.is_built_in()
+                                && schema
+                                    .root_operation(AstOperationType::Mutation)
+                                    .is_none_or(|root_name| {
+                                        extended_type.name() != root_name || self.allow_mutations
+                                    })
+                        })
+                        .map(|(_, extended_type)| {
+                            if self.options.minify {
+                                extended_type.minify()
+                            } else {
+                                extended_type.serialize().to_string()
+                            }
+                        }),
+                )
+                .chain(directives.iter().filter_map(|directive| {
+                    schema
+                        .directive_definitions
+                        .get(&directive.inner)
+                        .map(ToString::to_string)
+                }))
                 .map(Content::text)
                 .collect(),
             is_error: None,
         })
     }
+
+    /// Summarize the paths to the matching types in the configured format
+    fn summary(
+        &self,
+        schema: &Schema,
+        root_paths: &[Scored<PathNode>],
+    ) -> Result<Option<String>, McpError> {
+        let results = root_paths.iter().map(|root_path| SearchResult {
+            r#type: root_path
+                .inner
+                .iter()
+                .last()
+                .map(|node| node.node_type.to_string())
+                .unwrap_or_default(),
+            path: root_path.inner.to_string(),
+            score: root_path.score(),
+            skeleton: (self.options.format == SearchFormat::Json && root_path.inner.len() > 1)
+                .then(|| query_skeleton(schema, &root_path.inner, LeafSelection::default())),
+        });
+        match self.options.format {
+            SearchFormat::Plain => Ok(None),
+            SearchFormat::Markdown => Ok(Some(
+                [
+                    "| Type | Path | Score |".to_string(),
+                    "| --- | --- | --- |".to_string(),
+                ]
+                .into_iter()
+                .chain(results.map(|result| {
+                    format!(
+                        "| {} | {} | {:.2} |",
+                        result.r#type, result.path, result.score
+                    )
+                }))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            )),
+            SearchFormat::Json => serde_json::to_string(&results.collect::<Vec<_>>())
+                .map(Some)
+                .map_err(|e| {
+                    McpError::new(
+                        ErrorCode::INTERNAL_ERROR,
+                        format!("Failed to serialize search results: {e}"),
+                        None,
+                    )
+                }),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -180,6 +346,13 @@ mod tests {
 
     const TEST_SCHEMA: &str = include_str!("testdata/schema.graphql");
 
+    fn test_index_options() -> IndexOptions {
+        IndexOptions {
+            index_memory_bytes: 15_000_000,
+            ..Default::default()
+        }
+    }
+
     fn content_to_snapshot(result: CallToolResult) -> String {
         result
             .content
@@ -207,12 +380,18 @@ mod tests {
     #[tokio::test]
     async fn test_search_tool(schema: Valid<Schema>) {
         let schema = Arc::new(Mutex::new(schema));
-        let search = Search::new(schema.clone(), false, 1, 15_000_000, false)
-            .expect("Failed to create search tool");
+        let search = Search::new(
+            schema.clone(),
+            OperationType::Query.into(),
+            test_index_options(),
+            SearchOptions::default(),
+        )
+        .expect("Failed to create search tool");
 
         let result = search
             .execute(Input {
                 terms: vec!["User".to_string()],
+                include_directives: false,
             })
             .await
             .expect("Search execution failed");
@@ -225,13 +404,19 @@ mod tests {
     #[tokio::test]
     async fn test_referencing_types_are_collected(schema: Valid<Schema>) {
         let schema = Arc::new(Mutex::new(schema));
-        let search = Search::new(schema.clone(), true, 1, 15_000_000, false)
-            .expect("Failed to create search tool");
+        let search = Search::new(
+            schema.clone(),
+            OperationType::Query | OperationType::Mutation,
+            test_index_options(),
+            SearchOptions::default(),
+        )
+        .expect("Failed to create search tool");
 
         // Search for a type that should have references
         let result = search
             .execute(Input {
                 terms: vec!["User".to_string()],
+                include_directives: false,
             })
             .await
             .expect("Search execution failed");
@@ -242,4 +427,117 @@ mod tests {
             "Expected to find the createUser mutation in search results"
         );
     }
+
+    #[rstest]
+    #[tokio::test]
+    async fn test_json_format(schema: Valid<Schema>) {
+        let search = Search::new(
+            Arc::new(Mutex::new(schema)),
+            OperationType::Query.into(),
+            test_index_options(),
+            SearchOptions {
+                format: SearchFormat::Json,
+                ..Default::default()
+            },
+        )
+        .expect("Failed to create search tool");
+
+        let result = search
+            .execute(Input {
+                terms: vec!["User".to_string()],
+                include_directives: false,
+            })
+            .await
+            .expect("Search execution failed");
+
+        let summary = match result.content.first().map(Deref::deref) {
+            Some(RawContent::Text(text)) => text.text.clone(),
+            _ => panic!("Expected a text summary"),
+        };
+        let results: Vec<Value> = serde_json::from_str(&summary).unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|result| {
+            result["type"].is_string() && result["path"].is_string() && result["score"].is_number()
+        }));
+        assert!(results.iter().any(|result| result["type"] == "User"));
+        // Paths through fields carry a query selecting the matching type
+        let user = results
+            .iter()
+            .find(|result| result["type"] == "User" && result["path"] != "User")
+            .expect("Expected a path to User");
+        assert!(
+            user["skeleton"]
+                .as_str()
+                .is_some_and(|skeleton| skeleton.starts_with("query {")),
+            "{user}"
+        );
+        // The definitions of the matching types follow the summary
+        assert!(result.content.len() > 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_includes_directives() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            """Restricts a field to callers with the given role"""
+            directive @auth(role: String!) on FIELD_DEFINITION
+            type Query { secret: Secret @auth(role: "admin") }
+            type Secret { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let search = Search::new(
+            Arc::new(Mutex::new(schema)),
+            OperationType::Query.into(),
+            IndexOptions {
+                index_directives: true,
+                ..test_index_options()
+            },
+            SearchOptions::default(),
+        )
+        .expect("Failed to create search tool");
+
+        let search_auth = |include_directives| {
+            search.execute(Input {
+                terms: vec!["auth".to_string()],
+                include_directives,
+            })
+        };
+
+        let result = content_to_snapshot(search_auth(true).await.unwrap());
+        assert!(result.contains("directive @auth(role: String!) on FIELD_DEFINITION"));
+
+        let result = content_to_snapshot(search_auth(false).await.unwrap());
+        assert!(!result.contains("directive @auth"));
+    }
+
+    #[rstest]
+    #[tokio::test]
+    async fn empty_searches_can_be_broadened(schema: Valid<Schema>) {
+        let schema = Arc::new(Mutex::new(schema));
+        let search = |broaden_empty_results| {
+            Search::new(
+                schema.clone(),
+                OperationType::Query.into(),
+                test_index_options(),
+                SearchOptions {
+                    broaden_empty_results,
+                    ..Default::default()
+                },
+            )
+            .expect("Failed to create search tool")
+        };
+        let input = || Input {
+            terms: vec!["usernme".to_string()],
+            include_directives: false,
+        };
+
+        let result = search(false).execute(input()).await.unwrap();
+        assert!(result.content.is_empty());
+
+        let result = content_to_snapshot(search(true).execute(input()).await.unwrap());
+        assert!(result.starts_with("Broadened:"), "{result}");
+        assert!(result.contains("type User"), "{result}");
+    }
 }
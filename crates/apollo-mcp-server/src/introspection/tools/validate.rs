@@ -1,14 +1,16 @@
 use crate::errors::McpError;
-use crate::operations::operation_defs;
+use crate::operations::{MutationMode, operation_defs};
 use crate::schema_from_type;
 use apollo_compiler::Schema;
 use apollo_compiler::parser::Parser;
-use apollo_compiler::validation::Valid;
+use apollo_compiler::request::coerce_variable_values;
+use apollo_compiler::response::JsonMap;
+use apollo_compiler::validation::{DiagnosticList, Valid};
 use rmcp::model::CallToolResult;
 use rmcp::model::Content;
 use rmcp::model::{ErrorCode, Tool};
 use rmcp::schemars::JsonSchema;
-use rmcp::serde_json::Value;
+use rmcp::serde_json::{Value, json};
 use rmcp::{schemars, serde_json};
 use serde::Deserialize;
 use std::default::Default;
@@ -22,6 +24,7 @@ pub const VALIDATE_TOOL_NAME: &str = "validate";
 pub struct Validate {
     pub tool: Tool,
     schema: Arc<Mutex<Valid<Schema>>>,
+    mutation_mode: MutationMode,
 }
 
 /// Input for the validate tool
@@ -29,15 +32,20 @@ pub struct Validate {
 pub struct Input {
     /// The GraphQL operation
     operation: String,
+
+    /// The variable values represented as JSON
+    #[schemars(schema_with = "String::json_schema", default)]
+    variables: Option<Value>,
 }
 
 impl Validate {
-    pub fn new(schema: Arc<Mutex<Valid<Schema>>>) -> Self {
+    pub fn new(schema: Arc<Mutex<Valid<Schema>>>, mutation_mode: MutationMode) -> Self {
         Self {
             schema,
+            mutation_mode,
             tool: Tool::new(
                 VALIDATE_TOOL_NAME,
-                "Validates a GraphQL operation against the schema. \
+                "Validates a GraphQL operation and optional variables against the schema without executing it. \
                 Use the `introspect` tool first to get information about the GraphQL schema. \
                 Operations should be validated prior to calling the `execute` tool.",
                 schema_from_type!(Input),
@@ -51,22 +59,54 @@ impl Validate {
             McpError::new(ErrorCode::INVALID_PARAMS, "Invalid input".to_string(), None)
         })?;
 
-        operation_defs(&input.operation, true, None)
-            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?
-            .ok_or_else(|| {
-                McpError::new(
+        operation_defs(
+            &input.operation,
+            self.mutation_mode == MutationMode::All,
+            None,
+        )
+        .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?
+        .ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "Invalid operation type".to_string(),
+                None,
+            )
+        })?;
+
+        let variables = match input.variables {
+            None | Some(Value::Null) => JsonMap::new(),
+            Some(Value::String(s)) => serde_json::from_str(&s).map_err(|_| {
+                McpError::new(ErrorCode::INVALID_PARAMS, "Invalid input".to_string(), None)
+            })?,
+            Some(obj) if obj.is_object() => serde_json::from_value(obj).map_err(|_| {
+                McpError::new(ErrorCode::INVALID_PARAMS, "Invalid input".to_string(), None)
+            })?,
+            _ => {
+                return Err(McpError::new(
                     ErrorCode::INVALID_PARAMS,
-                    "Invalid operation type".to_string(),
+                    "Invalid input".to_string(),
                     None,
-                )
-            })?;
+                ));
+            }
+        };
 
         let schema_guard = self.schema.lock().await;
-        Parser::new()
+        let document = Parser::new()
             .parse_executable(&schema_guard, input.operation.as_str(), "operation.graphql")
-            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?
+            .map_err(|e| validation_error(e.errors))?
             .validate(&schema_guard)
-            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+            .map_err(|e| validation_error(e.errors))?;
+
+        if let Some(operation) = document.operations.iter().next() {
+            coerce_variable_values(&schema_guard, operation, &variables).map_err(|e| {
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    e.message().to_string(),
+                    Some(json!({ "errors": [e.to_graphql_error(&document.sources)] })),
+                )
+            })?;
+        }
+
         Ok(CallToolResult {
             content: vec![Content::text("Operation is valid")],
             is_error: None,
@@ -74,25 +114,36 @@ impl Validate {
     }
 }
 
+/// Build an error carrying the GraphQL validation errors, including their locations
+fn validation_error(errors: DiagnosticList) -> McpError {
+    let graphql_errors: Vec<_> = errors.iter().map(|d| d.to_json()).collect();
+    McpError::new(
+        ErrorCode::INVALID_PARAMS,
+        errors.to_string(),
+        Some(json!({ "errors": graphql_errors })),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use serde_json::json;
 
     use super::*;
-    static SCHEMA: std::sync::LazyLock<Arc<Mutex<Valid<Schema>>>> =
-        std::sync::LazyLock::new(|| {
+    static SCHEMA: std::sync::LazyLock<Arc<Mutex<Valid<Schema>>>> = std::sync::LazyLock::new(
+        || {
             Arc::new(Mutex::new(
                 Schema::parse_and_validate(
-                    "type Query { id: ID! hello(name: String!): String! }",
+                    "type Query { id: ID! hello(name: String!): String! } type Mutation { reset: Boolean }",
                     "schema.graphql",
                 )
                 .unwrap(),
             ))
-        });
+        },
+    );
 
     #[tokio::test]
     async fn validate_valid_query() {
-        let validate = Validate::new(SCHEMA.clone());
+        let validate = Validate::new(SCHEMA.clone(), MutationMode::None);
         let input = json!({
             "operation": "query Test { id }"
         });
@@ -101,7 +152,7 @@ mod tests {
 
     #[tokio::test]
     async fn validate_invalid_graphql_query() {
-        let validate = Validate::new(SCHEMA.clone());
+        let validate = Validate::new(SCHEMA.clone(), MutationMode::None);
         let input = json!({
             "operation": "query {"
         });
@@ -110,19 +161,67 @@ mod tests {
 
     #[tokio::test]
     async fn validate_invalid_query_field() {
-        let validate = Validate::new(SCHEMA.clone());
+        let validate = Validate::new(SCHEMA.clone(), MutationMode::None);
         let input = json!({
             "operation": "query { invalidField }"
         });
-        assert!(validate.execute(input).await.is_err());
+        let error = validate.execute(input).await.unwrap_err();
+        assert_eq!(
+            error.data,
+            Some(json!({
+                "errors": [{
+                    "message": "type `Query` does not have a field `invalidField`",
+                    "locations": [{ "line": 1, "column": 9 }]
+                }]
+            }))
+        );
     }
 
     #[tokio::test]
     async fn validate_invalid_argument() {
-        let validate = Validate::new(SCHEMA.clone());
+        let validate = Validate::new(SCHEMA.clone(), MutationMode::None);
         let input = json!({
             "operation": "query { hello }"
         });
         assert!(validate.execute(input).await.is_err());
     }
+
+    #[tokio::test]
+    async fn validate_query_with_variables() {
+        let validate = Validate::new(SCHEMA.clone(), MutationMode::None);
+        let input = json!({
+            "operation": "query Hello($name: String!) { hello(name: $name) }",
+            "variables": { "name": "world" }
+        });
+        assert!(validate.execute(input).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn validate_query_with_invalid_variables() {
+        let validate = Validate::new(SCHEMA.clone(), MutationMode::None);
+        let input = json!({
+            "operation": "query Hello($name: String!) { hello(name: $name) }",
+            "variables": "{}"
+        });
+        let error = validate.execute(input).await.unwrap_err();
+        assert!(error.data.is_some());
+    }
+
+    #[tokio::test]
+    async fn validate_mutation_not_allowed() {
+        let validate = Validate::new(SCHEMA.clone(), MutationMode::None);
+        let input = json!({
+            "operation": "mutation { reset }"
+        });
+        assert!(validate.execute(input).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn validate_mutation_allowed() {
+        let validate = Validate::new(SCHEMA.clone(), MutationMode::All);
+        let input = json!({
+            "operation": "mutation { reset }"
+        });
+        assert!(validate.execute(input).await.is_ok());
+    }
 }
@@ -0,0 +1,613 @@
+//! Load a schema from a running GraphQL endpoint using introspection
+
+use std::fmt::Write as _;
+use std::time::Duration;
+
+use futures::{Stream, StreamExt as _, stream};
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use tracing::{error, warn};
+use url::Url;
+
+/// The standard introspection query, used to fetch the schema from an endpoint
+pub const INTROSPECTION_QUERY: &str = r#"
+query IntrospectionQuery {
+  __schema {
+    queryType { name }
+    mutationType { name }
+    subscriptionType { name }
+    types { ...FullType }
+    directives {
+      name
+      description
+      locations
+      args { ...InputValue }
+    }
+  }
+}
+
+fragment FullType on __Type {
+  kind
+  name
+  description
+  fields(includeDeprecated: true) {
+    name
+    description
+    args { ...InputValue }
+    type { ...TypeRef }
+    isDeprecated
+    deprecationReason
+  }
+  inputFields { ...InputValue }
+  interfaces { ...TypeRef }
+  enumValues(includeDeprecated: true) {
+    name
+    description
+    isDeprecated
+    deprecationReason
+  }
+  possibleTypes { ...TypeRef }
+}
+
+fragment InputValue on __InputValue {
+  name
+  description
+  type { ...TypeRef }
+  defaultValue
+}
+
+fragment TypeRef on __Type {
+  kind
+  name
+  ofType {
+    kind
+    name
+    ofType {
+      kind
+      name
+      ofType {
+        kind
+        name
+        ofType {
+          kind
+          name
+          ofType {
+            kind
+            name
+            ofType {
+              kind
+              name
+              ofType {
+                kind
+                name
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+/// Scalars and directives built into every GraphQL schema, which must not be redefined
+const BUILT_IN_SCALARS: [&str; 5] = ["String", "Int", "Float", "Boolean", "ID"];
+const BUILT_IN_DIRECTIVES: [&str; 4] = ["skip", "include", "deprecated", "specifiedBy"];
+
+/// An error fetching a schema with introspection
+#[derive(Debug, thiserror::Error)]
+pub enum IntrospectionError {
+    #[error("Failed to send introspection request to {0}: {1}")]
+    Request(Url, reqwest::Error),
+
+    #[error("Failed to read introspection response from {0}: {1}")]
+    Response(Url, reqwest::Error),
+
+    #[error("Introspection is disabled or failed on {0}: {1}")]
+    Disabled(Url, String),
+
+    #[error("Invalid introspection result from {0}: {1}")]
+    InvalidResult(Url, serde_json::Error),
+}
+
+/// Fetch the schema from an endpoint with introspection, returning it as SDL
+pub async fn fetch_schema(
+    endpoint: &Url,
+    headers: &HeaderMap,
+) -> Result<String, IntrospectionError> {
+    let response = reqwest::Client::new()
+        .post(endpoint.as_str())
+        .headers(headers.clone())
+        .json(&json!({
+            "query": INTROSPECTION_QUERY,
+            "operationName": "IntrospectionQuery",
+        }))
+        .send()
+        .await
+        .map_err(|e| IntrospectionError::Request(endpoint.clone(), e))?
+        .json::<Value>()
+        .await
+        .map_err(|e| IntrospectionError::Response(endpoint.clone(), e))?;
+
+    let Some(schema) = response
+        .get("data")
+        .and_then(|data| data.get("__schema"))
+        .filter(|schema| !schema.is_null())
+    else {
+        let messages = response
+            .get("errors")
+            .and_then(Value::as_array)
+            .map(|errors| {
+                errors
+                    .iter()
+                    .filter_map(|error| error.get("message").and_then(Value::as_str))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .filter(|messages| !messages.is_empty())
+            .unwrap_or_else(|| "no schema was returned".to_string());
+        return Err(IntrospectionError::Disabled(endpoint.clone(), messages));
+    };
+
+    let schema = IntrospectionSchema::deserialize(schema)
+        .map_err(|e| IntrospectionError::InvalidResult(endpoint.clone(), e))?;
+    Ok(schema.to_sdl())
+}
+
+/// Stream the schema of an endpoint, optionally re-polling for changes at an interval.
+///
+/// Only schemas which differ from the previously fetched schema are emitted.
+pub fn schema_stream(
+    endpoint: Url,
+    headers: HeaderMap,
+    poll_interval: Option<Duration>,
+) -> impl Stream<Item = String> + Send {
+    stream::unfold((None::<String>, true), move |(previous, first)| {
+        let endpoint = endpoint.clone();
+        let headers = headers.clone();
+        async move {
+            if !first {
+                tokio::time::sleep(poll_interval?).await;
+            }
+            match fetch_schema(&endpoint, &headers).await {
+                Ok(sdl) if previous.as_ref() == Some(&sdl) => Some((None, (previous, false))),
+                Ok(sdl) => Some((Some(sdl.clone()), (Some(sdl), false))),
+                Err(e) if poll_interval.is_some() => {
+                    warn!("{e}");
+                    Some((None, (previous, false)))
+                }
+                Err(e) => {
+                    error!("{e}");
+                    None
+                }
+            }
+        }
+    })
+    .filter_map(|sdl| async move { sdl })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IntrospectionSchema {
+    query_type: Option<NamedType>,
+    mutation_type: Option<NamedType>,
+    subscription_type: Option<NamedType>,
+    types: Vec<FullType>,
+    #[serde(default)]
+    directives: Vec<Directive>,
+}
+
+#[derive(Deserialize)]
+struct NamedType {
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FullType {
+    kind: String,
+    name: String,
+    description: Option<String>,
+    fields: Option<Vec<Field>>,
+    input_fields: Option<Vec<InputValue>>,
+    interfaces: Option<Vec<TypeRef>>,
+    enum_values: Option<Vec<EnumValue>>,
+    possible_types: Option<Vec<TypeRef>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Field {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    args: Vec<InputValue>,
+    #[serde(rename = "type")]
+    field_type: TypeRef,
+    #[serde(default)]
+    is_deprecated: bool,
+    deprecation_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InputValue {
+    name: String,
+    description: Option<String>,
+    #[serde(rename = "type")]
+    input_type: TypeRef,
+    default_value: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EnumValue {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    is_deprecated: bool,
+    deprecation_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TypeRef {
+    kind: String,
+    name: Option<String>,
+    of_type: Option<Box<TypeRef>>,
+}
+
+#[derive(Deserialize)]
+struct Directive {
+    name: String,
+    description: Option<String>,
+    #[serde(default)]
+    locations: Vec<String>,
+    #[serde(default)]
+    args: Vec<InputValue>,
+}
+
+impl IntrospectionSchema {
+    fn to_sdl(&self) -> String {
+        let mut sdl = String::new();
+
+        let root_operations = [
+            ("query", &self.query_type),
+            ("mutation", &self.mutation_type),
+            ("subscription", &self.subscription_type),
+        ]
+        .into_iter()
+        .filter_map(|(operation, root)| {
+            root.as_ref()
+                .map(|root| format!("  {operation}: {}\n", root.name))
+        })
+        .collect::<String>();
+        if !root_operations.is_empty() {
+            let _ = writeln!(sdl, "schema {{\n{root_operations}}}\n");
+        }
+
+        for directive in self
+            .directives
+            .iter()
+            .filter(|directive| !BUILT_IN_DIRECTIVES.contains(&directive.name.as_str()))
+        {
+            write_description(&mut sdl, &directive.description, "");
+            let _ = writeln!(
+                sdl,
+                "directive @{}{} on {}\n",
+                directive.name,
+                arguments(&directive.args),
+                directive.locations.join(" | ")
+            );
+        }
+
+        for full_type in self.types.iter().filter(|full_type| {
+            !full_type.name.starts_with("__")
+                && !BUILT_IN_SCALARS.contains(&full_type.name.as_str())
+        }) {
+            write_description(&mut sdl, &full_type.description, "");
+            let name = &full_type.name;
+            match full_type.kind.as_str() {
+                "SCALAR" => {
+                    let _ = writeln!(sdl, "scalar {name}\n");
+                }
+                "OBJECT" | "INTERFACE" => {
+                    let keyword = if full_type.kind == "OBJECT" {
+                        "type"
+                    } else {
+                        "interface"
+                    };
+                    let interfaces = full_type
+                        .interfaces
+                        .iter()
+                        .flatten()
+                        .map(TypeRef::to_sdl)
+                        .collect::<Vec<_>>();
+                    let implements = if interfaces.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" implements {}", interfaces.join(" & "))
+                    };
+                    let _ = writeln!(sdl, "{keyword} {name}{implements} {{");
+                    for field in full_type.fields.iter().flatten() {
+                        write_description(&mut sdl, &field.description, "  ");
+                        let _ = writeln!(
+                            sdl,
+                            "  {}{}: {}{}",
+                            field.name,
+                            arguments(&field.args),
+                            field.field_type.to_sdl(),
+                            deprecated(field.is_deprecated, &field.deprecation_reason)
+                        );
+                    }
+                    let _ = writeln!(sdl, "}}\n");
+                }
+                "UNION" => {
+                    let members = full_type
+                        .possible_types
+                        .iter()
+                        .flatten()
+                        .map(TypeRef::to_sdl)
+                        .collect::<Vec<_>>();
+                    let _ = writeln!(sdl, "union {name} = {}\n", members.join(" | "));
+                }
+                "ENUM" => {
+                    let _ = writeln!(sdl, "enum {name} {{");
+                    for value in full_type.enum_values.iter().flatten() {
+                        write_description(&mut sdl, &value.description, "  ");
+                        let _ = writeln!(
+                            sdl,
+                            "  {}{}",
+                            value.name,
+                            deprecated(value.is_deprecated, &value.deprecation_reason)
+                        );
+                    }
+                    let _ = writeln!(sdl, "}}\n");
+                }
+                "INPUT_OBJECT" => {
+                    let _ = writeln!(sdl, "input {name} {{");
+                    for input_field in full_type.input_fields.iter().flatten() {
+                        write_description(&mut sdl, &input_field.description, "  ");
+                        let _ = writeln!(sdl, "  {}", input_field.to_sdl());
+                    }
+                    let _ = writeln!(sdl, "}}\n");
+                }
+                other => warn!("Skipping type {name} of unknown kind {other}"),
+            }
+        }
+
+        sdl
+    }
+}
+
+impl TypeRef {
+    fn to_sdl(&self) -> String {
+        match (self.kind.as_str(), &self.of_type) {
+            ("NON_NULL", Some(of_type)) => format!("{}!", of_type.to_sdl()),
+            ("LIST", Some(of_type)) => format!("[{}]", of_type.to_sdl()),
+            _ => self.name.clone().unwrap_or_default(),
+        }
+    }
+}
+
+impl InputValue {
+    fn to_sdl(&self) -> String {
+        match &self.default_value {
+            Some(default_value) => format!(
+                "{}: {} = {default_value}",
+                self.name,
+                self.input_type.to_sdl()
+            ),
+            None => format!("{}: {}", self.name, self.input_type.to_sdl()),
+        }
+    }
+}
+
+/// Format a list of arguments, including their descriptions
+fn arguments(args: &[InputValue]) -> String {
+    if args.is_empty() {
+        return String::new();
+    }
+    let args = args
+        .iter()
+        .map(|arg| match &arg.description {
+            Some(description) => format!("{} {}", string_value(description), arg.to_sdl()),
+            None => arg.to_sdl(),
+        })
+        .collect::<Vec<_>>();
+    format!("({})", args.join(", "))
+}
+
+fn deprecated(is_deprecated: bool, reason: &Option<String>) -> String {
+    match (is_deprecated, reason) {
+        (false, _) => String::new(),
+        (true, Some(reason)) => format!(" @deprecated(reason: {})", string_value(reason)),
+        (true, None) => " @deprecated".to_string(),
+    }
+}
+
+fn write_description(sdl: &mut String, description: &Option<String>, indent: &str) {
+    if let Some(description) = description.as_ref().filter(|d| !d.is_empty()) {
+        let _ = writeln!(sdl, "{indent}{}", string_value(description));
+    }
+}
+
+/// Format a string as a GraphQL string value. JSON string escaping is valid GraphQL.
+fn string_value(value: &str) -> String {
+    Value::String(value.to_string()).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use apollo_compiler::Schema;
+
+    use super::*;
+
+    fn type_ref(kind: &str, name: Option<&str>, of_type: Option<Value>) -> Value {
+        json!({ "kind": kind, "name": name, "ofType": of_type })
+    }
+
+    fn introspection_result() -> Value {
+        let string = type_ref("SCALAR", Some("String"), None);
+        let id = type_ref("NON_NULL", None, Some(type_ref("SCALAR", Some("ID"), None)));
+        json!({
+            "data": {
+                "__schema": {
+                    "queryType": { "name": "Query" },
+                    "mutationType": null,
+                    "subscriptionType": null,
+                    "types": [
+                        {
+                            "kind": "OBJECT",
+                            "name": "Query",
+                            "description": null,
+                            "fields": [{
+                                "name": "user",
+                                "description": "Look up a user",
+                                "args": [{ "name": "id", "description": null, "type": id, "defaultValue": null }],
+                                "type": type_ref("OBJECT", Some("User"), None),
+                                "isDeprecated": false,
+                                "deprecationReason": null
+                            }],
+                            "inputFields": null,
+                            "interfaces": [],
+                            "enumValues": null,
+                            "possibleTypes": null
+                        },
+                        {
+                            "kind": "OBJECT",
+                            "name": "User",
+                            "description": "A \"user\" of the system",
+                            "fields": [
+                                {
+                                    "name": "id",
+                                    "description": null,
+                                    "args": [],
+                                    "type": id,
+                                    "isDeprecated": false,
+                                    "deprecationReason": null
+                                },
+                                {
+                                    "name": "nicknames",
+                                    "description": null,
+                                    "args": [],
+                                    "type": type_ref("LIST", None, Some(string.clone())),
+                                    "isDeprecated": true,
+                                    "deprecationReason": "Use names"
+                                },
+                                {
+                                    "name": "role",
+                                    "description": null,
+                                    "args": [],
+                                    "type": type_ref("ENUM", Some("Role"), None),
+                                    "isDeprecated": false,
+                                    "deprecationReason": null
+                                }
+                            ],
+                            "inputFields": null,
+                            "interfaces": [],
+                            "enumValues": null,
+                            "possibleTypes": null
+                        },
+                        {
+                            "kind": "ENUM",
+                            "name": "Role",
+                            "description": null,
+                            "fields": null,
+                            "inputFields": null,
+                            "interfaces": null,
+                            "enumValues": [
+                                { "name": "ADMIN", "description": null, "isDeprecated": false, "deprecationReason": null },
+                                { "name": "MEMBER", "description": null, "isDeprecated": false, "deprecationReason": null }
+                            ],
+                            "possibleTypes": null
+                        },
+                        {
+                            "kind": "SCALAR",
+                            "name": "String",
+                            "description": "Built-in string",
+                            "fields": null,
+                            "inputFields": null,
+                            "interfaces": null,
+                            "enumValues": null,
+                            "possibleTypes": null
+                        }
+                    ],
+                    "directives": [{
+                        "name": "deprecated",
+                        "description": null,
+                        "locations": ["FIELD_DEFINITION"],
+                        "args": []
+                    }]
+                }
+            }
+        })
+    }
+
+    #[tokio::test]
+    async fn fetches_schema_from_endpoint() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/graphql")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(introspection_result().to_string())
+            .create_async()
+            .await;
+        let endpoint = Url::parse(&format!("{}/graphql", server.url())).unwrap();
+
+        let sdl = fetch_schema(&endpoint, &HeaderMap::new()).await.unwrap();
+        mock.assert_async().await;
+
+        let schema = Schema::parse_and_validate(sdl, "schema.graphql").unwrap();
+        assert!(schema.get_object("User").is_some());
+        assert!(schema.get_enum("Role").is_some());
+        insta::assert_snapshot!(schema.to_string(), @r#"
+        type Query {
+          """Look up a user"""
+          user(id: ID!): User
+        }
+
+        """A "user" of the system"""
+        type User {
+          id: ID!
+          nicknames: [String] @deprecated(reason: "Use names")
+          role: Role
+        }
+
+        enum Role {
+          ADMIN
+          MEMBER
+        }
+        "#);
+    }
+
+    #[tokio::test]
+    async fn reports_disabled_introspection() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/graphql")
+            .with_status(200)
+            .with_header("content-type", "application/json")
+            .with_body(
+                json!({
+                    "errors": [{ "message": "GraphQL introspection is not allowed" }]
+                })
+                .to_string(),
+            )
+            .create_async()
+            .await;
+        let endpoint = Url::parse(&format!("{}/graphql", server.url())).unwrap();
+
+        let error = fetch_schema(&endpoint, &HeaderMap::new())
+            .await
+            .unwrap_err();
+        assert!(matches!(error, IntrospectionError::Disabled(_, _)));
+        assert!(
+            error
+                .to_string()
+                .contains("GraphQL introspection is not allowed")
+        );
+    }
+}
@@ -1,13 +1,30 @@
+pub mod admin;
 pub mod auth;
+pub mod auxiliary;
+pub mod circuit_breaker;
+pub mod concurrency;
 pub mod custom_scalar_map;
 pub mod errors;
 pub mod event;
 mod explorer;
+pub mod feature_flags;
 mod graphql;
 pub mod health;
 mod introspection;
+pub mod introspection_schema;
 pub mod json_schema;
+pub mod meter;
+pub mod offline;
 pub mod operations;
+pub mod progress;
+pub mod projection;
+pub mod rest;
+pub mod result_cache;
 pub mod sanitize;
+pub mod schema_reload;
 pub(crate) mod schema_tree_shake;
 pub mod server;
+pub mod signing;
+mod subgraphs;
+pub mod telemetry;
+pub mod token_estimator;
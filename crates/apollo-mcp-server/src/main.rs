@@ -1,15 +1,22 @@
 use std::path::PathBuf;
+use std::sync::Arc;
 
 use apollo_mcp_registry::platform_api::operation_collections::collection_poller::CollectionSource;
 use apollo_mcp_registry::uplink::persisted_queries::ManifestSource;
 use apollo_mcp_registry::uplink::schema::SchemaSource;
+use apollo_mcp_server::auth;
 use apollo_mcp_server::custom_scalar_map::CustomScalarMap;
 use apollo_mcp_server::errors::ServerError;
-use apollo_mcp_server::operations::OperationSource;
+use apollo_mcp_server::feature_flags;
+use apollo_mcp_server::introspection_schema;
+use apollo_mcp_server::offline::OfflineCache;
+use apollo_mcp_server::operations::{OperationSource, SharedFragments};
+use apollo_mcp_server::schema_reload::SchemaReloadConfig;
 use apollo_mcp_server::server::Server;
 use clap::Parser;
 use clap::builder::Styles;
 use clap::builder::styling::{AnsiColor, Effects};
+use futures::StreamExt as _;
 use runtime::IdOrDefault;
 use runtime::logging::Logging;
 use tracing::{info, warn};
@@ -33,12 +40,28 @@ const STYLES: Styles = Styles::styled()
 struct Args {
     /// Path to the config file
     config: Option<PathBuf>,
+
+    /// Run from the cached schema and operations, without contacting uplink or the Platform API
+    #[arg(long)]
+    offline: bool,
+
+    /// The profile from the `profiles` section of the config file to overlay on the base config
+    #[arg(long, env = "APOLLO_MCP_PROFILE")]
+    profile: Option<String>,
+
+    /// Log the fully-resolved configuration at startup, with secrets redacted
+    #[arg(long)]
+    log_config: bool,
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let config: runtime::Config = match Args::parse().config {
-        Some(config_path) => runtime::read_config(config_path)?,
+    let args = Args::parse();
+    let config: runtime::Config = match &args.config {
+        Some(config_path) => runtime::read_config(config_path, args.profile.as_deref())?,
+        None if args.profile.is_some() => {
+            anyhow::bail!("A config file is required to select a profile")
+        }
         None => runtime::read_config_from_env().unwrap_or_default(),
     };
 
@@ -50,41 +73,126 @@ async fn main() -> anyhow::Result<()> {
         "Apollo MCP Server v{} // (c) Apollo Graph, Inc. // Licensed under MIT",
         env!("CARGO_PKG_VERSION")
     );
+    if args.log_config {
+        info!(
+            "Effective configuration:\n{}",
+            runtime::effective_config(&config)
+        );
+    }
 
-    let schema_source = match config.schema {
-        runtime::SchemaSource::Local { path } => SchemaSource::File { path, watch: true },
-        runtime::SchemaSource::Uplink => SchemaSource::Registry(config.graphos.uplink_config()?),
+    // Reload the auth configuration from the config file on SIGHUP, so that changes to the
+    // identity provider don't need a restart
+    let auth_loader = args
+        .config
+        .clone()
+        .map(|config_path| -> auth::ConfigLoader {
+            let profile = args.profile.clone();
+            Arc::new(move || {
+                let config = runtime::read_config(&config_path, profile.as_deref())?;
+                Ok(config.transport.auth().cloned())
+            })
+        });
+
+    // Evaluate the feature flags again on SIGHUP, so that gated operations can be exposed or
+    // hidden without a restart
+    let feature_flags_loader =
+        args.config
+            .clone()
+            .map(|config_path| -> feature_flags::ConfigLoader {
+                let profile = args.profile.clone();
+                Arc::new(move || {
+                    let config = runtime::read_config(&config_path, profile.as_deref())?;
+                    Ok(config.feature_flags)
+                })
+            });
+
+    let offline_cache = config.offline.cache_dir.clone().map(OfflineCache::new);
+    let offline = args.offline || config.offline.enabled;
+    if offline {
+        let cache = offline_cache.as_ref().ok_or(ServerError::OfflineCacheDir)?;
+        cache.check()?;
+        info!(
+            "Running offline from the cache in {}",
+            cache.dir().display()
+        );
+    }
+
+    let schema_reload = match &config.schema {
+        runtime::SchemaSource::Local { reload, .. } => reload.clone(),
+        _ => SchemaReloadConfig::default(),
+    };
+    let schema_source = match (offline_cache.as_ref().filter(|_| offline), config.schema) {
+        (Some(cache), _) => cache.schema_source(),
+        (None, runtime::SchemaSource::Local { path, .. }) => {
+            SchemaSource::File { path, watch: true }
+        }
+        (None, runtime::SchemaSource::Uplink) => {
+            SchemaSource::Registry(config.graphos.uplink_config()?)
+        }
+        (
+            None,
+            runtime::SchemaSource::Introspect {
+                endpoint,
+                poll_interval,
+            },
+        ) => SchemaSource::Stream(
+            introspection_schema::schema_stream(
+                endpoint.unwrap_or_else(|| config.endpoint.clone()),
+                config.headers.clone(),
+                poll_interval,
+            )
+            .boxed(),
+        ),
+    };
+
+    let shared_fragments = match &config.operations {
+        runtime::OperationSource::Local { fragments, .. } => SharedFragments::load(fragments)?,
+        _ => SharedFragments::default(),
     };
 
-    let operation_source = match config.operations {
+    let operation_kinds = config.operations.operation_kinds();
+    let operation_source = match (
+        offline_cache.as_ref().filter(|_| offline),
+        config.operations,
+    ) {
+        (Some(cache), _) => cache.operation_source(),
+
         // Default collection is special and requires other information
-        runtime::OperationSource::Collection {
-            id: IdOrDefault::Default,
-        } => OperationSource::Collection(CollectionSource::Default(
+        (
+            None,
+            runtime::OperationSource::Collection {
+                id: IdOrDefault::Default,
+                ..
+            },
+        ) => OperationSource::Collection(CollectionSource::Default(
             config.graphos.graph_ref()?,
             config.graphos.platform_api_config()?,
         )),
 
-        runtime::OperationSource::Collection {
-            id: IdOrDefault::Id(collection_id),
-        } => OperationSource::Collection(CollectionSource::Id(
+        (
+            None,
+            runtime::OperationSource::Collection {
+                id: IdOrDefault::Id(collection_id),
+                ..
+            },
+        ) => OperationSource::Collection(CollectionSource::Id(
             collection_id,
             config.graphos.platform_api_config()?,
         )),
-        runtime::OperationSource::Introspect => OperationSource::None,
-        runtime::OperationSource::Local { paths } if !paths.is_empty() => {
+        (None, runtime::OperationSource::Introspect) => OperationSource::None,
+        (None, runtime::OperationSource::Local { paths, .. }) if !paths.is_empty() => {
             OperationSource::from(paths)
         }
-        runtime::OperationSource::Manifest { path } => {
+        (None, runtime::OperationSource::Manifest { path, .. }) => {
             OperationSource::from(ManifestSource::LocalHotReload(vec![path]))
         }
-        runtime::OperationSource::Uplink => {
+        (None, runtime::OperationSource::Uplink { .. }) => {
             OperationSource::from(ManifestSource::Uplink(config.graphos.uplink_config()?))
         }
 
         // TODO: Inference requires many different combinations and preferences
         // TODO: We should maybe make this more explicit.
-        runtime::OperationSource::Local { .. } | runtime::OperationSource::Infer => {
+        (None, runtime::OperationSource::Local { .. } | runtime::OperationSource::Infer) => {
             if config.introspection.any_enabled() {
                 warn!("No operations specified, falling back to introspection");
                 OperationSource::None
@@ -101,30 +209,105 @@ async fn main() -> anyhow::Result<()> {
                 anyhow::bail!(ServerError::NoOperations)
             }
         }
-    };
+    }
+    .with_operation_kinds(operation_kinds);
 
     let explorer_graph_ref = config
         .overrides
         .enable_explorer
         .then(|| config.graphos.graph_ref())
         .transpose()?;
+    let tool_namespace = if config.overrides.graph_ref_tool_namespace {
+        Some(config.graphos.tool_namespace()?)
+    } else {
+        None
+    };
 
     Ok(Server::builder()
         .transport(config.transport)
         .schema_source(schema_source)
+        .schema_reload(schema_reload)
         .operation_source(operation_source)
         .endpoint(config.endpoint.into_inner())
         .maybe_explorer_graph_ref(explorer_graph_ref)
         .headers(config.headers)
+        .header_sets(config.header_sets)
+        .null_variables(config.overrides.null_variables)
+        .coerce_scalars(config.overrides.coerce_scalars)
+        .maybe_strip_operation_prefix(config.overrides.strip_operation_prefix)
+        .builtin_scalar_descriptions(config.overrides.builtin_scalar_descriptions)
+        .maybe_constraint_directive(config.overrides.constraint_directive)
+        .maybe_max_tools(config.overrides.max_tools)
+        .duplicate_operations(config.overrides.duplicate_operations)
+        .dedupe_identical_operations(config.overrides.dedupe_identical_operations)
+        .shake_cache(config.overrides.shake_cache)
+        .operation_constants(config.overrides.operation_constants)
+        .maybe_max_operation_depth(config.overrides.max_operation_depth)
+        .maybe_max_operation_variables(config.overrides.max_operation_variables)
+        .check_missing_fields(config.overrides.check_missing_fields)
+        .fragment_expansion(config.overrides.fragment_expansion)
+        .annotate_subgraphs(config.overrides.annotate_subgraphs)
         .execute_introspection(config.introspection.execute.enabled)
+        .execute_default_selections(config.introspection.execute.default_selections)
         .validate_introspection(config.introspection.validate.enabled)
+        .list_operations_introspection(config.introspection.list_operations.enabled)
         .introspect_introspection(config.introspection.introspect.enabled)
         .introspect_minify(config.introspection.introspect.minify)
         .search_minify(config.introspection.search.minify)
+        .search_format(config.introspection.search.format)
+        .search_collapse_redundant_paths(config.introspection.search.collapse_redundant_paths)
+        .search_broaden_empty_results(config.introspection.search.broaden_empty_results)
+        .search_index_subscriptions(config.introspection.search.index_subscriptions)
+        .search_index_directives(config.introspection.search.index_directives)
+        .search_index_argument_names(config.introspection.search.index_argument_names)
+        .search_max_visited_paths_per_type(config.introspection.search.max_visited_paths_per_type)
         .search_introspection(config.introspection.search.enabled)
         .mutation_mode(config.overrides.mutation_mode)
+        .maybe_max_mutation_mode(config.overrides.max_mutation_mode)
         .disable_type_description(config.overrides.disable_type_description)
         .disable_schema_description(config.overrides.disable_schema_description)
+        .return_type_description(config.overrides.return_type_description)
+        .include_root_schema_description(config.overrides.include_root_schema_description)
+        .variables_as_object(config.overrides.variables_as_object)
+        .token_estimator(config.logging.token_estimator)
+        .maybe_tool_size_warn_threshold(config.logging.tool_size_warn_threshold)
+        .maybe_max_input_definitions(config.overrides.max_input_definitions)
+        .describe_variables(config.overrides.describe_variables)
+        .describe_graphql_types(config.overrides.describe_graphql_types)
+        .property_titles(config.overrides.property_titles)
+        .variable_summary(config.overrides.variable_summary)
+        .always_include_fields(config.overrides.always_include_fields)
+        .confirm_destructive_operations(config.overrides.confirm_destructive_operations)
+        .split_list_strings(config.overrides.split_list_strings)
+        .sanitize_errors(config.overrides.sanitize_errors)
+        .output_validation(config.overrides.output_validation)
+        .maybe_fallback_description(config.overrides.fallback_description)
+        .definitions_keyword(config.overrides.definitions_keyword)
+        .maybe_operation_loading_threads(config.overrides.operation_loading_threads)
+        .maybe_schema_description_note(config.overrides.schema_description_note)
+        .disable_read_only_heuristic(config.overrides.disable_read_only_heuristic)
+        .nullable_style(config.overrides.nullable_style)
+        .claim_variables(config.overrides.claim_variables)
+        .shared_fragments(shared_fragments)
+        .strict_operations(config.overrides.strict_operations)
+        .single_operation_documents(config.overrides.single_operation_documents)
+        .result_projection(config.overrides.result_projection)
+        .cost_estimates(config.overrides.cost_estimates)
+        .include_operation_source(config.overrides.include_operation_source)
+        .include_examples(config.overrides.include_examples)
+        .tag_categories(config.overrides.tag_categories)
+        .tool_concurrency(config.overrides.tool_concurrency)
+        .maybe_operation_timeout(config.overrides.operation_timeout)
+        .partial_results_on_timeout(config.overrides.partial_results_on_timeout)
+        .graphql_errors(config.overrides.graphql_errors)
+        .maybe_strip_nulls(config.overrides.strip_nulls)
+        .report_coercions(config.overrides.report_coercions)
+        .maybe_websocket_endpoint(config.overrides.websocket_endpoint)
+        .error_codes(config.overrides.error_codes)
+        .maybe_signing(config.signing)
+        .maybe_builtin_tool_prefix(config.overrides.builtin_tool_prefix)
+        .maybe_tool_namespace(tool_namespace)
+        .maybe_tools_page_size(config.overrides.tools_page_size)
         .custom_scalar_map(
             config
                 .custom_scalars
@@ -133,7 +316,19 @@ async fn main() -> anyhow::Result<()> {
         )
         .search_leaf_depth(config.introspection.search.leaf_depth)
         .index_memory_bytes(config.introspection.search.index_memory_bytes)
+        .maybe_search_max_description_chars(config.introspection.search.max_description_chars)
         .health_check(config.health_check)
+        .meter(config.meter)
+        .circuit_breaker(config.circuit_breaker)
+        .progress(config.progress)
+        .result_cache(config.result_cache)
+        .rest_tools(config.rest_tools)
+        .admin(config.admin)
+        .auxiliary(config.auxiliary)
+        .maybe_offline_cache(offline_cache.filter(|_| !offline))
+        .maybe_auth_loader(auth_loader)
+        .feature_flags(config.feature_flags)
+        .maybe_feature_flags_loader(feature_flags_loader)
         .build()
         .start()
         .await?)
@@ -0,0 +1,218 @@
+//! Meter module for Apollo MCP Server
+//!
+//! Tracks the latency of each tool as an exponential moving average (EMA), which can be used to
+//! derive an adaptive timeout for tool calls. The averages are exposed via an HTTP metrics endpoint.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+/// Configuration options for adaptive tool timeouts.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct AdaptiveTimeoutConfig {
+    /// Set to true to time out tool calls based on their average latency
+    pub enabled: bool,
+
+    /// The multiple of the average latency after which a tool call times out (default: 3.0)
+    pub factor: f64,
+
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    #[schemars(with = "String")]
+    /// The smallest adaptive timeout (default: 1s)
+    pub min: Duration,
+
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    #[schemars(with = "String")]
+    /// The largest adaptive timeout (default: 30s)
+    pub max: Duration,
+}
+
+impl Default for AdaptiveTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            factor: 3.0,
+            min: Duration::from_secs(1),
+            max: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Configuration options for the meter component.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct MeterConfig {
+    /// Set to true to track tool latencies
+    pub enabled: bool,
+
+    /// Optionally set a custom metrics path
+    /// Defaults to /metrics
+    pub path: String,
+
+    /// The weight given to the newest latency sample, between 0 and 1 (default: 0.2)
+    pub smoothing: f64,
+
+    /// Adaptive timeout configuration
+    pub adaptive_timeout: AdaptiveTimeoutConfig,
+}
+
+impl Default for MeterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "/metrics".to_string(),
+            smoothing: 0.2,
+            adaptive_timeout: Default::default(),
+        }
+    }
+}
+
+/// Latency metrics for a single tool
+#[derive(Clone, Debug, Serialize)]
+pub struct ToolLatency {
+    /// The exponential moving average of the tool latency in milliseconds
+    pub ema_ms: f64,
+
+    /// The number of calls observed
+    pub count: u64,
+}
+
+#[derive(Clone)]
+pub struct Meter {
+    config: MeterConfig,
+    latencies: Arc<RwLock<HashMap<String, ToolLatency>>>,
+}
+
+impl Meter {
+    pub fn new(config: MeterConfig) -> Self {
+        Self {
+            config,
+            latencies: Default::default(),
+        }
+    }
+
+    pub fn config(&self) -> &MeterConfig {
+        &self.config
+    }
+
+    /// Record the latency of a call to a tool
+    pub async fn record(&self, tool: &str, latency: Duration) {
+        let sample = latency.as_secs_f64() * 1000.0;
+        let smoothing = self.config.smoothing.clamp(0.0, 1.0);
+        let mut latencies = self.latencies.write().await;
+        match latencies.get_mut(tool) {
+            Some(entry) => {
+                entry.ema_ms = smoothing * sample + (1.0 - smoothing) * entry.ema_ms;
+                entry.count += 1;
+            }
+            None => {
+                latencies.insert(
+                    tool.to_string(),
+                    ToolLatency {
+                        ema_ms: sample,
+                        count: 1,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Get the average latency of a tool, if any calls have been observed
+    pub async fn average(&self, tool: &str) -> Option<Duration> {
+        self.latencies
+            .read()
+            .await
+            .get(tool)
+            .map(|entry| Duration::from_secs_f64(entry.ema_ms / 1000.0))
+    }
+
+    /// Derive a timeout for a tool from its average latency, if adaptive timeouts are enabled
+    pub async fn adaptive_timeout(&self, tool: &str) -> Option<Duration> {
+        let config = &self.config.adaptive_timeout;
+        if !config.enabled {
+            return None;
+        }
+        let average = self.average(tool).await?;
+        let timeout = Duration::try_from_secs_f64(average.as_secs_f64() * config.factor)
+            .unwrap_or(config.max);
+        Some(timeout.clamp(config.min, config.max.max(config.min)))
+    }
+
+    /// Get a snapshot of the latencies of all tools
+    pub async fn latencies(&self) -> HashMap<String, ToolLatency> {
+        self.latencies.read().await.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn ema_converges_to_latency() {
+        let meter = Meter::new(MeterConfig {
+            enabled: true,
+            ..Default::default()
+        });
+
+        meter.record("tool", Duration::from_millis(1000)).await;
+        for _ in 0..50 {
+            meter.record("tool", Duration::from_millis(100)).await;
+        }
+
+        let average = meter.average("tool").await.unwrap();
+        assert!(average.abs_diff(Duration::from_millis(100)) < Duration::from_millis(1));
+        assert_eq!(meter.latencies().await.get("tool").unwrap().count, 51);
+        assert!(meter.average("other").await.is_none());
+    }
+
+    #[tokio::test]
+    async fn adaptive_timeout_is_clamped() {
+        let meter = Meter::new(MeterConfig {
+            enabled: true,
+            adaptive_timeout: AdaptiveTimeoutConfig {
+                enabled: true,
+                factor: 2.0,
+                min: Duration::from_millis(500),
+                max: Duration::from_secs(5),
+            },
+            ..Default::default()
+        });
+
+        meter.record("fast", Duration::from_millis(10)).await;
+        meter.record("medium", Duration::from_secs(1)).await;
+        meter.record("slow", Duration::from_secs(60)).await;
+
+        assert_eq!(
+            meter.adaptive_timeout("fast").await,
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            meter.adaptive_timeout("medium").await,
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            meter.adaptive_timeout("slow").await,
+            Some(Duration::from_secs(5))
+        );
+        assert_eq!(meter.adaptive_timeout("unknown").await, None);
+    }
+
+    #[tokio::test]
+    async fn adaptive_timeout_disabled() {
+        let meter = Meter::new(MeterConfig {
+            enabled: true,
+            ..Default::default()
+        });
+
+        meter.record("tool", Duration::from_millis(10)).await;
+        assert_eq!(meter.adaptive_timeout("tool").await, None);
+    }
+}
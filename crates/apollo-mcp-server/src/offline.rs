@@ -0,0 +1,220 @@
+//! Offline module for Apollo MCP Server
+//!
+//! Caches the schema and operations the server loads on disk, so that it can later run against
+//! them without contacting uplink or the GraphOS Platform API.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use apollo_mcp_registry::uplink::schema::SchemaSource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::ServerError;
+use crate::operations::{OperationSource, RawOperation};
+
+/// The name of the cached schema file
+const SCHEMA_FILE_NAME: &str = "schema.graphql";
+
+/// The name of the cached operations file
+const OPERATIONS_FILE_NAME: &str = "operations.json";
+
+/// Configuration options for offline mode.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct OfflineConfig {
+    /// Set to true to run from the cache only, without contacting uplink or the Platform API
+    pub enabled: bool,
+
+    /// The directory to cache the schema and operations in. When offline mode is disabled, the
+    /// schema and operations are written here every time they are loaded.
+    pub cache_dir: Option<PathBuf>,
+}
+
+/// A schema and operations cached on disk
+#[derive(Clone, Debug)]
+pub struct OfflineCache {
+    dir: PathBuf,
+}
+
+impl OfflineCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The path of the cached schema
+    pub fn schema_path(&self) -> PathBuf {
+        self.dir.join(SCHEMA_FILE_NAME)
+    }
+
+    /// The path of the cached operations
+    pub fn operations_path(&self) -> PathBuf {
+        self.dir.join(OPERATIONS_FILE_NAME)
+    }
+
+    /// Ensure both the schema and operations have been cached
+    #[allow(clippy::result_large_err)]
+    pub fn check(&self) -> Result<(), ServerError> {
+        for path in [self.schema_path(), self.operations_path()] {
+            if !path.exists() {
+                return Err(ServerError::OfflineCacheMissing(path));
+            }
+        }
+        Ok(())
+    }
+
+    /// Cache the schema SDL
+    pub fn store_schema(&self, sdl: &str) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.schema_path(), sdl)
+    }
+
+    /// Cache the operations, replacing any previously cached operations. Everything loaded with
+    /// each operation is cached, such as the persisted query ID and the headers and variables
+    /// saved in a collection.
+    pub fn store_operations(&self, operations: &[RawOperation]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.operations_path(),
+            serde_json::to_string_pretty(operations)?,
+        )
+    }
+
+    /// Read the cached operations
+    pub fn load_operations(&self) -> io::Result<Vec<RawOperation>> {
+        Ok(serde_json::from_str(&fs::read_to_string(
+            self.operations_path(),
+        )?)?)
+    }
+
+    /// The directory the cache is stored in
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// The source of the cached schema, which is not watched for changes
+    pub fn schema_source(&self) -> SchemaSource {
+        SchemaSource::File {
+            path: self.schema_path(),
+            watch: false,
+        }
+    }
+
+    /// The source of the cached operations
+    pub fn operation_source(&self) -> OperationSource {
+        OperationSource::Offline(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use apollo_mcp_registry::platform_api::operation_collections::collection_poller::OperationData;
+    use apollo_mcp_registry::uplink::persisted_queries::{ExecutionPolicy, ManifestOperation};
+
+    use super::*;
+    use crate::server::Transport;
+    use crate::server::test::{Client, free_port, server};
+
+    /// A cache in a new temporary directory
+    fn temp_cache(name: &str) -> OfflineCache {
+        let dir = std::env::temp_dir().join(format!("offline-cache-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        OfflineCache::new(dir)
+    }
+
+    #[test]
+    fn cached_schema_and_operations_are_required() {
+        let cache = temp_cache("required");
+        assert!(matches!(
+            cache.check(),
+            Err(ServerError::OfflineCacheMissing(path)) if path == cache.dir().join(SCHEMA_FILE_NAME)
+        ));
+
+        cache.store_schema("type Query { id: ID }").unwrap();
+        assert!(matches!(
+            cache.check(),
+            Err(ServerError::OfflineCacheMissing(path)) if path == cache.dir().join(OPERATIONS_FILE_NAME)
+        ));
+
+        cache
+            .store_operations(&[RawOperation::from(("query GetId { id }".to_string(), None))])
+            .unwrap();
+        assert!(cache.check().is_ok());
+
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[test]
+    fn cached_operations_keep_what_they_were_loaded_with() {
+        let cache = temp_cache("round-trip");
+        let operations = vec![
+            RawOperation::from(ManifestOperation {
+                id: "get-id".to_string(),
+                body: "query GetId { id }".to_string(),
+                client_name: None,
+                policy: ExecutionPolicy {
+                    timeout: Some(Duration::from_secs(5)),
+                    max_response_bytes: Some(1024),
+                },
+            }),
+            RawOperation::try_from(&OperationData::new(
+                "1".to_string(),
+                "2025-01-01T00:00:00Z".to_string(),
+                "query GetName($id: ID) { name(id: $id) }".to_string(),
+                Some(vec![
+                    ("x-client".to_string(), "mcp".to_string()),
+                    ("x-feature".to_string(), "a".to_string()),
+                    ("x-feature".to_string(), "b".to_string()),
+                ]),
+                Some(r#"{ "id": "1" }"#.to_string()),
+            ))
+            .unwrap()
+            .with_description(Some("Get the name".to_string())),
+        ];
+
+        cache.store_operations(&operations).unwrap();
+        let cached = cache.load_operations().unwrap();
+
+        assert_eq!(
+            serde_json::to_value(&cached).unwrap(),
+            serde_json::to_value(&operations).unwrap()
+        );
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn server_starts_offline_from_the_cache() {
+        let cache = temp_cache("start");
+        cache.store_schema("type Query { id: ID }").unwrap();
+        cache
+            .store_operations(&[RawOperation::from((
+                "get-id".to_string(),
+                "query GetId { id }".to_string(),
+            ))])
+            .unwrap();
+        cache.check().unwrap();
+
+        let port = free_port();
+        let transport = Transport::StreamableHttp {
+            auth: None,
+            address: "127.0.0.1".parse().unwrap(),
+            port,
+            path: "/mcp".to_string(),
+            max_request_body_bytes: 1024 * 1024,
+        };
+        tokio::spawn(server(transport, cache.schema_source(), cache.operation_source()).start());
+
+        assert_eq!(
+            Client::connect(&format!("http://127.0.0.1:{port}/mcp"))
+                .await
+                .list_tools()
+                .await,
+            vec!["GetId".to_string()]
+        );
+        fs::remove_dir_all(cache.dir()).unwrap();
+    }
+}
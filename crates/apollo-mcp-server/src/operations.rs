@@ -1,14 +1,17 @@
 use crate::custom_scalar_map::CustomScalarMap;
 use crate::errors::{McpError, OperationError};
 use crate::event::Event;
-use crate::graphql::{self, OperationDetails};
-use crate::schema_tree_shake::{DepthLimit, SchemaTreeShaker};
-use apollo_compiler::ast::{Document, OperationType, Selection};
+use crate::graphql::{self, ESTIMATE_ARGUMENT_NAME, OperationDetails};
+use crate::offline::OfflineCache;
+use crate::projection::PROJECT_ARGUMENT_NAME;
+use crate::schema_tree_shake::{DepthLimit, SchemaTreeShaker, ShakeCache};
+use crate::token_estimator::TokenEstimator;
+use apollo_compiler::ast::{DirectiveList, Document, OperationType, Selection};
 use apollo_compiler::schema::ExtendedType;
 use apollo_compiler::validation::Valid;
 use apollo_compiler::{
     Name, Node, Schema as GraphqlSchema,
-    ast::{Definition, OperationDefinition, Type},
+    ast::{Definition, FragmentDefinition, OperationDefinition, Type},
     parser::Parser,
 };
 use apollo_mcp_registry::files;
@@ -17,9 +20,11 @@ use apollo_mcp_registry::platform_api::operation_collections::collection_poller:
 };
 use apollo_mcp_registry::platform_api::operation_collections::error::CollectionError;
 use apollo_mcp_registry::platform_api::operation_collections::event::CollectionEvent;
-use apollo_mcp_registry::uplink::persisted_queries::ManifestSource;
 use apollo_mcp_registry::uplink::persisted_queries::event::Event as ManifestEvent;
-use futures::{Stream, StreamExt};
+use apollo_mcp_registry::uplink::persisted_queries::{
+    ExecutionPolicy, ManifestOperation, ManifestSource,
+};
+use futures::{Stream, StreamExt, stream::BoxStream};
 use regex::Regex;
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use rmcp::model::{ErrorCode, ToolAnnotations};
@@ -34,15 +39,39 @@ use rmcp::{
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
-use tracing::{debug, info, warn};
+use std::time::Duration;
+use tracing::{debug, error, info, warn};
+
+mod computed_default;
+mod fragments;
+mod return_types;
+
+use computed_default::ComputedDefault;
+
+pub use fragments::SharedFragments;
+pub use return_types::ReturnTypeIndex;
 
 const OPERATION_DOCUMENT_EXTENSION: &str = "graphql";
 
+/// The extension of the markdown files describing the operations of the files they are named
+/// after, such as `GetUser.md` for `GetUser.graphql`
+const DESCRIPTION_FILE_EXTENSION: &str = "md";
+
+/// The name of the single argument holding all variables when variables are accepted as an object
+const VARIABLES_ARGUMENT_NAME: &str = "variables";
+
+/// The name of the argument confirming a call to a destructive operation
+pub const CONFIRM_ARGUMENT_NAME: &str = "__confirm";
+
+/// The input schema keyword summarizing the required and optional variables of an operation
+pub const VARIABLE_SUMMARY_KEYWORD: &str = "x-variables";
+
 /// The source of the operations exposed as MCP tools
 #[derive(Clone)]
 pub enum OperationSource {
@@ -55,12 +84,18 @@ pub enum OperationSource {
     /// Operation collection
     Collection(CollectionSource),
 
+    /// Operations from another source, exposing only those of the given types
+    Filtered(Box<OperationSource>, Vec<OperationKind>),
+
+    /// Operations cached on disk for offline mode
+    Offline(OfflineCache),
+
     /// No operations provided
     None,
 }
 
 impl OperationSource {
-    pub async fn into_stream(self) -> impl Stream<Item = Event> {
+    pub async fn into_stream(self) -> BoxStream<'static, Event> {
         match self {
             OperationSource::Files(paths) => Self::stream_file_changes(paths).boxed(),
             OperationSource::Manifest(manifest_source) => manifest_source
@@ -75,30 +110,75 @@ impl OperationSource {
                 .boxed(),
             OperationSource::Collection(collection_source) => collection_source
                 .into_stream()
-                .map(|event| match event {
-                    CollectionEvent::UpdateOperationCollection(operations) => {
-                        match operations
-                            .iter()
-                            .map(RawOperation::try_from)
-                            .collect::<Result<Vec<_>, _>>()
-                        {
-                            Ok(operations) => Event::OperationsUpdated(operations),
-                            Err(e) => Event::CollectionError(e),
-                        }
-                    }
-                    CollectionEvent::CollectionError(error) => Event::CollectionError(error),
-                })
+                .map(Self::collection_event)
+                .boxed(),
+            OperationSource::Filtered(source, operation_kinds) => Box::pin(source.into_stream())
+                .await
+                .map(move |event| Self::retain_operation_kinds(event, &operation_kinds))
                 .boxed(),
+            OperationSource::Offline(cache) => futures::stream::once(async move {
+                match cache.load_operations() {
+                    Ok(operations) => Event::OperationsUpdated(operations),
+                    Err(e) => Event::OperationError(
+                        e,
+                        cache.operations_path().to_str().map(|s| s.to_string()),
+                    ),
+                }
+            })
+            .boxed(),
             OperationSource::None => {
                 futures::stream::once(async { Event::OperationsUpdated(vec![]) }).boxed()
             }
         }
     }
 
+    fn collection_event(event: CollectionEvent) -> Event {
+        match event {
+            CollectionEvent::UpdateOperationCollection(operations) => {
+                match operations
+                    .iter()
+                    .map(RawOperation::try_from)
+                    .collect::<Result<Vec<_>, _>>()
+                {
+                    Ok(operations) => Event::OperationsUpdated(operations),
+                    Err(e) => Event::CollectionError(e),
+                }
+            }
+            CollectionEvent::CollectionError(error) => Event::CollectionError(error),
+        }
+    }
+
+    /// Remove updated operations which are not of the given types. Operations which cannot be
+    /// parsed are kept, so that their errors are still reported.
+    fn retain_operation_kinds(event: Event, operation_kinds: &[OperationKind]) -> Event {
+        match event {
+            Event::OperationsUpdated(mut operations) => {
+                operations.retain(|operation| {
+                    operation
+                        .operation_kind()
+                        .is_none_or(|operation_kind| operation_kinds.contains(&operation_kind))
+                });
+                Event::OperationsUpdated(operations)
+            }
+            event => event,
+        }
+    }
+
+    /// Expose only the operations of the given types, or all of them if none are given
+    pub fn with_operation_kinds(self, operation_kinds: Vec<OperationKind>) -> Self {
+        if operation_kinds.is_empty() {
+            self
+        } else {
+            OperationSource::Filtered(Box::new(self), operation_kinds)
+        }
+    }
+
     fn stream_file_changes(paths: Vec<PathBuf>) -> impl Stream<Item = Event> {
+        // Operations are kept in the order of their paths, so that operations from paths listed
+        // first take precedence over duplicates from later paths
         let path_count = paths.len();
-        let state = Arc::new(Mutex::new(HashMap::<PathBuf, Vec<RawOperation>>::new()));
-        futures::stream::select_all(paths.into_iter().map(|path| {
+        let state = Arc::new(Mutex::new(BTreeMap::<usize, Vec<RawOperation>>::new()));
+        futures::stream::select_all(paths.into_iter().enumerate().map(|(index, path)| {
             let state = Arc::clone(&state);
             files::watch(path.as_ref())
                 .filter_map(move |_| {
@@ -109,8 +189,10 @@ impl OperationSource {
                         if path.is_dir() {
                             // Handle a directory
                             if let Ok(entries) = fs::read_dir(&path) {
-                                for entry in entries.flatten() {
-                                    let entry_path = entry.path();
+                                let mut entry_paths: Vec<PathBuf> =
+                                    entries.flatten().map(|entry| entry.path()).collect();
+                                entry_paths.sort();
+                                for entry_path in entry_paths {
                                     if entry_path.extension().and_then(|e| e.to_str())
                                         == Some(OPERATION_DOCUMENT_EXTENSION)
                                     {
@@ -120,10 +202,17 @@ impl OperationSource {
                                                 // It likely means a new file was created in an editor,
                                                 // but the operation hasn't been written yet.
                                                 if !content.trim().is_empty() {
-                                                    operations.push(RawOperation::from((
-                                                        content,
-                                                        entry_path.to_str().map(|s| s.to_string()),
-                                                    )));
+                                                    operations.push(
+                                                        RawOperation::from((
+                                                            content,
+                                                            entry_path
+                                                                .to_str()
+                                                                .map(|s| s.to_string()),
+                                                        ))
+                                                        .with_description(description_file(
+                                                            &entry_path,
+                                                        )),
+                                                    );
                                                 }
                                             }
                                             Err(e) => {
@@ -141,10 +230,13 @@ impl OperationSource {
                             match fs::read_to_string(&path) {
                                 Ok(content) => {
                                     if !content.trim().is_empty() {
-                                        operations.push(RawOperation::from((
-                                            content,
-                                            path.to_str().map(|s| s.to_string()),
-                                        )));
+                                        operations.push(
+                                            RawOperation::from((
+                                                content,
+                                                path.to_str().map(|s| s.to_string()),
+                                            ))
+                                            .with_description(description_file(&path)),
+                                        );
                                     } else {
                                         warn!(?path, "Empty operation file");
                                     }
@@ -159,7 +251,7 @@ impl OperationSource {
                         }
                         match state.lock() {
                             Ok(mut state) => {
-                                state.insert(path.clone(), operations);
+                                state.insert(index, operations);
                                 // All paths send an initial event on startup. To avoid repeated
                                 // operation events on startup, wait until all paths have been
                                 // loaded, then send a single event with the operations for all
@@ -197,7 +289,29 @@ impl From<Vec<PathBuf>> for OperationSource {
     }
 }
 
-#[derive(Clone, Default, Debug, Deserialize, Serialize, PartialEq, Copy, JsonSchema)]
+/// The types of operations an operation source can be limited to exposing
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OperationKind {
+    Query,
+    Mutation,
+    Subscription,
+}
+
+impl From<OperationType> for OperationKind {
+    fn from(operation_type: OperationType) -> Self {
+        match operation_type {
+            OperationType::Query => OperationKind::Query,
+            OperationType::Mutation => OperationKind::Mutation,
+            OperationType::Subscription => OperationKind::Subscription,
+        }
+    }
+}
+
+/// Mutation access levels, ordered from least to most permissive
+#[derive(
+    Clone, Default, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Copy, JsonSchema,
+)]
 #[serde(rename_all = "snake_case")]
 pub enum MutationMode {
     /// Don't allow any mutations
@@ -209,6 +323,604 @@ pub enum MutationMode {
     All,
 }
 
+/// How to send variables whose value is `null`
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NullVariables {
+    /// Send them as explicit nulls
+    #[default]
+    Keep,
+    /// Omit them, as if they were not provided
+    Omit,
+}
+
+/// How nullable values are represented in JSON schemas. Schemas referring to a definition are
+/// always represented with `oneOf`, since other keywords alongside a `$ref` are ignored.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NullableStyle {
+    /// `oneOf` the schema or a `null` schema
+    #[default]
+    OneOf,
+    /// A `type` array including `null`, such as `type: ["string", "null"]`
+    TypeArray,
+    /// The OpenAPI `nullable: true` keyword
+    Nullable,
+}
+
+impl NullableStyle {
+    /// Make a schema accept `null`
+    fn apply(self, schema: Schema) -> Schema {
+        let one_of = |schema| {
+            schema_factory(
+                None,
+                None,
+                None,
+                None,
+                Some(SubschemaValidation {
+                    one_of: Some(vec![
+                        schema,
+                        Schema::Object(SchemaObject {
+                            instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::Null))),
+                            ..Default::default()
+                        }),
+                    ]),
+                    ..Default::default()
+                }),
+                None,
+            )
+        };
+        let mut object = match schema {
+            Schema::Object(object)
+                if self != NullableStyle::OneOf && object.reference.is_none() =>
+            {
+                object
+            }
+            schema => return one_of(schema),
+        };
+        // Enumerated values must include null for null to be accepted
+        if let Some(enum_values) = object.enum_values.as_mut() {
+            enum_values.push(Value::Null);
+        }
+        match self {
+            NullableStyle::TypeArray => {
+                object.instance_type = match object.instance_type {
+                    Some(SingleOrVec::Single(instance_type)) => {
+                        Some(SingleOrVec::Vec(vec![*instance_type, InstanceType::Null]))
+                    }
+                    Some(SingleOrVec::Vec(mut instance_types)) => {
+                        instance_types.push(InstanceType::Null);
+                        Some(SingleOrVec::Vec(instance_types))
+                    }
+                    // A schema without a type already accepts null
+                    None => None,
+                };
+            }
+            NullableStyle::Nullable => {
+                object
+                    .extensions
+                    .insert("nullable".to_string(), Value::Bool(true));
+            }
+            NullableStyle::OneOf => {}
+        }
+        Schema::Object(object)
+    }
+}
+
+/// The keyword under which JSON schemas define shared types
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, JsonSchema)]
+pub enum DefinitionsKeyword {
+    /// `definitions`, as in Draft-07
+    #[default]
+    #[serde(rename = "definitions")]
+    Definitions,
+    /// `$defs`, as in Draft 2020-12
+    #[serde(rename = "$defs")]
+    Defs,
+}
+
+impl DefinitionsKeyword {
+    fn keyword(self) -> &'static str {
+        match self {
+            DefinitionsKeyword::Definitions => "definitions",
+            DefinitionsKeyword::Defs => "$defs",
+        }
+    }
+
+    /// Move the definitions of a schema generated with `definitions` under this keyword, along
+    /// with the references to them
+    fn apply(self, schema: &mut Value) {
+        if self == DefinitionsKeyword::Definitions {
+            return;
+        }
+        if let Some(object) = schema.as_object_mut()
+            && let Some(definitions) = object.remove("definitions")
+        {
+            object.insert(self.keyword().to_string(), definitions);
+        }
+        self.update_references(schema);
+    }
+
+    fn update_references(self, schema: &mut Value) {
+        match schema {
+            Value::Object(object) => {
+                for (key, value) in object.iter_mut() {
+                    match value {
+                        Value::String(reference) if key == "$ref" => {
+                            if let Some(name) = reference.strip_prefix("#/definitions/") {
+                                *reference = format!("#/{}/{name}", self.keyword());
+                            }
+                        }
+                        value => self.update_references(value),
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.update_references(value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// How to return GraphQL responses containing errors alongside data
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphQLErrors {
+    /// Return the data as a successful result, with the errors attached as a warning
+    #[default]
+    Warn,
+    /// Fail the tool call if the response contains any errors
+    Fail,
+}
+
+/// What to do when the data returned for an operation doesn't match its output schema
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputValidation {
+    /// Don't check the data
+    #[default]
+    Off,
+    /// Log a warning, and add it to the tool result alongside the data
+    Warn,
+    /// Fail the tool call
+    Fail,
+}
+
+/// Remove null and empty fields from responses, to save tokens
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
+pub struct StripNulls {
+    /// The deepest level of the response data to remove fields from (default: 32)
+    #[serde(default = "StripNulls::default_max_depth")]
+    pub max_depth: usize,
+
+    /// Keep fields which are null because of an error, and empty lists
+    #[serde(default)]
+    pub strict: bool,
+}
+
+impl StripNulls {
+    fn default_max_depth() -> usize {
+        32
+    }
+}
+
+/// Limits on the expansion of the fragments spread in an operation, guarding against recursive or
+/// repeatedly spread fragments
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct FragmentExpansion {
+    /// The deepest nesting of fragments expanded within a selection set (default: 32)
+    pub max_depth: usize,
+
+    /// The most selections, including fields and fragments, expanded across an operation
+    /// (default: 10000)
+    pub max_selections: usize,
+}
+
+impl Default for FragmentExpansion {
+    fn default() -> Self {
+        Self {
+            max_depth: 32,
+            max_selections: 10_000,
+        }
+    }
+}
+
+/// The phrasing of the description of the type returned by an operation's root field
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct ReturnTypeDescription {
+    /// The description, in which `{optional}` is replaced with `optional` or `required`,
+    /// `{list}` with `list` or `single`, and `{type}` with the name of the type
+    pub template: String,
+
+    /// The text for a nullable type
+    pub optional: String,
+
+    /// The text for a non-null type
+    pub required: String,
+
+    /// The text for a list type
+    pub list: String,
+
+    /// The text for a type which is not a list
+    pub single: String,
+}
+
+impl Default for ReturnTypeDescription {
+    fn default() -> Self {
+        Self {
+            template: "The returned value {optional}{list} `{type}`".to_string(),
+            optional: "is optional and ".to_string(),
+            required: String::new(),
+            list: "is an array of type".to_string(),
+            single: "has type".to_string(),
+        }
+    }
+}
+
+impl ReturnTypeDescription {
+    /// Describe a return type
+    fn describe(&self, ty: &Type) -> String {
+        let optional = if ty.is_non_null() {
+            &self.required
+        } else {
+            &self.optional
+        };
+        let list = if ty.is_list() {
+            &self.list
+        } else {
+            &self.single
+        };
+        self.template
+            .replace("{optional}", optional)
+            .replace("{list}", list)
+            .replace("{type}", ty.inner_named_type())
+    }
+}
+
+/// A limit on the number of operation tools
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
+pub struct MaxTools {
+    /// The maximum number of operation tools
+    pub limit: usize,
+
+    /// Fail to load the operations when there are too many, instead of dropping the extra ones
+    #[serde(default)]
+    pub strict: bool,
+
+    /// Which operations to keep when dropping the extra ones
+    #[serde(default)]
+    pub order: ToolOrder,
+}
+
+/// The order in which operations are kept when there are too many tools
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolOrder {
+    /// Keep the first operations by tool name
+    #[default]
+    Name,
+    /// Keep the operations with the highest `# @priority(n)` comment first, then by tool name.
+    /// Operations without the comment have a priority of 0
+    Priority,
+}
+
+/// How to handle operations with the same name from more than one source, such as two operation
+/// files or directories
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateOperations {
+    /// Keep the operation from the source listed first, warning about the shadowed ones
+    #[default]
+    PreferFirst,
+    /// Fail to load the operations
+    Error,
+}
+
+/// An MCP error code which upstream GraphQL errors can be mapped to
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum McpErrorCode {
+    /// The request was not valid, such as when the caller is not authorized (-32600)
+    InvalidRequest,
+    /// The tool arguments were not valid (-32602)
+    InvalidParams,
+    /// The tool failed (-32603)
+    InternalError,
+    /// The method was not found (-32601)
+    MethodNotFound,
+    /// The requested resource was not found (-32002)
+    ResourceNotFound,
+    /// The request could not be parsed (-32700)
+    ParseError,
+}
+
+impl From<McpErrorCode> for ErrorCode {
+    fn from(code: McpErrorCode) -> Self {
+        match code {
+            McpErrorCode::InvalidRequest => ErrorCode::INVALID_REQUEST,
+            McpErrorCode::InvalidParams => ErrorCode::INVALID_PARAMS,
+            McpErrorCode::InternalError => ErrorCode::INTERNAL_ERROR,
+            McpErrorCode::MethodNotFound => ErrorCode::METHOD_NOT_FOUND,
+            McpErrorCode::ResourceNotFound => ErrorCode::RESOURCE_NOT_FOUND,
+            McpErrorCode::ParseError => ErrorCode::PARSE_ERROR,
+        }
+    }
+}
+
+/// The MCP error codes to fail tool calls with, by the `extensions.code` of the upstream GraphQL
+/// errors which failed them
+///
+/// Configured codes take precedence over the default mapping of common Apollo error codes.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, JsonSchema)]
+#[serde(transparent)]
+pub struct ErrorCodes(HashMap<String, McpErrorCode>);
+
+impl ErrorCodes {
+    /// The MCP error code an upstream GraphQL error code maps to, if any
+    pub fn get(&self, code: &str) -> Option<ErrorCode> {
+        self.0
+            .get(code)
+            .copied()
+            .or(match code {
+                "UNAUTHENTICATED" | "FORBIDDEN" => Some(McpErrorCode::InvalidRequest),
+                "BAD_USER_INPUT" | "GRAPHQL_PARSE_FAILED" | "GRAPHQL_VALIDATION_FAILED" => {
+                    Some(McpErrorCode::InvalidParams)
+                }
+                "PERSISTED_QUERY_NOT_FOUND" => Some(McpErrorCode::ResourceNotFound),
+                "INTERNAL_SERVER_ERROR" => Some(McpErrorCode::InternalError),
+                _ => None,
+            })
+            .map(ErrorCode::from)
+    }
+}
+
+/// A built-in scalar type or an enum type which string variable values can be coerced into
+#[derive(Clone, Debug, Serialize, PartialEq)]
+enum CoercedScalar {
+    Int,
+    Float,
+    Boolean,
+    /// An enum type, whose values are matched regardless of case
+    Enum(Vec<String>),
+}
+
+impl CoercedScalar {
+    fn for_type(schema: &apollo_compiler::Schema, name: &str) -> Option<Self> {
+        match name {
+            "Int" => Some(Self::Int),
+            "Float" => Some(Self::Float),
+            "Boolean" => Some(Self::Boolean),
+            _ => match schema.types.get(name) {
+                Some(ExtendedType::Enum(enum_type)) => Some(Self::Enum(
+                    enum_type.values.keys().map(ToString::to_string).collect(),
+                )),
+                _ => None,
+            },
+        }
+    }
+
+    /// Parse a string into the scalar's JSON type, leaving values of other types unchanged.
+    /// Values in lists are coerced individually. Strings matching no value of an enum are left
+    /// for the GraphQL server to reject.
+    fn coerce(&self, value: Value) -> Result<Value, Value> {
+        match value {
+            Value::String(text) => {
+                let trimmed = text.trim();
+                match self {
+                    Self::Int => trimmed.parse::<i32>().ok().map(Value::from),
+                    Self::Float => trimmed
+                        .parse::<f64>()
+                        .ok()
+                        .and_then(serde_json::Number::from_f64)
+                        .map(Value::Number),
+                    Self::Boolean => trimmed.parse::<bool>().ok().map(Value::Bool),
+                    Self::Enum(values) => Some(Value::from(
+                        values
+                            .iter()
+                            .find(|value| value.eq_ignore_ascii_case(trimmed))
+                            .map_or(text.as_str(), String::as_str),
+                    )),
+                }
+                .ok_or(Value::String(text))
+            }
+            Value::Array(values) => values
+                .into_iter()
+                .map(|value| self.coerce(value))
+                .collect::<Result<_, _>>()
+                .map(Value::Array),
+            value => Ok(value),
+        }
+    }
+}
+
+/// Maps a claim of the validated JWT to an operation variable
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, JsonSchema)]
+pub struct ClaimVariable {
+    /// The name of the claim
+    pub claim: String,
+
+    /// The name of the variable to set to the claim's value
+    pub variable: String,
+
+    /// Leave the variable unset, instead of failing the request, when the claim is absent
+    #[serde(default)]
+    pub optional: bool,
+}
+
+/// Options controlling how operations are converted into tools
+#[derive(Clone, Debug, Default)]
+pub struct ToolOptions {
+    /// The mutation access level, unless an operation selects otherwise with a
+    /// `# @mutationMode("none" | "explicit" | "all")` comment
+    pub mutation_mode: MutationMode,
+
+    /// The most permissive mutation access level operations can select for themselves
+    pub max_mutation_mode: Option<MutationMode>,
+
+    /// Disable type descriptions in tool descriptions
+    pub disable_type_description: bool,
+
+    /// Disable schema descriptions in tool descriptions
+    pub disable_schema_description: bool,
+
+    /// The phrasing of the description of each root field's return type
+    pub return_type_description: ReturnTypeDescription,
+
+    /// Include the description of the schema itself at the top of the schema descriptions in
+    /// tool descriptions
+    pub include_root_schema_description: bool,
+
+    /// Accept all variables as a single `variables` object argument instead of one argument each
+    pub variables_as_object: bool,
+
+    /// How to estimate the number of tokens used by each tool
+    pub token_estimator: TokenEstimator,
+
+    /// Log tools whose character count exceeds this at `warn` instead of `info`
+    pub tool_size_warn_threshold: Option<usize>,
+
+    /// The maximum number of distinct type definitions in each tool's input schema
+    pub max_input_definitions: Option<usize>,
+
+    /// Summarize variable descriptions in the tool description
+    pub describe_variables: bool,
+
+    /// Append the GraphQL type of each variable to its description in the input schema
+    pub describe_graphql_types: bool,
+
+    /// Add a title derived from the name of each variable and input field to the input schema
+    pub property_titles: bool,
+
+    /// Add an `x-variables` summary of the required and optional variable names to the input schema
+    /// of each operation tool
+    pub variable_summary: bool,
+
+    /// Fields to always include in the types described with each operation, given as `Type.field`
+    pub always_include_fields: Vec<String>,
+
+    /// Require tools for destructive operations to be called with a `__confirm: true` argument
+    pub confirm_destructive_operations: bool,
+
+    /// Split comma-separated string values of list variables with scalar items into lists
+    pub split_list_strings: bool,
+
+    /// What to do when the data returned for an operation does not match its output schema
+    pub output_validation: OutputValidation,
+
+    /// The description of operations which would otherwise have an empty description, in which
+    /// `{name}` is replaced with the operation name
+    pub fallback_description: Option<String>,
+
+    /// The keyword under which input schemas define shared types
+    pub definitions_keyword: DefinitionsKeyword,
+
+    /// The number of threads converting operations into tools in parallel, defaulting to the number
+    /// of available CPUs
+    pub operation_loading_threads: Option<usize>,
+
+    /// A note added to tool descriptions in place of the schema description when it is disabled
+    pub schema_description_note: Option<String>,
+
+    /// Don't derive the read-only hint of tools from the operation type, leaving it to operations
+    /// which set it with a `# @readOnly(true | false)` comment
+    pub disable_read_only_heuristic: bool,
+
+    /// How nullable list items are represented in input schemas
+    pub nullable_style: NullableStyle,
+
+    /// Variables set from the claims of the validated JWT, rather than by the tool caller
+    pub claim_variables: Vec<ClaimVariable>,
+
+    /// Fragments which operations can spread without defining them
+    pub shared_fragments: SharedFragments,
+
+    /// Fail operations which select no fields in the schema, instead of skipping them
+    pub strict_operations: bool,
+
+    /// The maximum depth of nested selections in operations, including those of fragments.
+    /// Deeper operations are skipped, or fail if operations are strict
+    pub max_operation_depth: Option<usize>,
+
+    /// The maximum number of variables of operations. Operations with more variables are
+    /// reported with a warning, or fail if operations are strict
+    pub max_operation_variables: Option<usize>,
+
+    /// Limits on the expansion of fragments when describing operations
+    pub fragment_expansion: FragmentExpansion,
+
+    /// Warn about operations selecting fields which are not in the schema, such as fields removed
+    /// by a schema change. Such operations fail instead if operations are strict
+    pub check_missing_fields: bool,
+
+    /// Fail to load documents containing more than one operation, instead of loading each named
+    /// operation as its own tool
+    pub single_operation_documents: bool,
+
+    /// Accept a `__project` argument in operation tools, selecting part of the response to return
+    /// with a path such as `data.user.name`
+    pub result_projection: bool,
+
+    /// Accept an `__estimate` argument in query operation tools, returning the estimated cost of
+    /// the operation instead of executing it
+    pub cost_estimates: bool,
+
+    /// Append the formatted operation source text to the tool description
+    pub include_operation_source: bool,
+
+    /// Append an example of the tool arguments, with placeholders for each variable, to the tool
+    /// description
+    pub include_examples: bool,
+
+    /// Append the categories of the operation, from the `@tag` directives of its root fields, to
+    /// the tool description
+    pub tag_categories: bool,
+
+    /// Named sets of headers which operations can select with a `# @headers("name")` comment
+    pub header_sets: HashMap<String, HeaderMap>,
+
+    /// How to send variables whose value is `null`, unless an operation selects otherwise with a
+    /// `# @nullVariables("keep" | "omit")` comment
+    pub null_variables: NullVariables,
+
+    /// Parse string values of `Int`, `Float`, `Boolean`, and enum variables into the declared
+    /// type, matching enum values regardless of case
+    pub coerce_scalars: bool,
+
+    /// A prefix to remove from operation names when naming their tools
+    pub strip_operation_prefix: Option<String>,
+
+    /// Descriptions to give variables of built-in scalar types (such as `ID`), by type name
+    pub builtin_scalar_descriptions: HashMap<String, String>,
+
+    /// The name of the directive constraining the values of input fields and arguments, such as
+    /// `constraint` for `@constraint(minLength: 1)`, whose constraints are added to input schemas
+    pub constraint_directive: Option<String>,
+
+    /// The maximum number of operation tools
+    pub max_tools: Option<MaxTools>,
+
+    /// How to handle operations with the same name from more than one source
+    pub duplicate_operations: DuplicateOperations,
+
+    /// Keep only the first of the operations whose tools are identical apart from their names
+    pub dedupe_identical_operations: bool,
+
+    /// Constants substituted for `{{name}}` placeholders in the source of each operation before it
+    /// is parsed
+    pub operation_constants: HashMap<String, String>,
+
+    /// A cache of the schemas shaken to describe operations, shared by operations selecting the
+    /// same types and fields
+    pub shake_cache: Option<ShakeCache>,
+}
+
 #[derive(Debug, Clone)]
 pub struct RawOperation {
     source_text: String,
@@ -216,6 +928,10 @@ pub struct RawOperation {
     headers: Option<HeaderMap<HeaderValue>>,
     variables: Option<HashMap<String, Value>>,
     source_path: Option<String>,
+    policy: ExecutionPolicy,
+
+    /// The description of the operation from the markdown file named after its file
+    description: Option<String>,
 }
 
 // Custom Serialize implementation for RawOperation
@@ -250,11 +966,67 @@ impl serde::Serialize for RawOperation {
         if let Some(ref path) = self.source_path {
             state.serialize_field("source_path", path)?;
         }
+        if self.policy != ExecutionPolicy::default() {
+            state.serialize_field("policy", &self.policy)?;
+        }
+        if let Some(ref description) = self.description {
+            state.serialize_field("description", description)?;
+        }
 
         state.end()
     }
 }
 
+// Custom Deserialize implementation for RawOperation, reading what the Serialize implementation
+// writes, so that operations can be cached on disk
+impl<'de> serde::Deserialize<'de> for RawOperation {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error as _;
+
+        #[derive(Deserialize)]
+        struct Fields {
+            source_text: String,
+            persisted_query_id: Option<String>,
+            variables: Option<HashMap<String, Value>>,
+            headers: Option<String>,
+            source_path: Option<String>,
+            #[serde(default)]
+            policy: ExecutionPolicy,
+            description: Option<String>,
+        }
+
+        let fields = Fields::deserialize(deserializer)?;
+        let headers = fields
+            .headers
+            .map(|headers| {
+                let mut header_map = HeaderMap::new();
+                for line in headers.lines() {
+                    let (name, value) = line
+                        .split_once(": ")
+                        .ok_or_else(|| D::Error::custom(format!("invalid header: {line}")))?;
+                    header_map.append(
+                        HeaderName::from_str(name).map_err(D::Error::custom)?,
+                        HeaderValue::from_str(value).map_err(D::Error::custom)?,
+                    );
+                }
+                Ok(header_map)
+            })
+            .transpose()?;
+        Ok(Self {
+            source_text: fields.source_text,
+            persisted_query_id: fields.persisted_query_id,
+            headers,
+            variables: fields.variables,
+            source_path: fields.source_path,
+            policy: fields.policy,
+            description: fields.description,
+        })
+    }
+}
+
 impl From<(String, Option<String>)> for RawOperation {
     fn from((source_text, source_path): (String, Option<String>)) -> Self {
         Self {
@@ -263,6 +1035,8 @@ impl From<(String, Option<String>)> for RawOperation {
             headers: None,
             variables: None,
             source_path,
+            policy: ExecutionPolicy::default(),
+            description: None,
         }
     }
 }
@@ -275,6 +1049,22 @@ impl From<(String, String)> for RawOperation {
             headers: None,
             variables: None,
             source_path: None,
+            policy: ExecutionPolicy::default(),
+            description: None,
+        }
+    }
+}
+
+impl From<ManifestOperation> for RawOperation {
+    fn from(operation: ManifestOperation) -> Self {
+        Self {
+            persisted_query_id: Some(operation.id),
+            source_text: operation.body,
+            headers: None,
+            variables: None,
+            source_path: None,
+            policy: operation.policy,
+            description: None,
         }
     }
 }
@@ -296,10 +1086,11 @@ impl TryFrom<&OperationData> for RawOperation {
             None
         };
 
+        // Headers saved more than once are sent with every value
         let headers = if let Some(headers) = operation_data.headers.as_ref() {
             let mut header_map = HeaderMap::new();
             for header in headers {
-                header_map.insert(
+                header_map.append(
                     HeaderName::from_str(&header.0).map_err(CollectionError::HeaderName)?,
                     HeaderValue::from_str(&header.1).map_err(CollectionError::HeaderValue)?,
                 );
@@ -315,6 +1106,8 @@ impl TryFrom<&OperationData> for RawOperation {
             headers,
             variables,
             source_path: None,
+            policy: ExecutionPolicy::default(),
+            description: None,
         })
     }
 }
@@ -324,26 +1117,220 @@ impl RawOperation {
         self,
         schema: &Valid<apollo_compiler::Schema>,
         custom_scalars: Option<&CustomScalarMap>,
-        mutation_mode: MutationMode,
-        disable_type_description: bool,
-        disable_schema_description: bool,
+        options: &ToolOptions,
     ) -> Result<Option<Operation>, OperationError> {
-        Operation::from_document(
-            self,
-            schema,
-            custom_scalars,
-            mutation_mode,
-            disable_type_description,
-            disable_schema_description,
-        )
+        Operation::from_document(self, schema, custom_scalars, options)
     }
-}
 
-#[derive(Debug, Clone, Serialize)]
+    /// The type of the operations in the document, if it can be parsed and they all have the
+    /// same type
+    fn operation_kind(&self) -> Option<OperationKind> {
+        let document = Parser::new()
+            .parse_ast(&self.source_text, "operation.graphql")
+            .ok()?;
+        let mut operation_types =
+            document
+                .definitions
+                .iter()
+                .filter_map(|definition| match definition {
+                    Definition::OperationDefinition(operation) => Some(operation.operation_type),
+                    _ => None,
+                });
+        let operation_type = operation_types.next()?;
+        operation_types
+            .all(|other| other == operation_type)
+            .then_some(operation_type.into())
+    }
+
+    /// Describe the operation with the contents of a markdown file instead of its comments or
+    /// the schema
+    pub fn with_description(self, description: Option<String>) -> Self {
+        Self {
+            description,
+            ..self
+        }
+    }
+
+    /// Split a document containing more than one operation into a document for each operation,
+    /// keeping the comments preceding each operation and the fragments it spreads. A description
+    /// from a markdown file is kept only for the operation named after the file.
+    ///
+    /// Persisted queries are never split, since their ID refers to the whole document.
+    pub(crate) fn split(self, options: &ToolOptions) -> Vec<Self> {
+        if options.single_operation_documents || self.persisted_query_id.is_some() {
+            return vec![self];
+        }
+        let Ok(document) = Parser::new().parse_ast(
+            &self.source_text,
+            self.source_path.as_deref().unwrap_or("operation.graphql"),
+        ) else {
+            // Leave reporting the parse error to the conversion
+            return vec![self];
+        };
+        let operation_count = document
+            .definitions
+            .iter()
+            .filter(|definition| matches!(definition, Definition::OperationDefinition(_)))
+            .count();
+        if operation_count < 2 {
+            return vec![self];
+        }
+
+        let fragments = SharedFragments::from_document(&document);
+        let file_stem = self
+            .source_path
+            .as_deref()
+            .and_then(|path| Path::new(path).file_stem())
+            .and_then(|stem| stem.to_str());
+        let mut start = 0;
+        let mut split = Vec::with_capacity(operation_count);
+        for definition in &document.definitions {
+            let Some(location) = definition.location() else {
+                continue;
+            };
+            let end = location.end_offset();
+            if let Definition::OperationDefinition(operation) = definition
+                && let Some(operation_text) = self.source_text.get(start..end)
+            {
+                let operation_text = operation_text.trim_start_matches(['\n', '\r']);
+                let named_after_file = operation
+                    .name
+                    .as_ref()
+                    .is_some_and(|name| Some(name.as_str()) == file_stem);
+                split.push(Self {
+                    source_text: fragments
+                        .complete(operation_text)
+                        .unwrap_or_else(|| operation_text.to_string()),
+                    description: self.description.clone().filter(|_| named_after_file),
+                    ..self.clone()
+                });
+            }
+            start = end;
+        }
+        split
+    }
+}
+
+/// Read the markdown file describing the operations of an operation file, if there is one
+fn description_file(path: &Path) -> Option<String> {
+    fs::read_to_string(path.with_extension(DESCRIPTION_FILE_EXTENSION))
+        .ok()
+        .filter(|description| !description.trim().is_empty())
+}
+
+impl RawOperation {
+    /// Hash the parts of the raw operation that affect the generated tool
+    fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.source_text.hash(&mut hasher);
+        self.persisted_query_id.hash(&mut hasher);
+        self.source_path.hash(&mut hasher);
+        self.description.hash(&mut hasher);
+        self.policy.hash(&mut hasher);
+        if let Some(variables) = self.variables.as_ref() {
+            // Sort the variables since hash map iteration order is not stable
+            let mut variables = variables
+                .iter()
+                .map(|(name, value)| (name, value.to_string()))
+                .collect::<Vec<_>>();
+            variables.sort();
+            variables.hash(&mut hasher);
+        }
+        if let Some(headers) = self.headers.as_ref() {
+            for (name, value) in headers {
+                name.as_str().hash(&mut hasher);
+                value.as_bytes().hash(&mut hasher);
+            }
+        }
+        hasher.finish()
+    }
+}
+
+/// Cache key made up of the raw operation hash and the schema hash
+type CacheKey = (u64, u64);
+
+/// A cache of operations converted into tools, keyed by a hash of the raw operation and a hash of
+/// the schema it was converted against.
+///
+/// Generating the tool for an operation is expensive for large schemas, so this allows skipping
+/// the conversion on hot reload when neither the operation nor the schema has changed.
+#[derive(Clone, Default)]
+pub(crate) struct OperationCache {
+    entries: Arc<Mutex<HashMap<CacheKey, Option<Operation>>>>,
+}
+
+impl OperationCache {
+    /// Compute the hash of a schema to use as part of the cache key
+    pub(crate) fn schema_hash(schema: &GraphqlSchema) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        schema.serialize().to_string().hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// Get the cached conversion of a raw operation, or convert and cache it if not present.
+    ///
+    /// Errors are not cached, so an invalid operation is reported each time it is converted.
+    pub(crate) fn get_or_convert(
+        &self,
+        raw_operation: RawOperation,
+        schema_hash: u64,
+        convert: impl FnOnce(RawOperation) -> Result<Option<Operation>, OperationError>,
+    ) -> Result<Option<Operation>, OperationError> {
+        let key = (raw_operation.content_hash(), schema_hash);
+        if let Some(cached) = self
+            .entries
+            .lock()
+            .ok()
+            .and_then(|entries| entries.get(&key).cloned())
+        {
+            debug!(
+                "Reusing cached tool for operation {}",
+                raw_operation.source_path.as_deref().unwrap_or("<unnamed>")
+            );
+            return Ok(cached);
+        }
+
+        let operation = convert(raw_operation)?;
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.insert(key, operation.clone());
+        }
+        Ok(operation)
+    }
+
+    /// Drop any cached entries other than the given ones, so operations which were removed, or
+    /// converted against a previous schema, don't stay in the cache
+    fn retain(&self, keys: &HashSet<CacheKey>) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|key, _| keys.contains(key));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct Operation {
     tool: Tool,
     inner: RawOperation,
     operation_name: String,
+    variables_as_object: bool,
+    claim_variables: Vec<ClaimVariable>,
+    #[serde(skip)]
+    header_set: Option<HeaderMap>,
+    null_variables: NullVariables,
+    coerced_scalars: HashMap<String, CoercedScalar>,
+    split_list_variables: Vec<String>,
+    mutation_mode: MutationMode,
+    return_types: Vec<String>,
+    categories: Vec<String>,
+    next_operations: Vec<String>,
+    required_operations: Vec<String>,
+    computed_defaults: Vec<ComputedDefault>,
+    dependent_required: Vec<(String, Vec<String>)>,
+    priority: i64,
+    requires_confirmation: bool,
+    cache_ttl: Option<Duration>,
+    #[serde(skip)]
+    output_schema: Option<Value>,
+    output_validation: OutputValidation,
 }
 
 impl AsRef<Tool> for Operation {
@@ -358,10 +1345,487 @@ impl From<Operation> for Tool {
     }
 }
 
+/// Drop the operations beyond the maximum number of tools, keeping the first ones in the
+/// configured order and warning about the rest, or fail if the limit is strict
+pub(crate) fn limit_tools(
+    operations: &mut Vec<Operation>,
+    max_tools: &MaxTools,
+) -> Result<(), OperationError> {
+    let count = operations.len();
+    if count <= max_tools.limit {
+        return Ok(());
+    }
+    if max_tools.strict {
+        return Err(OperationError::TooManyTools {
+            count,
+            limit: max_tools.limit,
+        });
+    }
+    match max_tools.order {
+        ToolOrder::Name => operations.sort_by(|a, b| a.tool.name.cmp(&b.tool.name)),
+        ToolOrder::Priority => operations.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| a.tool.name.cmp(&b.tool.name))
+        }),
+    }
+    let dropped: Vec<_> = operations
+        .split_off(max_tools.limit)
+        .into_iter()
+        .map(|operation| operation.tool.name.to_string())
+        .collect();
+    warn!(
+        "Exposing {} of {count} operation tools, dropping: {}",
+        max_tools.limit,
+        dropped.join(", ")
+    );
+    Ok(())
+}
+
+/// Convert raw operations into tools for a schema, reusing cached conversions and skipping
+/// invalid operations. The operations are converted in parallel on up to
+/// `operation_loading_threads` threads, and returned in the order they were given. Afterwards,
+/// the cache only holds the conversions of these operations.
+pub(crate) fn convert_operations(
+    raw_operations: Vec<RawOperation>,
+    schema: &Valid<apollo_compiler::Schema>,
+    custom_scalars: Option<&CustomScalarMap>,
+    options: &ToolOptions,
+    cache: &OperationCache,
+) -> Vec<Operation> {
+    let schema_hash = OperationCache::schema_hash(schema);
+    if let Some(shake_cache) = &options.shake_cache {
+        shake_cache.retain_schema(schema_hash);
+    }
+    let convert = |raw_operations: Vec<RawOperation>| -> Vec<Operation> {
+        raw_operations
+            .into_iter()
+            .filter_map(|operation| {
+                cache
+                    .get_or_convert(operation, schema_hash, |operation| {
+                        operation.into_operation(schema, custom_scalars, options)
+                    })
+                    .unwrap_or_else(|error| {
+                        error!("Invalid operation: {}", error);
+                        None
+                    })
+            })
+            .collect()
+    };
+
+    let keys = raw_operations
+        .iter()
+        .map(|operation| (operation.content_hash(), schema_hash))
+        .collect();
+    let operations = convert_in_parallel(raw_operations, options, convert);
+    cache.retain(&keys);
+    operations
+}
+
+/// Convert chunks of the raw operations in parallel on up to `operation_loading_threads`
+/// threads, keeping their order
+fn convert_in_parallel(
+    raw_operations: Vec<RawOperation>,
+    options: &ToolOptions,
+    convert: impl Fn(Vec<RawOperation>) -> Vec<Operation> + Sync,
+) -> Vec<Operation> {
+    let threads = options
+        .operation_loading_threads
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, usize::from));
+    if threads <= 1 || raw_operations.len() <= 1 {
+        return convert(raw_operations);
+    }
+
+    // Each thread converts a contiguous chunk, so joining the chunks in order keeps the order
+    let chunk_size = raw_operations.len().div_ceil(threads);
+    let mut chunks = Vec::new();
+    let mut rest = raw_operations;
+    while rest.len() > chunk_size {
+        let tail = rest.split_off(chunk_size);
+        chunks.push(rest);
+        rest = tail;
+    }
+    chunks.push(rest);
+    std::thread::scope(|scope| {
+        chunks
+            .into_iter()
+            .map(|chunk| scope.spawn(|| convert(chunk)))
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|payload| std::panic::resume_unwind(payload))
+            })
+            .collect()
+    })
+}
+
+/// Keep only one of the operations sharing a name, preferring the one loaded first and warning
+/// about the operations it shadows, or fail if duplicates are errors
+pub(crate) fn resolve_duplicate_operations(
+    operations: &mut Vec<Operation>,
+    policy: DuplicateOperations,
+) -> Result<(), OperationError> {
+    let source = |operation: &Operation| {
+        operation
+            .inner
+            .source_path
+            .clone()
+            .unwrap_or_else(|| "<unnamed>".to_string())
+    };
+    let mut kept: HashMap<String, String> = HashMap::new();
+    let mut result = Ok(());
+    operations.retain(|operation| {
+        let Some(kept_source) = kept.get(&operation.operation_name) else {
+            kept.insert(operation.operation_name.clone(), source(operation));
+            return true;
+        };
+        match policy {
+            DuplicateOperations::PreferFirst => warn!(
+                "Operation {} from {} is shadowed by the operation of the same name from {}",
+                operation.operation_name,
+                source(operation),
+                kept_source
+            ),
+            DuplicateOperations::Error => {
+                if result.is_ok() {
+                    result = Err(OperationError::DuplicateOperation {
+                        operation: operation.operation_name.clone(),
+                        sources: vec![kept_source.clone(), source(operation)],
+                    });
+                }
+            }
+        }
+        false
+    });
+    result
+}
+
+/// Warn about the operations named by `# @next` or `# @requires` comments which are not loaded,
+/// since the hints in their descriptions would send the LLM to a tool that does not exist
+pub(crate) fn check_operation_dependencies(operations: &[Operation]) {
+    let names: HashSet<&str> = operations
+        .iter()
+        .map(|operation| operation.operation_name.as_str())
+        .collect();
+    for operation in operations {
+        for (directive, dependency) in operation
+            .required_operations
+            .iter()
+            .map(|name| (REQUIRES_DIRECTIVE, name))
+            .chain(
+                operation
+                    .next_operations
+                    .iter()
+                    .map(|name| (NEXT_DIRECTIVE, name)),
+            )
+        {
+            if !names.contains(dependency.as_str()) {
+                warn!(
+                    "Operation {} refers to the unknown operation {dependency} in its @{directive} comment",
+                    operation.operation_name
+                );
+            }
+        }
+    }
+}
+
+/// Keep only the first of the operations whose tools are identical apart from their names, with
+/// the same description, input schema, annotations, and return types, warning about the others
+pub(crate) fn dedupe_identical_operations(operations: &mut Vec<Operation>) {
+    let mut kept: Vec<Operation> = Vec::with_capacity(operations.len());
+    for operation in operations.drain(..) {
+        if let Some(original) = kept.iter().find(|kept| kept.has_identical_tool(&operation)) {
+            warn!(
+                "Skipping operation {}, whose tool is identical to the tool of operation {}",
+                operation.operation_name, original.operation_name
+            );
+        } else {
+            kept.push(operation);
+        }
+    }
+    *operations = kept;
+}
+
+/// Name the tools of operations whose prefix-stripped names collide with another tool after the
+/// operation itself, so that every tool name is unique
+pub(crate) fn restore_colliding_tool_names(operations: &mut [Operation]) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for operation in operations.iter() {
+        *counts.entry(operation.tool.name.to_string()).or_default() += 1;
+    }
+    for operation in operations.iter_mut() {
+        if operation.tool.name != operation.operation_name
+            && counts
+                .get(operation.tool.name.as_ref())
+                .is_some_and(|count| *count > 1)
+        {
+            warn!(
+                "Tool name {} of operation {} is not unique after stripping its prefix, using the operation name instead",
+                operation.tool.name, operation.operation_name
+            );
+            operation.tool.name = operation.operation_name.clone().into();
+        }
+    }
+}
+
 impl Operation {
+    /// Split comma-separated string values of list variables into lists of trimmed items,
+    /// describing each split in `coercions`
+    fn split_list_strings(&self, variables: Value, coercions: &mut Vec<String>) -> Value {
+        let Value::Object(mut variables) = variables else {
+            return variables;
+        };
+        for name in &self.split_list_variables {
+            if let Some(Value::String(text)) = variables.get(name) {
+                let items = text
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(Value::from)
+                    .collect();
+                let items = Value::Array(items);
+                coercions.push(format!("coerced {name} from {text} to {items}"));
+                variables.insert(name.clone(), items);
+            }
+        }
+        Value::Object(variables)
+    }
+
+    /// Parse string values of scalar and enum variables into their declared types, describing
+    /// each changed value in `coercions`
+    fn coerce_scalars(
+        &self,
+        variables: Value,
+        coercions: &mut Vec<String>,
+    ) -> Result<Value, McpError> {
+        let Value::Object(mut variables) = variables else {
+            return Ok(variables);
+        };
+        for (name, scalar) in &self.coerced_scalars {
+            if let Some(value) = variables.remove(name) {
+                let coerced = scalar.coerce(value.clone()).map_err(|value| {
+                    McpError::new(
+                        ErrorCode::INVALID_PARAMS,
+                        format!("Invalid value for {name}: expected {scalar:?}, got {value}"),
+                        None,
+                    )
+                })?;
+                if coerced != value {
+                    coercions.push(format!("coerced {name} from {value} to {coerced}"));
+                }
+                variables.insert(name.clone(), coerced);
+            }
+        }
+        Ok(Value::Object(variables))
+    }
+
+    /// Compute the defaults of absent variables from their sibling variables
+    fn compute_defaults(&self, variables: Value) -> Result<Value, McpError> {
+        if self.computed_defaults.is_empty() {
+            return Ok(variables);
+        }
+        let mut variables = match variables {
+            Value::Null => serde_json::Map::new(),
+            Value::Object(variables) => variables,
+            variables => return Ok(variables),
+        };
+        for computed_default in &self.computed_defaults {
+            if variables
+                .get(&computed_default.variable)
+                .is_some_and(|value| !value.is_null())
+            {
+                continue;
+            }
+            let Some(source) = variables
+                .get(&computed_default.source)
+                .filter(|value| !value.is_null())
+            else {
+                continue;
+            };
+            let value = computed_default.compute(source).map_err(|reason| {
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "Failed to compute the default of {}: {reason}",
+                        computed_default.variable
+                    ),
+                    None,
+                )
+            })?;
+            variables.insert(computed_default.variable.clone(), value);
+        }
+        Ok(Value::Object(variables))
+    }
+
+    /// Reject variables which are set without the variables they depend on
+    fn check_dependent_required(&self, variables: &Value) -> Result<(), McpError> {
+        let is_set = |name: &str| variables.get(name).is_some_and(|value| !value.is_null());
+        for (variable, required) in &self.dependent_required {
+            if !is_set(variable) {
+                continue;
+            }
+            if let Some(missing) = required.iter().find(|name| !is_set(name)) {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("{variable} requires {missing} to be set"),
+                    None,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the variables mapped from the claims of the validated JWT, overriding any input values
+    fn inject_claim_variables(
+        &self,
+        variables: Value,
+        claims: Option<&serde_json::Map<String, Value>>,
+    ) -> Result<Value, McpError> {
+        if self.claim_variables.is_empty() {
+            return Ok(variables);
+        }
+
+        let mut variables = match variables {
+            Value::Null => serde_json::Map::new(),
+            Value::Object(obj) => obj,
+            _ => {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    "Invalid input".to_string(),
+                    None,
+                ));
+            }
+        };
+        for claim_variable in &self.claim_variables {
+            match claims.and_then(|claims| claims.get(&claim_variable.claim)) {
+                Some(value) => {
+                    variables.insert(claim_variable.variable.clone(), value.clone());
+                }
+                None if claim_variable.optional => {}
+                None => {
+                    return Err(McpError::new(
+                        ErrorCode::INVALID_REQUEST,
+                        format!("Missing required claim: {}", claim_variable.claim),
+                        None,
+                    ));
+                }
+            }
+        }
+        Ok(Value::Object(variables))
+    }
+
+    pub(crate) fn operation_name(&self) -> &str {
+        &self.operation_name
+    }
+
     pub(crate) fn into_inner(self) -> RawOperation {
         self.inner
     }
+
+    /// The names of the types returned by the root fields the operation selects
+    pub fn return_types(&self) -> &[String] {
+        &self.return_types
+    }
+
+    fn return_types_of(
+        operation: &OperationDefinition,
+        graphql_schema: &GraphqlSchema,
+    ) -> Vec<String> {
+        let Some(root_name) = graphql_schema.root_operation(operation.operation_type) else {
+            return Vec::new();
+        };
+        let mut return_types = Vec::new();
+        for selection in &operation.selection_set {
+            if let Selection::Field(field) = selection
+                && let Ok(definition) = graphql_schema.type_field(root_name, &field.name)
+            {
+                let return_type = definition.ty.inner_named_type().to_string();
+                if !return_types.contains(&return_type) {
+                    return_types.push(return_type);
+                }
+            }
+        }
+        return_types
+    }
+
+    /// Whether the tools of two operations are identical apart from their names
+    fn has_identical_tool(&self, other: &Operation) -> bool {
+        self.tool.description == other.tool.description
+            && self.tool.input_schema == other.tool.input_schema
+            && self.tool.annotations == other.tool.annotations
+            && self.return_types == other.return_types
+    }
+
+    /// The categories of the operation, from the `@tag(name:)` directives of the root fields it
+    /// selects
+    pub fn categories(&self) -> &[String] {
+        &self.categories
+    }
+
+    fn categories_of(
+        operation: &OperationDefinition,
+        graphql_schema: &GraphqlSchema,
+    ) -> Vec<String> {
+        let Some(root_name) = graphql_schema.root_operation(operation.operation_type) else {
+            return Vec::new();
+        };
+        let mut categories = Vec::new();
+        for selection in &operation.selection_set {
+            if let Selection::Field(field) = selection
+                && let Ok(definition) = graphql_schema.type_field(root_name, &field.name)
+            {
+                for tag in definition.directives.get_all(TAG_DIRECTIVE) {
+                    if let Some(name) = tag
+                        .specified_argument_by_name("name")
+                        .and_then(|name| name.as_str())
+                        && !categories.iter().any(|category| category == name)
+                    {
+                        categories.push(name.to_string());
+                    }
+                }
+            }
+        }
+        categories
+    }
+
+    /// The mutation access level of the operation
+    pub fn mutation_mode(&self) -> MutationMode {
+        self.mutation_mode
+    }
+
+    /// Whether calls to the operation must be confirmed with the `__confirm` argument
+    pub(crate) fn requires_confirmation(&self) -> bool {
+        self.requires_confirmation
+    }
+
+    /// How long the results of the operation are cached for, if it overrides the result cache's
+    /// time to live
+    pub(crate) fn cache_ttl(&self) -> Option<Duration> {
+        self.cache_ttl
+    }
+
+    /// The root fields of the operation, if it is a mutation whose mutation mode is `all`, which
+    /// the LLM can then select in mutations it builds itself
+    pub(crate) fn buildable_mutation_fields(&self) -> Vec<String> {
+        if self.mutation_mode != MutationMode::All {
+            return Vec::new();
+        }
+        match operation_defs(&self.inner.source_text, true, None) {
+            Ok(Some((_, operation, _))) if operation.operation_type == OperationType::Mutation => {
+                operation
+                    .selection_set
+                    .iter()
+                    .filter_map(|selection| match selection {
+                        Selection::Field(field) => Some(field.name.to_string()),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
 }
 
 #[allow(clippy::type_complexity)]
@@ -440,47 +1904,289 @@ pub fn operation_defs(
     Ok(Some((document, operation, comments.map(|c| c.to_string()))))
 }
 
-pub fn extract_and_format_comments(comments: Option<String>) -> Option<String> {
-    comments.and_then(|comments| {
-        let content = Regex::new(r"(\n|^)(\s*,*)*#")
-            .ok()?
-            .replace_all(comments.as_str(), "$1");
-        let trimmed = content.trim();
+/// The depth of nested field selections, following fragment spreads. Spreads of a fragment within
+/// itself are not followed, as they are invalid.
+fn selection_depth<'a>(
+    selections: &'a [Selection],
+    document: &'a Document,
+    fragments: &mut Vec<&'a Name>,
+) -> usize {
+    selections
+        .iter()
+        .map(|selection| match selection {
+            Selection::Field(field) => {
+                1 + selection_depth(&field.selection_set, document, fragments)
+            }
+            Selection::InlineFragment(inline) => {
+                selection_depth(&inline.selection_set, document, fragments)
+            }
+            Selection::FragmentSpread(spread) => {
+                if fragments.contains(&&spread.fragment_name) {
+                    return 0;
+                }
+                let Some(fragment) = fragment_definition(document, &spread.fragment_name) else {
+                    return 0;
+                };
+                fragments.push(&fragment.name);
+                let depth = selection_depth(&fragment.selection_set, document, fragments);
+                fragments.pop();
+                depth
+            }
+        })
+        .max()
+        .unwrap_or_default()
+}
 
-        if trimmed.is_empty() {
-            None
-        } else {
-            Some(trimmed.to_string())
+/// The definition of a fragment in a document
+fn fragment_definition<'a>(
+    document: &'a Document,
+    name: &Name,
+) -> Option<&'a Node<FragmentDefinition>> {
+    document
+        .definitions
+        .iter()
+        .find_map(|definition| match definition {
+            Definition::FragmentDefinition(fragment) if fragment.name == *name => Some(fragment),
+            _ => None,
+        })
+}
+
+/// The fields selected by an operation which are not in the schema, as `Type.field`, following
+/// fragment spreads. Fields of types which are themselves not in the schema are not checked.
+fn missing_fields<'a>(
+    selections: &'a [Selection],
+    type_name: &Name,
+    schema: &GraphqlSchema,
+    document: &'a Document,
+    fragments: &mut Vec<&'a Name>,
+) -> Vec<String> {
+    let mut missing = Vec::new();
+    for selection in selections {
+        match selection {
+            Selection::Field(field) => {
+                // Introspection fields, such as `__typename`, are in every type
+                if field.name.starts_with("__") {
+                    continue;
+                }
+                match schema.type_field(type_name, &field.name) {
+                    Ok(definition) => missing.extend(missing_fields(
+                        &field.selection_set,
+                        definition.ty.inner_named_type(),
+                        schema,
+                        document,
+                        fragments,
+                    )),
+                    Err(_) if schema.types.contains_key(type_name) => {
+                        missing.push(format!("{type_name}.{}", field.name));
+                    }
+                    Err(_) => {}
+                }
+            }
+            Selection::InlineFragment(inline) => missing.extend(missing_fields(
+                &inline.selection_set,
+                inline.type_condition.as_ref().unwrap_or(type_name),
+                schema,
+                document,
+                fragments,
+            )),
+            Selection::FragmentSpread(spread) => {
+                if fragments.contains(&&spread.fragment_name) {
+                    continue;
+                }
+                let Some(fragment) = fragment_definition(document, &spread.fragment_name) else {
+                    continue;
+                };
+                fragments.push(&fragment.name);
+                missing.extend(missing_fields(
+                    &fragment.selection_set,
+                    &fragment.type_condition,
+                    schema,
+                    document,
+                    fragments,
+                ));
+                fragments.pop();
+            }
         }
-    })
+    }
+    missing
 }
 
-pub fn find_opening_parens_offset(
-    source_text: &str,
-    operation_definition: &Node<OperationDefinition>,
-) -> Option<usize> {
-    let regex = match Regex::new(r"(?m)^\s*\(") {
-        Ok(regex) => regex,
-        Err(_) => return None,
-    };
+/// The schema directive grouping fields into categories, e.g. `@tag(name: "billing")`
+const TAG_DIRECTIVE: &str = "tag";
 
-    operation_definition
-        .name
-        .as_ref()
-        .and_then(|n| n.location())
-        .map(|span| {
-            regex
-                .find(source_text[span.end_offset()..].as_ref())
-                .map(|m| m.start() + m.len() + span.end_offset())
-                .unwrap_or(0)
-        })
-}
+/// The comment directive selecting a named header set, e.g. `# @headers("serviceB")`
+const HEADERS_DIRECTIVE: &str = "headers";
 
-pub fn variable_description_overrides(
-    source_text: &str,
-    operation_definition: &Node<OperationDefinition>,
-) -> HashMap<String, String> {
-    let mut argument_overrides_map: HashMap<String, String> = HashMap::new();
+/// The comment directive selecting how null variables are sent, e.g. `# @nullVariables("omit")`
+const NULL_VARIABLES_DIRECTIVE: &str = "nullVariables";
+
+/// The comment directive selecting the mutation access level of an operation, e.g.
+/// `# @mutationMode("all")`
+const MUTATION_MODE_DIRECTIVE: &str = "mutationMode";
+
+/// The comment directive computing a variable's default from a sibling variable, e.g.
+/// `# @computedDefault("endDate = startDate + 30d")`
+const COMPUTED_DEFAULT_DIRECTIVE: &str = "computedDefault";
+
+/// The comment directive requiring variables whenever another variable is set, e.g.
+/// `# @dependentRequired("after: first")`
+const DEPENDENT_REQUIRED_DIRECTIVE: &str = "dependentRequired";
+
+/// The comment directive setting the priority of an operation when there are too many tools,
+/// e.g. `# @priority(10)`
+const PRIORITY_DIRECTIVE: &str = "priority";
+
+/// The comment directive naming an operation to call after this one, e.g.
+/// `# @next(CreateOrderConfirmation)`
+const NEXT_DIRECTIVE: &str = "next";
+
+/// The comment directive naming an operation to call before this one, e.g.
+/// `# @requires(CreateOrder)`
+const REQUIRES_DIRECTIVE: &str = "requires";
+
+/// The comment directive overriding the read-only hint derived from the operation type, e.g.
+/// `# @readOnly(false)`
+const READ_ONLY_DIRECTIVE: &str = "readOnly";
+
+/// The comment directive marking an operation as destructive, e.g. `# @destructive(true)`
+const DESTRUCTIVE_DIRECTIVE: &str = "destructive";
+
+/// The comment directive overriding how long the operation's results are cached for, e.g.
+/// `# @cacheTtl(30s)`, or `# @cacheTtl(0)` to never cache them
+const CACHE_TTL_DIRECTIVE: &str = "cacheTtl";
+
+/// Remove comment directives (such as `# @headers("name")`) from operation comments, returning
+/// the comments without them and the arguments of each directive by name, in order
+fn extract_comment_directives(
+    comments: Option<String>,
+) -> (Option<String>, HashMap<String, Vec<String>>) {
+    let Some(comments) = comments else {
+        return (None, HashMap::new());
+    };
+    let Ok(regex) = Regex::new(&format!(
+        r#"(?m)^\s*#\s*@({HEADERS_DIRECTIVE}|{NULL_VARIABLES_DIRECTIVE}|{MUTATION_MODE_DIRECTIVE}|{COMPUTED_DEFAULT_DIRECTIVE}|{DEPENDENT_REQUIRED_DIRECTIVE}|{PRIORITY_DIRECTIVE}|{NEXT_DIRECTIVE}|{REQUIRES_DIRECTIVE}|{READ_ONLY_DIRECTIVE}|{DESTRUCTIVE_DIRECTIVE}|{CACHE_TTL_DIRECTIVE})\(\s*(?:"([^"]*)"|(-?\w+))\s*\)\s*$\n?"#
+    )) else {
+        return (Some(comments), HashMap::new());
+    };
+    let mut directives: HashMap<String, Vec<String>> = HashMap::new();
+    for captures in regex.captures_iter(&comments) {
+        if let (Some(name), Some(argument)) = (captures.get(1), captures.get(2).or(captures.get(3)))
+        {
+            directives
+                .entry(name.as_str().to_string())
+                .or_default()
+                .push(argument.as_str().to_string());
+        }
+    }
+    (
+        Some(regex.replace_all(&comments, "").to_string()),
+        directives,
+    )
+}
+
+/// Replace the `{{name}}` placeholders in operation source with the value of each constant,
+/// escaped for use inside a GraphQL string so that it can't change the rest of the operation
+fn substitute_constants(
+    source_text: &str,
+    constants: &HashMap<String, String>,
+    source_path: Option<&str>,
+) -> Result<String, OperationError> {
+    let Ok(regex) = Regex::new(r"\{\{\s*([A-Za-z_][A-Za-z0-9_]*)\s*\}\}") else {
+        return Ok(source_text.to_string());
+    };
+    if let Some(constant) = regex
+        .captures_iter(source_text)
+        .filter_map(|captures| captures.get(1))
+        .find(|name| !constants.contains_key(name.as_str()))
+    {
+        return Err(OperationError::UnknownConstant {
+            source_path: source_path.map(ToString::to_string),
+            constant: constant.as_str().to_string(),
+        });
+    }
+    Ok(regex
+        .replace_all(source_text, |captures: &regex::Captures| {
+            captures
+                .get(1)
+                .and_then(|name| constants.get(name.as_str()))
+                .map(|value| escape_string(value))
+                .unwrap_or_default()
+        })
+        .to_string())
+}
+
+/// Escape a value for use inside a GraphQL string, which uses the same escape sequences as JSON
+fn escape_string(value: &str) -> String {
+    let quoted = Value::from(value).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+/// The tag marking where a description starts in comments, e.g. `# @description Get the forecast`.
+/// Comments above the tag, such as license headers, are not part of the description, which ends
+/// at the first line that is not a comment.
+const DESCRIPTION_TAG: &str = "@description";
+
+/// Select the comments tagged as the description, if any are
+fn tagged_description(comments: &str) -> Option<String> {
+    let is_comment = |line: &&str| line.trim_start().starts_with('#');
+    let mut lines = comments.lines().skip_while(|line| {
+        !line
+            .trim_start()
+            .trim_start_matches('#')
+            .trim_start()
+            .starts_with(DESCRIPTION_TAG)
+    });
+    let first = lines.next()?.replacen(DESCRIPTION_TAG, "", 1);
+    Some(
+        std::iter::once(first.as_str())
+            .chain(lines.take_while(is_comment))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+pub fn extract_and_format_comments(comments: Option<String>) -> Option<String> {
+    comments.and_then(|comments| {
+        let comments = tagged_description(&comments).unwrap_or(comments);
+        let content = Regex::new(r"(\n|^)(\s*,*)*#")
+            .ok()?
+            .replace_all(comments.as_str(), "$1");
+        let trimmed = content.trim();
+
+        if trimmed.is_empty() {
+            None
+        } else {
+            Some(trimmed.to_string())
+        }
+    })
+}
+
+pub fn find_opening_parens_offset(
+    source_text: &str,
+    operation_definition: &Node<OperationDefinition>,
+) -> Option<usize> {
+    let regex = match Regex::new(r"(?m)^\s*\(") {
+        Ok(regex) => regex,
+        Err(_) => return None,
+    };
+
+    operation_definition
+        .name
+        .as_ref()
+        .and_then(|n| n.location())
+        .map(|span| {
+            regex
+                .find(source_text[span.end_offset()..].as_ref())
+                .map(|m| m.start() + m.len() + span.end_offset())
+                .unwrap_or(0)
+        })
+}
+
+pub fn variable_description_overrides(
+    source_text: &str,
+    operation_definition: &Node<OperationDefinition>,
+) -> HashMap<String, String> {
+    let mut argument_overrides_map: HashMap<String, String> = HashMap::new();
     let mut last_offset = find_opening_parens_offset(source_text, operation_definition);
     operation_definition
         .variables
@@ -510,16 +2216,29 @@ pub fn variable_description_overrides(
 
 impl Operation {
     pub fn from_document(
-        raw_operation: RawOperation,
+        mut raw_operation: RawOperation,
         graphql_schema: &GraphqlSchema,
         custom_scalar_map: Option<&CustomScalarMap>,
-        mutation_mode: MutationMode,
-        disable_type_description: bool,
-        disable_schema_description: bool,
+        options: &ToolOptions,
     ) -> Result<Option<Self>, OperationError> {
+        // Persisted queries are registered with their source, so it is used as is
+        if !options.operation_constants.is_empty() && raw_operation.persisted_query_id.is_none() {
+            raw_operation.source_text = substitute_constants(
+                &raw_operation.source_text,
+                &options.operation_constants,
+                raw_operation.source_path.as_deref(),
+            )?;
+        }
+        if let Some(source_text) = options
+            .shared_fragments
+            .complete(&raw_operation.source_text)
+        {
+            raw_operation.source_text = source_text;
+        }
+        // Mutations are skipped below, once the operation's own mutation mode is known
         if let Some((document, operation, comments)) = operation_defs(
             &raw_operation.source_text,
-            mutation_mode != MutationMode::None,
+            true,
             raw_operation.source_path.clone(),
         )? {
             let operation_name = match operation_name(&operation, raw_operation.source_path.clone())
@@ -538,32 +2257,380 @@ impl Operation {
                 }
                 Err(e) => return Err(e),
             };
+            let (comments, mut directives) = extract_comment_directives(comments);
+            let header_set = directives
+                .remove(HEADERS_DIRECTIVE)
+                .and_then(|mut arguments| arguments.pop())
+                .map(|name| {
+                    options.header_sets.get(&name).cloned().ok_or_else(|| {
+                        OperationError::UnknownHeaderSet {
+                            source_path: raw_operation.source_path.clone(),
+                            operation: operation_name.clone(),
+                            header_set: name,
+                        }
+                    })
+                })
+                .transpose()?;
+            let null_variables = directives
+                .remove(NULL_VARIABLES_DIRECTIVE)
+                .and_then(|mut arguments| arguments.pop())
+                .map(|mode| {
+                    serde_json::from_value(Value::String(mode.clone())).map_err(|_| {
+                        OperationError::InvalidDirective {
+                            source_path: raw_operation.source_path.clone(),
+                            operation: operation_name.clone(),
+                            directive: format!("@{NULL_VARIABLES_DIRECTIVE}(\"{mode}\")"),
+                        }
+                    })
+                })
+                .transpose()?
+                .unwrap_or(options.null_variables);
+            let mutation_mode = directives
+                .remove(MUTATION_MODE_DIRECTIVE)
+                .and_then(|mut arguments| arguments.pop())
+                .map(|mode| {
+                    serde_json::from_value::<MutationMode>(Value::String(mode.clone()))
+                        .map(|mode| options.max_mutation_mode.map_or(mode, |max| mode.min(max)))
+                        .map_err(|_| OperationError::InvalidDirective {
+                            source_path: raw_operation.source_path.clone(),
+                            operation: operation_name.clone(),
+                            directive: format!("@{MUTATION_MODE_DIRECTIVE}(\"{mode}\")"),
+                        })
+                })
+                .transpose()?
+                .unwrap_or(options.mutation_mode);
+            let computed_defaults = directives
+                .remove(COMPUTED_DEFAULT_DIRECTIVE)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|expression| {
+                    ComputedDefault::parse(&expression)
+                        .filter(|computed_default| {
+                            [&computed_default.variable, &computed_default.source]
+                                .iter()
+                                .all(|name| {
+                                    operation
+                                        .variables
+                                        .iter()
+                                        .any(|variable| variable.name == name.as_str())
+                                })
+                        })
+                        .ok_or_else(|| OperationError::InvalidDirective {
+                            source_path: raw_operation.source_path.clone(),
+                            operation: operation_name.clone(),
+                            directive: format!("@{COMPUTED_DEFAULT_DIRECTIVE}(\"{expression}\")"),
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let dependent_required = directives
+                .remove(DEPENDENT_REQUIRED_DIRECTIVE)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|dependency| {
+                    let variable_name =
+                        |name: &str| name.trim().trim_start_matches('$').to_string();
+                    dependency
+                        .split_once(':')
+                        .map(|(variable, required)| {
+                            (
+                                variable_name(variable),
+                                required
+                                    .split(',')
+                                    .map(variable_name)
+                                    .filter(|name| !name.is_empty())
+                                    .collect::<Vec<_>>(),
+                            )
+                        })
+                        .filter(|(variable, required)| {
+                            !required.is_empty()
+                                && std::iter::once(variable).chain(required).all(|name| {
+                                    operation
+                                        .variables
+                                        .iter()
+                                        .any(|defined| defined.name == name.as_str())
+                                })
+                        })
+                        .ok_or_else(|| OperationError::InvalidDirective {
+                            source_path: raw_operation.source_path.clone(),
+                            operation: operation_name.clone(),
+                            directive: format!("@{DEPENDENT_REQUIRED_DIRECTIVE}(\"{dependency}\")"),
+                        })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let priority = directives
+                .remove(PRIORITY_DIRECTIVE)
+                .and_then(|mut arguments| arguments.pop())
+                .map(|priority| {
+                    priority
+                        .parse::<i64>()
+                        .map_err(|_| OperationError::InvalidDirective {
+                            source_path: raw_operation.source_path.clone(),
+                            operation: operation_name.clone(),
+                            directive: format!("@{PRIORITY_DIRECTIVE}({priority})"),
+                        })
+                })
+                .transpose()?
+                .unwrap_or_default();
+            let next_operations = directives.remove(NEXT_DIRECTIVE).unwrap_or_default();
+            let required_operations = directives.remove(REQUIRES_DIRECTIVE).unwrap_or_default();
+            let read_only = directives
+                .remove(READ_ONLY_DIRECTIVE)
+                .and_then(|mut arguments| arguments.pop())
+                .map(|read_only| {
+                    read_only
+                        .parse::<bool>()
+                        .map_err(|_| OperationError::InvalidDirective {
+                            source_path: raw_operation.source_path.clone(),
+                            operation: operation_name.clone(),
+                            directive: format!("@{READ_ONLY_DIRECTIVE}({read_only})"),
+                        })
+                })
+                .transpose()?
+                .or_else(|| {
+                    (!options.disable_read_only_heuristic)
+                        .then_some(operation.operation_type != OperationType::Mutation)
+                });
+            let destructive = directives
+                .remove(DESTRUCTIVE_DIRECTIVE)
+                .and_then(|mut arguments| arguments.pop())
+                .map(|destructive| {
+                    destructive
+                        .parse::<bool>()
+                        .map_err(|_| OperationError::InvalidDirective {
+                            source_path: raw_operation.source_path.clone(),
+                            operation: operation_name.clone(),
+                            directive: format!("@{DESTRUCTIVE_DIRECTIVE}({destructive})"),
+                        })
+                })
+                .transpose()?;
+            let requires_confirmation =
+                options.confirm_destructive_operations && destructive == Some(true);
+            let cache_ttl = directives
+                .remove(CACHE_TTL_DIRECTIVE)
+                .and_then(|mut arguments| arguments.pop())
+                .map(|ttl| {
+                    // A bare zero disables caching, though humantime requires a unit
+                    if ttl == "0" {
+                        return Ok(Duration::ZERO);
+                    }
+                    humantime::parse_duration(&ttl).map_err(|_| OperationError::InvalidDirective {
+                        source_path: raw_operation.source_path.clone(),
+                        operation: operation_name.clone(),
+                        directive: format!("@{CACHE_TTL_DIRECTIVE}({ttl})"),
+                    })
+                })
+                .transpose()?;
+            if operation.operation_type == OperationType::Mutation
+                && mutation_mode == MutationMode::None
+            {
+                warn!("Skipping mutation operation {operation_name}");
+                return Ok(None);
+            }
+            if let Some(max_depth) = options.max_operation_depth {
+                let depth = selection_depth(&operation.selection_set, &document, &mut Vec::new());
+                if depth > max_depth {
+                    if options.strict_operations {
+                        return Err(OperationError::TooDeep {
+                            source_path: raw_operation.source_path,
+                            operation: operation_name,
+                            depth,
+                            max_depth,
+                        });
+                    }
+                    warn!(
+                        "Skipping operation {operation_name}, whose selections are {depth} levels deep, more than the maximum of {max_depth}"
+                    );
+                    return Ok(None);
+                }
+            }
+            if let Some(max_variables) = options.max_operation_variables {
+                let count = operation.variables.len();
+                if count > max_variables {
+                    if options.strict_operations {
+                        return Err(OperationError::TooManyVariables {
+                            source_path: raw_operation.source_path,
+                            operation: operation_name,
+                            count,
+                            max_variables,
+                        });
+                    }
+                    warn!(
+                        "Operation {operation_name} has {count} variables, more than the maximum of {max_variables}"
+                    );
+                }
+            }
+            if options.check_missing_fields
+                && let Some(root_type) = graphql_schema.root_operation(operation.operation_type)
+            {
+                let fields = missing_fields(
+                    &operation.selection_set,
+                    root_type,
+                    graphql_schema,
+                    &document,
+                    &mut Vec::new(),
+                );
+                if !fields.is_empty() {
+                    if options.strict_operations {
+                        return Err(OperationError::MissingFields {
+                            source_path: raw_operation.source_path,
+                            operation: operation_name,
+                            fields,
+                        });
+                    }
+                    for field in fields {
+                        warn!(
+                            "Operation {operation_name} selects the field {field}, which is not in the schema"
+                        );
+                    }
+                }
+            }
             let variable_description_overrides =
                 variable_description_overrides(&raw_operation.source_text, &operation);
-            let mut tree_shaker = SchemaTreeShaker::new(graphql_schema);
+            let mut tree_shaker = SchemaTreeShaker::new(graphql_schema)
+                .with_fragment_expansion(options.fragment_expansion)
+                .with_always_included_fields(&options.always_include_fields);
             tree_shaker.retain_operation(&operation, &document, DepthLimit::Unlimited);
+            if tree_shaker.fragment_expansion_truncated() {
+                warn!(
+                    "Stopped expanding the fragments of operation {operation_name} at the fragment expansion limits, leaving the fields past them out of its description"
+                );
+            }
+            if !tree_shaker.retains_root_fields() {
+                if options.strict_operations {
+                    return Err(OperationError::NoSelectedFields {
+                        source_path: raw_operation.source_path,
+                        operation: operation_name,
+                    });
+                }
+                warn!("Skipping operation {operation_name}, which selects no fields in the schema");
+                return Ok(None);
+            }
 
-            let description = Self::tool_description(
-                comments,
-                &mut tree_shaker,
-                graphql_schema,
-                &operation,
-                disable_type_description,
-                disable_schema_description,
-            );
+            let mut description = match raw_operation.description.as_deref() {
+                Some(description) => description.trim().to_string(),
+                None => Self::tool_description(
+                    comments,
+                    &mut tree_shaker,
+                    graphql_schema,
+                    &operation,
+                    options,
+                ),
+            };
+            if description.is_empty()
+                && let Some(fallback_description) = &options.fallback_description
+            {
+                description = fallback_description.replace("{name}", &operation_name);
+            }
+            if options.describe_variables
+                && let Some(summary) = variables_summary(
+                    &operation,
+                    tree_shaker.argument_descriptions(),
+                    &variable_description_overrides,
+                    raw_operation.variables.as_ref(),
+                )
+            {
+                if !description.is_empty() {
+                    description.push_str("\n\n");
+                }
+                description.push_str(&format!("Arguments: {summary}"));
+            }
+            if options.include_examples
+                && let Some(example) = example_arguments(
+                    &operation,
+                    graphql_schema,
+                    custom_scalar_map,
+                    raw_operation.variables.as_ref(),
+                    options,
+                )
+            {
+                if !description.is_empty() {
+                    description.push_str("\n\n");
+                }
+                description.push_str(&format!("Example: {example}"));
+            }
+            let categories = Self::categories_of(&operation, graphql_schema);
+            if options.tag_categories && !categories.is_empty() {
+                if !description.is_empty() {
+                    description.push_str("\n\n");
+                }
+                description.push_str(&format!("Categories: {}", categories.join(", ")));
+            }
+            for (operations, hint) in [
+                (&required_operations, "Before calling this, use"),
+                (&next_operations, "After calling this, use"),
+            ] {
+                if !operations.is_empty() {
+                    if !description.is_empty() {
+                        description.push_str("\n\n");
+                    }
+                    description.push_str(&format!("{hint} {}", operations.join(", ")));
+                }
+            }
+            if options.include_operation_source {
+                if !description.is_empty() {
+                    description.push_str("\n\n");
+                }
+                // The formatted document omits comments, including those used for the description
+                description.push_str(&format!("Operation:\n{}", document.to_string().trim_end()));
+            }
 
             let mut object = serde_json::to_value(get_json_schema(
                 &operation,
-                tree_shaker.argument_descriptions(),
+                &tree_shaker,
                 &variable_description_overrides,
                 graphql_schema,
                 custom_scalar_map,
                 raw_operation.variables.as_ref(),
+                options,
             ))?;
 
             // make sure that the properties field exists since schemas::ObjectValidation is
             // configured to skip empty maps (in the case where there are no input args)
             ensure_properties_exists(&mut object);
+            options.definitions_keyword.apply(&mut object);
+
+            // Describe the dependencies between variables on the object holding them
+            if !dependent_required.is_empty() {
+                let variables_schema = if options.variables_as_object {
+                    object.pointer_mut(&format!("/properties/{VARIABLES_ARGUMENT_NAME}"))
+                } else {
+                    Some(&mut object)
+                };
+                if let Some(Value::Object(variables_schema)) = variables_schema {
+                    let mut dependencies = serde_json::Map::new();
+                    for (variable, required) in &dependent_required {
+                        if let Value::Array(names) = dependencies
+                            .entry(variable.clone())
+                            .or_insert_with(|| Value::Array(Vec::new()))
+                        {
+                            names.extend(required.iter().cloned().map(Value::from));
+                        }
+                    }
+                    variables_schema
+                        .insert("dependentRequired".to_string(), Value::Object(dependencies));
+                }
+            }
+
+            if requires_confirmation {
+                if let Some(properties) =
+                    object.get_mut("properties").and_then(Value::as_object_mut)
+                {
+                    properties.insert(
+                        CONFIRM_ARGUMENT_NAME.to_string(),
+                        serde_json::json!({
+                            "description": "Must be true to confirm the call, since the operation is destructive",
+                            "type": "boolean",
+                        }),
+                    );
+                }
+                if let Some(Value::Array(required)) = object.as_object_mut().map(|object| {
+                    object
+                        .entry("required")
+                        .or_insert_with(|| Value::Array(Vec::new()))
+                }) {
+                    required.push(Value::String(CONFIRM_ARGUMENT_NAME.to_string()));
+                }
+            }
 
             let Value::Object(schema) = object else {
                 return Err(OperationError::Internal(
@@ -571,17 +2638,40 @@ impl Operation {
                 ));
             };
 
-            let tool: Tool = Tool::new(operation_name.clone(), description, schema).annotate(
-                ToolAnnotations::new()
-                    .read_only(operation.operation_type != OperationType::Mutation),
-            );
-            let character_count = tool_character_length(&tool);
-            match character_count {
-                Ok(length) => info!(
+            // The operation is still executed by its original name
+            let tool_name = options
+                .strip_operation_prefix
+                .as_deref()
+                .and_then(|prefix| operation_name.strip_prefix(prefix))
+                .filter(|name| !name.is_empty())
+                .unwrap_or(&operation_name)
+                .to_string();
+            let mut annotations = ToolAnnotations::new();
+            if let Some(read_only) = read_only {
+                annotations = annotations.read_only(read_only);
+            }
+            if let Some(destructive) = destructive {
+                annotations = annotations.destructive(destructive);
+            }
+            let tool: Tool = Tool::new(tool_name, description, schema).annotate(annotations);
+            match tool_text(&tool) {
+                Ok(text)
+                    if options
+                        .tool_size_warn_threshold
+                        .is_some_and(|threshold| text.len() > threshold) =>
+                {
+                    warn!(
+                        "Tool {} loaded with a character count of {}, over the warning threshold. Estimated tokens: {}",
+                        operation_name,
+                        text.len(),
+                        options.token_estimator.estimate(&text)
+                    )
+                }
+                Ok(text) => info!(
                     "Tool {} loaded with a character count of {}. Estimated tokens: {}",
                     operation_name,
-                    length,
-                    length / 4 // We don't know the tokenization algorithm, so we just use 4 characters per token as a rough estimate. https://docs.anthropic.com/en/docs/resources/glossary#tokens
+                    text.len(),
+                    options.token_estimator.estimate(&text)
                 ),
                 Err(_) => info!(
                     "Tool {} loaded with an unknown character count",
@@ -592,6 +2682,63 @@ impl Operation {
                 tool,
                 inner: raw_operation,
                 operation_name,
+                variables_as_object: options.variables_as_object,
+                claim_variables: options
+                    .claim_variables
+                    .iter()
+                    .filter(|claim_variable| {
+                        operation
+                            .variables
+                            .iter()
+                            .any(|variable| variable.name == claim_variable.variable.as_str())
+                    })
+                    .cloned()
+                    .collect(),
+                header_set,
+                null_variables,
+                coerced_scalars: if options.coerce_scalars {
+                    operation
+                        .variables
+                        .iter()
+                        .filter_map(|variable| {
+                            CoercedScalar::for_type(graphql_schema, variable.ty.inner_named_type())
+                                .map(|scalar| (variable.name.to_string(), scalar))
+                        })
+                        .collect()
+                } else {
+                    HashMap::new()
+                },
+                split_list_variables: if options.split_list_strings {
+                    operation
+                        .variables
+                        .iter()
+                        .filter(|variable| {
+                            variable.ty.is_list()
+                                && !variable.ty.item_type().is_list()
+                                && graphql_schema
+                                    .types
+                                    .get(variable.ty.inner_named_type())
+                                    .is_some_and(ExtendedType::is_scalar)
+                        })
+                        .map(|variable| variable.name.to_string())
+                        .collect()
+                } else {
+                    Vec::new()
+                },
+                mutation_mode,
+                return_types: Self::return_types_of(&operation, graphql_schema),
+                categories,
+                next_operations,
+                required_operations,
+                computed_defaults,
+                dependent_required,
+                priority,
+                requires_confirmation,
+                cache_ttl,
+                output_schema: (options.output_validation != OutputValidation::Off)
+                    .then(|| graphql::output_schema(graphql_schema, &document, &operation))
+                    .flatten(),
+                output_validation: options.output_validation,
             }))
         } else {
             Ok(None)
@@ -604,8 +2751,7 @@ impl Operation {
         tree_shaker: &mut SchemaTreeShaker,
         graphql_schema: &GraphqlSchema,
         operation_def: &Node<OperationDefinition>,
-        disable_type_description: bool,
-        disable_schema_description: bool,
+        options: &ToolOptions,
     ) -> String {
         let comment_description = extract_and_format_comments(comments);
 
@@ -614,7 +2760,7 @@ impl Operation {
             None => {
                 // Add the tree-shaken types to the end of the tool description
                 let mut lines = vec![];
-                if !disable_type_description {
+                if !options.disable_type_description {
                     let descriptions = operation_def
                         .selection_set
                         .iter()
@@ -647,8 +2793,9 @@ impl Operation {
 
                                         // Add information about the return type
                                         let ty = field_definition.map(|field| field.ty.clone());
-                                        let type_description =
-                                            ty.as_ref().map(Self::type_description);
+                                        let type_description = ty
+                                            .as_ref()
+                                            .map(|ty| options.return_type_description.describe(ty));
 
                                         Some(
                                             vec![field_description, type_description]
@@ -671,9 +2818,13 @@ impl Operation {
 
                     lines.push(descriptions);
                 }
-                if !disable_schema_description {
-                    let shaken_schema =
-                        tree_shaker.shaken().unwrap_or_else(|schema| schema.partial);
+                if !options.disable_schema_description {
+                    let shaken_schema = match &options.shake_cache {
+                        Some(shake_cache) => shake_cache.get_or_shake(tree_shaker),
+                        None => {
+                            Arc::new(tree_shaker.shaken().unwrap_or_else(|schema| schema.partial))
+                        }
+                    };
 
                     let mut types = shaken_schema
                         .types
@@ -696,38 +2847,53 @@ impl Operation {
                                     .is_none_or(|op_name| extended_type.name() != op_name)
                         })
                         .peekable();
-                    if types.peek().is_some() {
+                    let root_description = graphql_schema
+                        .schema_definition
+                        .description
+                        .as_ref()
+                        .filter(|_| options.include_root_schema_description);
+                    if root_description.is_some() || types.peek().is_some() {
                         lines.push(String::from("---"));
                     }
+                    if let Some(description) = root_description {
+                        lines.push(description.to_string());
+                    }
 
-                    for ty in types {
-                        lines.push(ty.1.serialize().to_string());
+                    // The types are keyed by name, so each is only defined once
+                    let sdl = types
+                        .map(|(_, ty)| normalize_whitespace(&ty.serialize().to_string()))
+                        .filter(|definition| !definition.is_empty())
+                        .collect::<Vec<_>>()
+                        .join("\n\n");
+                    if !sdl.is_empty() {
+                        lines.push(sdl);
+                    }
+                } else if let Some(note) = &options.schema_description_note {
+                    if !lines.is_empty() {
+                        lines.push(String::from("---"));
                     }
+                    lines.push(note.clone());
                 }
                 lines.join("\n")
             }
         }
     }
+}
 
-    fn type_description(ty: &Type) -> String {
-        let type_name = ty.inner_named_type();
-        let mut lines = vec![];
-        let optional = if ty.is_non_null() {
-            ""
-        } else {
-            "is optional and "
-        };
-        let array = if ty.is_list() {
-            "is an array of type"
-        } else {
-            "has type"
-        };
-        lines.push(format!(
-            "The returned value {optional}{array} `{type_name}`"
-        ));
-
-        lines.join("\n")
+/// Remove trailing whitespace from each line, collapse runs of blank lines into one, and drop
+/// leading and trailing blank lines, to save tokens in descriptions
+fn normalize_whitespace(text: &str) -> String {
+    let mut normalized: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim_end) {
+        if line.is_empty() && normalized.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        normalized.push(line);
     }
+    while normalized.last().is_some_and(|line| line.is_empty()) {
+        normalized.pop();
+    }
+    normalized.join("\n")
 }
 
 fn ensure_properties_exists(json_object: &mut Value) {
@@ -759,58 +2925,298 @@ pub fn operation_name(
         .to_string())
 }
 
-fn tool_character_length(tool: &Tool) -> Result<usize, serde_json::Error> {
+/// The text of a tool as seen by the model
+fn tool_text(tool: &Tool) -> Result<String, serde_json::Error> {
     let tool_schema_string = serde_json::to_string_pretty(&serde_json::json!(tool.input_schema))?;
-    Ok(tool.name.len()
-        + tool.description.as_ref().map(|d| d.len()).unwrap_or(0)
-        + tool_schema_string.len())
+    Ok(format!(
+        "{}{}{}",
+        tool.name,
+        tool.description.as_deref().unwrap_or_default(),
+        tool_schema_string
+    ))
 }
 
-fn get_json_schema(
+/// Get the description of a variable, preferring an overridden description to the schema description
+fn variable_description(
+    variable_name: &str,
+    schema_argument_descriptions: &HashMap<String, Vec<String>>,
+    argument_descriptions_overrides: &HashMap<String, String>,
+) -> Option<String> {
+    match argument_descriptions_overrides.get(variable_name) {
+        Some(description) => Some(description.clone()),
+        None => schema_argument_descriptions
+            .get(variable_name)
+            .filter(|d| !d.is_empty())
+            .map(|d| d.join("#")),
+    }
+}
+
+/// Summarize the descriptions of the variables accepted by a tool, e.g. `id — the product id`
+fn variables_summary(
     operation: &Node<OperationDefinition>,
     schema_argument_descriptions: &HashMap<String, Vec<String>>,
     argument_descriptions_overrides: &HashMap<String, String>,
+    variable_overrides: Option<&HashMap<String, Value>>,
+) -> Option<String> {
+    let summary = operation
+        .variables
+        .iter()
+        .filter(|variable| {
+            !variable_overrides
+                .map(|o| o.contains_key(variable.name.as_str()))
+                .unwrap_or_default()
+        })
+        .filter_map(|variable| {
+            variable_description(
+                variable.name.as_str(),
+                schema_argument_descriptions,
+                argument_descriptions_overrides,
+            )
+            .map(|description| format!("{} — {}", variable.name, description.trim()))
+        })
+        .collect::<Vec<_>>();
+    (!summary.is_empty()).then(|| summary.join("; "))
+}
+
+/// An example of the arguments to call an operation's tool with. Custom scalars use the first of
+/// the `examples` in their JSON schema, and other types use placeholder values.
+fn example_arguments(
+    operation: &Node<OperationDefinition>,
+    graphql_schema: &GraphqlSchema,
+    custom_scalar_map: Option<&CustomScalarMap>,
+    variable_overrides: Option<&HashMap<String, Value>>,
+    options: &ToolOptions,
+) -> Option<Value> {
+    let arguments: serde_json::Map<String, Value> = operation
+        .variables
+        .iter()
+        .filter(|variable| {
+            !variable_overrides.is_some_and(|o| o.contains_key(variable.name.as_str()))
+                && !options
+                    .claim_variables
+                    .iter()
+                    .any(|claim_variable| claim_variable.variable == variable.name.as_str())
+        })
+        .map(|variable| {
+            (
+                variable.name.to_string(),
+                example_value(
+                    &variable.ty,
+                    graphql_schema,
+                    custom_scalar_map,
+                    &mut Vec::new(),
+                ),
+            )
+        })
+        .collect();
+    if arguments.is_empty() {
+        return None;
+    }
+    let arguments = Value::Object(arguments);
+    Some(if options.variables_as_object {
+        serde_json::json!({ VARIABLES_ARGUMENT_NAME: arguments })
+    } else {
+        arguments
+    })
+}
+
+/// An example value of a type. Input objects only include their required fields, and are `null`
+/// when nested within themselves.
+fn example_value(
+    ty: &Type,
+    graphql_schema: &GraphqlSchema,
+    custom_scalar_map: Option<&CustomScalarMap>,
+    input_types: &mut Vec<Name>,
+) -> Value {
+    let name = match ty {
+        Type::List(inner) | Type::NonNullList(inner) => {
+            return Value::Array(vec![example_value(
+                inner,
+                graphql_schema,
+                custom_scalar_map,
+                input_types,
+            )]);
+        }
+        Type::Named(name) | Type::NonNullNamed(name) => name,
+    };
+    match name.as_str() {
+        "String" => Value::from("text"),
+        "ID" => Value::from("abc123"),
+        "Int" => Value::from(1),
+        "Float" => Value::from(1.5),
+        "Boolean" => Value::from(true),
+        _ => match graphql_schema.types.get(name) {
+            Some(ExtendedType::Enum(enum_type)) => enum_type
+                .values
+                .keys()
+                .next()
+                .map(|value| Value::from(value.as_str()))
+                .unwrap_or_default(),
+            Some(ExtendedType::InputObject(input)) if !input_types.contains(name) => {
+                input_types.push(name.clone());
+                let fields = input
+                    .fields
+                    .iter()
+                    .filter(|(_, field)| field.ty.is_non_null() && field.default_value.is_none())
+                    .map(|(field_name, field)| {
+                        (
+                            field_name.to_string(),
+                            example_value(
+                                &field.ty,
+                                graphql_schema,
+                                custom_scalar_map,
+                                input_types,
+                            ),
+                        )
+                    })
+                    .collect();
+                input_types.pop();
+                Value::Object(fields)
+            }
+            Some(ExtendedType::Scalar(_)) => custom_scalar_map
+                .and_then(|map| map.get(name))
+                .and_then(|schema| schema.metadata.as_ref())
+                .and_then(|metadata| metadata.examples.first())
+                .cloned()
+                .unwrap_or_else(|| Value::from("value")),
+            _ => Value::Null,
+        },
+    }
+}
+
+fn get_json_schema(
+    operation: &Node<OperationDefinition>,
+    tree_shaker: &SchemaTreeShaker,
+    argument_descriptions_overrides: &HashMap<String, String>,
     graphql_schema: &GraphqlSchema,
     custom_scalar_map: Option<&CustomScalarMap>,
     variable_overrides: Option<&HashMap<String, Value>>,
+    options: &ToolOptions,
 ) -> RootSchema {
     let mut obj = ObjectValidation::default();
     let mut definitions = Map::new();
+    let mut required_variables = Vec::new();
+    let mut optional_variables = Vec::new();
 
     operation.variables.iter().for_each(|variable| {
         let variable_name = variable.name.to_string();
         if !variable_overrides
             .map(|o| o.contains_key(&variable_name))
             .unwrap_or_default()
+            && !options
+                .claim_variables
+                .iter()
+                .any(|claim_variable| claim_variable.variable == variable_name)
         {
-            // use overridden description if there is one, otherwise use the schema description
-            let description: Option<String> =
-                match argument_descriptions_overrides.get(&variable_name) {
-                    Some(description) => Some(description.clone()),
-                    None => schema_argument_descriptions
-                        .get(&variable_name)
-                        .filter(|d| !d.is_empty())
-                        .map(|d| d.join("#")),
-                };
+            let description = variable_description(
+                &variable_name,
+                tree_shaker.argument_descriptions(),
+                argument_descriptions_overrides,
+            );
 
-            let schema = type_to_schema(
+            let mut schema = type_to_schema(
                 description,
                 variable.ty.as_ref(),
                 graphql_schema,
                 custom_scalar_map,
+                options,
                 &mut definitions,
             );
-            obj.properties.insert(variable_name.clone(), schema);
-            if variable.ty.is_non_null() {
-                obj.required.insert(variable_name);
+            if let Some(directives) = tree_shaker.argument_directives().get(&variable_name) {
+                apply_constraints(&mut schema, directives, options);
+            }
+            if options.describe_graphql_types
+                && let Schema::Object(schema) = &mut schema
+            {
+                // Described on the variable itself, since list variables describe their items
+                let graphql_type = format!("GraphQL type: `{}`", variable.ty);
+                let metadata = schema.metadata();
+                metadata.description = Some(match metadata.description.take() {
+                    Some(description) => format!("{description} ({graphql_type})"),
+                    None => graphql_type,
+                });
+            }
+            if options.property_titles {
+                set_title(&mut schema, &variable_name);
+            }
+            obj.properties.insert(variable_name.clone(), schema);
+            if variable.ty.is_non_null() {
+                required_variables.push(variable_name.clone());
+                obj.required.insert(variable_name);
+            } else {
+                optional_variables.push(variable_name);
             }
         }
     });
 
+    if options.variables_as_object {
+        // Nest the variables under a single object argument. Definitions stay at the root so
+        // that references to them still resolve.
+        let mut variables = ObjectValidation::default();
+        if !obj.required.is_empty() {
+            variables
+                .required
+                .insert(VARIABLES_ARGUMENT_NAME.to_string());
+        }
+        variables.properties.insert(
+            VARIABLES_ARGUMENT_NAME.to_string(),
+            Schema::Object(SchemaObject {
+                instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::Object))),
+                object: Some(Box::new(obj)),
+                ..Default::default()
+            }),
+        );
+        obj = variables;
+    }
+
+    if options.result_projection {
+        obj.properties.insert(
+            PROJECT_ARGUMENT_NAME.to_string(),
+            schema_factory(
+                Some("Return only the part of the response at this path, such as `data.user.name` or `data.users[0].id`".to_string()),
+                Some(InstanceType::String),
+                None,
+                None,
+                None,
+                None,
+            ),
+        );
+    }
+
+    if options.cost_estimates && operation.operation_type == OperationType::Query {
+        obj.properties.insert(
+            ESTIMATE_ARGUMENT_NAME.to_string(),
+            schema_factory(
+                Some(
+                    "Return the estimated cost of the operation instead of executing it"
+                        .to_string(),
+                ),
+                Some(InstanceType::Boolean),
+                None,
+                None,
+                None,
+                None,
+            ),
+        );
+    }
+
+    let mut extensions = Map::new();
+    if options.variable_summary {
+        // A summary for clients which don't parse the schema, which stays authoritative
+        extensions.insert(
+            VARIABLE_SUMMARY_KEYWORD.to_string(),
+            serde_json::json!({
+                "required": required_variables,
+                "optional": optional_variables,
+            }),
+        );
+    }
+
     RootSchema {
         schema: SchemaObject {
             instance_type: Some(SingleOrVec::Single(Box::new(InstanceType::Object))),
             object: Some(Box::new(obj)),
+            extensions,
             ..Default::default()
         },
         definitions,
@@ -841,6 +3247,95 @@ fn schema_factory(
     })
 }
 
+/// Set the title of a property's schema to its name in title case
+fn set_title(schema: &mut Schema, name: &str) {
+    if let Schema::Object(schema) = schema {
+        schema.metadata().title = Some(title_case(name));
+    }
+}
+
+/// Convert a camelCase or snake_case name to title case, e.g. `firstName` to `First Name`. Runs
+/// of capitals are kept together as one word, so `userID` becomes `User ID`.
+fn title_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    for (index, &c) in chars.iter().enumerate() {
+        if c == '_' || c == '-' {
+            words.push(std::mem::take(&mut word));
+            continue;
+        }
+        let previous = index.checked_sub(1).and_then(|index| chars.get(index));
+        let next = chars.get(index + 1);
+        let starts_word = c.is_uppercase()
+            && previous.is_some_and(|previous| {
+                previous.is_lowercase()
+                    || previous.is_ascii_digit()
+                    || (previous.is_uppercase() && next.is_some_and(|next| next.is_lowercase()))
+            });
+        if starts_word {
+            words.push(std::mem::take(&mut word));
+        }
+        word.push(c);
+    }
+    words.push(word);
+    words
+        .iter()
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Add the JSON schema keywords matching the arguments of the configured constraint directive,
+/// such as `@constraint(minLength: 1, pattern: "^[a-z]+$")`, to a schema
+fn apply_constraints(schema: &mut Schema, directives: &DirectiveList, options: &ToolOptions) {
+    let (Some(directive_name), Schema::Object(schema)) =
+        (options.constraint_directive.as_deref(), schema)
+    else {
+        return;
+    };
+    for directive in directives.get_all(directive_name) {
+        let int = |name: &str| {
+            directive
+                .specified_argument_by_name(name)
+                .and_then(|value| value.to_i32())
+                .and_then(|value| u32::try_from(value).ok())
+        };
+        let number = |names: [&str; 2]| {
+            names.into_iter().find_map(|name| {
+                directive
+                    .specified_argument_by_name(name)
+                    .and_then(|value| value.to_f64())
+            })
+        };
+        if let Some(min_length) = int("minLength") {
+            schema.string().min_length = Some(min_length);
+        }
+        if let Some(max_length) = int("maxLength") {
+            schema.string().max_length = Some(max_length);
+        }
+        if let Some(pattern) = directive
+            .specified_argument_by_name("pattern")
+            .and_then(|value| value.as_str())
+        {
+            schema.string().pattern = Some(pattern.to_string());
+        }
+        if let Some(minimum) = number(["minimum", "min"]) {
+            schema.number().minimum = Some(minimum);
+        }
+        if let Some(maximum) = number(["maximum", "max"]) {
+            schema.number().maximum = Some(maximum);
+        }
+    }
+}
+
 fn input_object_description(name: &Name, graphql_schema: &GraphqlSchema) -> Option<String> {
     if let Some(input_object) = graphql_schema.get_input_object(name) {
         input_object.description.as_ref().map(|d| d.to_string())
@@ -882,12 +3377,21 @@ fn type_to_schema(
     variable_type: &Type,
     graphql_schema: &GraphqlSchema,
     custom_scalar_map: Option<&CustomScalarMap>,
+    options: &ToolOptions,
     definitions: &mut Map<String, Schema>,
 ) -> Schema {
+    // Built-in scalars are not given definitions, so their descriptions are applied inline
+    let builtin_description = |description: Option<String>, named: &Name| match (
+        description,
+        options.builtin_scalar_descriptions.get(named.as_str()),
+    ) {
+        (Some(description), Some(builtin)) => Some(format!("{description}\n{builtin}")),
+        (description, builtin) => description.or_else(|| builtin.cloned()),
+    };
     match variable_type {
         Type::NonNullNamed(named) | Type::Named(named) => match named.as_str() {
             "String" | "ID" => schema_factory(
-                description,
+                builtin_description(description, named),
                 Some(InstanceType::String),
                 None,
                 None,
@@ -895,7 +3399,7 @@ fn type_to_schema(
                 None,
             ),
             "Int" | "Float" => schema_factory(
-                description,
+                builtin_description(description, named),
                 Some(InstanceType::Number),
                 None,
                 None,
@@ -903,7 +3407,7 @@ fn type_to_schema(
                 None,
             ),
             "Boolean" => schema_factory(
-                description,
+                builtin_description(description, named),
                 Some(InstanceType::Boolean),
                 None,
                 None,
@@ -912,6 +3416,22 @@ fn type_to_schema(
             ),
             _ => {
                 if let Some(input_type) = graphql_schema.get_input_object(named) {
+                    if !definitions.contains_key(named.as_str())
+                        && options
+                            .max_input_definitions
+                            .is_some_and(|max| definitions.len() >= max)
+                    {
+                        // Stop expanding definitions, and accept any object for this type
+                        warn!(name=?named, "Input type definition limit reached");
+                        return schema_factory(
+                            description,
+                            Some(InstanceType::Object),
+                            None,
+                            None,
+                            None,
+                            None,
+                        );
+                    }
                     if !definitions.contains_key(named.as_str()) {
                         definitions
                             .insert(named.to_string(), Schema::Object(SchemaObject::default())); // Insert temporary value into map so any recursive references will not try to also create it.
@@ -919,16 +3439,19 @@ fn type_to_schema(
 
                         input_type.fields.iter().for_each(|(name, field)| {
                             let description = field.description.as_ref().map(|n| n.to_string());
-                            obj.properties.insert(
-                                name.to_string(),
-                                type_to_schema(
-                                    description,
-                                    field.ty.as_ref(),
-                                    graphql_schema,
-                                    custom_scalar_map,
-                                    definitions,
-                                ),
+                            let mut schema = type_to_schema(
+                                description,
+                                field.ty.as_ref(),
+                                graphql_schema,
+                                custom_scalar_map,
+                                options,
+                                definitions,
                             );
+                            apply_constraints(&mut schema, &field.directives, options);
+                            if options.property_titles {
+                                set_title(&mut schema, name);
+                            }
+                            obj.properties.insert(name.to_string(), schema);
 
                             if field.is_required() {
                                 obj.required.insert(name.to_string());
@@ -1004,23 +3527,25 @@ fn type_to_schema(
                     })
                 } else if let Some(enum_type) = graphql_schema.get_enum(named) {
                     if !definitions.contains_key(named.as_str()) {
-                        definitions.insert(
-                            named.to_string(),
-                            schema_factory(
-                                input_object_description(named, graphql_schema),
-                                Some(InstanceType::String),
-                                None,
-                                None,
-                                None,
-                                Some(
-                                    enum_type
-                                        .values
-                                        .iter()
-                                        .map(|(_name, value)| serde_json::json!(value.value))
-                                        .collect(),
-                                ),
-                            ),
+                        let mut values: Vec<Value> = enum_type
+                            .values
+                            .iter()
+                            .map(|(_name, value)| serde_json::json!(value.value))
+                            .collect();
+                        // An enum with a single value is a constant
+                        let const_value = (values.len() == 1).then(|| values.remove(0));
+                        let mut schema = schema_factory(
+                            input_object_description(named, graphql_schema),
+                            Some(InstanceType::String),
+                            None,
+                            None,
+                            None,
+                            const_value.is_none().then_some(values),
                         );
+                        if let Schema::Object(object) = &mut schema {
+                            object.const_value = const_value;
+                        }
+                        definitions.insert(named.to_string(), schema);
                     }
                     Schema::Object(SchemaObject {
                         metadata: Some(Box::new(Metadata {
@@ -1042,30 +3567,13 @@ fn type_to_schema(
                 list_type,
                 graphql_schema,
                 custom_scalar_map,
+                options,
                 definitions,
             );
             let items_schema = if list_type.is_non_null() {
                 inner_type_schema
             } else {
-                schema_factory(
-                    None,
-                    None,
-                    None,
-                    None,
-                    Some(SubschemaValidation {
-                        one_of: Some(vec![
-                            inner_type_schema,
-                            Schema::Object(SchemaObject {
-                                instance_type: Some(SingleOrVec::Single(Box::new(
-                                    InstanceType::Null,
-                                ))),
-                                ..Default::default()
-                            }),
-                        ]),
-                        ..Default::default()
-                    }),
-                    None,
-                )
+                options.nullable_style.apply(inner_type_schema)
             };
 
             schema_factory(
@@ -1096,7 +3604,37 @@ impl graphql::Executable for Operation {
         })
     }
 
-    fn variables(&self, input_variables: Value) -> Result<Value, McpError> {
+    fn variables(
+        &self,
+        input_variables: Value,
+        claims: Option<&serde_json::Map<String, Value>>,
+    ) -> Result<Value, McpError> {
+        self.coerced_variables(input_variables, claims)
+            .map(|(variables, _)| variables)
+    }
+
+    fn coerced_variables(
+        &self,
+        input_variables: Value,
+        claims: Option<&serde_json::Map<String, Value>>,
+    ) -> Result<(Value, Vec<String>), McpError> {
+        let mut coercions = Vec::new();
+        let input_variables = if self.variables_as_object {
+            match input_variables {
+                Value::Null => Value::Null,
+                Value::Object(mut obj) => obj.remove(VARIABLES_ARGUMENT_NAME).unwrap_or_default(),
+                _ => {
+                    return Err(McpError::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "Invalid input".to_string(),
+                        None,
+                    ));
+                }
+            }
+        } else {
+            input_variables
+        };
+
         if let Some(raw_variables) = self.inner.variables.as_ref() {
             let mut variables = match input_variables {
                 Value::Null => Ok(serde_json::Map::new()),
@@ -1125,41 +3663,99 @@ impl graphql::Executable for Operation {
         } else {
             Ok(input_variables)
         }
+        .map(|variables| match (self.null_variables, variables) {
+            (NullVariables::Omit, Value::Object(mut variables)) => {
+                variables.retain(|_, value| !value.is_null());
+                Value::Object(variables)
+            }
+            (_, variables) => variables,
+        })
+        .map(|variables| self.split_list_strings(variables, &mut coercions))
+        .and_then(|variables| self.coerce_scalars(variables, &mut coercions))
+        .and_then(|variables| self.compute_defaults(variables))
+        .and_then(|variables| self.inject_claim_variables(variables, claims))
+        .and_then(|variables| {
+            self.check_dependent_required(&variables)
+                .map(|()| variables)
+        })
+        .map(|variables| (variables, coercions))
     }
 
     fn headers(&self, default_headers: &HeaderMap<HeaderValue>) -> HeaderMap<HeaderValue> {
+        // A selected header set overrides the default headers of the same name
+        let default_headers = &match &self.header_set {
+            Some(header_set) => {
+                let mut headers = default_headers.clone();
+                header_set.iter().for_each(|(key, value)| {
+                    headers.insert(key, value.clone());
+                });
+                headers
+            }
+            None => default_headers.clone(),
+        };
         match self.inner.headers.as_ref() {
             None => default_headers.clone(),
             Some(raw_headers) if default_headers.is_empty() => raw_headers.clone(),
             Some(raw_headers) => {
                 let mut headers = default_headers.clone();
-                raw_headers.iter().for_each(|(key, value)| {
-                    if headers.contains_key(key) {
+                raw_headers.keys().for_each(|key| {
+                    if headers.remove(key).is_some() {
                         tracing::debug!(
                             "Header {} has a default value, overwriting with operation value",
                             key
                         );
                     }
-                    headers.insert(key, value.clone());
+                });
+                raw_headers.iter().for_each(|(key, value)| {
+                    headers.append(key, value.clone());
                 });
                 headers
             }
         }
     }
+
+    fn execution_policy(&self) -> ExecutionPolicy {
+        self.inner.policy
+    }
+
+    fn output_validation(&self) -> Option<(&Value, OutputValidation)> {
+        self.output_schema
+            .as_ref()
+            .map(|output_schema| (output_schema, self.output_validation))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::errors::OperationError;
     use crate::graphql::Executable;
     use apollo_compiler::{Schema, parser::Parser, validation::Valid};
+    use futures::StreamExt;
+    use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
     use rmcp::serde_json::Value;
     use rmcp::{model::Tool, serde_json};
-    use std::{collections::HashMap, str::FromStr, sync::LazyLock};
+    use std::{collections::HashMap, str::FromStr, sync::LazyLock, time::Duration};
     use tracing_test::traced_test;
 
     use crate::{
         custom_scalar_map::CustomScalarMap,
-        operations::{MutationMode, Operation, RawOperation},
+        event::Event,
+        graphql,
+        introspection::tools::execute::Execute,
+        operations::{
+            CONFIRM_ARGUMENT_NAME, ClaimVariable, DefinitionsKeyword, DuplicateOperations,
+            ErrorCodes, GraphQLErrors, MaxTools, MutationMode, NullVariables, NullableStyle,
+            Operation, OperationCache, OperationKind, OperationSource, OutputValidation,
+            RawOperation, ReturnTypeDescription, SharedFragments, ToolOptions, ToolOrder,
+            VARIABLE_SUMMARY_KEYWORD, check_operation_dependencies, convert_operations,
+            dedupe_identical_operations, limit_tools, resolve_duplicate_operations,
+            restore_colliding_tool_names, title_case,
+        },
+    };
+    use apollo_mcp_registry::platform_api::operation_collections::collection_poller::OperationData;
+    use apollo_mcp_registry::platform_api::operation_collections::event::CollectionEvent;
+    use apollo_mcp_registry::uplink::persisted_queries::{
+        ExecutionPolicy, PersistedQueryManifest, SignedUrlChunk,
     };
 
     // Example schema for tests
@@ -1233,12 +3829,12 @@ mod tests {
                     headers: None,
                     variables: None,
                     source_path: None,
+                    policy: ExecutionPolicy::default(),
+                    description: None,
                 },
                 &SCHEMA,
                 None,
-                MutationMode::None,
-                false,
-                false,
+                &ToolOptions::default(),
             )
             .unwrap()
             .is_none()
@@ -1255,12 +3851,12 @@ mod tests {
                     headers: None,
                     variables: None,
                     source_path: None,
+                    policy: ExecutionPolicy::default(),
+                    description: None,
                 },
                 &SCHEMA,
                 None,
-                MutationMode::None,
-                false,
-                false,
+                &ToolOptions::default(),
             )
             .ok()
             .unwrap()
@@ -1277,12 +3873,15 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::Explicit,
-            false,
-            false,
+            &ToolOptions {
+                mutation_mode: MutationMode::Explicit,
+                ..Default::default()
+            },
         )
         .unwrap()
         .unwrap();
@@ -1316,8 +3915,33 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy {
+                    timeout: None,
+                    max_response_bytes: None,
+                },
+                description: None,
             },
             operation_name: "MutationName",
+            variables_as_object: false,
+            claim_variables: [],
+            header_set: None,
+            null_variables: Keep,
+            coerced_scalars: {},
+            split_list_variables: [],
+            mutation_mode: Explicit,
+            return_types: [
+                "String",
+            ],
+            categories: [],
+            next_operations: [],
+            required_operations: [],
+            computed_defaults: [],
+            dependent_required: [],
+            priority: 0,
+            requires_confirmation: false,
+            cache_ttl: None,
+            output_schema: None,
+            output_validation: Off,
         }
         "#);
     }
@@ -1331,12 +3955,15 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::All,
-            false,
-            false,
+            &ToolOptions {
+                mutation_mode: MutationMode::All,
+                ..Default::default()
+            },
         )
         .unwrap()
         .unwrap();
@@ -1370,8 +3997,33 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy {
+                    timeout: None,
+                    max_response_bytes: None,
+                },
+                description: None,
             },
             operation_name: "MutationName",
+            variables_as_object: false,
+            claim_variables: [],
+            header_set: None,
+            null_variables: Keep,
+            coerced_scalars: {},
+            split_list_variables: [],
+            mutation_mode: All,
+            return_types: [
+                "String",
+            ],
+            categories: [],
+            next_operations: [],
+            required_operations: [],
+            computed_defaults: [],
+            dependent_required: [],
+            priority: 0,
+            requires_confirmation: false,
+            cache_ttl: None,
+            output_schema: None,
+            output_validation: Off,
         }
         "#);
     }
@@ -1385,12 +4037,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -1436,12 +4088,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -1495,12 +4147,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -1560,12 +4212,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -1645,12 +4297,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -1716,12 +4368,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -1795,12 +4447,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -1860,12 +4512,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -1959,12 +4611,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -2026,12 +4678,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -2088,12 +4740,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: Some("operation.graphql".to_string()),
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         );
         insta::assert_debug_snapshot!(operation, @r#"
         Err(
@@ -2107,6 +4759,58 @@ mod tests {
         "#);
     }
 
+    #[test]
+    fn multiple_operations_are_split() {
+        let raw_operation = RawOperation::from((
+            "# Get the ID\nquery GetId { id }\n\nfragment Fields on OutputType { id }\n\n# Get the custom output\nquery GetCustom { customQuery(id: \"1\") { ...Fields } }".to_string(),
+            Some("operations.graphql".to_string()),
+        ));
+
+        let operations = raw_operation
+            .clone()
+            .split(&ToolOptions::default())
+            .into_iter()
+            .map(|raw_operation| {
+                Operation::from_document(raw_operation, &SCHEMA, None, &ToolOptions::default())
+                    .unwrap()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            operations
+                .iter()
+                .map(|operation| operation.as_ref().name.to_string())
+                .collect::<Vec<_>>(),
+            vec!["GetId", "GetCustom"]
+        );
+        assert!(
+            operations
+                .first()
+                .and_then(|operation| operation.as_ref().description.as_deref())
+                .unwrap_or_default()
+                .starts_with("Get the ID")
+        );
+        insta::assert_snapshot!(operations.get(1).unwrap().operation(Value::Null).unwrap().query, @r#"
+        # Get the custom output
+        query GetCustom { customQuery(id: "1") { ...Fields } }
+
+        fragment Fields on OutputType {
+          id
+        }
+        "#);
+
+        // Documents are not split when single operation documents are required
+        assert_eq!(
+            raw_operation
+                .split(&ToolOptions {
+                    single_operation_documents: true,
+                    ..Default::default()
+                })
+                .len(),
+            1
+        );
+    }
+
     #[test]
     #[traced_test]
     fn unnamed_operations_should_be_skipped() {
@@ -2117,12 +4821,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: Some("operation.graphql".to_string()),
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         );
         assert!(operation.unwrap().is_none());
 
@@ -2147,12 +4851,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: Some("operation.graphql".to_string()),
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         );
         insta::assert_debug_snapshot!(operation, @r#"
         Err(
@@ -2174,12 +4878,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         );
         insta::assert_debug_snapshot!(operation, @r"
         Err(
@@ -2200,12 +4904,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -2258,12 +4962,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -2323,12 +5027,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             Some(&CustomScalarMap::from_str("{}").unwrap()),
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -2394,12 +5098,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             custom_scalar_map.ok().as_ref(),
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -2576,12 +5280,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &schema,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -2651,11 +5355,7 @@ mod tests {
                 source_text: r###"
             # Overridden tool #description
             query GetABZ($state: String!) {
-              b {
-                d {
-                  f
-                }
-              }
+              id
             }
             "###
                 .to_string(),
@@ -2663,12 +5363,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -2696,12 +5396,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -2721,12 +5421,15 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            true,
+            &ToolOptions {
+                disable_schema_description: true,
+                ..Default::default()
+            },
         )
         .unwrap()
         .unwrap();
@@ -2741,6 +5444,87 @@ mod tests {
         );
     }
 
+    #[test]
+    fn shaken_schema_defines_each_type_once_with_normalized_whitespace() {
+        let schema = Schema::parse_and_validate(
+            "type Query { me: User, users: [User], friend(id: ID): User }\n\"\"\"\nA user   \n\n\n\nof the site\n\"\"\"\ntype User { id: ID, name: String }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "query Users { me { id } users { name } friend(id: 1) { id name } }".to_string(),
+                None,
+            )),
+            &schema,
+            None,
+            &ToolOptions {
+                disable_type_description: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+        let description = operation.tool.description.unwrap_or_default();
+
+        assert_eq!(description.matches("type User").count(), 1);
+        assert!(!description.contains("\n\n\n"));
+        assert!(description.lines().all(|line| line.trim_end() == line));
+        insta::assert_snapshot!(description, @r#"
+        ---
+        """
+        A user
+
+        of the site
+        """
+        type User {
+          id: ID
+          name: String
+        }
+        "#);
+    }
+
+    #[test]
+    fn root_schema_description() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            """The weather service"""
+            schema { query: Query }
+            type Query { forecast: Forecast }
+            type Forecast { detailed: String }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let description = |include_root_schema_description| {
+            let operation = Operation::from_document(
+                RawOperation::from((
+                    "query GetForecast { forecast { detailed } }".to_string(),
+                    None,
+                )),
+                &schema,
+                None,
+                &ToolOptions {
+                    disable_type_description: true,
+                    include_root_schema_description,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            operation.tool.description.unwrap_or_default()
+        };
+
+        insta::assert_snapshot!(description(true), @r"
+        ---
+        The weather service
+        type Forecast {
+          detailed: String
+        }
+        ");
+        assert!(!description(false).contains("The weather service"));
+    }
+
     #[test]
     fn no_type_description() {
         let operation = Operation::from_document(
@@ -2750,12 +5534,15 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            true,
-            false,
+            &ToolOptions {
+                disable_type_description: true,
+                ..Default::default()
+            },
         )
         .unwrap()
         .unwrap();
@@ -2784,12 +5571,16 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            true,
-            true,
+            &ToolOptions {
+                disable_type_description: true,
+                disable_schema_description: true,
+                ..Default::default()
+            },
         )
         .unwrap()
         .unwrap();
@@ -2800,6 +5591,57 @@ mod tests {
         );
     }
 
+    #[test]
+    fn schema_description_note_replaces_disabled_schema_description() {
+        let description = |disable_type_description| {
+            Operation::from_document(
+                RawOperation::from(("query GetId { id }".to_string(), None)),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    disable_type_description,
+                    disable_schema_description: true,
+                    schema_description_note: Some(
+                        "Use the search tool to explore types".to_string(),
+                    ),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap()
+            .tool
+            .description
+            .unwrap()
+        };
+
+        insta::assert_snapshot!(description(false), @"
+        The returned value is optional and has type `String`
+        ---
+        Use the search tool to explore types
+        ");
+        insta::assert_snapshot!(description(true), @"Use the search tool to explore types");
+
+        // The note is not added when the schema description is enabled
+        let operation = Operation::from_document(
+            RawOperation::from(("query GetId { id }".to_string(), None)),
+            &SCHEMA,
+            None,
+            &ToolOptions {
+                schema_description_note: Some("Use the search tool to explore types".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+        assert!(
+            !operation
+                .tool
+                .description
+                .unwrap()
+                .contains("Use the search tool")
+        );
+    }
+
     #[test]
     fn recursive_inputs() {
         let operation = Operation::from_document(
@@ -2814,6 +5656,8 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &Schema::parse(
                 r#"
@@ -2836,9 +5680,11 @@ mod tests {
             )
             .unwrap(),
             None,
-            MutationMode::None,
-            true,
-            true,
+            &ToolOptions {
+                disable_type_description: true,
+                disable_schema_description: true,
+                ..Default::default()
+            },
         )
         .unwrap()
         .unwrap();
@@ -2885,94 +5731,2009 @@ mod tests {
                     open_world_hint: None,
                 },
             ),
-        }
-        "##);
+        }
+        "##);
+    }
+
+    #[test]
+    fn with_variable_overrides() {
+        let operation = Operation::from_document(
+            RawOperation {
+                source_text: "query QueryName($id: ID, $name: String) { id }".to_string(),
+                persisted_query_id: None,
+                headers: None,
+                variables: Some(HashMap::from([(
+                    "id".to_string(),
+                    serde_json::Value::String("v".to_string()),
+                )])),
+                source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
+            },
+            &SCHEMA,
+            None,
+            &ToolOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+        let tool = Tool::from(operation);
+
+        insta::assert_debug_snapshot!(tool, @r#"
+        Tool {
+            name: "QueryName",
+            description: Some(
+                "The returned value is optional and has type `String`",
+            ),
+            input_schema: {
+                "type": String("object"),
+                "properties": Object {
+                    "name": Object {
+                        "type": String("string"),
+                    },
+                },
+            },
+            annotations: Some(
+                ToolAnnotations {
+                    title: None,
+                    read_only_hint: Some(
+                        true,
+                    ),
+                    destructive_hint: None,
+                    idempotent_hint: None,
+                    open_world_hint: None,
+                },
+            ),
+        }
+        "#);
+    }
+
+    #[test]
+    fn variables_as_object() {
+        let operation = Operation::from_document(
+            RawOperation {
+                source_text: "query QueryName($id: ID!, $flag: Boolean) { customQuery(id: $id, flag: $flag) { id } }".to_string(),
+                persisted_query_id: None,
+                headers: None,
+                variables: Some(HashMap::from([(
+                    "flag".to_string(),
+                    serde_json::Value::Bool(true),
+                )])),
+                source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
+            },
+            &SCHEMA,
+            None,
+            &ToolOptions {
+                variables_as_object: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            operation
+                .variables(serde_json::json!({ "variables": { "id": "1" } }), None)
+                .unwrap(),
+            serde_json::json!({ "id": "1", "flag": true })
+        );
+        assert_eq!(
+            operation.variables(Value::Null, None).unwrap(),
+            serde_json::json!({ "flag": true })
+        );
+        assert!(operation.variables(serde_json::json!("1"), None).is_err());
+
+        let tool = Tool::from(operation);
+        insta::assert_debug_snapshot!(tool.input_schema, @r#"
+        {
+            "type": String("object"),
+            "required": Array [
+                String("variables"),
+            ],
+            "properties": Object {
+                "variables": Object {
+                    "type": String("object"),
+                    "required": Array [
+                        String("id"),
+                    ],
+                    "properties": Object {
+                        "id": Object {
+                            "description": String("id description"),
+                            "type": String("string"),
+                        },
+                    },
+                },
+            },
+        }
+        "#);
+    }
+
+    #[test]
+    fn claim_variables() {
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "query QueryName($id: ID!, $flag: Boolean) { customQuery(id: $id, flag: $flag) { id } }".to_string(),
+                None,
+            )),
+            &SCHEMA,
+            None,
+            &ToolOptions {
+                claim_variables: vec![
+                    ClaimVariable {
+                        claim: "org_id".to_string(),
+                        variable: "id".to_string(),
+                        optional: false,
+                    },
+                    ClaimVariable {
+                        claim: "feature_flag".to_string(),
+                        variable: "flag".to_string(),
+                        optional: true,
+                    },
+                    ClaimVariable {
+                        claim: "sub".to_string(),
+                        variable: "user".to_string(),
+                        optional: false,
+                    },
+                ],
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        let claims = serde_json::json!({ "sub": "user", "org_id": "org-1" });
+        let claims = claims.as_object();
+        assert_eq!(
+            operation
+                .variables(serde_json::json!({ "id": "other-org" }), claims)
+                .unwrap(),
+            serde_json::json!({ "id": "org-1" })
+        );
+        assert!(operation.variables(Value::Null, None).is_err());
+
+        // Variables set from claims are not tool arguments
+        let tool = Tool::from(operation);
+        insta::assert_debug_snapshot!(tool.input_schema, @r#"
+        {
+            "type": String("object"),
+            "properties": Object {},
+        }
+        "#);
+    }
+
+    #[test]
+    fn collections_can_expose_only_queries() {
+        let operation = |id: &str, source_text: &str| {
+            OperationData::new(
+                id.to_string(),
+                "2025-01-01T00:00:00Z".to_string(),
+                source_text.to_string(),
+                None,
+                None,
+            )
+        };
+        let event =
+            OperationSource::collection_event(CollectionEvent::UpdateOperationCollection(vec![
+                operation("1", "query GetId { id }"),
+                operation("2", "mutation SetId { id }"),
+                operation("3", "query GetEnum { enum }"),
+            ]));
+
+        let Event::OperationsUpdated(operations) =
+            OperationSource::retain_operation_kinds(event, &[OperationKind::Query])
+        else {
+            panic!("expected the operations to be updated");
+        };
+        let source_texts: Vec<_> = operations
+            .iter()
+            .map(|operation| operation.source_text.as_str())
+            .collect();
+        assert_eq!(
+            source_texts,
+            vec!["query GetId { id }", "query GetEnum { enum }"]
+        );
+    }
+
+    #[tokio::test]
+    async fn markdown_files_describe_the_operations_named_after_them() {
+        let dir = std::env::temp_dir().join(format!("description-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("GetUser.graphql"),
+            "# Get a user\nquery GetUser { customQuery(id: \"1\") { id } }",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("GetUser.md"),
+            "Get a user by ID.\n\n**Note:** IDs are case sensitive.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("GetOther.graphql"),
+            "# Get another user\nquery GetOther { customQuery(id: \"2\") { id } }",
+        )
+        .unwrap();
+        let event = OperationSource::Files(vec![dir.clone()])
+            .into_stream()
+            .await
+            .next()
+            .await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let Some(Event::OperationsUpdated(operations)) = event else {
+            panic!("expected the operations to be loaded");
+        };
+        let descriptions: Vec<_> = operations
+            .into_iter()
+            .map(|operation| {
+                operation
+                    .into_operation(&SCHEMA, None, &ToolOptions::default())
+                    .unwrap()
+                    .unwrap()
+                    .as_ref()
+                    .description
+                    .clone()
+                    .unwrap_or_default()
+            })
+            .collect();
+        assert_eq!(
+            descriptions,
+            vec![
+                "Get another user".to_string(),
+                "Get a user by ID.\n\n**Note:** IDs are case sensitive.".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_operations_keep_the_description_file_for_the_one_named_after_it() {
+        let raw = RawOperation::from((
+            "query GetUser { id }\nquery GetOther { id }".to_string(),
+            Some("operations/GetUser.graphql".to_string()),
+        ))
+        .with_description(Some("Get a user".to_string()));
+
+        let descriptions: Vec<_> = raw
+            .split(&ToolOptions::default())
+            .into_iter()
+            .map(|operation| operation.description)
+            .collect();
+        assert_eq!(descriptions, vec![Some("Get a user".to_string()), None]);
+    }
+
+    #[test]
+    fn shared_fragments() {
+        let dir = std::env::temp_dir().join(format!("shared-fragments-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("fragments.graphql"),
+            "fragment OutputFields on OutputType { id ...MoreFields }\nfragment MoreFields on OutputType { __typename }",
+        )
+        .unwrap();
+        let shared_fragments = SharedFragments::load(std::slice::from_ref(&dir)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "query QueryName { customQuery(id: \"1\") { ...OutputFields } }".to_string(),
+                Some("query.graphql".to_string()),
+            )),
+            &SCHEMA,
+            None,
+            &ToolOptions {
+                shared_fragments: shared_fragments.clone(),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        let details = operation.operation(Value::Null).unwrap();
+        insta::assert_snapshot!(details.query, @r#"
+        query QueryName { customQuery(id: "1") { ...OutputFields } }
+
+        fragment OutputFields on OutputType {
+          id
+          ...MoreFields
+        }
+
+        fragment MoreFields on OutputType {
+          __typename
+        }
+        "#);
+        assert!(
+            operation
+                .as_ref()
+                .description
+                .as_deref()
+                .unwrap_or_default()
+                .contains("custom output type")
+        );
+
+        // Completing an operation again doesn't duplicate the fragments
+        let operation = Operation::from_document(
+            operation.into_inner(),
+            &SCHEMA,
+            None,
+            &ToolOptions {
+                shared_fragments,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(operation.operation(Value::Null).unwrap(), details);
+    }
+
+    #[test]
+    #[traced_test]
+    fn operations_selecting_no_fields_are_skipped() {
+        let raw_operation =
+            || RawOperation::from(("query QueryName { removed }".to_string(), None));
+        let operation =
+            Operation::from_document(raw_operation(), &SCHEMA, None, &ToolOptions::default())
+                .unwrap();
+        assert!(operation.is_none());
+        assert!(logs_contain(
+            "Skipping operation QueryName, which selects no fields in the schema"
+        ));
+
+        let error = Operation::from_document(
+            raw_operation(),
+            &SCHEMA,
+            None,
+            &ToolOptions {
+                strict_operations: true,
+                ..Default::default()
+            },
+        )
+        .unwrap_err();
+        assert!(matches!(error, OperationError::NoSelectedFields { .. }));
+    }
+
+    #[test]
+    #[traced_test]
+    fn operations_deeper_than_the_maximum_are_rejected() {
+        let raw_operation = || {
+            RawOperation::from((
+                "query Deep { customQuery(id: 1) { ...Fields } } fragment Fields on OutputType { ... on OutputType { id } }"
+                    .to_string(),
+                None,
+            ))
+        };
+        let with_max_depth = |max_depth, strict_operations| ToolOptions {
+            max_operation_depth: Some(max_depth),
+            strict_operations,
+            ..Default::default()
+        };
+
+        let operation =
+            Operation::from_document(raw_operation(), &SCHEMA, None, &with_max_depth(2, false))
+                .unwrap();
+        assert!(operation.is_some());
+
+        let operation =
+            Operation::from_document(raw_operation(), &SCHEMA, None, &with_max_depth(1, false))
+                .unwrap();
+        assert!(operation.is_none());
+        assert!(logs_contain(
+            "Skipping operation Deep, whose selections are 2 levels deep, more than the maximum of 1"
+        ));
+
+        let error =
+            Operation::from_document(raw_operation(), &SCHEMA, None, &with_max_depth(1, true))
+                .unwrap_err();
+        assert!(matches!(
+            error,
+            OperationError::TooDeep {
+                depth: 2,
+                max_depth: 1,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    #[traced_test]
+    fn operations_with_too_many_variables_are_reported() {
+        let raw_operation = || {
+            RawOperation::from((
+                "query Many($a: ID, $b: ID, $c: ID) { customQuery(id: $a) { id } }".to_string(),
+                None,
+            ))
+        };
+        let with_max_variables = |max_variables, strict_operations| ToolOptions {
+            max_operation_variables: Some(max_variables),
+            strict_operations,
+            ..Default::default()
+        };
+
+        let operation = Operation::from_document(
+            raw_operation(),
+            &SCHEMA,
+            None,
+            &with_max_variables(3, false),
+        )
+        .unwrap();
+        assert!(operation.is_some());
+        assert!(!logs_contain("Operation Many has"));
+
+        let operation = Operation::from_document(
+            raw_operation(),
+            &SCHEMA,
+            None,
+            &with_max_variables(2, false),
+        )
+        .unwrap();
+        assert!(operation.is_some());
+        assert!(logs_contain(
+            "Operation Many has 3 variables, more than the maximum of 2"
+        ));
+
+        let error =
+            Operation::from_document(raw_operation(), &SCHEMA, None, &with_max_variables(2, true))
+                .unwrap_err();
+        assert!(matches!(
+            error,
+            OperationError::TooManyVariables {
+                count: 3,
+                max_variables: 2,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    #[traced_test]
+    fn operations_selecting_removed_fields_are_reported() {
+        let raw_operation = || {
+            RawOperation::from((
+                "query Stale { id customQuery(id: 1) { ...Fields } } fragment Fields on OutputType { id removed }"
+                    .to_string(),
+                None,
+            ))
+        };
+        let with_check = |strict_operations| ToolOptions {
+            check_missing_fields: true,
+            strict_operations,
+            ..Default::default()
+        };
+
+        let operation =
+            Operation::from_document(raw_operation(), &SCHEMA, None, &with_check(false)).unwrap();
+        assert!(operation.is_some());
+        assert!(logs_contain(
+            "Operation Stale selects the field OutputType.removed, which is not in the schema"
+        ));
+
+        let error = Operation::from_document(raw_operation(), &SCHEMA, None, &with_check(true))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Operation Stale selects fields which are not in the schema: OutputType.removed"
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn oversized_tools_log_a_warning() {
+        for (name, threshold) in [("SmallTool", 100_000), ("LargeTool", 10)] {
+            Operation::from_document(
+                RawOperation::from((format!("query {name} {{ id }}"), None)),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    tool_size_warn_threshold: Some(threshold),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+        }
+
+        logs_assert(|lines: &[&str]| {
+            let warnings = lines
+                .iter()
+                .filter(|line| line.contains("WARN") && line.contains("character count"))
+                .collect::<Vec<_>>();
+            match warnings.as_slice() {
+                [warning] if warning.contains("Tool LargeTool") => Ok(()),
+                _ => Err(format!(
+                    "expected a single warning for LargeTool: {warnings:?}"
+                )),
+            }
+        });
+        assert!(logs_contain("Tool SmallTool loaded with a character count"));
+    }
+
+    #[test]
+    fn header_sets() {
+        let options = ToolOptions {
+            header_sets: HashMap::from([(
+                "serviceB".to_string(),
+                HeaderMap::from_iter([(
+                    HeaderName::from_static("authorization"),
+                    HeaderValue::from_static("Bearer service-b"),
+                )]),
+            )]),
+            ..Default::default()
+        };
+        let operation = |source_text: &str| {
+            Operation::from_document(
+                RawOperation::from((source_text.to_string(), None)),
+                &SCHEMA,
+                None,
+                &options,
+            )
+        };
+        let default_headers = HeaderMap::from_iter([
+            (
+                HeaderName::from_static("authorization"),
+                HeaderValue::from_static("Bearer default"),
+            ),
+            (
+                HeaderName::from_static("x-tenant"),
+                HeaderValue::from_static("acme"),
+            ),
+        ]);
+
+        let service_b = operation("# Get the ID\n# @headers(\"serviceB\")\nquery ServiceB { id }")
+            .unwrap()
+            .unwrap();
+        let headers = service_b.headers(&default_headers);
+        assert_eq!(headers.get("authorization").unwrap(), "Bearer service-b");
+        assert_eq!(headers.get("x-tenant").unwrap(), "acme");
+        assert_eq!(
+            service_b.as_ref().description.as_deref(),
+            Some("Get the ID")
+        );
+
+        let default = operation("query Default { id }").unwrap().unwrap();
+        assert_eq!(default.headers(&default_headers), default_headers);
+
+        assert!(matches!(
+            operation("# @headers(\"serviceC\")\nquery ServiceC { id }"),
+            Err(OperationError::UnknownHeaderSet { .. })
+        ));
+    }
+
+    #[test]
+    fn null_variables() {
+        let operation = |source_text: &str, null_variables| {
+            Operation::from_document(
+                RawOperation::from((source_text.to_string(), None)),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    null_variables,
+                    ..Default::default()
+                },
+            )
+        };
+        let query = "query QueryName($id: ID!, $flag: Boolean) { customQuery(id: $id, flag: $flag) { id } }";
+        let input = serde_json::json!({ "id": "1", "flag": null });
+
+        let keep = operation(query, NullVariables::Keep).unwrap().unwrap();
+        assert_eq!(
+            keep.variables(input.clone(), None).unwrap(),
+            serde_json::json!({ "id": "1", "flag": null })
+        );
+
+        let omit = operation(query, NullVariables::Omit).unwrap().unwrap();
+        assert_eq!(
+            omit.variables(input.clone(), None).unwrap(),
+            serde_json::json!({ "id": "1" })
+        );
+
+        // Operations can select the mode with a comment directive
+        let keep = operation(
+            &format!("# @nullVariables(\"keep\")\n{query}"),
+            NullVariables::Omit,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            keep.variables(input, None).unwrap(),
+            serde_json::json!({ "id": "1", "flag": null })
+        );
+        assert!(matches!(
+            operation(
+                &format!("# @nullVariables(\"drop\")\n{query}"),
+                NullVariables::Keep
+            ),
+            Err(OperationError::InvalidDirective { .. })
+        ));
+    }
+
+    #[test]
+    fn mutation_mode_directive() {
+        let operation = |source_text: &str, max_mutation_mode| {
+            Operation::from_document(
+                RawOperation::from((source_text.to_string(), None)),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    mutation_mode: MutationMode::Explicit,
+                    max_mutation_mode,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let blessed = operation("# @mutationMode(all)\nmutation Blessed { id }", None);
+        let other = operation("mutation Other { id }", None);
+        assert_eq!(blessed.mutation_mode(), MutationMode::All);
+        assert_eq!(other.mutation_mode(), MutationMode::Explicit);
+
+        // Only the root fields of operations marked `all` can be selected in built mutations
+        let execute = Execute::new(MutationMode::Explicit, false);
+        let build = || {
+            graphql::Executable::operation(
+                &execute,
+                serde_json::json!({ "query": "mutation Built { id }" }),
+            )
+        };
+        execute.set_buildable_mutations(std::slice::from_ref(&other));
+        assert!(build().is_err());
+        execute.set_buildable_mutations(&[blessed, other]);
+        assert!(build().is_ok());
+
+        // Operations cannot be more permissive than the ceiling
+        let capped = operation(
+            "# @mutationMode(\"all\")\nmutation Blessed { id }",
+            Some(MutationMode::Explicit),
+        );
+        assert_eq!(capped.mutation_mode(), MutationMode::Explicit);
+        assert!(
+            Operation::from_document(
+                RawOperation::from((
+                    "# @mutationMode(none)\nmutation Blocked { id }".to_string(),
+                    None
+                )),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    mutation_mode: MutationMode::All,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn strip_operation_prefix() {
+        let options = ToolOptions {
+            strip_operation_prefix: Some("App_".to_string()),
+            ..Default::default()
+        };
+        let operation = |source_text: &str| {
+            Operation::from_document(
+                RawOperation::from((source_text.to_string(), None)),
+                &SCHEMA,
+                None,
+                &options,
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        let prefixed = operation("query App_GetUser($id: ID!) { customQuery(id: $id) { id } }");
+        assert_eq!(prefixed.as_ref().name, "GetUser");
+        assert_eq!(
+            prefixed.operation(Value::Null).unwrap().operation_name,
+            Some("App_GetUser".to_string())
+        );
+
+        // Operations keep their original names if stripping would make tool names collide
+        let mut operations = vec![
+            prefixed,
+            operation("query GetUser($id: ID!) { customQuery(id: $id) { id } }"),
+        ];
+        restore_colliding_tool_names(&mut operations);
+        assert_eq!(
+            operations
+                .iter()
+                .map(|operation| operation.as_ref().name.as_ref())
+                .collect::<Vec<_>>(),
+            vec!["App_GetUser", "GetUser"]
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn max_tools_drops_extra_operations() {
+        let operations = || {
+            [
+                "C",
+                "A",
+                "# @priority(5)\nquery D",
+                "B",
+                "# @priority(-1)\nquery E",
+            ]
+            .into_iter()
+            .map(|name| {
+                let source_text = if name.contains("query") {
+                    format!("{name} {{ id }}")
+                } else {
+                    format!("query {name} {{ id }}")
+                };
+                Operation::from_document(
+                    RawOperation::from((source_text, None)),
+                    &SCHEMA,
+                    None,
+                    &ToolOptions::default(),
+                )
+                .unwrap()
+                .unwrap()
+            })
+            .collect::<Vec<_>>()
+        };
+        let names = |operations: &[Operation]| {
+            operations
+                .iter()
+                .map(|operation| operation.as_ref().name.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut by_name = operations();
+        let max_tools = MaxTools {
+            limit: 3,
+            strict: false,
+            order: ToolOrder::Name,
+        };
+        limit_tools(&mut by_name, &max_tools).unwrap();
+        assert_eq!(names(&by_name), vec!["A", "B", "C"]);
+        assert!(logs_contain(
+            "Exposing 3 of 5 operation tools, dropping: D, E"
+        ));
+
+        let mut by_priority = operations();
+        let max_tools = MaxTools {
+            order: ToolOrder::Priority,
+            ..max_tools
+        };
+        limit_tools(&mut by_priority, &max_tools).unwrap();
+        assert_eq!(names(&by_priority), vec!["D", "A", "B"]);
+        assert!(logs_contain(
+            "Exposing 3 of 5 operation tools, dropping: C, E"
+        ));
+
+        let strict = MaxTools {
+            strict: true,
+            ..max_tools
+        };
+        assert!(matches!(
+            limit_tools(&mut operations(), &strict),
+            Err(OperationError::TooManyTools { count: 5, limit: 3 })
+        ));
+    }
+
+    #[test]
+    #[traced_test]
+    fn duplicate_operations_prefer_the_first_source() {
+        let operations = || {
+            [
+                ("query GetUser { id }", "local/GetUser.graphql"),
+                ("query GetUser { id }", "shared/GetUser.graphql"),
+                ("query GetOther { id }", "shared/GetOther.graphql"),
+            ]
+            .into_iter()
+            .map(|(source_text, source_path)| {
+                Operation::from_document(
+                    RawOperation::from((source_text.to_string(), Some(source_path.to_string()))),
+                    &SCHEMA,
+                    None,
+                    &ToolOptions::default(),
+                )
+                .unwrap()
+                .unwrap()
+            })
+            .collect::<Vec<_>>()
+        };
+
+        let mut preferred = operations();
+        resolve_duplicate_operations(&mut preferred, DuplicateOperations::PreferFirst).unwrap();
+        assert_eq!(
+            preferred
+                .iter()
+                .map(|operation| operation.inner.source_path.as_deref().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["local/GetUser.graphql", "shared/GetOther.graphql"]
+        );
+        assert!(logs_contain(
+            "Operation GetUser from shared/GetUser.graphql is shadowed by the operation of the same name from local/GetUser.graphql"
+        ));
+
+        assert_eq!(
+            resolve_duplicate_operations(&mut operations(), DuplicateOperations::Error)
+                .unwrap_err()
+                .to_string(),
+            "Operation GetUser is defined more than once, in local/GetUser.graphql and shared/GetUser.graphql"
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn identical_operations_are_deduped() {
+        let mut operations: Vec<_> = [
+            "query GetId { id }",
+            "query FetchId { id }",
+            "query GetEnum { enum }",
+            "# Get the ID\nquery DescribedId { id }",
+        ]
+        .into_iter()
+        .map(|source_text| {
+            Operation::from_document(
+                RawOperation::from((source_text.to_string(), None)),
+                &SCHEMA,
+                None,
+                &ToolOptions::default(),
+            )
+            .unwrap()
+            .unwrap()
+        })
+        .collect();
+
+        dedupe_identical_operations(&mut operations);
+        assert_eq!(
+            operations
+                .iter()
+                .map(|operation| operation.operation_name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["GetId", "GetEnum", "DescribedId"]
+        );
+        assert!(logs_contain(
+            "Skipping operation FetchId, whose tool is identical to the tool of operation GetId"
+        ));
+    }
+
+    #[test]
+    fn builtin_scalar_descriptions() {
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "query GetUser($id: ID!, $flag: Boolean) { customQuery(id: $id, flag: $flag) { id } }"
+                    .to_string(),
+                None,
+            )),
+            &SCHEMA,
+            None,
+            &ToolOptions {
+                builtin_scalar_descriptions: HashMap::from([(
+                    "ID".to_string(),
+                    "An opaque identifier.".to_string(),
+                )]),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        insta::assert_snapshot!(serde_json::to_string_pretty(&serde_json::json!(operation.as_ref().input_schema)).unwrap(), @r#"
+        {
+          "type": "object",
+          "required": [
+            "id"
+          ],
+          "properties": {
+            "flag": {
+              "description": "a flag",
+              "type": "boolean"
+            },
+            "id": {
+              "description": "id description\nAn opaque identifier.",
+              "type": "string"
+            }
+          }
+        }
+        "#);
+    }
+
+    #[test]
+    fn constraint_directive() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            directive @constraint(minLength: Int, maxLength: Int, pattern: String, min: Float, max: Float) on INPUT_FIELD_DEFINITION | ARGUMENT_DEFINITION
+            type Query { users(name: String @constraint(minLength: 3), filter: UserFilter): [String] }
+            input UserFilter {
+                code: String @constraint(maxLength: 8, pattern: "^[A-Z]+$")
+                age: Int @constraint(min: 18, max: 130)
+            }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "query Users($name: String, $filter: UserFilter) { users(name: $name, filter: $filter) }"
+                    .to_string(),
+                None,
+            )),
+            &schema,
+            None,
+            &ToolOptions {
+                constraint_directive: Some("constraint".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        insta::assert_snapshot!(serde_json::to_string_pretty(&serde_json::json!(operation.as_ref().input_schema)).unwrap(), @r##"
+        {
+          "type": "object",
+          "properties": {
+            "filter": {
+              "$ref": "#/definitions/UserFilter"
+            },
+            "name": {
+              "type": "string",
+              "minLength": 3
+            }
+          },
+          "definitions": {
+            "UserFilter": {
+              "type": "object",
+              "properties": {
+                "age": {
+                  "type": "number",
+                  "maximum": 130.0,
+                  "minimum": 18.0
+                },
+                "code": {
+                  "type": "string",
+                  "maxLength": 8,
+                  "pattern": "^[A-Z]+$"
+                }
+              }
+            }
+          }
+        }
+        "##);
+    }
+
+    #[test]
+    fn computed_default_directive() {
+        let schema = Schema::parse_and_validate(
+            "type Query { events(startDate: String!, endDate: String): [String] }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = |source_text: &str| {
+            Operation::from_document(
+                RawOperation::from((source_text.to_string(), None)),
+                &schema,
+                None,
+                &ToolOptions::default(),
+            )
+        };
+
+        let events = operation(
+            "# @computedDefault(\"endDate = startDate + 30d\")\nquery Events($startDate: String!, $endDate: String) { events(startDate: $startDate, endDate: $endDate) }",
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            events
+                .variables(serde_json::json!({ "startDate": "2025-01-15" }), None)
+                .unwrap(),
+            serde_json::json!({ "startDate": "2025-01-15", "endDate": "2025-02-14" })
+        );
+        assert_eq!(
+            events
+                .variables(
+                    serde_json::json!({ "startDate": "2025-01-15", "endDate": "2025-01-20" }),
+                    None
+                )
+                .unwrap(),
+            serde_json::json!({ "startDate": "2025-01-15", "endDate": "2025-01-20" })
+        );
+
+        let error = operation(
+            "# @computedDefault(\"endDate = since + 30d\")\nquery Events($startDate: String!, $endDate: String) { events(startDate: $startDate, endDate: $endDate) }",
+        )
+        .unwrap_err();
+        assert!(matches!(error, OperationError::InvalidDirective { .. }));
+    }
+
+    #[test]
+    fn dependent_required_directive() {
+        let schema = Schema::parse_and_validate(
+            "type Query { users(first: Int, after: String): [String] }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = |source_text: &str| {
+            Operation::from_document(
+                RawOperation::from((source_text.to_string(), None)),
+                &schema,
+                None,
+                &ToolOptions::default(),
+            )
+        };
+
+        let users = operation(
+            "# @dependentRequired(\"after: first\")\nquery Users($first: Int, $after: String) { users(first: $first, after: $after) }",
+        )
+        .unwrap()
+        .unwrap();
+        let error = users
+            .variables(serde_json::json!({ "after": "cursor" }), None)
+            .unwrap_err();
+        assert_eq!(error.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert_eq!(error.message, "after requires first to be set");
+        assert!(
+            users
+                .variables(serde_json::json!({ "after": "cursor", "first": 10 }), None)
+                .is_ok()
+        );
+        assert!(
+            users
+                .variables(serde_json::json!({ "first": 10 }), None)
+                .is_ok()
+        );
+        assert_eq!(
+            users.tool.input_schema.get("dependentRequired"),
+            Some(&serde_json::json!({ "after": ["first"] }))
+        );
+
+        let error = operation(
+            "# @dependentRequired(\"after: last\")\nquery Users($first: Int, $after: String) { users(first: $first, after: $after) }",
+        )
+        .unwrap_err();
+        assert!(matches!(error, OperationError::InvalidDirective { .. }));
+    }
+
+    #[test]
+    fn coerce_scalars() {
+        let schema = Schema::parse_and_validate(
+            "type Query { items(first: Int!, ratio: Float, tags: [Int!]): String }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = |coerce_scalars| {
+            Operation::from_document(
+                RawOperation::from((
+                    "query Items($first: Int!, $ratio: Float, $tags: [Int!]) { items(first: $first, ratio: $ratio, tags: $tags) }".to_string(),
+                    None,
+                )),
+                &schema,
+                None,
+                &ToolOptions {
+                    coerce_scalars,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        let coercing = operation(true);
+        assert_eq!(
+            coercing
+                .variables(
+                    serde_json::json!({ "first": "42", "ratio": "0.5", "tags": ["1", 2] }),
+                    None
+                )
+                .unwrap(),
+            serde_json::json!({ "first": 42, "ratio": 0.5, "tags": [1, 2] })
+        );
+        let error = coercing
+            .variables(serde_json::json!({ "first": "abc" }), None)
+            .unwrap_err();
+        assert_eq!(error.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert_eq!(
+            error.message,
+            "Invalid value for first: expected Int, got \"abc\""
+        );
+
+        // Values are sent as provided unless coercion is enabled
+        assert_eq!(
+            operation(false)
+                .variables(serde_json::json!({ "first": "42" }), None)
+                .unwrap(),
+            serde_json::json!({ "first": "42" })
+        );
+    }
+
+    #[test]
+    fn parallel_conversion_matches_sequential_conversion() {
+        let raw_operations: Vec<RawOperation> = (0..10)
+            .map(|i| RawOperation::from((format!("query Op{i} {{ id }}"), None)))
+            .chain(std::iter::once(RawOperation::from((
+                "query Invalid { missing }".to_string(),
+                None,
+            ))))
+            .collect();
+        let convert = |operation_loading_threads| {
+            convert_operations(
+                raw_operations.clone(),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    operation_loading_threads: Some(operation_loading_threads),
+                    ..Default::default()
+                },
+                &OperationCache::default(),
+            )
+            .into_iter()
+            .map(Tool::from)
+            .collect::<Vec<_>>()
+        };
+
+        let sequential = convert(1);
+        assert_eq!(sequential.len(), 10);
+        assert_eq!(convert(4), sequential);
+        assert_eq!(convert(32), sequential);
+    }
+
+    #[test]
+    fn split_list_strings() {
+        let schema = Schema::parse_and_validate(
+            "type Query { items(tags: [String!], first: String, ids: [Int]): String }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = |split_list_strings| {
+            Operation::from_document(
+                RawOperation::from((
+                    "query Items($tags: [String!], $first: String, $ids: [Int]) { items(tags: $tags, first: $first, ids: $ids) }".to_string(),
+                    None,
+                )),
+                &schema,
+                None,
+                &ToolOptions {
+                    split_list_strings,
+                    coerce_scalars: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        assert_eq!(
+            operation(true)
+                .variables(
+                    serde_json::json!({ "tags": "a,b,c", "first": "x,y", "ids": " 1, 2 " }),
+                    None
+                )
+                .unwrap(),
+            serde_json::json!({ "tags": ["a", "b", "c"], "first": "x,y", "ids": [1, 2] })
+        );
+        assert_eq!(
+            operation(true)
+                .variables(serde_json::json!({ "tags": ["a,b"] }), None)
+                .unwrap(),
+            serde_json::json!({ "tags": ["a,b"] })
+        );
+
+        // Strings are sent as provided unless splitting is enabled
+        assert_eq!(
+            operation(false)
+                .variables(serde_json::json!({ "tags": "a,b,c" }), None)
+                .unwrap(),
+            serde_json::json!({ "tags": "a,b,c" })
+        );
+    }
+
+    #[tokio::test]
+    async fn applied_coercions_are_reported() {
+        let schema = Schema::parse_and_validate(
+            "enum Status { ACTIVE INACTIVE } type Query { users(status: Status): String }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "query Users($status: Status) { users(status: $status) }".to_string(),
+                None,
+            )),
+            &schema,
+            None,
+            &ToolOptions {
+                coerce_scalars: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let endpoint = url::Url::parse(&server.url()).unwrap();
+        let mock = server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({ "variables": { "status": "ACTIVE" } }),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"data":{"users":"alice"}}"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let error_codes = ErrorCodes::default();
+        let execute = |report_coercions| {
+            operation.execute(graphql::Request {
+                input: serde_json::json!({ "status": "active" }),
+                endpoint: &endpoint,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &error_codes,
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions,
+                websocket: None,
+            })
+        };
+        let texts = |result: rmcp::model::CallToolResult| {
+            result
+                .content
+                .into_iter()
+                .filter_map(|content| content.as_text().map(|text| text.text.clone()))
+                .collect::<Vec<_>>()
+        };
+
+        let reported = texts(execute(true).await.unwrap());
+        assert_eq!(
+            reported.last().map(String::as_str),
+            Some(r#"Note: the input was coerced: coerced status from "active" to "ACTIVE""#)
+        );
+        assert_eq!(texts(execute(false).await.unwrap()).len(), 1);
+        mock.assert_async().await;
+    }
+
+    #[test]
+    fn input_definitions_are_bounded() {
+        // Every input type references the next two, so the whole chain is reachable
+        let types = (0..20)
+            .map(|i| format!("input T{i} {{ a: T{} b: T{} }}", (i + 1) % 20, (i + 2) % 20))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let schema = Schema::parse_and_validate(
+            format!("type Query {{ id(input: T0): String }}\n{types}"),
+            "schema.graphql",
+        )
+        .unwrap();
+        let definitions = |max_input_definitions| {
+            let operation = Operation::from_document(
+                RawOperation::from((
+                    "query QueryName($input: T0) { id(input: $input) }".to_string(),
+                    None,
+                )),
+                &schema,
+                None,
+                &ToolOptions {
+                    max_input_definitions,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            Tool::from(operation)
+                .input_schema
+                .get("definitions")
+                .and_then(Value::as_object)
+                .map(|definitions| definitions.len())
+        };
+
+        assert_eq!(definitions(None), Some(20));
+        assert_eq!(definitions(Some(5)), Some(5));
+    }
+
+    #[test]
+    fn input_schema_includes_variable_descriptions() {
+        let operation = Operation::from_document(
+            RawOperation {
+                source_text: "query QueryName($idArg: ID) { customQuery(id: $idArg) { id } }"
+                    .to_string(),
+                persisted_query_id: None,
+                headers: None,
+                variables: None,
+                source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
+            },
+            &SCHEMA,
+            None,
+            &ToolOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+        let tool = Tool::from(operation);
+
+        insta::assert_snapshot!(serde_json::to_string_pretty(&serde_json::json!(tool.input_schema)).unwrap(), @r#"
+        {
+          "type": "object",
+          "properties": {
+            "idArg": {
+              "description": "id description",
+              "type": "string"
+            }
+          }
+        }
+        "#);
+    }
+
+    #[test]
+    fn tool_description_summarizes_variable_descriptions() {
+        let description = |describe_variables| {
+            let operation = Operation::from_document(
+                RawOperation::from((
+                    "query QueryName($idArg: ID!, $flag: Boolean) { customQuery(id: $idArg, flag: $flag) { id } }"
+                        .to_string(),
+                    None,
+                )),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    describe_variables,
+                    disable_schema_description: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            Tool::from(operation).description.unwrap_or_default()
+        };
+
+        assert_eq!(
+            description(true),
+            "The returned value is optional and has type `OutputType`\n\nArguments: idArg — id description; flag — a flag"
+        );
+        assert!(!description(false).contains("Arguments:"));
+    }
+
+    #[test]
+    fn input_schema_describes_graphql_types() {
+        let input_schema = |describe_graphql_types| {
+            let operation = Operation::from_document(
+                RawOperation::from((
+                    "query QueryName($idArg: ID!, $objects: [RealInputObject!]) { customQuery(id: $idArg) { id } }"
+                        .to_string(),
+                    None,
+                )),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    describe_graphql_types,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            serde_json::to_value(Tool::from(operation).input_schema).unwrap()
+        };
+
+        let described = input_schema(true);
+        assert_eq!(
+            described["properties"]["idArg"]["description"],
+            "id description (GraphQL type: `ID!`)"
+        );
+        assert_eq!(
+            described["properties"]["objects"]["description"],
+            "GraphQL type: `[RealInputObject!]`"
+        );
+
+        let undescribed = input_schema(false);
+        assert_eq!(
+            undescribed["properties"]["idArg"]["description"],
+            "id description"
+        );
+        assert!(
+            undescribed["properties"]["objects"]
+                .get("description")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn constants_are_substituted_into_operation_source() {
+        let options = ToolOptions {
+            operation_constants: HashMap::from([("region".to_string(), "eu-west".to_string())]),
+            include_operation_source: true,
+            disable_type_description: true,
+            disable_schema_description: true,
+            ..Default::default()
+        };
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "# Get the query in {{ region }}\nquery QueryName { customQuery(id: \"{{region}}\") { id } }"
+                    .to_string(),
+                None,
+            )),
+            &SCHEMA,
+            None,
+            &options,
+        )
+        .unwrap()
+        .unwrap();
+
+        insta::assert_snapshot!(Tool::from(operation).description.unwrap_or_default(), @r#"
+        Get the query in eu-west
+
+        Operation:
+        query QueryName {
+          customQuery(id: "eu-west") {
+            id
+          }
+        }
+        "#);
+
+        let error = Operation::from_document(
+            RawOperation::from((
+                "query QueryName { customQuery(id: \"{{zone}}\") { id } }".to_string(),
+                Some("QueryName.graphql".to_string()),
+            )),
+            &SCHEMA,
+            None,
+            &options,
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "QueryName.graphql: Operation source refers to the unknown constant zone"
+        );
+    }
+
+    #[test]
+    fn constants_are_escaped_as_graphql_strings() {
+        let options = ToolOptions {
+            operation_constants: HashMap::from([(
+                "region".to_string(),
+                "eu\") { id } mutation Injected { id } query Rest { customQuery(id: \"\\"
+                    .to_string(),
+            )]),
+            ..Default::default()
+        };
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "query QueryName { customQuery(id: \"{{region}}\") { id } }".to_string(),
+                None,
+            )),
+            &SCHEMA,
+            None,
+            &options,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            operation.inner.source_text,
+            r#"query QueryName { customQuery(id: "eu\") { id } mutation Injected { id } query Rest { customQuery(id: \"\\") { id } }"#
+        );
+    }
+
+    #[test]
+    fn constants_are_not_substituted_into_persisted_queries() {
+        let options = ToolOptions {
+            operation_constants: HashMap::from([("region".to_string(), "eu-west".to_string())]),
+            ..Default::default()
+        };
+        let source_text = "query QueryName { customQuery(id: \"{{region}}\") { id } }";
+        let operation = Operation::from_document(
+            RawOperation::from(("persisted-id".to_string(), source_text.to_string())),
+            &SCHEMA,
+            None,
+            &options,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(operation.inner.source_text, source_text);
+    }
+
+    #[test]
+    fn input_schema_has_titles_when_enabled() {
+        let input_schema = |property_titles| {
+            let operation = Operation::from_document(
+                RawOperation::from((
+                    "query QueryName($firstName: String, $user_id: ID, $objects: [RealInputObject!]) { customQuery(id: $user_id) { id } }"
+                        .to_string(),
+                    None,
+                )),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    property_titles,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            serde_json::to_value(Tool::from(operation).input_schema).unwrap()
+        };
+
+        let titled = input_schema(true);
+        assert_eq!(titled["properties"]["firstName"]["title"], "First Name");
+        assert_eq!(titled["properties"]["user_id"]["title"], "User Id");
+        assert_eq!(
+            titled["definitions"]["RealInputObject"]["properties"]["required"]["title"],
+            "Required"
+        );
+
+        let untitled = input_schema(false);
+        assert!(untitled["properties"]["firstName"].get("title").is_none());
+    }
+
+    #[test]
+    fn input_schema_summarizes_variables_when_enabled() {
+        let input_schema = |variable_summary| {
+            let operation = Operation::from_document(
+                RawOperation::from((
+                    "query QueryName($id: ID!, $flag: Boolean) { customQuery(id: $id, flag: $flag) { id } }"
+                        .to_string(),
+                    None,
+                )),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    variable_summary,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            serde_json::to_value(Tool::from(operation).input_schema).unwrap()
+        };
+
+        assert_eq!(
+            input_schema(true)[VARIABLE_SUMMARY_KEYWORD],
+            serde_json::json!({ "required": ["id"], "optional": ["flag"] })
+        );
+        assert!(input_schema(false).get(VARIABLE_SUMMARY_KEYWORD).is_none());
+    }
+
+    #[test]
+    fn title_case_splits_words() {
+        assert_eq!(title_case("firstName"), "First Name");
+        assert_eq!(title_case("first_name"), "First Name");
+        assert_eq!(title_case("userID"), "User ID");
+        assert_eq!(title_case("HTTPServer2Url"), "HTTP Server2 Url");
+    }
+
+    #[test]
+    fn nullable_list_items_use_the_configured_style() {
+        let items = |nullable_style| {
+            let operation = Operation::from_document(
+                RawOperation::from((
+                    "query QueryName($ids: [ID], $enums: [RealEnum]) { customQuery(id: \"1\") { id } }"
+                        .to_string(),
+                    None,
+                )),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    nullable_style,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            let input_schema = serde_json::to_value(Tool::from(operation).input_schema).unwrap();
+            (
+                input_schema["properties"]["ids"]["items"].clone(),
+                input_schema["properties"]["enums"]["items"].clone(),
+            )
+        };
+
+        assert_eq!(
+            items(NullableStyle::OneOf).0,
+            serde_json::json!({ "oneOf": [{ "type": "string" }, { "type": "null" }] })
+        );
+        assert_eq!(
+            items(NullableStyle::TypeArray).0,
+            serde_json::json!({ "type": ["string", "null"] })
+        );
+        assert_eq!(
+            items(NullableStyle::Nullable).0,
+            serde_json::json!({ "type": "string", "nullable": true })
+        );
+
+        // Enums refer to their definition, so they are always represented with oneOf
+        let (_, enums) = items(NullableStyle::TypeArray);
+        assert_eq!(
+            enums,
+            serde_json::json!({
+                "oneOf": [{ "$ref": "#/definitions/RealEnum" }, { "type": "null" }]
+            })
+        );
+    }
+
+    #[test]
+    fn definitions_use_the_configured_keyword() {
+        let schema = Schema::parse_and_validate(
+            "type Query { posts(filter: Filter): String } input Filter { author: Author } input Author { name: String }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let input_schema = |definitions_keyword| {
+            let operation = Operation::from_document(
+                RawOperation::from((
+                    "query Posts($filter: Filter) { posts(filter: $filter) }".to_string(),
+                    None,
+                )),
+                &schema,
+                None,
+                &ToolOptions {
+                    definitions_keyword,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            serde_json::to_value(Tool::from(operation).input_schema).unwrap()
+        };
+
+        let defs = input_schema(DefinitionsKeyword::Defs);
+        assert!(defs.get("definitions").is_none());
+        assert_eq!(
+            defs["properties"]["filter"],
+            serde_json::json!({ "$ref": "#/$defs/Filter" })
+        );
+        assert_eq!(
+            defs["$defs"]["Filter"]["properties"]["author"],
+            serde_json::json!({ "$ref": "#/$defs/Author" })
+        );
+        assert!(defs["$defs"]["Author"].is_object());
+
+        let definitions = input_schema(DefinitionsKeyword::Definitions);
+        assert!(definitions.get("$defs").is_none());
+        assert_eq!(
+            definitions["properties"]["filter"],
+            serde_json::json!({ "$ref": "#/definitions/Filter" })
+        );
+    }
+
+    #[test]
+    fn single_value_enums_are_constants() {
+        let schema = Schema::parse_and_validate(
+            "type Query { posts(kind: Kind, sort: Sort): String } enum Kind { POST } enum Sort { NEWEST OLDEST }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "query Posts($kind: Kind, $sort: Sort) { posts(kind: $kind, sort: $sort) }"
+                    .to_string(),
+                None,
+            )),
+            &schema,
+            None,
+            &ToolOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+        let input_schema = serde_json::to_value(Tool::from(operation).input_schema).unwrap();
+
+        let kind = &input_schema["definitions"]["Kind"];
+        assert_eq!(kind["const"], serde_json::json!("POST"));
+        assert!(kind.get("enum").is_none());
+
+        let sort = &input_schema["definitions"]["Sort"];
+        assert_eq!(sort["enum"], serde_json::json!(["NEWEST", "OLDEST"]));
+        assert!(sort.get("const").is_none());
+    }
+
+    #[test]
+    fn empty_descriptions_use_the_fallback_description() {
+        let description = |fallback_description: Option<&str>| {
+            let operation = Operation::from_document(
+                RawOperation::from(("query GetId { id }".to_string(), None)),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    disable_type_description: true,
+                    fallback_description: fallback_description.map(str::to_string),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            Tool::from(operation).description.unwrap_or_default()
+        };
+
+        assert_eq!(description(None), "");
+        assert_eq!(
+            description(Some("Executes the {name} operation.")),
+            "Executes the GetId operation."
+        );
+    }
+
+    #[test]
+    fn tool_description_uses_the_return_type_description_template() {
+        let schema = Schema::parse_and_validate(
+            "type Query { users: [User] } type User { id: ID }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = Operation::from_document(
+            RawOperation::from(("query Users { users { id } }".to_string(), None)),
+            &schema,
+            None,
+            &ToolOptions {
+                disable_schema_description: true,
+                return_type_description: ReturnTypeDescription {
+                    template: "Returns {optional}{list} {type}".to_string(),
+                    optional: "maybe ".to_string(),
+                    list: "a list of".to_string(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            Tool::from(operation).description.as_deref(),
+            Some("Returns maybe a list of User")
+        );
+    }
+
+    #[test]
+    fn root_field_tags_are_tool_categories() {
+        let schema = Schema::parse_and_validate(
+            r#"
+                directive @tag(name: String!) repeatable on FIELD_DEFINITION
+                type Query { invoices: [Invoice] @tag(name: "billing") }
+                type Invoice { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let operation = Operation::from_document(
+            RawOperation::from(("query Invoices { invoices { id } }".to_string(), None)),
+            &schema,
+            None,
+            &ToolOptions {
+                disable_type_description: true,
+                disable_schema_description: true,
+                tag_categories: true,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(operation.categories(), ["billing"]);
+        assert_eq!(
+            Tool::from(operation).description.as_deref(),
+            Some("Categories: billing")
+        );
+    }
+
+    #[test]
+    #[traced_test]
+    fn dependency_comments_are_hints_in_the_description() {
+        let operation = |source: &str| {
+            Operation::from_document(
+                RawOperation::from((source.to_string(), None)),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    disable_type_description: true,
+                    disable_schema_description: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let operations = vec![
+            operation("# Create the order\n# @next(GetOrder)\nquery CreateOrder { id }"),
+            operation("# @requires(CreateOrder)\n# @next(CancelOrder)\nquery GetOrder { id }"),
+        ];
+
+        insta::assert_snapshot!(operations[0].as_ref().description.as_deref().unwrap_or_default(), @r"
+        Create the order
+
+        After calling this, use GetOrder
+        ");
+        insta::assert_snapshot!(operations[1].as_ref().description.as_deref().unwrap_or_default(), @r"
+        Before calling this, use CreateOrder
+
+        After calling this, use CancelOrder
+        ");
+
+        check_operation_dependencies(&operations);
+        assert!(logs_contain(
+            "Operation GetOrder refers to the unknown operation CancelOrder in its @next comment"
+        ));
+        assert!(!logs_contain("unknown operation CreateOrder"));
+        assert!(!logs_contain("unknown operation GetOrder"));
+    }
+
+    #[test]
+    fn read_only_comment_overrides_the_operation_type() {
+        let read_only_hint = |source: &str, options: &ToolOptions| {
+            Tool::from(
+                Operation::from_document(
+                    RawOperation::from((source.to_string(), None)),
+                    &SCHEMA,
+                    None,
+                    options,
+                )
+                .unwrap()
+                .unwrap(),
+            )
+            .annotations
+            .and_then(|annotations| annotations.read_only_hint)
+        };
+        let default_options = ToolOptions::default();
+        let disabled_heuristic = ToolOptions {
+            disable_read_only_heuristic: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            read_only_hint("query Logged { id }", &default_options),
+            Some(true)
+        );
+        assert_eq!(
+            read_only_hint("# @readOnly(false)\nquery Logged { id }", &default_options),
+            Some(false)
+        );
+        assert_eq!(
+            read_only_hint("query Logged { id }", &disabled_heuristic),
+            None
+        );
+        assert_eq!(
+            read_only_hint(
+                "# @readOnly(true)\nquery Logged { id }",
+                &disabled_heuristic
+            ),
+            Some(true)
+        );
+        assert!(matches!(
+            Operation::from_document(
+                RawOperation::from(("# @readOnly(maybe)\nquery Logged { id }".to_string(), None)),
+                &SCHEMA,
+                None,
+                &default_options,
+            ),
+            Err(OperationError::InvalidDirective { directive, .. }) if directive == "@readOnly(maybe)"
+        ));
     }
 
     #[test]
-    fn with_variable_overrides() {
-        let operation = Operation::from_document(
-            RawOperation {
-                source_text: "query QueryName($id: ID, $name: String) { id }".to_string(),
-                persisted_query_id: None,
-                headers: None,
-                variables: Some(HashMap::from([(
-                    "id".to_string(),
-                    serde_json::Value::String("v".to_string()),
-                )])),
-                source_path: None,
-            },
-            &SCHEMA,
-            None,
-            MutationMode::None,
-            false,
-            false,
-        )
-        .unwrap()
-        .unwrap();
-        let tool = Tool::from(operation);
+    fn cache_ttl_comment_overrides_the_result_cache_ttl() {
+        let cache_ttl = |source: &str| {
+            Operation::from_document(
+                RawOperation::from((source.to_string(), None)),
+                &SCHEMA,
+                None,
+                &ToolOptions::default(),
+            )
+            .map(|operation| operation.unwrap().cache_ttl())
+        };
 
-        insta::assert_debug_snapshot!(tool, @r#"
-        Tool {
-            name: "QueryName",
-            description: Some(
-                "The returned value is optional and has type `String`",
-            ),
-            input_schema: {
-                "type": String("object"),
-                "properties": Object {
-                    "name": Object {
-                        "type": String("string"),
-                    },
-                },
-            },
-            annotations: Some(
-                ToolAnnotations {
-                    title: None,
-                    read_only_hint: Some(
-                        true,
-                    ),
-                    destructive_hint: None,
-                    idempotent_hint: None,
-                    open_world_hint: None,
+        assert_eq!(cache_ttl("query Logged { id }").unwrap(), None);
+        assert_eq!(
+            cache_ttl("# @cacheTtl(30s)\nquery Logged { id }").unwrap(),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            cache_ttl("# @cacheTtl(0)\nquery Logged { id }").unwrap(),
+            Some(Duration::ZERO)
+        );
+        assert!(matches!(
+            cache_ttl("# @cacheTtl(soon)\nquery Logged { id }"),
+            Err(OperationError::InvalidDirective { directive, .. }) if directive == "@cacheTtl(soon)"
+        ));
+    }
+
+    #[test]
+    fn destructive_operations_require_confirmation_when_enabled() {
+        let operation = |confirm_destructive_operations: bool| {
+            Operation::from_document(
+                RawOperation::from((
+                    "# @destructive(true)\nmutation Wipe { id }".to_string(),
+                    None,
+                )),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    mutation_mode: MutationMode::All,
+                    confirm_destructive_operations,
+                    ..Default::default()
                 },
-            ),
-        }
-        "#);
+            )
+            .unwrap()
+            .unwrap()
+        };
+
+        let unconfirmed = operation(false);
+        assert!(!unconfirmed.requires_confirmation());
+        assert_eq!(
+            unconfirmed
+                .tool
+                .annotations
+                .as_ref()
+                .and_then(|annotations| annotations.destructive_hint),
+            Some(true)
+        );
+        assert!(
+            !unconfirmed
+                .tool
+                .input_schema
+                .get("properties")
+                .is_some_and(|properties| properties.get(CONFIRM_ARGUMENT_NAME).is_some())
+        );
+
+        let confirmed = operation(true);
+        assert!(confirmed.requires_confirmation());
+        assert_eq!(
+            confirmed.tool.input_schema.get("required"),
+            Some(&serde_json::json!([CONFIRM_ARGUMENT_NAME]))
+        );
+        assert_eq!(
+            confirmed.tool.input_schema["properties"][CONFIRM_ARGUMENT_NAME]["type"],
+            serde_json::json!("boolean")
+        );
     }
 
     #[test]
-    fn input_schema_includes_variable_descriptions() {
+    fn tool_description_includes_example_arguments() {
         let operation = Operation::from_document(
-            RawOperation {
-                source_text: "query QueryName($idArg: ID) { customQuery(id: $idArg) { id } }"
+            RawOperation::from((
+                "# Get the custom query\nquery QueryName($id: ID!, $name: String, $enum: RealEnum) { customQuery(id: $id) { id } }"
                     .to_string(),
-                persisted_query_id: None,
-                headers: None,
-                variables: None,
-                source_path: None,
-            },
+                None,
+            )),
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions {
+                include_examples: true,
+                ..Default::default()
+            },
         )
         .unwrap()
         .unwrap();
-        let tool = Tool::from(operation);
 
-        insta::assert_snapshot!(serde_json::to_string_pretty(&serde_json::json!(tool.input_schema)).unwrap(), @r#"
-        {
-          "type": "object",
-          "properties": {
-            "idArg": {
-              "description": "id description",
-              "type": "string"
-            }
+        insta::assert_snapshot!(Tool::from(operation).description.unwrap_or_default(), @r#"
+        Get the custom query
+
+        Example: {"id":"abc123","name":"text","enum":"ENUM_VALUE_1"}
+        "#);
+    }
+
+    #[test]
+    fn tool_description_includes_operation_source() {
+        let description = |include_operation_source| {
+            let operation = Operation::from_document(
+                RawOperation::from((
+                    "# Get the custom query\nquery QueryName($idArg: ID!) { customQuery(id: $idArg) { id } }"
+                        .to_string(),
+                    None,
+                )),
+                &SCHEMA,
+                None,
+                &ToolOptions {
+                    include_operation_source,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .unwrap();
+            Tool::from(operation).description.unwrap_or_default()
+        };
+
+        insta::assert_snapshot!(description(true), @r"
+        Get the custom query
+
+        Operation:
+        query QueryName($idArg: ID!) {
+          customQuery(id: $idArg) {
+            id
           }
         }
-        "#);
+        ");
+        assert_eq!(description(false), "Get the custom query");
     }
 
     #[test]
@@ -2984,12 +7745,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
             .unwrap()
             .unwrap();
@@ -3021,12 +7782,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
             .unwrap()
             .unwrap();
@@ -3051,18 +7812,19 @@ mod tests {
 
     #[test]
     fn test_operation_name_with_named_query() {
-        let source_text = "query GetUser($id: ID!) { user(id: $id) { name email } }";
+        let source_text = "query GetUser($id: ID!) { customQuery(id: $id) { id } }";
         let raw_op = RawOperation {
             source_text: source_text.to_string(),
             persisted_query_id: None,
             headers: None,
             variables: None,
             source_path: None,
+            policy: ExecutionPolicy::default(),
+            description: None,
         };
-        let operation =
-            Operation::from_document(raw_op, &SCHEMA, None, MutationMode::None, false, false)
-                .unwrap()
-                .unwrap();
+        let operation = Operation::from_document(raw_op, &SCHEMA, None, &ToolOptions::default())
+            .unwrap()
+            .unwrap();
 
         let op_details = operation.operation(Value::Null).unwrap();
         assert_eq!(op_details.operation_name, Some(String::from("GetUser")));
@@ -3070,19 +7832,27 @@ mod tests {
 
     #[test]
     fn test_operation_name_with_named_mutation() {
-        let source_text =
-            "mutation CreateUser($input: UserInput!) { createUser(input: $input) { id name } }";
+        let source_text = "mutation CreateUser { id }";
         let raw_op = RawOperation {
             source_text: source_text.to_string(),
             persisted_query_id: None,
             headers: None,
             variables: None,
             source_path: None,
+            policy: ExecutionPolicy::default(),
+            description: None,
         };
-        let operation =
-            Operation::from_document(raw_op, &SCHEMA, None, MutationMode::Explicit, false, false)
-                .unwrap()
-                .unwrap();
+        let operation = Operation::from_document(
+            raw_op,
+            &SCHEMA,
+            None,
+            &ToolOptions {
+                mutation_mode: MutationMode::Explicit,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
 
         let op_details = operation.operation(Value::Null).unwrap();
         assert_eq!(op_details.operation_name, Some(String::from("CreateUser")));
@@ -3097,12 +7867,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
             .unwrap()
             .unwrap();
@@ -3130,12 +7900,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
             .unwrap()
             .unwrap();
@@ -3154,6 +7924,27 @@ mod tests {
         "#);
     }
 
+    #[test]
+    fn tagged_description_ignores_leading_comments() {
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "# Copyright Example Corp. All rights reserved.\n# Licensed under the MIT license.\n\n# @description Get the forecast\n# for a location\nquery GetForecast { id }"
+                    .to_string(),
+                None,
+            )),
+            &SCHEMA,
+            None,
+            &ToolOptions::default(),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(
+            operation.tool.description.as_deref(),
+            Some("Get the forecast\n for a location")
+        );
+    }
+
     #[test]
     fn comment_with_parens_has_comments_extracted_correctly() {
         let operation = Operation::from_document(
@@ -3163,12 +7954,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
             .unwrap()
             .unwrap();
@@ -3196,12 +7987,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
             .unwrap()
             .unwrap();
@@ -3233,12 +8024,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -3261,12 +8052,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
             .unwrap()
             .unwrap();
@@ -3298,12 +8089,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -3326,12 +8117,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -3441,12 +8232,12 @@ mod tests {
                 headers: None,
                 variables: None,
                 source_path: None,
+                policy: ExecutionPolicy::default(),
+                description: None,
             },
             &SCHEMA,
             None,
-            MutationMode::None,
-            false,
-            false,
+            &ToolOptions::default(),
         )
         .unwrap()
         .unwrap();
@@ -3538,4 +8329,380 @@ mod tests {
         }
         "##);
     }
+
+    #[test]
+    fn cached_operation_is_reused_for_unchanged_schema() {
+        let cache = OperationCache::default();
+        let schema_hash = OperationCache::schema_hash(&SCHEMA);
+        let raw_operation = RawOperation::from((
+            "query QueryName { id }".to_string(),
+            Some("query.graphql".to_string()),
+        ));
+        let convert = |raw_operation: RawOperation| {
+            raw_operation.into_operation(&SCHEMA, None, &ToolOptions::default())
+        };
+
+        let first = cache
+            .get_or_convert(raw_operation.clone(), schema_hash, convert)
+            .unwrap()
+            .unwrap();
+        let second = cache
+            .get_or_convert(raw_operation, schema_hash, |_| {
+                panic!("operation should have been cached")
+            })
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(first.as_ref(), second.as_ref());
+    }
+
+    #[test]
+    fn cached_operation_is_regenerated_for_changed_schema() {
+        let cache = OperationCache::default();
+        let raw_operation = RawOperation::from((
+            "query QueryName { id }".to_string(),
+            Some("query.graphql".to_string()),
+        ));
+        let changed_schema = Schema::parse_and_validate(
+            "type Query { \"\"\"the id\"\"\" id: ID }",
+            "schema.graphql",
+        )
+        .unwrap();
+
+        let original = cache
+            .get_or_convert(
+                raw_operation.clone(),
+                OperationCache::schema_hash(&SCHEMA),
+                |raw_operation| {
+                    raw_operation.into_operation(&SCHEMA, None, &ToolOptions::default())
+                },
+            )
+            .unwrap()
+            .unwrap();
+
+        let mut converted = false;
+        let changed = cache
+            .get_or_convert(
+                raw_operation,
+                OperationCache::schema_hash(&changed_schema),
+                |raw_operation| {
+                    converted = true;
+                    raw_operation.into_operation(&changed_schema, None, &ToolOptions::default())
+                },
+            )
+            .unwrap()
+            .unwrap();
+
+        assert!(converted);
+        assert_ne!(original.as_ref(), changed.as_ref());
+    }
+
+    #[test]
+    fn cached_operation_is_regenerated_for_changed_policy() {
+        let cache = OperationCache::default();
+        let schema_hash = OperationCache::schema_hash(&SCHEMA);
+        let raw_operation = RawOperation::from((
+            "query QueryName { id }".to_string(),
+            Some("query.graphql".to_string()),
+        ));
+        let convert = |raw_operation: RawOperation| {
+            raw_operation.into_operation(&SCHEMA, None, &ToolOptions::default())
+        };
+        let policy = ExecutionPolicy {
+            timeout: Some(Duration::from_millis(200)),
+            max_response_bytes: None,
+        };
+
+        cache
+            .get_or_convert(raw_operation.clone(), schema_hash, convert)
+            .unwrap()
+            .unwrap();
+        let changed = cache
+            .get_or_convert(
+                RawOperation {
+                    policy,
+                    ..raw_operation
+                },
+                schema_hash,
+                convert,
+            )
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(changed.execution_policy(), policy);
+    }
+
+    #[test]
+    fn cache_only_keeps_the_last_converted_operations() {
+        let cache = OperationCache::default();
+        let first = RawOperation::from(("query First { id }".to_string(), None));
+        let second = RawOperation::from(("query Second { id }".to_string(), None));
+        let changed_schema = Schema::parse_and_validate(
+            "type Query { \"\"\"the id\"\"\" id: ID }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let cached_keys = || {
+            let mut keys = cache
+                .entries
+                .lock()
+                .unwrap()
+                .keys()
+                .copied()
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+        let keys = |raw_operations: &[&RawOperation], schema| {
+            let mut keys = raw_operations
+                .iter()
+                .map(|operation| {
+                    (
+                        operation.content_hash(),
+                        OperationCache::schema_hash(schema),
+                    )
+                })
+                .collect::<Vec<_>>();
+            keys.sort();
+            keys
+        };
+
+        convert_operations(
+            vec![first.clone(), second.clone()],
+            &SCHEMA,
+            None,
+            &ToolOptions::default(),
+            &cache,
+        );
+        assert_eq!(cached_keys(), keys(&[&first, &second], &SCHEMA));
+
+        // Removed operations are evicted
+        convert_operations(
+            vec![first.clone()],
+            &SCHEMA,
+            None,
+            &ToolOptions::default(),
+            &cache,
+        );
+        assert_eq!(cached_keys(), keys(&[&first], &SCHEMA));
+
+        // Operations converted against a previous schema are evicted
+        convert_operations(
+            vec![first.clone()],
+            &changed_schema,
+            None,
+            &ToolOptions::default(),
+            &cache,
+        );
+        assert_eq!(cached_keys(), keys(&[&first], &changed_schema));
+    }
+
+    #[tokio::test]
+    async fn manifest_execution_policy_is_applied() {
+        let chunk = SignedUrlChunk::parse_and_validate(
+            &serde_json::json!({
+                "format": "apollo-persisted-query-manifest",
+                "version": 1,
+                "operations": [
+                    { "id": "1", "body": "query Slow { id }", "timeout": "200ms" },
+                    { "id": "2", "body": "query Large { id }", "maxResponseBytes": 20 },
+                ]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let mut manifest = PersistedQueryManifest::default();
+        manifest.add_chunk(&chunk);
+        let operations: HashMap<String, Operation> = manifest
+            .operations()
+            .map(|operation| {
+                let operation = RawOperation::from(operation)
+                    .into_operation(&SCHEMA, None, &ToolOptions::default())
+                    .unwrap()
+                    .unwrap();
+                (operation.as_ref().name.to_string(), operation)
+            })
+            .collect();
+        assert_eq!(
+            operations["Slow"].execution_policy().timeout,
+            Some(std::time::Duration::from_millis(200))
+        );
+
+        let mut server = mockito::Server::new_async().await;
+        let endpoint = url::Url::parse(&server.url()).unwrap();
+        server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({ "operationName": "Slow" }),
+            ))
+            .with_header("content-type", "application/json")
+            .with_chunked_body(|w| {
+                std::thread::sleep(std::time::Duration::from_secs(1));
+                w.write_all(br#"{"data":{"id":"1"}}"#)
+            })
+            .create_async()
+            .await;
+        server
+            .mock("POST", "/")
+            .match_body(mockito::Matcher::PartialJson(
+                serde_json::json!({ "operationName": "Large" }),
+            ))
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"data":{"id":"a value longer than the limit"}}"#)
+            .create_async()
+            .await;
+        let error_codes = ErrorCodes::default();
+        let execute = |name: &str| {
+            operations[name].execute(graphql::Request {
+                input: Value::Null,
+                endpoint: &endpoint,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &error_codes,
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: None,
+            })
+        };
+
+        let error = execute("Slow").await.unwrap_err();
+        assert_eq!(error.message, "GraphQL operation timed out after 200ms");
+
+        let error = execute("Large").await.unwrap_err();
+        assert_eq!(
+            error.message,
+            "GraphQL response of 47 bytes exceeds the maximum of 20 bytes"
+        );
+    }
+
+    #[tokio::test]
+    async fn collection_operation_headers_are_sent() {
+        let operation_data = OperationData::new(
+            "1".to_string(),
+            "2025-01-01T00:00:00Z".to_string(),
+            "query GetId { id }".to_string(),
+            Some(vec![
+                ("x-client".to_string(), "mcp".to_string()),
+                ("x-feature".to_string(), "a".to_string()),
+                ("x-feature".to_string(), "b".to_string()),
+            ]),
+            None,
+        );
+        let raw_operation = RawOperation::try_from(&operation_data).unwrap();
+        assert_eq!(
+            raw_operation
+                .headers
+                .as_ref()
+                .map(|headers| headers.get_all("x-feature").iter().count()),
+            Some(2)
+        );
+        let operation = raw_operation
+            .into_operation(&SCHEMA, None, &ToolOptions::default())
+            .unwrap()
+            .unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let endpoint = url::Url::parse(&server.url()).unwrap();
+        let mock = server
+            .mock("POST", "/")
+            .match_header("x-client", "mcp")
+            .match_header("x-feature", mockito::Matcher::Regex("^(a|b)$".to_string()))
+            .match_header("authorization", "Bearer token")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"data":{"id":"1"}}"#)
+            .create_async()
+            .await;
+        let mut default_headers = HeaderMap::new();
+        default_headers.insert("authorization", HeaderValue::from_static("Bearer token"));
+        default_headers.insert("x-feature", HeaderValue::from_static("default"));
+        assert_eq!(
+            operation
+                .headers(&default_headers)
+                .get_all("x-feature")
+                .iter()
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        operation
+            .execute(graphql::Request {
+                input: Value::Null,
+                endpoint: &endpoint,
+                headers: default_headers,
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &ErrorCodes::default(),
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: None,
+            })
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn responses_are_validated_against_the_output_schema() {
+        let operation = Operation::from_document(
+            RawOperation::from((
+                "query GetCustom { customQuery(id: \"1\") { id } }".to_string(),
+                None,
+            )),
+            &SCHEMA,
+            None,
+            &ToolOptions {
+                output_validation: OutputValidation::Warn,
+                ..Default::default()
+            },
+        )
+        .unwrap()
+        .unwrap();
+
+        let mut server = mockito::Server::new_async().await;
+        let endpoint = url::Url::parse(&server.url()).unwrap();
+        server
+            .mock("POST", "/")
+            .with_header("content-type", "application/json")
+            .with_body(r#"{"data":{"customQuery":{}}}"#)
+            .create_async()
+            .await;
+        let result = operation
+            .execute(graphql::Request {
+                input: Value::Null,
+                endpoint: &endpoint,
+                headers: HeaderMap::new(),
+                scope_mask: None,
+                claims: None,
+                timeout: None,
+                graphql_errors: GraphQLErrors::default(),
+                error_codes: &ErrorCodes::default(),
+                signing: None,
+                projection: None,
+                strip_nulls: None,
+                report_coercions: false,
+                websocket: None,
+            })
+            .await
+            .unwrap();
+
+        // The data is still returned, with the mismatch as a warning
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(
+            result.content[1].as_text().unwrap().text,
+            "Warning: The response does not match the output schema of the operation: /data/customQuery: missing field `id`"
+        );
+        assert!(logs_contain(
+            "The response does not match the output schema of the operation"
+        ));
+    }
 }
@@ -0,0 +1,164 @@
+//! Variable defaults computed from sibling variables
+//!
+//! An operation can default a variable to the value of another variable, optionally offset by a
+//! number or a duration, with a comment directive such as
+//! `# @computedDefault("endDate = startDate + 30d")`. The expressions are deliberately limited:
+//!
+//! - `target = source` copies the value of `source`
+//! - `target = source + 10` (or `-`) offsets a number
+//! - `target = source + 30d` (or `-`, with `d`, `h`, `m`, or `s` units) offsets a date
+//!   (`YYYY-MM-DD`) or an RFC 3339 date-time
+
+use std::sync::LazyLock;
+
+use chrono::{DateTime, NaiveDate, SecondsFormat, TimeDelta};
+use regex::Regex;
+use serde::Serialize;
+use serde_json::Value;
+
+/// The format of the dates computed defaults can offset
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[allow(clippy::expect_used)]
+static EXPRESSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*\$?(\w+)\s*=\s*\$?(\w+)\s*(?:([+-])\s*(\d+(?:\.\d+)?)\s*([dhms])?)?\s*$")
+        .expect("computed default expression regex is valid")
+});
+
+/// An offset added to the source variable's value
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+enum Offset {
+    /// Add to a number
+    Number(f64),
+
+    /// Add a number of seconds to a date or date-time
+    Seconds(i64),
+}
+
+/// A variable whose default is computed from a sibling variable
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub(super) struct ComputedDefault {
+    /// The variable to compute the default of
+    pub(super) variable: String,
+
+    /// The variable the default is computed from
+    pub(super) source: String,
+
+    offset: Option<Offset>,
+}
+
+impl ComputedDefault {
+    /// Parse a computed default expression, returning `None` if it is not valid
+    pub(super) fn parse(expression: &str) -> Option<Self> {
+        let captures = EXPRESSION.captures(expression)?;
+        let variable = captures.get(1)?.as_str().to_string();
+        let source = captures.get(2)?.as_str().to_string();
+        let offset = match (captures.get(3), captures.get(4)) {
+            (Some(sign), Some(amount)) => {
+                let amount = amount.as_str().parse::<f64>().ok()?;
+                let amount = if sign.as_str() == "-" {
+                    -amount
+                } else {
+                    amount
+                };
+                Some(match captures.get(5).map(|unit| unit.as_str()) {
+                    None => Offset::Number(amount),
+                    Some(unit) => {
+                        let seconds_per_unit = match unit {
+                            "d" => 86_400.0,
+                            "h" => 3_600.0,
+                            "m" => 60.0,
+                            _ => 1.0,
+                        };
+                        Offset::Seconds((amount * seconds_per_unit).round() as i64)
+                    }
+                })
+            }
+            _ => None,
+        };
+        (variable != source).then_some(Self {
+            variable,
+            source,
+            offset,
+        })
+    }
+
+    /// Compute the default from the source variable's value
+    pub(super) fn compute(&self, value: &Value) -> Result<Value, String> {
+        match self.offset {
+            None => Ok(value.clone()),
+            Some(Offset::Number(offset)) => match (value.as_i64(), value.as_f64()) {
+                (Some(value), _) if offset.fract() == 0.0 => Ok(Value::from(value + offset as i64)),
+                (_, Some(value)) => serde_json::Number::from_f64(value + offset)
+                    .map(Value::Number)
+                    .ok_or_else(|| format!("{} is not a finite number", value + offset)),
+                _ => Err(format!("{} must be a number", self.source)),
+            },
+            Some(Offset::Seconds(seconds)) => {
+                let delta = TimeDelta::seconds(seconds);
+                let text = value
+                    .as_str()
+                    .ok_or_else(|| format!("{} must be a date or date-time", self.source))?;
+                if let Ok(date) = NaiveDate::parse_from_str(text, DATE_FORMAT) {
+                    date.and_hms_opt(0, 0, 0)
+                        .and_then(|date| date.checked_add_signed(delta))
+                        .map(|date| Value::String(date.format(DATE_FORMAT).to_string()))
+                        .ok_or_else(|| format!("{} is out of range", self.variable))
+                } else if let Ok(date_time) = DateTime::parse_from_rfc3339(text) {
+                    date_time
+                        .checked_add_signed(delta)
+                        .map(|date_time| {
+                            Value::String(date_time.to_rfc3339_opts(SecondsFormat::AutoSi, true))
+                        })
+                        .ok_or_else(|| format!("{} is out of range", self.variable))
+                } else {
+                    Err(format!("{} must be a date or date-time", self.source))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn compute(expression: &str, value: Value) -> Result<Value, String> {
+        ComputedDefault::parse(expression).unwrap().compute(&value)
+    }
+
+    #[test]
+    fn computes_offsets() {
+        assert_eq!(compute("end = start", json!("a")), Ok(json!("a")));
+        assert_eq!(compute("last = first + 10", json!(5)), Ok(json!(15)));
+        assert_eq!(compute("ratio = base - 0.5", json!(2)), Ok(json!(1.5)));
+        assert_eq!(
+            compute("endDate = startDate + 30d", json!("2025-01-15")),
+            Ok(json!("2025-02-14"))
+        );
+        assert_eq!(
+            compute("$until = $since - 90m", json!("2025-01-15T12:00:00Z")),
+            Ok(json!("2025-01-15T10:30:00Z"))
+        );
+        assert!(compute("endDate = startDate + 30d", json!(3)).is_err());
+        assert!(compute("last = first + 10", json!("5")).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_expressions() {
+        for expression in [
+            "endDate",
+            "endDate = startDate * 2",
+            "endDate = startDate + 30w",
+            "endDate = now()",
+            "endDate = endDate + 1d",
+        ] {
+            assert!(
+                ComputedDefault::parse(expression).is_none(),
+                "{expression:?} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Fragment definitions shared across operation files
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use apollo_compiler::ast::{Definition, Document, FragmentDefinition, Selection};
+use apollo_compiler::parser::Parser;
+use apollo_compiler::{Name, Node};
+use tracing::warn;
+
+use super::OPERATION_DOCUMENT_EXTENSION;
+use crate::errors::OperationError;
+
+/// Fragment definitions which operations can spread without defining them in the same document
+#[derive(Clone, Debug, Default)]
+pub struct SharedFragments(Arc<HashMap<Name, Node<FragmentDefinition>>>);
+
+impl SharedFragments {
+    /// Load the fragments defined in GraphQL files, or in the GraphQL files in directories
+    pub fn load(paths: &[PathBuf]) -> Result<Self, OperationError> {
+        let mut fragments = HashMap::new();
+        for path in paths {
+            if path.is_dir() {
+                for entry in fs::read_dir(path)?.flatten() {
+                    let entry_path = entry.path();
+                    if entry_path.extension().and_then(|e| e.to_str())
+                        == Some(OPERATION_DOCUMENT_EXTENSION)
+                    {
+                        Self::load_file(&entry_path, &mut fragments)?;
+                    }
+                }
+            } else {
+                Self::load_file(path, &mut fragments)?;
+            }
+        }
+        Ok(Self(Arc::new(fragments)))
+    }
+
+    /// Collect the fragments defined in a single document
+    pub(crate) fn from_document(document: &Document) -> Self {
+        Self(Arc::new(
+            document
+                .definitions
+                .iter()
+                .filter_map(|definition| match definition {
+                    Definition::FragmentDefinition(fragment) => {
+                        Some((fragment.name.clone(), fragment.clone()))
+                    }
+                    _ => None,
+                })
+                .collect(),
+        ))
+    }
+
+    fn load_file(
+        path: &Path,
+        fragments: &mut HashMap<Name, Node<FragmentDefinition>>,
+    ) -> Result<(), OperationError> {
+        let source_text = fs::read_to_string(path)?;
+        let document = Parser::new()
+            .parse_ast(&source_text, path)
+            .map_err(|e| OperationError::GraphQLDocument(Box::new(e)))?;
+        for definition in document.definitions {
+            match definition {
+                Definition::FragmentDefinition(fragment) => {
+                    if fragments.contains_key(&fragment.name) {
+                        warn!(
+                            ?path,
+                            "Fragment {} is defined more than once", fragment.name
+                        );
+                    }
+                    fragments.insert(fragment.name.clone(), fragment);
+                }
+                _ => warn!(?path, "Skipping non-fragment definition in fragments file"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Append the shared fragments an operation spreads, but does not define itself, to its
+    /// source text. Fragments spread by those fragments are appended too.
+    pub(crate) fn complete(&self, source_text: &str) -> Option<String> {
+        if self.0.is_empty() {
+            return None;
+        }
+        let document = Parser::new()
+            .parse_ast(source_text, "operation.graphql")
+            .ok()?;
+
+        let mut defined = HashSet::new();
+        let mut spreads = Vec::new();
+        for definition in &document.definitions {
+            match definition {
+                Definition::OperationDefinition(operation) => {
+                    fragment_spreads(&operation.selection_set, &mut spreads);
+                }
+                Definition::FragmentDefinition(fragment) => {
+                    defined.insert(fragment.name.clone());
+                    fragment_spreads(&fragment.selection_set, &mut spreads);
+                }
+                _ => {}
+            }
+        }
+
+        let mut completed = source_text.to_string();
+        while let Some(name) = spreads.pop() {
+            if !defined.insert(name.clone()) {
+                continue;
+            }
+            if let Some(fragment) = self.0.get(&name) {
+                completed.push_str(&format!("\n\n{fragment}"));
+                fragment_spreads(&fragment.selection_set, &mut spreads);
+            }
+        }
+        (completed.len() != source_text.len()).then_some(completed)
+    }
+}
+
+/// Collect the names of the fragments spread in a selection set
+fn fragment_spreads(selections: &[Selection], spreads: &mut Vec<Name>) {
+    for selection in selections {
+        match selection {
+            Selection::Field(field) => fragment_spreads(&field.selection_set, spreads),
+            Selection::FragmentSpread(spread) => spreads.push(spread.fragment_name.clone()),
+            Selection::InlineFragment(inline) => fragment_spreads(&inline.selection_set, spreads),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+//! An index of loaded operations by the types they return
+
+use std::collections::HashMap;
+
+use super::Operation;
+
+/// Operation names keyed by the types returned by the root fields the operations select, to find
+/// e.g. which operations return a `User`
+#[derive(Clone, Debug, Default)]
+pub struct ReturnTypeIndex(HashMap<String, Vec<String>>);
+
+impl ReturnTypeIndex {
+    /// Index the return types of operations
+    pub fn new(operations: &[Operation]) -> Self {
+        let mut index: HashMap<String, Vec<String>> = HashMap::new();
+        for operation in operations {
+            for return_type in operation.return_types() {
+                index
+                    .entry(return_type.clone())
+                    .or_default()
+                    .push(operation.operation_name.clone());
+            }
+        }
+        Self(index)
+    }
+
+    /// The names of the operations returning a type, in the order they were loaded
+    pub fn search_operations_by_return_type(&self, type_name: &str) -> Vec<String> {
+        self.0.get(type_name).cloned().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apollo_compiler::Schema;
+
+    use super::*;
+    use crate::operations::{RawOperation, ToolOptions};
+
+    #[test]
+    fn finds_operations_by_return_type() {
+        let schema = Schema::parse_and_validate(
+            "type Query { user(id: ID!): User, posts: [Post!]! } type User { id: ID, name: String } type Post { id: ID }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let operations = [
+            "query GetUser($id: ID!) { user(id: $id) { name } }",
+            "query ListPosts { posts { id } }",
+        ]
+        .into_iter()
+        .map(|source_text| {
+            Operation::from_document(
+                RawOperation::from((source_text.to_string(), None)),
+                &schema,
+                None,
+                &ToolOptions::default(),
+            )
+            .unwrap()
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+        let index = ReturnTypeIndex::new(&operations);
+        assert_eq!(
+            index.search_operations_by_return_type("User"),
+            vec!["GetUser"]
+        );
+        assert_eq!(
+            index.search_operations_by_return_type("Post"),
+            vec!["ListPosts"]
+        );
+        assert!(index.search_operations_by_return_type("Query").is_empty());
+    }
+}
@@ -0,0 +1,147 @@
+//! Progress module for Apollo MCP Server
+//!
+//! Sends MCP progress notifications while a tool call runs longer than a threshold, so that
+//! clients waiting on a slow operation can tell it is still running. The total is unknown, so the
+//! progress only counts the notifications sent. Notifications are only sent for calls whose
+//! request includes a progress token.
+
+use std::{future::Future, time::Duration};
+
+use rmcp::{
+    Peer, RoleServer,
+    model::{ProgressNotificationParam, ProgressToken},
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::debug;
+
+/// The shortest interval between notifications, so that a zero interval can't flood the client
+const MIN_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Configuration options for progress notifications.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct ProgressConfig {
+    /// Set to true to send progress notifications during slow tool calls
+    pub enabled: bool,
+
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    #[schemars(with = "String")]
+    /// How long a tool call runs before the first notification (default: 2s)
+    pub threshold: Duration,
+
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    #[schemars(with = "String")]
+    /// How often notifications are sent after the first one (default: 5s)
+    pub interval: Duration,
+}
+
+impl Default for ProgressConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: Duration::from_secs(2),
+            interval: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ProgressConfig {
+    /// Run a tool call, notifying the peer of its progress if the call is slow
+    pub(crate) async fn notify<F: Future>(
+        &self,
+        tool: &str,
+        peer: &Peer<RoleServer>,
+        progress_token: ProgressToken,
+        call: F,
+    ) -> F::Output {
+        self.report(call, |progress| {
+            let notification = ProgressNotificationParam {
+                progress_token: progress_token.clone(),
+                progress,
+                total: None,
+                message: Some(format!("Tool {tool} is still running")),
+            };
+            async move {
+                if let Err(e) = peer.notify_progress(notification).await {
+                    debug!("Failed to send progress notification: {e}");
+                }
+            }
+        })
+        .await
+    }
+
+    /// Run a future, reporting a count of the intervals it has run for once it runs longer than
+    /// the threshold
+    async fn report<F, R, N>(&self, call: F, mut report: R) -> F::Output
+    where
+        F: Future,
+        R: FnMut(u32) -> N,
+        N: Future<Output = ()>,
+    {
+        tokio::pin!(call);
+        let mut ticks = tokio::time::interval_at(
+            Instant::now() + self.threshold,
+            self.interval.max(MIN_INTERVAL),
+        );
+        ticks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut progress = 0;
+        loop {
+            tokio::select! {
+                output = &mut call => return output,
+                _ = ticks.tick() => {
+                    progress += 1;
+                    report(progress).await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ProgressConfig {
+        ProgressConfig {
+            enabled: true,
+            threshold: Duration::from_secs(1),
+            interval: Duration::from_secs(1),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_calls_report_progress_before_completing() {
+        let mut reported = Vec::new();
+        let output = config()
+            .report(
+                async {
+                    tokio::time::sleep(Duration::from_millis(3500)).await;
+                    "done"
+                },
+                |progress| {
+                    reported.push(progress);
+                    async {}
+                },
+            )
+            .await;
+        assert_eq!(output, "done");
+        assert_eq!(reported, [1, 2, 3]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fast_calls_report_no_progress() {
+        let mut reported = Vec::new();
+        config()
+            .report(tokio::time::sleep(Duration::from_millis(500)), |progress| {
+                reported.push(progress);
+                async {}
+            })
+            .await;
+        assert!(reported.is_empty());
+    }
+}
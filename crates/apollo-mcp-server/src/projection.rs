@@ -0,0 +1,190 @@
+//! Projection module for Apollo MCP Server
+//!
+//! Lets the caller of an operation tool select just a slice of the GraphQL response with a
+//! restricted path expression, such as `data.user.name` or `data.users[0].name`, to reduce the
+//! number of tokens returned. Only field names and array indexes are supported.
+
+use std::fmt;
+
+use rmcp::model::ErrorCode;
+use serde_json::Value;
+
+use crate::errors::McpError;
+
+/// The name of the optional argument operation tools accept a projection in
+pub const PROJECT_ARGUMENT_NAME: &str = "__project";
+
+/// A single step of a projection path
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    /// Select a field of an object
+    Field(String),
+
+    /// Select an element of an array
+    Index(usize),
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::Field(name) => write!(f, ".{name}"),
+            Step::Index(index) => write!(f, "[{index}]"),
+        }
+    }
+}
+
+/// A path selecting part of a GraphQL response
+#[derive(Clone, Debug, PartialEq)]
+pub struct Projection {
+    steps: Vec<Step>,
+}
+
+impl Projection {
+    /// Parse a projection expression, optionally starting with a `.`
+    pub fn parse(expression: &str) -> Result<Self, McpError> {
+        let invalid = |reason: String| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("Invalid projection {expression:?}: {reason}"),
+                None,
+            )
+        };
+
+        let trimmed = expression.trim();
+        let mut rest = trimmed.strip_prefix('.').unwrap_or(trimmed);
+        if rest.is_empty() {
+            return Err(invalid("the expression is empty".to_string()));
+        }
+
+        let mut steps = Vec::new();
+        while !rest.is_empty() {
+            let (name, mut tail) = rest.split_at(rest.find(['.', '[']).unwrap_or(rest.len()));
+            if !name.is_empty() {
+                if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(invalid(format!("invalid field name {name:?}")));
+                }
+                steps.push(Step::Field(name.to_string()));
+            } else if !steps.is_empty() || !tail.starts_with('[') {
+                return Err(invalid("empty field name".to_string()));
+            }
+
+            while let Some(index) = tail.strip_prefix('[') {
+                let (index, after) = index
+                    .split_once(']')
+                    .ok_or_else(|| invalid("unclosed `[`".to_string()))?;
+                steps.push(Step::Index(
+                    index
+                        .parse()
+                        .map_err(|_| invalid(format!("invalid array index {index:?}")))?,
+                ));
+                tail = after;
+            }
+
+            rest = match tail.strip_prefix('.') {
+                Some("") => return Err(invalid("trailing `.`".to_string())),
+                Some(next) => next,
+                None if tail.is_empty() => tail,
+                None => return Err(invalid(format!("unexpected {tail:?}"))),
+            };
+        }
+        Ok(Self { steps })
+    }
+
+    /// Parse a projection from the value of the projection argument
+    pub fn from_argument(argument: Value) -> Result<Self, McpError> {
+        match argument {
+            Value::String(expression) => Self::parse(&expression),
+            _ => Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("{PROJECT_ARGUMENT_NAME} must be a string"),
+                None,
+            )),
+        }
+    }
+
+    /// Select the part of a response at the path, failing if the path does not exist
+    pub fn apply(&self, response: &Value) -> Result<Value, McpError> {
+        let mut value = response;
+        for (position, step) in self.steps.iter().enumerate() {
+            let next = match step {
+                Step::Field(name) => value.as_object().and_then(|object| object.get(name)),
+                Step::Index(index) => value.as_array().and_then(|array| array.get(*index)),
+            };
+            value = next.ok_or_else(|| {
+                let path = self
+                    .steps
+                    .iter()
+                    .take(position + 1)
+                    .map(Step::to_string)
+                    .collect::<String>();
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "Projection path {} not found in the response",
+                        path.trim_start_matches('.')
+                    ),
+                    None,
+                )
+            })?;
+        }
+        Ok(value.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn projects_a_path_out_of_the_response() {
+        let response = json!({
+            "data": {
+                "user": {
+                    "id": "1",
+                    "name": "Ada",
+                    "posts": [{ "title": "First" }, { "title": "Second" }],
+                },
+            },
+        });
+
+        let project = |expression| Projection::parse(expression).and_then(|p| p.apply(&response));
+        assert_eq!(project("data.user.name").unwrap(), json!("Ada"));
+        assert_eq!(
+            project(".data.user.posts[1].title").unwrap(),
+            json!("Second")
+        );
+        assert_eq!(
+            project("data.user.posts[2].title").unwrap_err().message,
+            "Projection path data.user.posts[2] not found in the response"
+        );
+        assert_eq!(
+            project("data.user.email").unwrap_err().message,
+            "Projection path data.user.email not found in the response"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_expressions() {
+        for expression in [
+            "",
+            ".",
+            "data..user",
+            "data.",
+            "data[x]",
+            "data[0",
+            "data[0]x",
+            "data.$x",
+        ] {
+            assert!(
+                Projection::parse(expression).is_err(),
+                "{expression:?} should be invalid"
+            );
+        }
+        assert_eq!(
+            Projection::parse("[0].id").unwrap().steps,
+            vec![Step::Index(0), Step::Field("id".to_string())]
+        );
+    }
+}
@@ -0,0 +1,521 @@
+//! REST tools for Apollo MCP Server
+//!
+//! Some tools need to call endpoints which aren't GraphQL, such as a file upload. A REST tool is
+//! declared in the config with a method, a URL template, and templates for the headers and JSON
+//! body of the request. Templates refer to tool arguments with `{argument}` placeholders, and are
+//! validated when the server starts.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, LazyLock};
+use std::time::Duration;
+
+use percent_encoding::{AsciiSet, NON_ALPHANUMERIC, utf8_percent_encode};
+use regex::Regex;
+use reqwest::Method;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rmcp::model::{CallToolResult, Content, ErrorCode, Tool, ToolAnnotations};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use url::Url;
+
+use crate::errors::{McpError, ServerError};
+
+/// A placeholder for the value of an argument in a template, e.g. `{id}`
+static PLACEHOLDER: LazyLock<Option<Regex>> =
+    LazyLock::new(|| Regex::new(r"\{([A-Za-z_][A-Za-z0-9_]*)\}").ok());
+
+/// The characters escaped in argument values substituted into a URL, leaving those which are
+/// safe in any part of a URL
+const URL_ESCAPED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// The configuration of a tool calling a REST endpoint
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RestToolConfig {
+    /// The name of the tool
+    pub name: String,
+
+    /// The description of the tool
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// The HTTP method of the request (default: GET)
+    #[serde(default = "RestToolConfig::default_method")]
+    pub method: String,
+
+    /// The URL of the request, with `{argument}` placeholders for argument values
+    pub url: String,
+
+    /// The headers of the request, whose values can contain `{argument}` placeholders
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+
+    /// The JSON body of the request. Strings which are a single `{argument}` placeholder are
+    /// replaced by the argument value, and placeholders within other strings by its text
+    #[serde(default)]
+    pub body: Option<Value>,
+
+    /// The arguments of the tool
+    #[serde(default)]
+    pub arguments: Vec<RestArgument>,
+}
+
+impl RestToolConfig {
+    fn default_method() -> String {
+        "GET".to_string()
+    }
+}
+
+// Header values often hold credentials, so they are redacted to never appear in logs
+impl fmt::Debug for RestToolConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RestToolConfig")
+            .field("name", &self.name)
+            .field("description", &self.description)
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field(
+                "headers",
+                &self
+                    .headers
+                    .keys()
+                    .map(|name| (name, "[REDACTED]"))
+                    .collect::<BTreeMap<_, _>>(),
+            )
+            .field("body", &self.body)
+            .field("arguments", &self.arguments)
+            .finish()
+    }
+}
+
+/// An argument of a REST tool
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RestArgument {
+    /// The name of the argument
+    pub name: String,
+
+    /// The JSON type of the argument (default: string)
+    #[serde(rename = "type", default)]
+    pub kind: RestArgumentType,
+
+    /// The description of the argument
+    #[serde(default)]
+    pub description: Option<String>,
+
+    /// Whether the argument must be provided
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// The JSON type of a REST tool argument
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum RestArgumentType {
+    #[default]
+    String,
+    Number,
+    Integer,
+    Boolean,
+}
+
+/// A tool calling a REST endpoint
+#[derive(Clone)]
+pub struct RestTool {
+    pub tool: Tool,
+    method: Method,
+    url: String,
+    headers: Vec<(HeaderName, String)>,
+    body: Option<Value>,
+    required: Vec<String>,
+}
+
+impl RestTool {
+    /// Create a REST tool, validating its templates against its arguments
+    pub fn new(config: RestToolConfig) -> Result<Self, ServerError> {
+        let invalid = |reason: String| ServerError::RestTool {
+            name: config.name.clone(),
+            reason,
+        };
+
+        let method = Method::from_bytes(config.method.to_uppercase().as_bytes())
+            .map_err(|_| invalid(format!("unknown method {}", config.method)))?;
+
+        let mut argument_names = HashSet::new();
+        for argument in &config.arguments {
+            if !argument_names.insert(argument.name.as_str()) {
+                return Err(invalid(format!(
+                    "argument {} is defined more than once",
+                    argument.name
+                )));
+            }
+        }
+        let check_placeholders = |template: &str, location: &str| match placeholders(template)
+            .find(|name| !argument_names.contains(name))
+        {
+            Some(name) => Err(invalid(format!(
+                "unknown argument {name} in the {location}"
+            ))),
+            None => Ok(()),
+        };
+
+        check_placeholders(&config.url, "url")?;
+        let example_url = PLACEHOLDER
+            .as_ref()
+            .map(|placeholder| placeholder.replace_all(&config.url, "x").to_string())
+            .unwrap_or_else(|| config.url.clone());
+        match Url::parse(&example_url) {
+            Ok(url) if matches!(url.scheme(), "http" | "https") => {}
+            Ok(url) => return Err(invalid(format!("unsupported URL scheme {}", url.scheme()))),
+            Err(error) => return Err(invalid(format!("invalid url: {error}"))),
+        }
+
+        let headers = config
+            .headers
+            .iter()
+            .map(|(name, value)| {
+                check_placeholders(value, &format!("{name} header"))?;
+                let name = HeaderName::from_bytes(name.as_bytes())
+                    .map_err(|_| invalid(format!("invalid header name {name}")))?;
+                Ok((name, value.clone()))
+            })
+            .collect::<Result<Vec<_>, ServerError>>()?;
+
+        if let Some(body) = &config.body {
+            for template in strings(body) {
+                check_placeholders(template, "body")?;
+            }
+        }
+
+        let properties: Map<String, Value> = config
+            .arguments
+            .iter()
+            .map(|argument| {
+                let mut schema = json!({ "type": argument.kind });
+                if let (Some(description), Some(schema)) =
+                    (&argument.description, schema.as_object_mut())
+                {
+                    schema.insert("description".to_string(), json!(description));
+                }
+                (argument.name.clone(), schema)
+            })
+            .collect();
+        let required: Vec<String> = config
+            .arguments
+            .iter()
+            .filter(|argument| argument.required)
+            .map(|argument| argument.name.clone())
+            .collect();
+        let mut input_schema = Map::new();
+        input_schema.insert("type".to_string(), json!("object"));
+        input_schema.insert("properties".to_string(), Value::Object(properties));
+        input_schema.insert("required".to_string(), json!(required));
+
+        let tool = Tool::new(
+            config.name.clone(),
+            config.description.clone().unwrap_or_default(),
+            Arc::new(input_schema),
+        )
+        .annotate(ToolAnnotations::new().read_only(matches!(method, Method::GET | Method::HEAD)));
+
+        Ok(Self {
+            tool,
+            method,
+            url: config.url,
+            headers,
+            body: config.body,
+            required,
+        })
+    }
+
+    /// Call the REST endpoint with the given arguments
+    pub async fn execute(
+        &self,
+        arguments: Option<Map<String, Value>>,
+        timeout: Option<Duration>,
+    ) -> Result<CallToolResult, McpError> {
+        let arguments = arguments.unwrap_or_default();
+        if let Some(missing) = self
+            .required
+            .iter()
+            .find(|name| arguments.get(*name).is_none_or(Value::is_null))
+        {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("Missing required argument {missing}"),
+                None,
+            ));
+        }
+
+        let url = render(&self.url, &arguments, |value| {
+            utf8_percent_encode(value, URL_ESCAPED).to_string()
+        });
+        let mut headers = HeaderMap::new();
+        for (name, template) in &self.headers {
+            let value = render(template, &arguments, str::to_string);
+            let value = HeaderValue::from_str(&value).map_err(|_| {
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("Invalid value for the {name} header: {value}"),
+                    None,
+                )
+            })?;
+            headers.insert(name.clone(), value);
+        }
+
+        let mut request = reqwest::Client::new()
+            .request(self.method.clone(), url)
+            .headers(headers);
+        if let Some(body) = &self.body {
+            request = request.json(&render_json(body, &arguments));
+        }
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        let response = request.send().await.map_err(|reqwest_error| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Failed to send REST request: {reqwest_error}"),
+                None,
+            )
+        })?;
+
+        let status = response.status();
+        let text = response.text().await.map_err(|reqwest_error| {
+            McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!("Failed to read REST response body: {reqwest_error}"),
+                None,
+            )
+        })?;
+        if status.is_success() {
+            Ok(CallToolResult::success(vec![Content::text(text)]))
+        } else {
+            Ok(CallToolResult::error(vec![Content::text(format!(
+                "The REST endpoint responded with {status}: {text}"
+            ))]))
+        }
+    }
+}
+
+/// The names of the arguments referred to by the placeholders in a template
+fn placeholders(template: &str) -> impl Iterator<Item = &str> {
+    PLACEHOLDER.iter().flat_map(move |placeholder| {
+        placeholder
+            .captures_iter(template)
+            .filter_map(|captures| captures.get(1))
+            .map(|name| name.as_str())
+    })
+}
+
+/// All of the strings in a JSON value, including object keys
+fn strings(value: &Value) -> Vec<&str> {
+    match value {
+        Value::String(string) => vec![string.as_str()],
+        Value::Array(values) => values.iter().flat_map(strings).collect(),
+        Value::Object(object) => object
+            .iter()
+            .flat_map(|(key, value)| std::iter::once(key.as_str()).chain(strings(value)))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Replace the placeholders in a template with the text of the argument values, encoded with the
+/// given function. Missing arguments are replaced with nothing.
+fn render(
+    template: &str,
+    arguments: &Map<String, Value>,
+    encode: impl Fn(&str) -> String,
+) -> String {
+    let Some(placeholder) = PLACEHOLDER.as_ref() else {
+        return template.to_string();
+    };
+    placeholder
+        .replace_all(template, |captures: &regex::Captures| {
+            captures
+                .get(1)
+                .and_then(|name| arguments.get(name.as_str()))
+                .map(|value| match value {
+                    Value::String(string) => encode(string),
+                    Value::Null => String::new(),
+                    value => encode(&value.to_string()),
+                })
+                .unwrap_or_default()
+        })
+        .to_string()
+}
+
+/// Replace the placeholders in the strings of a JSON template. A string which is a single
+/// placeholder is replaced by the argument value itself, keeping its type.
+fn render_json(template: &Value, arguments: &Map<String, Value>) -> Value {
+    match template {
+        Value::String(string) => {
+            let whole = PLACEHOLDER
+                .as_ref()
+                .and_then(|placeholder| placeholder.captures(string))
+                .filter(|captures| captures.get(0).is_some_and(|all| all.len() == string.len()))
+                .and_then(|captures| captures.get(1));
+            match whole {
+                Some(name) => arguments.get(name.as_str()).cloned().unwrap_or(Value::Null),
+                None => Value::String(render(string, arguments, str::to_string)),
+            }
+        }
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| render_json(value, arguments))
+                .collect(),
+        ),
+        Value::Object(object) => Value::Object(
+            object
+                .iter()
+                .map(|(key, value)| {
+                    (
+                        render(key, arguments, str::to_string),
+                        render_json(value, arguments),
+                    )
+                })
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(json: &str) -> RestToolConfig {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn calls_the_templated_endpoint() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("PUT", "/files/report%202024.pdf")
+            .match_header("x-folder", "reports")
+            .match_body(mockito::Matcher::Json(json!({
+                "size": 42,
+                "label": "size: 42",
+            })))
+            .with_body(r#"{ "uploaded": true }"#)
+            .create_async()
+            .await;
+        let tool = RestTool::new(config(&format!(
+            r#"{{
+                "name": "UploadFile",
+                "description": "Upload a file",
+                "method": "put",
+                "url": "{}/files/{{name}}",
+                "headers": {{ "x-folder": "{{folder}}" }},
+                "body": {{ "size": "{{size}}", "label": "size: {{size}}" }},
+                "arguments": [
+                    {{ "name": "name", "required": true }},
+                    {{ "name": "folder" }},
+                    {{ "name": "size", "type": "integer" }}
+                ]
+            }}"#,
+            server.url()
+        )))
+        .unwrap();
+
+        let result = tool
+            .execute(
+                json!({ "name": "report 2024.pdf", "folder": "reports", "size": 42 })
+                    .as_object()
+                    .cloned(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        mock.assert_async().await;
+        assert_eq!(result.is_error, Some(false));
+        assert_eq!(
+            result
+                .content
+                .first()
+                .and_then(|content| content.as_text())
+                .map(|text| text.text.as_str()),
+            Some(r#"{ "uploaded": true }"#)
+        );
+        assert_eq!(
+            tool.tool.input_schema.get("required"),
+            Some(&json!(["name"]))
+        );
+        assert_eq!(
+            tool.tool
+                .annotations
+                .and_then(|annotations| annotations.read_only_hint),
+            Some(false)
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_required_arguments_are_rejected() {
+        let tool = RestTool::new(config(
+            r#"{
+                "name": "GetFile",
+                "url": "http://localhost/files/{id}",
+                "arguments": [{ "name": "id", "required": true }]
+            }"#,
+        ))
+        .unwrap();
+
+        let error = tool.execute(None, None).await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    }
+
+    #[test]
+    fn invalid_mappings_are_rejected_at_load() {
+        let error = |json: &str| {
+            RestTool::new(config(json))
+                .err()
+                .map(|error| error.to_string())
+        };
+
+        assert_eq!(
+            error(r#"{ "name": "GetFile", "url": "http://localhost/files/{id}" }"#).as_deref(),
+            Some("Invalid REST tool GetFile: unknown argument id in the url")
+        );
+        assert_eq!(
+            error(
+                r#"{
+                    "name": "GetFile",
+                    "url": "http://localhost/files",
+                    "headers": { "x-token": "{token}" }
+                }"#
+            )
+            .as_deref(),
+            Some("Invalid REST tool GetFile: unknown argument token in the x-token header")
+        );
+        assert_eq!(
+            error(r#"{ "name": "GetFile", "method": "FETCH ME", "url": "http://localhost" }"#)
+                .as_deref(),
+            Some("Invalid REST tool GetFile: unknown method FETCH ME")
+        );
+        assert_eq!(
+            error(r#"{ "name": "GetFile", "url": "ftp://localhost/files" }"#).as_deref(),
+            Some("Invalid REST tool GetFile: unsupported URL scheme ftp")
+        );
+        assert_eq!(
+            error(
+                r#"{
+                    "name": "GetFile",
+                    "url": "http://localhost/files",
+                    "body": { "id": "{id}" }
+                }"#
+            )
+            .as_deref(),
+            Some("Invalid REST tool GetFile: unknown argument id in the body")
+        );
+    }
+}
@@ -0,0 +1,342 @@
+//! Result cache module for Apollo MCP Server
+//!
+//! Caches the results of read-only operation tools for a time to live. Results are keyed by the
+//! tool, its arguments, and the headers forwarded to the GraphQL endpoint, which carry the identity
+//! and context of the caller, so that data fetched for one caller is never served to another.
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use http::HeaderMap;
+use rmcp::model::CallToolResult;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::signing::SigningConfig;
+
+/// Headers which differ between calls without changing their results, and so are not keyed on
+const UNKEYED_HEADERS: [&str; 1] = ["mcp-session-id"];
+
+/// Configuration options for the result cache.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct ResultCacheConfig {
+    /// Set to true to cache the results of read-only operation tools
+    pub enabled: bool,
+
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    #[schemars(with = "String")]
+    /// How long a result is cached for (default: 60s)
+    pub ttl: Duration,
+
+    /// The maximum number of cached results (default: 1000)
+    pub max_entries: usize,
+
+    /// Set to true to never cache the results of calls forwarding the caller's `Authorization`
+    /// header, rather than caching them per caller
+    pub skip_forwarded_auth: bool,
+}
+
+impl Default for ResultCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl: Duration::from_secs(60),
+            max_entries: 1000,
+            skip_forwarded_auth: false,
+        }
+    }
+}
+
+/// The key of a cached result
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ResultCacheKey {
+    tool: String,
+    arguments: String,
+
+    /// A hash of the headers forwarded to the GraphQL endpoint
+    headers: [u8; 32],
+}
+
+impl ResultCacheKey {
+    /// Create the key of a call forwarding the given headers, other than the MCP session ID and
+    /// the request signature, which differ between calls by the same caller
+    pub fn new(
+        tool: &str,
+        arguments: Option<&Map<String, Value>>,
+        headers: &HeaderMap,
+        signing: Option<&SigningConfig>,
+    ) -> Self {
+        let signing_headers = signing.map(SigningConfig::header_names);
+        let mut keyed = headers
+            .iter()
+            .filter(|(name, _)| {
+                !UNKEYED_HEADERS.contains(&name.as_str())
+                    && !signing_headers.is_some_and(|signing_headers| {
+                        signing_headers
+                            .iter()
+                            .any(|header| name.as_str().eq_ignore_ascii_case(header))
+                    })
+            })
+            .map(|(name, value)| (name.as_str(), value.as_bytes()))
+            .collect::<Vec<_>>();
+        keyed.sort();
+
+        // Header names and values can't contain NUL, so it separates them unambiguously
+        let mut hasher = Sha256::new();
+        for (name, value) in keyed {
+            hasher.update(name);
+            hasher.update([0]);
+            hasher.update(value);
+            hasher.update([0]);
+        }
+
+        Self {
+            tool: tool.to_string(),
+            arguments: arguments
+                .map(|arguments| Value::Object(arguments.clone()).to_string())
+                .unwrap_or_default(),
+            headers: hasher.finalize().into(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ResultCache {
+    config: ResultCacheConfig,
+    entries: Arc<Mutex<HashMap<ResultCacheKey, (Instant, CallToolResult)>>>,
+}
+
+impl ResultCache {
+    pub fn new(config: ResultCacheConfig) -> Self {
+        Self {
+            config,
+            entries: Default::default(),
+        }
+    }
+
+    /// Whether the result of a call should be cached, given whether it forwards the caller's
+    /// `Authorization` header
+    pub fn caches(&self, forwards_auth: bool) -> bool {
+        !(forwards_auth && self.config.skip_forwarded_auth)
+    }
+
+    /// Get the cached result for a key, if it has not expired
+    pub async fn get(&self, key: &ResultCacheKey) -> Option<CallToolResult> {
+        let entries = self.entries.lock().await;
+        entries
+            .get(key)
+            .filter(|(expires, _)| *expires > Instant::now())
+            .map(|(_, result)| result.clone())
+    }
+
+    /// Cache a successful result for a key, evicting the result closest to expiring if the cache
+    /// is full
+    pub async fn insert(&self, key: ResultCacheKey, result: &CallToolResult) {
+        self.insert_with_ttl(key, result, self.config.ttl).await
+    }
+
+    /// Cache a successful result for a key for a time to live other than the configured one
+    pub async fn insert_with_ttl(
+        &self,
+        key: ResultCacheKey,
+        result: &CallToolResult,
+        ttl: Duration,
+    ) {
+        if result.is_error == Some(true) || self.config.max_entries == 0 || ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().await;
+        let now = Instant::now();
+        if entries.len() >= self.config.max_entries && !entries.contains_key(&key) {
+            entries.retain(|_, (expires, _)| *expires > now);
+            if entries.len() >= self.config.max_entries
+                && let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, (expires, _))| *expires)
+                    .map(|(key, _)| key.clone())
+            {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key, (now + ttl, result.clone()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rmcp::model::Content;
+    use serde_json::json;
+
+    use super::*;
+
+    fn cache(max_entries: usize) -> ResultCache {
+        ResultCache::new(ResultCacheConfig {
+            enabled: true,
+            ttl: Duration::from_secs(30),
+            max_entries,
+            skip_forwarded_auth: false,
+        })
+    }
+
+    fn result(text: &str) -> CallToolResult {
+        CallToolResult::success(vec![Content::text(text)])
+    }
+
+    fn headers(headers: &[(&'static str, &'static str)]) -> HeaderMap {
+        headers
+            .iter()
+            .map(|(name, value)| {
+                (
+                    http::HeaderName::from_static(name),
+                    http::HeaderValue::from_static(value),
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn callers_do_not_share_cached_results() {
+        let cache = cache(10);
+        let arguments = json!({ "id": "1" });
+        let arguments = arguments.as_object();
+        let alice = headers(&[("authorization", "Bearer alice")]);
+        let bob = headers(&[("authorization", "Bearer bob")]);
+
+        cache
+            .insert(
+                ResultCacheKey::new("GetCart", arguments, &alice, None),
+                &result("alice's cart"),
+            )
+            .await;
+
+        assert_eq!(
+            cache
+                .get(&ResultCacheKey::new("GetCart", arguments, &alice, None))
+                .await,
+            Some(result("alice's cart"))
+        );
+        assert_eq!(
+            cache
+                .get(&ResultCacheKey::new("GetCart", arguments, &bob, None))
+                .await,
+            None
+        );
+        assert_eq!(
+            cache
+                .get(&ResultCacheKey::new(
+                    "GetCart",
+                    arguments,
+                    &HeaderMap::new(),
+                    None
+                ))
+                .await,
+            None
+        );
+    }
+
+    #[test]
+    fn keys_cover_all_forwarded_headers_but_the_session_and_signature() {
+        let signing: SigningConfig = serde_json::from_value(json!({ "secret": "secret" })).unwrap();
+        let key =
+            |headers: &HeaderMap| ResultCacheKey::new("GetCart", None, headers, Some(&signing));
+        let alice = key(&headers(&[
+            ("authorization", "Bearer alice"),
+            ("x-athena-context", "1"),
+        ]));
+
+        assert_ne!(
+            key(&headers(&[
+                ("authorization", "Bearer alice"),
+                ("x-athena-context", "2"),
+            ])),
+            alice
+        );
+        assert_ne!(key(&headers(&[("authorization", "Bearer alice")])), alice);
+        assert_eq!(
+            key(&headers(&[
+                ("x-athena-context", "1"),
+                ("authorization", "Bearer alice"),
+                ("mcp-session-id", "session"),
+                ("x-signature", "signature"),
+                ("x-signature-timestamp", "1700000000"),
+            ])),
+            alice
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn results_expire_after_the_ttl() {
+        let cache = cache(10);
+        let key = ResultCacheKey::new("GetCatalog", None, &HeaderMap::new(), None);
+        cache.insert(key.clone(), &result("catalog")).await;
+
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert!(cache.get(&key).await.is_some());
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(cache.get(&key).await.is_none());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn results_can_override_the_ttl() {
+        let cache = cache(10);
+        let short = ResultCacheKey::new("GetCart", None, &HeaderMap::new(), None);
+        let long = ResultCacheKey::new("GetCatalog", None, &HeaderMap::new(), None);
+        cache
+            .insert_with_ttl(short.clone(), &result("cart"), Duration::from_secs(5))
+            .await;
+        cache
+            .insert_with_ttl(long.clone(), &result("catalog"), Duration::from_secs(300))
+            .await;
+
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert!(cache.get(&short).await.is_none());
+        tokio::time::advance(Duration::from_secs(60)).await;
+        assert!(cache.get(&long).await.is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn full_cache_evicts_the_result_closest_to_expiring() {
+        let cache = cache(2);
+        let key = |tool: &str| ResultCacheKey::new(tool, None, &HeaderMap::new(), None);
+        cache.insert(key("First"), &result("first")).await;
+        tokio::time::advance(Duration::from_secs(1)).await;
+        cache.insert(key("Second"), &result("second")).await;
+        cache.insert(key("Third"), &result("third")).await;
+
+        assert!(cache.get(&key("First")).await.is_none());
+        assert!(cache.get(&key("Second")).await.is_some());
+        assert!(cache.get(&key("Third")).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn errors_are_not_cached() {
+        let cache = cache(10);
+        let key = ResultCacheKey::new("GetCatalog", None, &HeaderMap::new(), None);
+        cache
+            .insert(
+                key.clone(),
+                &CallToolResult::error(vec![Content::text("failed")]),
+            )
+            .await;
+
+        assert!(cache.get(&key).await.is_none());
+    }
+
+    #[test]
+    fn forwarded_auth_can_skip_the_cache() {
+        let cache = ResultCache::new(ResultCacheConfig {
+            skip_forwarded_auth: true,
+            ..Default::default()
+        });
+
+        assert!(cache.caches(false));
+        assert!(!cache.caches(true));
+    }
+}
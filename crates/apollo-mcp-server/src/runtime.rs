@@ -35,14 +35,38 @@ pub fn read_config_from_env() -> Result<Config, figment::Error> {
         .extract()
 }
 
-/// Read in a config from a YAML file, filling in any missing values from the environment
+/// The fully-resolved config, after merging the config file, profile, and environment, formatted
+/// for debugging deployments. Secrets such as the Apollo key and header values are redacted.
+pub fn effective_config(config: &Config) -> String {
+    format!("{config:#?}")
+}
+
+/// The key of the named profiles in a config file
+const PROFILES_KEY: &str = "profiles";
+
+/// Read in a config from a YAML file, overlaid by the named profile from its `profiles` section if
+/// selected, filling in any missing values from the environment.
+///
+/// The profile is merged into the base config: options set in the profile override the same
+/// options in the base config, nested options are merged option by option, and lists are
+/// replaced as a whole. Environment variables override both.
 #[allow(clippy::result_large_err)]
-pub fn read_config(yaml_path: impl AsRef<Path>) -> Result<Config, figment::Error> {
-    Figment::new()
+pub fn read_config(
+    yaml_path: impl AsRef<Path>,
+    profile: Option<&str>,
+) -> Result<Config, figment::Error> {
+    let yaml = Figment::from(Yaml::file(yaml_path));
+    let mut figment = Figment::new()
         .join(apollo_common_env())
-        .join(Env::prefixed("APOLLO_MCP_").split(ENV_NESTED_SEPARATOR))
-        .join(Yaml::file(yaml_path))
-        .extract()
+        .join(Env::prefixed("APOLLO_MCP_").split(ENV_NESTED_SEPARATOR));
+    if let Some(profile) = profile {
+        let key = format!("{PROFILES_KEY}.{profile}");
+        if yaml.find_value(&key).is_err() {
+            return Err(format!("Config profile {profile} not found").into());
+        }
+        figment = figment.join(yaml.focus(&key));
+    }
+    figment.join(yaml).extract()
 }
 
 /// Figment provider that handles mapping common Apollo environment variables into
@@ -63,7 +87,11 @@ fn apollo_common_env() -> Env {
 
 #[cfg(test)]
 mod test {
-    use super::read_config;
+    use std::time::Duration;
+
+    use apollo_mcp_server::operations::MutationMode;
+
+    use super::{effective_config, read_config};
 
     #[test]
     fn it_prioritizes_env_vars() {
@@ -78,13 +106,88 @@ mod test {
             jail.create_file(path, config)?;
             jail.set_env("APOLLO_MCP_ENDPOINT", endpoint);
 
-            let config = read_config(path)?;
+            let config = read_config(path, None)?;
 
             assert_eq!(config.endpoint.as_str(), endpoint);
             Ok(())
         });
     }
 
+    #[test]
+    fn effective_config_reflects_overrides_and_redacts_secrets() {
+        let config = r#"
+            endpoint: http://from_file:4000
+            auxiliary:
+                bearer_token: auxiliary-token
+            headers:
+                Authorization: Bearer header-token
+            header_sets:
+                admin:
+                    Authorization: Bearer header-set-token
+            rest_tools:
+                - name: upload
+                  url: https://example.com/upload
+                  headers:
+                      Authorization: Bearer rest-token
+            telemetry:
+                otlp:
+                    endpoint: https://collector:4317
+                    headers:
+                        x-api-key: otlp-token
+        "#;
+
+        figment::Jail::expect_with(move |jail| {
+            let path = "config.yaml";
+            jail.create_file(path, config)?;
+            jail.set_env("APOLLO_MCP_ENDPOINT", "https://from_env:4000/");
+            jail.set_env("APOLLO_KEY", "service:graph:secret-key");
+
+            let effective = effective_config(&read_config(path, None)?);
+            assert!(effective.contains("from_env"));
+            assert!(!effective.contains("from_file"));
+            assert!(!effective.contains("secret-key"));
+            assert!(!effective.contains("auxiliary-token"));
+            assert!(!effective.contains("header-token"));
+            assert!(!effective.contains("header-set-token"));
+            assert!(!effective.contains("rest-token"));
+            assert!(!effective.contains("otlp-token"));
+            assert!(effective.contains("[REDACTED]"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn it_overlays_the_selected_profile() {
+        let config = r#"
+            endpoint: http://base:4000
+            overrides:
+                mutation_mode: explicit
+                disable_type_description: true
+            profiles:
+                prod:
+                    overrides:
+                        mutation_mode: none
+                dev:
+                    endpoint: http://dev:4000
+        "#;
+
+        figment::Jail::expect_with(move |jail| {
+            let path = "config.yaml";
+            jail.create_file(path, config)?;
+
+            let config = read_config(path, Some("prod"))?;
+            assert_eq!(config.endpoint.as_str(), "http://base:4000/");
+            assert_eq!(config.overrides.mutation_mode, MutationMode::None);
+            assert!(config.overrides.disable_type_description);
+
+            let config = read_config(path, None)?;
+            assert_eq!(config.overrides.mutation_mode, MutationMode::Explicit);
+
+            assert!(read_config(path, Some("staging")).is_err());
+            Ok(())
+        });
+    }
+
     #[test]
     fn it_extracts_nested_env() {
         let config = r#"
@@ -98,7 +201,7 @@ mod test {
             jail.create_file(path, config)?;
             jail.set_env("APOLLO_MCP_OVERRIDES__DISABLE_TYPE_DESCRIPTION", "true");
 
-            let config = read_config(path)?;
+            let config = read_config(path, None)?;
 
             assert!(config.overrides.disable_type_description);
             Ok(())
@@ -117,7 +220,7 @@ mod test {
             jail.create_file(path, config)?;
             jail.set_env("APOLLO_MCP_INTROSPECTION__EXECUTE__ENABLED", "true");
 
-            let config = read_config(path)?;
+            let config = read_config(path, None)?;
 
             assert_eq!(config.endpoint.as_str(), "http://from_file:4000/");
             assert!(config.introspection.execute.enabled);
@@ -125,6 +228,36 @@ mod test {
         });
     }
 
+    #[test]
+    fn it_configures_collection_polling() {
+        let config = "
+            graphos:
+                apollo_key: key
+                collection_polling:
+                    poll_interval: 1m
+                    timeout: 10s
+                    max_retries: 5
+                    retry_backoff: 500ms
+        ";
+
+        figment::Jail::expect_with(move |jail| {
+            let path = "config.yaml";
+            jail.create_file(path, config)?;
+
+            let config = read_config(path, None)?;
+            let platform_api_config = config.graphos.platform_api_config().unwrap();
+
+            assert_eq!(platform_api_config.poll_interval, Duration::from_secs(60));
+            assert_eq!(platform_api_config.timeout, Duration::from_secs(10));
+            assert_eq!(platform_api_config.max_retries, 5);
+            assert_eq!(
+                platform_api_config.retry_backoff,
+                Duration::from_millis(500)
+            );
+            Ok(())
+        });
+    }
+
     #[test]
     fn it_merges_env_and_file_with_uplink_endpoints() {
         let config = "
@@ -140,10 +273,27 @@ mod test {
                 "http://from_env:4000/,http://from_env2:4000/",
             );
 
-            let config = read_config(path)?;
+            let config = read_config(path, None)?;
 
             insta::assert_debug_snapshot!(config, @r#"
             Config {
+                admin: AdminConfig {
+                    enabled: false,
+                    path: "/admin",
+                    scope: "admin",
+                },
+                auxiliary: AuxiliaryConfig {
+                    bearer_token: None,
+                    port: None,
+                    address: 127.0.0.1,
+                    tls: None,
+                },
+                circuit_breaker: CircuitBreakerConfig {
+                    enabled: false,
+                    failure_threshold: 5,
+                    window: 60s,
+                    cooldown: 30s,
+                },
                 custom_scalars: None,
                 endpoint: Endpoint(
                     Url {
@@ -164,6 +314,10 @@ mod test {
                         fragment: None,
                     },
                 ),
+                feature_flags: FeatureFlagsConfig {
+                    flags: {},
+                    operations: {},
+                },
                 graphos: GraphOSConfig {
                     apollo_key: None,
                     apollo_graph_ref: None,
@@ -204,8 +358,15 @@ mod test {
                             fragment: None,
                         },
                     ],
+                    collection_polling: CollectionPollingConfig {
+                        poll_interval: 30s,
+                        timeout: 30s,
+                        max_retries: 3,
+                        retry_backoff: 1s,
+                    },
                 },
                 headers: {},
+                header_sets: {},
                 health_check: HealthCheckConfig {
                     enabled: false,
                     path: "/health",
@@ -220,16 +381,28 @@ mod test {
                 introspection: Introspection {
                     execute: ExecuteConfig {
                         enabled: false,
+                        default_selections: false,
                     },
                     introspect: IntrospectConfig {
                         enabled: false,
                         minify: false,
                     },
+                    list_operations: ListOperationsConfig {
+                        enabled: false,
+                    },
                     search: SearchConfig {
                         enabled: false,
                         index_memory_bytes: 50000000,
                         leaf_depth: 1,
+                        max_description_chars: None,
                         minify: false,
+                        format: Plain,
+                        collapse_redundant_paths: false,
+                        broaden_empty_results: false,
+                        index_subscriptions: false,
+                        index_directives: false,
+                        index_argument_names: false,
+                        max_visited_paths_per_type: 10000,
                     },
                     validate: ValidateConfig {
                         enabled: false,
@@ -241,15 +414,116 @@ mod test {
                     ),
                     path: None,
                     rotation: Hourly,
+                    token_estimator: CharsPerToken(
+                        4,
+                    ),
+                    tool_size_warn_threshold: None,
+                },
+                meter: MeterConfig {
+                    enabled: false,
+                    path: "/metrics",
+                    smoothing: 0.2,
+                    adaptive_timeout: AdaptiveTimeoutConfig {
+                        enabled: false,
+                        factor: 3.0,
+                        min: 1s,
+                        max: 30s,
+                    },
+                },
+                offline: OfflineConfig {
+                    enabled: false,
+                    cache_dir: None,
                 },
                 operations: Infer,
                 overrides: Overrides {
+                    builtin_tool_prefix: None,
+                    graph_ref_tool_namespace: false,
                     disable_type_description: false,
                     disable_schema_description: false,
+                    return_type_description: ReturnTypeDescription {
+                        template: "The returned value {optional}{list} `{type}`",
+                        optional: "is optional and ",
+                        required: "",
+                        list: "is an array of type",
+                        single: "has type",
+                    },
+                    include_root_schema_description: false,
+                    describe_variables: false,
+                    describe_graphql_types: false,
+                    property_titles: false,
+                    variable_summary: false,
+                    always_include_fields: [],
+                    confirm_destructive_operations: false,
+                    split_list_strings: false,
+                    sanitize_errors: false,
+                    output_validation: Off,
+                    fallback_description: None,
+                    definitions_keyword: Definitions,
+                    operation_loading_threads: None,
+                    schema_description_note: None,
+                    disable_read_only_heuristic: false,
+                    nullable_style: OneOf,
+                    include_operation_source: false,
+                    include_examples: false,
+                    tag_categories: false,
                     enable_explorer: false,
                     mutation_mode: None,
+                    max_mutation_mode: None,
+                    variables_as_object: false,
+                    null_variables: Keep,
+                    coerce_scalars: false,
+                    strip_operation_prefix: None,
+                    builtin_scalar_descriptions: {},
+                    constraint_directive: None,
+                    annotate_subgraphs: false,
+                    max_tools: None,
+                    tools_page_size: None,
+                    duplicate_operations: PreferFirst,
+                    dedupe_identical_operations: false,
+                    operation_constants: {},
+                    shake_cache: false,
+                    max_input_definitions: None,
+                    claim_variables: [],
+                    strict_operations: false,
+                    max_operation_depth: None,
+                    max_operation_variables: None,
+                    check_missing_fields: false,
+                    fragment_expansion: FragmentExpansion {
+                        max_depth: 32,
+                        max_selections: 10000,
+                    },
+                    single_operation_documents: false,
+                    result_projection: false,
+                    cost_estimates: false,
+                    tool_concurrency: {},
+                    operation_timeout: None,
+                    partial_results_on_timeout: false,
+                    graphql_errors: Warn,
+                    strip_nulls: None,
+                    report_coercions: false,
+                    websocket_endpoint: None,
+                    error_codes: ErrorCodes(
+                        {},
+                    ),
+                },
+                progress: ProgressConfig {
+                    enabled: false,
+                    threshold: 2s,
+                    interval: 5s,
+                },
+                rest_tools: [],
+                result_cache: ResultCacheConfig {
+                    enabled: false,
+                    ttl: 60s,
+                    max_entries: 1000,
+                    skip_forwarded_auth: false,
                 },
                 schema: Uplink,
+                signing: None,
+                telemetry: TelemetryConfig {
+                    service_name: "apollo-mcp-server",
+                    otlp: None,
+                },
                 transport: Stdio,
             }
             "#);
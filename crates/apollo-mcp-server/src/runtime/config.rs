@@ -1,6 +1,13 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-use apollo_mcp_server::{health::HealthCheckConfig, server::Transport};
+use apollo_mcp_server::{
+    admin::AdminConfig, auxiliary::AuxiliaryConfig, circuit_breaker::CircuitBreakerConfig,
+    feature_flags::FeatureFlagsConfig, health::HealthCheckConfig, meter::MeterConfig,
+    offline::OfflineConfig, progress::ProgressConfig, rest::RestToolConfig,
+    result_cache::ResultCacheConfig, server::Transport, signing::SigningConfig,
+    telemetry::TelemetryConfig,
+};
 use reqwest::header::HeaderMap;
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -15,6 +22,15 @@ use super::{
 #[derive(Debug, Default, Deserialize, JsonSchema)]
 #[serde(default)]
 pub struct Config {
+    /// Admin endpoint configuration
+    pub admin: AdminConfig,
+
+    /// Health check and metrics endpoint protection
+    pub auxiliary: AuxiliaryConfig,
+
+    /// Circuit breaker configuration
+    pub circuit_breaker: CircuitBreakerConfig,
+
     /// Path to a custom scalar map
     pub custom_scalars: Option<PathBuf>,
 
@@ -22,6 +38,9 @@ pub struct Config {
     #[schemars(schema_with = "Url::json_schema")]
     pub endpoint: Endpoint,
 
+    /// Feature flags gating operation tools
+    pub feature_flags: FeatureFlagsConfig,
+
     /// Apollo-specific credential overrides
     pub graphos: GraphOSConfig,
 
@@ -30,6 +49,11 @@ pub struct Config {
     #[schemars(schema_with = "super::schemas::header_map")]
     pub headers: HeaderMap,
 
+    /// Named sets of headers which operations can select with a `# @headers("name")` comment
+    #[serde(deserialize_with = "parsers::header_sets")]
+    #[schemars(schema_with = "super::schemas::header_sets")]
+    pub header_sets: HashMap<String, HeaderMap>,
+
     /// Health check configuration
     #[serde(default)]
     pub health_check: HealthCheckConfig,
@@ -40,24 +64,46 @@ pub struct Config {
     /// Logging configuration
     pub logging: Logging,
 
+    /// Tool latency metrics configuration
+    pub meter: MeterConfig,
+
+    /// Offline mode configuration
+    pub offline: OfflineConfig,
+
     /// Operations
     pub operations: OperationSource,
 
     /// Overrides for server behaviour
     pub overrides: Overrides,
 
+    /// Progress notification configuration
+    pub progress: ProgressConfig,
+
+    /// Tools calling REST endpoints
+    pub rest_tools: Vec<RestToolConfig>,
+
+    /// Result cache configuration
+    pub result_cache: ResultCacheConfig,
+
     /// The schema to load for operations
     pub schema: SchemaSource,
 
+    /// Request signing configuration
+    pub signing: Option<SigningConfig>,
+
+    /// Telemetry configuration
+    pub telemetry: TelemetryConfig,
+
     /// The type of server transport to use
     pub transport: Transport,
 }
 
 mod parsers {
+    use std::collections::HashMap;
     use std::str::FromStr;
 
     use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-    use serde::Deserializer;
+    use serde::{Deserialize, Deserializer};
 
     pub(super) fn map_from_str<'de, D>(deserializer: D) -> Result<HeaderMap, D::Error>
     where
@@ -82,9 +128,11 @@ mod parsers {
                 while let Some((key, value)) = map.next_entry::<String, String>()? {
                     let key = HeaderName::from_str(&key)
                         .map_err(|e| serde::de::Error::custom(e.to_string()))?;
-                    let value = HeaderValue::from_str(&value)
+                    let mut value = HeaderValue::from_str(&value)
                         .map_err(|e| serde::de::Error::custom(e.to_string()))?;
 
+                    // Header values often hold credentials, so they are never printed
+                    value.set_sensitive(true);
                     parsed.insert(key, value);
                 }
 
@@ -94,6 +142,21 @@ mod parsers {
 
         deserializer.deserialize_map(MapFromStrVisitor)
     }
+
+    pub(super) fn header_sets<'de, D>(
+        deserializer: D,
+    ) -> Result<HashMap<String, HeaderMap>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct HeaderSet(#[serde(deserialize_with = "map_from_str")] HeaderMap);
+
+        Ok(HashMap::<String, HeaderSet>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(name, HeaderSet(headers))| (name, headers))
+            .collect())
+    }
 }
 
 #[cfg(test)]
@@ -59,6 +59,45 @@ pub struct GraphOSConfig {
     /// List of uplink URL overrides
     #[serde(deserialize_with = "apollo_uplink_endpoints_deserializer")]
     apollo_uplink_endpoints: Vec<Url>,
+
+    /// How operation collections are polled from the platform API
+    collection_polling: CollectionPollingConfig,
+}
+
+/// Polling of operation collections from the platform API
+#[derive(Debug, Deserialize, JsonSchema)]
+#[cfg_attr(test, derive(Serialize))]
+#[serde(default, deny_unknown_fields)]
+pub struct CollectionPollingConfig {
+    /// How long to wait between polls (default: 30s)
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    poll_interval: Duration,
+
+    /// The timeout of each request (default: 30s)
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    timeout: Duration,
+
+    /// How many times to retry a request failing with a transient error, such as a timeout or a
+    /// server error (default: 3)
+    max_retries: u32,
+
+    /// The delay before the first retry, doubled for each retry after it (default: 1s)
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "String")]
+    retry_backoff: Duration,
+}
+
+impl Default for CollectionPollingConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(30),
+            max_retries: 3,
+            retry_backoff: Duration::from_secs(1),
+        }
+    }
 }
 
 impl GraphOSConfig {
@@ -70,6 +109,25 @@ impl GraphOSConfig {
             .ok_or_else(|| ServerError::EnvironmentVariable(APOLLO_GRAPH_REF_ENV.to_string()))
     }
 
+    /// The namespace for tool names derived from the graph ref, such as `mygraph_` for
+    /// `mygraph@current`. Characters not allowed in tool names are replaced with underscores.
+    #[allow(clippy::result_large_err)]
+    pub fn tool_namespace(&self) -> Result<String, ServerError> {
+        let graph_ref = self.graph_ref()?;
+        let graph_id = graph_ref.split('@').next().unwrap_or_default();
+        let slug: String = graph_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() {
+                    c.to_ascii_lowercase()
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Ok(format!("{slug}_"))
+    }
+
     /// Extract the apollo key from the config or from the current env
     #[allow(clippy::result_large_err)]
     fn key(&self) -> Result<SecretString, ServerError> {
@@ -100,15 +158,38 @@ impl GraphOSConfig {
     /// Generate a platform API config based on configuration params
     #[allow(clippy::result_large_err)]
     pub fn platform_api_config(&self) -> Result<PlatformApiConfig, ServerError> {
+        let polling = &self.collection_polling;
         let config = PlatformApiConfig::new(
             self.apollo_key
                 .clone()
                 .ok_or(ServerError::EnvironmentVariable(APOLLO_KEY_ENV.to_string()))?,
-            Duration::from_secs(30),
-            Duration::from_secs(30),
+            polling.poll_interval,
+            polling.timeout,
             self.apollo_registry_url.clone(),
-        );
+        )
+        .with_retries(polling.max_retries, polling.retry_backoff);
 
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graphos(graph_ref: &str) -> GraphOSConfig {
+        GraphOSConfig {
+            apollo_graph_ref: Some(graph_ref.to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tool_namespace_is_a_slug_of_the_graph_id() {
+        assert_eq!(
+            graphos("mygraph@current").tool_namespace().unwrap(),
+            "mygraph_"
+        );
+        assert_eq!(graphos("My-Graph").tool_namespace().unwrap(), "my_graph_");
+    }
+}
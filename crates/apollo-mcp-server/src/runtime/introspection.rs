@@ -1,3 +1,4 @@
+use apollo_mcp_server::server::SearchFormat;
 use schemars::JsonSchema;
 use serde::Deserialize;
 
@@ -11,6 +12,9 @@ pub struct Introspection {
     /// Introspect configuration for allowing clients to run introspection
     pub introspect: IntrospectConfig,
 
+    /// List operations tool configuration
+    pub list_operations: ListOperationsConfig,
+
     /// Search tool configuration
     pub search: SearchConfig,
 
@@ -24,6 +28,10 @@ pub struct Introspection {
 pub struct ExecuteConfig {
     /// Enable introspection for execution
     pub enabled: bool,
+
+    /// Complete the selections of object, interface, and union fields selected without
+    /// subfields, with their scalar and enum fields, or their type name if they have none
+    pub default_selections: bool,
 }
 
 /// Introspect-specific introspection configuration
@@ -37,6 +45,14 @@ pub struct IntrospectConfig {
     pub minify: bool,
 }
 
+/// List operations tool configuration
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct ListOperationsConfig {
+    /// Enable the tool listing the available operation tools
+    pub enabled: bool,
+}
+
 /// Search tool configuration
 #[derive(Debug, Deserialize, JsonSchema)]
 #[serde(default)]
@@ -52,8 +68,39 @@ pub struct SearchConfig {
     /// Defaults to 1.)
     pub leaf_depth: usize,
 
+    /// The maximum number of characters of each description to index. Longer descriptions are
+    /// truncated before indexing.
+    pub max_description_chars: Option<usize>,
+
     /// Minify search results
     pub minify: bool,
+
+    /// How to summarize the paths to the matching types ahead of their definitions
+    pub format: SearchFormat,
+
+    /// Collapse redundant paths to the matching types: paths which are the start of a longer
+    /// path, and paths through the same fields which differ only in their intermediate types
+    pub collapse_redundant_paths: bool,
+
+    /// Retry searches which find nothing once, matching their words fuzzily, and label the
+    /// results as broadened
+    pub broaden_empty_results: bool,
+
+    /// Index the types reachable from the subscription root type, so that they can be found by
+    /// searches, even though subscriptions can't be called as tools
+    pub index_subscriptions: bool,
+
+    /// Index custom directive definitions, so that searches can include them. This grows the
+    /// index, so it is disabled by default
+    pub index_directives: bool,
+
+    /// Index the names of the arguments of each field, so that searching for an argument name
+    /// matches the type defining the field
+    pub index_argument_names: bool,
+
+    /// The maximum number of partial paths visited while looking for the paths to each matching
+    /// type, bounding searches on highly-connected schemas
+    pub max_visited_paths_per_type: usize,
 }
 
 impl Default for SearchConfig {
@@ -62,7 +109,15 @@ impl Default for SearchConfig {
             enabled: false,
             index_memory_bytes: 50_000_000,
             leaf_depth: 1,
+            max_description_chars: None,
             minify: false,
+            format: SearchFormat::default(),
+            collapse_redundant_paths: false,
+            broaden_empty_results: false,
+            index_subscriptions: false,
+            index_directives: false,
+            index_argument_names: false,
+            max_visited_paths_per_type: 10_000,
         }
     }
 }
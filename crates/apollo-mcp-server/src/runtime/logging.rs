@@ -7,7 +7,10 @@ mod defaults;
 mod log_rotation_kind;
 mod parsers;
 
+use apollo_mcp_server::telemetry;
+use apollo_mcp_server::token_estimator::TokenEstimator;
 use log_rotation_kind::LogRotationKind;
+use opentelemetry_sdk::trace::SdkTracerProvider;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use std::path::PathBuf;
@@ -40,6 +43,15 @@ pub struct Logging {
     /// [default: Hourly]
     #[serde(default = "defaults::default_rotation")]
     pub rotation: LogRotationKind,
+
+    /// How to estimate the number of tokens used by each tool when it is loaded
+    /// [default: 4 characters per token]
+    #[serde(default)]
+    pub token_estimator: TokenEstimator,
+
+    /// Log tools whose character count exceeds this at `warn` instead of `info`
+    #[serde(default)]
+    pub tool_size_warn_threshold: Option<usize>,
 }
 
 impl Default for Logging {
@@ -48,12 +60,30 @@ impl Default for Logging {
             level: defaults::log_level(),
             path: None,
             rotation: defaults::default_rotation(),
+            token_estimator: TokenEstimator::default(),
+            tool_size_warn_threshold: None,
+        }
+    }
+}
+
+/// Keeps logging set up until dropped, when buffered logs and spans are flushed
+pub struct LoggingGuard {
+    _worker: Option<WorkerGuard>,
+    tracer_provider: Option<SdkTracerProvider>,
+}
+
+impl Drop for LoggingGuard {
+    fn drop(&mut self) {
+        if let Some(tracer_provider) = &self.tracer_provider
+            && let Err(e) = tracer_provider.shutdown()
+        {
+            eprintln!("Failed to flush exported spans: {e}");
         }
     }
 }
 
 impl Logging {
-    pub fn setup(config: &Config) -> Result<Option<WorkerGuard>, anyhow::Error> {
+    pub fn setup(config: &Config) -> Result<LoggingGuard, anyhow::Error> {
         let mut env_filter =
             EnvFilter::from_default_env().add_directive(config.logging.level.into());
 
@@ -98,6 +128,8 @@ impl Logging {
             None => (BoxMakeWriter::new(std::io::stdout), None, true),
         };
 
+        let tracer_provider = config.telemetry.tracer_provider()?;
+
         tracing_subscriber::registry()
             .with(env_filter)
             .with(
@@ -106,9 +138,13 @@ impl Logging {
                     .with_ansi(with_ansi)
                     .with_target(false),
             )
+            .with(tracer_provider.as_ref().map(telemetry::layer))
             .init();
 
-        Ok(guard)
+        Ok(LoggingGuard {
+            _worker: guard,
+            tracer_provider,
+        })
     }
 }
 
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use apollo_mcp_server::operations::OperationKind;
 use schemars::JsonSchema;
 use serde::Deserialize;
 
@@ -11,6 +12,10 @@ pub enum OperationSource {
     Collection {
         #[schemars(with = "String")]
         id: IdOrDefault,
+
+        /// Only expose operations of these types, such as only queries (default: all types)
+        #[serde(default)]
+        operation_types: Vec<OperationKind>,
     },
 
     /// Infer where to load operations based on other configuration options.
@@ -26,13 +31,52 @@ pub enum OperationSource {
     Introspect,
 
     /// Load operations from local GraphQL files / folders
-    Local { paths: Vec<PathBuf> },
+    Local {
+        paths: Vec<PathBuf>,
+
+        /// GraphQL files / folders defining fragments which any operation can spread
+        #[serde(default)]
+        fragments: Vec<PathBuf>,
+
+        /// Only expose operations of these types, such as only queries (default: all types)
+        #[serde(default)]
+        operation_types: Vec<OperationKind>,
+    },
 
     /// Load operations from a persisted queries manifest file
-    Manifest { path: PathBuf },
+    Manifest {
+        path: PathBuf,
+
+        /// Only expose operations of these types, such as only queries (default: all types)
+        #[serde(default)]
+        operation_types: Vec<OperationKind>,
+    },
 
     /// Load operations from uplink manifest
-    Uplink,
+    Uplink {
+        /// Only expose operations of these types, such as only queries (default: all types)
+        #[serde(default)]
+        operation_types: Vec<OperationKind>,
+    },
+}
+
+impl OperationSource {
+    /// The types of operations to expose, or none to expose all of them
+    pub fn operation_kinds(&self) -> Vec<OperationKind> {
+        match self {
+            OperationSource::Collection {
+                operation_types, ..
+            }
+            | OperationSource::Local {
+                operation_types, ..
+            }
+            | OperationSource::Manifest {
+                operation_types, ..
+            }
+            | OperationSource::Uplink { operation_types } => operation_types.clone(),
+            OperationSource::Infer | OperationSource::Introspect => Vec::new(),
+        }
+    }
 }
 
 /// Either a custom ID or the default variant
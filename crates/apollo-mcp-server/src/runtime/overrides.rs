@@ -1,4 +1,12 @@
-use apollo_mcp_server::operations::MutationMode;
+use apollo_mcp_server::operations::{
+    ClaimVariable, DefinitionsKeyword, DuplicateOperations, ErrorCodes, FragmentExpansion,
+    GraphQLErrors, MaxTools, MutationMode, NullVariables, NullableStyle, OutputValidation,
+    ReturnTypeDescription, StripNulls,
+};
+use std::collections::HashMap;
+use std::time::Duration;
+use url::Url;
+
 use schemars::JsonSchema;
 use serde::Deserialize;
 
@@ -6,15 +14,227 @@ use serde::Deserialize;
 #[derive(Debug, Deserialize, Default, JsonSchema)]
 #[serde(default)]
 pub struct Overrides {
+    /// Prefix the names of the built-in tools (such as `search` and `execute`) so they never
+    /// clash with operation tools
+    pub builtin_tool_prefix: Option<String>,
+
+    /// Prefix the names of all tools with a namespace derived from the graph ref (such as
+    /// `mygraph_` for `mygraph@current`), so that tools from several graphs never clash (requires
+    /// APOLLO_GRAPH_REF)
+    pub graph_ref_tool_namespace: bool,
+
     /// Disable type descriptions to save on context-window space
     pub disable_type_description: bool,
 
     /// Disable schema descriptions to save on context-window space
     pub disable_schema_description: bool,
 
+    /// The phrasing of the description of each root field's return type, added to tool
+    /// descriptions. The template has `{optional}`, `{list}`, and `{type}` placeholders
+    pub return_type_description: ReturnTypeDescription,
+
+    /// Include the description of the schema itself at the top of the schema descriptions, even
+    /// when type descriptions are disabled
+    pub include_root_schema_description: bool,
+
+    /// Summarize variable descriptions in each tool's description
+    pub describe_variables: bool,
+
+    /// Append the GraphQL type of each variable, such as `RealInputObject!`, to its description in
+    /// the tool's input schema
+    pub describe_graphql_types: bool,
+
+    /// Add a `title` derived from the name of each variable and input field, such as `First Name`
+    /// for `firstName`, to the tool's input schema
+    pub property_titles: bool,
+
+    /// Add an `x-variables` summary of the required and optional variable names to the input schema
+    /// of each operation tool, for clients which do not parse the full schema
+    pub variable_summary: bool,
+
+    /// Fields to always include in the schema types described with each operation, given as
+    /// `Type.field`, even when the operation does not select them
+    pub always_include_fields: Vec<String>,
+
+    /// Require tools for operations marked destructive with a `# @destructive(true)` comment to be
+    /// called with a confirmation argument set to true, rejecting calls without it before they are
+    /// executed
+    pub confirm_destructive_operations: bool,
+
+    /// Split string values of list variables with scalar items on commas, such as `"a,b,c"` into
+    /// `["a", "b", "c"]`, trimming whitespace from each item
+    pub split_list_strings: bool,
+
+    /// Replace the detail of errors from the GraphQL endpoint or transport returned to clients with
+    /// a generic message and an error ID, logging the full detail against the ID
+    pub sanitize_errors: bool,
+
+    /// What to do when the data returned for an operation does not match the output schema of the
+    /// operation, built from the fields it selects and their types in the GraphQL schema (default:
+    /// off)
+    pub output_validation: OutputValidation,
+
+    /// The description of operations which have neither comments nor schema descriptions, in which
+    /// `{name}` is replaced with the name of the operation
+    pub fallback_description: Option<String>,
+
+    /// The keyword under which input schemas define shared types, and which their references point
+    /// to: `definitions` for Draft-07 clients or `$defs` for Draft 2020-12 clients
+    pub definitions_keyword: DefinitionsKeyword,
+
+    /// The number of threads converting operations into tools in parallel, defaulting to the number
+    /// of available CPUs. Set to 1 to convert them one at a time
+    pub operation_loading_threads: Option<usize>,
+
+    /// A note added to tool descriptions in place of the schema description when schema
+    /// descriptions are disabled, such as a pointer to the search tool
+    pub schema_description_note: Option<String>,
+
+    /// Don't mark query operation tools as read-only and mutation operation tools as not, leaving
+    /// the read-only hint to operations which set it with a `# @readOnly(true | false)` comment
+    pub disable_read_only_heuristic: bool,
+
+    /// How nullable list items are represented in input schemas: `one_of` a schema or `null`, a
+    /// `type_array` including `null`, or `nullable: true`
+    pub nullable_style: NullableStyle,
+
+    /// Append each operation's formatted source text to its tool description
+    pub include_operation_source: bool,
+
+    /// Append an example of the arguments to call each operation tool with to its description
+    pub include_examples: bool,
+
+    /// Append the categories of each operation, from the `@tag(name:)` directives of the root
+    /// fields it selects, to its tool description, so that clients can group tools
+    pub tag_categories: bool,
+
     /// Expose a tool that returns the URL to open a GraphQL operation in Apollo Explorer (requires APOLLO_GRAPH_REF)
     pub enable_explorer: bool,
 
     /// Set the mutation mode access level for the MCP server
     pub mutation_mode: MutationMode,
+
+    /// The most permissive mutation mode operations can select with a `# @mutationMode` comment
+    pub max_mutation_mode: Option<MutationMode>,
+
+    /// Accept all operation variables as a single `variables` object argument
+    pub variables_as_object: bool,
+
+    /// How to send operation variables whose value is `null`
+    pub null_variables: NullVariables,
+
+    /// Parse string values of `Int`, `Float`, `Boolean`, and enum variables into the declared
+    /// type, matching enum values regardless of case
+    pub coerce_scalars: bool,
+
+    /// Remove this prefix from operation names when naming their tools
+    pub strip_operation_prefix: Option<String>,
+
+    /// Descriptions to give variables of built-in scalar types (such as `ID`), by type name
+    pub builtin_scalar_descriptions: HashMap<String, String>,
+
+    /// The name of a directive constraining the values of input fields and arguments, such as
+    /// `constraint` for `@constraint(minLength: 1, pattern: "^[a-z]+$")`. Its constraints are added
+    /// to the input schemas of tools
+    pub constraint_directive: Option<String>,
+
+    /// Annotate the fields of federated schemas with the subgraphs resolving them, using a
+    /// `@subgraph(name: "...")` directive shown in search results and tool descriptions
+    pub annotate_subgraphs: bool,
+
+    /// Limit the number of operation tools, for clients which can't handle many tools
+    pub max_tools: Option<MaxTools>,
+
+    /// Split the tools listed to clients into pages of at most this many tools, continued with
+    /// the cursor returned alongside each page
+    pub tools_page_size: Option<usize>,
+
+    /// How to handle operations with the same name from more than one source. By default, the
+    /// operation from the source listed first is kept, and the others are logged as shadowed
+    pub duplicate_operations: DuplicateOperations,
+
+    /// Keep only the first of the operations whose tools are identical apart from their names,
+    /// logging the others as duplicates
+    pub dedupe_identical_operations: bool,
+
+    /// Constants substituted for `{{name}}` placeholders in the source of each operation before it
+    /// is parsed, so one template can generate a tool with the constants baked in
+    pub operation_constants: HashMap<String, String>,
+
+    /// Cache the schemas shaken to describe operations, so that operations selecting the same
+    /// types and fields reuse them instead of shaking the schema again
+    pub shake_cache: bool,
+
+    /// Limit the number of distinct type definitions in each tool's input schema
+    pub max_input_definitions: Option<usize>,
+
+    /// Set operation variables from the claims of the caller's validated JWT
+    pub claim_variables: Vec<ClaimVariable>,
+
+    /// Fail operations which select no fields in the schema, instead of skipping them with a
+    /// warning
+    pub strict_operations: bool,
+
+    /// Skip operations whose selections are nested deeper than this, including the selections of
+    /// fragments. Such operations fail instead if `strict_operations` is set
+    pub max_operation_depth: Option<usize>,
+
+    /// Warn about operations with more variables than this, whose tools are hard to fill in.
+    /// Such operations fail instead if `strict_operations` is set
+    pub max_operation_variables: Option<usize>,
+
+    /// Warn about operations selecting fields which are not in the schema, such as fields removed
+    /// by a schema change. Such operations fail instead if `strict_operations` is set
+    pub check_missing_fields: bool,
+
+    /// Limits on the expansion of the fragments spread in operations, which guard against
+    /// recursive or repeatedly spread fragments. Fields past the limits are left out of tool
+    /// descriptions, with a warning
+    pub fragment_expansion: FragmentExpansion,
+
+    /// Fail to load documents containing more than one operation, instead of loading each named
+    /// operation as its own tool
+    pub single_operation_documents: bool,
+
+    /// Accept a projection argument in operation tools, selecting part of the response to return
+    /// with a path such as `data.user.name`
+    pub result_projection: bool,
+
+    /// Accept an estimate argument in query operation tools, returning the estimated cost of the
+    /// operation from the schema's `@cost` and `@listSize` annotations instead of executing it
+    pub cost_estimates: bool,
+
+    /// Limit the number of concurrent calls to a tool, by tool name (the operation name for
+    /// operation tools). Calls over the limit wait for an earlier call to finish
+    pub tool_concurrency: HashMap<String, usize>,
+
+    /// Time out GraphQL operations which take longer than this
+    #[serde(with = "humantime_serde")]
+    #[schemars(with = "Option<String>")]
+    pub operation_timeout: Option<Duration>,
+
+    /// Return the data delivered so far by incremental (`@defer`) responses when an operation
+    /// times out, instead of an error
+    pub partial_results_on_timeout: bool,
+
+    /// How to return GraphQL responses containing errors alongside data
+    pub graphql_errors: GraphQLErrors,
+
+    /// Remove null fields, empty lists, and empty objects from the data of responses, to save
+    /// tokens
+    pub strip_nulls: Option<StripNulls>,
+
+    /// Describe the coercions applied to the variables of operations, such as enum values
+    /// matched regardless of case, in the results of tool calls
+    pub report_coercions: bool,
+
+    /// A websocket endpoint to send operations to over one persistent connection, using the
+    /// `graphql-transport-ws` protocol. Operations are sent to the HTTP endpoint when the
+    /// websocket can't be connected to.
+    pub websocket_endpoint: Option<Url>,
+
+    /// The MCP error codes to fail tool calls with, by the `extensions.code` of the upstream
+    /// GraphQL errors which failed them. These take precedence over the default mapping of
+    /// common Apollo error codes, such as `UNAUTHENTICATED` to `invalid_request`
+    pub error_codes: ErrorCodes,
 }
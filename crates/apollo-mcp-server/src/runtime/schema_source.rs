@@ -1,16 +1,35 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
+use apollo_mcp_server::schema_reload::SchemaReloadConfig;
 use schemars::JsonSchema;
 use serde::Deserialize;
+use url::Url;
 
 /// Source for upstream GraphQL schema
 #[derive(Debug, Default, Deserialize, JsonSchema)]
 #[serde(tag = "source", rename_all = "snake_case")]
 pub enum SchemaSource {
     /// Schema should be loaded (and watched) from a local file path
-    Local { path: PathBuf },
+    Local {
+        path: PathBuf,
+
+        /// How to reload the schema when the file changes
+        #[serde(default)]
+        reload: SchemaReloadConfig,
+    },
 
     /// Fetch the schema from uplink
     #[default]
     Uplink,
+
+    /// Fetch the schema from a running GraphQL endpoint using introspection
+    Introspect {
+        /// The endpoint to introspect, defaulting to the target GraphQL endpoint
+        endpoint: Option<Url>,
+
+        /// How often to poll the endpoint for schema changes. The schema is fetched once if unset.
+        #[serde(default, with = "humantime_serde")]
+        #[schemars(with = "Option<String>")]
+        poll_interval: Option<Duration>,
+    },
 }
@@ -6,3 +6,7 @@ pub(super) fn header_map(generator: &mut schemars::SchemaGenerator) -> schemars:
     // A header map is just a hash map of string to string with extra validation
     HashMap::<String, String>::json_schema(generator)
 }
+
+pub(super) fn header_sets(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+    HashMap::<String, HashMap<String, String>>::json_schema(generator)
+}
@@ -0,0 +1,204 @@
+//! Debounce and retry hot reloads of the schema
+//!
+//! A schema file can be read while it is still being written, when it is not yet valid. Instead of
+//! failing the server, reloaded schemas are only applied once they are valid: changes to a schema
+//! file are read after a short debounce, and the file is read again a number of times if it is not
+//! valid. Until then, the server keeps serving the last valid schema.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use apollo_mcp_registry::uplink::schema::{SchemaState, event::Event as SchemaEvent};
+use futures::{Stream, StreamExt as _, stream};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+/// Configuration options for reloading a local schema file
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct SchemaReloadConfig {
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    #[schemars(with = "String")]
+    /// How long to wait after the schema file changes before reading it (default: 500ms)
+    pub debounce: Duration,
+
+    #[serde(deserialize_with = "humantime_serde::deserialize")]
+    #[serde(serialize_with = "humantime_serde::serialize")]
+    #[schemars(with = "String")]
+    /// How long to wait before reading an invalid schema file again (default: 1s)
+    pub retry_interval: Duration,
+
+    /// How many times to read an invalid schema file again before waiting for it to change
+    /// (default: 5)
+    pub max_retries: u32,
+}
+
+impl Default for SchemaReloadConfig {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(500),
+            retry_interval: Duration::from_secs(1),
+            max_retries: 5,
+        }
+    }
+}
+
+/// Reloads schemas, reading them again from their file if there is one
+struct Reloader<V> {
+    path: Option<PathBuf>,
+    config: SchemaReloadConfig,
+    is_valid: V,
+}
+
+impl<V: Fn(&str) -> bool> Reloader<V> {
+    /// The reloaded schema, or `None` if it never became valid
+    async fn reload(&self, schema_state: SchemaState) -> Option<SchemaState> {
+        let Some(path) = &self.path else {
+            if (self.is_valid)(&schema_state.sdl) {
+                return Some(schema_state);
+            }
+            warn!("Reloaded schema is not valid, keeping the last valid schema");
+            return None;
+        };
+
+        tokio::time::sleep(self.config.debounce).await;
+        let mut sdl = self.read(path).await.unwrap_or(schema_state.sdl);
+        for retry in 0..=self.config.max_retries {
+            if (self.is_valid)(&sdl) {
+                let Ok(schema_state) = sdl.parse();
+                return Some(schema_state);
+            }
+            if retry < self.config.max_retries {
+                warn!(
+                    "Reloaded schema at {} is not valid, reading it again in {:?}",
+                    path.display(),
+                    self.config.retry_interval
+                );
+                tokio::time::sleep(self.config.retry_interval).await;
+                sdl = self.read(path).await.unwrap_or(sdl);
+            }
+        }
+        warn!(
+            "Reloaded schema at {} is not valid, keeping the last valid schema until it changes",
+            path.display()
+        );
+        None
+    }
+
+    async fn read(&self, path: &Path) -> Option<String> {
+        tokio::fs::read_to_string(path)
+            .await
+            .inspect_err(|e| warn!("Failed to read schema at {}: {e}", path.display()))
+            .ok()
+    }
+}
+
+/// Apply the first schema of a stream of schema events as it is, and only apply the schemas
+/// reloaded after it once they are valid. If the schema comes from a file at `path`, changes are
+/// debounced and invalid schemas are read again.
+pub(crate) fn debounce_and_retry<V>(
+    events: impl Stream<Item = SchemaEvent> + Send + 'static,
+    path: Option<PathBuf>,
+    config: SchemaReloadConfig,
+    is_valid: V,
+) -> impl Stream<Item = SchemaEvent>
+where
+    V: Fn(&str) -> bool + Send + Sync + 'static,
+{
+    let reloader = Arc::new(Reloader {
+        path,
+        config,
+        is_valid,
+    });
+    stream::unfold((events.boxed(), true), move |(mut events, first)| {
+        let reloader = reloader.clone();
+        async move {
+            loop {
+                match events.next().await? {
+                    SchemaEvent::UpdateSchema(schema_state) if !first => {
+                        if let Some(schema_state) = reloader.reload(schema_state).await {
+                            return Some((
+                                SchemaEvent::UpdateSchema(schema_state),
+                                (events, false),
+                            ));
+                        }
+                    }
+                    event => return Some((event, (events, false))),
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use apollo_compiler::Schema;
+    use futures::SinkExt as _;
+    use futures::channel::mpsc;
+
+    use super::*;
+
+    const VALID: &str = "type Query { id: ID }";
+    const INVALID: &str = "type Query {";
+    const UPDATED: &str = "type Query { id: ID, name: String }";
+
+    fn update(sdl: &str) -> SchemaEvent {
+        SchemaEvent::UpdateSchema(sdl.parse().unwrap())
+    }
+
+    fn sdl(event: Option<SchemaEvent>) -> Option<String> {
+        match event {
+            Some(SchemaEvent::UpdateSchema(schema_state)) => Some(schema_state.sdl),
+            _ => None,
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_reloads_do_not_replace_the_valid_schema() {
+        let path =
+            std::env::temp_dir().join(format!("schema-reload-{}.graphql", std::process::id()));
+        std::fs::write(&path, VALID).unwrap();
+        let (mut sender, receiver) = mpsc::unbounded();
+        let mut events = debounce_and_retry(
+            receiver,
+            Some(path.clone()),
+            SchemaReloadConfig {
+                debounce: Duration::from_millis(10),
+                retry_interval: Duration::from_millis(50),
+                max_retries: 2,
+            },
+            |sdl| Schema::parse_and_validate(sdl, "schema.graphql").is_ok(),
+        )
+        .boxed();
+
+        sender.send(update(VALID)).await.unwrap();
+        assert_eq!(sdl(events.next().await).as_deref(), Some(VALID));
+
+        // An invalid schema which is never fixed is dropped
+        std::fs::write(&path, INVALID).unwrap();
+        sender.send(update(INVALID)).await.unwrap();
+        sender.send(SchemaEvent::NoMoreSchema).await.unwrap();
+        assert!(matches!(
+            events.next().await,
+            Some(SchemaEvent::NoMoreSchema)
+        ));
+
+        // An invalid schema which is fixed while retrying is replaced by the fixed one
+        sender.send(update(INVALID)).await.unwrap();
+        let writer = tokio::spawn({
+            let path = path.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(30)).await;
+                std::fs::write(&path, UPDATED).unwrap();
+            }
+        });
+        assert_eq!(sdl(events.next().await).as_deref(), Some(UPDATED));
+
+        writer.await.unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -11,8 +11,12 @@ use apollo_compiler::schema::InputValueDefinition;
 use apollo_compiler::validation::WithErrors;
 use apollo_compiler::{Name, Node, Schema};
 use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash as _, Hasher as _};
+use std::sync::{Arc, Mutex};
 use tracing::debug;
 
+use crate::operations::FragmentExpansion;
+
 struct RootOperationNames {
     query: String,
     mutation: String,
@@ -82,6 +86,11 @@ pub struct SchemaTreeShaker<'schema> {
     operation_type_names: RootOperationNames,
     named_fragments: HashMap<String, Node<FragmentDefinition>>,
     arguments_descriptions: HashMap<String, Vec<String>>,
+    arguments_directives: HashMap<String, DirectiveList>,
+    fragment_expansion: FragmentExpansion,
+    remaining_selections: usize,
+    fragment_expansion_truncated: bool,
+    always_included_fields: HashMap<String, Vec<String>>,
 }
 
 struct TreeTypeNode {
@@ -99,6 +108,11 @@ impl<'schema> SchemaTreeShaker<'schema> {
         &self.arguments_descriptions
     }
 
+    /// The directives of the arguments each operation variable is passed to, by variable name
+    pub(crate) fn argument_directives(&self) -> &HashMap<String, DirectiveList> {
+        &self.arguments_directives
+    }
+
     pub fn new(schema: &'schema Schema) -> Self {
         let mut named_type_nodes: HashMap<String, TreeTypeNode> = HashMap::default();
         let mut directive_nodes: HashMap<String, TreeDirectiveNode> = HashMap::default();
@@ -142,7 +156,38 @@ impl<'schema> SchemaTreeShaker<'schema> {
             named_fragments: HashMap::default(),
             operation_type_names: RootOperationNames::new(schema),
             arguments_descriptions: HashMap::default(),
+            arguments_directives: HashMap::default(),
+            fragment_expansion: FragmentExpansion::default(),
+            remaining_selections: FragmentExpansion::default().max_selections,
+            fragment_expansion_truncated: false,
+            always_included_fields: HashMap::default(),
+        }
+    }
+
+    /// Always retain these fields, given as `Type.field`, alongside the selected fields of their
+    /// type. The types of the fields are retained without the types they reference.
+    pub fn with_always_included_fields(mut self, fields: &[String]) -> Self {
+        for field in fields {
+            if let Some((type_name, field_name)) = field.split_once('.') {
+                self.always_included_fields
+                    .entry(type_name.to_string())
+                    .or_default()
+                    .push(field_name.to_string());
+            }
         }
+        self
+    }
+
+    /// Limit the expansion of the fragments of retained operations
+    pub fn with_fragment_expansion(mut self, fragment_expansion: FragmentExpansion) -> Self {
+        self.fragment_expansion = fragment_expansion;
+        self.remaining_selections = fragment_expansion.max_selections;
+        self
+    }
+
+    /// Whether the expansion of fragments stopped at the limits, leaving out the fields past them
+    pub fn fragment_expansion_truncated(&self) -> bool {
+        self.fragment_expansion_truncated
     }
 
     pub fn retain_operation_type(
@@ -194,6 +239,7 @@ impl<'schema> SchemaTreeShaker<'schema> {
                 _ => None,
             })
             .collect();
+        self.remaining_selections = self.fragment_expansion.max_selections;
         self.retain_operation_type(
             operation.operation_type,
             Some(&operation.selection_set),
@@ -201,6 +247,60 @@ impl<'schema> SchemaTreeShaker<'schema> {
         )
     }
 
+    /// Whether any field of the retained root operation types exists in the schema. An operation
+    /// which only selects fields missing from the schema retains no fields.
+    pub fn retains_root_fields(&self) -> bool {
+        self.operation_types.iter().any(|operation_type| {
+            let type_name = self
+                .operation_type_names
+                .name_for_operation_type(*operation_type);
+            let Some(ExtendedType::Object(root_type)) = self.schema.types.get(type_name) else {
+                return false;
+            };
+            self.named_type_nodes
+                .get(type_name)
+                .map(|tree_node| match &tree_node.filtered_field {
+                    Some(fields) => fields
+                        .iter()
+                        .any(|field| root_type.fields.contains_key(field.as_str())),
+                    None => tree_node.retain && !root_type.fields.is_empty(),
+                })
+                .unwrap_or_default()
+        })
+    }
+
+    /// A hash of the types, fields, and directives retained so far. Tree shakers of the same
+    /// schema with the same signature produce the same shaken schema.
+    pub fn signature(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for operation_type in &self.operation_types {
+            operation_type.to_string().hash(&mut hasher);
+        }
+        let mut types: Vec<_> = self
+            .named_type_nodes
+            .iter()
+            .filter(|(_, tree_node)| tree_node.retain)
+            .map(|(name, tree_node)| {
+                let mut fields = tree_node.filtered_field.clone();
+                if let Some(fields) = &mut fields {
+                    fields.sort();
+                }
+                (name, fields)
+            })
+            .collect();
+        types.sort();
+        types.hash(&mut hasher);
+        let mut directives: Vec<_> = self
+            .directive_nodes
+            .iter()
+            .filter(|(_, directive_node)| directive_node.retain)
+            .map(|(name, _)| name)
+            .collect();
+        directives.sort();
+        directives.hash(&mut hasher);
+        hasher.finish()
+    }
+
     /// Return the set of types retained after tree shaking.
     pub fn shaken(&mut self) -> Result<Schema, Box<WithErrors<Schema>>> {
         let root_operations = self
@@ -496,32 +596,49 @@ impl<'schema> SchemaTreeShaker<'schema> {
     }
 }
 
+/// Collect the fields of a selection, expanding fragments up to a maximum depth and within a
+/// budget of selections, so that recursive or repeatedly spread fragments can't expand without
+/// bound. Returns false if a limit was reached and fields were left out.
 fn selection_set_to_fields(
-    selection_set: &Selection,
+    selection: &Selection,
     named_fragments: &HashMap<String, Node<FragmentDefinition>>,
-) -> Vec<Node<Field>> {
-    match selection_set {
+    max_depth: usize,
+    remaining_selections: &mut usize,
+    fields: &mut Vec<Node<Field>>,
+) -> bool {
+    let Some(remaining) = remaining_selections.checked_sub(1) else {
+        return false;
+    };
+    *remaining_selections = remaining;
+
+    let mut expand = |selections: &[Selection]| {
+        let Some(max_depth) = max_depth.checked_sub(1) else {
+            return false;
+        };
+        // Keep expanding the other selections when one of them reaches a limit
+        let mut complete = true;
+        for selection in selections {
+            complete &= selection_set_to_fields(
+                selection,
+                named_fragments,
+                max_depth,
+                remaining_selections,
+                fields,
+            );
+        }
+        complete
+    };
+    match selection {
         Selection::Field(field) => {
-            if field.name == "__typename" {
-                vec![]
-            } else {
-                vec![field.clone()]
+            if field.name != "__typename" {
+                fields.push(field.clone());
             }
+            true
         }
         Selection::FragmentSpread(fragment) => named_fragments
             .get(fragment.fragment_name.as_str())
-            .map(|f| {
-                f.selection_set
-                    .iter()
-                    .flat_map(|s| selection_set_to_fields(s, named_fragments))
-                    .collect()
-            })
-            .unwrap_or_default(),
-        Selection::InlineFragment(fragment) => fragment
-            .selection_set
-            .iter()
-            .flat_map(|s| selection_set_to_fields(s, named_fragments))
-            .collect(),
+            .is_none_or(|f| expand(&f.selection_set)),
+        Selection::InlineFragment(fragment) => expand(&fragment.selection_set),
     }
 }
 
@@ -533,6 +650,13 @@ fn retain_argument_descriptions(
     let operation_argument_name = operation_arguments.get(arg.name.as_str());
 
     if let Some(op_arg_name) = operation_argument_name {
+        if !arg.directives.is_empty() {
+            tree_shaker
+                .arguments_directives
+                .entry(op_arg_name.to_string())
+                .or_default()
+                .extend(arg.directives.iter().cloned());
+        }
         if let Some(description) = arg.description.as_deref() {
             if !description.trim().is_empty() {
                 let descriptions = tree_shaker
@@ -565,10 +689,18 @@ fn retain_type(
 
     let type_name = extended_type.name().as_str();
     let selected_fields = if let Some(selection_set) = selection_set {
-        let selected_fields = selection_set
-            .iter()
-            .flat_map(|s| selection_set_to_fields(s, &tree_shaker.named_fragments))
-            .collect::<Vec<_>>();
+        let mut selected_fields = Vec::new();
+        for selection in selection_set {
+            if !selection_set_to_fields(
+                selection,
+                &tree_shaker.named_fragments,
+                tree_shaker.fragment_expansion.max_depth,
+                &mut tree_shaker.remaining_selections,
+                &mut selected_fields,
+            ) {
+                tree_shaker.fragment_expansion_truncated = true;
+            }
+        }
 
         Some(selected_fields)
     } else {
@@ -590,6 +722,14 @@ fn retain_type(
             let additional_fields = selected_fields
                 .iter()
                 .map(|f| f.name.to_string())
+                .chain(
+                    tree_shaker
+                        .always_included_fields
+                        .get(type_name)
+                        .into_iter()
+                        .flatten()
+                        .cloned(),
+                )
                 .collect::<Vec<_>>();
 
             tree_node.filtered_field = Some(
@@ -607,6 +747,25 @@ fn retain_type(
         .iter()
         .for_each(|t| retain_directive(tree_shaker, t.name.as_str(), depth_limit));
 
+    // The types of always included fields are needed to describe them, but not the types they
+    // reference in turn
+    if selected_fields.is_some()
+        && let Some(field_names) = tree_shaker.always_included_fields.get(type_name).cloned()
+    {
+        for field_name in field_names {
+            let field_definition = match extended_type {
+                ExtendedType::Object(def) => def.fields.get(field_name.as_str()),
+                ExtendedType::Interface(def) => def.fields.get(field_name.as_str()),
+                _ => None,
+            };
+            if let Some(field_type) = field_definition
+                .and_then(|field| tree_shaker.schema.types.get(field.ty.inner_named_type()))
+            {
+                retain_type(tree_shaker, field_type, None, DepthLimit::Limited(1));
+            }
+        }
+    }
+
     match extended_type {
         ExtendedType::Object(def) => {
             selected_fields
@@ -889,16 +1048,109 @@ fn retain_directive(
     }
 }
 
+/// A cache of shaken schemas, keyed by the signature of the tree shaker which produced them.
+///
+/// Operations selecting the same types and fields share a shaken schema, so describing them only
+/// shakes the schema once. The cache only holds schemas shaken from one schema at a time, and is
+/// emptied when operations are converted against another schema.
+#[derive(Clone, Debug, Default)]
+pub struct ShakeCache {
+    entries: Arc<Mutex<ShakeCacheEntries>>,
+}
+
+#[derive(Debug, Default)]
+struct ShakeCacheEntries {
+    schema_hash: Option<u64>,
+    shaken: HashMap<u64, Arc<Schema>>,
+}
+
+impl ShakeCache {
+    /// Drop the cached schemas unless they were shaken from the schema with the given hash
+    pub(crate) fn retain_schema(&self, schema_hash: u64) {
+        if let Ok(mut entries) = self.entries.lock()
+            && entries.schema_hash != Some(schema_hash)
+        {
+            entries.schema_hash = Some(schema_hash);
+            entries.shaken.clear();
+        }
+    }
+
+    /// Get the cached schema shaken with the same signature, or shake and cache it if not present.
+    /// A schema which fails to validate is cached as the partial schema.
+    pub(crate) fn get_or_shake(&self, tree_shaker: &mut SchemaTreeShaker) -> Arc<Schema> {
+        let signature = tree_shaker.signature();
+        if let Some(shaken) = self
+            .entries
+            .lock()
+            .ok()
+            .and_then(|entries| entries.shaken.get(&signature).cloned())
+        {
+            debug!("Reusing cached shaken schema {signature:x}");
+            return shaken;
+        }
+
+        // Shaken outside the lock, so other threads aren't blocked. Concurrent misses for the same
+        // signature shake the schema more than once, but cache the same result.
+        let shaken = Arc::new(tree_shaker.shaken().unwrap_or_else(|schema| schema.partial));
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.shaken.insert(signature, shaken.clone());
+        }
+        shaken
+    }
+
+    /// The number of cached schemas
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .map(|entries| entries.shaken.len())
+            .unwrap_or_default()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use apollo_compiler::{ast::OperationType, parser::Parser};
+    use apollo_compiler::{Schema, ast::OperationType, parser::Parser};
     use rstest::{fixture, rstest};
 
+    use std::sync::Arc;
+
     use crate::{
-        operations::operation_defs,
-        schema_tree_shake::{DepthLimit, SchemaTreeShaker},
+        operations::{FragmentExpansion, operation_defs},
+        schema_tree_shake::{DepthLimit, SchemaTreeShaker, ShakeCache},
     };
 
+    #[test]
+    fn operations_over_the_same_types_share_a_cached_shaken_schema() {
+        let schema = Schema::parse_and_validate(
+            "type Query { user: User, post: Post } type User { id: ID, name: String } type Post { id: ID }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let cache = ShakeCache::default();
+        cache.retain_schema(1);
+        let shake = |source_text: &str| {
+            let (document, operation, _) =
+                operation_defs(source_text, false, None).unwrap().unwrap();
+            let mut shaker = SchemaTreeShaker::new(&schema);
+            shaker.retain_operation(&operation, &document, DepthLimit::Unlimited);
+            cache.get_or_shake(&mut shaker)
+        };
+
+        let user = shake("query GetUser { user { id name } }");
+        let same_user = shake("query UserName { user { name id } }");
+        assert!(Arc::ptr_eq(&user, &same_user));
+        assert_eq!(cache.len(), 1);
+
+        let post = shake("query GetPost { post { id } }");
+        assert!(!Arc::ptr_eq(&user, &post));
+        assert_eq!(cache.len(), 2);
+
+        // Schemas shaken from another schema are dropped
+        cache.retain_schema(2);
+        assert_eq!(cache.len(), 0);
+    }
+
     #[test]
     fn should_remove_type_mutation_mode_none() {
         let source_text = r#"
@@ -1040,6 +1292,46 @@ mod test {
         );
     }
 
+    #[test]
+    fn should_limit_fragment_expansion() {
+        let schema = Schema::parse_and_validate("type Query { id: ID }", "schema.graphql").unwrap();
+
+        // A fragment spreading itself, and fragments each spreading the next one twice, which
+        // would expand without bound or to 2^30 fields
+        let fragments = (0..30)
+            .map(|i| format!("fragment F{i} on Query {{ ...F{} ...F{} }}", i + 1, i + 1))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let source_text = format!(
+            "query TestQuery {{ ...Recursive ...F0 }}\nfragment Recursive on Query {{ id ...Recursive }}\n{fragments}\nfragment F30 on Query {{ id }}"
+        );
+        let (operation_document, operation_def, _comments) =
+            operation_defs(&source_text, false, None).unwrap().unwrap();
+
+        let mut shaker =
+            SchemaTreeShaker::new(&schema).with_fragment_expansion(FragmentExpansion {
+                max_depth: 8,
+                max_selections: 1_000,
+            });
+        shaker.retain_operation(&operation_def, &operation_document, DepthLimit::Unlimited);
+        assert!(shaker.fragment_expansion_truncated());
+        assert_eq!(
+            shaker.shaken().unwrap().to_string(),
+            "type Query {\n  id: ID\n}\n"
+        );
+
+        let mut shaker = SchemaTreeShaker::new(&schema);
+        let (operation_document, operation_def, _comments) = operation_defs(
+            "query TestQuery { ...F } fragment F on Query { id }",
+            false,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        shaker.retain_operation(&operation_def, &operation_document, DepthLimit::Unlimited);
+        assert!(!shaker.fragment_expansion_truncated());
+    }
+
     #[fixture]
     fn nested_schema() -> apollo_compiler::Schema {
         Parser::new()
@@ -1404,4 +1696,34 @@ mod test {
         assert!(description.is_some());
         assert_eq!(*description.unwrap(), vec!["the value"]);
     }
+
+    #[test]
+    fn should_retain_always_included_fields() {
+        let source_text = r#"
+            type Query { user(id: ID!): User }
+            type User { id: ID! name: String email: String team: Team }
+            type Team { name: String members: [User] }
+        "#;
+        let document = Parser::new()
+            .parse_ast(source_text, "schema.graphql")
+            .unwrap();
+        let schema = document.to_schema_validate().unwrap();
+        let mut shaker = SchemaTreeShaker::new(&schema)
+            .with_always_included_fields(&["User.email".to_string(), "User.team".to_string()]);
+        let (operation_document, operation_def, _comments) = operation_defs(
+            "query GetUser($id: ID!) { user(id: $id) { id } }",
+            false,
+            Some("operation.graphql".to_string()),
+        )
+        .unwrap()
+        .unwrap();
+        shaker.retain_operation(&operation_def, &operation_document, DepthLimit::Unlimited);
+
+        // The unselected email and team fields are kept, with the team type but not the types it
+        // references in turn
+        assert_eq!(
+            shaker.shaken().unwrap().to_string(),
+            "type Query {\n  user(id: ID!): User\n}\n\ntype User {\n  id: ID!\n  email: String\n  team: Team\n}\n\ntype Team {\n  name: String\n  members: [User]\n}\n"
+        );
+    }
 }
@@ -1,4 +1,6 @@
+use std::collections::HashMap;
 use std::net::{IpAddr, Ipv4Addr};
+use std::time::Duration;
 
 use apollo_mcp_registry::uplink::schema::SchemaSource;
 use bon::bon;
@@ -7,38 +9,141 @@ use schemars::JsonSchema;
 use serde::Deserialize;
 use url::Url;
 
+use crate::admin::AdminConfig;
 use crate::auth;
+use crate::auxiliary::AuxiliaryConfig;
+use crate::circuit_breaker::CircuitBreakerConfig;
 use crate::custom_scalar_map::CustomScalarMap;
 use crate::errors::ServerError;
 use crate::event::Event as ServerEvent;
-use crate::health::HealthCheckConfig;
-use crate::operations::{MutationMode, OperationSource};
+use crate::feature_flags::{self, FeatureFlagsConfig};
+use crate::health::{HealthCheckConfig, ReadinessCheck};
+use crate::meter::MeterConfig;
+use crate::offline::OfflineCache;
+use crate::operations::{
+    ClaimVariable, DefinitionsKeyword, DuplicateOperations, ErrorCodes, FragmentExpansion,
+    GraphQLErrors, MaxTools, MutationMode, NullVariables, NullableStyle, OperationSource,
+    OutputValidation, ReturnTypeDescription, SharedFragments, StripNulls,
+};
+use crate::progress::ProgressConfig;
+use crate::rest::RestToolConfig;
+use crate::result_cache::ResultCacheConfig;
+use crate::schema_reload::SchemaReloadConfig;
+use crate::signing::SigningConfig;
+use crate::token_estimator::TokenEstimator;
 
+pub mod middleware;
 mod states;
 
+pub use crate::introspection::tools::search::SearchFormat;
+
+use middleware::Middleware;
 use states::StateMachine;
 
 /// An Apollo MCP Server
 pub struct Server {
     transport: Transport,
     schema_source: SchemaSource,
+    schema_reload: SchemaReloadConfig,
     operation_source: OperationSource,
     endpoint: Url,
     headers: HeaderMap,
     execute_introspection: bool,
+    execute_default_selections: bool,
     validate_introspection: bool,
+    list_operations_introspection: bool,
     introspect_introspection: bool,
     introspect_minify: bool,
     search_minify: bool,
+    search_format: SearchFormat,
+    search_collapse_redundant_paths: bool,
+    search_broaden_empty_results: bool,
+    search_index_subscriptions: bool,
+    search_index_directives: bool,
+    search_index_argument_names: bool,
+    search_max_visited_paths_per_type: usize,
     search_introspection: bool,
     explorer_graph_ref: Option<String>,
     custom_scalar_map: Option<CustomScalarMap>,
     mutation_mode: MutationMode,
+    max_mutation_mode: Option<MutationMode>,
     disable_type_description: bool,
     disable_schema_description: bool,
+    return_type_description: ReturnTypeDescription,
+    include_root_schema_description: bool,
+    variables_as_object: bool,
+    token_estimator: TokenEstimator,
+    tool_size_warn_threshold: Option<usize>,
+    max_input_definitions: Option<usize>,
+    describe_variables: bool,
+    describe_graphql_types: bool,
+    property_titles: bool,
+    variable_summary: bool,
+    always_include_fields: Vec<String>,
+    confirm_destructive_operations: bool,
+    split_list_strings: bool,
+    sanitize_errors: bool,
+    output_validation: OutputValidation,
+    fallback_description: Option<String>,
+    definitions_keyword: DefinitionsKeyword,
+    operation_loading_threads: Option<usize>,
+    schema_description_note: Option<String>,
+    disable_read_only_heuristic: bool,
+    nullable_style: NullableStyle,
+    claim_variables: Vec<ClaimVariable>,
+    shared_fragments: SharedFragments,
+    strict_operations: bool,
+    single_operation_documents: bool,
+    result_projection: bool,
+    cost_estimates: bool,
+    include_operation_source: bool,
+    include_examples: bool,
+    tag_categories: bool,
+    header_sets: HashMap<String, HeaderMap>,
+    null_variables: NullVariables,
+    coerce_scalars: bool,
+    strip_operation_prefix: Option<String>,
+    builtin_scalar_descriptions: HashMap<String, String>,
+    constraint_directive: Option<String>,
+    max_tools: Option<MaxTools>,
+    duplicate_operations: DuplicateOperations,
+    dedupe_identical_operations: bool,
+    shake_cache: bool,
+    operation_constants: HashMap<String, String>,
+    max_operation_depth: Option<usize>,
+    max_operation_variables: Option<usize>,
+    check_missing_fields: bool,
+    fragment_expansion: FragmentExpansion,
+    annotate_subgraphs: bool,
+    tool_concurrency: HashMap<String, usize>,
+    operation_timeout: Option<Duration>,
+    partial_results_on_timeout: bool,
+    graphql_errors: GraphQLErrors,
+    strip_nulls: Option<StripNulls>,
+    report_coercions: bool,
+    websocket_endpoint: Option<Url>,
+    error_codes: ErrorCodes,
+    signing: Option<SigningConfig>,
+    builtin_tool_prefix: Option<String>,
+    tool_namespace: Option<String>,
+    tools_page_size: Option<usize>,
     search_leaf_depth: usize,
     index_memory_bytes: usize,
+    search_max_description_chars: Option<usize>,
     health_check: HealthCheckConfig,
+    meter: MeterConfig,
+    circuit_breaker: CircuitBreakerConfig,
+    progress: ProgressConfig,
+    result_cache: ResultCacheConfig,
+    rest_tools: Vec<RestToolConfig>,
+    admin: AdminConfig,
+    auxiliary: AuxiliaryConfig,
+    offline_cache: Option<OfflineCache>,
+    middlewares: Vec<Middleware>,
+    readiness_checks: Vec<ReadinessCheck>,
+    auth_loader: Option<auth::ConfigLoader>,
+    feature_flags: FeatureFlagsConfig,
+    feature_flags_loader: Option<feature_flags::ConfigLoader>,
 }
 
 #[derive(Debug, Clone, Deserialize, Default, JsonSchema)]
@@ -64,6 +169,16 @@ pub enum Transport {
         /// The port to bind to
         #[serde(default = "Transport::default_port")]
         port: u16,
+
+        /// A path prefix for the `/sse` and `/message` endpoints, such as `/mcp/v1` to host the
+        /// server behind a gateway
+        #[serde(default)]
+        base_path: String,
+
+        /// The largest request body accepted, in bytes. Larger requests are rejected with a
+        /// `413 Payload Too Large` status before they are read
+        #[serde(default = "Transport::default_max_request_body_bytes")]
+        max_request_body_bytes: usize,
     },
 
     /// Host the MCP server on the configuration, using streamable HTTP messages.
@@ -79,6 +194,16 @@ pub enum Transport {
         /// The port to bind to
         #[serde(default = "Transport::default_port")]
         port: u16,
+
+        /// The path to serve the MCP endpoint at, such as `/mcp/v1` to host the server behind a
+        /// gateway
+        #[serde(default = "Transport::default_path")]
+        path: String,
+
+        /// The largest request body accepted, in bytes. Larger requests are rejected with a
+        /// `413 Payload Too Large` status before they are read
+        #[serde(default = "Transport::default_max_request_body_bytes")]
+        max_request_body_bytes: usize,
     },
 }
 
@@ -90,6 +215,31 @@ impl Transport {
     fn default_port() -> u16 {
         5000
     }
+
+    fn default_path() -> String {
+        "/mcp".to_string()
+    }
+
+    fn default_max_request_body_bytes() -> usize {
+        4 * 1024 * 1024
+    }
+
+    /// The auth configuration of the transport, if it has one
+    pub fn auth(&self) -> Option<&auth::Config> {
+        match self {
+            Transport::Stdio => None,
+            Transport::SSE { auth, .. } | Transport::StreamableHttp { auth, .. } => auth.as_ref(),
+        }
+    }
+
+    /// The name of the transport, as used in the config file
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Transport::Stdio => "stdio",
+            Transport::SSE { .. } => "sse",
+            Transport::StreamableHttp { .. } => "streamable_http",
+        }
+    }
 }
 
 #[bon]
@@ -98,23 +248,114 @@ impl Server {
     pub fn new(
         transport: Transport,
         schema_source: SchemaSource,
+        #[builder(default)] schema_reload: SchemaReloadConfig,
         operation_source: OperationSource,
         endpoint: Url,
         headers: HeaderMap,
         execute_introspection: bool,
+        #[builder(default)] execute_default_selections: bool,
         validate_introspection: bool,
+        #[builder(default)] list_operations_introspection: bool,
         introspect_introspection: bool,
         search_introspection: bool,
         introspect_minify: bool,
         search_minify: bool,
+        search_format: SearchFormat,
+        #[builder(default)] search_collapse_redundant_paths: bool,
+        #[builder(default)] search_broaden_empty_results: bool,
+        #[builder(default)] search_index_subscriptions: bool,
+        #[builder(default)] search_index_directives: bool,
+        #[builder(default)] search_index_argument_names: bool,
+        #[builder(default = 10_000)] search_max_visited_paths_per_type: usize,
         explorer_graph_ref: Option<String>,
         #[builder(required)] custom_scalar_map: Option<CustomScalarMap>,
         mutation_mode: MutationMode,
+        max_mutation_mode: Option<MutationMode>,
         disable_type_description: bool,
         disable_schema_description: bool,
+        #[builder(default)] return_type_description: ReturnTypeDescription,
+        include_root_schema_description: bool,
+        variables_as_object: bool,
+        token_estimator: TokenEstimator,
+        tool_size_warn_threshold: Option<usize>,
+        max_input_definitions: Option<usize>,
+        describe_variables: bool,
+        #[builder(default)] describe_graphql_types: bool,
+        #[builder(default)] property_titles: bool,
+        #[builder(default)] variable_summary: bool,
+        #[builder(default)] always_include_fields: Vec<String>,
+        #[builder(default)] confirm_destructive_operations: bool,
+        #[builder(default)] split_list_strings: bool,
+        #[builder(default)] sanitize_errors: bool,
+        #[builder(default)] output_validation: OutputValidation,
+        fallback_description: Option<String>,
+        #[builder(default)] definitions_keyword: DefinitionsKeyword,
+        operation_loading_threads: Option<usize>,
+        schema_description_note: Option<String>,
+        #[builder(default)] disable_read_only_heuristic: bool,
+        #[builder(default)] nullable_style: NullableStyle,
+        claim_variables: Vec<ClaimVariable>,
+        shared_fragments: SharedFragments,
+        strict_operations: bool,
+        single_operation_documents: bool,
+        result_projection: bool,
+        cost_estimates: bool,
+        include_operation_source: bool,
+        #[builder(default)] include_examples: bool,
+        #[builder(default)] tag_categories: bool,
+        header_sets: HashMap<String, HeaderMap>,
+        null_variables: NullVariables,
+        coerce_scalars: bool,
+        strip_operation_prefix: Option<String>,
+        #[builder(default)] builtin_scalar_descriptions: HashMap<String, String>,
+        constraint_directive: Option<String>,
+        max_tools: Option<MaxTools>,
+        #[builder(default)] duplicate_operations: DuplicateOperations,
+        #[builder(default)] dedupe_identical_operations: bool,
+        #[builder(default)] shake_cache: bool,
+        #[builder(default)] operation_constants: HashMap<String, String>,
+        max_operation_depth: Option<usize>,
+        max_operation_variables: Option<usize>,
+        #[builder(default)] check_missing_fields: bool,
+        #[builder(default)] fragment_expansion: FragmentExpansion,
+        #[builder(default)] annotate_subgraphs: bool,
+        #[builder(default)] tool_concurrency: HashMap<String, usize>,
+        operation_timeout: Option<Duration>,
+        partial_results_on_timeout: bool,
+        graphql_errors: GraphQLErrors,
+        strip_nulls: Option<StripNulls>,
+        #[builder(default)] report_coercions: bool,
+        websocket_endpoint: Option<Url>,
+        #[builder(default)] error_codes: ErrorCodes,
+        signing: Option<SigningConfig>,
+        builtin_tool_prefix: Option<String>,
+        /// Prefix the names of all tools offered to clients, removed again when they are called
+        tool_namespace: Option<String>,
+        /// Split the tools listed to clients into pages of at most this many tools
+        tools_page_size: Option<usize>,
         search_leaf_depth: usize,
         index_memory_bytes: usize,
+        search_max_description_chars: Option<usize>,
         health_check: HealthCheckConfig,
+        meter: MeterConfig,
+        #[builder(default)] circuit_breaker: CircuitBreakerConfig,
+        #[builder(default)] progress: ProgressConfig,
+        #[builder(default)] result_cache: ResultCacheConfig,
+        #[builder(default)] rest_tools: Vec<RestToolConfig>,
+        admin: AdminConfig,
+        #[builder(default)] auxiliary: AuxiliaryConfig,
+        offline_cache: Option<OfflineCache>,
+        /// Middleware wrapping tool dispatch, outermost first
+        #[builder(default)]
+        middlewares: Vec<Middleware>,
+        /// Checks which must pass for the health check to report the server ready
+        #[builder(default)]
+        readiness_checks: Vec<ReadinessCheck>,
+        /// Reads the latest auth configuration when the process receives `SIGHUP`
+        auth_loader: Option<auth::ConfigLoader>,
+        #[builder(default)] feature_flags: FeatureFlagsConfig,
+        /// Reads the latest feature flags when the process receives `SIGHUP`
+        feature_flags_loader: Option<feature_flags::ConfigLoader>,
     ) -> Self {
         let headers = {
             let mut headers = headers.clone();
@@ -124,23 +365,106 @@ impl Server {
         Self {
             transport,
             schema_source,
+            schema_reload,
             operation_source,
             endpoint,
             headers,
             execute_introspection,
+            execute_default_selections,
             validate_introspection,
+            list_operations_introspection,
             introspect_introspection,
             search_introspection,
             introspect_minify,
             search_minify,
+            search_format,
+            search_collapse_redundant_paths,
+            search_broaden_empty_results,
+            search_index_subscriptions,
+            search_index_directives,
+            search_index_argument_names,
+            search_max_visited_paths_per_type,
             explorer_graph_ref,
             custom_scalar_map,
             mutation_mode,
+            max_mutation_mode,
             disable_type_description,
             disable_schema_description,
+            return_type_description,
+            include_root_schema_description,
+            variables_as_object,
+            token_estimator,
+            tool_size_warn_threshold,
+            max_input_definitions,
+            describe_variables,
+            describe_graphql_types,
+            property_titles,
+            variable_summary,
+            always_include_fields,
+            confirm_destructive_operations,
+            split_list_strings,
+            sanitize_errors,
+            output_validation,
+            fallback_description,
+            definitions_keyword,
+            operation_loading_threads,
+            schema_description_note,
+            disable_read_only_heuristic,
+            nullable_style,
+            claim_variables,
+            shared_fragments,
+            strict_operations,
+            single_operation_documents,
+            result_projection,
+            cost_estimates,
+            include_operation_source,
+            include_examples,
+            tag_categories,
+            header_sets,
+            null_variables,
+            coerce_scalars,
+            strip_operation_prefix,
+            builtin_scalar_descriptions,
+            constraint_directive,
+            max_tools,
+            duplicate_operations,
+            dedupe_identical_operations,
+            shake_cache,
+            operation_constants,
+            max_operation_depth,
+            max_operation_variables,
+            check_missing_fields,
+            fragment_expansion,
+            annotate_subgraphs,
+            tool_concurrency,
+            operation_timeout,
+            partial_results_on_timeout,
+            graphql_errors,
+            strip_nulls,
+            report_coercions,
+            websocket_endpoint,
+            error_codes,
+            signing,
+            builtin_tool_prefix,
+            tool_namespace,
+            tools_page_size,
             search_leaf_depth,
             index_memory_bytes,
+            search_max_description_chars,
             health_check,
+            meter,
+            circuit_breaker,
+            progress,
+            result_cache,
+            rest_tools,
+            admin,
+            auxiliary,
+            offline_cache,
+            middlewares,
+            readiness_checks,
+            auth_loader,
+            feature_flags,
+            feature_flags_loader,
         }
     }
 
@@ -148,3 +472,161 @@ impl Server {
         StateMachine {}.start(self).await
     }
 }
+
+#[cfg(test)]
+pub(crate) mod test {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    /// A server with the default configuration, loading its schema and operations from the sources
+    pub(crate) fn server(
+        transport: Transport,
+        schema_source: SchemaSource,
+        operation_source: OperationSource,
+    ) -> Server {
+        Server::builder()
+            .transport(transport)
+            .schema_source(schema_source)
+            .operation_source(operation_source)
+            .endpoint("http://localhost:4000".parse().unwrap())
+            .headers(HeaderMap::new())
+            .execute_introspection(false)
+            .validate_introspection(false)
+            .introspect_introspection(false)
+            .search_introspection(false)
+            .introspect_minify(false)
+            .search_minify(false)
+            .search_format(SearchFormat::default())
+            .custom_scalar_map(None)
+            .mutation_mode(MutationMode::default())
+            .disable_type_description(false)
+            .disable_schema_description(false)
+            .include_root_schema_description(false)
+            .variables_as_object(false)
+            .token_estimator(TokenEstimator::default())
+            .describe_variables(false)
+            .claim_variables(Vec::new())
+            .shared_fragments(SharedFragments::default())
+            .strict_operations(false)
+            .single_operation_documents(false)
+            .result_projection(false)
+            .cost_estimates(false)
+            .include_operation_source(false)
+            .header_sets(HashMap::new())
+            .null_variables(NullVariables::default())
+            .coerce_scalars(false)
+            .partial_results_on_timeout(false)
+            .graphql_errors(GraphQLErrors::default())
+            .search_leaf_depth(1)
+            .index_memory_bytes(50_000_000)
+            .health_check(HealthCheckConfig::default())
+            .meter(MeterConfig::default())
+            .admin(AdminConfig::default())
+            .build()
+    }
+
+    /// A local port which is free to listen on
+    pub(crate) fn free_port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port()
+    }
+
+    /// A client session with a server started with the streamable HTTP transport
+    pub(crate) struct Client {
+        client: reqwest::Client,
+        url: String,
+        session_id: Option<String>,
+    }
+
+    impl Client {
+        /// Initialize a session with the server at the URL, waiting for it to start
+        pub(crate) async fn connect(url: &str) -> Self {
+            let mut client = Self {
+                client: reqwest::Client::new(),
+                url: url.to_string(),
+                session_id: None,
+            };
+
+            let mut initialized = None;
+            for _ in 0..100 {
+                let initialize = client.send(json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "initialize",
+                    "params": {
+                        "protocolVersion": "2025-03-26",
+                        "capabilities": {},
+                        "clientInfo": { "name": "test", "version": "1.0" }
+                    }
+                }));
+                if let Ok(response) = initialize.await {
+                    initialized = Some(response);
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            let response = initialized.expect("the server should start");
+            client.session_id = response
+                .headers()
+                .get("mcp-session-id")
+                .and_then(|session_id| session_id.to_str().ok())
+                .map(ToString::to_string);
+            client
+                .send(json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+                .await
+                .unwrap();
+            client
+        }
+
+        /// The names of the tools the server lists
+        pub(crate) async fn list_tools(&self) -> Vec<String> {
+            self.request("tools/list", json!({})).await["tools"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(|tool| tool["name"].as_str().map(ToString::to_string))
+                .collect()
+        }
+
+        /// The result of calling a tool
+        pub(crate) async fn call_tool(&self, name: &str, arguments: Value) -> Value {
+            self.request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )
+            .await
+        }
+
+        async fn request(&self, method: &str, params: Value) -> Value {
+            let response = self
+                .send(json!({ "jsonrpc": "2.0", "id": 2, "method": method, "params": params }))
+                .await
+                .unwrap()
+                .text()
+                .await
+                .unwrap();
+            let response: Value = response
+                .lines()
+                .find_map(|line| line.strip_prefix("data:"))
+                .and_then(|data| serde_json::from_str(data.trim()).ok())
+                .unwrap_or_else(|| panic!("{method} should return a response"));
+            response["result"].clone()
+        }
+
+        async fn send(&self, message: Value) -> reqwest::Result<reqwest::Response> {
+            let mut request = self
+                .client
+                .post(&self.url)
+                .header("Accept", "application/json, text/event-stream")
+                .json(&message);
+            if let Some(session_id) = &self.session_id {
+                request = request.header("mcp-session-id", session_id);
+            }
+            request.send().await
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! Middleware wrapping tool dispatch, for adding cross-cutting behavior when embedding the server
+//!
+//! Middleware runs for every tool call, regardless of the transport. Each middleware receives the
+//! tool call request and the rest of the chain as [`Next`], and can inspect or modify the request,
+//! short-circuit by returning a result without calling [`Next::run`], or post-process the result.
+//!
+//! Middleware runs in the order it is registered with the server builder: the first middleware
+//! registered is the outermost, so it sees the request first and the result last. The tool itself
+//! is dispatched after the last middleware calls [`Next::run`].
+
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+use rmcp::model::{CallToolRequestParam, CallToolResult};
+
+use crate::errors::McpError;
+
+/// The result of a tool call
+pub type ToolCallResult = Result<CallToolResult, McpError>;
+
+/// A layer wrapping tool dispatch
+pub type Middleware = Arc<
+    dyn for<'a> Fn(CallToolRequestParam, Next<'a>) -> BoxFuture<'a, ToolCallResult> + Send + Sync,
+>;
+
+/// Create a middleware from a function of the request and the rest of the chain
+pub fn middleware<F>(f: F) -> Middleware
+where
+    F: for<'a> Fn(CallToolRequestParam, Next<'a>) -> BoxFuture<'a, ToolCallResult>
+        + Send
+        + Sync
+        + 'static,
+{
+    Arc::new(f)
+}
+
+/// The rest of a middleware chain, ending with the tool dispatch
+pub struct Next<'a> {
+    middlewares: &'a [Middleware],
+    dispatch: Box<dyn FnOnce(CallToolRequestParam) -> BoxFuture<'a, ToolCallResult> + Send + 'a>,
+}
+
+impl<'a> Next<'a> {
+    pub(crate) fn new(
+        middlewares: &'a [Middleware],
+        dispatch: impl FnOnce(CallToolRequestParam) -> BoxFuture<'a, ToolCallResult> + Send + 'a,
+    ) -> Self {
+        Self {
+            middlewares,
+            dispatch: Box::new(dispatch),
+        }
+    }
+
+    /// Run the rest of the chain
+    pub fn run(self, request: CallToolRequestParam) -> BoxFuture<'a, ToolCallResult> {
+        match self.middlewares.split_first() {
+            Some((middleware, middlewares)) => middleware(
+                request,
+                Next {
+                    middlewares,
+                    dispatch: self.dispatch,
+                },
+            ),
+            None => (self.dispatch)(request),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use futures::FutureExt as _;
+    use rmcp::model::Content;
+
+    use super::*;
+
+    fn request(name: &'static str) -> CallToolRequestParam {
+        CallToolRequestParam {
+            name: name.into(),
+            arguments: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_runs_in_order_before_dispatch() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorder = |label: &'static str| {
+            let calls = calls.clone();
+            middleware(move |request, next| {
+                calls.lock().unwrap().push(label);
+                next.run(request)
+            })
+        };
+        let middlewares = vec![
+            recorder("first"),
+            middleware(|request, next| {
+                if request.name == "blocked" {
+                    async { Ok(CallToolResult::success(vec![Content::text("blocked")])) }.boxed()
+                } else {
+                    next.run(request)
+                }
+            }),
+            recorder("last"),
+        ];
+        let call = |name| {
+            let calls = calls.clone();
+            Next::new(&middlewares, move |_| {
+                calls.lock().unwrap().push("dispatch");
+                async { Ok(CallToolResult::success(vec![Content::text("dispatched")])) }.boxed()
+            })
+            .run(request(name))
+        };
+
+        let result = call("blocked").await.unwrap();
+        assert_eq!(
+            result
+                .content
+                .first()
+                .and_then(|c| c.as_text())
+                .map(|t| t.text.as_str()),
+            Some("blocked")
+        );
+        assert_eq!(*calls.lock().unwrap(), vec!["first"]);
+
+        calls.lock().unwrap().clear();
+        call("allowed").await.unwrap();
+        assert_eq!(*calls.lock().unwrap(), vec!["first", "last", "dispatch"]);
+    }
+}
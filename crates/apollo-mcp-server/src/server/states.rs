@@ -1,24 +1,49 @@
+use std::collections::HashMap;
+
 use apollo_compiler::{Schema, validation::Valid};
 use apollo_federation::{ApiSchemaOptions, Supergraph};
-use apollo_mcp_registry::uplink::schema::{SchemaState, event::Event as SchemaEvent};
+use apollo_mcp_registry::uplink::schema::{SchemaSource, SchemaState, event::Event as SchemaEvent};
 use futures::{FutureExt as _, Stream, StreamExt as _, stream};
 use reqwest::header::HeaderMap;
+use tracing::warn;
 use url::Url;
 
 use crate::{
+    admin::AdminConfig,
+    auth,
+    auxiliary::AuxiliaryConfig,
+    circuit_breaker::CircuitBreakerConfig,
     custom_scalar_map::CustomScalarMap,
     errors::{OperationError, ServerError},
-    health::HealthCheckConfig,
-    operations::MutationMode,
+    feature_flags::{self, FeatureFlagsConfig},
+    graphql::OperationTimeout,
+    health::{HealthCheckConfig, ReadinessCheck},
+    meter::MeterConfig,
+    operations::{
+        ClaimVariable, DefinitionsKeyword, DuplicateOperations, ErrorCodes, FragmentExpansion,
+        GraphQLErrors, MaxTools, MutationMode, NullVariables, NullableStyle, OutputValidation,
+        ReturnTypeDescription, SharedFragments, StripNulls,
+    },
+    progress::ProgressConfig,
+    rest::RestToolConfig,
+    result_cache::ResultCacheConfig,
+    schema_reload, subgraphs,
+    token_estimator::TokenEstimator,
 };
 
-use super::{Server, ServerEvent, Transport};
+use crate::signing::SigningConfig;
+
+use super::{SearchFormat, Server, ServerEvent, Transport, middleware::Middleware};
 
 mod configuring;
 mod operations_configured;
 mod running;
 mod schema_configured;
 mod starting;
+mod tool_changes;
+
+/// The URL of the join spec, which supergraphs link to
+const JOIN_SPEC_URL: &str = "https://specs.apollo.dev/join/";
 
 use configuring::Configuring;
 use operations_configured::OperationsConfigured;
@@ -34,31 +59,122 @@ struct Config {
     endpoint: Url,
     headers: HeaderMap,
     execute_introspection: bool,
+    execute_default_selections: bool,
     validate_introspection: bool,
+    list_operations_introspection: bool,
     introspect_introspection: bool,
     search_introspection: bool,
     introspect_minify: bool,
     search_minify: bool,
+    search_format: SearchFormat,
+    search_collapse_redundant_paths: bool,
+    search_broaden_empty_results: bool,
+    search_index_subscriptions: bool,
+    search_index_directives: bool,
+    search_index_argument_names: bool,
+    search_max_visited_paths_per_type: usize,
     explorer_graph_ref: Option<String>,
     custom_scalar_map: Option<CustomScalarMap>,
     mutation_mode: MutationMode,
+    max_mutation_mode: Option<MutationMode>,
     disable_type_description: bool,
     disable_schema_description: bool,
+    return_type_description: ReturnTypeDescription,
+    include_root_schema_description: bool,
+    variables_as_object: bool,
+    token_estimator: TokenEstimator,
+    tool_size_warn_threshold: Option<usize>,
+    max_input_definitions: Option<usize>,
+    describe_variables: bool,
+    describe_graphql_types: bool,
+    property_titles: bool,
+    variable_summary: bool,
+    always_include_fields: Vec<String>,
+    confirm_destructive_operations: bool,
+    split_list_strings: bool,
+    sanitize_errors: bool,
+    output_validation: OutputValidation,
+    fallback_description: Option<String>,
+    definitions_keyword: DefinitionsKeyword,
+    operation_loading_threads: Option<usize>,
+    schema_description_note: Option<String>,
+    disable_read_only_heuristic: bool,
+    nullable_style: NullableStyle,
+    claim_variables: Vec<ClaimVariable>,
+    shared_fragments: SharedFragments,
+    strict_operations: bool,
+    single_operation_documents: bool,
+    result_projection: bool,
+    cost_estimates: bool,
+    include_operation_source: bool,
+    include_examples: bool,
+    tag_categories: bool,
+    header_sets: HashMap<String, HeaderMap>,
+    null_variables: NullVariables,
+    coerce_scalars: bool,
+    strip_operation_prefix: Option<String>,
+    builtin_scalar_descriptions: HashMap<String, String>,
+    constraint_directive: Option<String>,
+    max_tools: Option<MaxTools>,
+    duplicate_operations: DuplicateOperations,
+    dedupe_identical_operations: bool,
+    shake_cache: bool,
+    operation_constants: HashMap<String, String>,
+    max_operation_depth: Option<usize>,
+    max_operation_variables: Option<usize>,
+    check_missing_fields: bool,
+    fragment_expansion: FragmentExpansion,
+    tool_concurrency: HashMap<String, usize>,
+    operation_timeout: Option<OperationTimeout>,
+    graphql_errors: GraphQLErrors,
+    strip_nulls: Option<StripNulls>,
+    report_coercions: bool,
+    websocket_endpoint: Option<Url>,
+    error_codes: ErrorCodes,
+    signing: Option<SigningConfig>,
+    builtin_tool_prefix: Option<String>,
+    tool_namespace: Option<String>,
+    tools_page_size: Option<usize>,
     search_leaf_depth: usize,
     index_memory_bytes: usize,
+    search_max_description_chars: Option<usize>,
     health_check: HealthCheckConfig,
+    meter: MeterConfig,
+    circuit_breaker: CircuitBreakerConfig,
+    progress: ProgressConfig,
+    result_cache: ResultCacheConfig,
+    rest_tools: Vec<RestToolConfig>,
+    admin: AdminConfig,
+    auxiliary: AuxiliaryConfig,
+    middlewares: Vec<Middleware>,
+    readiness_checks: Vec<ReadinessCheck>,
+    auth_loader: Option<auth::ConfigLoader>,
+    feature_flags: FeatureFlagsConfig,
+    feature_flags_loader: Option<feature_flags::ConfigLoader>,
 }
 
 impl StateMachine {
     pub(crate) async fn start(self, server: Server) -> Result<(), ServerError> {
-        let schema_stream = server
-            .schema_source
-            .into_stream()
-            .map(ServerEvent::SchemaUpdated)
-            .boxed();
+        let schema_path = match &server.schema_source {
+            SchemaSource::File { path, watch: true } => Some(path.clone()),
+            _ => None,
+        };
+        let schema_stream = schema_reload::debounce_and_retry(
+            server.schema_source.into_stream(),
+            schema_path,
+            server.schema_reload,
+            |sdl| {
+                let Ok(schema_state) = sdl.parse::<SchemaState>();
+                Self::load_api_schema(schema_state, false).is_ok()
+            },
+        )
+        .map(ServerEvent::SchemaUpdated)
+        .boxed();
         let operation_stream = server.operation_source.into_stream().await.boxed();
         let ctrl_c_stream = Self::ctrl_c_stream().boxed();
         let mut stream = stream::select_all(vec![schema_stream, operation_stream, ctrl_c_stream]);
+        let offline_cache = server.offline_cache;
+        let annotate_subgraphs = server.annotate_subgraphs;
 
         let mut state = State::Configuring(Configuring {
             config: Config {
@@ -66,19 +182,101 @@ impl StateMachine {
                 endpoint: server.endpoint,
                 headers: server.headers,
                 execute_introspection: server.execute_introspection,
+                execute_default_selections: server.execute_default_selections,
                 validate_introspection: server.validate_introspection,
+                list_operations_introspection: server.list_operations_introspection,
                 introspect_introspection: server.introspect_introspection,
                 search_introspection: server.search_introspection,
                 introspect_minify: server.introspect_minify,
                 search_minify: server.search_minify,
+                search_format: server.search_format,
+                search_collapse_redundant_paths: server.search_collapse_redundant_paths,
+                search_broaden_empty_results: server.search_broaden_empty_results,
+                search_index_subscriptions: server.search_index_subscriptions,
+                search_index_directives: server.search_index_directives,
+                search_index_argument_names: server.search_index_argument_names,
+                search_max_visited_paths_per_type: server.search_max_visited_paths_per_type,
                 explorer_graph_ref: server.explorer_graph_ref,
                 custom_scalar_map: server.custom_scalar_map,
                 mutation_mode: server.mutation_mode,
+                max_mutation_mode: server.max_mutation_mode,
                 disable_type_description: server.disable_type_description,
                 disable_schema_description: server.disable_schema_description,
+                return_type_description: server.return_type_description,
+                include_root_schema_description: server.include_root_schema_description,
+                variables_as_object: server.variables_as_object,
+                token_estimator: server.token_estimator,
+                tool_size_warn_threshold: server.tool_size_warn_threshold,
+                max_input_definitions: server.max_input_definitions,
+                describe_variables: server.describe_variables,
+                describe_graphql_types: server.describe_graphql_types,
+                property_titles: server.property_titles,
+                variable_summary: server.variable_summary,
+                always_include_fields: server.always_include_fields,
+                confirm_destructive_operations: server.confirm_destructive_operations,
+                split_list_strings: server.split_list_strings,
+                sanitize_errors: server.sanitize_errors,
+                output_validation: server.output_validation,
+                fallback_description: server.fallback_description,
+                definitions_keyword: server.definitions_keyword,
+                operation_loading_threads: server.operation_loading_threads,
+                schema_description_note: server.schema_description_note,
+                disable_read_only_heuristic: server.disable_read_only_heuristic,
+                nullable_style: server.nullable_style,
+                claim_variables: server.claim_variables,
+                shared_fragments: server.shared_fragments,
+                strict_operations: server.strict_operations,
+                single_operation_documents: server.single_operation_documents,
+                result_projection: server.result_projection,
+                cost_estimates: server.cost_estimates,
+                include_operation_source: server.include_operation_source,
+                include_examples: server.include_examples,
+                tag_categories: server.tag_categories,
+                header_sets: server.header_sets,
+                null_variables: server.null_variables,
+                coerce_scalars: server.coerce_scalars,
+                strip_operation_prefix: server.strip_operation_prefix,
+                builtin_scalar_descriptions: server.builtin_scalar_descriptions,
+                constraint_directive: server.constraint_directive,
+                max_tools: server.max_tools,
+                duplicate_operations: server.duplicate_operations,
+                dedupe_identical_operations: server.dedupe_identical_operations,
+                shake_cache: server.shake_cache,
+                operation_constants: server.operation_constants,
+                max_operation_depth: server.max_operation_depth,
+                max_operation_variables: server.max_operation_variables,
+                check_missing_fields: server.check_missing_fields,
+                fragment_expansion: server.fragment_expansion,
+                tool_concurrency: server.tool_concurrency,
+                operation_timeout: server.operation_timeout.map(|duration| OperationTimeout {
+                    duration,
+                    partial_results: server.partial_results_on_timeout,
+                }),
+                graphql_errors: server.graphql_errors,
+                strip_nulls: server.strip_nulls,
+                report_coercions: server.report_coercions,
+                websocket_endpoint: server.websocket_endpoint,
+                error_codes: server.error_codes,
+                signing: server.signing,
+                builtin_tool_prefix: server.builtin_tool_prefix,
+                tool_namespace: server.tool_namespace,
+                tools_page_size: server.tools_page_size,
                 search_leaf_depth: server.search_leaf_depth,
                 index_memory_bytes: server.index_memory_bytes,
+                search_max_description_chars: server.search_max_description_chars,
                 health_check: server.health_check,
+                meter: server.meter,
+                circuit_breaker: server.circuit_breaker,
+                progress: server.progress,
+                result_cache: server.result_cache,
+                rest_tools: server.rest_tools,
+                admin: server.admin,
+                auxiliary: server.auxiliary,
+                middlewares: server.middlewares,
+                readiness_checks: server.readiness_checks,
+                auth_loader: server.auth_loader,
+                feature_flags: server.feature_flags,
+                feature_flags_loader: server.feature_flags_loader,
             },
         });
 
@@ -86,7 +284,12 @@ impl StateMachine {
             state = match event {
                 ServerEvent::SchemaUpdated(registry_event) => match registry_event {
                     SchemaEvent::UpdateSchema(schema_state) => {
-                        let schema = Self::sdl_to_api_schema(schema_state)?;
+                        if let Some(cache) = &offline_cache
+                            && let Err(e) = cache.store_schema(&schema_state.sdl)
+                        {
+                            warn!("Failed to cache schema in {}: {e}", cache.dir().display());
+                        }
+                        let schema = Self::sdl_to_api_schema(schema_state, annotate_subgraphs)?;
                         match state {
                             State::Configuring(configuring) => {
                                 configuring.set_schema(schema).await.into()
@@ -108,20 +311,32 @@ impl StateMachine {
                         _ => state,
                     },
                 },
-                ServerEvent::OperationsUpdated(operations) => match state {
-                    State::Configuring(configuring) => {
-                        configuring.set_operations(operations).await.into()
+                ServerEvent::OperationsUpdated(operations) => {
+                    if let Some(cache) = &offline_cache
+                        && let Err(e) = cache.store_operations(&operations)
+                    {
+                        warn!(
+                            "Failed to cache operations in {}: {e}",
+                            cache.dir().display()
+                        );
                     }
-                    State::SchemaConfigured(schema_configured) => {
-                        schema_configured.set_operations(operations).await.into()
+                    match state {
+                        State::Configuring(configuring) => {
+                            configuring.set_operations(operations).await.into()
+                        }
+                        State::SchemaConfigured(schema_configured) => {
+                            schema_configured.set_operations(operations).await.into()
+                        }
+                        State::OperationsConfigured(operations_configured) => operations_configured
+                            .set_operations(operations)
+                            .await
+                            .into(),
+                        State::Running(running) => {
+                            running.update_operations(operations).await.into()
+                        }
+                        other => other,
                     }
-                    State::OperationsConfigured(operations_configured) => operations_configured
-                        .set_operations(operations)
-                        .await
-                        .into(),
-                    State::Running(running) => running.update_operations(operations).await.into(),
-                    other => other,
-                },
+                }
                 ServerEvent::OperationError(e, _) => {
                     State::Error(ServerError::Operation(OperationError::File(e)))
                 }
@@ -149,16 +364,54 @@ impl StateMachine {
         }
     }
 
+    /// Load the API schema from SDL, logging any warnings about it
     #[allow(clippy::result_large_err)]
-    fn sdl_to_api_schema(schema_state: SchemaState) -> Result<Valid<Schema>, ServerError> {
+    fn sdl_to_api_schema(
+        schema_state: SchemaState,
+        annotate_subgraphs: bool,
+    ) -> Result<Valid<Schema>, ServerError> {
+        let (schema, warnings) = Self::load_api_schema(schema_state, annotate_subgraphs)?;
+        for warning in warnings {
+            warn!("Schema loaded with a warning: {warning}");
+        }
+        Ok(schema)
+    }
+
+    /// Load the API schema from SDL, along with warnings about it
+    ///
+    /// Only errors fail the load. A schema which looks like a supergraph but is not a valid one
+    /// is a warning, since it is still loaded as a plain GraphQL schema if it is valid.
+    #[allow(clippy::result_large_err)]
+    fn load_api_schema(
+        schema_state: SchemaState,
+        annotate_subgraphs: bool,
+    ) -> Result<(Valid<Schema>, Vec<String>), ServerError> {
         match Supergraph::new(&schema_state.sdl) {
-            Ok(supergraph) => Ok(supergraph
-                .to_api_schema(ApiSchemaOptions::default())
-                .map_err(ServerError::Federation)?
-                .schema()
-                .clone()),
-            Err(_) => Schema::parse_and_validate(schema_state.sdl, "schema.graphql")
-                .map_err(|e| ServerError::GraphQLSchema(e.into())),
+            Ok(supergraph) => {
+                let api_schema = supergraph
+                    .to_api_schema(ApiSchemaOptions::default())
+                    .map_err(ServerError::Federation)?
+                    .schema()
+                    .clone();
+                let api_schema = if annotate_subgraphs {
+                    subgraphs::annotate_subgraphs(supergraph.schema.schema(), api_schema)
+                } else {
+                    api_schema
+                };
+                Ok((api_schema, Vec::new()))
+            }
+            Err(supergraph_error) => {
+                let warnings = if schema_state.sdl.contains(JOIN_SPEC_URL) {
+                    vec![format!(
+                        "the schema is not a valid supergraph, so it is loaded as a plain GraphQL schema: {supergraph_error}"
+                    )]
+                } else {
+                    Vec::new()
+                };
+                Schema::parse_and_validate(schema_state.sdl, "schema.graphql")
+                    .map(|schema| (schema, warnings))
+                    .map_err(|e| ServerError::GraphQLSchema(e.into()))
+            }
         }
     }
 
@@ -277,3 +530,45 @@ impl From<ServerError> for State {
         State::Error(error)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tracing_test::traced_test;
+
+    use super::*;
+
+    #[test]
+    #[traced_test]
+    fn invalid_supergraphs_are_loaded_with_a_warning() {
+        let sdl = r#"
+            schema
+                @link(url: "https://specs.apollo.dev/link/v1.0")
+                @link(url: "https://specs.apollo.dev/join/v9.9", for: EXECUTION) {
+                query: Query
+            }
+            directive @link(url: String, as: String, for: link__Purpose, import: [link__Import]) repeatable on SCHEMA
+            scalar link__Import
+            enum link__Purpose { SECURITY EXECUTION }
+            type Query { id: ID }
+        "#;
+        let Ok(schema_state) = sdl.parse::<SchemaState>();
+
+        let schema = StateMachine::sdl_to_api_schema(schema_state, false).unwrap();
+        assert!(schema.get_object("Query").is_some());
+        assert!(logs_contain(
+            "Schema loaded with a warning: the schema is not a valid supergraph"
+        ));
+    }
+
+    #[test]
+    #[traced_test]
+    fn invalid_schemas_fail_to_load() {
+        let Ok(schema_state) = "type Query { id: Missing }".parse::<SchemaState>();
+
+        assert!(matches!(
+            StateMachine::sdl_to_api_schema(schema_state, false),
+            Err(ServerError::GraphQLSchema(_))
+        ));
+        assert!(!logs_contain("Schema loaded with a warning"));
+    }
+}
@@ -2,43 +2,76 @@ use std::ops::Deref as _;
 use std::sync::Arc;
 
 use apollo_compiler::{Schema, validation::Valid};
+use axum::{
+    Json, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    routing::{get, post},
+};
 use headers::HeaderMapExt as _;
 use reqwest::header::{HeaderMap, HeaderValue};
 use rmcp::model::Implementation;
 use rmcp::{
     Peer, RoleServer, ServerHandler, ServiceError,
     model::{
-        CallToolRequestParam, CallToolResult, ErrorCode, InitializeRequestParam, InitializeResult,
-        ListToolsResult, PaginatedRequestParam, ServerCapabilities, ServerInfo,
+        CallToolRequestParam, CallToolResult, Content, ErrorCode, InitializeRequestParam,
+        InitializeResult, ListToolsResult, PaginatedRequestParam, ServerCapabilities, ServerInfo,
+        Tool,
     },
     service::RequestContext,
 };
-use serde_json::Value;
+use serde_json::{Map, Value, json};
 use tokio::sync::{Mutex, RwLock};
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, error};
+use tracing::{debug, error, info, warn};
 use url::Url;
 
 use crate::{
+    admin::DisabledTools,
     auth::ValidToken,
+    circuit_breaker::CircuitBreaker,
+    concurrency::ToolConcurrency,
     custom_scalar_map::CustomScalarMap,
-    errors::{McpError, ServerError},
+    errors::{McpError, ServerError, sanitize_result},
     explorer::{EXPLORER_TOOL_NAME, Explorer},
-    graphql::{self, Executable as _},
+    feature_flags::{self, FeatureFlags},
+    graphql::{
+        self, ESTIMATE_ARGUMENT_NAME, Executable as _, OperationTimeout, ScopeMask,
+        WebSocketConnection,
+    },
     health::HealthCheck,
     introspection::tools::{
         execute::{EXECUTE_TOOL_NAME, Execute},
         introspect::{INTROSPECT_TOOL_NAME, Introspect},
+        list_operations::{LIST_OPERATIONS_TOOL_NAME, ListOperations},
         search::{SEARCH_TOOL_NAME, Search},
         validate::{VALIDATE_TOOL_NAME, Validate},
     },
-    operations::{MutationMode, Operation, RawOperation},
+    meter::Meter,
+    operations::{
+        CONFIRM_ARGUMENT_NAME, ErrorCodes, GraphQLErrors, Operation, OperationCache, RawOperation,
+        StripNulls, ToolOptions, check_operation_dependencies, convert_operations,
+        dedupe_identical_operations, limit_tools, resolve_duplicate_operations,
+        restore_colliding_tool_names,
+    },
+    progress::ProgressConfig,
+    projection::{PROJECT_ARGUMENT_NAME, Projection},
+    rest::RestTool,
+    result_cache::{ResultCache, ResultCacheKey},
+    telemetry::trace_tool_call,
 };
 
+use super::tool_changes::ToolChanges;
+use crate::server::middleware::{Middleware, Next};
+use crate::signing::SigningConfig;
+use futures::FutureExt as _;
+
 #[derive(Clone)]
 pub(super) struct Running {
     pub(super) schema: Arc<Mutex<Valid<Schema>>>,
     pub(super) operations: Arc<Mutex<Vec<Operation>>>,
+    pub(super) operation_cache: OperationCache,
     pub(super) headers: HeaderMap,
     pub(super) endpoint: Url,
     pub(super) execute_tool: Option<Execute>,
@@ -46,44 +79,62 @@ pub(super) struct Running {
     pub(super) search_tool: Option<Search>,
     pub(super) explorer_tool: Option<Explorer>,
     pub(super) validate_tool: Option<Validate>,
+    pub(super) list_operations_tool: Option<ListOperations>,
     pub(super) custom_scalar_map: Option<CustomScalarMap>,
     pub(super) peers: Arc<RwLock<Vec<Peer<RoleServer>>>>,
     pub(super) cancellation_token: CancellationToken,
-    pub(super) mutation_mode: MutationMode,
-    pub(super) disable_type_description: bool,
-    pub(super) disable_schema_description: bool,
+    pub(super) tool_options: ToolOptions,
     pub(super) health_check: Option<HealthCheck>,
+    pub(super) meter: Option<Meter>,
+    pub(super) circuit_breaker: Option<CircuitBreaker>,
+    pub(super) progress: Option<ProgressConfig>,
+    pub(super) result_cache: Option<ResultCache>,
+    pub(super) rest_tools: Vec<RestTool>,
+    pub(super) tool_concurrency: ToolConcurrency,
+    pub(super) operation_timeout: Option<OperationTimeout>,
+    pub(super) graphql_errors: GraphQLErrors,
+    pub(super) strip_nulls: Option<StripNulls>,
+    pub(super) report_coercions: bool,
+    pub(super) websocket: Option<WebSocketConnection>,
+    pub(super) error_codes: ErrorCodes,
+    pub(super) sanitize_errors: bool,
+    pub(super) signing: Option<SigningConfig>,
+    pub(super) tool_namespace: Option<String>,
+    pub(super) tools_page_size: Option<usize>,
+    pub(super) disabled_tools: DisabledTools,
+    pub(super) feature_flags: FeatureFlags,
+    pub(super) middlewares: Vec<Middleware>,
+    pub(super) transport: &'static str,
 }
 
 impl Running {
     /// Update a running server with a new schema.
     pub(super) async fn update_schema(self, schema: Valid<Schema>) -> Result<Running, ServerError> {
         debug!("Schema updated:\n{}", schema);
+        let old_tools = self.tools().await;
 
         // Update the operations based on the new schema. This is necessary because the MCP tool
         // input schemas and description are derived from the schema.
-        let operations: Vec<Operation> = self
+        let raw_operations = self
             .operations
             .lock()
             .await
             .iter()
             .cloned()
             .map(|operation| operation.into_inner())
-            .filter_map(|operation| {
-                operation
-                    .into_operation(
-                        &schema,
-                        self.custom_scalar_map.as_ref(),
-                        self.mutation_mode,
-                        self.disable_type_description,
-                        self.disable_schema_description,
-                    )
-                    .unwrap_or_else(|error| {
-                        error!("Invalid operation: {}", error);
-                        None
-                    })
-            })
             .collect();
+        let mut operations = convert_operations(
+            raw_operations,
+            &schema,
+            self.custom_scalar_map.as_ref(),
+            &self.tool_options,
+            &self.operation_cache,
+        );
+        restore_colliding_tool_names(&mut operations);
+        if let Some(execute_tool) = &self.execute_tool {
+            execute_tool.set_buildable_mutations(&operations);
+            execute_tool.update_default_selections(&schema);
+        }
 
         debug!(
             "Updated {} operations:\n{}",
@@ -96,7 +147,7 @@ impl Running {
         *self.schema.lock().await = schema;
 
         // Notify MCP clients that tools have changed
-        Self::notify_tool_list_changed(self.peers.clone()).await;
+        self.notify_tool_changes(&old_tools).await;
         Ok(self)
     }
 
@@ -105,27 +156,37 @@ impl Running {
         operations: Vec<RawOperation>,
     ) -> Result<Running, ServerError> {
         debug!("Operations updated:\n{:?}", operations);
+        let old_tools = self.tools().await;
 
         // Update the operations based on the current schema
         {
             let schema = &*self.schema.lock().await;
-            let updated_operations: Vec<Operation> = operations
-                .into_iter()
-                .filter_map(|operation| {
-                    operation
-                        .into_operation(
-                            schema,
-                            self.custom_scalar_map.as_ref(),
-                            self.mutation_mode,
-                            self.disable_type_description,
-                            self.disable_schema_description,
-                        )
-                        .unwrap_or_else(|error| {
-                            error!("Invalid operation: {}", error);
-                            None
-                        })
-                })
-                .collect();
+            let mut updated_operations = convert_operations(
+                operations
+                    .into_iter()
+                    .flat_map(|operation| operation.split(&self.tool_options))
+                    .collect(),
+                schema,
+                self.custom_scalar_map.as_ref(),
+                &self.tool_options,
+                &self.operation_cache,
+            );
+            resolve_duplicate_operations(
+                &mut updated_operations,
+                self.tool_options.duplicate_operations,
+            )?;
+            if self.tool_options.dedupe_identical_operations {
+                dedupe_identical_operations(&mut updated_operations);
+            }
+            restore_colliding_tool_names(&mut updated_operations);
+            if let Some(max_tools) = &self.tool_options.max_tools {
+                limit_tools(&mut updated_operations, max_tools)?;
+            }
+            check_operation_dependencies(&updated_operations);
+            self.warn_about_shadowed_operations(&updated_operations);
+            if let Some(execute_tool) = &self.execute_tool {
+                execute_tool.set_buildable_mutations(&updated_operations);
+            }
 
             debug!(
                 "Loaded {} operations:\n{}",
@@ -136,10 +197,27 @@ impl Running {
         }
 
         // Notify MCP clients that tools have changed
-        Self::notify_tool_list_changed(self.peers.clone()).await;
+        self.notify_tool_changes(&old_tools).await;
         Ok(self)
     }
 
+    /// Notify any peers of the tools changed since the given tools were listed. Peers are not
+    /// notified if no tool was added, removed, or modified.
+    async fn notify_tool_changes(&self, old_tools: &[Tool]) {
+        let changes = ToolChanges::between(old_tools, &self.tools().await);
+        if changes.is_empty() {
+            debug!("Tools are unchanged, not notifying peers");
+        } else {
+            info!(
+                added = ?changes.added,
+                removed = ?changes.removed,
+                modified = ?changes.modified,
+                "Tools changed"
+            );
+            Self::notify_tool_list_changed(self.peers.clone()).await;
+        }
+    }
+
     /// Notify any peers that tools have changed. Drops unreachable peers from the list.
     async fn notify_tool_list_changed(peers: Arc<RwLock<Vec<Peer<RoleServer>>>>) {
         let mut peers = peers.write().await;
@@ -166,26 +244,268 @@ impl Running {
         }
         *peers = retained_peers;
     }
-}
 
-impl ServerHandler for Running {
-    async fn initialize(
+    /// All tools currently available, excluding those disabled at runtime
+    async fn tools(&self) -> Vec<Tool> {
+        let mut tools = self.all_tools().await;
+        let disabled = self.disabled_tools.names().await;
+        tools.retain(|tool| !disabled.iter().any(|name| *name == tool.name));
+        if let Some(namespace) = &self.tool_namespace {
+            for tool in &mut tools {
+                tool.name = format!("{namespace}{}", tool.name).into();
+            }
+        }
+        tools
+    }
+
+    /// A page of the tools offered to clients, starting at the given cursor. The cursor is the
+    /// position of the first tool in the page, and the next cursor is returned while more tools
+    /// remain. All tools are listed at once if no page size is configured.
+    async fn tools_page(&self, cursor: Option<&str>) -> Result<ListToolsResult, McpError> {
+        let mut tools = self.tools().await;
+        let Some(page_size) = self.tools_page_size else {
+            return Ok(ListToolsResult {
+                next_cursor: None,
+                tools,
+            });
+        };
+
+        let start = match cursor {
+            Some(cursor) => cursor
+                .parse::<usize>()
+                .ok()
+                .filter(|start| *start <= tools.len())
+                .ok_or_else(|| {
+                    McpError::new(
+                        ErrorCode::INVALID_PARAMS,
+                        format!("Invalid cursor {cursor}"),
+                        None,
+                    )
+                })?,
+            None => 0,
+        };
+        let end = start.saturating_add(page_size.max(1)).min(tools.len());
+        let next_cursor = (end < tools.len()).then(|| end.to_string());
+        Ok(ListToolsResult {
+            next_cursor,
+            tools: tools.drain(start..end).collect(),
+        })
+    }
+
+    /// Remove the tool namespace from the name of a called tool. Names outside the namespace
+    /// don't refer to any tool.
+    fn without_namespace(
         &self,
-        _request: InitializeRequestParam,
-        context: RequestContext<RoleServer>,
-    ) -> Result<InitializeResult, McpError> {
-        // TODO: how to remove these?
-        let mut peers = self.peers.write().await;
-        peers.push(context.peer);
-        Ok(self.get_info())
+        mut request: CallToolRequestParam,
+    ) -> Result<CallToolRequestParam, McpError> {
+        if let Some(namespace) = &self.tool_namespace {
+            let name = request
+                .name
+                .strip_prefix(namespace.as_str())
+                .ok_or_else(|| tool_not_found(&request.name))?
+                .to_string();
+            request.name = name.into();
+        }
+        Ok(request)
     }
 
-    async fn call_tool(
+    /// The operation tools currently available, excluding those disabled at runtime
+    async fn operation_tools(&self) -> Vec<Tool> {
+        let disabled = self.disabled_tools.names().await;
+        let feature_flags = self.feature_flags.current().await;
+        self.operations
+            .lock()
+            .await
+            .iter()
+            .filter(|op| feature_flags.is_exposed(op.operation_name()))
+            .map(|op| op.as_ref().clone())
+            .filter(|tool| !disabled.iter().any(|name| *name == tool.name))
+            .collect()
+    }
+
+    /// All tools currently registered, including operations exposed by their feature flags and
+    /// enabled introspection tools
+    async fn all_tools(&self) -> Vec<Tool> {
+        let feature_flags = self.feature_flags.current().await;
+        self.operations
+            .lock()
+            .await
+            .iter()
+            .filter(|op| feature_flags.is_exposed(op.operation_name()))
+            .map(|op| op.as_ref().clone())
+            .chain(
+                self.rest_tools
+                    .iter()
+                    .map(|rest_tool| rest_tool.tool.clone()),
+            )
+            .chain(self.execute_tool.as_ref().iter().map(|e| e.tool.clone()))
+            .chain(self.introspect_tool.as_ref().iter().map(|e| e.tool.clone()))
+            .chain(self.search_tool.as_ref().iter().map(|e| e.tool.clone()))
+            .chain(self.explorer_tool.as_ref().iter().map(|e| e.tool.clone()))
+            .chain(self.validate_tool.as_ref().iter().map(|e| e.tool.clone()))
+            .chain(
+                self.list_operations_tool
+                    .as_ref()
+                    .iter()
+                    .map(|e| e.tool.clone()),
+            )
+            .collect()
+    }
+
+    /// Disable or re-enable a tool, notifying peers if its state changed.
+    /// Returns `None` if there is no such tool.
+    pub(super) async fn set_tool_enabled(&self, name: &str, enabled: bool) -> Option<bool> {
+        if !self.all_tools().await.iter().any(|tool| tool.name == name) {
+            return None;
+        }
+        if self.disabled_tools.set_enabled(name, enabled).await {
+            info!(tool = name, enabled, "Tool state changed");
+            Self::notify_tool_list_changed(self.peers.clone()).await;
+        }
+        Some(enabled)
+    }
+
+    /// Reload the feature flags whenever the process receives `SIGHUP`, notifying peers that the
+    /// tools exposed by the flags may have changed
+    pub(super) fn reload_feature_flags_on_hangup(&self, loader: feature_flags::ConfigLoader) {
+        let peers = self.peers.clone();
+        self.feature_flags.reload_on_hangup(loader, move || {
+            Self::notify_tool_list_changed(peers.clone())
+        });
+    }
+
+    /// Reject calls to tools disabled at runtime
+    async fn ensure_enabled(&self, name: &str) -> Result<(), McpError> {
+        if self.disabled_tools.is_disabled(name).await {
+            Err(McpError::new(
+                ErrorCode::INVALID_REQUEST,
+                format!("Tool {name} is disabled"),
+                None,
+            ))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A machine-readable description of what the server offers, assembled from its config and
+    /// the tools currently available
+    pub(super) async fn capabilities(&self) -> Value {
+        let introspection_tools: Vec<_> = self
+            .execute_tool
+            .iter()
+            .map(|e| &e.tool)
+            .chain(self.introspect_tool.iter().map(|e| &e.tool))
+            .chain(self.search_tool.iter().map(|e| &e.tool))
+            .chain(self.explorer_tool.iter().map(|e| &e.tool))
+            .chain(self.validate_tool.iter().map(|e| &e.tool))
+            .chain(self.list_operations_tool.iter().map(|e| &e.tool))
+            .map(|tool| tool.name.to_string())
+            .collect();
+        let tools: Vec<_> = self
+            .tools()
+            .await
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect();
+        let server_info = self.get_info().server_info;
+        json!({
+            "server": {
+                "name": server_info.name,
+                "version": server_info.version,
+            },
+            "transport": self.transport,
+            "mutation_mode": self.tool_options.mutation_mode,
+            "introspection_tools": introspection_tools,
+            "tool_count": tools.len(),
+            "tools": tools,
+            "limits": {
+                "operation_timeout_ms": self
+                    .operation_timeout
+                    .map(|timeout| timeout.duration.as_millis()),
+                "tool_concurrency": self.tool_concurrency.limits(),
+                "circuit_breaker": self.circuit_breaker.is_some(),
+            },
+            "progress_notifications": self.progress.is_some(),
+        })
+    }
+
+    /// Routes for describing the server's capabilities, and listing, disabling, and re-enabling
+    /// tools at runtime
+    pub(super) fn admin_router(&self, path: &str) -> Router {
+        Router::new()
+            .route(&format!("{path}/capabilities"), get(capabilities))
+            .route(&format!("{path}/tools/disabled"), get(disabled_tools))
+            .route(
+                &format!("{path}/tools/{{name}}/disable"),
+                post(disable_tool),
+            )
+            .route(&format!("{path}/tools/{{name}}/enable"), post(enable_tool))
+            .with_state(self.clone())
+    }
+
+    /// The enabled built-in tools, with their unprefixed names
+    fn builtin_tools(&self) -> impl Iterator<Item = (&Tool, &'static str)> {
+        [
+            (
+                self.execute_tool.as_ref().map(|tool| &tool.tool),
+                EXECUTE_TOOL_NAME,
+            ),
+            (
+                self.introspect_tool.as_ref().map(|tool| &tool.tool),
+                INTROSPECT_TOOL_NAME,
+            ),
+            (
+                self.search_tool.as_ref().map(|tool| &tool.tool),
+                SEARCH_TOOL_NAME,
+            ),
+            (
+                self.explorer_tool.as_ref().map(|tool| &tool.tool),
+                EXPLORER_TOOL_NAME,
+            ),
+            (
+                self.validate_tool.as_ref().map(|tool| &tool.tool),
+                VALIDATE_TOOL_NAME,
+            ),
+            (
+                self.list_operations_tool.as_ref().map(|tool| &tool.tool),
+                LIST_OPERATIONS_TOOL_NAME,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(tool, name)| Some((tool?, name)))
+    }
+
+    /// Get the unprefixed name of the built-in tool a tool name refers to, if any. Only enabled
+    /// built-in tools are matched, by the name they are offered with, so that an operation can
+    /// have the name of a built-in tool which is not enabled.
+    fn builtin_tool_name(&self, name: &str) -> Option<&'static str> {
+        self.builtin_tools()
+            .find(|(tool, _)| tool.name == name)
+            .map(|(_, builtin_name)| builtin_name)
+    }
+
+    /// Warn about operations named like an enabled built-in tool, which the built-in tool
+    /// shadows
+    pub(super) fn warn_about_shadowed_operations(&self, operations: &[Operation]) {
+        for operation in operations {
+            let name = &operation.as_ref().name;
+            if self.builtin_tool_name(name).is_some() {
+                warn!(
+                    "Operation {name} has the same name as a built-in tool, so calls to {name} run the built-in tool. Rename the operation, or set builtin_tool_prefix to namespace the built-in tools."
+                );
+            }
+        }
+    }
+
+    /// Dispatch a tool call to the tool it names
+    async fn execute_tool_call(
         &self,
         request: CallToolRequestParam,
         context: RequestContext<RoleServer>,
     ) -> Result<CallToolResult, McpError> {
-        let result = match request.name.as_ref() {
+        self.ensure_enabled(&request.name).await?;
+
+        match self.builtin_tool_name(&request.name).unwrap_or_default() {
             INTROSPECT_TOOL_NAME => {
                 self.introspect_tool
                     .as_ref()
@@ -209,10 +529,13 @@ impl ServerHandler for Running {
             }
             EXECUTE_TOOL_NAME => {
                 let mut headers = self.headers.clone();
+                // Without a token, the caller is not scoped for any protected field
+                let mut scope_mask = self.scope_mask(None);
                 if let Some(axum_parts) = context.extensions.get::<axum::http::request::Parts>() {
                     // Optionally extract the validated token and propagate it to upstream servers if present
                     if let Some(token) = axum_parts.extensions.get::<ValidToken>() {
                         headers.typed_insert(token.deref().clone());
+                        scope_mask = self.scope_mask(Some(token));
                     }
 
                     // Forward the mcp-session-id header if present
@@ -228,6 +551,16 @@ impl ServerHandler for Running {
                         input: Value::from(request.arguments.clone()),
                         endpoint: &self.endpoint,
                         headers,
+                        scope_mask: Some(scope_mask),
+                        claims: None,
+                        timeout: self.operation_timeout,
+                        graphql_errors: self.graphql_errors,
+                        strip_nulls: self.strip_nulls,
+                        report_coercions: self.report_coercions,
+                        websocket: self.websocket.as_ref(),
+                        error_codes: &self.error_codes,
+                        signing: self.signing.as_ref(),
+                        projection: None,
                     })
                     .await
             }
@@ -238,12 +571,39 @@ impl ServerHandler for Running {
                     .execute(convert_arguments(request)?)
                     .await
             }
+            LIST_OPERATIONS_TOOL_NAME => {
+                let list_operations = self
+                    .list_operations_tool
+                    .as_ref()
+                    .ok_or(tool_not_found(&request.name))?;
+                Ok(list_operations.execute(&self.operation_tools().await))
+            }
             _ => {
+                // REST tools are called without the GraphQL endpoint's headers
+                if let Some(rest_tool) = self
+                    .rest_tools
+                    .iter()
+                    .find(|rest_tool| rest_tool.tool.name == request.name)
+                {
+                    return rest_tool
+                        .execute(
+                            request.arguments,
+                            self.operation_timeout.map(|timeout| timeout.duration),
+                        )
+                        .await;
+                }
+
                 let mut headers = self.headers.clone();
+                // Without a token, the caller is not scoped for any protected field
+                let mut scope_mask = self.scope_mask(None);
+                let mut claims = None;
+                let mut caller = None;
                 if let Some(axum_parts) = context.extensions.get::<axum::http::request::Parts>() {
                     // Optionally extract the validated token and propagate it to upstream servers if present
                     if let Some(token) = axum_parts.extensions.get::<ValidToken>() {
                         headers.typed_insert(token.deref().clone());
+                        scope_mask = self.scope_mask(Some(token));
+                        claims = Some(token.claims());
                     }
 
                     // Also forward the mcp-session-id header if present
@@ -253,6 +613,7 @@ impl ServerHandler for Running {
 
                     if let Some(auth) = axum_parts.headers.get("authorization") {
                         headers.insert("authorization", auth.clone());
+                        caller = Some(auth.clone());
                     }
 
                     //                         if name_lower == "authorization"
@@ -268,20 +629,333 @@ impl ServerHandler for Running {
                     }
                 }
 
-                let graphql_request = graphql::Request {
-                    input: Value::from(request.arguments.clone()),
-                    endpoint: &self.endpoint,
-                    headers,
-                };
-                self.operations
-                    .lock()
+                self.call_operation(request, headers, Some(scope_mask), claims, caller.as_ref())
                     .await
-                    .iter()
-                    .find(|op| op.as_ref().name == request.name)
-                    .ok_or(tool_not_found(&request.name))?
-                    .execute(graphql_request)
+            }
+        }
+    }
+
+    /// Call the operation tool with the request's name
+    async fn call_operation(
+        &self,
+        request: CallToolRequestParam,
+        headers: HeaderMap,
+        scope_mask: Option<ScopeMask>,
+        claims: Option<&Map<String, Value>>,
+        caller: Option<&HeaderValue>,
+    ) -> Result<CallToolResult, McpError> {
+        // Cached results are keyed by all of the arguments, including the projection, and the
+        // forwarded headers
+        let cache_key = self
+            .result_cache
+            .as_ref()
+            .filter(|cache| cache.caches(caller.is_some()))
+            .map(|_| {
+                ResultCacheKey::new(
+                    &request.name,
+                    request.arguments.as_ref(),
+                    &headers,
+                    self.signing.as_ref(),
+                )
+            });
+
+        // The projection is not an operation variable, so it is removed from the input
+        let mut arguments = request.arguments;
+        let projection = arguments
+            .as_mut()
+            .filter(|_| self.tool_options.result_projection)
+            .and_then(|arguments| arguments.remove(PROJECT_ARGUMENT_NAME))
+            .map(Projection::from_argument)
+            .transpose()?;
+
+        // Neither is the flag asking for the cost estimate instead of the result
+        let estimate = arguments
+            .as_mut()
+            .filter(|_| self.tool_options.cost_estimates)
+            .and_then(|arguments| arguments.remove(ESTIMATE_ARGUMENT_NAME))
+            .is_some_and(|estimate| estimate == Value::Bool(true));
+
+        // Nor is the confirmation of a call to a destructive operation
+        let confirmed = arguments
+            .as_mut()
+            .filter(|_| self.tool_options.confirm_destructive_operations)
+            .and_then(|arguments| arguments.remove(CONFIRM_ARGUMENT_NAME))
+            .is_some_and(|confirm| confirm == Value::Bool(true));
+
+        let feature_flags = self.feature_flags.current().await;
+        let operations = self.operations.lock().await;
+        let operation = operations
+            .iter()
+            .filter(|op| feature_flags.is_exposed(op.operation_name()))
+            .find(|op| op.as_ref().name == request.name)
+            .ok_or(tool_not_found(&request.name))?;
+        if operation.requires_confirmation() && !confirmed && !estimate {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "{} is destructive, and must be called with `{CONFIRM_ARGUMENT_NAME}: true` to confirm it",
+                    request.name
+                ),
+                None,
+            ));
+        }
+        if estimate {
+            return self
+                .estimate_cost(operation, Value::from(arguments), claims)
+                .await;
+        }
+
+        // Only the results of read-only operations are cached, unless they disable caching
+        let cache = self.result_cache.as_ref().zip(cache_key).filter(|_| {
+            is_read_only(operation) && !operation.cache_ttl().is_some_and(|ttl| ttl.is_zero())
+        });
+        if let Some((cache, key)) = &cache
+            && let Some(result) = cache.get(key).await
+        {
+            return Ok(result);
+        }
+
+        let graphql_request = graphql::Request {
+            input: Value::from(arguments),
+            endpoint: &self.endpoint,
+            headers,
+            scope_mask,
+            claims,
+            timeout: self.operation_timeout,
+            graphql_errors: self.graphql_errors,
+            strip_nulls: self.strip_nulls,
+            report_coercions: self.report_coercions,
+            websocket: self.websocket.as_ref(),
+            error_codes: &self.error_codes,
+            signing: self.signing.as_ref(),
+            projection,
+        };
+        let result = operation.execute(graphql_request).await;
+        if let (Some((cache, key)), Ok(result)) = (cache, &result) {
+            match operation.cache_ttl() {
+                Some(ttl) => cache.insert_with_ttl(key, result, ttl).await,
+                None => cache.insert(key, result).await,
+            }
+        }
+        result
+    }
+
+    /// Estimate the cost of calling a read-only operation tool, without executing the operation
+    async fn estimate_cost(
+        &self,
+        operation: &Operation,
+        input: Value,
+        claims: Option<&Map<String, Value>>,
+    ) -> Result<CallToolResult, McpError> {
+        if !is_read_only(operation) {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "Cannot estimate the cost of {}, which is not read-only",
+                    operation.as_ref().name
+                ),
+                None,
+            ));
+        }
+
+        let details = operation.operation(input.clone())?;
+        let variables = operation.variables(input, claims)?;
+        let cost = graphql::estimate_cost(
+            &*self.schema.lock().await,
+            &details.query,
+            details.operation_name.as_deref(),
+            &variables,
+        )?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Estimated cost: {cost}"
+        ))]))
+    }
+
+    /// Execute a tool call, failing fast if the circuit for the tool is open
+    async fn call_tool_guarded(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let Some(circuit_breaker) = &self.circuit_breaker else {
+            return self.call_tool_metered(request, context).await;
+        };
+
+        let tool_name = request.name.to_string();
+        if let Err(retry_after) = circuit_breaker.check(&tool_name).await {
+            return Err(McpError::new(
+                ErrorCode::INTERNAL_ERROR,
+                format!(
+                    "Tool {tool_name} is unavailable after repeated failures. Retry in {}s",
+                    retry_after.as_secs().max(1)
+                ),
+                None,
+            ));
+        }
+
+        let result = self.call_tool_metered(request, context).await;
+
+        // Only failures of the tool itself count, not invalid calls to it
+        let failed = match &result {
+            Ok(result) => result.is_error == Some(true),
+            Err(error) => error.code == ErrorCode::INTERNAL_ERROR,
+        };
+        circuit_breaker.record(&tool_name, failed).await;
+        result
+    }
+
+    /// Execute a tool call, timing it out if an adaptive timeout is known for the tool. Tools with
+    /// a configured timeout are only timed out by it, so that it can return partial results.
+    async fn call_tool_metered(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let Some(meter) = &self.meter else {
+            return self.execute_tool_call(request, context).await;
+        };
+
+        let tool_name = request.name.to_string();
+        let started = Instant::now();
+        let adaptive_timeout = if self.has_configured_timeout(&tool_name).await {
+            None
+        } else {
+            meter.adaptive_timeout(&tool_name).await
+        };
+        let result = match adaptive_timeout {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, self.execute_tool_call(request, context))
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(McpError::new(
+                            ErrorCode::INTERNAL_ERROR,
+                            format!("Tool {tool_name} timed out after {timeout:?}"),
+                            None,
+                        ))
+                    })
+            }
+            None => self.execute_tool_call(request, context).await,
+        };
+
+        // Unknown tools are not recorded, so that clients cannot grow the metrics without bound
+        if !matches!(&result, Err(error) if error.code == ErrorCode::METHOD_NOT_FOUND) {
+            meter.record(&tool_name, started.elapsed()).await;
+        }
+
+        result
+    }
+
+    /// Whether calls to a tool are timed out by the server's operation timeout, or by the timeout
+    /// of the operation's own execution policy
+    async fn has_configured_timeout(&self, tool_name: &str) -> bool {
+        self.operation_timeout.is_some()
+            || self.operations.lock().await.iter().any(|operation| {
+                operation.as_ref().name == tool_name
+                    && operation.execution_policy().timeout.is_some()
+            })
+    }
+
+    /// Mask response fields which the token, if any, is not scoped for
+    fn scope_mask(&self, token: Option<&ValidToken>) -> ScopeMask {
+        let scopes = token
+            .map(|token| token.scopes().to_vec())
+            .unwrap_or_default();
+        ScopeMask::new(self.schema.clone(), scopes)
+    }
+}
+
+/// Whether an operation tool is annotated as read-only
+fn is_read_only(operation: &Operation) -> bool {
+    operation
+        .as_ref()
+        .annotations
+        .as_ref()
+        .and_then(|annotations| annotations.read_only_hint)
+        .unwrap_or_default()
+}
+
+/// Admin handler describing the server's capabilities
+async fn capabilities(State(running): State<Running>) -> Json<Value> {
+    Json(running.capabilities().await)
+}
+
+/// Admin handler listing the tools disabled at runtime
+async fn disabled_tools(State(running): State<Running>) -> Json<Value> {
+    Json(json!({ "disabled": running.disabled_tools.names().await }))
+}
+
+/// Admin handler disabling a tool
+async fn disable_tool(
+    State(running): State<Running>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    set_tool_enabled(running, name, false).await
+}
+
+/// Admin handler re-enabling a tool
+async fn enable_tool(
+    State(running): State<Running>,
+    Path(name): Path<String>,
+) -> Result<Json<Value>, StatusCode> {
+    set_tool_enabled(running, name, true).await
+}
+
+async fn set_tool_enabled(
+    running: Running,
+    name: String,
+    enabled: bool,
+) -> Result<Json<Value>, StatusCode> {
+    running
+        .set_tool_enabled(&name, enabled)
+        .await
+        .map(|enabled| Json(json!({ "name": name, "enabled": enabled })))
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+impl ServerHandler for Running {
+    async fn initialize(
+        &self,
+        _request: InitializeRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<InitializeResult, McpError> {
+        // TODO: how to remove these?
+        let mut peers = self.peers.write().await;
+        peers.push(context.peer);
+        Ok(self.get_info())
+    }
+
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let request = self.without_namespace(request)?;
+        let tool_name = request.name.to_string();
+        let peer = context.peer.clone();
+        let progress_token = context.meta.get_progress_token();
+        let call = Next::new(&self.middlewares, |request| {
+            async move {
+                let tool_name = request.name.to_string();
+                self.tool_concurrency
+                    .run(&tool_name, self.call_tool_guarded(request, context))
                     .await
             }
+            .boxed()
+        })
+        .run(request);
+        let call = trace_tool_call(&tool_name, call);
+        let result = match (&self.progress, progress_token) {
+            (Some(progress), Some(progress_token)) => {
+                progress
+                    .notify(&tool_name, &peer, progress_token, call)
+                    .await
+            }
+            _ => call.await,
+        };
+        let result = if self.sanitize_errors {
+            sanitize_result(&tool_name, result)
+        } else {
+            result
         };
 
         // Track errors for health check
@@ -294,24 +968,15 @@ impl ServerHandler for Running {
 
     async fn list_tools(
         &self,
-        _request: Option<PaginatedRequestParam>,
+        request: Option<PaginatedRequestParam>,
         _context: RequestContext<RoleServer>,
     ) -> Result<ListToolsResult, McpError> {
-        Ok(ListToolsResult {
-            next_cursor: None,
-            tools: self
-                .operations
-                .lock()
-                .await
-                .iter()
-                .map(|op| op.as_ref().clone())
-                .chain(self.execute_tool.as_ref().iter().map(|e| e.tool.clone()))
-                .chain(self.introspect_tool.as_ref().iter().map(|e| e.tool.clone()))
-                .chain(self.search_tool.as_ref().iter().map(|e| e.tool.clone()))
-                .chain(self.explorer_tool.as_ref().iter().map(|e| e.tool.clone()))
-                .chain(self.validate_tool.as_ref().iter().map(|e| e.tool.clone()))
-                .collect(),
-        })
+        self.tools_page(
+            request
+                .as_ref()
+                .and_then(|request| request.cursor.as_deref()),
+        )
+        .await
     }
 
     fn get_info(&self) -> ServerInfo {
@@ -346,18 +1011,30 @@ fn convert_arguments<T: serde::de::DeserializeOwned>(
 
 #[cfg(test)]
 mod tests {
-    use super::*;
+    use apollo_mcp_registry::uplink::schema::SchemaSource;
+    use apollo_schema_index::{IndexOptions, OperationType};
+    use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+    use tracing_subscriber::layer::SubscriberExt as _;
+    use tracing_subscriber::util::SubscriberInitExt as _;
+    use tracing_test::traced_test;
 
-    #[tokio::test]
-    async fn invalid_operations_should_not_crash_server() {
-        let schema = Schema::parse("type Query { id: String }", "schema.graphql")
-            .unwrap()
-            .validate()
-            .unwrap();
+    use std::collections::HashMap;
+    use std::time::Duration;
 
-        let running = Running {
-            schema: Arc::new(Mutex::new(schema)),
+    use super::*;
+    use crate::feature_flags::FeatureFlagsConfig;
+    use crate::introspection::tools::search::SearchOptions;
+    use crate::meter::{AdaptiveTimeoutConfig, MeterConfig};
+    use crate::operations::{MutationMode, OperationSource};
+    use crate::server::Transport;
+    use crate::server::test::{Client, free_port, server};
+    use crate::telemetry::{self, GRAPHQL_REQUEST_SPAN_NAME, TOOL_CALL_SPAN_NAME};
+
+    fn running(schema: Arc<Mutex<Valid<Schema>>>) -> Running {
+        Running {
+            schema,
             operations: Arc::new(Mutex::new(vec![])),
+            operation_cache: OperationCache::default(),
             headers: HeaderMap::new(),
             endpoint: "http://localhost:4000".parse().unwrap(),
             execute_tool: None,
@@ -365,14 +1042,43 @@ mod tests {
             search_tool: None,
             explorer_tool: None,
             validate_tool: None,
+            list_operations_tool: None,
             custom_scalar_map: None,
             peers: Arc::new(RwLock::new(vec![])),
             cancellation_token: CancellationToken::new(),
-            mutation_mode: MutationMode::None,
-            disable_type_description: false,
-            disable_schema_description: false,
+            tool_options: ToolOptions::default(),
             health_check: None,
-        };
+            meter: None,
+            circuit_breaker: None,
+            result_cache: None,
+            rest_tools: Vec::new(),
+            progress: None,
+            tool_concurrency: ToolConcurrency::default(),
+            operation_timeout: None,
+            graphql_errors: GraphQLErrors::default(),
+            strip_nulls: None,
+            report_coercions: false,
+            websocket: None,
+            error_codes: ErrorCodes::default(),
+            sanitize_errors: false,
+            signing: None,
+            tool_namespace: None,
+            tools_page_size: None,
+            disabled_tools: DisabledTools::default(),
+            feature_flags: FeatureFlags::default(),
+            middlewares: Vec::new(),
+            transport: "stdio",
+        }
+    }
+
+    #[tokio::test]
+    async fn invalid_operations_should_not_crash_server() {
+        let schema = Schema::parse("type Query { id: String }", "schema.graphql")
+            .unwrap()
+            .validate()
+            .unwrap();
+
+        let running = running(Arc::new(Mutex::new(schema)));
 
         let operations = vec![
             RawOperation::from((
@@ -395,4 +1101,821 @@ mod tests {
         assert_eq!(updated_operations.len(), 1);
         assert_eq!(updated_operations.first().unwrap().as_ref().name, "Valid");
     }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn schema_changes_only_modify_affected_tools() {
+        let schema = Schema::parse_and_validate(
+            "type Query { user(role: Role): String post(id: ID): String } enum Role { ADMIN }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let running = running(Arc::new(Mutex::new(schema)))
+            .update_operations(vec![
+                RawOperation::from((
+                    "query GetUser($role: Role) { user(role: $role) }".to_string(),
+                    None,
+                )),
+                RawOperation::from(("query GetPost($id: ID) { post(id: $id) }".to_string(), None)),
+            ])
+            .await
+            .unwrap();
+
+        let schema = Schema::parse_and_validate(
+            "type Query { user(role: Role): String post(id: ID): String } enum Role { ADMIN MEMBER }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let running = running.update_schema(schema.clone()).await.unwrap();
+        assert!(logs_contain(
+            r#"Tools changed added=[] removed=[] modified=["GetUser"]"#
+        ));
+
+        running.update_schema(schema).await.unwrap();
+        assert!(logs_contain("Tools are unchanged, not notifying peers"));
+    }
+
+    #[tokio::test]
+    async fn only_enabled_introspection_tools_are_registered() {
+        let port = free_port();
+        let transport = Transport::StreamableHttp {
+            auth: None,
+            address: "127.0.0.1".parse().unwrap(),
+            port,
+            path: "/mcp".to_string(),
+            max_request_body_bytes: 1024 * 1024,
+        };
+        let schema_source = SchemaSource::Static {
+            schema_sdl: "type Query { id: String }".to_string(),
+        };
+        let mut server = server(transport, schema_source, OperationSource::None);
+        server.search_introspection = true;
+        tokio::spawn(server.start());
+
+        let client = Client::connect(&format!("http://127.0.0.1:{port}/mcp")).await;
+        assert_eq!(
+            client.list_tools().await,
+            vec![SEARCH_TOOL_NAME.to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn prefixed_builtin_tools_do_not_clash_with_operations() {
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate("type Query { id: String }", "schema.graphql").unwrap(),
+        ));
+        let mut search_tool = Search::new(
+            schema.clone(),
+            OperationType::Query.into(),
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ..Default::default()
+            },
+            SearchOptions::default(),
+        )
+        .unwrap();
+        search_tool.tool.name = format!("apollo_{SEARCH_TOOL_NAME}").into();
+
+        let running = Running {
+            search_tool: Some(search_tool),
+            ..running(schema)
+        }
+        .update_operations(vec![RawOperation::from((
+            "query search { id }".to_string(),
+            Some("search.graphql".to_string()),
+        ))])
+        .await
+        .unwrap();
+
+        let tools = running.tools().await;
+        let names: Vec<_> = tools.iter().map(|tool| tool.name.as_ref()).collect();
+        assert_eq!(names, vec!["search", "apollo_search"]);
+
+        // The unprefixed name is routed to the operation, and the prefixed name to the search tool
+        assert_eq!(running.builtin_tool_name("search"), None);
+        assert_eq!(
+            running.builtin_tool_name("apollo_search"),
+            Some(SEARCH_TOOL_NAME)
+        );
+    }
+
+    #[tokio::test]
+    async fn tool_calls_dispatch_to_operations_named_like_prefixed_builtin_tools() {
+        let mut endpoint = mockito::Server::new_async().await;
+        endpoint
+            .mock("POST", "/")
+            .with_body(r#"{ "data": { "id": "1" } }"#)
+            .create_async()
+            .await;
+        let dir = std::env::temp_dir().join(format!("prefixed-builtins-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let operation = dir.join("apollo_introspect.graphql");
+        std::fs::write(&operation, "query apollo_introspect { id }").unwrap();
+
+        let port = free_port();
+        let transport = Transport::StreamableHttp {
+            auth: None,
+            address: "127.0.0.1".parse().unwrap(),
+            port,
+            path: "/mcp".to_string(),
+            max_request_body_bytes: 1024 * 1024,
+        };
+        let schema_source = SchemaSource::Static {
+            schema_sdl: "type Query { id: String }".to_string(),
+        };
+        let mut server = server(
+            transport,
+            schema_source,
+            OperationSource::Files(vec![operation]),
+        );
+        server.endpoint = endpoint.url().parse().unwrap();
+        server.search_introspection = true;
+        server.builtin_tool_prefix = Some("apollo_".to_string());
+        tokio::spawn(server.start());
+
+        let client = Client::connect(&format!("http://127.0.0.1:{port}/mcp")).await;
+        assert_eq!(
+            client.list_tools().await,
+            vec!["apollo_introspect".to_string(), "apollo_search".to_string()]
+        );
+
+        // The introspect tool is not enabled, so its prefixed name refers to the operation
+        let result = client
+            .call_tool("apollo_introspect", serde_json::json!({}))
+            .await;
+        assert_eq!(result["isError"], false);
+        assert!(
+            result["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .contains(r#""id":"1""#)
+        );
+
+        let result = client
+            .call_tool("apollo_search", serde_json::json!({ "terms": ["id"] }))
+            .await;
+        assert!(
+            result["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .contains("type Query")
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn configured_timeouts_take_precedence_over_adaptive_timeouts() {
+        let mut endpoint = mockito::Server::new_async().await;
+        let fast = endpoint
+            .mock("POST", "/")
+            .with_body(r#"{ "data": { "id": "1" } }"#)
+            .create_async()
+            .await;
+        let dir = std::env::temp_dir().join(format!("adaptive-timeouts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let operation = dir.join("GetId.graphql");
+        std::fs::write(&operation, "query GetId { id }").unwrap();
+
+        let port = free_port();
+        let transport = Transport::StreamableHttp {
+            auth: None,
+            address: "127.0.0.1".parse().unwrap(),
+            port,
+            path: "/mcp".to_string(),
+            max_request_body_bytes: 1024 * 1024,
+        };
+        let schema_source = SchemaSource::Static {
+            schema_sdl: "type Query { id: ID }".to_string(),
+        };
+        let mut server = server(
+            transport,
+            schema_source,
+            OperationSource::Files(vec![operation]),
+        );
+        server.endpoint = endpoint.url().parse().unwrap();
+        server.operation_timeout = Some(Duration::from_millis(500));
+        server.partial_results_on_timeout = true;
+        server.meter = MeterConfig {
+            enabled: true,
+            adaptive_timeout: AdaptiveTimeoutConfig {
+                enabled: true,
+                min: Duration::from_millis(100),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        tokio::spawn(server.start());
+        let client = Client::connect(&format!("http://127.0.0.1:{port}/mcp")).await;
+
+        // A fast call teaches the meter an adaptive timeout shorter than the configured timeout
+        client.call_tool("GetId", serde_json::json!({})).await;
+        fast.remove_async().await;
+
+        // The first part of a deferred response arrives straight away, and the rest after 2s
+        endpoint
+            .mock("POST", "/")
+            .with_header(
+                "content-type",
+                "multipart/mixed;boundary=\"graphql\";deferSpec=20220824",
+            )
+            .with_chunked_body(|w| {
+                w.write_all(b"\r\n--graphql\r\ncontent-type: application/json\r\n\r\n{\"data\":{\"id\":\"1\"},\"hasNext\":true}\r\n--graphql\r\n")?;
+                w.flush()?;
+                std::thread::sleep(Duration::from_secs(2));
+                w.write_all(b"content-type: application/json\r\n\r\n{\"incremental\":[],\"hasNext\":false}\r\n--graphql--\r\n")
+            })
+            .create_async()
+            .await;
+
+        let result = client.call_tool("GetId", serde_json::json!({})).await;
+        assert_eq!(result["isError"], false, "{result}");
+        assert_eq!(
+            result["content"][1]["text"],
+            "The operation timed out after 500ms. The response contains only the data delivered before the timeout."
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn protected_fields_are_masked_without_a_token() {
+        let mut endpoint = mockito::Server::new_async().await;
+        endpoint
+            .mock("POST", "/")
+            .with_body(r#"{ "data": { "user": { "id": "1", "email": "ada@example.com" } } }"#)
+            .create_async()
+            .await;
+        let dir = std::env::temp_dir().join(format!("unscoped-caller-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let operation = dir.join("GetUser.graphql");
+        std::fs::write(&operation, "query GetUser { user { id email } }").unwrap();
+
+        let port = free_port();
+        let transport = Transport::StreamableHttp {
+            auth: None,
+            address: "127.0.0.1".parse().unwrap(),
+            port,
+            path: "/mcp".to_string(),
+            max_request_body_bytes: 1024 * 1024,
+        };
+        let schema_source = SchemaSource::Static {
+            schema_sdl: r#"
+                directive @requiresScope(scope: String!) on FIELD_DEFINITION
+                type Query { user: User }
+                type User { id: ID! email: String @requiresScope(scope: "read:email") }
+            "#
+            .to_string(),
+        };
+        let mut server = server(
+            transport,
+            schema_source,
+            OperationSource::Files(vec![operation]),
+        );
+        server.endpoint = endpoint.url().parse().unwrap();
+        tokio::spawn(server.start());
+        let client = Client::connect(&format!("http://127.0.0.1:{port}/mcp")).await;
+
+        let result = client.call_tool("GetUser", serde_json::json!({})).await;
+        let text = result["content"][0]["text"].as_str().unwrap();
+        assert_eq!(
+            serde_json::from_str::<Value>(text).unwrap(),
+            serde_json::json!({ "data": { "user": { "id": "1", "email": null } } }),
+            "{result}"
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn operations_shadowed_by_builtin_tools_are_warned_about() {
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate("type Query { id: String }", "schema.graphql").unwrap(),
+        ));
+        let search_tool = Search::new(
+            schema.clone(),
+            OperationType::Query.into(),
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ..Default::default()
+            },
+            SearchOptions::default(),
+        )
+        .unwrap();
+
+        Running {
+            search_tool: Some(search_tool),
+            ..running(schema)
+        }
+        .update_operations(vec![
+            RawOperation::from(("query search { id }".to_string(), None)),
+            RawOperation::from(("query introspect { id }".to_string(), None)),
+        ])
+        .await
+        .unwrap();
+
+        assert!(logs_contain(
+            "Operation search has the same name as a built-in tool"
+        ));
+        assert!(!logs_contain("Operation introspect"));
+    }
+
+    #[tokio::test]
+    async fn tools_are_namespaced_by_the_graph_ref() {
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate("type Query { id: String }", "schema.graphql").unwrap(),
+        ));
+        let running = Running {
+            introspect_tool: Some(Introspect::new(
+                schema.clone(),
+                Some("Query".to_string()),
+                None,
+                false,
+            )),
+            tool_namespace: Some("mygraph_".to_string()),
+            ..running(schema)
+        }
+        .update_operations(vec![RawOperation::from((
+            "query GetId { id }".to_string(),
+            None,
+        ))])
+        .await
+        .unwrap();
+
+        let tools = running.tools().await;
+        let names: Vec<_> = tools.iter().map(|tool| tool.name.as_ref()).collect();
+        assert_eq!(names, vec!["mygraph_GetId", "mygraph_introspect"]);
+
+        // The namespace is removed before dispatch, and names outside it are not found
+        let request = |name: &str| CallToolRequestParam {
+            name: name.to_string().into(),
+            arguments: None,
+        };
+        assert_eq!(
+            running
+                .without_namespace(request("mygraph_GetId"))
+                .unwrap()
+                .name,
+            "GetId"
+        );
+        assert_eq!(
+            running
+                .without_namespace(request("GetId"))
+                .unwrap_err()
+                .code,
+            ErrorCode::METHOD_NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn tools_are_listed_in_pages() {
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate("type Query { id: String }", "schema.graphql").unwrap(),
+        ));
+        let running = Running {
+            tools_page_size: Some(2),
+            ..running(schema)
+        }
+        .update_operations(
+            ["A", "B", "C"]
+                .into_iter()
+                .map(|name| RawOperation::from((format!("query {name} {{ id }}"), None)))
+                .collect(),
+        )
+        .await
+        .unwrap();
+
+        let first = running.tools_page(None).await.unwrap();
+        assert_eq!(first.tools.len(), 2);
+        let cursor = first.next_cursor.expect("more tools remain");
+        let second = running.tools_page(Some(&cursor)).await.unwrap();
+        assert_eq!(second.next_cursor, None);
+
+        let names: Vec<_> = first
+            .tools
+            .iter()
+            .chain(&second.tools)
+            .map(|tool| tool.name.to_string())
+            .collect();
+        assert_eq!(names, vec!["A", "B", "C"]);
+
+        assert_eq!(
+            running.tools_page(Some("bogus")).await.unwrap_err().code,
+            ErrorCode::INVALID_PARAMS
+        );
+    }
+
+    #[tokio::test]
+    async fn tools_can_be_disabled_via_the_admin_endpoint() {
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate("type Query { id: String }", "schema.graphql").unwrap(),
+        ));
+        let running = running(schema)
+            .update_operations(vec![RawOperation::from((
+                "query GetId { id }".to_string(),
+                Some("get_id.graphql".to_string()),
+            ))])
+            .await
+            .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let router = running.admin_router("/admin");
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let client = reqwest::Client::new();
+        let post = |path: &str| client.post(format!("http://{address}/admin{path}")).send();
+
+        let response = post("/tools/GetId/disable").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.json::<Value>().await.unwrap(),
+            json!({ "name": "GetId", "enabled": false })
+        );
+        assert!(running.tools().await.is_empty());
+        let error = running.ensure_enabled("GetId").await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_REQUEST);
+        assert_eq!(error.message, "Tool GetId is disabled");
+
+        let response = post("/tools/Unknown/disable").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = post("/tools/GetId/enable").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(running.tools().await.len(), 1);
+        assert!(running.ensure_enabled("GetId").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn operations_are_exposed_when_their_feature_flag_is_turned_on() {
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate("type Query { id: String }", "schema.graphql").unwrap(),
+        ));
+        let gated = |enabled| FeatureFlagsConfig {
+            flags: HashMap::from([("beta".to_string(), enabled)]),
+            operations: HashMap::from([("GetId".to_string(), "beta".to_string())]),
+        };
+        let mut running = running(schema);
+        running.feature_flags = FeatureFlags::new(gated(false));
+        let running = running
+            .update_operations(vec![RawOperation::from((
+                "query GetId { id }".to_string(),
+                Some("get_id.graphql".to_string()),
+            ))])
+            .await
+            .unwrap();
+        assert!(running.tools().await.is_empty());
+
+        let loader: feature_flags::ConfigLoader = Arc::new(move || Ok(gated(true)));
+        running.feature_flags.reload(&loader).await.unwrap();
+        let tools = running.tools().await;
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "GetId");
+    }
+
+    #[tokio::test]
+    async fn capabilities_describe_the_configured_server() {
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate(
+                "type Query { id: String } type Mutation { setId: String }",
+                "schema.graphql",
+            )
+            .unwrap(),
+        ));
+        let running = Running {
+            introspect_tool: Some(Introspect::new(
+                schema.clone(),
+                Some("Query".to_string()),
+                None,
+                false,
+            )),
+            validate_tool: Some(Validate::new(schema.clone(), MutationMode::Explicit)),
+            tool_options: ToolOptions {
+                mutation_mode: MutationMode::Explicit,
+                ..Default::default()
+            },
+            transport: "streamable_http",
+            ..running(schema)
+        }
+        .update_operations(vec![
+            RawOperation::from(("query GetId { id }".to_string(), None)),
+            RawOperation::from(("mutation SetId { setId }".to_string(), None)),
+        ])
+        .await
+        .unwrap();
+
+        let capabilities = running.capabilities().await;
+        assert_eq!(capabilities["transport"], "streamable_http");
+        assert_eq!(capabilities["mutation_mode"], "explicit");
+        assert_eq!(
+            capabilities["introspection_tools"],
+            json!([INTROSPECT_TOOL_NAME, VALIDATE_TOOL_NAME])
+        );
+        assert_eq!(capabilities["tool_count"], 4);
+        assert_eq!(
+            capabilities["tools"],
+            json!(["GetId", "SetId", INTROSPECT_TOOL_NAME, VALIDATE_TOOL_NAME])
+        );
+
+        // Tools disabled at runtime are not offered
+        running.set_tool_enabled("SetId", false).await;
+        assert_eq!(running.capabilities().await["tool_count"], 3);
+    }
+
+    #[tokio::test]
+    async fn tool_calls_are_traced() {
+        let mut server = mockito::Server::new_async().await;
+        server
+            .mock("POST", "/")
+            .with_body(r#"{ "data": { "id": "1" } }"#)
+            .create_async()
+            .await;
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate("type Query { id: String }", "schema.graphql").unwrap(),
+        ));
+        let running = Running {
+            endpoint: server.url().parse().unwrap(),
+            ..running(schema)
+        }
+        .update_operations(vec![RawOperation::from((
+            "query GetId { id }".to_string(),
+            None,
+        ))])
+        .await
+        .unwrap();
+
+        let exporter = InMemorySpanExporter::default();
+        let provider = SdkTracerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        let _subscriber = tracing_subscriber::registry()
+            .with(telemetry::layer(&provider))
+            .set_default();
+
+        trace_tool_call(
+            "GetId",
+            running.call_operation(
+                CallToolRequestParam {
+                    name: "GetId".into(),
+                    arguments: None,
+                },
+                HeaderMap::new(),
+                None,
+                None,
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+
+        let spans = exporter.get_finished_spans().unwrap();
+        let span = |name: &str| spans.iter().find(|span| span.name == name).unwrap();
+        let attribute = |name: &str, key: &str| {
+            span(name)
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key.as_str() == key)
+                .map(|attribute| attribute.value.to_string())
+        };
+
+        assert_eq!(
+            attribute(TOOL_CALL_SPAN_NAME, "mcp.tool.name").as_deref(),
+            Some("GetId")
+        );
+        assert_eq!(
+            attribute(TOOL_CALL_SPAN_NAME, "mcp.tool.is_error").as_deref(),
+            Some("false")
+        );
+        assert_eq!(
+            attribute(GRAPHQL_REQUEST_SPAN_NAME, "url.full"),
+            Some(format!("{}/", server.url()))
+        );
+        assert_eq!(
+            attribute(GRAPHQL_REQUEST_SPAN_NAME, "graphql.operation.name").as_deref(),
+            Some("GetId")
+        );
+        assert_eq!(
+            attribute(GRAPHQL_REQUEST_SPAN_NAME, "http.response.status_code").as_deref(),
+            Some("200")
+        );
+        assert_eq!(
+            span(GRAPHQL_REQUEST_SPAN_NAME).parent_span_id,
+            span(TOOL_CALL_SPAN_NAME).span_context.span_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn estimates_cost_without_executing_the_operation() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server.mock("POST", "/").expect(0).create_async().await;
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate(
+                r#"
+                directive @cost(weight: Int!) on FIELD_DEFINITION | OBJECT
+                directive @listSize(assumedSize: Int, slicingArguments: [String!]) on FIELD_DEFINITION
+                type Query { users(first: Int): [User] @listSize(slicingArguments: ["first"]) }
+                type Mutation { addUser: User }
+                type User @cost(weight: 5) { id: ID }
+                "#,
+                "schema.graphql",
+            )
+            .unwrap(),
+        ));
+        let running = Running {
+            endpoint: server.url().parse().unwrap(),
+            tool_options: ToolOptions {
+                cost_estimates: true,
+                mutation_mode: MutationMode::All,
+                ..Default::default()
+            },
+            ..running(schema)
+        }
+        .update_operations(vec![
+            RawOperation::from((
+                "query GetUsers($first: Int) { users(first: $first) { id } }".to_string(),
+                None,
+            )),
+            RawOperation::from(("mutation AddUser { addUser { id } }".to_string(), None)),
+        ])
+        .await
+        .unwrap();
+        let call = |name: &'static str| {
+            running.call_operation(
+                CallToolRequestParam {
+                    name: name.into(),
+                    arguments: json!({ "first": 4, ESTIMATE_ARGUMENT_NAME: true })
+                        .as_object()
+                        .cloned(),
+                },
+                HeaderMap::new(),
+                None,
+                None,
+                None,
+            )
+        };
+
+        let result = call("GetUsers").await.unwrap();
+        assert_eq!(
+            result
+                .content
+                .first()
+                .and_then(|content| content.as_text())
+                .unwrap()
+                .text,
+            "Estimated cost: 20"
+        );
+
+        let error = call("AddUser").await.unwrap_err();
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn caches_read_only_results_per_caller() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/")
+            .with_body(r#"{ "data": { "id": "4" } }"#)
+            .expect(2)
+            .create_async()
+            .await;
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate("type Query { id: String }", "schema.graphql").unwrap(),
+        ));
+        let running = Running {
+            endpoint: server.url().parse().unwrap(),
+            result_cache: Some(ResultCache::new(Default::default())),
+            ..running(schema)
+        }
+        .update_operations(vec![RawOperation::from((
+            "query GetId { id }".to_string(),
+            None,
+        ))])
+        .await
+        .unwrap();
+        let running = &running;
+        let call = |caller: &'static str| {
+            let caller = HeaderValue::from_static(caller);
+            let mut headers = HeaderMap::new();
+            headers.insert("authorization", caller.clone());
+            async move {
+                running
+                    .call_operation(
+                        CallToolRequestParam {
+                            name: "GetId".into(),
+                            arguments: None,
+                        },
+                        headers,
+                        None,
+                        None,
+                        Some(&caller),
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let first = call("Bearer alice").await;
+        assert_eq!(call("Bearer alice").await, first);
+        call("Bearer bob").await;
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn operations_can_disable_result_caching() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/")
+            .with_body(r#"{ "data": { "id": "4" } }"#)
+            .expect(3)
+            .create_async()
+            .await;
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate("type Query { id: String }", "schema.graphql").unwrap(),
+        ));
+        let running = Running {
+            endpoint: server.url().parse().unwrap(),
+            result_cache: Some(ResultCache::new(Default::default())),
+            ..running(schema)
+        }
+        .update_operations(vec![
+            RawOperation::from(("# @cacheTtl(0)\nquery Uncached { id }".to_string(), None)),
+            RawOperation::from(("# @cacheTtl(5m)\nquery Cached { id }".to_string(), None)),
+        ])
+        .await
+        .unwrap();
+        let call = |name: &'static str| {
+            running.call_operation(
+                CallToolRequestParam {
+                    name: name.into(),
+                    arguments: None,
+                },
+                HeaderMap::new(),
+                None,
+                None,
+                None,
+            )
+        };
+
+        for name in ["Uncached", "Uncached", "Cached", "Cached"] {
+            call(name).await.unwrap();
+        }
+
+        mock.assert_async().await;
+    }
+
+    #[tokio::test]
+    async fn destructive_operations_must_be_confirmed() {
+        let mut server = mockito::Server::new_async().await;
+        let mock = server
+            .mock("POST", "/")
+            .with_body(r#"{ "data": { "wipe": true } }"#)
+            .expect(1)
+            .create_async()
+            .await;
+        let schema = Arc::new(Mutex::new(
+            Schema::parse_and_validate(
+                "type Query { id: String } type Mutation { wipe: Boolean }",
+                "schema.graphql",
+            )
+            .unwrap(),
+        ));
+        let running = Running {
+            endpoint: server.url().parse().unwrap(),
+            tool_options: ToolOptions {
+                mutation_mode: MutationMode::All,
+                confirm_destructive_operations: true,
+                ..Default::default()
+            },
+            ..running(schema)
+        }
+        .update_operations(vec![RawOperation::from((
+            "# @destructive(true)\nmutation Wipe { wipe }".to_string(),
+            None,
+        ))])
+        .await
+        .unwrap();
+        let call = |arguments: Value| {
+            running.call_operation(
+                CallToolRequestParam {
+                    name: "Wipe".into(),
+                    arguments: arguments.as_object().cloned(),
+                },
+                HeaderMap::new(),
+                None,
+                None,
+                None,
+            )
+        };
+
+        let unconfirmed = call(json!({})).await.unwrap_err();
+        assert_eq!(unconfirmed.code, ErrorCode::INVALID_PARAMS);
+        assert!(call(json!({ CONFIRM_ARGUMENT_NAME: false })).await.is_err());
+        assert!(
+            call(json!({ CONFIRM_ARGUMENT_NAME: true }))
+                .await
+                .is_ok_and(|result| result.is_error != Some(true))
+        );
+
+        mock.assert_async().await;
+    }
 }
@@ -1,26 +1,47 @@
 use std::{net::SocketAddr, sync::Arc};
 
 use apollo_compiler::{Name, Schema, ast::OperationType, validation::Valid};
+use apollo_schema_index::{IndexOptions, OperationType as IndexOperationType};
 use axum::{Router, extract::Query, http::StatusCode, response::Json, routing::get};
+use enumset::EnumSet;
 use rmcp::transport::StreamableHttpService;
 use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
 use rmcp::{
-    ServiceExt as _,
+    ServerHandler, ServiceExt as _,
     transport::{SseServer, sse_server::SseServerConfig, stdio},
 };
 use serde_json::json;
 use tokio::sync::{Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
-use tracing::{Instrument as _, debug, error, info, trace};
+use tower_http::limit::RequestBodyLimitLayer;
+use tracing::{Instrument as _, debug, error, info, trace, warn};
 
 use crate::{
+    admin::DisabledTools,
+    auth::ReloadableConfig,
+    circuit_breaker::CircuitBreaker,
+    concurrency::ToolConcurrency,
     errors::ServerError,
     explorer::Explorer,
+    feature_flags::FeatureFlags,
+    graphql::WebSocketConnection,
     health::HealthCheck,
     introspection::tools::{
-        execute::Execute, introspect::Introspect, search::Search, validate::Validate,
+        execute::Execute,
+        introspect::Introspect,
+        list_operations::ListOperations,
+        search::{Search, SearchOptions},
+        validate::Validate,
     },
-    operations::{MutationMode, RawOperation},
+    meter::Meter,
+    operations::{
+        MutationMode, OperationCache, RawOperation, ToolOptions, check_operation_dependencies,
+        convert_operations, dedupe_identical_operations, limit_tools, resolve_duplicate_operations,
+        restore_colliding_tool_names,
+    },
+    rest::RestTool,
+    result_cache::ResultCache,
+    schema_tree_shake::ShakeCache,
     server::Transport,
 };
 
@@ -36,24 +57,76 @@ impl Starting {
     pub(super) async fn start(self) -> Result<Running, ServerError> {
         let peers = Arc::new(RwLock::new(Vec::new()));
 
-        let operations: Vec<_> = self
-            .operations
-            .into_iter()
-            .filter_map(|operation| {
-                operation
-                    .into_operation(
-                        &self.schema,
-                        self.config.custom_scalar_map.as_ref(),
-                        self.config.mutation_mode,
-                        self.config.disable_type_description,
-                        self.config.disable_schema_description,
-                    )
-                    .unwrap_or_else(|error| {
-                        error!("Invalid operation: {}", error);
-                        None
-                    })
-            })
-            .collect();
+        let tool_options = ToolOptions {
+            mutation_mode: self.config.mutation_mode,
+            max_mutation_mode: self.config.max_mutation_mode,
+            disable_type_description: self.config.disable_type_description,
+            disable_schema_description: self.config.disable_schema_description,
+            return_type_description: self.config.return_type_description,
+            include_root_schema_description: self.config.include_root_schema_description,
+            variables_as_object: self.config.variables_as_object,
+            token_estimator: self.config.token_estimator,
+            tool_size_warn_threshold: self.config.tool_size_warn_threshold,
+            max_input_definitions: self.config.max_input_definitions,
+            describe_variables: self.config.describe_variables,
+            describe_graphql_types: self.config.describe_graphql_types,
+            property_titles: self.config.property_titles,
+            variable_summary: self.config.variable_summary,
+            always_include_fields: self.config.always_include_fields,
+            confirm_destructive_operations: self.config.confirm_destructive_operations,
+            split_list_strings: self.config.split_list_strings,
+            output_validation: self.config.output_validation,
+            fallback_description: self.config.fallback_description,
+            definitions_keyword: self.config.definitions_keyword,
+            operation_loading_threads: self.config.operation_loading_threads,
+            schema_description_note: self.config.schema_description_note.clone(),
+            disable_read_only_heuristic: self.config.disable_read_only_heuristic,
+            nullable_style: self.config.nullable_style,
+            claim_variables: self.config.claim_variables.clone(),
+            shared_fragments: self.config.shared_fragments.clone(),
+            strict_operations: self.config.strict_operations,
+            max_operation_depth: self.config.max_operation_depth,
+            max_operation_variables: self.config.max_operation_variables,
+            check_missing_fields: self.config.check_missing_fields,
+            fragment_expansion: self.config.fragment_expansion,
+            single_operation_documents: self.config.single_operation_documents,
+            result_projection: self.config.result_projection,
+            cost_estimates: self.config.cost_estimates,
+            include_operation_source: self.config.include_operation_source,
+            include_examples: self.config.include_examples,
+            tag_categories: self.config.tag_categories,
+            header_sets: self.config.header_sets.clone(),
+            null_variables: self.config.null_variables,
+            coerce_scalars: self.config.coerce_scalars,
+            strip_operation_prefix: self.config.strip_operation_prefix.clone(),
+            builtin_scalar_descriptions: self.config.builtin_scalar_descriptions.clone(),
+            constraint_directive: self.config.constraint_directive.clone(),
+            max_tools: self.config.max_tools.clone(),
+            duplicate_operations: self.config.duplicate_operations,
+            dedupe_identical_operations: self.config.dedupe_identical_operations,
+            operation_constants: self.config.operation_constants.clone(),
+            shake_cache: self.config.shake_cache.then(ShakeCache::default),
+        };
+        let operation_cache = OperationCache::default();
+        let mut operations = convert_operations(
+            self.operations
+                .into_iter()
+                .flat_map(|operation| operation.split(&tool_options))
+                .collect(),
+            &self.schema,
+            self.config.custom_scalar_map.as_ref(),
+            &tool_options,
+            &operation_cache,
+        );
+        resolve_duplicate_operations(&mut operations, tool_options.duplicate_operations)?;
+        if tool_options.dedupe_identical_operations {
+            dedupe_identical_operations(&mut operations);
+        }
+        restore_colliding_tool_names(&mut operations);
+        if let Some(max_tools) = &tool_options.max_tools {
+            limit_tools(&mut operations, max_tools)?;
+        }
+        check_operation_dependencies(&operations);
 
         debug!(
             "Loaded {} operations:\n{}",
@@ -61,10 +134,17 @@ impl Starting {
             serde_json::to_string_pretty(&operations)?
         );
 
-        let execute_tool = self
-            .config
-            .execute_introspection
-            .then(|| Execute::new(self.config.mutation_mode));
+        let mut execute_tool = self.config.execute_introspection.then(|| {
+            let execute = Execute::new(
+                self.config.mutation_mode,
+                self.config.introspect_introspection,
+            );
+            execute.set_buildable_mutations(&operations);
+            if self.config.execute_default_selections {
+                execute.enable_default_selections(&self.schema);
+            }
+            execute
+        });
 
         let root_query_type = self
             .config
@@ -91,7 +171,7 @@ impl Starting {
             })
             .flatten();
         let schema = Arc::new(Mutex::new(self.schema));
-        let introspect_tool = self.config.introspect_introspection.then(|| {
+        let mut introspect_tool = self.config.introspect_introspection.then(|| {
             Introspect::new(
                 schema.clone(),
                 root_query_type,
@@ -99,42 +179,111 @@ impl Starting {
                 self.config.introspect_minify,
             )
         });
-        let validate_tool = self
+        let mut validate_tool = self
             .config
             .validate_introspection
-            .then(|| Validate::new(schema.clone()));
-        let search_tool = if self.config.search_introspection {
+            .then(|| Validate::new(schema.clone(), self.config.mutation_mode));
+        let mut search_tool = if self.config.search_introspection {
+            let mut root_types = EnumSet::from(IndexOperationType::Query);
+            if matches!(self.config.mutation_mode, MutationMode::All) {
+                root_types |= IndexOperationType::Mutation;
+            }
+            if self.config.search_index_subscriptions {
+                root_types |= IndexOperationType::Subscription;
+            }
             Some(Search::new(
                 schema.clone(),
-                matches!(self.config.mutation_mode, MutationMode::All),
-                self.config.search_leaf_depth,
-                self.config.index_memory_bytes,
-                self.config.search_minify,
+                root_types,
+                IndexOptions {
+                    index_memory_bytes: self.config.index_memory_bytes,
+                    max_description_chars: self.config.search_max_description_chars,
+                    index_directives: self.config.search_index_directives,
+                    index_argument_names: self.config.search_index_argument_names,
+                    // Search results are displayed from the schema, not the stored index text
+                    store_display_fields: false,
+                    ..Default::default()
+                },
+                SearchOptions {
+                    leaf_depth: self.config.search_leaf_depth,
+                    minify: self.config.search_minify,
+                    format: self.config.search_format,
+                    collapse_redundant_paths: self.config.search_collapse_redundant_paths,
+                    broaden_empty_results: self.config.search_broaden_empty_results,
+                    max_visited_paths_per_type: self.config.search_max_visited_paths_per_type,
+                },
             )?)
         } else {
             None
         };
 
-        let explorer_tool = self.config.explorer_graph_ref.map(Explorer::new);
+        let mut list_operations_tool = self
+            .config
+            .list_operations_introspection
+            .then(ListOperations::new);
+        let mut explorer_tool = self.config.explorer_graph_ref.map(Explorer::new);
+
+        // Namespace the built-in tools so they never clash with operation tools
+        if let Some(prefix) = &self.config.builtin_tool_prefix {
+            for tool in [
+                execute_tool.as_mut().map(|tool| &mut tool.tool),
+                introspect_tool.as_mut().map(|tool| &mut tool.tool),
+                search_tool.as_mut().map(|tool| &mut tool.tool),
+                explorer_tool.as_mut().map(|tool| &mut tool.tool),
+                validate_tool.as_mut().map(|tool| &mut tool.tool),
+                list_operations_tool.as_mut().map(|tool| &mut tool.tool),
+            ]
+            .into_iter()
+            .flatten()
+            {
+                tool.name = format!("{prefix}{}", tool.name).into();
+            }
+        }
 
         let cancellation_token = CancellationToken::new();
 
         // Create health check if enabled (only for StreamableHttp transport)
         let health_check = match (&self.config.transport, self.config.health_check.enabled) {
-            (
-                Transport::StreamableHttp {
-                    auth: _,
-                    address: _,
-                    port: _,
-                },
-                true,
-            ) => Some(HealthCheck::new(self.config.health_check.clone())),
+            (Transport::StreamableHttp { .. }, true) => Some(
+                HealthCheck::new(self.config.health_check.clone())
+                    .with_readiness_checks(self.config.readiness_checks.clone()),
+            ),
             _ => None, // No health check for SSE, Stdio, or when disabled
         };
 
+        let meter = self
+            .config
+            .meter
+            .enabled
+            .then(|| Meter::new(self.config.meter.clone()));
+        let circuit_breaker = self
+            .config
+            .circuit_breaker
+            .enabled
+            .then(|| CircuitBreaker::new(self.config.circuit_breaker.clone()));
+        let progress = self
+            .config
+            .progress
+            .enabled
+            .then(|| self.config.progress.clone());
+        let result_cache = self
+            .config
+            .result_cache
+            .enabled
+            .then(|| ResultCache::new(self.config.result_cache.clone()));
+        let rest_tools = self
+            .config
+            .rest_tools
+            .into_iter()
+            .map(RestTool::new)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Each operation is sent the same headers over the websocket as over HTTP
+        let websocket = self.config.websocket_endpoint.map(WebSocketConnection::new);
+
         let running = Running {
             schema,
             operations: Arc::new(Mutex::new(operations)),
+            operation_cache,
             headers: self.config.headers,
             endpoint: self.config.endpoint,
             execute_tool,
@@ -142,13 +291,54 @@ impl Starting {
             search_tool,
             explorer_tool,
             validate_tool,
+            list_operations_tool,
             custom_scalar_map: self.config.custom_scalar_map,
             peers,
             cancellation_token: cancellation_token.clone(),
-            mutation_mode: self.config.mutation_mode,
-            disable_type_description: self.config.disable_type_description,
-            disable_schema_description: self.config.disable_schema_description,
+            tool_options,
             health_check: health_check.clone(),
+            meter: meter.clone(),
+            circuit_breaker,
+            progress,
+            result_cache,
+            rest_tools,
+            tool_concurrency: ToolConcurrency::new(&self.config.tool_concurrency),
+            operation_timeout: self.config.operation_timeout,
+            graphql_errors: self.config.graphql_errors,
+            strip_nulls: self.config.strip_nulls,
+            report_coercions: self.config.report_coercions,
+            websocket,
+            error_codes: self.config.error_codes,
+            sanitize_errors: self.config.sanitize_errors,
+            signing: self.config.signing,
+            tool_namespace: self.config.tool_namespace,
+            tools_page_size: self.config.tools_page_size,
+            disabled_tools: DisabledTools::default(),
+            feature_flags: FeatureFlags::new(self.config.feature_flags),
+            middlewares: self.config.middlewares,
+            transport: self.config.transport.name(),
+        };
+        if let Some(loader) = self.config.feature_flags_loader.clone() {
+            running.reload_feature_flags_on_hangup(loader);
+        }
+        running.warn_about_shadowed_operations(&running.operations.lock().await);
+
+        // Expose the admin endpoint if configured (HTTP transports only), requiring auth
+        let admin_router = match (&self.config.transport, self.config.admin.enabled) {
+            (_, false) => None,
+            (Transport::Stdio, true) => {
+                warn!("The admin endpoint is not available in stdio mode");
+                None
+            }
+            (
+                Transport::StreamableHttp { auth: None, .. } | Transport::SSE { auth: None, .. },
+                true,
+            ) => return Err(ServerError::AdminWithoutAuth),
+            (_, true) => Some(
+                self.config
+                    .admin
+                    .protect(running.admin_router(&self.config.admin.path)),
+            ),
         };
 
         // Helper to enable auth
@@ -156,6 +346,10 @@ impl Starting {
             ($router:expr, $auth:ident) => {{
                 let mut router = $router;
                 if let Some(auth) = $auth {
+                    let auth = ReloadableConfig::new(auth);
+                    if let Some(loader) = self.config.auth_loader.clone() {
+                        auth.reload_on_hangup(loader);
+                    }
                     router = auth.enable_middleware(router);
                 }
 
@@ -167,24 +361,43 @@ impl Starting {
                 auth,
                 address,
                 port,
+                path,
+                max_request_body_bytes,
             } => {
-                info!(port = ?port, address = ?address, "Starting MCP server in Streamable HTTP mode");
-                let running = running.clone();
+                info!(port = ?port, address = ?address, path, "Starting MCP server in Streamable HTTP mode");
                 let listen_address = SocketAddr::new(address, port);
-                let service = StreamableHttpService::new(
-                    move || Ok(running.clone()),
-                    LocalSessionManager::default().into(),
-                    Default::default(),
-                );
+                let mut router = streamable_http_router(running.clone(), &path);
+                if let Some(admin_router) = admin_router {
+                    router = router.merge(admin_router);
+                }
                 let mut router =
-                    with_auth!(axum::Router::new().nest_service("/mcp", service), auth);
+                    limit_request_bodies(with_auth!(router, auth), max_request_body_bytes);
 
                 // Add health check endpoint if configured
+                let mut auxiliary_router = Router::new();
                 if let Some(health_check) = health_check.filter(|h| h.config().enabled) {
                     let health_router = Router::new()
                         .route(&health_check.config().path, get(health_endpoint))
                         .with_state(health_check.clone());
-                    router = router.merge(health_router);
+                    auxiliary_router = auxiliary_router.merge(health_router);
+                }
+
+                // Add metrics endpoint if configured
+                if let Some(meter) = meter {
+                    let metrics_router = Router::new()
+                        .route(&meter.config().path, get(metrics_endpoint))
+                        .with_state(meter.clone());
+                    auxiliary_router = auxiliary_router.merge(metrics_router);
+                }
+
+                // Serve the health check and metrics endpoints on their own port if configured,
+                // and alongside the MCP transport otherwise
+                if auxiliary_router.has_routes() {
+                    let auxiliary = &self.config.auxiliary;
+                    let auxiliary_router = auxiliary.protect(auxiliary_router);
+                    if let Some(auxiliary_router) = auxiliary.serve(auxiliary_router).await? {
+                        router = router.merge(auxiliary_router);
+                    }
                 }
 
                 let tcp_listener = tokio::net::TcpListener::bind(listen_address).await?;
@@ -203,21 +416,32 @@ impl Starting {
                 auth,
                 address,
                 port,
+                base_path,
+                max_request_body_bytes,
             } => {
-                info!(port = ?port, address = ?address, "Starting MCP server in SSE mode");
+                info!(port = ?port, address = ?address, base_path, "Starting MCP server in SSE mode");
                 let running = running.clone();
                 let listen_address = SocketAddr::new(address, port);
 
+                let base_path = match base_path.trim_matches('/') {
+                    "" => String::new(),
+                    base_path => format!("/{base_path}"),
+                };
                 let (server, router) = SseServer::new(SseServerConfig {
                     bind: listen_address,
-                    sse_path: "/sse".to_string(),
-                    post_path: "/message".to_string(),
+                    sse_path: format!("{base_path}/sse"),
+                    post_path: format!("{base_path}/message"),
                     ct: cancellation_token,
                     sse_keep_alive: None,
                 });
 
+                let router = match admin_router {
+                    Some(admin_router) => router.merge(admin_router),
+                    None => router,
+                };
+
                 // Optionally wrap the router with auth, if enabled
-                let router = with_auth!(router, auth);
+                let router = limit_request_bodies(with_auth!(router, auth), max_request_body_bytes);
 
                 // Start up the SSE server
                 // Note: Until RMCP consolidates SSE with the same tower system as StreamableHTTP,
@@ -256,15 +480,199 @@ impl Starting {
     }
 }
 
+/// Serve the streamable HTTP transport for a handler at a path
+fn streamable_http_router<H>(handler: H, path: &str) -> Router
+where
+    H: ServerHandler + Clone,
+{
+    let service = StreamableHttpService::new(
+        move || Ok(handler.clone()),
+        LocalSessionManager::default().into(),
+        Default::default(),
+    );
+    match path.trim_matches('/') {
+        "" => Router::new().fallback_service(service),
+        path => Router::new().nest_service(&format!("/{path}"), service),
+    }
+}
+
+/// Reject requests with bodies larger than the limit, before reading them
+fn limit_request_bodies(router: Router, max_request_body_bytes: usize) -> Router {
+    router.layer(RequestBodyLimitLayer::new(max_request_body_bytes))
+}
+
 /// Health check endpoint handler
 async fn health_endpoint(
     axum::extract::State(health_check): axum::extract::State<HealthCheck>,
     Query(params): Query<std::collections::HashMap<String, String>>,
 ) -> Result<(StatusCode, Json<serde_json::Value>), StatusCode> {
     let query = params.keys().next().map(|k| k.as_str());
-    let (health, status_code) = health_check.get_health_state(query);
+    let (health, status_code) = health_check.get_health_state(query).await;
 
     trace!(?health, query = ?query, "health check");
 
     Ok((status_code, Json(json!(health))))
 }
+
+/// Metrics endpoint handler
+async fn metrics_endpoint(
+    axum::extract::State(meter): axum::extract::State<Meter>,
+) -> Json<serde_json::Value> {
+    Json(json!({ "tool_latency": meter.latencies().await }))
+}
+
+#[cfg(test)]
+mod tests {
+    use apollo_mcp_registry::uplink::schema::SchemaSource;
+    use axum::routing::post;
+
+    use super::*;
+    use crate::operations::OperationSource;
+    use crate::server::test::{free_port, server};
+
+    #[tokio::test]
+    async fn request_bodies_over_the_limit_are_rejected() {
+        let router = limit_request_bodies(
+            Router::new().route("/mcp", post(|body: String| async move { body })),
+            16,
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = reqwest::Client::new();
+        let oversized = client
+            .post(&url)
+            .body("x".repeat(100))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(oversized.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+        let small = client.post(&url).body("{}").send().await.unwrap();
+        assert_eq!(small.status(), reqwest::StatusCode::OK);
+        assert_eq!(small.text().await.unwrap(), "{}");
+    }
+
+    #[tokio::test]
+    async fn only_http_transports_enforce_auth() {
+        let auth = crate::auth::Config {
+            servers: vec!["https://auth.example.com".parse().unwrap()],
+            audiences: vec!["mcp".to_string()],
+            resource: "https://mcp.example.com".parse().unwrap(),
+            resource_documentation: None,
+            scopes: vec![],
+        };
+        assert!(Transport::Stdio.auth().is_none());
+
+        let streamable_http_port = free_port();
+        let sse_port = free_port();
+        let transports = [
+            (
+                Transport::StreamableHttp {
+                    auth: Some(auth.clone()),
+                    address: "127.0.0.1".parse().unwrap(),
+                    port: streamable_http_port,
+                    path: "/mcp".to_string(),
+                    max_request_body_bytes: 1024,
+                },
+                reqwest::Method::POST,
+                format!("http://127.0.0.1:{streamable_http_port}/mcp"),
+            ),
+            (
+                Transport::SSE {
+                    auth: Some(auth),
+                    address: "127.0.0.1".parse().unwrap(),
+                    port: sse_port,
+                    base_path: "/".to_string(),
+                    max_request_body_bytes: 1024,
+                },
+                reqwest::Method::GET,
+                format!("http://127.0.0.1:{sse_port}/sse"),
+            ),
+        ];
+
+        for (transport, method, url) in transports {
+            let schema_source = SchemaSource::Static {
+                schema_sdl: "type Query { id: ID }".to_string(),
+            };
+            tokio::spawn(server(transport, schema_source, OperationSource::None).start());
+
+            let mut response = None;
+            for _ in 0..100 {
+                if let Ok(sent) = reqwest::Client::new()
+                    .request(method.clone(), &url)
+                    .send()
+                    .await
+                {
+                    response = Some(sent);
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+            }
+            let response = response.expect("the server should start");
+            assert_eq!(
+                response.status(),
+                reqwest::StatusCode::UNAUTHORIZED,
+                "{url}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_endpoint_requires_auth() {
+        let transport = Transport::StreamableHttp {
+            auth: None,
+            address: "127.0.0.1".parse().unwrap(),
+            port: free_port(),
+            path: "/mcp".to_string(),
+            max_request_body_bytes: 1024,
+        };
+        let schema_source = SchemaSource::Static {
+            schema_sdl: "type Query { id: ID }".to_string(),
+        };
+        let mut server = server(transport, schema_source, OperationSource::None);
+        server.admin.enabled = true;
+
+        assert!(matches!(
+            server.start().await,
+            Err(ServerError::AdminWithoutAuth)
+        ));
+    }
+
+    #[tokio::test]
+    async fn streamable_http_is_served_at_the_configured_path() {
+        #[derive(Clone)]
+        struct Handler;
+        impl ServerHandler for Handler {}
+
+        let router = streamable_http_router(Handler, "/mcp/v1/");
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+
+        let client = reqwest::Client::new();
+        let initialize = |path: &str| {
+            client
+                .post(format!("http://{address}{path}"))
+                .header("Accept", "application/json, text/event-stream")
+                .json(&json!({
+                    "jsonrpc": "2.0",
+                    "id": 1,
+                    "method": "initialize",
+                    "params": {
+                        "protocolVersion": "2025-03-26",
+                        "capabilities": {},
+                        "clientInfo": { "name": "test", "version": "1.0" }
+                    }
+                }))
+                .send()
+        };
+
+        let response = initialize("/mcp/v1").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::OK);
+
+        let response = initialize("/mcp").await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,89 @@
+//! Compute which tools changed when the schema or operations are reloaded
+
+use std::collections::HashMap;
+
+use rmcp::model::Tool;
+use serde_json::Value;
+
+/// The tools added, removed, or modified between two tool sets
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct ToolChanges {
+    pub(super) added: Vec<String>,
+    pub(super) removed: Vec<String>,
+    pub(super) modified: Vec<String>,
+}
+
+impl ToolChanges {
+    /// Compare two tool sets by name, treating a tool as modified if its serialized form differs
+    pub(super) fn between(old: &[Tool], new: &[Tool]) -> Self {
+        let old = serialized(old);
+        let new = serialized(new);
+
+        let mut changes = Self::default();
+        for (name, tool) in &new {
+            match old.get(name) {
+                None => changes.added.push(name.clone()),
+                Some(old_tool) if old_tool != tool => changes.modified.push(name.clone()),
+                Some(_) => {}
+            }
+        }
+        changes.removed = old
+            .keys()
+            .filter(|name| !new.contains_key(*name))
+            .cloned()
+            .collect();
+
+        changes.added.sort();
+        changes.removed.sort();
+        changes.modified.sort();
+        changes
+    }
+
+    pub(super) fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+fn serialized(tools: &[Tool]) -> HashMap<String, Value> {
+    tools
+        .iter()
+        .map(|tool| {
+            (
+                tool.name.to_string(),
+                serde_json::to_value(tool).unwrap_or(Value::Null),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+
+    fn tool(name: &'static str, description: &'static str) -> Tool {
+        Tool::new(
+            name,
+            description,
+            Arc::new(json!({}).as_object().unwrap().clone()),
+        )
+    }
+
+    #[test]
+    fn added_removed_and_modified_tools() {
+        let old = [tool("A", "a"), tool("B", "b"), tool("C", "c")];
+        let new = [tool("A", "a"), tool("B", "b2"), tool("D", "d")];
+        assert_eq!(
+            ToolChanges::between(&old, &new),
+            ToolChanges {
+                added: vec!["D".to_string()],
+                removed: vec!["C".to_string()],
+                modified: vec!["B".to_string()],
+            }
+        );
+        assert!(ToolChanges::between(&old, &old).is_empty());
+    }
+}
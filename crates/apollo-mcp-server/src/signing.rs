@@ -0,0 +1,171 @@
+//! Signing module for Apollo MCP Server
+//!
+//! Signs every request sent to the GraphQL endpoint with an HMAC computed over a timestamp and the
+//! request body, for endpoints which require signed requests.
+
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rmcp::model::ErrorCode;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Sha256, Sha512};
+
+use crate::errors::McpError;
+
+/// The hash algorithm used to compute request signatures
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningAlgorithm {
+    /// HMAC with SHA-256
+    #[default]
+    HmacSha256,
+
+    /// HMAC with SHA-512
+    HmacSha512,
+}
+
+/// Configuration options for signing requests to the GraphQL endpoint.
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SigningConfig {
+    /// The secret used to compute request signatures
+    pub secret: String,
+
+    /// The hash algorithm used to compute request signatures
+    #[serde(default)]
+    pub algorithm: SigningAlgorithm,
+
+    /// The header to send the hex-encoded signature in
+    #[serde(default = "default_signature_header")]
+    pub header: String,
+
+    /// The header to send the signed Unix timestamp (in seconds) in
+    #[serde(default = "default_timestamp_header")]
+    pub timestamp_header: String,
+}
+
+fn default_signature_header() -> String {
+    "x-signature".to_string()
+}
+
+fn default_timestamp_header() -> String {
+    "x-signature-timestamp".to_string()
+}
+
+// The secret is redacted so that it never appears in logs
+impl fmt::Debug for SigningConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SigningConfig")
+            .field("secret", &"[REDACTED]")
+            .field("algorithm", &self.algorithm)
+            .field("header", &self.header)
+            .field("timestamp_header", &self.timestamp_header)
+            .finish()
+    }
+}
+
+impl SigningConfig {
+    /// The names of the headers added to signed requests
+    pub fn header_names(&self) -> [&str; 2] {
+        [&self.header, &self.timestamp_header]
+    }
+
+    /// Add the signature and timestamp headers for a request body
+    pub fn sign(&self, body: &str, headers: &mut HeaderMap) -> Result<(), McpError> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.sign_at(body, timestamp, headers)
+    }
+
+    fn sign_at(&self, body: &str, timestamp: u64, headers: &mut HeaderMap) -> Result<(), McpError> {
+        let message = format!("{timestamp}.{body}");
+        let signature = match self.algorithm {
+            SigningAlgorithm::HmacSha256 => hmac::<Hmac<Sha256>>(&self.secret, &message)?,
+            SigningAlgorithm::HmacSha512 => hmac::<Hmac<Sha512>>(&self.secret, &message)?,
+        };
+        headers.insert(
+            header_name(&self.timestamp_header)?,
+            HeaderValue::from(timestamp),
+        );
+        headers.insert(
+            header_name(&self.header)?,
+            HeaderValue::from_str(&signature).map_err(|_| signing_error("invalid signature"))?,
+        );
+        Ok(())
+    }
+}
+
+/// Compute the hex-encoded HMAC of a message
+fn hmac<M: Mac + KeyInit>(secret: &str, message: &str) -> Result<String, McpError> {
+    let mut mac = <M as KeyInit>::new_from_slice(secret.as_bytes())
+        .map_err(|_| signing_error("invalid secret"))?;
+    mac.update(message.as_bytes());
+    Ok(mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+fn header_name(name: &str) -> Result<HeaderName, McpError> {
+    HeaderName::try_from(name).map_err(|_| signing_error(&format!("invalid header name {name}")))
+}
+
+fn signing_error(reason: &str) -> McpError {
+    McpError::new(
+        ErrorCode::INTERNAL_ERROR,
+        format!("Failed to sign GraphQL request: {reason}"),
+        None,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = r#"{"query":"{ id }"}"#;
+
+    fn config(algorithm: SigningAlgorithm) -> SigningConfig {
+        SigningConfig {
+            secret: "secret".to_string(),
+            algorithm,
+            header: default_signature_header(),
+            timestamp_header: default_timestamp_header(),
+        }
+    }
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        let mut headers = HeaderMap::new();
+        config(SigningAlgorithm::HmacSha256)
+            .sign_at(BODY, 1_700_000_000, &mut headers)
+            .unwrap();
+        assert_eq!(
+            headers.get("x-signature").unwrap(),
+            "305fd73cf655426e37de6d8fa39569c2941baf203a715556f7631389158a4fb3"
+        );
+        assert_eq!(headers.get("x-signature-timestamp").unwrap(), "1700000000");
+
+        config(SigningAlgorithm::HmacSha512)
+            .sign_at(BODY, 1_700_000_000, &mut headers)
+            .unwrap();
+        assert_eq!(
+            headers.get("x-signature").unwrap(),
+            "e537ffc767a5697a0868444d320955316f77ab4ad402151870857b052b05faacaab78730e507b56b3ba8157c0398125d191b1eef9adca9f728e1c1ceecf9a866"
+        );
+    }
+
+    #[test]
+    fn secret_is_redacted() {
+        let debug = format!("{:?}", config(SigningAlgorithm::HmacSha256));
+        assert!(!debug.contains("\"secret\""));
+        assert!(debug.contains("[REDACTED]"));
+    }
+}
@@ -0,0 +1,227 @@
+//! Annotate the fields of federated schemas with the subgraphs which resolve them
+//!
+//! The API schema of a supergraph drops the `@join__*` directives recording which subgraph owns
+//! each field. To surface that provenance in search results and tool descriptions, the fields are
+//! annotated with a `@subgraph(name: "...")` directive instead.
+
+use std::collections::HashMap;
+
+use apollo_compiler::ast::{
+    Argument, Directive, DirectiveDefinition, DirectiveList, DirectiveLocation,
+    InputValueDefinition, Value,
+};
+use apollo_compiler::schema::{Component, ExtendedType};
+use apollo_compiler::validation::Valid;
+use apollo_compiler::{Name, Node, Schema, name, ty};
+use tracing::warn;
+
+/// The name of the directive annotating a field with a subgraph which resolves it
+const SUBGRAPH_DIRECTIVE_NAME: Name = name!("subgraph");
+
+/// The name of the enum listing the subgraphs of a supergraph
+const JOIN_GRAPH_ENUM_NAME: &str = "join__Graph";
+
+/// Annotate the fields of an API schema with the subgraphs resolving them in its supergraph.
+/// Fields without a `@join__field` directive are resolved by every subgraph defining their type.
+pub(crate) fn annotate_subgraphs(supergraph: &Schema, api_schema: Valid<Schema>) -> Valid<Schema> {
+    if api_schema
+        .directive_definitions
+        .contains_key(&SUBGRAPH_DIRECTIVE_NAME)
+    {
+        warn!("Not annotating fields with subgraphs, as the schema already defines @subgraph");
+        return api_schema;
+    }
+    let graph_names = graph_names(supergraph);
+    let mut schema = api_schema.clone().into_inner();
+    for (type_name, extended_type) in schema.types.iter_mut() {
+        let Some(supergraph_type) = supergraph.types.get(type_name) else {
+            continue;
+        };
+        let fields = match extended_type {
+            ExtendedType::Object(object) => &mut object.make_mut().fields,
+            ExtendedType::Interface(interface) => &mut interface.make_mut().fields,
+            _ => continue,
+        };
+        let type_graphs: Vec<&Name> = supergraph_type
+            .directives()
+            .get_all("join__type")
+            .filter_map(|directive| graph_argument(directive))
+            .collect();
+        for (field_name, field) in fields.iter_mut() {
+            let Some(supergraph_field) = supergraph_fields(supergraph_type, field_name) else {
+                continue;
+            };
+            let join_fields: Vec<_> = supergraph_field.get_all("join__field").collect();
+            let graphs: Vec<&Name> = if join_fields.is_empty() {
+                type_graphs.clone()
+            } else {
+                join_fields
+                    .into_iter()
+                    .filter(|directive| {
+                        directive
+                            .specified_argument_by_name("external")
+                            .and_then(|external| external.to_bool())
+                            != Some(true)
+                    })
+                    .filter_map(|directive| graph_argument(directive))
+                    .collect()
+            };
+            let directives = &mut field.make_mut().directives;
+            for graph in graphs {
+                let name = graph_names
+                    .get(graph)
+                    .cloned()
+                    .unwrap_or_else(|| graph.to_lowercase());
+                directives.push(Node::new(Directive {
+                    name: SUBGRAPH_DIRECTIVE_NAME,
+                    arguments: vec![Node::new(Argument {
+                        name: name!("name"),
+                        value: Node::new(Value::String(name)),
+                    })],
+                }));
+            }
+        }
+    }
+    schema.directive_definitions.insert(
+        SUBGRAPH_DIRECTIVE_NAME,
+        Node::new(DirectiveDefinition {
+            description: Some("A subgraph resolving this field".into()),
+            name: SUBGRAPH_DIRECTIVE_NAME,
+            arguments: vec![Node::new(InputValueDefinition {
+                description: None,
+                name: name!("name"),
+                ty: ty!(String!).into(),
+                default_value: None,
+                directives: DirectiveList::default(),
+            })],
+            repeatable: true,
+            locations: vec![DirectiveLocation::FieldDefinition],
+        }),
+    );
+    schema.validate().unwrap_or_else(|errors| {
+        warn!("Not annotating fields with subgraphs: {}", errors.errors);
+        api_schema
+    })
+}
+
+/// The names of the subgraphs of a supergraph, by their `join__Graph` enum value
+fn graph_names(supergraph: &Schema) -> HashMap<Name, String> {
+    let Some(ExtendedType::Enum(graphs)) = supergraph.types.get(JOIN_GRAPH_ENUM_NAME) else {
+        return HashMap::new();
+    };
+    graphs
+        .values
+        .iter()
+        .filter_map(|(value, definition)| {
+            let name = definition
+                .directives
+                .get("join__graph")?
+                .specified_argument_by_name("name")?
+                .as_str()?;
+            Some((value.clone(), name.to_string()))
+        })
+        .collect()
+}
+
+/// The directives of a field of a supergraph type
+fn supergraph_fields<'a>(
+    supergraph_type: &'a ExtendedType,
+    field_name: &Name,
+) -> Option<&'a DirectiveList> {
+    match supergraph_type {
+        ExtendedType::Object(object) => object.fields.get(field_name),
+        ExtendedType::Interface(interface) => interface.fields.get(field_name),
+        _ => None,
+    }
+    .map(|field: &Component<_>| &field.directives)
+}
+
+/// The `join__Graph` enum value of a `graph` directive argument
+fn graph_argument(directive: &Directive) -> Option<&Name> {
+    directive.specified_argument_by_name("graph")?.as_enum()
+}
+
+#[cfg(test)]
+mod tests {
+    use apollo_federation::Supergraph;
+
+    use super::*;
+
+    const SUPERGRAPH: &str = r#"
+        schema
+          @link(url: "https://specs.apollo.dev/link/v1.0")
+          @link(url: "https://specs.apollo.dev/join/v0.5", for: EXECUTION)
+        {
+          query: Query
+        }
+
+        directive @join__enumValue(graph: join__Graph!) repeatable on ENUM_VALUE
+        directive @join__field(graph: join__Graph, requires: join__FieldSet, provides: join__FieldSet, type: String, external: Boolean, override: String, usedOverridden: Boolean, overrideLabel: String, contextArguments: [join__ContextArgument!]) repeatable on FIELD_DEFINITION | INPUT_FIELD_DEFINITION
+        directive @join__graph(name: String!, url: String!) on ENUM_VALUE
+        directive @join__implements(graph: join__Graph!, interface: String!) repeatable on OBJECT | INTERFACE
+        directive @join__type(graph: join__Graph!, key: join__FieldSet, extension: Boolean! = false, resolvable: Boolean! = true, isInterfaceObject: Boolean! = false) repeatable on OBJECT | INTERFACE | UNION | ENUM | INPUT_OBJECT | SCALAR
+        directive @join__unionMember(graph: join__Graph!, member: String!) repeatable on UNION
+        directive @link(url: String, as: String, for: link__Purpose, import: [link__Import]) repeatable on SCHEMA
+
+        input join__ContextArgument {
+          name: String!
+          type: String!
+          context: String!
+          selection: join__FieldValue!
+        }
+        scalar join__DirectiveArguments
+        scalar join__FieldSet
+        scalar join__FieldValue
+        enum join__Graph {
+          PRODUCTS @join__graph(name: "products", url: "http://products")
+          REVIEWS @join__graph(name: "reviews", url: "http://reviews")
+        }
+        scalar link__Import
+        enum link__Purpose {
+          SECURITY
+          EXECUTION
+        }
+
+        type Product
+          @join__type(graph: PRODUCTS, key: "id")
+          @join__type(graph: REVIEWS, key: "id")
+        {
+          id: ID!
+          name: String @join__field(graph: PRODUCTS)
+          reviews: [Review] @join__field(graph: REVIEWS)
+        }
+
+        type Query
+          @join__type(graph: PRODUCTS)
+          @join__type(graph: REVIEWS)
+        {
+          product(id: ID!): Product @join__field(graph: PRODUCTS)
+        }
+
+        type Review
+          @join__type(graph: REVIEWS)
+        {
+          body: String
+        }
+    "#;
+
+    #[test]
+    fn annotates_fields_with_their_subgraphs() {
+        let supergraph = Supergraph::new(SUPERGRAPH).unwrap();
+        let api_schema = supergraph
+            .to_api_schema(Default::default())
+            .unwrap()
+            .schema()
+            .clone();
+        let schema = annotate_subgraphs(supergraph.schema.schema(), api_schema);
+
+        let product = schema.types.get("Product").unwrap().serialize().to_string();
+        assert!(product.contains(r#"name: String @subgraph(name: "products")"#));
+        assert!(product.contains(r#"reviews: [Review] @subgraph(name: "reviews")"#));
+        assert!(
+            product.contains(r#"id: ID! @subgraph(name: "products") @subgraph(name: "reviews")"#)
+        );
+        let review = schema.types.get("Review").unwrap().serialize().to_string();
+        assert!(review.contains(r#"body: String @subgraph(name: "reviews")"#));
+    }
+}
@@ -0,0 +1,205 @@
+//! Telemetry module for Apollo MCP Server
+//!
+//! Exports the server's tracing spans, such as those of tool calls and of the requests they make
+//! to the GraphQL endpoint, to an OpenTelemetry collector over OTLP.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::tonic_types::metadata::MetadataMap;
+use opentelemetry_otlp::{
+    ExporterBuildError, Protocol, SpanExporter, WithExportConfig, WithHttpConfig, WithTonicConfig,
+};
+use opentelemetry_sdk::Resource;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use rmcp::model::CallToolResult;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tracing::{Instrument as _, Subscriber, field};
+use tracing_opentelemetry::OpenTelemetryLayer;
+use tracing_subscriber::registry::LookupSpan;
+use url::Url;
+
+use crate::errors::McpError;
+
+/// The name of the span covering a tool call
+pub const TOOL_CALL_SPAN_NAME: &str = "tool_call";
+
+/// The name of the span covering a request to the GraphQL endpoint
+pub const GRAPHQL_REQUEST_SPAN_NAME: &str = "graphql_request";
+
+/// An error setting up telemetry
+#[derive(Debug, thiserror::Error)]
+pub enum TelemetryError {
+    #[error("Invalid OTLP header: {0}")]
+    InvalidHeader(#[from] http::Error),
+
+    #[error("Failed to build the OTLP exporter: {0}")]
+    Exporter(#[from] ExporterBuildError),
+}
+
+/// Configuration options for telemetry.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    /// The service name reported with exported spans (default: apollo-mcp-server)
+    pub service_name: String,
+
+    /// Export spans to an OpenTelemetry collector over OTLP, if set
+    pub otlp: Option<OtlpConfig>,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            service_name: env!("CARGO_PKG_NAME").to_string(),
+            otlp: None,
+        }
+    }
+}
+
+/// Configuration options for the OTLP exporter.
+#[derive(Clone, Deserialize, Serialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct OtlpConfig {
+    /// The endpoint of the OpenTelemetry collector
+    pub endpoint: Url,
+
+    /// The protocol to export spans with (default: grpc)
+    #[serde(default)]
+    pub protocol: OtlpProtocol,
+
+    /// Headers to send to the collector with each export, such as an API key
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+// The header values, such as API keys, are redacted so that they never appear in logs
+impl fmt::Debug for OtlpConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("OtlpConfig")
+            .field("endpoint", &self.endpoint)
+            .field("protocol", &self.protocol)
+            .field(
+                "headers",
+                &self
+                    .headers
+                    .keys()
+                    .map(|name| (name, "[REDACTED]"))
+                    .collect::<HashMap<_, _>>(),
+            )
+            .finish()
+    }
+}
+
+/// The protocol used to export spans over OTLP
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OtlpProtocol {
+    /// gRPC, usually on port 4317
+    #[default]
+    Grpc,
+
+    /// Protobuf over HTTP, usually on port 4318
+    Http,
+}
+
+impl TelemetryConfig {
+    /// Build a tracer provider exporting spans to the configured collector, if any
+    ///
+    /// For the gRPC protocol, this must be called within a Tokio runtime.
+    pub fn tracer_provider(&self) -> Result<Option<SdkTracerProvider>, TelemetryError> {
+        let Some(otlp) = &self.otlp else {
+            return Ok(None);
+        };
+
+        let exporter = match otlp.protocol {
+            OtlpProtocol::Grpc => SpanExporter::builder()
+                .with_tonic()
+                .with_endpoint(otlp.endpoint.as_str())
+                .with_metadata(MetadataMap::from_headers(http::HeaderMap::try_from(
+                    &otlp.headers,
+                )?))
+                .build()?,
+            OtlpProtocol::Http => SpanExporter::builder()
+                .with_http()
+                .with_protocol(Protocol::HttpBinary)
+                .with_endpoint(otlp.endpoint.as_str())
+                .with_headers(otlp.headers.clone())
+                .build()?,
+        };
+
+        Ok(Some(
+            SdkTracerProvider::builder()
+                .with_resource(
+                    Resource::builder()
+                        .with_service_name(self.service_name.clone())
+                        .build(),
+                )
+                .with_batch_exporter(exporter)
+                .build(),
+        ))
+    }
+}
+
+/// A tracing layer sending spans to a tracer provider
+pub fn layer<S>(
+    provider: &SdkTracerProvider,
+) -> OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>
+where
+    S: Subscriber + for<'span> LookupSpan<'span>,
+{
+    tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+}
+
+/// Run a tool call in a span, recording whether it failed
+pub(crate) async fn trace_tool_call(
+    tool: &str,
+    call: impl Future<Output = Result<CallToolResult, McpError>>,
+) -> Result<CallToolResult, McpError> {
+    let span = tracing::info_span!(
+        TOOL_CALL_SPAN_NAME,
+        "mcp.tool.name" = tool,
+        "mcp.tool.is_error" = field::Empty
+    );
+    let result = call.instrument(span.clone()).await;
+    span.record(
+        "mcp.tool.is_error",
+        !matches!(&result, Ok(result) if result.is_error != Some(true)),
+    );
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_tracer_provider_without_otlp() {
+        assert!(
+            TelemetryConfig::default()
+                .tracer_provider()
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn invalid_headers_are_rejected() {
+        let config = TelemetryConfig {
+            otlp: Some(OtlpConfig {
+                endpoint: "http://localhost:4317".parse().unwrap(),
+                protocol: OtlpProtocol::Grpc,
+                headers: HashMap::from([("bad header".to_string(), "value".to_string())]),
+            }),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            config.tracer_provider(),
+            Err(TelemetryError::InvalidHeader(_))
+        ));
+    }
+}
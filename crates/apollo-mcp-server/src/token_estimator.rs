@@ -0,0 +1,83 @@
+//! Estimate the number of tokens a tool definition uses in a model's context window
+
+use std::num::NonZeroUsize;
+
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// The default number of characters per token.
+/// We don't know the tokenization algorithm, so we just use 4 characters per token as a rough
+/// estimate. https://docs.anthropic.com/en/docs/resources/glossary#tokens
+const DEFAULT_CHARS_PER_TOKEN: NonZeroUsize = NonZeroUsize::MIN.saturating_add(3);
+
+/// How to estimate the number of tokens in a piece of text
+#[derive(Clone, Copy, Debug, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenEstimator {
+    /// Divide the number of characters by a fixed number of characters per token
+    CharsPerToken(NonZeroUsize),
+
+    /// Count tokens with the `cl100k_base` tokenizer
+    #[cfg(feature = "tiktoken")]
+    Tiktoken,
+}
+
+impl Default for TokenEstimator {
+    fn default() -> Self {
+        Self::CharsPerToken(DEFAULT_CHARS_PER_TOKEN)
+    }
+}
+
+impl TokenEstimator {
+    /// Estimate the number of tokens in the text
+    pub fn estimate(&self, text: &str) -> usize {
+        match self {
+            Self::CharsPerToken(chars_per_token) => text.len() / chars_per_token.get(),
+            #[cfg(feature = "tiktoken")]
+            Self::Tiktoken => {
+                tiktoken::count(text).unwrap_or_else(|| Self::default().estimate(text))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+mod tiktoken {
+    use std::sync::LazyLock;
+
+    use tiktoken_rs::CoreBPE;
+    use tracing::warn;
+
+    static TOKENIZER: LazyLock<Option<CoreBPE>> = LazyLock::new(|| {
+        tiktoken_rs::cl100k_base()
+            .inspect_err(|e| warn!("Failed to load tokenizer: {e}"))
+            .ok()
+    });
+
+    pub(super) fn count(text: &str) -> Option<usize> {
+        TOKENIZER
+            .as_ref()
+            .map(|tokenizer| tokenizer.encode_with_special_tokens(text).len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_uses_four_characters_per_token() {
+        assert_eq!(TokenEstimator::default().estimate("abcdefghijkl"), 3);
+    }
+
+    #[test]
+    fn configured_characters_per_token() {
+        let estimator: TokenEstimator =
+            serde_json::from_value(serde_json::json!({ "chars_per_token": 3 })).unwrap();
+        assert_eq!(estimator.estimate("abcdefghijkl"), 4);
+        assert!(
+            serde_json::from_value::<TokenEstimator>(serde_json::json!({ "chars_per_token": 0 }))
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,26 @@
+//! Timings and counts describing a single search, for tuning search performance.
+
+use std::time::Duration;
+
+/// How long each phase of a search took, and how much work it did
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchDiagnostics {
+    /// The time taken to run the index query for the matching types
+    pub query_duration: Duration,
+
+    /// The time taken to fetch the documents of the matching types
+    pub fetch_duration: Duration,
+
+    /// The time taken to find the paths from root types to the matching types
+    pub path_duration: Duration,
+
+    /// The number of types matching the search terms
+    pub candidate_count: usize,
+
+    /// The number of paths visited while finding the paths to root types
+    pub visited_paths: usize,
+
+    /// The number of root paths found, before shorter paths are boosted and redundant paths
+    /// collapsed
+    pub root_path_count: usize,
+}
@@ -0,0 +1,170 @@
+//! Searching for the types which changed between two versions of an indexed schema.
+//!
+//! The index of a previous version of the schema is attached to the index of the current version
+//! with [`SchemaIndex::with_previous_version`]. [`SchemaIndex::search_diff`] then searches both
+//! versions, and tags the matching types by how they changed. Types are compared by name and by
+//! their stored field signatures, so changes to fields are only found when display fields are
+//! stored.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Display;
+use std::sync::Arc;
+
+use apollo_compiler::ast::NamedType;
+use itertools::Itertools;
+use tantivy::collector::TopDocs;
+use tantivy::query::TermQuery;
+use tantivy::schema::{IndexRecordOption, Value};
+use tantivy::{Searcher, TantivyDocument, Term};
+
+use crate::error::SearchError;
+use crate::{Options, SchemaIndex, Scored, TYPE_KIND};
+
+/// How a type changed between two versions of a schema
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TypeChange {
+    /// The type is only in the current version
+    Added,
+
+    /// The type is only in the previous version
+    Removed,
+
+    /// The type is in both versions, with different fields
+    Changed,
+}
+
+impl Display for TypeChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TypeChange::Added => "added",
+            TypeChange::Removed => "removed",
+            TypeChange::Changed => "changed",
+        })
+    }
+}
+
+/// A type matching a diff search, and how it changed
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TypeDiff {
+    pub type_name: NamedType,
+    pub change: TypeChange,
+}
+
+impl Display for TypeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.type_name, self.change)
+    }
+}
+
+impl SchemaIndex {
+    /// Attach the index of a previous version of the schema, to search for the types which
+    /// changed since with [`SchemaIndex::search_diff`]
+    pub fn with_previous_version(mut self, previous: SchemaIndex) -> Self {
+        self.previous = Some(Arc::new(previous));
+        self
+    }
+
+    /// Search both versions of the schema for a set of terms, returning the top matching types
+    /// which were added, removed, or changed since the previous version. Types which are the same
+    /// in both versions are left out. Without a previous version, nothing has changed.
+    pub fn search_diff<I>(
+        &self,
+        terms: I,
+        max_matches: usize,
+    ) -> Result<Vec<Scored<TypeDiff>>, SearchError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let Some(previous) = &self.previous else {
+            return Ok(Vec::new());
+        };
+        let terms: Vec<String> = terms.into_iter().collect();
+        let current_searcher = self.inner.reader()?.searcher();
+        let previous_searcher = previous.inner.reader()?.searcher();
+
+        // The best score of each type matching in either version
+        let mut scores: HashMap<String, f32> = HashMap::new();
+        for (index, searcher) in [(self, &current_searcher), (&**previous, &previous_searcher)] {
+            for (type_name, score) in index.matching_types(searcher, terms.iter().cloned())? {
+                let best = scores.entry(type_name).or_insert(score);
+                *best = best.max(score);
+            }
+        }
+
+        let mut diffs = Vec::new();
+        for (type_name, score) in scores {
+            let change = match (
+                self.type_fields(&current_searcher, &type_name)?,
+                previous.type_fields(&previous_searcher, &type_name)?,
+            ) {
+                (Some(_), None) => TypeChange::Added,
+                (None, Some(_)) => TypeChange::Removed,
+                (Some(current), Some(previous)) if current != previous => TypeChange::Changed,
+                _ => continue,
+            };
+            diffs.push(Scored::new(
+                TypeDiff {
+                    type_name: NamedType::new_unchecked(&type_name),
+                    change,
+                },
+                score,
+            ));
+        }
+        Ok(diffs
+            .into_iter()
+            .sorted_by(|a, b| {
+                b.score()
+                    .total_cmp(&a.score())
+                    .then_with(|| a.inner.type_name.cmp(&b.inner.type_name))
+            })
+            .take(max_matches)
+            .collect())
+    }
+
+    /// The names and scores of the types matching a set of terms
+    fn matching_types<I>(
+        &self,
+        searcher: &Searcher,
+        terms: I,
+    ) -> Result<Vec<(String, f32)>, SearchError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let query = self.query(terms, TYPE_KIND, &Options::default());
+        let mut matches = Vec::new();
+        for (score, address) in searcher.search(&query, &TopDocs::with_limit(100))? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            if let Some(type_name) = doc
+                .get_first(self.raw_type_name_field)
+                .and_then(|value| value.as_str())
+            {
+                matches.push((type_name.to_string(), score));
+            }
+        }
+        Ok(matches)
+    }
+
+    /// The stored field signatures of a type, if it is in the index
+    fn type_fields(
+        &self,
+        searcher: &Searcher,
+        type_name: &str,
+    ) -> Result<Option<String>, SearchError> {
+        let term = Term::from_field_text(self.raw_type_name_field, type_name);
+        let type_search = searcher.search(
+            &TermQuery::new(term, IndexRecordOption::Basic),
+            &TopDocs::with_limit(1),
+        )?;
+        let Some((_, address)) = type_search.first() else {
+            return Ok(None);
+        };
+        let doc: TantivyDocument = searcher.doc(*address)?;
+        Ok(Some(
+            doc.get_first(self.fields_field)
+                .and_then(|value| value.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        ))
+    }
+}
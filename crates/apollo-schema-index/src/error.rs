@@ -5,6 +5,37 @@ use tantivy::TantivyError;
 pub enum IndexingError {
     #[error("Unable to index schema: {0}")]
     TantivyError(#[from] TantivyError),
+
+    #[error("Unable to read or write index snapshot: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid index snapshot: {0}")]
+    InvalidSnapshot(String),
+
+    #[error("Index snapshot was built from a different schema")]
+    IncompatibleSnapshot,
+
+    #[error("Schema has more than the maximum of {0} indexed types")]
+    TooManyTypes(usize),
+}
+
+/// A problem indexing a single type, which leaves the type out of the index without failing to
+/// build it
+#[derive(Debug, thiserror::Error)]
+pub enum TypeIndexingError {
+    #[error("Type {type_name} has a field {field} of undefined type {undefined_type}")]
+    UndefinedType {
+        type_name: String,
+        field: String,
+        undefined_type: String,
+    },
+
+    #[error("Unable to index type {type_name}: {source}")]
+    Document {
+        type_name: String,
+        #[source]
+        source: TantivyError,
+    },
 }
 
 /// An error in a search operation
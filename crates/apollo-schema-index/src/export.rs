@@ -0,0 +1,107 @@
+//! Exporting the text indexed for each type, so it can be embedded by an external pipeline.
+//!
+//! The text is read back from the stored fields of the index, so it is only available when
+//! display fields are stored.
+
+use apollo_compiler::ast::NamedType;
+use tantivy::collector::DocSetCollector;
+use tantivy::query::TermQuery;
+use tantivy::schema::{IndexRecordOption, Value};
+use tantivy::{TantivyDocument, Term};
+
+use crate::error::SearchError;
+use crate::{FULL_DESCRIPTION_FIELD, SchemaIndex, TYPE_KIND};
+
+/// The text indexed for a type
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IndexedTypeDoc {
+    /// The name of the type
+    pub type_name: NamedType,
+
+    /// The description of the type, in full even if a truncated description was indexed
+    pub description: String,
+
+    /// The signatures of the fields of the type, or the values of an enum, separated by commas
+    pub fields: String,
+}
+
+impl SchemaIndex {
+    /// Export the text indexed for each type, sorted by type name. The description and fields are
+    /// empty unless the index stores display fields.
+    pub fn export_documents(&self) -> Result<Vec<IndexedTypeDoc>, SearchError> {
+        let searcher = self.inner.reader()?.searcher();
+        let full_description_field = self.inner.schema().get_field(FULL_DESCRIPTION_FIELD).ok();
+        let query = TermQuery::new(
+            Term::from_field_text(self.kind_field, TYPE_KIND),
+            IndexRecordOption::Basic,
+        );
+
+        let mut documents = Vec::new();
+        for address in searcher.search(&query, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let text = |field| {
+                doc.get_first(field)
+                    .and_then(|value| value.as_str())
+                    .map(ToString::to_string)
+            };
+            let Some(type_name) = text(self.raw_type_name_field) else {
+                continue;
+            };
+            documents.push(IndexedTypeDoc {
+                type_name: NamedType::new_unchecked(&type_name),
+                description: full_description_field
+                    .and_then(text)
+                    .or_else(|| text(self.description_field))
+                    .unwrap_or_default(),
+                fields: text(self.fields_field).unwrap_or_default(),
+            });
+        }
+        documents.sort_by(|a, b| a.type_name.cmp(&b.type_name));
+        Ok(documents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use apollo_compiler::Schema;
+
+    use crate::{IndexOptions, OperationType};
+
+    use super::*;
+
+    #[test]
+    fn exported_documents_cover_every_indexed_type() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { user(id: ID!): User }
+            """A person using the app"""
+            type User { id: ID!, name: String, role: Role }
+            enum Role { ADMIN MEMBER }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index = SchemaIndex::new(
+            &schema,
+            OperationType::Query.into(),
+            IndexOptions::default(),
+        )
+        .unwrap();
+
+        let documents = index.export_documents().unwrap();
+        let names: Vec<_> = documents
+            .iter()
+            .map(|document| document.type_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["Query", "Role", "User"]);
+
+        let user = &documents[2];
+        assert_eq!(user.description, "A person using the app");
+        assert!(user.fields.contains("name: String"), "{}", user.fields);
+        assert!(
+            documents[1].fields.contains("ADMIN"),
+            "{}",
+            documents[1].fields
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Re-ranking of search results, such as by feedback on which results were used.
+//!
+//! A [`Rerank`] implementation adjusts the scores of the types matching a search before the paths
+//! to them are scored. [`SelectionFeedback`] boosts the types selected from the results of
+//! earlier, similar searches, so that the results which turned out to be useful rank higher.
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use apollo_compiler::collections::IndexMap;
+
+/// A re-ranking step for the types matching a search
+pub trait Rerank: Send + Sync {
+    /// Adjust the scores of the types matching the search terms, by type name
+    fn rerank(&self, terms: &[String], scores: &mut IndexMap<String, f32>);
+}
+
+/// A type selected from the results of a search
+struct Selection {
+    terms: HashSet<String>,
+    type_name: String,
+}
+
+/// An in-memory store of the types selected from search results, boosting them in the results
+/// of similar searches. The oldest selections are dropped once the store is full.
+#[derive(Clone)]
+pub struct SelectionFeedback {
+    selections: Arc<Mutex<VecDeque<Selection>>>,
+    capacity: usize,
+    boost_factor: f32,
+}
+
+impl SelectionFeedback {
+    /// Create a store holding up to `capacity` selections. The score of a type is multiplied by
+    /// `1 + boost_factor` for each selection of it from a search with the same terms, and boosted
+    /// proportionally less for each selection from a search sharing only some of the terms.
+    pub fn new(capacity: usize, boost_factor: f32) -> Self {
+        Self {
+            selections: Default::default(),
+            capacity,
+            boost_factor,
+        }
+    }
+
+    /// Record that a type was selected from the results of a search
+    pub fn record_selection(&self, query: &str, type_name: &str) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut selections = self
+            .selections
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        while selections.len() >= self.capacity {
+            selections.pop_front();
+        }
+        selections.push_back(Selection {
+            terms: normalize_terms([query]),
+            type_name: type_name.to_string(),
+        });
+    }
+}
+
+impl Rerank for SelectionFeedback {
+    fn rerank(&self, terms: &[String], scores: &mut IndexMap<String, f32>) {
+        let terms = normalize_terms(terms);
+        if terms.is_empty() {
+            return;
+        }
+        let selections = self
+            .selections
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        for selection in selections.iter() {
+            // The similarity of the searches is the share of the terms they have in common
+            let common = selection.terms.intersection(&terms).count();
+            if common == 0 {
+                continue;
+            }
+            let similarity = common as f32 / selection.terms.union(&terms).count() as f32;
+            if let Some(score) = scores.get_mut(&selection.type_name) {
+                *score *= 1.0 + self.boost_factor * similarity;
+            }
+        }
+        drop(selections);
+        scores.sort_by(|_, a, _, b| b.total_cmp(a));
+    }
+}
+
+/// The distinct lowercase words of a set of search terms
+fn normalize_terms<I, S>(terms: I) -> HashSet<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    terms
+        .into_iter()
+        .flat_map(|term| {
+            term.as_ref()
+                .split_whitespace()
+                .map(str::to_lowercase)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use apollo_compiler::Schema;
+
+    use super::*;
+    use crate::{OperationType, Options, SchemaIndex};
+
+    #[test]
+    fn selections_raise_the_rank_of_the_selected_type() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { user: User, admin: Admin }
+            "A user of the site"
+            type User { id: ID!, name: String }
+            "An admin of the site, who is not a user"
+            type Admin { id: ID! }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+        let feedback = SelectionFeedback::new(100, 1.0);
+        let top_type = |query: &str| {
+            index
+                .search(
+                    vec![query.to_string()],
+                    Options {
+                        reranker: Some(Arc::new(feedback.clone())),
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .first()
+                .map(|path| path.inner.to_string())
+        };
+
+        assert_eq!(top_type("user").as_deref(), Some("Query -> user -> User"));
+        for _ in 0..5 {
+            feedback.record_selection("user", "Admin");
+        }
+        assert_eq!(top_type("user").as_deref(), Some("Query -> admin -> Admin"));
+
+        // Selections only boost types in the results of similar searches
+        assert_eq!(top_type("name").as_deref(), Some("Query -> user -> User"));
+    }
+
+    #[test]
+    fn the_oldest_selections_are_dropped_when_full() {
+        let feedback = SelectionFeedback::new(2, 1.0);
+        feedback.record_selection("user", "User");
+        feedback.record_selection("user", "Admin");
+        feedback.record_selection("user", "Admin");
+
+        let mut scores =
+            IndexMap::from_iter([("User".to_string(), 1.0), ("Admin".to_string(), 1.0)]);
+        feedback.rerank(&["user".to_string()], &mut scores);
+        assert_eq!(scores.get("User"), Some(&1.0));
+        assert_eq!(scores.get("Admin"), Some(&4.0));
+    }
+}
@@ -0,0 +1,62 @@
+//! The graph of references between the types of an indexed schema, which can be exported for
+//! documentation tooling.
+
+use apollo_compiler::Name;
+use apollo_compiler::ast::NamedType;
+use std::fmt;
+use std::fmt::Display;
+
+/// The types of an indexed schema, and the references between them
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TypeGraph {
+    /// The indexed types, sorted by name
+    pub nodes: Vec<NamedType>,
+
+    /// The references from types to the types of their fields, sorted by referencing type
+    pub edges: Vec<TypeEdge>,
+}
+
+/// A reference from one type to another in a [`TypeGraph`]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TypeEdge {
+    /// The referencing type
+    pub from: NamedType,
+
+    /// The referenced type
+    pub to: NamedType,
+
+    /// The name of the field of the referencing type with the referenced type, if the reference
+    /// is a field rather than a union member or interface implementation
+    pub field_name: Option<Name>,
+}
+
+impl TypeEdge {
+    /// Parse an edge to a type from a stored referencing type, in the form
+    /// `Type#field#arg1,arg2`
+    pub(crate) fn parse(reference: &str, to: NamedType) -> Self {
+        let mut parts = reference.split('#');
+        Self {
+            from: NamedType::new_unchecked(parts.next().unwrap_or_default()),
+            to,
+            field_name: parts.next().map(Name::new_unchecked),
+        }
+    }
+}
+
+/// Formats the graph in the DOT language, with edges labelled by field name
+impl Display for TypeGraph {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph schema {{")?;
+        for node in &self.nodes {
+            writeln!(f, "  \"{node}\";")?;
+        }
+        for edge in &self.edges {
+            write!(f, "  \"{}\" -> \"{}\"", edge.from, edge.to)?;
+            if let Some(field_name) = &edge.field_name {
+                write!(f, " [label=\"{field_name}\"]")?;
+            }
+            writeln!(f, ";")?;
+        }
+        write!(f, "}}")
+    }
+}
@@ -0,0 +1,152 @@
+//! A token filter splitting camelCase identifiers into their words, so that compound type and
+//! field names can be found by their parts. `ShippingAddress` is indexed as `ShippingAddress`,
+//! `Shipping`, and `Address`, and `HTTPRequest` as `HTTPRequest`, `HTTP`, and `Request`.
+//! snake_case identifiers are already split by the tokenizer, which splits on underscores.
+
+use tantivy::tokenizer::{Token, TokenFilter, TokenStream, Tokenizer};
+
+/// Splits camelCase tokens into their words, keeping the whole token as well
+#[derive(Clone)]
+pub(crate) struct IdentifierSplitter;
+
+impl TokenFilter for IdentifierSplitter {
+    type Tokenizer<T: Tokenizer> = IdentifierSplitterFilter<T>;
+
+    fn transform<T: Tokenizer>(self, tokenizer: T) -> IdentifierSplitterFilter<T> {
+        IdentifierSplitterFilter {
+            inner: tokenizer,
+            tokens: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct IdentifierSplitterFilter<T> {
+    inner: T,
+    tokens: Vec<Token>,
+}
+
+impl<T: Tokenizer> Tokenizer for IdentifierSplitterFilter<T> {
+    type TokenStream<'a> = IdentifierSplitterTokenStream<'a, T::TokenStream<'a>>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        self.tokens.clear();
+        IdentifierSplitterTokenStream {
+            tail: self.inner.token_stream(text),
+            tokens: &mut self.tokens,
+        }
+    }
+}
+
+pub(crate) struct IdentifierSplitterTokenStream<'a, T> {
+    tail: T,
+    /// The tokens split from the current token of the tail, in reverse order of emission
+    tokens: &'a mut Vec<Token>,
+}
+
+impl<T: TokenStream> IdentifierSplitterTokenStream<'_, T> {
+    fn split(&mut self) {
+        let token = self.tail.token();
+        let starts = word_starts(&token.text);
+        if starts.len() < 2 {
+            return;
+        }
+        let ends = starts.iter().skip(1).copied().chain([token.text.len()]);
+        let words: Vec<Token> = starts
+            .iter()
+            .zip(ends)
+            .map(|(&start, end)| Token {
+                offset_from: token.offset_from + start,
+                offset_to: token.offset_from + end,
+                text: token.text.get(start..end).unwrap_or_default().to_string(),
+                ..*token
+            })
+            .collect();
+        self.tokens.extend(words.into_iter().rev());
+        self.tokens.push(token.clone());
+    }
+}
+
+impl<T: TokenStream> TokenStream for IdentifierSplitterTokenStream<'_, T> {
+    fn advance(&mut self) -> bool {
+        self.tokens.pop();
+        if !self.tokens.is_empty() {
+            return true;
+        }
+        if !self.tail.advance() {
+            return false;
+        }
+        self.split();
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.tokens.last().unwrap_or_else(|| self.tail.token())
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.tokens
+            .last_mut()
+            .unwrap_or_else(|| self.tail.token_mut())
+    }
+}
+
+/// The byte offsets of the words in a camelCase identifier. A word starts at an uppercase letter
+/// following a lowercase letter or digit, or at the last uppercase letter of an acronym followed
+/// by a lowercase letter.
+fn word_starts(text: &str) -> Vec<usize> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut starts = vec![0];
+    for (window_index, window) in chars.windows(2).enumerate() {
+        let [(_, previous), (offset, current)] = window else {
+            continue;
+        };
+        let next = chars.get(window_index + 2).map(|(_, next)| *next);
+        let starts_word = current.is_uppercase()
+            && (previous.is_lowercase()
+                || previous.is_numeric()
+                || (previous.is_uppercase() && next.is_some_and(char::is_lowercase)));
+        if starts_word {
+            starts.push(*offset);
+        }
+    }
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tantivy::tokenizer::{LowerCaser, SimpleTokenizer, TextAnalyzer};
+
+    fn tokens(text: &str) -> Vec<String> {
+        let mut analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
+            .filter(IdentifierSplitter)
+            .filter(LowerCaser)
+            .build();
+        let mut tokens = Vec::new();
+        analyzer
+            .token_stream(text)
+            .process(&mut |token| tokens.push(token.text.clone()));
+        tokens
+    }
+
+    #[test]
+    fn splits_camel_case_identifiers() {
+        assert_eq!(
+            tokens("ShippingAddress HTTPRequest venue_id v2Venue"),
+            [
+                "shippingaddress",
+                "shipping",
+                "address",
+                "httprequest",
+                "http",
+                "request",
+                "venue",
+                "id",
+                "v2venue",
+                "v2",
+                "venue",
+            ]
+        );
+    }
+}
@@ -6,51 +6,134 @@
 //!
 //! * The type name
 //! * The type description
-//! * The field names
+//! * The field names, and optionally the names of their arguments
+//! * The alternate names given to the type, its fields, or its enum values with an
+//!   `@alias(name:)` directive
+//!
+//! Directive definitions can optionally be indexed as well, by their name, description, and
+//! argument names. Directives are indexed separately from types, and are searched with
+//! [`SchemaIndex::search_directives`].
 //!
 //! Searching for a set of terms returns the top root paths to types matching the search terms.
 //! A root path is a path from a root type (Query, Mutation, or Subscription) to the type. This
 //! provides not only information about the type itself, but also how to construct a query to
-//! retrieve that type.
+//! retrieve that type. Deprecated types and fields in a root path carry the reason they were
+//! deprecated, so that callers can avoid them.
 //!
 //! Shorter paths are preferred by a customizable boost factor. If parent types in the path also
 //! match the search terms, a customizable portion of their scores are added to the path score.
 //! The total number of matching types considered can be customized, as can the maximum number of
 //! paths to each type (types may be reachable by more than one path - the shortest paths to root
 //! take precedence over longer paths).
+//!
+//! The scores of the matching types can be adjusted by a pluggable [`Rerank`] step before the
+//! paths to them are scored, such as [`SelectionFeedback`], which boosts the types selected from
+//! the results of earlier, similar searches.
+//!
+//! Types which can't be indexed, such as types with fields of undefined types in a schema from a
+//! malformed composition, are left out of the index rather than failing to build it.
+//! [`SchemaIndex::new_with_errors`] returns the errors for these types alongside the index.
+//!
+//! Very large schemas can be indexed in batches of types with [`IndexOptions::index_batch_size`],
+//! bounding the memory held for the references between types.
+//!
+//! [`SchemaIndex::search_with_diagnostics`] also returns [`SearchDiagnostics`], timing the index
+//! query, the document fetch, and the search for paths to root types.
+//!
+//! The references between the indexed types are exported by [`SchemaIndex::type_graph`] as a
+//! [`TypeGraph`], which formats as a DOT graph for documentation tooling.
+//!
+//! An index can be exported to a snapshot file with [`SchemaIndex::export`], and imported with
+//! [`SchemaIndex::import`] without traversing the schema again.
+//!
+//! The index of a previous version of the schema can be attached with
+//! [`SchemaIndex::with_previous_version`]. [`SchemaIndex::search_diff`] then returns the matching
+//! types tagged by whether they were added, removed, or changed between the two versions.
 
-use crate::path::PathNode;
-use apollo_compiler::ast::{NamedType, OperationType as AstOperationType};
+use apollo_compiler::ast::{self, NamedType, OperationType as AstOperationType};
 use apollo_compiler::collections::IndexMap;
-use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::schema::{ExtendedType, FieldDefinition, Implementers};
 use apollo_compiler::validation::Valid;
 use apollo_compiler::{Name, Schema};
 use enumset::{EnumSet, EnumSetType};
-use error::{IndexingError, SearchError};
+use error::{IndexingError, SearchError, TypeIndexingError};
+use identifiers::IdentifierSplitter;
 use itertools::Itertools;
-use path::Scored;
+use std::borrow::Cow;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
 use std::time::Instant;
-use tantivy::collector::TopDocs;
-use tantivy::query::{BooleanQuery, Occur, Query, TermQuery};
-use tantivy::schema::{Field, IndexRecordOption, TextFieldIndexing, TextOptions, Value};
-use tantivy::tokenizer::{Language, LowerCaser, SimpleTokenizer, Stemmer, TextAnalyzer};
+use tantivy::collector::{DocSetCollector, TopDocs};
+use tantivy::query::{
+    BooleanQuery, ConstScoreQuery, FuzzyTermQuery, Occur, PhraseQuery, Query, TermQuery,
+};
+use tantivy::schema::{Field, FieldType, TextFieldIndexing, TextOptions, Value};
+use tantivy::tokenizer::{
+    AsciiFoldingFilter, Language, LowerCaser, SimpleTokenizer, Stemmer, TextAnalyzer,
+};
 use tantivy::{
-    Index, TantivyDocument, Term,
+    DocAddress, DocSet, Index, Searcher, TantivyDocument, TantivyError, Term,
     schema::{STORED, Schema as TantivySchema},
 };
 use tracing::{Level, debug, error, info, warn};
 use traverse::SchemaExt;
 
+mod diagnostics;
+mod diff;
 pub mod error;
+mod export;
+mod feedback;
+mod graph;
+mod identifiers;
 mod path;
+mod skeleton;
+mod snapshot;
 mod traverse;
+mod tree;
+
+pub use diagnostics::SearchDiagnostics;
+pub use diff::{TypeChange, TypeDiff};
+pub use export::IndexedTypeDoc;
+pub use feedback::{Rerank, SelectionFeedback};
+pub use graph::{TypeEdge, TypeGraph};
+pub use path::{MatchedToken, PathNode, Scored, SourceLocation};
+pub use skeleton::{LeafSelection, query_skeleton};
+pub use tantivy::schema::IndexRecordOption;
+pub use tree::{PathTree, TreeNode};
 
 pub const TYPE_NAME_FIELD: &str = "type_name";
 pub const DESCRIPTION_FIELD: &str = "description";
 pub const FIELDS_FIELD: &str = "fields";
 pub const RAW_TYPE_NAME_FIELD: &str = "raw_type_name";
+pub const LOWER_TYPE_NAME_FIELD: &str = "lower_type_name";
+pub const ALIASES_FIELD: &str = "aliases";
 pub const REFERENCING_TYPES_FIELD: &str = "referencing_types";
+pub const FULL_DESCRIPTION_FIELD: &str = "full_description";
+pub const KIND_FIELD: &str = "kind";
+pub const LOCATION_FIELD: &str = "location";
+pub const DEPRECATIONS_FIELD: &str = "deprecations";
+pub const POSSIBLE_TYPES_FIELD: &str = "possible_types";
+
+/// The kind of a document describing a type
+const TYPE_KIND: &str = "type";
+
+/// The kind of a document describing a directive definition
+const DIRECTIVE_KIND: &str = "directive";
+
+/// The directive giving an alternate name to a schema element, which is indexed for search
+const ALIAS_DIRECTIVE: &str = "alias";
+
+/// The directive deprecating a schema element, whose reason is stored for search results
+const DEPRECATED_DIRECTIVE: &str = "deprecated";
+
+/// The reason of a `@deprecated` directive without one, as defined by the GraphQL spec
+const DEFAULT_DEPRECATION_REASON: &str = "No longer supported";
+
+/// A type matching a search, and the scored root paths leading to it
+pub type TypePaths = (NamedType, Vec<Scored<PathNode>>);
+
+/// The boolean operators which can join search terms
+const BOOLEAN_OPERATORS: [&str; 3] = ["AND", "OR", "NOT"];
 
 /// Types of operations to be included in the schema index. Unlike the AST types, these types can
 /// be included in an [`EnumSet`](EnumSet).
@@ -71,6 +154,73 @@ impl From<AstOperationType> for OperationType {
     }
 }
 
+/// The curve mapping the length of a path to root to the boost given to it. The shortest paths
+/// matching a search always receive the full boost, and the longest receive none.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BoostCurve {
+    /// The boost decreases evenly with the length of the path
+    #[default]
+    Linear,
+
+    /// The boost falls off quickly past the shortest paths, favoring them more strongly
+    Exponential,
+
+    /// Only the shortest paths are boosted
+    Step,
+}
+
+/// The decay rate of the [exponential boost curve](BoostCurve::Exponential)
+const EXPONENTIAL_BOOST_RATE: f32 = 3.0;
+
+impl BoostCurve {
+    /// The boost for a path with the given normalized length, from 0.0 for the shortest paths to
+    /// 1.0 for the longest. Returns 1.0 for the shortest paths and 0.0 for the longest.
+    fn boost(&self, normalized_length: f32) -> f32 {
+        match self {
+            BoostCurve::Linear => 1.0 - normalized_length,
+            BoostCurve::Exponential => {
+                let floor = (-EXPONENTIAL_BOOST_RATE).exp();
+                ((-EXPONENTIAL_BOOST_RATE * normalized_length).exp() - floor) / (1.0 - floor)
+            }
+            BoostCurve::Step => {
+                if normalized_length == 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+}
+
+/// How many of the words of a search a type must match to be included in the results
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MinShouldMatch {
+    /// At least this many words, or all of them if the search has fewer
+    Count(usize),
+
+    /// At least this percentage of the words, rounded down
+    Percent(u8),
+}
+
+impl Default for MinShouldMatch {
+    fn default() -> Self {
+        MinShouldMatch::Count(1)
+    }
+}
+
+impl MinShouldMatch {
+    /// The number of words which must match, out of the given number of words in a search. At
+    /// least one word must always match.
+    fn resolve(&self, word_count: usize) -> usize {
+        let count = match self {
+            MinShouldMatch::Count(count) => *count,
+            MinShouldMatch::Percent(percent) => word_count * usize::from(*percent).min(100) / 100,
+        };
+        count.clamp(1, word_count.max(1))
+    }
+}
+
 impl From<OperationType> for AstOperationType {
     fn from(value: OperationType) -> Self {
         match value {
@@ -91,9 +241,37 @@ pub struct Options {
     /// The boost factor applied to shorter paths to root (0.0 for no boost, 1.0 for 100% boost)
     pub short_path_boost_factor: f32,
 
+    /// How the boost given to shorter paths decreases with the length of the path
+    pub boost_curve: BoostCurve,
+
     /// The percentage of the score of each parent type added to the overall score of the path
     /// to root 0.0 for 0%, 1.0 for 100%)
     pub parent_match_boost_factor: f32,
+
+    /// The maximum number of partial paths visited while looking for the paths to root of each
+    /// matching schema type, bounding the search on highly-connected schemas
+    pub max_visited_paths_per_type: usize,
+
+    /// Only include paths to root starting at these root operation types (all indexed root
+    /// operation types if not set)
+    pub root_types: Option<EnumSet<OperationType>>,
+
+    /// Collapse redundant paths, keeping the highest-scoring representative. A path is redundant
+    /// if it is the start of a longer path in the results, or if it reaches the same type from
+    /// the same root type through the same fields as another path, differing only in the
+    /// intermediate types.
+    pub collapse_redundant_paths: bool,
+
+    /// How many of the words of the search a type must match. Searches using boolean operators
+    /// are matched according to the operators instead.
+    pub min_should_match: MinShouldMatch,
+
+    /// Match the words of the search within one edit of the indexed words, and as the start of
+    /// them, to find types when no exact match exists
+    pub fuzzy: bool,
+
+    /// A step adjusting the scores of the matching types before the paths to them are scored
+    pub reranker: Option<Arc<dyn Rerank>>,
 }
 
 impl Default for Options {
@@ -102,7 +280,97 @@ impl Default for Options {
             max_type_matches: 10,
             max_paths_per_type: 3,
             short_path_boost_factor: 0.5,
+            boost_curve: BoostCurve::default(),
             parent_match_boost_factor: 0.2,
+            max_visited_paths_per_type: 10_000,
+            root_types: None,
+            collapse_redundant_paths: false,
+            min_should_match: MinShouldMatch::default(),
+            fuzzy: false,
+            reranker: None,
+        }
+    }
+}
+
+/// Options for building a schema index
+pub struct IndexOptions {
+    /// The amount of memory used for indexing (in bytes)
+    pub index_memory_bytes: usize,
+
+    /// The maximum number of characters of each description to index. Longer descriptions are
+    /// truncated with an ellipsis, and stored in full alongside the indexed text.
+    pub max_description_chars: Option<usize>,
+
+    /// Whether to index directive definitions in addition to types
+    pub index_directives: bool,
+
+    /// The maximum number of referencing types stored for each type, bounding the size of the
+    /// index on highly-connected schemas
+    pub max_referencing_types: usize,
+
+    /// The maximum number of types reachable from the root types. Larger schemas fail to index,
+    /// bounding the memory used by the index.
+    pub max_indexed_types: Option<usize>,
+
+    /// Whether to fold accented characters into their ASCII equivalents, so that unaccented
+    /// search terms match accented text
+    pub ascii_folding: bool,
+
+    /// Whether to also index camelCase words by their parts, so that `address` matches
+    /// `ShippingAddress`. snake_case words are always split.
+    pub split_identifiers: bool,
+
+    /// Whether to store where each type is defined in the schema source, so that the matching
+    /// type of each search result carries its location
+    pub store_locations: bool,
+
+    /// Whether to store the description and fields text of each type, in addition to indexing
+    /// it. Searching only needs the stored type names and references, so disabling this reduces
+    /// the size of the index when the text is not displayed.
+    pub store_display_fields: bool,
+
+    /// Whether to warn about types which can't be reached from any root operation type, and so
+    /// are never indexed. These are often left over from schema changes.
+    pub warn_unreachable_types: bool,
+
+    /// The number of types to index at a time, if set. The types referencing each type are only
+    /// collected for the types in the current batch, bounding the memory used to index very
+    /// large schemas at the cost of traversing the schema once per batch. By default, all types
+    /// are indexed in one batch.
+    pub index_batch_size: Option<usize>,
+
+    /// Whether to record the positions of the words in the text fields by default, so that
+    /// quoted phrases in searches only match the words in order
+    pub phrase_search: bool,
+
+    /// Whether to index the names of the arguments of each field along with the field, so that
+    /// searching for an argument name matches the type defining the field. This changes the
+    /// scores of all searches, since the indexed fields text grows.
+    pub index_argument_names: bool,
+
+    /// What is recorded for each word of the text fields (type names, descriptions, fields, and
+    /// aliases), by field name, overriding the default. Frequencies and positions grow the index,
+    /// and quoted phrases only match the fields recording positions.
+    pub record_options: HashMap<String, IndexRecordOption>,
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self {
+            index_memory_bytes: 50_000_000,
+            max_description_chars: None,
+            index_directives: false,
+            max_referencing_types: 1_000,
+            max_indexed_types: None,
+            ascii_folding: false,
+            split_identifiers: false,
+            store_locations: false,
+            store_display_fields: true,
+            warn_unreachable_types: false,
+            index_batch_size: None,
+            phrase_search: false,
+            index_argument_names: false,
+            record_options: HashMap::new(),
         }
     }
 }
@@ -112,56 +380,196 @@ pub struct SchemaIndex {
     inner: Index,
     text_analyzer: TextAnalyzer,
     raw_type_name_field: Field,
+    lower_type_name_field: Field,
+    aliases_field: Field,
     type_name_field: Field,
     description_field: Field,
     fields_field: Field,
     referencing_types_field: Field,
+    kind_field: Field,
+    location_field: Option<Field>,
+    deprecations_field: Field,
+    possible_types_field: Field,
+    root_operation_types: HashMap<String, OperationType>,
+    ascii_folding: bool,
+    split_identifiers: bool,
+    schema_hash: u64,
+    previous: Option<Arc<SchemaIndex>>,
+}
+
+/// Collapse paths which are redundant with another path, keeping the highest score of each set of
+/// redundant paths. The paths must be sorted by descending score.
+fn collapse_redundant_paths(paths: Vec<Scored<PathNode>>) -> Vec<Scored<PathNode>> {
+    // Paths which are the start of a longer path are dropped, and the longer path keeps the
+    // higher of their scores
+    let paths = paths
+        .iter()
+        .filter(|path| {
+            !paths
+                .iter()
+                .any(|other| is_path_prefix(&path.inner, &other.inner))
+        })
+        .map(|path| {
+            let score = paths
+                .iter()
+                .filter(|other| is_path_prefix(&other.inner, &path.inner))
+                .map(Scored::score)
+                .fold(path.score(), f32::max);
+            Scored::new(path.inner.clone(), score)
+        })
+        .sorted_by(|a, b| b.score().total_cmp(&a.score()));
+
+    // Paths reaching the same type from the same root through the same fields are equivalent, so
+    // only the first (highest-scoring) of them is kept
+    let mut routes = HashSet::new();
+    paths
+        .filter(|path| {
+            let fields: Vec<_> = path
+                .inner
+                .iter()
+                .map(|node| node.field_name.clone())
+                .collect();
+            let target = path.inner.iter().last().map(|node| node.node_type.clone());
+            routes.insert((path.inner.node_type.clone(), fields, target))
+        })
+        .collect()
+}
+
+/// Whether a path is the start of a longer path
+fn is_path_prefix(path: &PathNode, longer: &PathNode) -> bool {
+    let length = path.len();
+    length < longer.len()
+        && path
+            .iter()
+            .zip(longer.iter())
+            .enumerate()
+            .all(|(index, (node, other))| {
+                node.node_type == other.node_type
+                    && (index + 1 == length || node.field_name == other.field_name)
+            })
+}
+
+/// A custom analyzer with English stemming and lowercasing, optionally splitting camelCase words
+/// into their parts and folding accented characters into their ASCII equivalents. The same
+/// analyzer is used for indexing and queries.
+// TODO: support other languages
+fn text_analyzer(ascii_folding: bool, split_identifiers: bool) -> TextAnalyzer {
+    let mut text_analyzer = TextAnalyzer::builder(SimpleTokenizer::default()).dynamic();
+    // Words are split before lowercasing, which loses their boundaries
+    if split_identifiers {
+        text_analyzer = text_analyzer.filter_dynamic(IdentifierSplitter);
+    }
+    text_analyzer = text_analyzer.filter_dynamic(LowerCaser);
+    if ascii_folding {
+        text_analyzer = text_analyzer.filter_dynamic(AsciiFoldingFilter);
+    }
+    text_analyzer
+        .filter_dynamic(Stemmer::new(Language::English))
+        .build()
 }
 
 impl SchemaIndex {
+    /// Index a schema, warning about the types left out of the index because they couldn't be
+    /// indexed
     pub fn new(
         schema: &Valid<Schema>,
         root_types: EnumSet<OperationType>,
-        index_memory_bytes: usize,
+        options: IndexOptions,
     ) -> Result<Self, IndexingError> {
+        let (index, errors) = Self::new_with_errors(schema, root_types, options)?;
+        for error in errors {
+            warn!("Skipped indexing a type: {error}");
+        }
+        Ok(index)
+    }
+
+    /// Index a schema, returning the errors for the types left out of the index because they
+    /// couldn't be indexed. Only errors affecting the whole index fail the build.
+    pub fn new_with_errors(
+        schema: &Valid<Schema>,
+        root_types: EnumSet<OperationType>,
+        options: IndexOptions,
+    ) -> Result<(Self, Vec<TypeIndexingError>), IndexingError> {
         let start_time = Instant::now();
 
-        // Register a custom analyzer with English stemming and lowercasing
-        // TODO: support other languages
-        let text_analyzer = TextAnalyzer::builder(SimpleTokenizer::default())
-            .filter(LowerCaser)
-            .filter(Stemmer::new(Language::English))
-            .build();
+        let text_analyzer = text_analyzer(options.ascii_folding, options.split_identifiers);
 
-        // Create the schema builder and add fields with the custom analyzer
+        // Create the schema builder and add fields with the custom analyzer, recording positions
+        // for phrases if enabled
         let mut index_schema = TantivySchema::builder();
+        let text_indexing = |name: &str| {
+            let default_record_option = if options.phrase_search {
+                IndexRecordOption::WithFreqsAndPositions
+            } else {
+                IndexRecordOption::Basic
+            };
+            TextFieldIndexing::default()
+                .set_tokenizer("en_stem")
+                .set_index_option(
+                    options
+                        .record_options
+                        .get(name)
+                        .copied()
+                        .unwrap_or(default_record_option),
+                )
+        };
         let type_name_field = index_schema.add_text_field(
             TYPE_NAME_FIELD,
             TextOptions::default()
-                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("en_stem"))
+                .set_indexing_options(text_indexing(TYPE_NAME_FIELD))
                 .set_stored(),
         );
-        let description_field = index_schema.add_text_field(
-            DESCRIPTION_FIELD,
+        let display_field_options = |name: &str| {
+            let text_options = TextOptions::default().set_indexing_options(text_indexing(name));
+            if options.store_display_fields {
+                text_options.set_stored()
+            } else {
+                text_options
+            }
+        };
+        let description_field = index_schema
+            .add_text_field(DESCRIPTION_FIELD, display_field_options(DESCRIPTION_FIELD));
+        let fields_field =
+            index_schema.add_text_field(FIELDS_FIELD, display_field_options(FIELDS_FIELD));
+
+        // The raw type name is indexed as the exact name (no stemming or lowercasing)
+        let raw_type_name_field = index_schema.add_text_field(
+            RAW_TYPE_NAME_FIELD,
             TextOptions::default()
-                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("en_stem"))
+                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("raw"))
                 .set_stored(),
         );
-        let fields_field = index_schema.add_text_field(
-            FIELDS_FIELD,
+        // The lowercased type name is indexed as a single term, for case-insensitive lookups
+        let lower_type_name_field = index_schema.add_text_field(
+            LOWER_TYPE_NAME_FIELD,
             TextOptions::default()
-                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("en_stem"))
-                .set_stored(),
+                .set_indexing_options(TextFieldIndexing::default().set_tokenizer("raw")),
+        );
+        // Alternate names from `@alias` directives are matched like the type name
+        let aliases_field = index_schema.add_text_field(
+            ALIASES_FIELD,
+            TextOptions::default().set_indexing_options(text_indexing(ALIASES_FIELD)),
         );
+        let referencing_types_field = index_schema.add_text_field(REFERENCING_TYPES_FIELD, STORED);
+        let full_description_field = index_schema.add_text_field(FULL_DESCRIPTION_FIELD, STORED);
+        // The deprecation reasons of the type and its fields, as `field#reason` (with an empty
+        // field name for the type itself)
+        let deprecations_field = index_schema.add_text_field(DEPRECATIONS_FIELD, STORED);
+        // The concrete object types each interface or union may resolve to
+        let possible_types_field = index_schema.add_text_field(POSSIBLE_TYPES_FIELD, STORED);
 
-        // The raw type name is indexed as the exact name (no stemming or lowercasing)
-        let raw_type_name_field = index_schema.add_text_field(
-            RAW_TYPE_NAME_FIELD,
+        // The kind of schema element (type or directive) described by each document
+        let kind_field = index_schema.add_text_field(
+            KIND_FIELD,
             TextOptions::default()
                 .set_indexing_options(TextFieldIndexing::default().set_tokenizer("raw"))
                 .set_stored(),
         );
-        let referencing_types_field = index_schema.add_text_field(REFERENCING_TYPES_FIELD, STORED);
+
+        // The location of each type in the schema source, if enabled
+        let location_field = options
+            .store_locations
+            .then(|| index_schema.add_text_field(LOCATION_FIELD, STORED));
 
         // Create the index
         let index_schema = index_schema.build();
@@ -170,145 +578,251 @@ impl SchemaIndex {
             .tokenizers()
             .register("en_stem", text_analyzer.clone());
 
-        // Map every type in the schema to the types referencing it
-        let mut index_writer = index.writer(index_memory_bytes)?;
-        let mut type_references: HashMap<String, Vec<String>> = HashMap::default();
-        for (extended_type, path) in schema.traverse(root_types) {
-            let entry = type_references
-                .entry(extended_type.name().to_string())
-                .or_default();
-            if let Some((ref_type, field_name, field_args)) = path.referencing_type() {
-                if let Some(field_name) = field_name {
-                    entry.push(format!(
-                        "{}#{}{}",
-                        ref_type,
-                        field_name.as_str(),
-                        if field_args.is_empty() {
-                            "".to_string()
-                        } else {
-                            format!("#{}", field_args.iter().join(","))
-                        }
-                    ));
-                } else {
-                    entry.push(ref_type.to_string())
+        // Map the types in the schema to the types referencing them, in batches if enabled
+        let mut index_writer = index.writer(options.index_memory_bytes)?;
+        let implementers = schema.implementers_map();
+        let mut capped_types = HashSet::new();
+        let batches = match options.index_batch_size {
+            Some(batch_size) => reached_types(schema, root_types, options.max_indexed_types)?
+                .chunks(batch_size.max(1))
+                .map(|batch| Some(batch.iter().cloned().collect::<HashSet<_>>()))
+                .collect(),
+            None => vec![None],
+        };
+        let mut type_count = 0usize;
+        let mut type_errors = Vec::new();
+        for batch in batches {
+            let type_references = type_references(
+                schema,
+                root_types,
+                &options,
+                batch.as_ref(),
+                &mut capped_types,
+            )?;
+            if tracing::enabled!(Level::DEBUG) {
+                for (type_name, references) in &type_references {
+                    debug!("Type '{}' is referenced by: {:?}", type_name, references);
                 }
             }
-        }
+            type_count += type_references.len();
 
-        if tracing::enabled!(Level::DEBUG) {
+            // Build an index of each type in the batch
             for (type_name, references) in &type_references {
-                debug!("Type '{}' is referenced by: {:?}", type_name, references);
-            }
-        }
-
-        // Build an index of each type
-        for (type_name, references) in &type_references {
-            let type_name = NamedType::new_unchecked(type_name.as_str());
-            let extended_type = if let Some(extended_type) = schema.types.get(&type_name) {
-                extended_type
-            } else {
-                // This can never really happen since we got the type name from the schema above
-                continue;
-            };
-            if extended_type.is_built_in() {
-                continue;
-            }
+                let type_name = NamedType::new_unchecked(type_name.as_str());
+                let extended_type = if let Some(extended_type) = schema.types.get(&type_name) {
+                    extended_type
+                } else {
+                    // This can never really happen since we got the type name from the schema above
+                    continue;
+                };
+                if extended_type.is_built_in() {
+                    continue;
+                }
+                if let Some(error) = undefined_field_type(schema, extended_type) {
+                    type_errors.push(error);
+                    continue;
+                }
 
-            // Create a document for each type
-            let mut doc = TantivyDocument::default();
-            doc.add_text(type_name_field, extended_type.name());
-            doc.add_text(raw_type_name_field, extended_type.name());
-            doc.add_text(
-                description_field,
-                extended_type
+                // Create a document for each type
+                let mut doc = TantivyDocument::default();
+                doc.add_text(kind_field, TYPE_KIND);
+                doc.add_text(type_name_field, extended_type.name());
+                doc.add_text(raw_type_name_field, extended_type.name());
+                doc.add_text(lower_type_name_field, extended_type.name().to_lowercase());
+                for alias in aliases(extended_type) {
+                    doc.add_text(aliases_field, alias);
+                }
+                let description = extended_type
                     .description()
                     .map(|d| d.to_string())
-                    .unwrap_or_default(),
-            );
+                    .unwrap_or_default();
+                let indexed_description = truncate(&description, options.max_description_chars);
+                if options.store_display_fields && indexed_description.len() != description.len() {
+                    doc.add_text(full_description_field, &description);
+                }
+                doc.add_text(description_field, indexed_description);
+                // Types without a location, such as those built by composition, have no location
+                // stored
+                if let Some((location_field, location)) =
+                    location_field.zip(source_location(schema, extended_type))
+                {
+                    doc.add_text(location_field, location.to_string());
+                }
 
-            for ref_type in references {
-                doc.add_text(referencing_types_field, ref_type);
-            }
-            let fields = match extended_type {
-                ExtendedType::Object(obj) => obj
-                    .fields
-                    .iter()
-                    .map(|(name, field)| format!("{}: {}", name, field.ty.inner_named_type()))
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                ExtendedType::Interface(interface) => interface
-                    .fields
-                    .iter()
-                    .map(|(name, field)| format!("{}: {}", name, field.ty.inner_named_type()))
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                ExtendedType::InputObject(input) => input
-                    .fields
-                    .iter()
-                    .map(|(name, field)| format!("{}: {}", name, field.ty.inner_named_type()))
-                    .collect::<Vec<_>>()
-                    .join(", "),
-                ExtendedType::Enum(enum_type) => format!(
-                    "{}: {}",
-                    enum_type.name,
-                    enum_type
+                for ref_type in references {
+                    doc.add_text(referencing_types_field, ref_type);
+                }
+                for (member, reason) in deprecations(schema, extended_type) {
+                    doc.add_text(deprecations_field, format!("{member}#{reason}"));
+                }
+                for possible_type in possible_types(&implementers, extended_type) {
+                    doc.add_text(possible_types_field, possible_type);
+                }
+                let fields = match extended_type {
+                    ExtendedType::Object(obj) => obj
+                        .fields
+                        .values()
+                        .map(|field| field_signature(field, options.index_argument_names))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    ExtendedType::Interface(interface) => interface
+                        .fields
+                        .values()
+                        .map(|field| field_signature(field, options.index_argument_names))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    ExtendedType::InputObject(input) => input
+                        .fields
+                        .iter()
+                        .map(|(name, field)| format!("{}: {}", name, field.ty.inner_named_type()))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    ExtendedType::Enum(enum_type) => format!(
+                        "{}: {}",
+                        enum_type.name,
+                        enum_type
+                            .values
+                            .iter()
+                            .map(|(name, _)| name.to_string())
+                            .collect::<Vec<_>>()
+                            .join(" | ")
+                    ),
+                    _ => String::new(),
+                };
+                doc.add_text(fields_field, &fields);
+                let field_descriptions = match extended_type {
+                    ExtendedType::Enum(enum_type) => enum_type
                         .values
                         .iter()
-                        .map(|(name, _)| name.to_string())
+                        .flat_map(|(_, value)| value.description.as_ref())
+                        .map(|node| truncate(node.as_str(), options.max_description_chars))
                         .collect::<Vec<_>>()
-                        .join(" | ")
-                ),
-                _ => String::new(),
-            };
-            doc.add_text(fields_field, &fields);
-            let field_descriptions = match extended_type {
-                ExtendedType::Enum(enum_type) => enum_type
-                    .values
-                    .iter()
-                    .flat_map(|(_, value)| value.description.as_ref())
-                    .map(|node| node.as_str())
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-                ExtendedType::Object(obj) => obj
-                    .fields
-                    .iter()
-                    .flat_map(|(_, field)| field.description.as_ref())
-                    .map(|node| node.as_str())
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-                ExtendedType::Interface(interface) => interface
-                    .fields
-                    .iter()
-                    .flat_map(|(_, field)| field.description.as_ref())
-                    .map(|node| node.as_str())
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-                ExtendedType::InputObject(input) => input
-                    .fields
-                    .iter()
-                    .flat_map(|(_, field)| field.description.as_ref())
-                    .map(|node| node.as_str())
-                    .collect::<Vec<_>>()
-                    .join("\n"),
-                _ => String::new(),
-            };
-            doc.add_text(description_field, &field_descriptions);
-            index_writer.add_document(doc)?;
+                        .join("\n"),
+                    ExtendedType::Object(obj) => obj
+                        .fields
+                        .iter()
+                        .flat_map(|(_, field)| field.description.as_ref())
+                        .map(|node| truncate(node.as_str(), options.max_description_chars))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    ExtendedType::Interface(interface) => interface
+                        .fields
+                        .iter()
+                        .flat_map(|(_, field)| field.description.as_ref())
+                        .map(|node| truncate(node.as_str(), options.max_description_chars))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    ExtendedType::InputObject(input) => input
+                        .fields
+                        .iter()
+                        .flat_map(|(_, field)| field.description.as_ref())
+                        .map(|node| truncate(node.as_str(), options.max_description_chars))
+                        .collect::<Vec<_>>()
+                        .join("\n"),
+                    _ => String::new(),
+                };
+                doc.add_text(description_field, &field_descriptions);
+                if let Err(source) = index_writer.add_document(doc) {
+                    type_errors.push(TypeIndexingError::Document {
+                        type_name: type_name.to_string(),
+                        source,
+                    });
+                }
+            }
+        }
+
+        if !capped_types.is_empty() {
+            warn!(
+                "Only indexed the first {} referencing types of {} types: {}",
+                options.max_referencing_types,
+                capped_types.len(),
+                capped_types.iter().sorted().join(", ")
+            );
+        }
+
+        if options.warn_unreachable_types {
+            let unreachable_types = schema.unreachable_types();
+            if !unreachable_types.is_empty() {
+                warn!(
+                    "{} types can't be reached from any root type: {}",
+                    unreachable_types.len(),
+                    unreachable_types.iter().sorted().join(", ")
+                );
+            }
+        }
+
+        // Create a document for each custom directive definition
+        let mut directive_count = 0usize;
+        if options.index_directives {
+            for directive in schema
+                .directive_definitions
+                .values()
+                .filter(|directive| !directive.is_built_in())
+            {
+                let mut doc = TantivyDocument::default();
+                doc.add_text(kind_field, DIRECTIVE_KIND);
+                doc.add_text(type_name_field, directive.name.as_str());
+                let description = directive
+                    .description
+                    .as_ref()
+                    .map(|d| d.to_string())
+                    .unwrap_or_default();
+                doc.add_text(
+                    description_field,
+                    truncate(&description, options.max_description_chars),
+                );
+                doc.add_text(
+                    fields_field,
+                    directive
+                        .arguments
+                        .iter()
+                        .map(|arg| format!("{}: {}", arg.name, arg.ty.inner_named_type()))
+                        .join(", "),
+                );
+                index_writer.add_document(doc)?;
+                directive_count += 1;
+            }
         }
         index_writer.commit()?;
 
+        let root_operation_types = root_types
+            .iter()
+            .filter_map(|root_type| {
+                schema
+                    .root_operation(root_type.into())
+                    .map(|name| (name.to_string(), root_type))
+            })
+            .collect();
+
         let elapsed = start_time.elapsed();
-        info!("Indexed {} types in {:.2?}", type_references.len(), elapsed);
-
-        Ok(Self {
-            inner: index,
-            text_analyzer,
-            raw_type_name_field,
-            type_name_field,
-            description_field,
-            fields_field,
-            referencing_types_field,
-        })
+        info!(
+            "Indexed {} types and {} directives in {:.2?}",
+            type_count, directive_count, elapsed
+        );
+
+        Ok((
+            Self {
+                inner: index,
+                text_analyzer,
+                raw_type_name_field,
+                lower_type_name_field,
+                aliases_field,
+                type_name_field,
+                description_field,
+                fields_field,
+                referencing_types_field,
+                kind_field,
+                location_field,
+                deprecations_field,
+                possible_types_field,
+                root_operation_types,
+                ascii_folding: options.ascii_folding,
+                split_identifiers: options.split_identifiers,
+                schema_hash: snapshot::schema_hash(schema),
+                previous: None,
+            },
+            type_errors,
+        ))
     }
 
     /// Search the schema for a set of terms
@@ -320,17 +834,39 @@ impl SchemaIndex {
     where
         I: IntoIterator<Item = String>,
     {
+        let (root_paths, diagnostics) = self.search_with_diagnostics(terms, options)?;
+        debug!("Search diagnostics: {:?}", diagnostics);
+        Ok(root_paths)
+    }
+
+    /// Search the schema for a set of terms, returning how long each phase of the search took
+    /// alongside the results
+    pub fn search_with_diagnostics<I>(
+        &self,
+        terms: I,
+        options: Options,
+    ) -> Result<(Vec<Scored<PathNode>>, SearchDiagnostics), SearchError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut diagnostics = SearchDiagnostics::default();
         let searcher = self.inner.reader()?.searcher();
         let mut root_paths: Vec<Scored<PathNode>> = Default::default();
         let mut scores: IndexMap<String, f32> = Default::default();
 
-        let query = self.query(terms);
+        let terms: Vec<String> = terms.into_iter().collect();
+        let query = self.query(terms.iter().cloned(), TYPE_KIND, &options);
         debug!("Index query: {:?}", query);
 
         // Get the top GraphQL schema types matching the search terms
+        let query_start = Instant::now();
         let top_docs = searcher.search(&query, &TopDocs::with_limit(100))?;
+        diagnostics.query_duration = query_start.elapsed();
+        diagnostics.candidate_count = top_docs.len();
 
-        // Map each type name to its score
+        // Map each type name to its score, and the tokens of the search it matched
+        let fetch_start = Instant::now();
+        let mut matched_tokens: HashMap<String, Vec<MatchedToken>> = HashMap::new();
         for (score, doc_address) in top_docs {
             let doc: TantivyDocument = searcher.doc(doc_address)?;
             if let Some(type_name) = doc
@@ -342,13 +878,32 @@ impl SchemaIndex {
                     query.explain(&searcher, doc_address)?
                 );
                 scores.insert(type_name.to_string(), score);
+                matched_tokens.insert(
+                    type_name.to_string(),
+                    self.matched_tokens(&searcher, doc_address, &terms)?,
+                );
             } else {
                 // This should never happen, since every document we add has this field defined
                 error!("Doc address {doc_address:?} missing raw type name field");
             }
         }
 
+        // Types with equal scores are returned in the order they happened to be indexed in, so
+        // break ties by name to make the top types the same every time
+        scores.sort_by(|name, score, other_name, other_score| {
+            other_score
+                .total_cmp(score)
+                .then_with(|| name.cmp(other_name))
+        });
+
+        diagnostics.fetch_duration = fetch_start.elapsed();
+
+        if let Some(reranker) = &options.reranker {
+            reranker.rerank(&terms, &mut scores);
+        }
+
         // For the top M types, compute the top N root paths to that type
+        let path_start = Instant::now();
         for (type_name, score) in scores.iter().take(options.max_type_matches) {
             let mut root_path_score = *score;
 
@@ -356,14 +911,30 @@ impl SchemaIndex {
             let mut visited = HashSet::new();
             let mut queue = VecDeque::new();
             let mut root_path_count = 0usize;
+            let mut visited_paths = 0usize;
 
             // Start with the current type as a Path
-            queue.push_back(PathNode::new(NamedType::new_unchecked(type_name)));
+            queue.push_back(
+                PathNode::new(NamedType::new_unchecked(type_name))
+                    .with_location(self.type_location(&searcher, type_name)?)
+                    .with_possible_types(self.possible_types(&searcher, type_name)?)
+                    .with_matched_tokens(
+                        matched_tokens.get(type_name).cloned().unwrap_or_default(),
+                    ),
+            );
 
             while let Some(current_path) = queue.pop_front() {
                 if root_path_count >= options.max_paths_per_type {
                     break;
                 }
+                if visited_paths >= options.max_visited_paths_per_type {
+                    warn!(
+                        type_name,
+                        "Stopped searching for paths to root after visiting {visited_paths} paths"
+                    );
+                    break;
+                }
+                visited_paths += 1;
                 let current_type = current_path.node_type.to_string();
                 visited.insert(current_type.clone());
 
@@ -392,6 +963,9 @@ impl SchemaIndex {
 
                 if referencing_types.is_empty() {
                     // This is a root type (no referencing types)
+                    if !self.is_included_root(&current_type, options.root_types) {
+                        continue;
+                    }
                     let root_path = current_path.clone();
                     root_paths.push(Scored::new(root_path, root_path_score));
                     root_path_count += 1;
@@ -429,95 +1003,818 @@ impl SchemaIndex {
                     }
                 }
             }
+            diagnostics.visited_paths += visited_paths;
         }
+        diagnostics.path_duration = path_start.elapsed();
+        diagnostics.root_path_count = root_paths.len();
 
-        Ok(self
-            .boost_shorter_paths(root_paths, options.short_path_boost_factor)
+        let root_paths = self
+            .boost_shorter_paths(
+                root_paths,
+                options.short_path_boost_factor,
+                options.boost_curve,
+            )
             .into_iter()
             .sorted_by(|a, b| {
                 b.score()
                     .partial_cmp(&a.score())
                     .unwrap_or(std::cmp::Ordering::Equal)
             })
-            .collect::<Vec<_>>())
+            .collect::<Vec<_>>();
+        let mut root_paths = if options.collapse_redundant_paths {
+            collapse_redundant_paths(root_paths)
+        } else {
+            root_paths
+        };
+        self.add_deprecations(&searcher, &mut root_paths)?;
+        Ok((root_paths, diagnostics))
     }
 
-    /// Apply a boost factor to shorter paths
-    fn boost_shorter_paths(
+    /// The index query that searching for a set of terms would execute, formatted for debugging
+    pub fn debug_query<I>(&self, terms: I) -> String
+    where
+        I: IntoIterator<Item = String>,
+    {
+        format!("{:?}", self.query(terms, TYPE_KIND, &Options::default()))
+    }
+
+    /// Look up a type by its exact name, or by its name in any casing if `case_insensitive` is
+    /// set. Returns the name of the type as declared in the schema.
+    pub fn lookup_type(
         &self,
-        scored_paths: Vec<Scored<PathNode>>,
-        boost_factor: f32,
-    ) -> Vec<Scored<PathNode>> {
-        if scored_paths.is_empty() || boost_factor == 0f32 {
-            return scored_paths;
-        }
+        name: &str,
+        case_insensitive: bool,
+    ) -> Result<Option<NamedType>, SearchError> {
+        let searcher = self.inner.reader()?.searcher();
+        let term = if case_insensitive {
+            Term::from_field_text(self.lower_type_name_field, &name.to_lowercase())
+        } else {
+            Term::from_field_text(self.raw_type_name_field, name)
+        };
+        let type_search = searcher.search(
+            &TermQuery::new(term, IndexRecordOption::Basic),
+            &TopDocs::with_limit(1),
+        )?;
+        let Some((_, address)) = type_search.first() else {
+            return Ok(None);
+        };
+        let doc: TantivyDocument = searcher.doc(*address)?;
+        Ok(doc
+            .get_first(self.raw_type_name_field)
+            .and_then(|value| value.as_str())
+            .map(NamedType::new_unchecked))
+    }
 
-        // Calculate the range of path lengths
-        let path_lengths: Vec<usize> = scored_paths
-            .iter()
-            .map(|scored| scored.inner.len())
-            .collect();
-        let min_length = *path_lengths.iter().min().unwrap_or(&1);
-        let max_length = *path_lengths.iter().max().unwrap_or(&1);
+    /// The graph of references between the indexed types, built from the types referencing each
+    /// type. Only the first `max_referencing_types` references to a type are included.
+    pub fn type_graph(&self) -> Result<TypeGraph, SearchError> {
+        let searcher = self.inner.reader()?.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(self.kind_field, TYPE_KIND),
+            IndexRecordOption::Basic,
+        );
 
-        // Only apply boost if there's a range in path lengths
-        if max_length <= min_length {
-            return scored_paths;
+        let mut graph = TypeGraph::default();
+        for address in searcher.search(&query, &DocSetCollector)? {
+            let doc: TantivyDocument = searcher.doc(address)?;
+            let Some(type_name) = doc
+                .get_first(self.raw_type_name_field)
+                .and_then(|value| value.as_str())
+                .map(NamedType::new_unchecked)
+            else {
+                continue;
+            };
+            graph.edges.extend(
+                doc.get_all(self.referencing_types_field)
+                    .filter_map(|value| value.as_str())
+                    .map(|reference| TypeEdge::parse(reference, type_name.clone())),
+            );
+            graph.nodes.push(type_name);
         }
+        graph.nodes.sort();
+        graph.edges.sort();
+        graph.edges.dedup();
+        Ok(graph)
+    }
 
-        let length_range = (max_length - min_length) as f32;
+    /// Set the deprecation reasons of the types and fields in search results
+    fn add_deprecations(
+        &self,
+        searcher: &Searcher,
+        paths: &mut [Scored<PathNode>],
+    ) -> Result<(), SearchError> {
+        let mut deprecations: HashMap<NamedType, HashMap<String, String>> = HashMap::new();
+        for node in paths.iter_mut().flat_map(|path| path.inner.iter_mut()) {
+            if !deprecations.contains_key(&node.node_type) {
+                let type_deprecations = self.type_deprecations(searcher, &node.node_type)?;
+                deprecations.insert(node.node_type.clone(), type_deprecations);
+            }
+            let Some(type_deprecations) = deprecations.get(&node.node_type) else {
+                continue;
+            };
+            node.deprecation = type_deprecations.get("").cloned();
+            node.field_deprecation = node
+                .field_name
+                .as_ref()
+                .and_then(|field_name| type_deprecations.get(field_name.as_str()))
+                .cloned();
+        }
+        Ok(())
+    }
 
-        // Apply normalized boost to each path
-        scored_paths
-            .into_iter()
-            .map(|scored_path| {
-                let path_length = scored_path.inner.len();
-                let normalized_length = (path_length - min_length) as f32 / length_range;
-                // Boost shorter paths: 1.0 for shortest, 0.0 for longest
-                let length_boost = 1.0 - normalized_length;
-                let boosted_score = scored_path.score() * (1.0 + boost_factor * length_boost);
-                Scored::new(scored_path.inner, boosted_score)
-            })
-            .collect()
+    /// Get the stored deprecation reasons of a type and its fields, keyed by field name, with the
+    /// type's own reason keyed by an empty name
+    fn type_deprecations(
+        &self,
+        searcher: &Searcher,
+        type_name: &str,
+    ) -> Result<HashMap<String, String>, SearchError> {
+        let term = Term::from_field_text(self.raw_type_name_field, type_name);
+        let type_search = searcher.search(
+            &TermQuery::new(term, IndexRecordOption::Basic),
+            &TopDocs::with_limit(1),
+        )?;
+        let Some((_, address)) = type_search.first() else {
+            return Ok(HashMap::new());
+        };
+        let doc: TantivyDocument = searcher.doc(*address)?;
+        Ok(doc
+            .get_all(self.deprecations_field)
+            .filter_map(|value| value.as_str())
+            .filter_map(|deprecation| deprecation.split_once('#'))
+            .map(|(member, reason)| (member.to_string(), reason.to_string()))
+            .collect())
     }
 
-    /// Create the query used to search for a given set of terms.
-    fn query<I>(&self, terms: I) -> impl Query
-    where
-        I: IntoIterator<Item = String>,
-    {
+    /// The tokens of a search which match a document, and the text fields each matched in. Each
+    /// word of the search is tokenized again, and its tokens are looked up in the fields of the
+    /// document.
+    fn matched_tokens(
+        &self,
+        searcher: &Searcher,
+        doc_address: DocAddress,
+        terms: &[String],
+    ) -> Result<Vec<MatchedToken>, SearchError> {
+        let segment_reader = searcher.segment_reader(doc_address.segment_ord);
         let mut text_analyzer = self.text_analyzer.clone();
-        let mut query = BooleanQuery::new(
-            terms
-                .into_iter()
-                .flat_map(|term| {
-                    let mut terms: Vec<Term> = Vec::new();
-                    let mut token_stream = text_analyzer.token_stream(&term);
-                    token_stream.process(&mut |token| {
-                        terms.push(Term::from_field_text(self.type_name_field, &token.text));
-                        terms.push(Term::from_field_text(self.description_field, &token.text));
-                        terms.push(Term::from_field_text(self.fields_field, &token.text));
-                    });
-                    terms
-                })
-                .map(|term| {
-                    (
-                        Occur::Should,
-                        Box::new(TermQuery::new(term, IndexRecordOption::Basic)) as Box<dyn Query>,
-                    )
-                })
-                .collect(),
-        );
-        query.set_minimum_number_should_match(1);
-        query
-    }
-}
+        let mut matched_tokens: Vec<MatchedToken> = Vec::new();
+        for word in search_words(terms) {
+            if BOOLEAN_OPERATORS.contains(&word) {
+                continue;
+            }
+            let mut tokens: Vec<(String, String)> = Vec::new();
+            let mut token_stream = text_analyzer.token_stream(word);
+            token_stream.process(&mut |token| {
+                let written = word
+                    .get(token.offset_from..token.offset_to)
+                    .unwrap_or(&token.text);
+                tokens.push((written.to_string(), token.text.clone()));
+            });
+
+            for (token, text) in tokens {
+                let mut fields = Vec::new();
+                for (name, field) in self.text_fields() {
+                    let postings = segment_reader
+                        .inverted_index(field)?
+                        .read_postings(
+                            &Term::from_field_text(field, &text),
+                            IndexRecordOption::Basic,
+                        )
+                        .map_err(TantivyError::from)?;
+                    if let Some(mut postings) = postings
+                        // Postings can only be advanced, so may already be past the document
+                        && postings.doc() <= doc_address.doc_id
+                        && postings.seek(doc_address.doc_id) == doc_address.doc_id
+                    {
+                        fields.push(name);
+                    }
+                }
+                if !fields.is_empty() && !matched_tokens.iter().any(|m| m.token == token) {
+                    matched_tokens.push(MatchedToken { token, fields });
+                }
+            }
+        }
+        Ok(matched_tokens)
+    }
+
+    /// Get the stored location of a type, if any
+    fn type_location(
+        &self,
+        searcher: &Searcher,
+        type_name: &str,
+    ) -> Result<Option<SourceLocation>, SearchError> {
+        let Some(location_field) = self.location_field else {
+            return Ok(None);
+        };
+        let term = Term::from_field_text(self.raw_type_name_field, type_name);
+        let type_search = searcher.search(
+            &TermQuery::new(term, IndexRecordOption::Basic),
+            &TopDocs::with_limit(1),
+        )?;
+        let Some((_, address)) = type_search.first() else {
+            return Ok(None);
+        };
+        let doc: TantivyDocument = searcher.doc(*address)?;
+        Ok(doc
+            .get_first(location_field)
+            .and_then(|value| value.as_str())
+            .and_then(SourceLocation::parse))
+    }
+
+    /// Get the stored concrete object types an interface or union may resolve to
+    fn possible_types(
+        &self,
+        searcher: &Searcher,
+        type_name: &str,
+    ) -> Result<Vec<NamedType>, SearchError> {
+        let term = Term::from_field_text(self.raw_type_name_field, type_name);
+        let type_search = searcher.search(
+            &TermQuery::new(term, IndexRecordOption::Basic),
+            &TopDocs::with_limit(1),
+        )?;
+        let Some((_, address)) = type_search.first() else {
+            return Ok(Vec::new());
+        };
+        let doc: TantivyDocument = searcher.doc(*address)?;
+        Ok(doc
+            .get_all(self.possible_types_field)
+            .filter_map(|value| value.as_str())
+            .map(NamedType::new_unchecked)
+            .collect())
+    }
+
+    /// Check if paths to root starting at a root type should be included in search results
+    fn is_included_root(
+        &self,
+        type_name: &str,
+        root_types: Option<EnumSet<OperationType>>,
+    ) -> bool {
+        match (root_types, self.root_operation_types.get(type_name)) {
+            (Some(root_types), Some(operation_type)) => root_types.contains(*operation_type),
+            (Some(_), None) => false,
+            (None, _) => true,
+        }
+    }
+
+    /// Search the schema for a set of terms, merging the root paths to matching types into a
+    /// single tree so that paths sharing a prefix share tree nodes
+    pub fn search_tree<I>(&self, terms: I, options: Options) -> Result<PathTree, SearchError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        Ok(self.search(terms, options)?.iter().collect())
+    }
+
+    /// Search the schema for a set of terms, grouping the root paths by the matching type they
+    /// lead to. Groups are ordered by the score of their best path, and the paths within each
+    /// group are in the same order as in [`SchemaIndex::search`].
+    pub fn search_grouped<I>(
+        &self,
+        terms: I,
+        options: Options,
+    ) -> Result<Vec<TypePaths>, SearchError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut groups: IndexMap<NamedType, Vec<Scored<PathNode>>> = Default::default();
+        for path in self.search(terms, options)? {
+            if let Some(matched_type) = path.inner.iter().last() {
+                groups
+                    .entry(matched_type.node_type.clone())
+                    .or_default()
+                    .push(path);
+            }
+        }
+        Ok(groups.into_iter().collect())
+    }
+
+    /// Search the directive definitions in the schema for a set of terms, returning the names of
+    /// the top matching directives. Directives are only found if they were indexed.
+    pub fn search_directives<I>(
+        &self,
+        terms: I,
+        max_matches: usize,
+    ) -> Result<Vec<Scored<Name>>, SearchError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let searcher = self.inner.reader()?.searcher();
+        let query = self.query(terms, DIRECTIVE_KIND, &Options::default());
+        debug!("Directive index query: {:?}", query);
+
+        let mut directives = Vec::new();
+        for (score, doc_address) in searcher.search(&query, &TopDocs::with_limit(max_matches))? {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            if let Some(name) = doc
+                .get_first(self.type_name_field)
+                .and_then(|v| v.as_str())
+                .and_then(|name| Name::new(name).ok())
+            {
+                directives.push(Scored::new(name, score));
+            }
+        }
+        Ok(directives)
+    }
+
+    /// Apply a boost factor to shorter paths
+    fn boost_shorter_paths(
+        &self,
+        scored_paths: Vec<Scored<PathNode>>,
+        boost_factor: f32,
+        boost_curve: BoostCurve,
+    ) -> Vec<Scored<PathNode>> {
+        if scored_paths.is_empty() || boost_factor == 0f32 {
+            return scored_paths;
+        }
+
+        // Calculate the range of path lengths
+        let path_lengths: Vec<usize> = scored_paths
+            .iter()
+            .map(|scored| scored.inner.len())
+            .collect();
+        let min_length = *path_lengths.iter().min().unwrap_or(&1);
+        let max_length = *path_lengths.iter().max().unwrap_or(&1);
+
+        // Only apply boost if there's a range in path lengths
+        if max_length <= min_length {
+            return scored_paths;
+        }
+
+        let length_range = (max_length - min_length) as f32;
+
+        // Apply normalized boost to each path
+        scored_paths
+            .into_iter()
+            .map(|scored_path| {
+                let path_length = scored_path.inner.len();
+                let normalized_length = (path_length - min_length) as f32 / length_range;
+                // Boost shorter paths: 1.0 for shortest, 0.0 for longest
+                let length_boost = boost_curve.boost(normalized_length);
+                let boosted_score = scored_path.score() * (1.0 + boost_factor * length_boost);
+                Scored::new(scored_path.inner, boosted_score)
+            })
+            .collect()
+    }
+
+    /// Create the query used to search for a given set of terms in documents of the given kind.
+    ///
+    /// Documents match any of the terms, unless the terms contain the boolean operators `AND`,
+    /// `OR`, or `NOT`. Terms joined by `AND` must all match, and terms following `NOT` must not.
+    /// Quoted phrases match their words in order, in the fields recording positions. Without
+    /// boolean operators, documents must match at least `min_should_match` of the words.
+    fn query<I>(&self, terms: I, kind: &str, options: &Options) -> impl Query + use<I>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let terms: Vec<String> = terms.into_iter().collect();
+        let words = search_words(&terms);
+        let query = if words.iter().any(|word| BOOLEAN_OPERATORS.contains(word)) {
+            let clauses = boolean_clauses(words.into_iter());
+            let has_required_clause = clauses.iter().any(|(occur, _)| *occur == Occur::Must);
+            let mut query = BooleanQuery::new(
+                clauses
+                    .into_iter()
+                    .map(|(occur, word)| {
+                        (
+                            occur,
+                            Box::new(self.term_query([word], options.fuzzy)) as Box<dyn Query>,
+                        )
+                    })
+                    .collect(),
+            );
+            if !has_required_clause {
+                query.set_minimum_number_should_match(1);
+            }
+            query
+        } else {
+            let word_queries: Vec<BooleanQuery> = words
+                .iter()
+                .map(|word| self.term_query([*word], options.fuzzy))
+                // Words without any tokens, such as stop words, can never match
+                .filter(|query| !query.clauses().is_empty())
+                .collect();
+            let required = options.min_should_match.resolve(word_queries.len());
+            let mut query = if required > 1 {
+                BooleanQuery::new(
+                    word_queries
+                        .into_iter()
+                        .map(|query| (Occur::Should, Box::new(query) as Box<dyn Query>))
+                        .collect(),
+                )
+            } else {
+                // Any token of any word may match
+                self.term_query(words, options.fuzzy)
+            };
+            query.set_minimum_number_should_match(required);
+            query
+        };
+        BooleanQuery::new(vec![
+            (Occur::Must, Box::new(query) as Box<dyn Query>),
+            // Restrict to the kind of document without affecting the score
+            (
+                Occur::Must,
+                Box::new(ConstScoreQuery::new(
+                    Box::new(TermQuery::new(
+                        Term::from_field_text(self.kind_field, kind),
+                        IndexRecordOption::Basic,
+                    )),
+                    0.0,
+                )),
+            ),
+        ])
+    }
+
+    /// The fields holding the text searched for, by name
+    fn text_fields(&self) -> [(&'static str, Field); 4] {
+        [
+            (TYPE_NAME_FIELD, self.type_name_field),
+            (DESCRIPTION_FIELD, self.description_field),
+            (FIELDS_FIELD, self.fields_field),
+            (ALIASES_FIELD, self.aliases_field),
+        ]
+    }
+
+    /// Create a query for documents matching any of the tokens in the given words, in any field.
+    ///
+    /// Quoted phrases match their tokens in order in the fields recording positions. If no field
+    /// records positions, phrases match any of their tokens like other words. Fuzzy tokens also
+    /// match the start of indexed tokens, within one edit.
+    fn term_query<'a>(
+        &self,
+        words: impl IntoIterator<Item = &'a str>,
+        fuzzy: bool,
+    ) -> BooleanQuery {
+        let text_fields = self.text_fields().map(|(_, field)| field);
+        let index_schema = self.inner.schema();
+        let phrase_fields: Vec<Field> = text_fields
+            .into_iter()
+            .filter(
+                |field| match index_schema.get_field_entry(*field).field_type() {
+                    FieldType::Str(text_options) => text_options
+                        .get_indexing_options()
+                        .is_some_and(|indexing| indexing.index_option().has_positions()),
+                    _ => false,
+                },
+            )
+            .collect();
+
+        let mut text_analyzer = self.text_analyzer.clone();
+        BooleanQuery::new(
+            words
+                .into_iter()
+                .flat_map(|word| {
+                    let phrase = word
+                        .strip_prefix('"')
+                        .map(|phrase| phrase.strip_suffix('"').unwrap_or(phrase));
+                    let mut tokens: Vec<(usize, String)> = Vec::new();
+                    let mut token_stream = text_analyzer.token_stream(phrase.unwrap_or(word));
+                    token_stream.process(&mut |token| {
+                        tokens.push((token.position, token.text.clone()));
+                    });
+
+                    if phrase.is_some() && tokens.len() > 1 {
+                        if !phrase_fields.is_empty() {
+                            return phrase_fields
+                                .iter()
+                                .map(|field| {
+                                    let terms = tokens
+                                        .iter()
+                                        .map(|(position, text)| {
+                                            (*position, Term::from_field_text(*field, text))
+                                        })
+                                        .collect();
+                                    Box::new(PhraseQuery::new_with_offset(terms)) as Box<dyn Query>
+                                })
+                                .collect::<Vec<_>>();
+                        }
+                        debug!("No field records positions, so {word} matches any of its words");
+                    }
+                    tokens
+                        .iter()
+                        .flat_map(|(_, text)| {
+                            text_fields.map(|field| Term::from_field_text(field, text))
+                        })
+                        .map(|term| {
+                            if fuzzy {
+                                Box::new(FuzzyTermQuery::new_prefix(term, 1, true))
+                                    as Box<dyn Query>
+                            } else {
+                                Box::new(TermQuery::new(term, IndexRecordOption::Basic))
+                                    as Box<dyn Query>
+                            }
+                        })
+                        .collect()
+                })
+                .map(|query| (Occur::Should, query))
+                .collect(),
+        )
+    }
+}
+
+/// Split the terms of a search into words, keeping each quoted phrase together as one word
+fn search_words(terms: &[String]) -> Vec<&str> {
+    let mut words = Vec::new();
+    for term in terms {
+        let mut rest = term.trim_start();
+        while !rest.is_empty() {
+            let end = match rest.strip_prefix('"') {
+                // An unterminated phrase runs to the end of the term
+                Some(phrase) => phrase.find('"').map_or(rest.len(), |end| end + 2),
+                None => rest.find(char::is_whitespace).unwrap_or(rest.len()),
+            };
+            words.push(&rest[..end]);
+            rest = rest[end..].trim_start();
+        }
+    }
+    words
+}
+
+/// Assign each word in a search to a clause of a boolean query, according to the boolean
+/// operators between them
+fn boolean_clauses<'a>(words: impl Iterator<Item = &'a str>) -> Vec<(Occur, &'a str)> {
+    let mut clauses: Vec<(Occur, &str)> = Vec::new();
+    let mut next = Occur::Should;
+    for word in words {
+        match word {
+            "AND" => {
+                // Both sides of the operator are required
+                if let Some((occur @ Occur::Should, _)) = clauses.last_mut() {
+                    *occur = Occur::Must;
+                }
+                next = Occur::Must;
+            }
+            "OR" => next = Occur::Should,
+            "NOT" => next = Occur::MustNot,
+            word => {
+                clauses.push((next, word));
+                next = Occur::Should;
+            }
+        }
+    }
+    clauses
+}
+
+/// The names of the types reached by traversing the schema from the root types, in the order they
+/// are first reached, failing if there are more than the maximum number of indexed types
+fn reached_types(
+    schema: &Valid<Schema>,
+    root_types: EnumSet<OperationType>,
+    max_indexed_types: Option<usize>,
+) -> Result<Vec<String>, IndexingError> {
+    let mut seen = HashSet::new();
+    let mut types = Vec::new();
+    for (extended_type, _) in schema.traverse(root_types) {
+        if seen.insert(extended_type.name()) {
+            if let Some(max_indexed_types) = max_indexed_types
+                && types.len() >= max_indexed_types
+            {
+                return Err(IndexingError::TooManyTypes(max_indexed_types));
+            }
+            types.push(extended_type.name().to_string());
+        }
+    }
+    Ok(types)
+}
+
+/// Map the types reached by traversing the schema from the root types to the types referencing
+/// them, as `type#field#arguments`. If a batch is given, only the types in the batch are mapped;
+/// otherwise, every type is, and there must be no more than the maximum number of indexed types.
+/// Types with more than the maximum number of referencing types are added to the capped types.
+fn type_references(
+    schema: &Valid<Schema>,
+    root_types: EnumSet<OperationType>,
+    options: &IndexOptions,
+    batch: Option<&HashSet<String>>,
+    capped_types: &mut HashSet<String>,
+) -> Result<HashMap<String, Vec<String>>, IndexingError> {
+    let mut type_references: HashMap<String, Vec<String>> = HashMap::default();
+    for (extended_type, path) in schema.traverse(root_types) {
+        if let Some(batch) = batch {
+            if !batch.contains(extended_type.name().as_str()) {
+                continue;
+            }
+        } else if let Some(max_indexed_types) = options.max_indexed_types
+            && type_references.len() >= max_indexed_types
+            && !type_references.contains_key(extended_type.name().as_str())
+        {
+            return Err(IndexingError::TooManyTypes(max_indexed_types));
+        }
+        let entry = type_references
+            .entry(extended_type.name().to_string())
+            .or_default();
+        if entry.len() >= options.max_referencing_types {
+            capped_types.insert(extended_type.name().to_string());
+            continue;
+        }
+        if let Some((ref_type, field_name, field_args)) = path.referencing_type() {
+            if let Some(field_name) = field_name {
+                entry.push(format!(
+                    "{}#{}{}",
+                    ref_type,
+                    field_name.as_str(),
+                    if field_args.is_empty() {
+                        "".to_string()
+                    } else {
+                        format!("#{}", field_args.iter().join(","))
+                    }
+                ));
+            } else {
+                entry.push(ref_type.to_string())
+            }
+        }
+    }
+    Ok(type_references)
+}
+
+/// Find the first field of a type whose type, or the type of one of its arguments, is not defined
+/// in the schema. A validated schema never has one, unless it was assumed valid.
+fn undefined_field_type(
+    schema: &Schema,
+    extended_type: &ExtendedType,
+) -> Option<TypeIndexingError> {
+    let field_types: Vec<(&Name, &NamedType)> = match extended_type {
+        ExtendedType::Object(obj) => obj
+            .fields
+            .values()
+            .flat_map(|field| field_and_argument_types(field))
+            .collect(),
+        ExtendedType::Interface(interface) => interface
+            .fields
+            .values()
+            .flat_map(|field| field_and_argument_types(field))
+            .collect(),
+        ExtendedType::InputObject(input) => input
+            .fields
+            .iter()
+            .map(|(name, field)| (name, field.ty.inner_named_type()))
+            .collect(),
+        ExtendedType::Union(union) => union
+            .members
+            .iter()
+            .map(|member| (&member.name, &member.name))
+            .collect(),
+        _ => Vec::new(),
+    };
+    field_types
+        .into_iter()
+        .find(|(_, ty)| !schema.types.contains_key(*ty))
+        .map(|(field, ty)| TypeIndexingError::UndefinedType {
+            type_name: extended_type.name().to_string(),
+            field: field.to_string(),
+            undefined_type: ty.to_string(),
+        })
+}
+
+/// The type of a field and of each of its arguments, by the field name
+fn field_and_argument_types(field: &FieldDefinition) -> Vec<(&Name, &NamedType)> {
+    std::iter::once(field.ty.inner_named_type())
+        .chain(field.arguments.iter().map(|arg| arg.ty.inner_named_type()))
+        .map(|ty| (&field.name, ty))
+        .collect()
+}
+
+/// The names given with `@alias(name:)` directives on a type, its fields, or its enum values
+fn aliases(extended_type: &ExtendedType) -> Vec<&str> {
+    let member_directives: Vec<&ast::DirectiveList> = match extended_type {
+        ExtendedType::Object(obj) => obj.fields.values().map(|field| &field.directives).collect(),
+        ExtendedType::Interface(interface) => interface
+            .fields
+            .values()
+            .map(|field| &field.directives)
+            .collect(),
+        ExtendedType::InputObject(input) => input
+            .fields
+            .values()
+            .map(|field| &field.directives)
+            .collect(),
+        ExtendedType::Enum(enum_type) => enum_type
+            .values
+            .values()
+            .map(|value| &value.directives)
+            .collect(),
+        _ => Vec::new(),
+    };
+    extended_type
+        .directives()
+        .get_all(ALIAS_DIRECTIVE)
+        .map(|directive| &***directive)
+        .chain(
+            member_directives
+                .into_iter()
+                .flat_map(|directives| directives.get_all(ALIAS_DIRECTIVE))
+                .map(|directive| &**directive),
+        )
+        .filter_map(|directive| directive.specified_argument_by_name("name"))
+        .filter_map(|name| name.as_str())
+        .collect()
+}
+
+/// The concrete object types an interface or union may resolve to, in schema order. Other types
+/// have none.
+fn possible_types<'a>(
+    implementers: &'a apollo_compiler::collections::HashMap<Name, Implementers>,
+    extended_type: &'a ExtendedType,
+) -> Box<dyn Iterator<Item = &'a Name> + 'a> {
+    match extended_type {
+        ExtendedType::Interface(interface) => Box::new(
+            implementers
+                .get(&interface.name)
+                .into_iter()
+                .flat_map(|implementers| implementers.objects.iter()),
+        ),
+        ExtendedType::Union(union) => Box::new(union.members.iter().map(|member| &member.name)),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// The reasons given with `@deprecated` directives on a type or its fields, by field name, with
+/// an empty name for the type itself
+fn deprecations<'a>(
+    schema: &'a Schema,
+    extended_type: &'a ExtendedType,
+) -> Vec<(&'a str, &'a str)> {
+    let fields: Vec<(&Name, &ast::DirectiveList)> = match extended_type {
+        ExtendedType::Object(obj) => obj
+            .fields
+            .iter()
+            .map(|(name, field)| (name, &field.directives))
+            .collect(),
+        ExtendedType::Interface(interface) => interface
+            .fields
+            .iter()
+            .map(|(name, field)| (name, &field.directives))
+            .collect(),
+        _ => Vec::new(),
+    };
+    let reason = |directive: &'a ast::Directive| {
+        directive
+            .argument_by_name("reason", schema)
+            .ok()
+            .and_then(|reason| reason.as_str())
+            .unwrap_or(DEFAULT_DEPRECATION_REASON)
+    };
+    extended_type
+        .directives()
+        .get(DEPRECATED_DIRECTIVE)
+        .map(|directive| ("", reason(directive)))
+        .into_iter()
+        .chain(fields.into_iter().filter_map(|(name, directives)| {
+            directives
+                .get(DEPRECATED_DIRECTIVE)
+                .map(|directive| (name.as_str(), reason(directive)))
+        }))
+        .collect()
+}
+
+/// Format a field with its type, and optionally its argument names, so that searching for an
+/// argument name matches the type defining the field
+fn field_signature(field: &FieldDefinition, argument_names: bool) -> String {
+    if !argument_names || field.arguments.is_empty() {
+        format!("{}: {}", field.name, field.ty.inner_named_type())
+    } else {
+        format!(
+            "{}({}): {}",
+            field.name,
+            field
+                .arguments
+                .iter()
+                .map(|arg| arg.name.as_str())
+                .join(", "),
+            field.ty.inner_named_type()
+        )
+    }
+}
+
+/// Get where a type is defined in the schema source, if known
+fn source_location(schema: &Schema, extended_type: &ExtendedType) -> Option<SourceLocation> {
+    let span = extended_type.location()?;
+    let line_column = span.line_column(&schema.sources)?;
+    Some(SourceLocation {
+        path: schema
+            .sources
+            .get(&span.file_id())?
+            .path()
+            .display()
+            .to_string(),
+        line: line_column.line,
+        column: line_column.column,
+    })
+}
+
+/// Truncate text to a maximum number of characters, ending it with an ellipsis if truncated
+fn truncate(text: &str, max_chars: Option<usize>) -> Cow<'_, str> {
+    match max_chars.and_then(|max_chars| text.char_indices().nth(max_chars)) {
+        Some((end, _)) => Cow::Owned(format!("{}…", &text[..end])),
+        None => Cow::Borrowed(text),
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use insta::assert_snapshot;
     use rstest::{fixture, rstest};
+    use std::time::Duration;
 
     const TEST_SCHEMA: &str = include_str!("testdata/schema.graphql");
 
@@ -529,12 +1826,108 @@ mod tests {
             .expect("Failed to validate test schema")
     }
 
+    #[test]
+    fn indexes_types_reachable_from_subscriptions() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { id: ID }
+            type Subscription { priceChanged(symbol: String!): PriceChange }
+            type PriceChange { symbol: String!, price: Float! }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let search = |index: &SchemaIndex, root_types| {
+            index
+                .search(
+                    vec!["price".to_string()],
+                    Options {
+                        root_types,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .iter()
+                .map(|path| path.inner.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let index = SchemaIndex::new(
+            &schema,
+            OperationType::Query | OperationType::Subscription,
+            Default::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            search(&index, None),
+            ["Subscription -> priceChanged(String) -> PriceChange"]
+        );
+        assert_eq!(
+            search(&index, Some(OperationType::Subscription.into())),
+            ["Subscription -> priceChanged(String) -> PriceChange"]
+        );
+        assert!(search(&index, Some(OperationType::Query.into())).is_empty());
+
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+        assert!(search(&index, None).is_empty());
+    }
+
+    #[test]
+    fn collapses_redundant_paths() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { search: SearchResult, me: User }
+            union SearchResult = User | Admin
+            type User { id: ID!, address: Address }
+            type Admin { id: ID!, address: Address }
+            type Address { street: String }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+        let search = |collapse_redundant_paths| {
+            index
+                .search(
+                    vec!["address".to_string()],
+                    Options {
+                        max_paths_per_type: 10,
+                        collapse_redundant_paths,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .iter()
+                .map(|path| path.inner.to_string())
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+
+        assert_snapshot!(search(false).join("\n"), @r"
+        Query -> me -> User
+        Query -> me -> User -> address -> Address
+        Query -> search -> SearchResult -> Admin
+        Query -> search -> SearchResult -> Admin -> address -> Address
+        Query -> search -> SearchResult -> User
+        Query -> search -> SearchResult -> User -> address -> Address
+        ");
+        assert_snapshot!(search(true).join("\n"), @r"
+        Query -> me -> User -> address -> Address
+        Query -> search -> SearchResult -> User -> address -> Address
+        ");
+    }
+
     #[rstest]
     fn test_search(schema: Valid<Schema>) {
         let search = SchemaIndex::new(
             &schema,
             OperationType::Query | OperationType::Mutation,
-            15_000_000,
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ..Default::default()
+            },
         )
         .unwrap();
 
@@ -551,4 +1944,1057 @@ mod tests {
                 .join("\n")
         );
     }
+
+    #[rstest]
+    fn test_without_display_fields_stored(schema: Valid<Schema>) {
+        let index = |store_display_fields| {
+            SchemaIndex::new(
+                &schema,
+                OperationType::Query | OperationType::Mutation,
+                IndexOptions {
+                    index_memory_bytes: 15_000_000,
+                    store_display_fields,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let search = |index: &SchemaIndex| {
+            index
+                .search(vec!["dimensions".to_string()], Options::default())
+                .unwrap()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        };
+        let size = |index: &SchemaIndex| {
+            index
+                .inner
+                .reader()
+                .unwrap()
+                .searcher()
+                .space_usage()
+                .unwrap()
+                .total()
+        };
+
+        let full = index(true);
+        let reduced = index(false);
+        assert!(!search(&reduced).is_empty());
+        assert_eq!(search(&reduced), search(&full));
+        assert!(size(&reduced) < size(&full));
+    }
+
+    #[test]
+    fn test_truncated_descriptions() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { widget: Widget }
+            """A widget that frobnicates, with extremely verbose documentation about gizmos"""
+            type Widget { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let search = SchemaIndex::new(
+            &schema,
+            OperationType::Query.into(),
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                max_description_chars: Some(25),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let matches = |term: &str| {
+            search
+                .search(vec![term.to_string()], Options::default())
+                .unwrap()
+                .iter()
+                .any(|result| result.to_string().contains("Widget"))
+        };
+        assert!(matches("frobnicates"));
+        assert!(!matches("gizmos"));
+
+        let searcher = search.inner.reader().unwrap().searcher();
+        let term = Term::from_field_text(search.raw_type_name_field, "Widget");
+        let (_, address) = searcher
+            .search(
+                &TermQuery::new(term, IndexRecordOption::Basic),
+                &TopDocs::with_limit(1),
+            )
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let doc: TantivyDocument = searcher.doc(address).unwrap();
+        assert_eq!(
+            doc.get_first(search.description_field)
+                .and_then(|v| v.as_str()),
+            Some("A widget that frobnicates…")
+        );
+        assert!(
+            doc.get_first(
+                search
+                    .inner
+                    .schema()
+                    .get_field(FULL_DESCRIPTION_FIELD)
+                    .unwrap()
+            )
+            .and_then(|v| v.as_str())
+            .is_some_and(|d| d.ends_with("about gizmos"))
+        );
+    }
+
+    #[rstest]
+    fn type_graph_contains_field_references(schema: Valid<Schema>) {
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+
+        let graph = index.type_graph().unwrap();
+
+        assert!(graph.nodes.contains(&NamedType::new_unchecked("User")));
+        assert!(graph.edges.contains(&TypeEdge {
+            from: NamedType::new_unchecked("Query"),
+            to: NamedType::new_unchecked("User"),
+            field_name: Some(Name::new_unchecked("user")),
+        }));
+        assert!(
+            graph
+                .to_string()
+                .contains(r#"  "User" -> "UserProfile" [label="profile"];"#)
+        );
+    }
+
+    #[rstest]
+    fn test_lookup_type(schema: Valid<Schema>) {
+        let search = SchemaIndex::new(
+            &schema,
+            OperationType::Query | OperationType::Mutation,
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            search.lookup_type("Dimensions", false).unwrap().as_deref(),
+            Some("Dimensions")
+        );
+        assert_eq!(search.lookup_type("dimensions", false).unwrap(), None);
+        assert_eq!(
+            search.lookup_type("dimensions", true).unwrap().as_deref(),
+            Some("Dimensions")
+        );
+        assert_eq!(search.lookup_type("Dimension", true).unwrap(), None);
+    }
+
+    #[test]
+    fn skips_types_which_cannot_be_indexed() {
+        // Composition could produce a schema referencing an undefined type, which validation
+        // would reject
+        let schema = Valid::assume_valid(
+            Schema::parse(
+                r#"
+                type Query { user: User, broken: Broken }
+                type User { id: ID!, name: String }
+                type Broken { ghost: Ghost }
+                "#,
+                "schema.graphql",
+            )
+            .unwrap(),
+        );
+        let (index, errors) =
+            SchemaIndex::new_with_errors(&schema, OperationType::Query.into(), Default::default())
+                .unwrap();
+
+        assert_eq!(
+            errors.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            ["Type Broken has a field ghost of undefined type Ghost"]
+        );
+        assert_eq!(index.lookup_type("Broken", false).unwrap(), None);
+
+        // The rest of the schema can still be searched
+        let results = index
+            .search(vec!["name".to_string()], Options::default())
+            .unwrap();
+        assert_eq!(
+            results
+                .first()
+                .map(|path| path.inner.to_string())
+                .as_deref(),
+            Some("Query -> user -> User")
+        );
+    }
+
+    #[test]
+    fn search_results_carry_deprecations() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            directive @deprecated(reason: String = "No longer supported") on FIELD_DEFINITION | ARGUMENT_DEFINITION | INPUT_FIELD_DEFINITION | ENUM_VALUE | OBJECT
+            type Query { legacyAccount: LegacyAccount @deprecated(reason: "Use account") }
+            type LegacyAccount @deprecated(reason: "Replaced by Account") { id: ID! }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+
+        let results = index
+            .search(vec!["LegacyAccount".to_string()], Options::default())
+            .unwrap();
+        let path = &results
+            .iter()
+            .find(|result| result.inner.len() > 1)
+            .unwrap()
+            .inner;
+        let nodes: Vec<_> = path.iter().collect();
+
+        assert_eq!(path.to_string(), "Query -> legacyAccount -> LegacyAccount");
+        assert_eq!(
+            nodes.first().unwrap().field_deprecation.as_deref(),
+            Some("Use account")
+        );
+        assert_eq!(nodes.first().unwrap().deprecation, None);
+        assert_eq!(
+            nodes.last().unwrap().deprecation.as_deref(),
+            Some("Replaced by Account")
+        );
+    }
+
+    #[test]
+    fn test_min_should_match() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { a: Order, b: Shipment, c: Invoice }
+            """A customer order, shipped to an address"""
+            type Order { id: ID }
+            """A shipment of goods"""
+            type Shipment { id: ID }
+            """A bill for an order"""
+            type Invoice { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+        let matching_types = |min_should_match| {
+            index
+                .search(
+                    vec!["customer order address".to_string()],
+                    Options {
+                        min_should_match,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .iter()
+                .filter_map(|path| path.inner.iter().last())
+                .map(|node| node.node_type.to_string())
+                .sorted()
+                .dedup()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            matching_types(MinShouldMatch::default()),
+            vec!["Invoice", "Order", "Query"]
+        );
+        assert_eq!(matching_types(MinShouldMatch::Count(2)), vec!["Order"]);
+        assert_eq!(matching_types(MinShouldMatch::Percent(67)), vec!["Order"]);
+        assert_eq!(MinShouldMatch::Percent(50).resolve(3), 1);
+        assert_eq!(MinShouldMatch::Count(5).resolve(3), 3);
+    }
+
+    #[test]
+    fn test_fuzzy_search() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { warehouse: Warehouse }
+            """A site storing inventory"""
+            type Warehouse { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+        let matching_types = |terms: &str, fuzzy| {
+            index
+                .search(
+                    vec![terms.to_string()],
+                    Options {
+                        fuzzy,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .iter()
+                .filter_map(|path| path.inner.iter().last())
+                .map(|node| node.node_type.to_string())
+                .sorted()
+                .dedup()
+                .collect::<Vec<_>>()
+        };
+
+        // A misspelling and the start of a word only match fuzzily
+        assert!(matching_types("invetory", false).is_empty());
+        assert_eq!(matching_types("invetory", true), vec!["Warehouse"]);
+        assert!(matching_types("wareh", false).is_empty());
+        assert_eq!(matching_types("wareh", true), vec!["Query", "Warehouse"]);
+    }
+
+    #[test]
+    fn search_results_carry_the_possible_types() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { node: Node, result: SearchResult }
+            interface Node { id: ID! }
+            type User implements Node { id: ID! }
+            type Product implements Node { id: ID! }
+            union SearchResult = User | Product
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+        let possible_types = |terms: &str| {
+            index
+                .search(vec![terms.to_string()], Options::default())
+                .unwrap()
+                .first()
+                .and_then(|path| path.inner.iter().last())
+                .map(|node| {
+                    node.possible_types
+                        .iter()
+                        .map(|name| name.to_string())
+                        .collect::<Vec<_>>()
+                })
+                .unwrap()
+        };
+
+        assert_eq!(possible_types("Node"), vec!["User", "Product"]);
+        assert_eq!(possible_types("SearchResult"), vec!["User", "Product"]);
+        assert!(possible_types("User").is_empty());
+    }
+
+    #[rstest]
+    fn debug_query_includes_each_term(schema: Valid<Schema>) {
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+
+        let query = index.debug_query(vec!["users".to_string(), "posts".to_string()]);
+
+        // Each term is stemmed, and matched against every searchable field
+        for term in ["user", "post"] {
+            for field in [
+                index.type_name_field,
+                index.description_field,
+                index.fields_field,
+                index.aliases_field,
+            ] {
+                let term_query = format!(
+                    r#"TermQuery(Term(field={}, type=Str, "{term}"))"#,
+                    field.field_id()
+                );
+                assert!(query.contains(&term_query), "{query} has no {term_query}");
+            }
+        }
+    }
+
+    #[test]
+    fn schemas_with_too_many_types_are_rejected() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { user: User, team: Team }
+            type User { id: ID!, name: String }
+            type Team { id: ID!, members: [User] }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index = |max_indexed_types| {
+            SchemaIndex::new(
+                &schema,
+                OperationType::Query.into(),
+                IndexOptions {
+                    max_indexed_types: Some(max_indexed_types),
+                    ..Default::default()
+                },
+            )
+        };
+
+        // Query, User, and Team are reachable
+        assert!(index(3).is_ok());
+        assert!(matches!(index(2), Err(IndexingError::TooManyTypes(2))));
+    }
+
+    #[test]
+    fn search_diagnostics_describe_each_phase() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { user: User, team: Team }
+            type User { id: ID!, name: String }
+            type Team { id: ID!, name: String, members: [User] }
+            type Other { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+
+        let (results, diagnostics) = index
+            .search_with_diagnostics(vec!["name".to_string()], Options::default())
+            .unwrap();
+
+        assert_eq!(diagnostics.candidate_count, 2);
+        assert_eq!(diagnostics.root_path_count, 3);
+        assert!(diagnostics.visited_paths >= diagnostics.root_path_count);
+        assert!(diagnostics.query_duration > Duration::ZERO);
+        assert!(diagnostics.fetch_duration > Duration::ZERO);
+        assert!(diagnostics.path_duration > Duration::ZERO);
+        let paths = |results: Vec<Scored<PathNode>>| {
+            results
+                .iter()
+                .map(|path| path.inner.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            paths(results),
+            paths(
+                index
+                    .search(vec!["name".to_string()], Options::default())
+                    .unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn aliases_are_searchable() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            directive @alias(name: String!) on OBJECT | FIELD_DEFINITION
+            type Query { account: Account }
+            type Account { id: ID!, balance: Int @alias(name: "ledger") }
+            type Other { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let index =
+            SchemaIndex::new(&schema, OperationType::Query.into(), Default::default()).unwrap();
+
+        let results = index
+            .search(vec!["ledger".to_string()], Options::default())
+            .unwrap();
+        assert_eq!(
+            results
+                .first()
+                .map(|path| path.inner.to_string())
+                .as_deref(),
+            Some("Query -> account -> Account")
+        );
+    }
+
+    #[test]
+    fn test_search_directives() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            """Restricts a field to callers with the given role"""
+            directive @auth(role: String!) on FIELD_DEFINITION
+            type Query { secret: Secret @auth(role: "admin") }
+            """Something only admins may see, guarded by auth"""
+            type Secret { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let search = SchemaIndex::new(
+            &schema,
+            OperationType::Query.into(),
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                index_directives: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let directives = search
+            .search_directives(vec!["auth".to_string()], 10)
+            .unwrap();
+        assert_eq!(
+            directives
+                .iter()
+                .map(|directive| directive.inner.as_str())
+                .collect::<Vec<_>>(),
+            vec!["auth"]
+        );
+
+        // The directive is not returned as a type
+        let types = search
+            .search(vec!["auth".to_string()], Options::default())
+            .unwrap();
+        assert!(!types.is_empty());
+        assert!(
+            types
+                .iter()
+                .all(|path| path.inner.node_type.as_str() != "auth")
+        );
+    }
+
+    #[test]
+    fn test_highly_connected_schema_is_bounded() {
+        // Every type references every other type. The field names don't contain the type names,
+        // so that searching for a type name only matches that type.
+        let type_names: Vec<_> = (0..30).map(|i| format!("T{i}")).collect();
+        let fields = type_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| format!("f{i}: {name}"))
+            .join(" ");
+        let types = type_names
+            .iter()
+            .map(|name| format!("type {name} {{ {fields} }}"))
+            .join("\n");
+        let schema = Schema::parse_and_validate(
+            format!("type Query {{ {fields} }}\n{types}"),
+            "schema.graphql",
+        )
+        .unwrap();
+        let search = SchemaIndex::new(
+            &schema,
+            OperationType::Query.into(),
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                max_referencing_types: 5,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let searcher = search.inner.reader().unwrap().searcher();
+        let term = Term::from_field_text(search.raw_type_name_field, "T25");
+        let (_, address) = searcher
+            .search(
+                &TermQuery::new(term, IndexRecordOption::Basic),
+                &TopDocs::with_limit(1),
+            )
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let doc: TantivyDocument = searcher.doc(address).unwrap();
+        assert_eq!(doc.get_all(search.referencing_types_field).count(), 5);
+
+        let search_with_cap = |max_visited_paths_per_type| {
+            search
+                .search(
+                    vec!["T25".to_string()],
+                    Options {
+                        max_visited_paths_per_type,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+        };
+        let root_path = |max_visited_paths_per_type| {
+            search_with_cap(max_visited_paths_per_type)
+                .iter()
+                .any(|path| path.inner.to_string() == "Query -> f25 -> T25")
+        };
+
+        // The first referencing types stored are T26 to T29 and then Query, so the root path is
+        // only found after visiting T25 and each of the other referencing types
+        assert!(!root_path(5));
+        assert!(root_path(6));
+    }
+
+    #[test]
+    fn test_search_argument_names() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { catalog: Catalog }
+            type Catalog { product(sku: String!): Product }
+            type Product { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let search = SchemaIndex::new(
+            &schema,
+            OperationType::Query.into(),
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                index_argument_names: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let results = search
+            .search(vec!["sku".to_string()], Options::default())
+            .unwrap();
+        assert_eq!(
+            results
+                .first()
+                .and_then(|path| path.inner.iter().last())
+                .map(|node| node.node_type.as_str()),
+            Some("Catalog")
+        );
+    }
+
+    #[test]
+    fn test_search_boolean_operators() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { shipping: Shipping, box: Box, item: Item }
+            """The dimensions and product of a shipment"""
+            type Shipping { id: ID }
+            """The dimensions of a box"""
+            type Box { id: ID }
+            """A product"""
+            type Item { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let search = SchemaIndex::new(
+            &schema,
+            OperationType::Query.into(),
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let matching_types = |terms: &[&str]| {
+            search
+                .search(
+                    terms.iter().map(|term| term.to_string()),
+                    Options::default(),
+                )
+                .unwrap()
+                .iter()
+                .filter_map(|path| path.inner.iter().last())
+                .map(|node| node.node_type.to_string())
+                .sorted()
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            matching_types(&["dimensions", "product"]),
+            vec!["Box", "Item", "Shipping"]
+        );
+        assert_eq!(
+            matching_types(&["dimensions AND product"]),
+            vec!["Shipping"]
+        );
+        assert_eq!(
+            matching_types(&["dimensions", "AND", "product"]),
+            vec!["Shipping"]
+        );
+        assert_eq!(matching_types(&["dimensions NOT product"]), vec!["Box"]);
+        assert_eq!(
+            matching_types(&["shipment OR product"]),
+            vec!["Item", "Shipping"]
+        );
+    }
+
+    #[rstest]
+    fn test_search_grouped(schema: Valid<Schema>) {
+        let search = SchemaIndex::new(
+            &schema,
+            OperationType::Query | OperationType::Mutation,
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let terms = || vec!["dimensions".to_string()];
+        let describe = |groups: Vec<TypePaths>| {
+            groups
+                .into_iter()
+                .map(|(matched_type, paths)| {
+                    let paths = paths.iter().map(ToString::to_string).collect::<Vec<_>>();
+                    (matched_type.to_string(), paths)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let flat = search.search(terms(), Options::default()).unwrap();
+        let grouped = search.search_grouped(terms(), Options::default()).unwrap();
+        assert!(grouped.len() > 1);
+
+        // Re-grouping the flat results by the type at the end of each path gives the same groups
+        let mut regrouped: IndexMap<NamedType, Vec<Scored<PathNode>>> = Default::default();
+        for path in flat {
+            let matched_type = path.inner.iter().last().unwrap().node_type.clone();
+            regrouped.entry(matched_type).or_default().push(path);
+        }
+        assert_eq!(describe(grouped), describe(regrouped.into_iter().collect()));
+    }
+
+    #[test]
+    fn test_search_restricted_to_root_types() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { order(id: ID!): Order }
+            type Mutation { cancelOrder(id: ID!): Order }
+            type Order { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let search = SchemaIndex::new(
+            &schema,
+            OperationType::Query | OperationType::Mutation,
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let roots = |root_types| {
+            search
+                .search(
+                    vec!["Order".to_string()],
+                    Options {
+                        root_types,
+                        ..Default::default()
+                    },
+                )
+                .unwrap()
+                .iter()
+                .map(|path| path.inner.node_type.to_string())
+                .sorted()
+                .dedup()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(roots(None), vec!["Mutation", "Query"]);
+        assert_eq!(
+            roots(Some(OperationType::Mutation.into())),
+            vec!["Mutation"]
+        );
+    }
+
+    #[test]
+    fn test_ascii_folding() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { venue: Venue }
+            "A café serving breakfast"
+            type Venue { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let matches = |ascii_folding| {
+            SchemaIndex::new(
+                &schema,
+                OperationType::Query.into(),
+                IndexOptions {
+                    index_memory_bytes: 15_000_000,
+                    ascii_folding,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .search(vec!["cafe".to_string()], Options::default())
+            .unwrap()
+            .iter()
+            .any(|result| result.to_string().contains("Venue"))
+        };
+        assert!(!matches(false));
+        assert!(matches(true));
+    }
+
+    #[test]
+    fn test_split_identifiers() {
+        let schema = Schema::parse_and_validate(
+            "type Query { order: Order }\ntype Order { shipTo: ShippingAddress }\ntype ShippingAddress { id: ID }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let matches = |split_identifiers| {
+            SchemaIndex::new(
+                &schema,
+                OperationType::Query.into(),
+                IndexOptions {
+                    index_memory_bytes: 15_000_000,
+                    split_identifiers,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .search(vec!["address".to_string()], Options::default())
+            .unwrap()
+            .iter()
+            .any(|result| result.to_string().contains("ShippingAddress"))
+        };
+        assert!(!matches(false));
+        assert!(matches(true));
+    }
+
+    #[test]
+    fn test_phrase_search() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { order: Order, warehouse: Warehouse }
+            """An order, sent to a shipping address"""
+            type Order { id: ID }
+            """The address of a site shipping orders"""
+            type Warehouse { id: ID }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let matching_types = |phrase_search| {
+            SchemaIndex::new(
+                &schema,
+                OperationType::Query.into(),
+                IndexOptions {
+                    index_memory_bytes: 15_000_000,
+                    phrase_search,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .search(vec!["\"shipping address\"".to_string()], Options::default())
+            .unwrap()
+            .iter()
+            .filter_map(|path| path.inner.iter().last())
+            .map(|node| node.node_type.to_string())
+            .sorted()
+            .collect::<Vec<_>>()
+        };
+
+        // With positions, only the words in order match
+        assert_eq!(matching_types(true), vec!["Order"]);
+        // Without positions, the phrase matches any of its words instead of failing
+        assert_eq!(matching_types(false), vec!["Order", "Warehouse"]);
+    }
+
+    #[test]
+    fn exponential_boost_curve_favors_the_shortest_paths() {
+        let schema = Schema::parse_and_validate(
+            "type Query { a: A } type A { b: B } type B { id: ID }",
+            "schema.graphql",
+        )
+        .unwrap();
+        let index = SchemaIndex::new(
+            &schema,
+            OperationType::Query.into(),
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let path = |length: usize| {
+            (1..length).fold(PathNode::new(Name::new_unchecked("B")), |path, _| {
+                path.add_parent(
+                    Some(Name::new_unchecked("b")),
+                    vec![],
+                    Name::new_unchecked("A"),
+                )
+            })
+        };
+        let shortest_to_medium = |boost_curve| {
+            let boosted = index.boost_shorter_paths(
+                vec![
+                    Scored::new(path(1), 1.0),
+                    Scored::new(path(2), 1.0),
+                    Scored::new(path(3), 1.0),
+                ],
+                0.5,
+                boost_curve,
+            );
+            boosted[0].score() / boosted[1].score()
+        };
+
+        assert!(
+            shortest_to_medium(BoostCurve::Exponential) > shortest_to_medium(BoostCurve::Linear)
+        );
+        assert_eq!(shortest_to_medium(BoostCurve::Step), 1.5);
+    }
+
+    #[test]
+    fn search_results_carry_the_matched_tokens() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            type Query { order: Order }
+            """A purchase"""
+            type Order { shipping: String }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+        let search = SchemaIndex::new(
+            &schema,
+            OperationType::Query.into(),
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let results = search
+            .search(
+                vec!["Purchase".to_string(), "shipping".to_string()],
+                Options::default(),
+            )
+            .unwrap();
+        let order = results
+            .iter()
+            .filter_map(|path| path.inner.iter().last())
+            .find(|node| node.node_type == "Order")
+            .unwrap();
+        assert_eq!(
+            order.matched_tokens,
+            vec![
+                MatchedToken {
+                    token: "Purchase".to_string(),
+                    fields: vec![DESCRIPTION_FIELD],
+                },
+                MatchedToken {
+                    token: "shipping".to_string(),
+                    fields: vec![FIELDS_FIELD],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_source_locations() {
+        let schema = Schema::parse_and_validate(
+            "type Query { venue: Venue }\n\ntype Venue { id: ID }\n",
+            "venues.graphql",
+        )
+        .unwrap();
+        let search = |store_locations| {
+            SchemaIndex::new(
+                &schema,
+                OperationType::Query.into(),
+                IndexOptions {
+                    index_memory_bytes: 15_000_000,
+                    store_locations,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+            .search(vec!["Venue".to_string()], Options::default())
+            .unwrap()
+            .into_iter()
+            .filter_map(|path| path.inner.iter().last().cloned())
+            .find(|node| node.node_type == "Venue")
+            .and_then(|node| node.location)
+        };
+        assert_eq!(
+            search(true),
+            Some(SourceLocation {
+                path: "venues.graphql".to_string(),
+                line: 3,
+                column: 1,
+            })
+        );
+        assert_eq!(search(false), None);
+    }
+
+    #[test]
+    fn search_diff_tags_changed_types() {
+        let index = |sdl: &str| {
+            SchemaIndex::new(
+                &Schema::parse_and_validate(sdl, "schema.graphql").unwrap(),
+                OperationType::Query.into(),
+                IndexOptions {
+                    index_memory_bytes: 15_000_000,
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let previous = index("type Query { venue: Venue }\n\ntype Venue { id: ID name: String }\n");
+        let current = index(
+            "type Query { venue: Venue }\n\n\
+             type Venue { id: ID name: String seating: Seating }\n\n\
+             type Seating { capacity: Int }\n",
+        )
+        .with_previous_version(previous);
+
+        let changes: HashMap<String, TypeChange> = current
+            .search_diff(vec!["venue".to_string(), "seating".to_string()], 10)
+            .unwrap()
+            .into_iter()
+            .map(|diff| (diff.inner.type_name.to_string(), diff.inner.change))
+            .collect();
+        assert_eq!(changes.get("Seating"), Some(&TypeChange::Added));
+        assert_eq!(changes.get("Venue"), Some(&TypeChange::Changed));
+        assert_eq!(changes.get("Query"), None);
+    }
+
+    #[test]
+    fn batched_indexing_bounds_the_references_held() {
+        // Each type references its parent in a binary tree of types
+        let types = (1..2_000)
+            .map(|i| format!("type T{i} {{ id: ID name: String parent: T{} }}", i / 2))
+            .join("\n");
+        let schema = Schema::parse_and_validate(
+            format!(
+                "type Query {{ {} }}\ntype T0 {{ id: ID }}\n{types}",
+                (0..2_000).map(|i| format!("t{i}: T{i}")).join(" ")
+            ),
+            "schema.graphql",
+        )
+        .unwrap();
+        let options = |index_batch_size| IndexOptions {
+            index_memory_bytes: 15_000_000,
+            index_batch_size,
+            ..Default::default()
+        };
+
+        // Only the references of the types in each batch are held at once
+        let reached = reached_types(&schema, OperationType::Query.into(), None).unwrap();
+        assert_eq!(reached.len(), 2_001);
+        let mut capped_types = HashSet::new();
+        for batch in reached.chunks(100) {
+            let batch: HashSet<String> = batch.iter().cloned().collect();
+            let references = type_references(
+                &schema,
+                OperationType::Query.into(),
+                &options(Some(100)),
+                Some(&batch),
+                &mut capped_types,
+            )
+            .unwrap();
+            assert_eq!(references.len(), batch.len());
+        }
+
+        // Indexing in batches finds the same results as indexing all at once
+        let search = |index_batch_size| {
+            SchemaIndex::new(
+                &schema,
+                OperationType::Query.into(),
+                options(index_batch_size),
+            )
+            .unwrap()
+            .search(vec!["T1234".to_string()], Options::default())
+            .unwrap()
+            .into_iter()
+            .map(|path| path.to_string())
+            .collect::<Vec<_>>()
+        };
+        let results = search(Some(100));
+        assert!(results.iter().any(|path| path.contains("T1234")));
+        assert_eq!(results, search(None));
+    }
 }
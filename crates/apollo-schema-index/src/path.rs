@@ -69,6 +69,25 @@ pub struct PathNode {
     /// The arguments of the field referencing the child type, if the child is a field type
     pub field_args: Vec<NamedType>,
 
+    /// Where the schema type is defined, if known. Only set on the matching type of search
+    /// results, and only if the index stores locations.
+    pub location: Option<SourceLocation>,
+
+    /// Why the schema type is deprecated, if it is. Only set on search results.
+    pub deprecation: Option<String>,
+
+    /// Why the field referencing the child type is deprecated, if it is. Only set on search
+    /// results.
+    pub field_deprecation: Option<String>,
+
+    /// The tokens of the search which matched the schema type, and the fields they matched in.
+    /// Only set on the matching type of search results.
+    pub matched_tokens: Vec<MatchedToken>,
+
+    /// The concrete object types an interface or union may resolve to, for building fragments
+    /// on them. Only set on the matching type of search results.
+    pub possible_types: Vec<NamedType>,
+
     /// The child type
     child: Option<Box<PathNode>>,
 }
@@ -80,10 +99,36 @@ impl PathNode {
             node_type,
             field_name: None,
             field_args: Vec::default(),
+            location: None,
+            deprecation: None,
+            field_deprecation: None,
+            matched_tokens: Vec::default(),
+            possible_types: Vec::default(),
             child: None,
         }
     }
 
+    /// Set where the schema type of this node is defined
+    pub fn with_location(self, location: Option<SourceLocation>) -> Self {
+        Self { location, ..self }
+    }
+
+    /// Set the tokens of a search which matched the schema type of this node
+    pub fn with_matched_tokens(self, matched_tokens: Vec<MatchedToken>) -> Self {
+        Self {
+            matched_tokens,
+            ..self
+        }
+    }
+
+    /// Set the concrete object types the schema type of this node may resolve to
+    pub fn with_possible_types(self, possible_types: Vec<NamedType>) -> Self {
+        Self {
+            possible_types,
+            ..self
+        }
+    }
+
     /// Add a child to the end of a path. Allows building up a path from the root down.
     pub fn add_child(
         self,
@@ -96,6 +141,11 @@ impl PathNode {
                 node_type: self.node_type,
                 field_name: self.field_name,
                 field_args: self.field_args,
+                location: self.location,
+                deprecation: self.deprecation,
+                field_deprecation: self.field_deprecation,
+                matched_tokens: self.matched_tokens,
+                possible_types: self.possible_types,
                 child: Some(Box::new(
                     child.add_child(field_name, field_args, child_type),
                 )),
@@ -105,6 +155,11 @@ impl PathNode {
                 node_type: self.node_type,
                 field_name,
                 field_args,
+                location: self.location,
+                deprecation: self.deprecation,
+                field_deprecation: None,
+                matched_tokens: self.matched_tokens,
+                possible_types: self.possible_types,
                 child: Some(Box::new(PathNode::new(child_type))),
             }
         }
@@ -121,6 +176,11 @@ impl PathNode {
             node_type: parent_type,
             field_name,
             field_args,
+            location: None,
+            deprecation: None,
+            field_deprecation: None,
+            matched_tokens: Vec::default(),
+            possible_types: Vec::default(),
             child: Some(Box::new(self)),
         }
     }
@@ -168,7 +228,8 @@ impl PathNode {
         }
     }
 
-    /// Gets the length of the path
+    /// Gets the length of the path. A path always contains at least its own node.
+    #[allow(clippy::len_without_is_empty)]
     pub fn len(&self) -> usize {
         if let Some(child) = &self.child {
             child.len() + 1
@@ -256,6 +317,46 @@ impl Display for PathNode {
     }
 }
 
+/// Where a schema type is defined in the schema source
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SourceLocation {
+    /// The path of the schema source file
+    pub path: String,
+
+    /// The line number, starting at 1
+    pub line: usize,
+
+    /// The column number, starting at 1
+    pub column: usize,
+}
+
+impl SourceLocation {
+    /// Parse a location formatted as `path:line:column`
+    pub(crate) fn parse(location: &str) -> Option<Self> {
+        let mut parts = location.rsplitn(3, ':');
+        let column = parts.next()?.parse().ok()?;
+        let line = parts.next()?.parse().ok()?;
+        let path = parts.next()?.to_string();
+        Some(Self { path, line, column })
+    }
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.path, self.line, self.column)
+    }
+}
+
+/// A token of a search which matched a schema type
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MatchedToken {
+    /// The token as written in the search
+    pub token: String,
+
+    /// The names of the index fields the token matched in, such as `description`
+    pub fields: Vec<&'static str>,
+}
+
 /// An item with a score
 pub struct Scored<T: Eq + Hash + Display> {
     pub inner: T,
@@ -0,0 +1,173 @@
+//! Generates query skeletons from paths to root, showing how to select a matching type.
+
+use crate::path::PathNode;
+use apollo_compiler::Schema;
+use apollo_compiler::ast::OperationType;
+use apollo_compiler::schema::{ExtendedType, FieldDefinition};
+
+/// The name of the field identifying objects
+const ID_FIELD_NAME: &str = "id";
+
+/// The strategy for choosing which leaf fields of the matched type a skeleton selects
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LeafSelection {
+    /// Select every scalar and enum field
+    #[default]
+    AllScalars,
+
+    /// Select only the `id` field (if any) and `__typename`
+    Identifiers,
+
+    /// Select up to the given number of scalar and enum fields
+    FirstScalars(usize),
+}
+
+/// Generate a query skeleton selecting the type at the end of a path from a root type.
+///
+/// Fields requiring arguments are selected without them, so the skeleton only shows the shape of
+/// the selection and must be completed before it can be executed.
+pub fn query_skeleton(schema: &Schema, path: &PathNode, leaf_selection: LeafSelection) -> String {
+    let operation_type = [OperationType::Mutation, OperationType::Subscription]
+        .into_iter()
+        .find(|operation_type| {
+            schema
+                .root_operation(*operation_type)
+                .is_some_and(|root| *root == path.node_type)
+        })
+        .unwrap_or(OperationType::Query);
+
+    let fields: Vec<_> = path
+        .iter()
+        .filter_map(|node| node.field_name.as_ref())
+        .collect();
+    let leaves = path
+        .iter()
+        .last()
+        .map(|node| leaf_fields(schema, node, leaf_selection))
+        .unwrap_or_default();
+    let indent = |depth: usize| "  ".repeat(depth);
+
+    let mut lines = vec![format!("{operation_type} {{")];
+    for (depth, field_name) in fields.iter().enumerate() {
+        // A field referencing a leaf type has no selection set
+        if depth + 1 == fields.len() && leaves.is_empty() {
+            lines.push(format!("{}{field_name}", indent(depth + 1)));
+        } else {
+            lines.push(format!("{}{field_name} {{", indent(depth + 1)));
+        }
+    }
+    for leaf in &leaves {
+        lines.push(format!("{}{leaf}", indent(fields.len() + 1)));
+    }
+    let open_fields = if leaves.is_empty() {
+        fields.len().saturating_sub(1)
+    } else {
+        fields.len()
+    };
+    for depth in (0..open_fields).rev() {
+        lines.push(format!("{}}}", indent(depth + 1)));
+    }
+    lines.push("}".to_string());
+    lines.join("\n")
+}
+
+/// Choose the leaf fields to select on the matched type
+fn leaf_fields(schema: &Schema, node: &PathNode, leaf_selection: LeafSelection) -> Vec<String> {
+    let fields: Vec<&FieldDefinition> = match schema.types.get(&node.node_type) {
+        Some(ExtendedType::Object(object)) => object
+            .fields
+            .values()
+            .map(|field| field.node.as_ref())
+            .collect(),
+        Some(ExtendedType::Interface(interface)) => interface
+            .fields
+            .values()
+            .map(|field| field.node.as_ref())
+            .collect(),
+        Some(ExtendedType::Union(_)) => return vec!["__typename".to_string()],
+        _ => return Vec::new(),
+    };
+    let scalars = fields.into_iter().filter(|field| {
+        field.arguments.iter().all(|arg| !arg.is_required())
+            && schema
+                .types
+                .get(field.ty.inner_named_type())
+                .is_some_and(|ty| ty.is_scalar() || ty.is_enum())
+    });
+    match leaf_selection {
+        LeafSelection::AllScalars => scalars.map(|field| field.name.to_string()).collect(),
+        LeafSelection::FirstScalars(max) => scalars
+            .take(max)
+            .map(|field| field.name.to_string())
+            .collect(),
+        LeafSelection::Identifiers => scalars
+            .filter(|field| field.name == ID_FIELD_NAME)
+            .map(|field| field.name.to_string())
+            .chain(std::iter::once("__typename".to_string()))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use apollo_compiler::name;
+    use insta::assert_snapshot;
+
+    fn schema() -> Schema {
+        Schema::parse_and_validate(
+            r#"
+            type Query { user: User }
+            type User { id: ID! name: String email: String age: Int role: Role address: Address }
+            type Address { street: String }
+            enum Role { ADMIN MEMBER }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap()
+        .into_inner()
+    }
+
+    fn user_path() -> PathNode {
+        PathNode::new(name!("Query")).add_child(Some(name!("user")), vec![], name!("User"))
+    }
+
+    #[test]
+    fn test_all_scalars() {
+        assert_snapshot!(query_skeleton(&schema(), &user_path(), LeafSelection::AllScalars), @r"
+        query {
+          user {
+            id
+            name
+            email
+            age
+            role
+          }
+        }
+        ");
+    }
+
+    #[test]
+    fn test_identifiers() {
+        assert_snapshot!(query_skeleton(&schema(), &user_path(), LeafSelection::Identifiers), @r"
+        query {
+          user {
+            id
+            __typename
+          }
+        }
+        ");
+    }
+
+    #[test]
+    fn test_first_scalars() {
+        assert_snapshot!(query_skeleton(&schema(), &user_path(), LeafSelection::FirstScalars(2)), @r"
+        query {
+          user {
+            id
+            name
+          }
+        }
+        ");
+    }
+}
@@ -0,0 +1,263 @@
+//! Snapshots of a schema index, which can be built once and loaded by other instances without
+//! traversing the schema again.
+//!
+//! A snapshot is a single file holding the files of the tantivy index, the root operation types of
+//! the schema, and the options needed to query the index. It also holds a hash of the indexed
+//! schema, so that a snapshot is only loaded for the schema it was built from.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use apollo_compiler::Schema;
+use apollo_compiler::validation::Valid;
+use tantivy::Index;
+use tantivy::directory::{Directory, RamDirectory};
+use tantivy::schema::Field;
+
+use crate::error::IndexingError;
+use crate::{
+    ALIASES_FIELD, DEPRECATIONS_FIELD, DESCRIPTION_FIELD, FIELDS_FIELD, KIND_FIELD, LOCATION_FIELD,
+    LOWER_TYPE_NAME_FIELD, OperationType, POSSIBLE_TYPES_FIELD, RAW_TYPE_NAME_FIELD,
+    REFERENCING_TYPES_FIELD, SchemaIndex, TYPE_NAME_FIELD, text_analyzer,
+};
+
+/// The bytes starting every snapshot file
+const MAGIC: &[u8; 8] = b"APSCHIDX";
+
+/// The file holding the metadata of a tantivy index
+const META_FILE: &str = "meta.json";
+
+/// The version of the snapshot format, incremented on incompatible changes
+const VERSION: u32 = 6;
+
+/// A hash of a schema, which is stable across builds and platforms so that snapshots are portable
+pub(crate) fn schema_hash(schema: &Valid<Schema>) -> u64 {
+    // 64-bit FNV-1a
+    schema
+        .to_string()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+impl SchemaIndex {
+    /// Export the index to a snapshot file, which can be imported with [`SchemaIndex::import`]
+    pub fn export(&self, path: impl AsRef<Path>) -> Result<(), IndexingError> {
+        let directory = self.inner.directory();
+        let mut files = vec![PathBuf::from(META_FILE)];
+        for segment in self.inner.searchable_segment_metas()? {
+            let mut segment_files: Vec<PathBuf> = segment.list_files().into_iter().collect();
+            segment_files.sort();
+            files.extend(segment_files);
+        }
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&VERSION.to_le_bytes())?;
+        writer.write_all(&self.schema_hash.to_le_bytes())?;
+        writer.write_all(&[u8::from(self.ascii_folding)])?;
+        writer.write_all(&[u8::from(self.split_identifiers)])?;
+
+        write_len(&mut writer, self.root_operation_types.len())?;
+        for (type_name, operation_type) in &self.root_operation_types {
+            write_bytes(&mut writer, type_name.as_bytes())?;
+            writer.write_all(&[match operation_type {
+                OperationType::Query => 0,
+                OperationType::Mutation => 1,
+                OperationType::Subscription => 2,
+            }])?;
+        }
+
+        // Segment files may be missing if they were never written, such as for unused fields
+        let mut contents = Vec::with_capacity(files.len());
+        for file in files {
+            if directory
+                .exists(&file)
+                .map_err(tantivy::TantivyError::from)?
+            {
+                let data = directory
+                    .atomic_read(&file)
+                    .map_err(tantivy::TantivyError::from)?;
+                contents.push((file, data));
+            }
+        }
+        write_len(&mut writer, contents.len())?;
+        for (file, data) in contents {
+            write_bytes(&mut writer, file.to_string_lossy().as_bytes())?;
+            write_bytes(&mut writer, &data)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Import an index from a snapshot file written by [`SchemaIndex::export`]. The snapshot must
+    /// have been exported from an index of the same schema.
+    pub fn import(path: impl AsRef<Path>, schema: &Valid<Schema>) -> Result<Self, IndexingError> {
+        let mut reader = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(IndexingError::InvalidSnapshot(
+                "not a schema index snapshot".to_string(),
+            ));
+        }
+        let version = u32::from_le_bytes(read_array(&mut reader)?);
+        if version != VERSION {
+            return Err(IndexingError::InvalidSnapshot(format!(
+                "unsupported snapshot version {version}"
+            )));
+        }
+        let snapshot_hash = u64::from_le_bytes(read_array(&mut reader)?);
+        let schema_hash = schema_hash(schema);
+        if snapshot_hash != schema_hash {
+            return Err(IndexingError::IncompatibleSnapshot);
+        }
+        let [ascii_folding] = read_array(&mut reader)?;
+        let [split_identifiers] = read_array(&mut reader)?;
+
+        let mut root_operation_types = HashMap::new();
+        for _ in 0..read_len(&mut reader)? {
+            let type_name = read_string(&mut reader)?;
+            let [operation_type] = read_array(&mut reader)?;
+            let operation_type = match operation_type {
+                0 => OperationType::Query,
+                1 => OperationType::Mutation,
+                2 => OperationType::Subscription,
+                other => {
+                    return Err(IndexingError::InvalidSnapshot(format!(
+                        "unknown operation type {other}"
+                    )));
+                }
+            };
+            root_operation_types.insert(type_name, operation_type);
+        }
+
+        let directory = RamDirectory::create();
+        for _ in 0..read_len(&mut reader)? {
+            let file = read_string(&mut reader)?;
+            let data = read_bytes(&mut reader)?;
+            directory.atomic_write(Path::new(&file), &data)?;
+        }
+
+        let index = Index::open(directory)?;
+        let text_analyzer = text_analyzer(ascii_folding != 0, split_identifiers != 0);
+        index
+            .tokenizers()
+            .register("en_stem", text_analyzer.clone());
+        let index_schema = index.schema();
+        let field = |name: &str| -> Result<Field, IndexingError> {
+            index_schema.get_field(name).map_err(|_| {
+                IndexingError::InvalidSnapshot(format!("the index has no {name} field"))
+            })
+        };
+
+        Ok(Self {
+            raw_type_name_field: field(RAW_TYPE_NAME_FIELD)?,
+            lower_type_name_field: field(LOWER_TYPE_NAME_FIELD)?,
+            aliases_field: field(ALIASES_FIELD)?,
+            type_name_field: field(TYPE_NAME_FIELD)?,
+            description_field: field(DESCRIPTION_FIELD)?,
+            fields_field: field(FIELDS_FIELD)?,
+            referencing_types_field: field(REFERENCING_TYPES_FIELD)?,
+            kind_field: field(KIND_FIELD)?,
+            location_field: index_schema.get_field(LOCATION_FIELD).ok(),
+            deprecations_field: field(DEPRECATIONS_FIELD)?,
+            possible_types_field: field(POSSIBLE_TYPES_FIELD)?,
+            inner: index,
+            text_analyzer,
+            root_operation_types,
+            ascii_folding: ascii_folding != 0,
+            split_identifiers: split_identifiers != 0,
+            schema_hash,
+            previous: None,
+        })
+    }
+}
+
+fn write_len(writer: &mut impl Write, len: usize) -> std::io::Result<()> {
+    writer.write_all(&(len as u64).to_le_bytes())
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> std::io::Result<()> {
+    write_len(writer, bytes.len())?;
+    writer.write_all(bytes)
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> std::io::Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_len(reader: &mut impl Read) -> Result<usize, IndexingError> {
+    usize::try_from(u64::from_le_bytes(read_array(reader)?))
+        .map_err(|_| IndexingError::InvalidSnapshot("length out of range".to_string()))
+}
+
+fn read_bytes(reader: &mut impl Read) -> Result<Vec<u8>, IndexingError> {
+    let len = read_len(reader)?;
+    let mut bytes = Vec::new();
+    reader.take(len as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != len {
+        return Err(IndexingError::InvalidSnapshot(
+            "truncated snapshot".to_string(),
+        ));
+    }
+    Ok(bytes)
+}
+
+fn read_string(reader: &mut impl Read) -> Result<String, IndexingError> {
+    String::from_utf8(read_bytes(reader)?)
+        .map_err(|_| IndexingError::InvalidSnapshot("invalid file name".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{IndexOptions, Options};
+
+    const TEST_SCHEMA: &str = include_str!("testdata/schema.graphql");
+
+    fn search(index: &SchemaIndex) -> Vec<String> {
+        index
+            .search(vec!["dimensions".to_string()], Options::default())
+            .unwrap()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn export_and_import_round_trip() {
+        let schema = Schema::parse_and_validate(TEST_SCHEMA, "schema.graphql").unwrap();
+        let index = SchemaIndex::new(
+            &schema,
+            OperationType::Query | OperationType::Mutation,
+            IndexOptions {
+                index_memory_bytes: 15_000_000,
+                ascii_folding: true,
+                split_identifiers: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let path =
+            std::env::temp_dir().join(format!("schema-index-{}.snapshot", std::process::id()));
+        index.export(&path).unwrap();
+
+        let imported = SchemaIndex::import(&path, &schema).unwrap();
+        assert!(!search(&index).is_empty());
+        assert_eq!(search(&imported), search(&index));
+
+        let other_schema =
+            Schema::parse_and_validate("type Query { id: ID }", "other.graphql").unwrap();
+        assert!(matches!(
+            SchemaIndex::import(&path, &other_schema),
+            Err(IndexingError::IncompatibleSnapshot)
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -3,12 +3,13 @@
 
 use crate::OperationType;
 use crate::path::PathNode;
-use apollo_compiler::Schema;
 use apollo_compiler::ast::NamedType;
-use apollo_compiler::schema::ExtendedType;
+use apollo_compiler::collections::IndexMap;
+use apollo_compiler::schema::{Component, ExtendedType, FieldDefinition};
+use apollo_compiler::{Name, Schema};
 use enumset::EnumSet;
-use std::collections::HashMap;
 use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 
 /// Extension trait to allow traversing a schema
 pub trait SchemaExt {
@@ -18,6 +19,11 @@ pub trait SchemaExt {
         &self,
         root_types: EnumSet<OperationType>,
     ) -> Box<dyn Iterator<Item = (&ExtendedType, PathNode)> + '_>;
+
+    /// Find the types which can't be reached from any root operation type or directive
+    /// definition, through field, argument, and input field types, union members, or interface
+    /// implementations
+    fn unreachable_types(&self) -> Vec<Name>;
 }
 
 impl SchemaExt for Schema {
@@ -119,6 +125,76 @@ impl SchemaExt for Schema {
             None
         }))
     }
+
+    fn unreachable_types(&self) -> Vec<Name> {
+        let implementers = self.implementers_map();
+        let field_types = |fields: &IndexMap<Name, Component<FieldDefinition>>| {
+            fields
+                .values()
+                .flat_map(|field| {
+                    std::iter::once(field.ty.inner_named_type())
+                        .chain(field.arguments.iter().map(|arg| arg.ty.inner_named_type()))
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let mut stack: Vec<Name> = EnumSet::<OperationType>::all()
+            .iter()
+            .filter_map(|root_type| self.root_operation(root_type.into()).cloned())
+            .chain(
+                self.directive_definitions
+                    .values()
+                    .flat_map(|directive| directive.arguments.iter())
+                    .map(|arg| arg.ty.inner_named_type().clone()),
+            )
+            .collect();
+        let mut visited = HashSet::new();
+        while let Some(name) = stack.pop() {
+            if visited.contains(&name) {
+                continue;
+            }
+            match self.types.get(&name) {
+                Some(ExtendedType::Object(object)) => {
+                    stack.extend(field_types(&object.fields));
+                    stack.extend(object.implements_interfaces.iter().map(|i| i.name.clone()));
+                }
+                Some(ExtendedType::Interface(interface)) => {
+                    stack.extend(field_types(&interface.fields));
+                    stack.extend(
+                        interface
+                            .implements_interfaces
+                            .iter()
+                            .map(|i| i.name.clone()),
+                    );
+                    if let Some(implementers) = implementers.get(&name) {
+                        stack.extend(implementers.iter().cloned());
+                    }
+                }
+                Some(ExtendedType::Union(union)) => {
+                    stack.extend(union.members.iter().map(|member| member.name.clone()));
+                }
+                Some(ExtendedType::InputObject(input)) => {
+                    stack.extend(
+                        input
+                            .fields
+                            .values()
+                            .map(|field| field.ty.inner_named_type().clone()),
+                    );
+                }
+                _ => {}
+            }
+            visited.insert(name);
+        }
+
+        self.types
+            .iter()
+            .filter(|(name, extended_type)| {
+                !extended_type.is_built_in() && !visited.contains(*name)
+            })
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
 }
 
 #[cfg(test)]
@@ -147,4 +223,27 @@ mod tests {
         }
         insta::assert_debug_snapshot!(paths);
     }
+
+    #[test]
+    fn test_unreachable_types() {
+        let schema = Schema::parse_and_validate(
+            r#"
+            directive @tag(kind: TagKind) on FIELD_DEFINITION
+            enum TagKind { PUBLIC }
+            type Query { node(filter: Filter): Node }
+            input Filter { range: Range }
+            input Range { from: Int }
+            interface Node { id: ID }
+            type User implements Node { id: ID }
+            type Orphan { id: ID }
+            enum OrphanEnum { VALUE }
+            "#,
+            "schema.graphql",
+        )
+        .unwrap();
+
+        let mut unreachable = schema.unreachable_types();
+        unreachable.sort();
+        assert_eq!(unreachable, vec!["Orphan", "OrphanEnum"]);
+    }
 }
@@ -0,0 +1,183 @@
+//! Merges paths from root types into a tree, so that paths sharing a prefix share tree nodes.
+
+use crate::path::{PathNode, Scored};
+use apollo_compiler::Name;
+use apollo_compiler::ast::NamedType;
+use std::fmt;
+use std::fmt::Display;
+
+/// A tree of the paths from root types (Query, Mutation, or Subscription) to matching types
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathTree {
+    /// The root types of the tree
+    pub roots: Vec<TreeNode>,
+}
+
+/// A type in a [`PathTree`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeNode {
+    /// The schema type of this node
+    pub node_type: NamedType,
+
+    /// The name of the field referencing this type from its parent, if it is a field type
+    pub field_name: Option<Name>,
+
+    /// The arguments of the field referencing this type from its parent
+    pub field_args: Vec<NamedType>,
+
+    /// The highest score of the paths passing through this node
+    pub score: f32,
+
+    /// Whether this type matched the search terms (i.e. a path ends here)
+    pub matched: bool,
+
+    /// The types referenced by this type
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    fn new(node_type: NamedType, field_name: Option<Name>, field_args: Vec<NamedType>) -> Self {
+        Self {
+            node_type,
+            field_name,
+            field_args,
+            score: 0.0,
+            matched: false,
+            children: Vec::new(),
+        }
+    }
+
+    /// Get the child for a type referenced by a field, adding it if it doesn't exist yet
+    fn child(
+        children: &mut Vec<TreeNode>,
+        node_type: &NamedType,
+        field_name: Option<&Name>,
+        field_args: &[NamedType],
+    ) -> usize {
+        children
+            .iter()
+            .position(|child| {
+                child.node_type == *node_type && child.field_name.as_ref() == field_name
+            })
+            .unwrap_or_else(|| {
+                children.push(TreeNode::new(
+                    node_type.clone(),
+                    field_name.cloned(),
+                    field_args.to_vec(),
+                ));
+                children.len() - 1
+            })
+    }
+
+    fn fmt_indented(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "{}", "  ".repeat(depth))?;
+        if let Some(field_name) = &self.field_name {
+            write!(f, "{field_name}")?;
+            if !self.field_args.is_empty() {
+                write!(
+                    f,
+                    "({})",
+                    self.field_args
+                        .iter()
+                        .map(|arg| arg.as_str())
+                        .collect::<Vec<_>>()
+                        .join(",")
+                )?;
+            }
+            write!(f, ": ")?;
+        }
+        write!(f, "{}", self.node_type)?;
+        if self.matched {
+            write!(f, " *")?;
+        }
+        writeln!(f)?;
+        self.children
+            .iter()
+            .try_for_each(|child| child.fmt_indented(f, depth + 1))
+    }
+}
+
+impl PathTree {
+    /// Add a scored path to the tree
+    pub fn insert(&mut self, path: &Scored<PathNode>) {
+        let score = path.score();
+        let mut children = &mut self.roots;
+        let mut field: Option<(Option<&Name>, &[NamedType])> = None;
+        let mut nodes = path.inner.iter().peekable();
+        while let Some(node) = nodes.next() {
+            let (field_name, field_args) = field.unwrap_or((None, &[]));
+            let index = TreeNode::child(children, &node.node_type, field_name, field_args);
+            let Some(tree_node) = children.get_mut(index) else {
+                return;
+            };
+            tree_node.score = tree_node.score.max(score);
+            if nodes.peek().is_none() {
+                tree_node.matched = true;
+            }
+            // The field on this node references the next node in the path
+            field = Some((node.field_name.as_ref(), &node.field_args));
+            children = &mut tree_node.children;
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a Scored<PathNode>> for PathTree {
+    fn from_iter<I: IntoIterator<Item = &'a Scored<PathNode>>>(paths: I) -> Self {
+        let mut tree = PathTree::default();
+        for path in paths {
+            tree.insert(path);
+        }
+        tree
+    }
+}
+
+impl Display for PathTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.roots
+            .iter()
+            .try_for_each(|root| root.fmt_indented(f, 0))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use apollo_compiler::name;
+    use insta::assert_snapshot;
+
+    #[test]
+    fn test_shared_prefixes_are_merged() {
+        let user_path = PathNode::new(name!("Query"))
+            .add_child(Some(name!("user")), vec![name!("UserInput")], name!("User"))
+            .add_child(Some(name!("address")), vec![], name!("Address"));
+        let friend_path = PathNode::new(name!("Query"))
+            .add_child(Some(name!("user")), vec![name!("UserInput")], name!("User"))
+            .add_child(Some(name!("friends")), vec![], name!("User"));
+        let post_path =
+            PathNode::new(name!("Query")).add_child(Some(name!("posts")), vec![], name!("Post"));
+
+        let paths = [
+            Scored::new(user_path, 2.0),
+            Scored::new(friend_path, 1.0),
+            Scored::new(post_path, 3.0),
+        ];
+        let tree: PathTree = paths.iter().collect();
+
+        assert_eq!(tree.roots.len(), 1);
+        let query = tree.roots.first().unwrap();
+        assert_eq!(query.score, 3.0);
+        assert_eq!(query.children.len(), 2);
+        let user = query.children.first().unwrap();
+        assert_eq!(user.score, 2.0);
+        assert!(!user.matched);
+        assert_eq!(user.children.len(), 2);
+
+        assert_snapshot!(tree, @r"
+        Query
+          user(UserInput): User
+            address: Address *
+            friends: User *
+          posts: Post *
+        ");
+    }
+}